@@ -0,0 +1,142 @@
+//! Compare the galloping intersection and union used by the packed vector kernels against a
+//! plain two-pointer merge, for a tiny vector against a huge one.
+//!
+//! Run with `cargo bench --bench merge`.
+
+use std::cmp::Ordering;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use sparse_matrix::ops::merge::{all_union, for_each_intersection, UnionStep};
+
+fn linear_dot(x_index: &[usize], x: &[f64], y_index: &[usize], y: &[f64]) -> f64 {
+    let mut product = 0.0;
+    let (mut kx, mut ky) = (0, 0);
+    while kx < x_index.len() && ky < y_index.len() {
+        match x_index[kx].cmp(&y_index[ky]) {
+            Ordering::Equal => {
+                product += x[kx] * y[ky];
+                kx += 1;
+                ky += 1;
+            }
+            Ordering::Greater => ky += 1,
+            Ordering::Less => kx += 1,
+        }
+    }
+    product
+}
+
+fn gallop_dot(x_index: &[usize], x: &[f64], y_index: &[usize], y: &[f64]) -> f64 {
+    let mut product = 0.0;
+    for_each_intersection(x_index, y_index, |kx, ky| product += x[kx] * y[ky]);
+    product
+}
+
+fn linear_sum(x_index: &[usize], x: &[f64], y_index: &[usize], y: &[f64]) -> Vec<f64> {
+    let mut sum = Vec::with_capacity(x.len() + y.len());
+    let (mut kx, mut ky) = (0, 0);
+    while kx < x_index.len() || ky < y_index.len() {
+        let ix = x_index.get(kx).copied().unwrap_or(usize::MAX);
+        let iy = y_index.get(ky).copied().unwrap_or(usize::MAX);
+        match ix.cmp(&iy) {
+            Ordering::Equal => {
+                sum.push(x[kx] + y[ky]);
+                kx += 1;
+                ky += 1;
+            }
+            Ordering::Greater => {
+                sum.push(y[ky]);
+                ky += 1;
+            }
+            Ordering::Less => {
+                sum.push(x[kx]);
+                kx += 1;
+            }
+        }
+    }
+    sum
+}
+
+fn gallop_sum(x_index: &[usize], x: &[f64], y_index: &[usize], y: &[f64]) -> Vec<f64> {
+    let mut sum = Vec::with_capacity(x.len() + y.len());
+    all_union(x_index, y_index, |step| {
+        match step {
+            UnionStep::Left(run) => sum.extend_from_slice(&x[run]),
+            UnionStep::Right(run) => sum.extend_from_slice(&y[run]),
+            UnionStep::Both(kx, ky) => sum.push(x[kx] + y[ky]),
+        }
+        true
+    });
+    sum
+}
+
+fn time<R>(rounds: u32, mut f: impl FnMut() -> R) -> (Duration, R) {
+    let start = Instant::now();
+    let mut result = black_box(f());
+    for _ in 1..rounds {
+        result = black_box(f());
+    }
+    (start.elapsed() / rounds, result)
+}
+
+fn main() {
+    // 1M stored entries against 10 entries spread over the whole range.
+    let big_index: Vec<usize> = (0..1_000_000).map(|i| 4 * i).collect();
+    let big_data: Vec<f64> = (0..1_000_000).map(|i| (i % 7) as f64).collect();
+    let small_index: Vec<usize> = (0..10).map(|i| 400_000 * i + 4 * (i % 3)).collect();
+    let small_data: Vec<f64> = (1..=10).map(f64::from).collect();
+
+    let rounds = 50;
+    let (linear, a) = time(rounds, || {
+        linear_dot(
+            black_box(&small_index),
+            &small_data,
+            black_box(&big_index),
+            &big_data,
+        )
+    });
+    let (gallop, b) = time(rounds, || {
+        gallop_dot(
+            black_box(&small_index),
+            &small_data,
+            black_box(&big_index),
+            &big_data,
+        )
+    });
+    assert_eq!(a.to_bits(), b.to_bits());
+
+    println!("10 nnz x 1M nnz dot product:");
+    println!("  linear merge:    {linear:?}");
+    println!("  galloping merge: {gallop:?}");
+    println!(
+        "  speedup:         {:.0}x",
+        linear.as_secs_f64() / gallop.as_secs_f64()
+    );
+
+    // The sum has to copy the long side, but the galloping union copies it run by run.
+    let (linear, a) = time(rounds, || {
+        linear_sum(
+            black_box(&small_index),
+            &small_data,
+            black_box(&big_index),
+            &big_data,
+        )
+    });
+    let (gallop, b) = time(rounds, || {
+        gallop_sum(
+            black_box(&small_index),
+            &small_data,
+            black_box(&big_index),
+            &big_data,
+        )
+    });
+    assert_eq!(a, b);
+
+    println!("10 nnz + 1M nnz sum:");
+    println!("  linear merge:    {linear:?}");
+    println!("  galloping merge: {gallop:?}");
+    println!(
+        "  speedup:         {:.1}x",
+        linear.as_secs_f64() / gallop.as_secs_f64()
+    );
+}
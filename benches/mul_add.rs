@@ -0,0 +1,44 @@
+//! Compare the workspaces of `mul_add` on a very long vector whose few nonzeros are clustered at
+//! the far end.
+//!
+//! Run with `cargo bench --bench mul_add`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use sparse_matrix::vec::{MulAddWorkspace, PackedVec};
+
+fn time(rounds: u32, mut f: impl FnMut()) -> Duration {
+    let start = Instant::now();
+    for _ in 0..rounds {
+        f();
+    }
+    start.elapsed() / rounds
+}
+
+fn main() {
+    let n = 10_000_000;
+    let mut x = vec![0.0; n];
+    let mut y = vec![0.0; n];
+    for k in 0..8 {
+        x[n - 1 - 3 * k] = 1.0;
+        y[n - 2 - 2 * k] = 2.0;
+    }
+    let x = PackedVec::gather(&x);
+    let y = PackedVec::gather(&y);
+
+    println!("mul_add on a {n}-long vector with 8 + 8 nonzeros at the end:");
+    for workspace in [
+        MulAddWorkspace::Full,
+        MulAddWorkspace::Span,
+        MulAddWorkspace::Hash,
+        MulAddWorkspace::Auto,
+    ] {
+        let elapsed = time(10, || {
+            let mut z = x.clone();
+            z.mul_add_with(black_box(&y), 0.5, workspace);
+            black_box(z);
+        });
+        println!("  {:<8} {elapsed:?}", format!("{workspace:?}"));
+    }
+}
@@ -0,0 +1,62 @@
+//! Measure what the run-time kernel dispatch costs: SpMV on a long matrix, once with rows long
+//! enough for the kernels to dominate and once with a single entry per row, where the work per
+//! dispatched call is smallest, each against the same product written out as a plain loop. Then
+//! the same products with the scalar kernels forced.
+//!
+//! Run with `cargo bench --bench simd --features simd`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use sparse_matrix::csr::CsrMatrix;
+use sparse_matrix::simd;
+
+fn time(rounds: u32, mut f: impl FnMut()) -> Duration {
+    let start = Instant::now();
+    for _ in 0..rounds {
+        f();
+    }
+    start.elapsed() / rounds
+}
+
+/// An `n × n` matrix with `per_row` entries per row, at spread out columns.
+fn banded(n: usize, per_row: usize) -> CsrMatrix<f64> {
+    let mut dense = vec![0.0; n * n];
+    for i in 0..n {
+        for k in 0..per_row {
+            dense[i * n + (i + 37 * k) % n] = 1.0 + k as f64 / 8.0;
+        }
+    }
+    CsrMatrix::from_dense(n, n, &dense).unwrap()
+}
+
+/// `y = A x` without going through the kernels, the baseline of the dispatch.
+fn plain_mul_vec(a: &CsrMatrix<f64>, x: &[f64], y: &mut [f64]) {
+    for (i, yi) in y.iter_mut().enumerate() {
+        let (cols, values) = a.row(i);
+        *yi = cols.iter().zip(values).map(|(&j, &v)| v * x[j]).sum();
+    }
+}
+
+fn main() {
+    let n = 4_000;
+    let x: Vec<f64> = (0..n).map(|i| (i % 17) as f64 / 17.0).collect();
+    let mut y = vec![0.0; n];
+    let cases = [
+        ("64 entries per row", banded(n, 64)),
+        ("1 entry per row", banded(n, 1)),
+    ];
+
+    for forced in [false, true] {
+        if forced {
+            simd::force_scalar();
+        }
+        println!("SpMV on {n} rows, {:?} kernels:", simd::active());
+        for (name, a) in &cases {
+            let dispatched = time(50, || a.mul_vec_into(black_box(&x), &mut y));
+            let plain = time(50, || plain_mul_vec(a, black_box(&x), &mut y));
+            black_box(&y);
+            println!("  {name:<20} dispatched {dispatched:?}, plain loop {plain:?}");
+        }
+    }
+}
@@ -0,0 +1,34 @@
+//! Accumulate many packed vectors and update blocks of the result, handling the errors the
+//! crate reports for mismatched dimensions.
+
+use sparse_matrix::prelude::*;
+
+fn main() -> Result<(), SparseError> {
+    let len = 12;
+    let contributions: Vec<PackedVec> = (0..4)
+        .map(|k| {
+            let mut dense = vec![0.0; len];
+            dense[3 * k] = 1.0;
+            dense[3 * k + 1] = k as f64;
+            PackedVec::gather(&dense)
+        })
+        .collect();
+
+    let refs: Vec<&PackedVec> = contributions.iter().collect();
+    let mut total = PackedVec::sum_all(len, &refs)?;
+    println!("sum = {:?}", total.scatter());
+
+    // Double the middle block in place.
+    let block = total.slice(4..8)?;
+    total.add_slice(4, &block, 1.0)?;
+    println!("after doubling 4..8 = {:?}", total.scatter());
+
+    // A vector of the wrong length is reported, not silently accepted.
+    let short = PackedVec::gather(&[1.0, 0.0, 0.0]);
+    match PackedVec::sum_all(len, &[&total, &short]) {
+        Ok(_) => unreachable!(),
+        Err(err) => println!("sum_all rejected the input: {err}"),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,61 @@
+//! Assemble a 2-D Poisson matrix from triplets and solve it with the direct solver, then the
+//! same system with the solver that picks a method on its own.
+
+use sparse_matrix::prelude::*;
+
+fn main() -> Result<(), SparseError> {
+    // The five-point Laplacian on an m by m grid, one triplet at a time.
+    let m = 20;
+    let n = m * m;
+    let mut coo = CooMatrix::new(n, n);
+    for i in 0..m {
+        for j in 0..m {
+            let k = i * m + j;
+            coo.push(k, k, 4.0);
+            if i > 0 {
+                coo.push(k, k - m, -1.0);
+            }
+            if i + 1 < m {
+                coo.push(k, k + m, -1.0);
+            }
+            if j > 0 {
+                coo.push(k, k - 1, -1.0);
+            }
+            if j + 1 < m {
+                coo.push(k, k + 1, -1.0);
+            }
+        }
+    }
+    let a: CsrMatrix<f64> = coo.to_csr();
+    let b = vec![1.0; n];
+
+    let solver = DirectSolver::new(&a, DirectOptions::default())?;
+    let x = solver.solve(&b);
+    let residual = a
+        .mul_vec(&x)
+        .iter()
+        .zip(&b)
+        .map(|(ax, b)| (ax - b).abs())
+        .fold(0.0, f64::max);
+    println!(
+        "direct: {} entries in A, {} in the factors, max residual {residual:.2e}",
+        a.nnz(),
+        solver.factor_nnz()
+    );
+
+    let (y, report) = solve(&a, &b, &SolverOptions::default())?;
+    let diff = x
+        .iter()
+        .zip(&y)
+        .map(|(x, y)| (x - y).abs())
+        .fold(0.0, f64::max);
+    println!("{report:?}");
+    println!("the two solutions differ by at most {diff:.2e}");
+
+    // A right-hand side of the wrong length is reported, not silently accepted.
+    match solve(&a, &[1.0; 3], &SolverOptions::default()) {
+        Ok(_) => unreachable!(),
+        Err(err) => println!("solve rejected the input: {err}"),
+    }
+    Ok(())
+}
@@ -0,0 +1,38 @@
+//! Gather dense data into packed vectors, combine them, and scatter the result back.
+
+use sparse_matrix::prelude::*;
+
+fn main() {
+    #[rustfmt::skip]
+    let x = [
+        0.0, 0.0, 0.0, 0.0, 0.0,
+        0.0, 1.0, 0.0, 0.0, 2.0,
+        0.0, 0.0, 3.0, 0.0, 0.0,
+        0.0, 0.0, 0.0, 1.0, 0.0,
+    ];
+    #[rustfmt::skip]
+    let y = [
+        0.0, 1.0, 0.0, 0.0, 0.0,
+        0.0, 1.0, 0.0, 7.0, 2.0,
+        0.0, 0.0, 2.0, 0.0, 0.0,
+        0.0, 1.0, 0.0, 0.0, 0.0,
+    ];
+
+    let mut packed_x = PackedVec::gather(&x);
+    let packed_y = PackedVec::gather(&y);
+    println!(
+        "x stores {} of {} components",
+        packed_x.len(),
+        packed_x.full_len()
+    );
+
+    println!("x . y = {}", &packed_x * &packed_y);
+
+    let stats = packed_x.mul_add_tracked(&packed_y, 0.5);
+    println!(
+        "x += 0.5 y filled in {} entries, density is now {:.2}",
+        stats.fill_in, stats.density
+    );
+
+    println!("x = {:?}", packed_x.scatter());
+}
@@ -0,0 +1,301 @@
+//! Python bindings for `PackedVec` and `CsrMatrix` over f64, exchanged with NumPy arrays and
+//! `scipy.sparse.csr_matrix`.
+//!
+//! No array crosses the boundary with a copy it doesn't need:
+//!
+//! - Arrays computed on the Rust side, such as products and scattered vectors, are moved into
+//!   NumPy.
+//! - The stored arrays of a `PackedVec` or `CsrMatrix`, and the `scipy.sparse.csr_matrix` that
+//!   `to_scipy` returns, are read-only NumPy views of the Rust buffers, which keep the Rust
+//!   object alive. The classes are frozen, so the buffers never change under a view. Indices
+//!   are shown as `intp`, the signed integer of the same width as `usize`.
+//! - Vector operands are borrowed: a contiguous float64 NumPy array is read in place, and only
+//!   other array-likes are converted first.
+//!
+//! The arrays a `PackedVec` or `CsrMatrix` is built from are copied once, since the Rust types
+//! own their arrays, and SciPy usually stores its indices as 32-bit integers where this crate
+//! uses `usize`.
+//!
+//! The smoke tests in `tests/` run with `maturin develop` followed by `pytest`.
+
+use numpy::ndarray::ArrayView1;
+use numpy::{
+    Element, IntoPyArray, PyArray1, PyArray2, PyArrayMethods, PyReadonlyArray1,
+    PyUntypedArrayMethods,
+};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use sparse::csr::CsrMatrix;
+use sparse::error::SparseError;
+use sparse::vec::PackedVec;
+
+fn value_error(e: SparseError) -> PyErr {
+    PyValueError::new_err(e.to_string())
+}
+
+/// Borrow any array-like of numbers as f64. A contiguous float64 NumPy array is read in place;
+/// anything else is converted into one first.
+fn floats<'py>(array: &Bound<'py, PyAny>) -> PyResult<PyReadonlyArray1<'py, f64>> {
+    if let Ok(borrowed) = array.extract::<PyReadonlyArray1<'py, f64>>() {
+        if borrowed.is_contiguous() {
+            return Ok(borrowed);
+        }
+    }
+    let np = array.py().import("numpy")?;
+    Ok(np
+        .call_method1("ascontiguousarray", (array, "float64"))?
+        .extract()?)
+}
+
+/// Show `slice`, a buffer owned by `owner`, to NumPy as a read-only array without copying it.
+/// The array holds a reference to `owner`, so the buffer outlives it.
+fn borrowed<'py, T: Element>(slice: &[T], owner: &Bound<'py, PyAny>) -> Bound<'py, PyArray1<T>> {
+    // SAFETY: `slice` belongs to `owner`, an instance of a frozen class whose buffers are never
+    // written or reallocated, and the array keeps `owner` alive as its base object.
+    let array = unsafe { PyArray1::borrow_from_array(&ArrayView1::from(slice), owner.clone()) };
+    let readonly = array.readwrite().make_nonwriteable();
+    (*readonly).clone()
+}
+
+/// Reinterpret indices as `isize`, NumPy's `intp`, failing when one doesn't fit.
+fn signed(indices: &[usize], bound: usize) -> PyResult<&[isize]> {
+    if isize::try_from(bound).is_err() {
+        return Err(PyValueError::new_err(format!(
+            "{bound} is too large for an intp index"
+        )));
+    }
+    Ok(bytemuck::cast_slice(indices))
+}
+
+/// Copy any array-like of integers into indices, failing on negative ones.
+fn indices_from(array: &Bound<'_, PyAny>) -> PyResult<Vec<usize>> {
+    let np = array.py().import("numpy")?;
+    let array: PyReadonlyArray1<'_, i64> =
+        np.call_method1("asarray", (array, "int64"))?.extract()?;
+    array
+        .as_array()
+        .iter()
+        .map(|&i| {
+            usize::try_from(i).map_err(|_| PyValueError::new_err(format!("negative index {i}")))
+        })
+        .collect()
+}
+
+/// A sparse vector holding its nonzero components as index, value pairs.
+#[pyclass(name = "PackedVec", module = "sparse_matrix", frozen)]
+struct PyPackedVec(PackedVec<f64>);
+
+#[pymethods]
+impl PyPackedVec {
+    /// Gather the nonzero components of a dense vector.
+    #[new]
+    fn new(dense: &Bound<'_, PyAny>) -> PyResult<Self> {
+        Ok(Self(PackedVec::gather(floats(dense)?.as_slice()?)))
+    }
+
+    /// Build a vector of length `len` from its indices and values, summing duplicates.
+    #[staticmethod]
+    fn from_pairs(
+        len: usize,
+        indices: &Bound<'_, PyAny>,
+        values: &Bound<'_, PyAny>,
+    ) -> PyResult<Self> {
+        let indices = indices_from(indices)?;
+        let values = floats(values)?;
+        let values = values.as_slice()?;
+        if indices.len() != values.len() {
+            return Err(PyValueError::new_err(
+                "indices and values have different lengths",
+            ));
+        }
+        PackedVec::from_pairs(len, indices.into_iter().zip(values.iter().copied()))
+            .map(Self)
+            .map_err(value_error)
+    }
+
+    /// The length of the full vector.
+    #[getter]
+    fn full_len(&self) -> usize {
+        self.0.full_len()
+    }
+
+    /// The number of stored components.
+    #[getter]
+    fn nnz(&self) -> usize {
+        self.0.len()
+    }
+
+    /// The stored indices, a read-only view of the Rust buffer.
+    fn indices<'py>(slf: &Bound<'py, Self>) -> PyResult<Bound<'py, PyArray1<isize>>> {
+        let v = &slf.get().0;
+        Ok(borrowed(signed(v.indices(), v.full_len())?, slf.as_any()))
+    }
+
+    /// The stored values, a read-only view of the Rust buffer.
+    fn values<'py>(slf: &Bound<'py, Self>) -> Bound<'py, PyArray1<f64>> {
+        borrowed(slf.get().0.data(), slf.as_any())
+    }
+
+    /// Scatter into a dense NumPy array.
+    fn scatter<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<f64>> {
+        self.0.scatter().into_pyarray(py)
+    }
+
+    /// The inner product with another packed vector.
+    fn dot(&self, other: &Self) -> PyResult<f64> {
+        if self.0.full_len() != other.0.full_len() {
+            return Err(PyValueError::new_err("the vectors have different lengths"));
+        }
+        Ok(self.0.dot(&other.0))
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "PackedVec(full_len={}, nnz={})",
+            self.0.full_len(),
+            self.0.len()
+        )
+    }
+}
+
+/// A sparse matrix in compressed sparse row form.
+#[pyclass(name = "CsrMatrix", module = "sparse_matrix", frozen)]
+struct PyCsrMatrix(CsrMatrix<f64>);
+
+#[pymethods]
+impl PyCsrMatrix {
+    /// Build a matrix from its CSR arrays, in the order `scipy.sparse.csr_matrix` takes them.
+    /// The column indices of each row must be sorted and unique.
+    #[new]
+    fn new(
+        data: &Bound<'_, PyAny>,
+        indices: &Bound<'_, PyAny>,
+        indptr: &Bound<'_, PyAny>,
+        shape: (usize, usize),
+    ) -> PyResult<Self> {
+        CsrMatrix::try_from_csr_data(
+            shape.0,
+            shape.1,
+            indices_from(indptr)?,
+            indices_from(indices)?,
+            floats(data)?.as_slice()?.to_vec(),
+        )
+        .map(Self)
+        .map_err(value_error)
+    }
+
+    /// Copy any SciPy sparse matrix or array, converting it to CSR and summing duplicates.
+    #[staticmethod]
+    fn from_scipy(matrix: &Bound<'_, PyAny>) -> PyResult<Self> {
+        let csr = matrix.call_method0("tocsr")?.call_method0("copy")?;
+        csr.call_method0("sum_duplicates")?;
+        let shape: (usize, usize) = csr.getattr("shape")?.extract()?;
+        Self::new(
+            &csr.getattr("data")?,
+            &csr.getattr("indices")?,
+            &csr.getattr("indptr")?,
+            shape,
+        )
+    }
+
+    /// The stored values, a read-only view of the Rust buffer.
+    #[getter]
+    fn data<'py>(slf: &Bound<'py, Self>) -> Bound<'py, PyArray1<f64>> {
+        borrowed(slf.get().0.data(), slf.as_any())
+    }
+
+    /// The column indices, a read-only view of the Rust buffer.
+    #[getter]
+    fn indices<'py>(slf: &Bound<'py, Self>) -> PyResult<Bound<'py, PyArray1<isize>>> {
+        let a = &slf.get().0;
+        Ok(borrowed(signed(a.indices(), a.ncols())?, slf.as_any()))
+    }
+
+    /// The row pointers, a read-only view of the Rust buffer.
+    #[getter]
+    fn indptr<'py>(slf: &Bound<'py, Self>) -> PyResult<Bound<'py, PyArray1<isize>>> {
+        let a = &slf.get().0;
+        Ok(borrowed(signed(a.indptr(), a.nnz())?, slf.as_any()))
+    }
+
+    /// Return a `scipy.sparse.csr_matrix` sharing the buffers of this matrix, read-only. Call
+    /// `.copy()` on it for a matrix SciPy can modify in place.
+    ///
+    /// The arrays are set on an empty matrix rather than passed to the constructor, which
+    /// would narrow indices that fit in 32 bits into a copy.
+    fn to_scipy<'py>(slf: &Bound<'py, Self>) -> PyResult<Bound<'py, PyAny>> {
+        let py = slf.py();
+        let shape = slf.get().0.shape();
+        let csr = py
+            .import("scipy.sparse")?
+            .getattr("csr_matrix")?
+            .call1((shape,))?;
+        csr.setattr("data", Self::data(slf))?;
+        csr.setattr("indices", Self::indices(slf)?)?;
+        csr.setattr("indptr", Self::indptr(slf)?)?;
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("full_check", false)?;
+        csr.call_method("check_format", (), Some(&kwargs))?;
+        Ok(csr)
+    }
+
+    #[getter]
+    fn shape(&self) -> (usize, usize) {
+        self.0.shape()
+    }
+
+    #[getter]
+    fn nnz(&self) -> usize {
+        self.0.nnz()
+    }
+
+    /// Multiply by a dense vector, returning a NumPy array. A contiguous float64 `x` is read in
+    /// place.
+    fn mul_vec<'py>(
+        &self,
+        py: Python<'py>,
+        x: &Bound<'py, PyAny>,
+    ) -> PyResult<Bound<'py, PyArray1<f64>>> {
+        let x = floats(x)?;
+        let x = x.as_slice()?;
+        if x.len() != self.0.ncols() {
+            return Err(value_error(SparseError::DimensionMismatch {
+                expected: self.0.ncols(),
+                found: x.len(),
+            }));
+        }
+        Ok(self.0.mul_vec(x).into_pyarray(py))
+    }
+
+    /// `a @ x` for a dense vector `x`, the same as `a.mul_vec(x)`.
+    fn __matmul__<'py>(
+        &self,
+        py: Python<'py>,
+        x: &Bound<'py, PyAny>,
+    ) -> PyResult<Bound<'py, PyArray1<f64>>> {
+        self.mul_vec(py, x)
+    }
+
+    fn transpose(&self) -> Self {
+        Self(self.0.transpose())
+    }
+
+    /// Scatter into a dense two-dimensional NumPy array.
+    fn to_dense<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyArray2<f64>>> {
+        let (nrows, ncols) = self.0.shape();
+        self.0.to_dense().into_pyarray(py).reshape([nrows, ncols])
+    }
+
+    fn __repr__(&self) -> String {
+        let (nrows, ncols) = self.0.shape();
+        format!("CsrMatrix(shape=({nrows}, {ncols}), nnz={})", self.0.nnz())
+    }
+}
+
+#[pymodule]
+fn sparse_matrix(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyPackedVec>()?;
+    m.add_class::<PyCsrMatrix>()?;
+    Ok(())
+}
@@ -0,0 +1,110 @@
+"""Smoke tests of the bindings, run with `maturin develop` followed by `pytest`."""
+
+import numpy as np
+import pytest
+
+import sparse_matrix as sm
+
+sp = pytest.importorskip("scipy.sparse")
+
+
+def test_packed_vec_roundtrip():
+    dense = np.array([0.0, 1.5, 0.0, -2.0, 0.0])
+    v = sm.PackedVec(dense)
+    assert (v.full_len, v.nnz) == (5, 2)
+    np.testing.assert_array_equal(v.indices(), [1, 3])
+    np.testing.assert_array_equal(v.values(), [1.5, -2.0])
+    np.testing.assert_array_equal(v.scatter(), dense)
+
+    w = sm.PackedVec.from_pairs(5, [3, 1, 3], [1.0, 2.0, 1.0])
+    np.testing.assert_array_equal(w.scatter(), [0.0, 2.0, 0.0, 2.0, 0.0])
+    assert v.dot(w) == 1.5 * 2.0 - 2.0 * 2.0
+
+
+def test_csr_scipy_roundtrip():
+    a = sp.random(40, 30, density=0.1, format="csr", random_state=7)
+    m = sm.CsrMatrix.from_scipy(a)
+    assert m.shape == (40, 30)
+    assert m.nnz == a.nnz
+    back = m.to_scipy()
+    assert isinstance(back, sp.csr_matrix)
+    np.testing.assert_array_equal(back.toarray(), a.toarray())
+    np.testing.assert_array_equal(m.to_dense(), a.toarray())
+    np.testing.assert_array_equal(m.transpose().to_dense(), a.toarray().T)
+
+    m = sm.CsrMatrix(a.data, a.indices, a.indptr, a.shape)
+    np.testing.assert_array_equal(m.to_dense(), a.toarray())
+
+    # Other formats are converted, with duplicates summed.
+    coo = sp.coo_matrix(([1.0, 2.0, 3.0], ([0, 0, 1], [1, 1, 0])), shape=(2, 2))
+    np.testing.assert_array_equal(
+        sm.CsrMatrix.from_scipy(coo).to_dense(), [[0.0, 3.0], [3.0, 0.0]]
+    )
+
+
+def test_mul_vec_operands():
+    a = sp.random(25, 20, density=0.2, format="csr", random_state=11)
+    m = sm.CsrMatrix.from_scipy(a)
+    x = np.linspace(-1.0, 1.0, 20)
+    expected = a @ x
+
+    # A contiguous float64 array is borrowed, and left unchanged.
+    before = x.copy()
+    np.testing.assert_allclose(m.mul_vec(x), expected, rtol=1e-14)
+    np.testing.assert_array_equal(x, before)
+
+    # Strided arrays, other dtypes and lists are converted first.
+    strided = np.repeat(x, 2)[::2]
+    assert not strided.flags.c_contiguous
+    np.testing.assert_allclose(m @ strided, expected, rtol=1e-14)
+    np.testing.assert_allclose(m @ x.astype(np.float32), a @ x.astype(np.float32), rtol=1e-6)
+    np.testing.assert_allclose(m @ list(x), expected, rtol=1e-14)
+
+    ints = np.arange(20)
+    np.testing.assert_allclose(m @ ints, a @ ints.astype(np.float64), rtol=1e-14)
+
+
+def test_errors():
+    m = sm.CsrMatrix.from_scipy(sp.identity(3, format="csr"))
+    with pytest.raises(ValueError, match="dimension mismatch"):
+        m.mul_vec(np.ones(4))
+    with pytest.raises(ValueError):
+        sm.CsrMatrix([1.0], [5], [0, 1], (1, 3))
+    with pytest.raises(ValueError, match="negative index"):
+        sm.PackedVec.from_pairs(3, [-1], [1.0])
+    with pytest.raises(ValueError, match="different lengths"):
+        sm.PackedVec.from_pairs(3, [0, 1], [1.0])
+    with pytest.raises(ValueError, match="different lengths"):
+        sm.PackedVec(np.ones(3)).dot(sm.PackedVec(np.ones(4)))
+
+
+def test_buffers_are_shared_read_only():
+    a = sp.random(30, 20, density=0.15, format="csr", random_state=13)
+    m = sm.CsrMatrix.from_scipy(a)
+    back = m.to_scipy()
+    for ours, theirs in [
+        (m.data, back.data),
+        (m.indices, back.indices),
+        (m.indptr, back.indptr),
+    ]:
+        assert np.shares_memory(ours, theirs)
+        assert not ours.flags.writeable
+        assert not theirs.flags.writeable
+    assert m.indices.dtype == np.intp
+    with pytest.raises(ValueError):
+        m.data[0] = 1.0
+
+    # The views keep the Rust matrix alive.
+    del m
+    np.testing.assert_array_equal(back.toarray(), a.toarray())
+
+    # A copy is SciPy's own, and writable.
+    writable = back.copy()
+    writable.data[:] = 0.0
+    assert writable.nnz == a.nnz
+    np.testing.assert_array_equal(back.toarray(), a.toarray())
+
+    v = sm.PackedVec(np.array([0.0, 4.0, 5.0]))
+    values = v.values()
+    del v
+    np.testing.assert_array_equal(values, [4.0, 5.0])
@@ -0,0 +1,169 @@
+//! [proptest](https://docs.rs/proptest) strategies for packed vectors and CSR matrices, behind
+//! the `proptest` feature, to check algebraic identities over many random inputs:
+//!
+//! ```
+//! use proptest::prelude::*;
+//! use sparse_matrix::arbitrary::{csr_matrix, dense_vec};
+//!
+//! proptest!(|(a in csr_matrix(4, 6), x in dense_vec(6))| {
+//!     prop_assert_eq!(a.transpose().transpose().mul_vec(&x), a.mul_vec(&x));
+//! });
+//! ```
+//!
+//! The patterns favour the edge cases: no entry at all, a single entry and a full pattern come
+//! up as often as a random one. Values are nonzero multiples of 1/8 no larger than 125 in
+//! magnitude, so the sums and products of a few of them are exact and identities can be
+//! checked with `==` rather than with a tolerance.
+
+use proptest::arbitrary::Arbitrary;
+use proptest::collection::vec;
+use proptest::prelude::*;
+use proptest::sample::subsequence;
+
+use crate::csr::CsrMatrix;
+use crate::vec::PackedVec;
+
+/// The largest length of the vectors from [`Arbitrary`].
+pub const MAX_VEC_LEN: usize = 32;
+
+/// The largest number of rows and of columns of the matrices from [`Arbitrary`].
+pub const MAX_MATRIX_DIM: usize = 8;
+
+/// A nonzero multiple of 1/8 between -125 and 125.
+pub fn value() -> impl Strategy<Value = f64> {
+    (1..=1000i32, any::<bool>()).prop_map(|(k, negative)| {
+        let v = k as f64 / 8.0;
+        if negative {
+            -v
+        } else {
+            v
+        }
+    })
+}
+
+/// A dense vector of length `len`, of [`value`]s.
+pub fn dense_vec(len: usize) -> impl Strategy<Value = Vec<f64>> {
+    vec(value(), len)
+}
+
+/// Increasing positions in `0..len`: none, one, all of them, or a random subset.
+fn pattern(len: usize) -> BoxedStrategy<Vec<usize>> {
+    if len == 0 {
+        return Just(Vec::new()).boxed();
+    }
+    let all: Vec<usize> = (0..len).collect();
+    prop_oneof![
+        Just(Vec::new()),
+        (0..len).prop_map(|k| vec![k]),
+        Just(all.clone()),
+        subsequence(all, 0..=len),
+    ]
+    .boxed()
+}
+
+/// A pattern of `len` positions with a [`value`] at each.
+fn entries(len: usize) -> impl Strategy<Value = Vec<(usize, f64)>> {
+    pattern(len).prop_flat_map(|positions| {
+        let n = positions.len();
+        (Just(positions), vec(value(), n))
+            .prop_map(|(positions, values)| positions.into_iter().zip(values).collect())
+    })
+}
+
+/// A packed vector of length `len`.
+pub fn packed_vec(len: usize) -> impl Strategy<Value = PackedVec> {
+    entries(len)
+        .prop_map(move |pairs| PackedVec::from_pairs(len, pairs).expect("positions are in bounds"))
+}
+
+/// An `nrows` by `ncols` CSR matrix.
+pub fn csr_matrix(nrows: usize, ncols: usize) -> impl Strategy<Value = CsrMatrix> {
+    entries(nrows * ncols).prop_map(move |entries| {
+        // The positions increase, so the entries come row by row with sorted columns.
+        let mut indptr = vec![0; nrows + 1];
+        for &(p, _) in &entries {
+            indptr[p / ncols + 1] += 1;
+        }
+        for i in 0..nrows {
+            indptr[i + 1] += indptr[i];
+        }
+        let indices = entries.iter().map(|&(p, _)| p % ncols).collect();
+        let data = entries.iter().map(|&(_, v)| v).collect();
+        CsrMatrix::from_parts(nrows, ncols, indptr, indices, data)
+    })
+}
+
+/// Vectors of any length up to [`MAX_VEC_LEN`].
+impl Arbitrary for PackedVec<f64> {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (0..=MAX_VEC_LEN).prop_flat_map(packed_vec).boxed()
+    }
+}
+
+/// Matrices of any shape up to [`MAX_MATRIX_DIM`] by [`MAX_MATRIX_DIM`].
+impl Arbitrary for CsrMatrix<f64> {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (0..=MAX_MATRIX_DIM, 0..=MAX_MATRIX_DIM)
+            .prop_flat_map(|(nrows, ncols)| csr_matrix(nrows, ncols))
+            .boxed()
+    }
+}
+
+/// Two matrices and a vector that can be multiplied as `A B x`.
+#[cfg(test)]
+fn chain() -> impl Strategy<Value = (CsrMatrix, CsrMatrix, Vec<f64>)> {
+    (0..=MAX_MATRIX_DIM, 0..=MAX_MATRIX_DIM, 0..=MAX_MATRIX_DIM)
+        .prop_flat_map(|(m, k, n)| (csr_matrix(m, k), csr_matrix(k, n), dense_vec(n)))
+}
+
+#[cfg(test)]
+proptest! {
+    #[test]
+    fn test_arbitrary_is_valid(a in any::<CsrMatrix>(), x in any::<PackedVec>()) {
+        let copy = CsrMatrix::try_from_csr_data(
+            a.nrows(),
+            a.ncols(),
+            a.indptr().to_vec(),
+            a.indices().to_vec(),
+            a.data().to_vec(),
+        );
+        prop_assert_eq!(copy, Ok(a.clone()));
+        prop_assert!(a.data().iter().all(|&v| v != 0.0));
+        prop_assert!(x.len() <= x.full_len() && x.iter().all(|(_, v)| v != 0.0));
+    }
+
+    /// `[A B] [x; x] = A x + B x`, the sum of two matrices through a block row.
+    #[test]
+    fn test_mul_vec_distributes(
+        (a, b, x) in (0..=MAX_MATRIX_DIM, 0..=MAX_MATRIX_DIM).prop_flat_map(|(m, n)| {
+            (csr_matrix(m, n), csr_matrix(m, n), dense_vec(n))
+        })
+    ) {
+        let sum = crate::ops::stack::hstack(&[&a, &b]).unwrap();
+        let xx: Vec<f64> = x.iter().chain(&x).copied().collect();
+        let expected: Vec<f64> = a.mul_vec(&x).iter().zip(b.mul_vec(&x)).map(|(p, q)| p + q).collect();
+        prop_assert_eq!(sum.mul_vec(&xx), expected);
+    }
+
+    #[test]
+    fn test_matmul_associates_with_mul_vec((a, b, x) in chain()) {
+        prop_assert_eq!((&a * &b).mul_vec(&x), a.mul_vec(&b.mul_vec(&x)));
+        prop_assert_eq!(a.transpose().transpose(), a.clone());
+        prop_assert_eq!(a.to_csc().to_csr(), a);
+    }
+
+    #[test]
+    fn test_packed_vec_identities(
+        (x, y, z) in (0..=MAX_VEC_LEN).prop_flat_map(|n| (packed_vec(n), packed_vec(n), dense_vec(n)))
+    ) {
+        prop_assert_eq!(PackedVec::gather(&x.scatter()), x.clone());
+        prop_assert_eq!((&x + &y).dot_dense(&z), x.dot_dense(&z) + y.dot_dense(&z));
+        prop_assert_eq!(&x * &y, &y * &x);
+    }
+}
@@ -0,0 +1,246 @@
+//! Tracking the largest of a set of values that change a few at a time, for greedy coordinate
+//! selection.
+//!
+//! A Gauss–Southwell coordinate descent updates one coordinate per step and then picks the
+//! coordinate with the largest gradient magnitude. Only the gradients of the columns sharing a
+//! row with the updated one change, so rescanning all `n` of them each step wastes most of the
+//! work. [`ArgmaxTracker`] keeps the values in a binary heap instead and invalidates the stale
+//! heap entries lazily, which makes both an update and the selection O(log n) amortized.
+//!
+//! ```
+//! use sparse_matrix::argmax::ArgmaxTracker;
+//!
+//! let mut tracker = ArgmaxTracker::new(&[1.0, 4.0, 2.0]);
+//! assert_eq!(tracker.argmax(), Some((1, 4.0)));
+//! tracker.update(1, 0.5);
+//! assert_eq!(tracker.argmax(), Some((2, 2.0)));
+//! ```
+
+use alloc::collections::BinaryHeap;
+use alloc::vec::Vec;
+use core::cmp::{Ordering, Reverse};
+
+/// A heap entry: a value, its position, and the version of the position it was pushed at.
+#[derive(Clone, Copy, Debug)]
+struct Entry {
+    value: f64,
+    index: usize,
+    version: u64,
+}
+
+/// Order entries by value with [`f64::total_cmp`], then by the lower index, so that ties go to
+/// the first position as a linear scan would.
+impl Ord for Entry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.value
+            .total_cmp(&other.value)
+            .then(Reverse(self.index).cmp(&Reverse(other.index)))
+    }
+}
+
+impl PartialOrd for Entry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Entry {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Entry {}
+
+/// The position of the largest of `n` values under updates, such as the gradient magnitudes of
+/// a coordinate descent.
+///
+/// An update pushes a new heap entry and bumps the version of its position rather than search
+/// the heap for the old entry, which [`argmax`](Self::argmax) drops once it surfaces. The heap
+/// is rebuilt from the current values when the stale entries outnumber the live ones, so it
+/// never holds more than about `2n` entries. Values are compared with [`f64::total_cmp`], so a
+/// (positive) NaN is larger than any number, and ties go to the lowest position.
+#[derive(Clone, Debug)]
+pub struct ArgmaxTracker {
+    values: Vec<f64>,
+    versions: Vec<u64>,
+    heap: BinaryHeap<Entry>,
+}
+
+impl ArgmaxTracker {
+    /// Track the values `values`, indexed by position. O(n).
+    pub fn new(values: &[f64]) -> Self {
+        let mut tracker = Self {
+            values: values.to_vec(),
+            versions: alloc::vec![0; values.len()],
+            heap: BinaryHeap::new(),
+        };
+        tracker.rebuild();
+        tracker
+    }
+
+    /// Return the number of tracked values
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Return true when no value is tracked
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Return the current value at position `j`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `j >= self.len()`.
+    pub fn get(&self, j: usize) -> f64 {
+        self.values[j]
+    }
+
+    /// Set the value at position `j`. O(log n) amortized.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `j >= self.len()`.
+    pub fn update(&mut self, j: usize, value: f64) {
+        self.values[j] = value;
+        self.versions[j] += 1;
+        self.heap.push(Entry {
+            value,
+            index: j,
+            version: self.versions[j],
+        });
+        if self.heap.len() > 2 * self.values.len() + 16 {
+            self.rebuild();
+        }
+    }
+
+    /// Return the position of the largest value and the value, `None` when no value is
+    /// tracked. Drops the stale entries on top of the heap, O(log n) amortized.
+    pub fn argmax(&mut self) -> Option<(usize, f64)> {
+        while let Some(top) = self.heap.peek() {
+            if top.version == self.versions[top.index] {
+                return Some((top.index, top.value));
+            }
+            self.heap.pop();
+        }
+        None
+    }
+
+    /// Replace the heap with one live entry per position. O(n).
+    fn rebuild(&mut self) {
+        let entries: Vec<Entry> = (self.values.iter().zip(&self.versions).enumerate())
+            .map(|(index, (&value, &version))| Entry {
+                value,
+                index,
+                version,
+            })
+            .collect();
+        self.heap = BinaryHeap::from(entries);
+    }
+}
+
+#[test]
+fn test_argmax_tracker() {
+    use crate::test_util::Lcg;
+
+    let mut rng = Lcg::new(59);
+    let n = 40;
+    let mut values: Vec<f64> = (0..n).map(|_| rng.uniform()).collect();
+    let mut tracker = ArgmaxTracker::new(&values);
+    assert_eq!(tracker.len(), n);
+
+    // Many more updates than positions, to go through several rebuilds.
+    for step in 0..10 * n {
+        let j = rng.below(n);
+        values[j] = rng.uniform();
+        tracker.update(j, values[j]);
+        let (k, v) = tracker.argmax().unwrap();
+        let expected = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        assert_eq!(v, expected, "step {step}");
+        assert_eq!(k, values.iter().position(|&x| x == expected).unwrap());
+        assert_eq!(tracker.get(j), values[j]);
+        assert!(tracker.heap.len() <= 2 * n + 16);
+    }
+
+    // Ties go to the first position, NaN wins.
+    let mut tracker = ArgmaxTracker::new(&[1.0, 3.0, 3.0, 2.0]);
+    assert_eq!(tracker.argmax(), Some((1, 3.0)));
+    tracker.update(1, 0.0);
+    assert_eq!(tracker.argmax(), Some((2, 3.0)));
+    tracker.update(0, f64::NAN);
+    assert_eq!(tracker.argmax().unwrap().0, 0);
+
+    let mut empty = ArgmaxTracker::new(&[]);
+    assert!(empty.is_empty());
+    assert_eq!(empty.argmax(), None);
+}
+
+#[test]
+fn test_coordinate_descent() {
+    use crate::csr::CsrMatrix;
+    use crate::test_util::{assert_close, Lcg};
+
+    // Gauss–Southwell coordinate descent on min ‖b − A x‖², exact line search along the
+    // coordinate of the largest gradient magnitude |(A e_j)ᵀ r|.
+    let (m, n) = (40, 25);
+    let mut rng = Lcg::new(61);
+    let mut dense = rng.dense(m, n, 0.1);
+    for j in 0..n {
+        dense[j * n + j] += 2.0;
+    }
+    let b: Vec<f64> = (0..m).map(|_| rng.uniform()).collect();
+    let a_rows = CsrMatrix::from_dense(m, n, &dense).unwrap();
+    let a = a_rows.to_csc();
+    let col_norms: Vec<f64> = (0..n)
+        .map(|j| a.col(j).1.iter().map(|v| v * v).sum())
+        .collect();
+
+    // The sparse version, updating only the gradients the step touched.
+    let mut x = vec![0.0; n];
+    let mut r = b.clone();
+    let gradient: Vec<f64> = (0..n).map(|j| a.col_dot_dense(j, &r).abs()).collect();
+    let mut tracker = ArgmaxTracker::new(&gradient);
+
+    // The dense reference, rescanning every gradient.
+    let mut dense_x = vec![0.0; n];
+    let mut dense_r = b.clone();
+
+    for step in 0..50 {
+        let (j, _) = tracker.argmax().unwrap();
+        let delta = a.col_dot_dense(j, &r) / col_norms[j];
+        x[j] += delta;
+        a.col_axpy_into(j, -delta, &mut r);
+        let (rows, _) = a.col(j);
+        for &i in rows {
+            for &k in a_rows.row(i).0 {
+                tracker.update(k, a.col_dot_dense(k, &r).abs());
+            }
+        }
+
+        let dense_gradient: Vec<f64> = (0..n)
+            .map(|k| (0..m).map(|i| dense[i * n + k] * dense_r[i]).sum::<f64>())
+            .collect();
+        let dense_j = (0..n).fold(0, |best, k| {
+            if dense_gradient[k].abs() > dense_gradient[best].abs() {
+                k
+            } else {
+                best
+            }
+        });
+        assert_eq!(j, dense_j, "step {step}");
+        let dense_delta = dense_gradient[dense_j] / col_norms[dense_j];
+        dense_x[dense_j] += dense_delta;
+        for i in 0..m {
+            dense_r[i] -= dense_delta * dense[i * n + dense_j];
+        }
+
+        assert_close(&x, &dense_x, 1e-12);
+        assert_close(&r, &dense_r, 1e-12);
+    }
+
+    // The residual went down.
+    let norm = |v: &[f64]| v.iter().map(|vi| vi * vi).sum::<f64>();
+    assert!(norm(&r) < 0.5 * norm(&b));
+}
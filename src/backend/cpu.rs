@@ -0,0 +1,76 @@
+//! The backend that keeps the matrix in host memory.
+
+use super::Backend;
+use crate::csr::CsrMatrix;
+use crate::error::SparseError;
+
+/// The host itself as a [`Backend`]: a matrix is a copy of the [`CsrMatrix`], multiplied by
+/// the kernels of this crate.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CpuBackend;
+
+impl Backend for CpuBackend {
+    type Matrix = CsrMatrix<f64>;
+
+    fn upload(&self, a: &CsrMatrix<f64>) -> Result<CsrMatrix<f64>, SparseError> {
+        Ok(a.clone())
+    }
+
+    fn spmm(
+        &self,
+        a: &CsrMatrix<f64>,
+        x: &[f64],
+        k: usize,
+        y: &mut [f64],
+    ) -> Result<(), SparseError> {
+        assert_eq!(x.len(), a.ncols() * k, "x has the wrong length");
+        assert_eq!(y.len(), a.nrows() * k, "y has the wrong length");
+        if k == 0 {
+            return Ok(());
+        }
+        let (indptr, indices, data) = (a.indptr(), a.indices(), a.data());
+        for (i, yi) in y.chunks_exact_mut(k).enumerate() {
+            yi.fill(0.0);
+            for p in indptr[i]..indptr[i + 1] {
+                let (j, v) = (indices[p], data[p]);
+                for (yic, &xjc) in yi.iter_mut().zip(&x[j * k..(j + 1) * k]) {
+                    *yic += v * xjc;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_cpu_backend() {
+    use crate::test_util::{assert_close, Lcg};
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    let (m, n, k) = (13, 9, 3);
+    let mut rng = Lcg::new(113);
+    let a = CsrMatrix::from_dense(m, n, &rng.dense(m, n, 0.3)).unwrap();
+    let x: Vec<f64> = (0..n * k).map(|_| rng.uniform()).collect();
+
+    let backend = CpuBackend;
+    let device_a = backend.upload(&a).unwrap();
+    let mut y = vec![f64::NAN; m * k];
+    backend.spmm(&device_a, &x, k, &mut y).unwrap();
+    for c in 0..k {
+        let xc: Vec<f64> = (0..n).map(|j| x[j * k + c]).collect();
+        let yc: Vec<f64> = (0..m).map(|i| y[i * k + c]).collect();
+        assert_close(&yc, &a.mul_vec(&xc), 1e-14);
+    }
+
+    let mut y1 = vec![0.0; m];
+    backend.spmv(&device_a, &x[..n], &mut y1);
+    assert_eq!(y1, a.mul_vec(&x[..n]));
+
+    // No columns at all.
+    backend.spmm(&device_a, &[], 0, &mut []).unwrap();
+
+    // An ELL matrix is taken as CSR.
+    let ell = crate::ell::EllMatrix::from_csr(&a);
+    assert_eq!(backend.upload_ell(&ell).unwrap(), a);
+}
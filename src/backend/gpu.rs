@@ -0,0 +1,984 @@
+//! The GPU backend, behind the `gpu` feature: CSR and ELL matrices in device buffers, multiplied
+//! by WGSL compute kernels through wgpu, on whichever of Vulkan, Metal, DirectX 12 or OpenGL
+//! the platform offers.
+//!
+//! WGSL has no portable 64-bit float, so the device stores and multiplies in `f32`. A single
+//! product, through [`Backend::spmv`], [`Backend::spmm`] or the [`LinearOperator`] a
+//! [`GpuMatrix`] is, is therefore only good to about `1e-7` relative to the magnitude of its
+//! terms, and a solver of [`crate::iterative`] run over it stalls there.
+//!
+//! [`GpuBackend::cg`] and [`GpuBackend::bicgstab`] reach `f64` accuracy by mixed-precision
+//! iterative refinement. Each refinement step runs the Krylov method entirely on the device in
+//! `f32`, vectors included, to a relative residual of [`INNER_TOL`], for a correction to the
+//! current solution; the residual of the corrected solution is then recomputed in `f64` on the
+//! host, with the copy of the matrix a [`GpuMatrix`] keeps there. Only the scalars of the
+//! inner products come back to the host during an iteration, to pick the step lengths. Each
+//! step gains about four digits as long as the matrix is well conditioned in `f32`; when a step
+//! no longer reduces the `f64` residual, the solver stops without converging.
+
+use std::sync::mpsc;
+use std::vec;
+use std::vec::Vec;
+
+use wgpu::util::DeviceExt;
+
+use super::Backend;
+use crate::csr::CsrMatrix;
+use crate::dense::norm2;
+use crate::ell::EllMatrix;
+use crate::error::SparseError;
+use crate::iterative::{initial_guess, SolveResult, SolverOptions};
+use crate::operator::LinearOperator;
+
+/// Threads per workgroup of every kernel, the `@workgroup_size` of the shaders
+const WORKGROUP_SIZE: u32 = 64;
+
+/// The relative residual every refinement step of [`GpuBackend::cg`] and
+/// [`GpuBackend::bicgstab`] solves its correction to, well above the `f32` rounding error
+pub const INNER_TOL: f64 = 1e-4;
+
+/// `y = A x` for a CSR `A` and the `k` row-major columns of `x`, one thread per entry of `y`.
+/// The threads are laid out over a 2-D grid of workgroups, `stride` threads per row of the
+/// grid, so that no dimension exceeds the limit of the device.
+const CSR_SHADER: &str = r#"
+struct Params {
+    len: u32,
+    k: u32,
+    stride: u32,
+    width: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read> indptr: array<u32>;
+@group(0) @binding(2) var<storage, read> indices: array<u32>;
+@group(0) @binding(3) var<storage, read> values: array<f32>;
+@group(0) @binding(4) var<storage, read> x: array<f32>;
+@group(0) @binding(5) var<storage, read_write> y: array<f32>;
+
+@compute @workgroup_size(64)
+fn main(@builtin(global_invocation_id) gid: vec3<u32>) {
+    let t = gid.y * params.stride + gid.x;
+    if t >= params.len {
+        return;
+    }
+    let i = t / params.k;
+    let c = t % params.k;
+    var sum = 0.0;
+    for (var p = indptr[i]; p < indptr[i + 1u]; p++) {
+        sum += values[p] * x[indices[p] * params.k + c];
+    }
+    y[t] = sum;
+}
+"#;
+
+/// `y = A x` for an ELL `A` of `width` column-major slots per row, laid out as
+/// [`CSR_SHADER`]. Neighbouring threads read neighbouring slots.
+const ELL_SHADER: &str = r#"
+struct Params {
+    len: u32,
+    k: u32,
+    stride: u32,
+    width: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read> indices: array<u32>;
+@group(0) @binding(2) var<storage, read> values: array<f32>;
+@group(0) @binding(3) var<storage, read> x: array<f32>;
+@group(0) @binding(4) var<storage, read_write> y: array<f32>;
+
+@compute @workgroup_size(64)
+fn main(@builtin(global_invocation_id) gid: vec3<u32>) {
+    let t = gid.y * params.stride + gid.x;
+    if t >= params.len {
+        return;
+    }
+    let nrows = params.len / params.k;
+    let i = t / params.k;
+    let c = t % params.k;
+    var sum = 0.0;
+    for (var s = 0u; s < params.width; s++) {
+        let slot = s * nrows + i;
+        sum += values[slot] * x[indices[slot] * params.k + c];
+    }
+    y[t] = sum;
+}
+"#;
+
+/// The sum of `x[t] * y[t]` over each workgroup, written to `partial` at the index of the
+/// workgroup, for the host to add up.
+const DOT_SHADER: &str = r#"
+struct Params {
+    len: u32,
+    stride: u32,
+    a: f32,
+    b: f32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read> x: array<f32>;
+@group(0) @binding(2) var<storage, read> y: array<f32>;
+@group(0) @binding(3) var<storage, read_write> partial: array<f32>;
+
+var<workgroup> sums: array<f32, 64>;
+
+@compute @workgroup_size(64)
+fn main(
+    @builtin(global_invocation_id) gid: vec3<u32>,
+    @builtin(local_invocation_index) lid: u32,
+    @builtin(workgroup_id) wid: vec3<u32>,
+    @builtin(num_workgroups) groups: vec3<u32>,
+) {
+    let t = gid.y * params.stride + gid.x;
+    var v = 0.0;
+    if t < params.len {
+        v = x[t] * y[t];
+    }
+    sums[lid] = v;
+    workgroupBarrier();
+    for (var half = 32u; half > 0u; half = half / 2u) {
+        if lid < half {
+            sums[lid] += sums[lid + half];
+        }
+        workgroupBarrier();
+    }
+    if lid == 0u {
+        partial[wid.y * groups.x + wid.x] = sums[0];
+    }
+}
+"#;
+
+/// `y = a x + b y`.
+const AXPBY_SHADER: &str = r#"
+struct Params {
+    len: u32,
+    stride: u32,
+    a: f32,
+    b: f32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read> x: array<f32>;
+@group(0) @binding(2) var<storage, read_write> y: array<f32>;
+
+@compute @workgroup_size(64)
+fn main(@builtin(global_invocation_id) gid: vec3<u32>) {
+    let t = gid.y * params.stride + gid.x;
+    if t >= params.len {
+        return;
+    }
+    y[t] = params.a * x[t] + params.b * y[t];
+}
+"#;
+
+/// What a matrix needs of the device to multiply itself and to solve with itself.
+#[derive(Clone, Debug)]
+struct Context {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    csr: wgpu::ComputePipeline,
+    ell: wgpu::ComputePipeline,
+    dot: wgpu::ComputePipeline,
+    axpby: wgpu::ComputePipeline,
+    /// Largest storage buffer a kernel can bind, in bytes
+    max_binding: u64,
+    /// Largest number of workgroups along one dimension of a dispatch
+    max_workgroups: u32,
+}
+
+/// A GPU as a [`Backend`], holding its matrices as [`GpuMatrix`].
+#[derive(Clone, Debug)]
+pub struct GpuBackend {
+    context: Context,
+    info: wgpu::AdapterInfo,
+}
+
+impl GpuBackend {
+    /// Acquire the default adapter, preferring a discrete GPU, and compile the kernels for it.
+    ///
+    /// Fails with [`SparseError::Device`] when the platform offers no adapter or the adapter
+    /// refuses a device.
+    pub fn new() -> Result<GpuBackend, SparseError> {
+        let instance = wgpu::Instance::default();
+        let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            ..Default::default()
+        }))
+        .map_err(|e| SparseError::Device(e.to_string()))?;
+        let limits = adapter.limits();
+        let (device, queue) = pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor {
+            label: Some("sparse-matrix"),
+            required_limits: limits.clone(),
+            ..Default::default()
+        }))
+        .map_err(|e| SparseError::Device(e.to_string()))?;
+
+        let pipeline = |label, source: &str| {
+            let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some(label),
+                source: wgpu::ShaderSource::Wgsl(source.into()),
+            });
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(label),
+                layout: None,
+                module: &module,
+                entry_point: Some("main"),
+                compilation_options: Default::default(),
+                cache: None,
+            })
+        };
+        let (csr, ell) = (pipeline("csr", CSR_SHADER), pipeline("ell", ELL_SHADER));
+        let (dot, axpby) = (pipeline("dot", DOT_SHADER), pipeline("axpby", AXPBY_SHADER));
+
+        Ok(GpuBackend {
+            context: Context {
+                device,
+                queue,
+                csr,
+                ell,
+                dot,
+                axpby,
+                max_binding: limits.max_storage_buffer_binding_size,
+                max_workgroups: limits.max_compute_workgroups_per_dimension,
+            },
+            info: adapter.get_info(),
+        })
+    }
+
+    /// Return the name of the adapter, as its driver reports it
+    pub fn adapter_name(&self) -> &str {
+        &self.info.name
+    }
+
+    /// Solve `A x = b` for a symmetric positive definite `A` with the conjugate gradient
+    /// method on the device, refined to `f64` accuracy, starting from `x0` (zero when `None`).
+    ///
+    /// `iterations` counts the CG iterations of all refinement steps together, each of which
+    /// costs one product with `A` and two inner products on the device. `residual_history`
+    /// holds the `f64` residual norm of the initial guess, then after every refinement step.
+    ///
+    /// Fails as [`crate::iterative::cg()`] does, when `A` isn't square, `b` or `x0` has the wrong
+    /// length, or a row empty in `A` has a nonzero in `b`. A breakdown, on a matrix that isn't
+    /// positive definite, ends the refinement without converging.
+    pub fn cg(
+        &self,
+        a: &GpuMatrix,
+        b: &[f64],
+        x0: Option<&[f64]>,
+        opts: &SolverOptions,
+    ) -> Result<SolveResult, SparseError> {
+        a.refine(b, x0, opts, GpuMatrix::cg_correction)
+    }
+
+    /// Solve `A x = b` for a general square `A` with the stabilized biconjugate gradient method
+    /// on the device, refined to `f64` accuracy, starting from `x0` (zero when `None`).
+    ///
+    /// Reports and fails as [`GpuBackend::cg`] does. Each BiCGSTAB iteration costs two
+    /// products with `A` and five inner products on the device.
+    pub fn bicgstab(
+        &self,
+        a: &GpuMatrix,
+        b: &[f64],
+        x0: Option<&[f64]>,
+        opts: &SolverOptions,
+    ) -> Result<SolveResult, SparseError> {
+        a.refine(b, x0, opts, GpuMatrix::bicgstab_correction)
+    }
+}
+
+impl Backend for GpuBackend {
+    type Matrix = GpuMatrix;
+
+    /// Copy the structure of `a` to the device as `u32` and its entries as `f32`.
+    ///
+    /// Fails with [`SparseError::TooManyEntries`] when a row pointer doesn't fit in a `u32` or
+    /// the entries don't fit in one storage buffer of the device.
+    fn upload(&self, a: &CsrMatrix<f64>) -> Result<GpuMatrix, SparseError> {
+        let context = &self.context;
+        context.check_len(a.nnz())?;
+        context.check_len(a.nrows() + 1)?;
+        context.check_len(a.ncols())?;
+        let indptr: Vec<u32> = a.indptr().iter().map(|&p| p as u32).collect();
+        let indices: Vec<u32> = a.indices().iter().map(|&j| j as u32).collect();
+        let values: Vec<f32> = a.data().iter().map(|&v| v as f32).collect();
+        Ok(GpuMatrix {
+            layout: Layout::Csr {
+                indptr: context.storage_buffer("indptr", &indptr),
+                indices: context.storage_buffer("indices", &indices),
+                values: context.storage_buffer("values", &values),
+            },
+            context: context.clone(),
+            host: a.clone(),
+        })
+    }
+
+    /// Copy the slots of `a` to the device, padding included, the columns as `u32` and the
+    /// values as `f32`.
+    ///
+    /// Fails with [`SparseError::TooManyEntries`] when the slots don't fit in one storage
+    /// buffer of the device.
+    fn upload_ell(&self, a: &EllMatrix<f64>) -> Result<GpuMatrix, SparseError> {
+        let context = &self.context;
+        context.check_len(a.indices().len())?;
+        context.check_len(a.nrows() + 1)?;
+        context.check_len(a.ncols())?;
+        let indices: Vec<u32> = a.indices().iter().map(|&j| j as u32).collect();
+        let values: Vec<f32> = a.data().iter().map(|&v| v as f32).collect();
+        Ok(GpuMatrix {
+            layout: Layout::Ell {
+                width: a.width() as u32,
+                indices: context.storage_buffer("indices", &indices),
+                values: context.storage_buffer("values", &values),
+            },
+            context: context.clone(),
+            host: a.to_csr(),
+        })
+    }
+
+    fn spmm(&self, a: &GpuMatrix, x: &[f64], k: usize, y: &mut [f64]) -> Result<(), SparseError> {
+        a.mul_dense(x, k, y)
+    }
+}
+
+/// Return `len` as the `u32` the kernels index with, failing with
+/// [`SparseError::TooManyEntries`] when it exceeds that or `max_binding` bytes of `f32`.
+fn checked_len(len: usize, max_binding: u64) -> Result<u32, SparseError> {
+    let limit = (max_binding / 4).min(u32::MAX.into()) as usize;
+    if len > limit {
+        return Err(SparseError::TooManyEntries { nnz: len, limit });
+    }
+    Ok(len as u32)
+}
+
+impl Context {
+    fn check_len(&self, len: usize) -> Result<u32, SparseError> {
+        checked_len(len, self.max_binding)
+    }
+
+    /// Create a storage buffer holding `data`, or one zero when it is empty, since a binding
+    /// can't be empty.
+    fn storage_buffer<T: bytemuck::Pod>(&self, label: &str, data: &[T]) -> wgpu::Buffer {
+        let zero = [0u32];
+        let contents = if data.is_empty() {
+            bytemuck::cast_slice(&zero)
+        } else {
+            bytemuck::cast_slice(data)
+        };
+        self.device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some(label),
+                contents,
+                usage: wgpu::BufferUsages::STORAGE
+                    | wgpu::BufferUsages::COPY_SRC
+                    | wgpu::BufferUsages::COPY_DST,
+            })
+    }
+
+    /// Create a zero vector of `len` `f32` on the device.
+    fn vector(&self, len: usize) -> wgpu::Buffer {
+        self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("vector"),
+            size: 4 * len.max(1) as u64,
+            usage: wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::COPY_SRC
+                | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    /// Return the workgroups along x and y and the threads per row of the grid for `len`
+    /// threads.
+    fn grid(&self, len: u32) -> (u32, u32, u32) {
+        let groups = len.div_ceil(WORKGROUP_SIZE).max(1);
+        let groups_x = groups.min(self.max_workgroups);
+        (
+            groups_x,
+            groups.div_ceil(groups_x),
+            groups_x * WORKGROUP_SIZE,
+        )
+    }
+
+    /// Run `pipeline` once over the grid of `len` threads, with the uniform `params` at binding
+    /// 0 and `buffers` at the bindings after it.
+    fn run(
+        &self,
+        pipeline: &wgpu::ComputePipeline,
+        (groups_x, groups_y): (u32, u32),
+        params: [u32; 4],
+        buffers: &[&wgpu::Buffer],
+    ) {
+        let params = self
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("params"),
+                contents: bytemuck::cast_slice(&params),
+                usage: wgpu::BufferUsages::UNIFORM,
+            });
+        let entries: Vec<wgpu::BindGroupEntry<'_>> = core::iter::once(&params)
+            .chain(buffers.iter().copied())
+            .enumerate()
+            .map(|(binding, buffer)| wgpu::BindGroupEntry {
+                binding: binding as u32,
+                resource: buffer.as_entire_binding(),
+            })
+            .collect();
+        let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: &pipeline.get_bind_group_layout(0),
+            entries: &entries,
+        });
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: None,
+                timestamp_writes: None,
+            });
+            pass.set_pipeline(pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.dispatch_workgroups(groups_x, groups_y, 1);
+        }
+        self.queue.submit([encoder.finish()]);
+    }
+
+    /// Copy the first `len` `f32` of `src` into `dst`.
+    fn copy(&self, src: &wgpu::Buffer, dst: &wgpu::Buffer, len: usize) {
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        encoder.copy_buffer_to_buffer(src, 0, dst, 0, 4 * len as u64);
+        self.queue.submit([encoder.finish()]);
+    }
+
+    /// Read the first `len` `f32` of `buffer` back, waiting for the work submitted before.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the device is lost.
+    fn read(&self, buffer: &wgpu::Buffer, len: usize) -> Vec<f32> {
+        if len == 0 {
+            return Vec::new();
+        }
+        let bytes = 4 * len as u64;
+        let readback = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("readback"),
+            size: bytes,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        encoder.copy_buffer_to_buffer(buffer, 0, &readback, 0, bytes);
+        self.queue.submit([encoder.finish()]);
+
+        let (sender, receiver) = mpsc::channel();
+        readback
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                let _ = sender.send(result);
+            });
+        self.device
+            .poll(wgpu::PollType::wait_indefinitely())
+            .expect("the GPU device was lost");
+        receiver
+            .recv()
+            .expect("the readback was dropped")
+            .expect("the buffer could not be read back");
+        let view = readback.slice(..).get_mapped_range().unwrap();
+        bytemuck::cast_slice(&view).to_vec()
+    }
+
+    /// Return `x · y` over the first `len` entries, summed per workgroup on the device and
+    /// across workgroups in `f64` on the host.
+    fn dot(&self, x: &wgpu::Buffer, y: &wgpu::Buffer, len: usize) -> f64 {
+        let (groups_x, groups_y, stride) = self.grid(len as u32);
+        let groups = (groups_x * groups_y) as usize;
+        let partial = self.vector(groups);
+        self.run(
+            &self.dot,
+            (groups_x, groups_y),
+            [len as u32, stride, 0, 0],
+            &[x, y, &partial],
+        );
+        self.read(&partial, groups)
+            .iter()
+            .map(|&v| f64::from(v))
+            .sum()
+    }
+
+    /// Set `y = a x + b y` over the first `len` entries.
+    fn axpby(&self, a: f64, x: &wgpu::Buffer, b: f64, y: &wgpu::Buffer, len: usize) {
+        let (groups_x, groups_y, stride) = self.grid(len as u32);
+        let params = [
+            len as u32,
+            stride,
+            (a as f32).to_bits(),
+            (b as f32).to_bits(),
+        ];
+        self.run(&self.axpby, (groups_x, groups_y), params, &[x, y]);
+    }
+}
+
+/// The storage of a [`GpuMatrix`] on the device.
+#[derive(Clone, Debug)]
+enum Layout {
+    Csr {
+        indptr: wgpu::Buffer,
+        indices: wgpu::Buffer,
+        values: wgpu::Buffer,
+    },
+    Ell {
+        width: u32,
+        indices: wgpu::Buffer,
+        values: wgpu::Buffer,
+    },
+}
+
+/// A CSR or ELL matrix in the buffers of a GPU, from [`GpuBackend::upload`] or
+/// [`GpuBackend::upload_ell`], with a CSR copy in `f64` kept on the host for the residuals of
+/// the refinement.
+///
+/// It is a [`LinearOperator`], whose products run on the GPU in `f32`.
+#[derive(Clone, Debug)]
+pub struct GpuMatrix {
+    context: Context,
+    layout: Layout,
+    host: CsrMatrix<f64>,
+}
+
+/// A Krylov method run on the device, see [`GpuMatrix::cg_correction`].
+type Correction = fn(&GpuMatrix, &wgpu::Buffer, f64, usize) -> (wgpu::Buffer, usize);
+
+impl GpuMatrix {
+    /// Return the copy of the matrix kept on the host
+    pub fn host(&self) -> &CsrMatrix<f64> {
+        &self.host
+    }
+
+    /// Compute `y = A x` on the device for vectors already there, holding `k` row-major
+    /// columns each, with `len = nrows * k` already checked to fit.
+    fn multiply(&self, x: &wgpu::Buffer, y: &wgpu::Buffer, k: usize, len: u32) {
+        let context = &self.context;
+        let (groups_x, groups_y, stride) = context.grid(len);
+        match &self.layout {
+            Layout::Csr {
+                indptr,
+                indices,
+                values,
+            } => context.run(
+                &context.csr,
+                (groups_x, groups_y),
+                [len, k as u32, stride, 0],
+                &[indptr, indices, values, x, y],
+            ),
+            Layout::Ell {
+                width,
+                indices,
+                values,
+            } => context.run(
+                &context.ell,
+                (groups_x, groups_y),
+                [len, k as u32, stride, *width],
+                &[indices, values, x, y],
+            ),
+        }
+    }
+
+    /// Compute `Y = A X` for the `k` row-major columns of `X` on the device.
+    ///
+    /// Fails with [`SparseError::TooManyEntries`] if `X` or `Y` doesn't fit in one storage
+    /// buffer of the device or has more than `u32::MAX` entries.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `x.len() != self.ncols() * k` or `y.len() != self.nrows() * k`, or if the
+    /// device is lost.
+    fn mul_dense(&self, x: &[f64], k: usize, y: &mut [f64]) -> Result<(), SparseError> {
+        assert_eq!(x.len(), self.ncols() * k, "x has the wrong length");
+        assert_eq!(y.len(), self.nrows() * k, "y has the wrong length");
+        let context = &self.context;
+        context.check_len(x.len())?;
+        let len = context.check_len(y.len())?;
+        if len == 0 {
+            return Ok(());
+        }
+        let x32: Vec<f32> = x.iter().map(|&v| v as f32).collect();
+        let x_buffer = context.storage_buffer("x", &x32);
+        let y_buffer = context.vector(y.len());
+        self.multiply(&x_buffer, &y_buffer, k, len);
+        for (yi, v) in y.iter_mut().zip(context.read(&y_buffer, len as usize)) {
+            *yi = v.into();
+        }
+        Ok(())
+    }
+
+    /// Solve `A x = b` by iterative refinement: solve for the correction with `correction` on
+    /// the device, relative to the current `f64` residual scaled to unit norm, add it to the
+    /// solution and recompute the residual in `f64` on the host, until the residual meets the
+    /// tolerance, `max_iter` iterations have been spent, or a step stops reducing it.
+    fn refine(
+        &self,
+        b: &[f64],
+        x0: Option<&[f64]>,
+        opts: &SolverOptions,
+        correction: Correction,
+    ) -> Result<SolveResult, SparseError> {
+        let mut x = initial_guess(self, b, x0)?;
+        let threshold = opts.tol * norm2(b);
+        let residual = |x: &[f64]| -> Vec<f64> {
+            let ax = self.host.mul_vec(x);
+            b.iter().zip(ax).map(|(bi, axi)| bi - axi).collect()
+        };
+        let mut r = residual(&x);
+        let mut residual_norm = norm2(&r);
+        let mut residual_history = vec![residual_norm];
+        let mut iterations = 0;
+
+        while residual_norm > threshold && iterations < opts.max_iter {
+            let scaled: Vec<f32> = r.iter().map(|&v| (v / residual_norm) as f32).collect();
+            let rhs = self.context.storage_buffer("rhs", &scaled);
+            let tol = (threshold / residual_norm).max(INNER_TOL);
+            let (d, steps) = correction(self, &rhs, tol, opts.max_iter - iterations);
+            iterations += steps;
+
+            let d = self.context.read(&d, x.len());
+            let next: Vec<f64> = x
+                .iter()
+                .zip(d)
+                .map(|(xi, di)| xi + residual_norm * f64::from(di))
+                .collect();
+            let r_next = residual(&next);
+            let next_norm = norm2(&r_next);
+            // Not even NaN-safe progress: a breakdown, or f32 can't resolve the correction.
+            if steps == 0 || next_norm.is_nan() || next_norm >= residual_norm {
+                break;
+            }
+            (x, r, residual_norm) = (next, r_next, next_norm);
+            residual_history.push(residual_norm);
+        }
+
+        Ok(SolveResult {
+            x,
+            iterations,
+            residual_norm,
+            converged: residual_norm <= threshold,
+            residual_history,
+        })
+    }
+
+    /// Run CG on the device from zero for `A d = rhs` until the recursive residual falls to
+    /// `tol` times its start, for at most `max_iter` iterations or until a breakdown. Return
+    /// the buffer of `d` and the number of iterations.
+    fn cg_correction(
+        &self,
+        rhs: &wgpu::Buffer,
+        tol: f64,
+        max_iter: usize,
+    ) -> (wgpu::Buffer, usize) {
+        let context = &self.context;
+        let n = self.nrows();
+        let [x, r, p, q] = [(); 4].map(|_| context.vector(n));
+        context.copy(rhs, &r, n);
+        context.copy(rhs, &p, n);
+        let mut rr = context.dot(&r, &r, n);
+        let threshold = tol * tol * rr;
+
+        let mut steps = 0;
+        while rr > threshold && steps < max_iter {
+            self.multiply(&p, &q, 1, n as u32);
+            let pq = context.dot(&p, &q, n);
+            if pq <= 0.0 || !pq.is_finite() {
+                // A is not positive definite along p: no further progress is possible.
+                break;
+            }
+            let alpha = rr / pq;
+            context.axpby(alpha, &p, 1.0, &x, n);
+            context.axpby(-alpha, &q, 1.0, &r, n);
+            let rr_next = context.dot(&r, &r, n);
+            context.axpby(1.0, &r, rr_next / rr, &p, n);
+            rr = rr_next;
+            steps += 1;
+        }
+        (x, steps)
+    }
+
+    /// Run BiCGSTAB on the device from zero for `A d = rhs`, as [`GpuMatrix::cg_correction`]
+    /// does CG.
+    fn bicgstab_correction(
+        &self,
+        rhs: &wgpu::Buffer,
+        tol: f64,
+        max_iter: usize,
+    ) -> (wgpu::Buffer, usize) {
+        let context = &self.context;
+        let n = self.nrows();
+        let [x, r, r_hat, p, v, s, t] = [(); 7].map(|_| context.vector(n));
+        for dst in [&r, &r_hat, &p] {
+            context.copy(rhs, dst, n);
+        }
+        let mut rho = context.dot(&r_hat, &r, n);
+        let threshold = tol * tol * rho;
+        let mut rr = rho;
+
+        let mut steps = 0;
+        while rr > threshold && steps < max_iter {
+            self.multiply(&p, &v, 1, n as u32);
+            let r_hat_v = context.dot(&r_hat, &v, n);
+            if r_hat_v == 0.0 || !r_hat_v.is_finite() {
+                break;
+            }
+            let alpha = rho / r_hat_v;
+            // s = r − α v
+            context.copy(&r, &s, n);
+            context.axpby(-alpha, &v, 1.0, &s, n);
+            context.axpby(alpha, &p, 1.0, &x, n);
+            steps += 1;
+            if context.dot(&s, &s, n) <= threshold {
+                break;
+            }
+
+            self.multiply(&s, &t, 1, n as u32);
+            let tt = context.dot(&t, &t, n);
+            if tt == 0.0 || !tt.is_finite() {
+                break;
+            }
+            let omega = context.dot(&t, &s, n) / tt;
+            context.axpby(omega, &s, 1.0, &x, n);
+            // r = s − ω t
+            context.copy(&s, &r, n);
+            context.axpby(-omega, &t, 1.0, &r, n);
+            rr = context.dot(&r, &r, n);
+
+            let rho_next = context.dot(&r_hat, &r, n);
+            if rho_next == 0.0 || omega == 0.0 {
+                break;
+            }
+            // p = r + β (p − ω v)
+            let beta = (rho_next / rho) * (alpha / omega);
+            context.axpby(-omega, &v, 1.0, &p, n);
+            context.axpby(1.0, &r, beta, &p, n);
+            rho = rho_next;
+        }
+        (x, steps)
+    }
+}
+
+impl LinearOperator for GpuMatrix {
+    fn nrows(&self) -> usize {
+        self.host.nrows()
+    }
+
+    fn ncols(&self) -> usize {
+        self.host.ncols()
+    }
+
+    /// Compute `y = A x` on the device, in `f32`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `x.len() != self.ncols()` or `y.len() != self.nrows()`, or if the device is
+    /// lost.
+    fn apply(&self, x: &[f64], y: &mut [f64]) {
+        self.mul_dense(x, 1, y)
+            .expect("the upload checked that a vector fits");
+    }
+
+    fn find_empty_rows(&self) -> Vec<usize> {
+        self.host.find_empty_rows()
+    }
+}
+
+/// Return the GPU backend, or `None` when the machine has no adapter, which skips a test.
+#[cfg(test)]
+fn test_backend() -> Option<GpuBackend> {
+    match GpuBackend::new() {
+        Ok(backend) => Some(backend),
+        Err(SparseError::Device(_)) => None,
+        Err(err) => panic!("{err}"),
+    }
+}
+
+#[test]
+fn test_gpu_checked_len() {
+    assert_eq!(checked_len(10, 400), Ok(10));
+    assert_eq!(
+        checked_len(101, 400),
+        Err(SparseError::TooManyEntries {
+            nnz: 101,
+            limit: 100
+        })
+    );
+    // The kernels index with u32, whatever the buffer size.
+    let huge = u64::MAX;
+    assert_eq!(checked_len(u32::MAX as usize, huge), Ok(u32::MAX));
+    assert_eq!(
+        checked_len(u32::MAX as usize + 1, huge),
+        Err(SparseError::TooManyEntries {
+            nnz: u32::MAX as usize + 1,
+            limit: u32::MAX as usize
+        })
+    );
+}
+
+#[test]
+fn test_gpu_spmv_spmm() {
+    use crate::test_util::{assert_close, Lcg};
+
+    let Some(gpu) = test_backend() else {
+        return;
+    };
+    let (m, n, k) = (300, 200, 5);
+    let mut rng = Lcg::new(127);
+    let a = CsrMatrix::from_dense(m, n, &rng.dense(m, n, 0.05)).unwrap();
+    let x: Vec<f64> = (0..n * k).map(|_| rng.uniform()).collect();
+    let mut expected = vec![0.0; m * k];
+    super::CpuBackend.spmm(&a, &x, k, &mut expected).unwrap();
+
+    for device_a in [
+        gpu.upload(&a).unwrap(),
+        gpu.upload_ell(&EllMatrix::from_csr(&a)).unwrap(),
+    ] {
+        assert_eq!((device_a.nrows(), device_a.ncols()), (m, n));
+        assert_eq!(device_a.host(), &a);
+
+        let mut y = vec![0.0; m];
+        gpu.spmv(&device_a, &x[..n], &mut y);
+        assert_close(&y, &a.mul_vec(&x[..n]), 1e-5);
+
+        let mut y = vec![0.0; m * k];
+        gpu.spmm(&device_a, &x, k, &mut y).unwrap();
+        assert_close(&y, &expected, 1e-5);
+    }
+
+    // Rows without entries come back as zero, in both layouts.
+    let sparse = CsrMatrix::from_dense(3, 2, &[0.0, 0.0, 1.0, 2.0, 0.0, 0.0]).unwrap();
+    for device_sparse in [
+        gpu.upload(&sparse).unwrap(),
+        gpu.upload_ell(&EllMatrix::from_csr(&sparse)).unwrap(),
+    ] {
+        let mut y = vec![f64::NAN; 3];
+        device_sparse.apply(&[1.0, 1.0], &mut y);
+        assert_eq!(y, [0.0, 3.0, 0.0]);
+        assert_eq!(device_sparse.find_empty_rows(), [0, 2]);
+    }
+
+    // Empty matrices, and an ELL matrix of width 0.
+    let empty = gpu.upload(&CsrMatrix::new(0, 4)).unwrap();
+    empty.apply(&[1.0; 4], &mut []);
+    let zero = gpu
+        .upload_ell(&EllMatrix::from_csr(&CsrMatrix::new(3, 3)))
+        .unwrap();
+    let mut y = vec![f64::NAN; 3];
+    zero.apply(&[1.0; 3], &mut y);
+    assert_eq!(y, [0.0; 3]);
+}
+
+#[test]
+fn test_gpu_cg() {
+    use crate::iterative::cg;
+    use crate::test_util::assert_close;
+
+    let Some(gpu) = test_backend() else {
+        return;
+    };
+    let a = CsrMatrix::poisson2d(16, 16);
+    let n = a.nrows();
+    let x_true: Vec<f64> = (0..n).map(|i| (i as f64 * 0.1).sin()).collect();
+    let b = a.mul_vec(&x_true);
+    let opts = SolverOptions::default();
+    let on_cpu = cg(&a, &b, None, None, &opts).unwrap();
+
+    for device_a in [
+        gpu.upload(&a).unwrap(),
+        gpu.upload_ell(&EllMatrix::from_csr(&a)).unwrap(),
+    ] {
+        // The refinement reaches the default tolerance, far below the f32 rounding error.
+        let result = gpu.cg(&device_a, &b, None, &opts).unwrap();
+        assert!(result.converged);
+        assert!(result.residual_norm <= opts.tol * norm2(&b));
+        assert!(result.residual_history.len() >= 3);
+        assert!(result.residual_history.windows(2).all(|w| w[1] < w[0]));
+        assert_close(&result.x, &on_cpu.x, 1e-8);
+        assert_close(&result.x, &x_true, 1e-8);
+
+        // Starting from the solution needs no iteration.
+        let result = gpu.cg(&device_a, &b, Some(&x_true), &opts).unwrap();
+        assert!(result.converged);
+        assert!(result.iterations <= 1);
+    }
+
+    let device_a = gpu.upload(&a).unwrap();
+    let limited = SolverOptions {
+        max_iter: 5,
+        ..opts
+    };
+    let result = gpu.cg(&device_a, &b, None, &limited).unwrap();
+    assert!(!result.converged);
+    assert!(result.iterations <= 5);
+
+    assert_eq!(
+        gpu.cg(&device_a, &b[1..], None, &opts).unwrap_err(),
+        SparseError::DimensionMismatch {
+            expected: n,
+            found: n - 1
+        }
+    );
+    let mut rhs = vec![0.0; 2];
+    rhs[1] = 1.0;
+    let empty_row = CsrMatrix::from_dense(2, 2, &[1.0, 0.0, 0.0, 0.0]).unwrap();
+    assert_eq!(
+        gpu.cg(&gpu.upload(&empty_row).unwrap(), &rhs, None, &opts)
+            .unwrap_err(),
+        SparseError::EmptyRow { index: 1 }
+    );
+
+    // Indefinite: CG breaks down and reports the guess it has.
+    let indefinite = CsrMatrix::from_dense(2, 2, &[1.0, 0.0, 0.0, -1.0]).unwrap();
+    let result = gpu
+        .cg(&gpu.upload(&indefinite).unwrap(), &[1.0, 1.0], None, &opts)
+        .unwrap();
+    assert!(!result.converged);
+}
+
+#[test]
+fn test_gpu_bicgstab() {
+    use crate::test_util::assert_close;
+
+    let Some(gpu) = test_backend() else {
+        return;
+    };
+    // Convection-diffusion: the Laplacian plus an upwinded first derivative along x.
+    let (nx, ny) = (12, 10);
+    let n = nx * ny;
+    let mut dense = CsrMatrix::poisson2d(nx, ny).to_dense();
+    for i in 0..n {
+        dense[i * n + i] += 0.5;
+        if i % nx > 0 {
+            dense[i * n + i - 1] -= 0.5;
+        }
+    }
+    let a = CsrMatrix::from_dense(n, n, &dense).unwrap();
+    let x_true: Vec<f64> = (0..n).map(|i| (i as f64 * 0.2).cos()).collect();
+    let b = a.mul_vec(&x_true);
+    let opts = SolverOptions::default();
+
+    for device_a in [
+        gpu.upload(&a).unwrap(),
+        gpu.upload_ell(&EllMatrix::from_csr(&a)).unwrap(),
+    ] {
+        let result = gpu.bicgstab(&device_a, &b, None, &opts).unwrap();
+        assert!(result.converged);
+        assert!(result.residual_norm <= opts.tol * norm2(&b));
+        assert_close(&result.x, &x_true, 1e-8);
+    }
+
+    // A zero right-hand side is solved by the zero guess.
+    let result = gpu
+        .bicgstab(&gpu.upload(&a).unwrap(), &vec![0.0; n], None, &opts)
+        .unwrap();
+    assert!(result.converged);
+    assert_eq!(result.iterations, 0);
+    assert_eq!(result.x, vec![0.0; n]);
+}
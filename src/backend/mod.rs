@@ -0,0 +1,80 @@
+//! The devices the products of the iterative solvers run on.
+//!
+//! A [`Backend`] takes a CSR or ELL matrix into its own storage once, with [`Backend::upload`]
+//! or [`Backend::upload_ell`], and hands back a [`LinearOperator`]. The solvers in
+//! [`crate::iterative`] only ever multiply through that trait, so they run unchanged on any
+//! backend: every product of an iteration is computed by the device that holds the matrix,
+//! while the vector updates stay on the host.
+//!
+//! [`CpuBackend`] keeps the matrix as a [`CsrMatrix`] in memory. With the `gpu` feature,
+//! `GpuBackend` uploads it to a GPU through wgpu, and also runs whole CG and BiCGSTAB
+//! iterations there, see the `gpu` module for their precision.
+
+pub mod cpu;
+#[cfg(feature = "gpu")]
+pub mod gpu;
+
+pub use cpu::CpuBackend;
+#[cfg(feature = "gpu")]
+pub use gpu::{GpuBackend, GpuMatrix};
+
+use crate::csr::CsrMatrix;
+use crate::ell::EllMatrix;
+use crate::error::SparseError;
+use crate::operator::LinearOperator;
+
+/// A device that stores sparse matrices and multiplies them with dense vectors.
+pub trait Backend {
+    /// A matrix held by the backend
+    type Matrix: LinearOperator;
+
+    /// Copy `a` into the storage of the backend.
+    fn upload(&self, a: &CsrMatrix<f64>) -> Result<Self::Matrix, SparseError>;
+
+    /// Copy the ELL matrix `a` into the storage of the backend. A backend without an ELL
+    /// layout of its own takes it as CSR.
+    fn upload_ell(&self, a: &EllMatrix<f64>) -> Result<Self::Matrix, SparseError> {
+        self.upload(&a.to_csr())
+    }
+
+    /// Compute `y = A x`. Same as [`LinearOperator::apply`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `x.len() != a.ncols()` or `y.len() != a.nrows()`.
+    fn spmv(&self, a: &Self::Matrix, x: &[f64], y: &mut [f64]) {
+        assert_eq!(x.len(), a.ncols(), "x has the wrong length");
+        assert_eq!(y.len(), a.nrows(), "y has the wrong length");
+        a.apply(x, y);
+    }
+
+    /// Compute `Y = A X` for the `k` columns of `X`, with `X` and `Y` dense and row-major:
+    /// `X` is `a.ncols()` by `k` and `Y` is `a.nrows()` by `k`.
+    ///
+    /// Fails with [`SparseError::TooManyEntries`] when `X` or `Y` is too large for the device.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `x.len() != a.ncols() * k` or `y.len() != a.nrows() * k`.
+    fn spmm(&self, a: &Self::Matrix, x: &[f64], k: usize, y: &mut [f64])
+        -> Result<(), SparseError>;
+}
+
+#[test]
+fn test_backend_agnostic_solve() {
+    use crate::iterative::{cg, SolverOptions};
+
+    /// Upload `a` to `backend` and solve `A x = b` there with CG.
+    fn solve_on<B: Backend>(backend: &B, a: &CsrMatrix<f64>, b: &[f64]) -> alloc::vec::Vec<f64> {
+        let device_a = backend.upload(a).unwrap();
+        let result = cg(&device_a, b, None, None, &SolverOptions::default()).unwrap();
+        assert!(result.converged);
+        result.x
+    }
+
+    let a = CsrMatrix::poisson2d(8, 6);
+    let b = alloc::vec![1.0; a.nrows()];
+    let x = solve_on(&CpuBackend, &a, &b);
+    let expected = cg(&a, &b, None, None, &SolverOptions::default()).unwrap();
+    assert_eq!(x, expected.x);
+}
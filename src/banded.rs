@@ -0,0 +1,451 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::csr::CsrMatrix;
+use crate::error::SparseError;
+use crate::scalar::Scalar;
+
+/// A square matrix with `kl` diagonals below the main one and `ku` above it, in the band storage
+/// of LAPACK.
+///
+/// The band is stored column-major in `kl + ku + 1` rows: entry `(i, j)` is at
+/// `data[j * (kl + ku + 1) + ku + i - j]`, for `j - ku <= i <= j + kl`. Slots for positions
+/// outside the matrix, in the corners, are never read. [`CsrMatrix::bandwidth`] tells whether a
+/// sparse matrix is narrow enough for this to pay off.
+#[derive(Clone, Debug)]
+pub struct BandedMatrix<T = f64> {
+    n: usize,
+    kl: usize,
+    ku: usize,
+    data: Vec<T>,
+}
+
+/// The LU factorization with partial pivoting of a [`BandedMatrix`], from [`BandedMatrix::lu`].
+///
+/// Row interchanges let `U` grow to `kl + ku` diagonals above the main one, so the factors are
+/// stored in a band of `2 kl + ku + 1` rows as in LAPACK's `gbtrf`, with the multipliers of `L`
+/// below the diagonal.
+#[derive(Clone, Debug)]
+pub struct BandedLu {
+    factors: BandedMatrix<f64>,
+    /// Row swapped with row `j` at step `j`
+    pivots: Vec<usize>,
+}
+
+impl<T: Scalar> BandedMatrix<T> {
+    /// Create an `n` by `n` zero matrix with `kl` subdiagonals and `ku` superdiagonals.
+    pub fn new(n: usize, kl: usize, ku: usize) -> Self {
+        Self {
+            n,
+            kl,
+            ku,
+            data: vec![T::zero(); n * (kl + ku + 1)],
+        }
+    }
+
+    /// Copy a square CSR matrix into band storage, with the bandwidth the matrix has. Fails with
+    /// [`SparseError::DimensionMismatch`] if `a` isn't square. O(nnz + n (kl + ku)).
+    pub fn from_csr(a: &CsrMatrix<T>) -> Result<Self, SparseError> {
+        if a.nrows() != a.ncols() {
+            return Err(SparseError::DimensionMismatch {
+                expected: a.nrows(),
+                found: a.ncols(),
+            });
+        }
+
+        let (kl, ku) = a.bandwidth();
+        let mut banded = Self::new(a.nrows(), kl, ku);
+        for i in 0..a.nrows() {
+            let (cols, values) = a.row(i);
+            for (&j, &v) in cols.iter().zip(values) {
+                banded.set(i, j, v);
+            }
+        }
+        Ok(banded)
+    }
+
+    /// Return the number of rows and columns
+    pub fn n(&self) -> usize {
+        self.n
+    }
+
+    /// Return the lower and upper bandwidth `(kl, ku)`
+    pub fn bandwidth(&self) -> (usize, usize) {
+        (self.kl, self.ku)
+    }
+
+    /// Return the number of positions in the band, which are all stored. O(n).
+    pub fn nnz(&self) -> usize {
+        (0..self.n).map(|i| self.band_cols(i).len()).sum()
+    }
+
+    fn in_band(&self, i: usize, j: usize) -> bool {
+        i + self.ku >= j && i <= j + self.kl
+    }
+
+    fn offset(&self, i: usize, j: usize) -> usize {
+        j * (self.kl + self.ku + 1) + self.ku + i - j
+    }
+
+    /// Return the entry at `(i, j)`, zero outside the band.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` or `j` is out of bounds.
+    pub fn get(&self, i: usize, j: usize) -> T {
+        self.check_bounds(i, j);
+        if self.in_band(i, j) {
+            self.data[self.offset(i, j)]
+        } else {
+            T::zero()
+        }
+    }
+
+    /// Set the entry at `(i, j)` to `v`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` or `j` is out of bounds or `(i, j)` is outside the band.
+    pub fn set(&mut self, i: usize, j: usize, v: T) {
+        self.check_bounds(i, j);
+        assert!(
+            self.in_band(i, j),
+            "({i}, {j}) is outside the band of {} subdiagonals and {} superdiagonals",
+            self.kl,
+            self.ku
+        );
+        let k = self.offset(i, j);
+        self.data[k] = v;
+    }
+
+    /// Return the columns `j` of row `i` that lie in the band.
+    fn band_cols(&self, i: usize) -> core::ops::Range<usize> {
+        i.saturating_sub(self.kl)..(i + self.ku + 1).min(self.n)
+    }
+
+    /// Multiply the matrix by the dense vector `x`. O(n (kl + ku)).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `x.len() != self.n()`.
+    pub fn mul_vec(&self, x: &[T]) -> Vec<T> {
+        assert_eq!(x.len(), self.n, "x has the wrong length");
+        (0..self.n)
+            .map(|i| {
+                let mut sum = T::zero();
+                for j in self.band_cols(i) {
+                    sum += self.data[self.offset(i, j)] * x[j];
+                }
+                sum
+            })
+            .collect()
+    }
+
+    /// Solve `L x = b` by forward substitution, where `L` is the diagonal and the subdiagonals;
+    /// the superdiagonals are ignored. O(n kl).
+    ///
+    /// Fails with [`SparseError::ZeroPivot`] when a diagonal entry is zero.
+    pub fn solve_lower_triangular(&self, b: &[T]) -> Result<Vec<T>, SparseError> {
+        self.check_rhs(b)?;
+        let mut x = b.to_vec();
+        for i in 0..self.n {
+            let first = i.saturating_sub(self.kl);
+            let mut sum = x[i];
+            for (j, &xj) in (first..).zip(&x[first..i]) {
+                sum -= self.data[self.offset(i, j)] * xj;
+            }
+            x[i] = sum / self.pivot(i)?;
+        }
+        Ok(x)
+    }
+
+    /// Solve `U x = b` by backward substitution, where `U` is the diagonal and the
+    /// superdiagonals; the subdiagonals are ignored. O(n ku).
+    ///
+    /// Fails with [`SparseError::ZeroPivot`] when a diagonal entry is zero.
+    pub fn solve_upper_triangular(&self, b: &[T]) -> Result<Vec<T>, SparseError> {
+        self.check_rhs(b)?;
+        let mut x = b.to_vec();
+        for i in (0..self.n).rev() {
+            let last = (i + self.ku + 1).min(self.n);
+            let mut sum = x[i];
+            for (j, &xj) in (i + 1..).zip(&x[i + 1..last]) {
+                sum -= self.data[self.offset(i, j)] * xj;
+            }
+            x[i] = sum / self.pivot(i)?;
+        }
+        Ok(x)
+    }
+
+    /// Convert to CSR, leaving out the zeros in the band. O(n (kl + ku)).
+    pub fn to_csr(&self) -> CsrMatrix<T> {
+        let mut indptr = Vec::with_capacity(self.n + 1);
+        let mut indices = Vec::new();
+        let mut data = Vec::new();
+        indptr.push(0);
+        for i in 0..self.n {
+            for j in self.band_cols(i) {
+                let v = self.data[self.offset(i, j)];
+                if v != T::zero() {
+                    indices.push(j);
+                    data.push(v);
+                }
+            }
+            indptr.push(indices.len());
+        }
+
+        CsrMatrix::from_parts(self.n, self.n, indptr, indices, data)
+    }
+
+    fn pivot(&self, i: usize) -> Result<T, SparseError> {
+        let d = self.data[self.offset(i, i)];
+        if d == T::zero() {
+            return Err(SparseError::ZeroPivot { index: i });
+        }
+        Ok(d)
+    }
+
+    fn check_rhs(&self, b: &[T]) -> Result<(), SparseError> {
+        if b.len() != self.n {
+            return Err(SparseError::DimensionMismatch {
+                expected: self.n,
+                found: b.len(),
+            });
+        }
+        Ok(())
+    }
+
+    fn check_bounds(&self, i: usize, j: usize) {
+        assert!(i < self.n, "row {i} out of bounds for {} rows", self.n);
+        assert!(
+            j < self.n,
+            "column {j} out of bounds for {} columns",
+            self.n
+        );
+    }
+}
+
+impl BandedMatrix<f64> {
+    /// Factor the matrix as `P A = L U` with partial pivoting, the algorithm of LAPACK's
+    /// `gbtf2`. Fails with [`SparseError::ZeroPivot`] when a whole column below the diagonal is
+    /// zero, that is when the matrix is singular. O(n kl (kl + ku)).
+    pub fn lu(&self) -> Result<BandedLu, SparseError> {
+        let (n, kl) = (self.n, self.kl);
+        let ku = kl + self.ku;
+        let mut f = BandedMatrix::new(n, kl, ku);
+        for j in 0..n {
+            for i in self.band_rows(j) {
+                f.set(i, j, self.data[self.offset(i, j)]);
+            }
+        }
+
+        let mut pivots = Vec::with_capacity(n);
+        for j in 0..n {
+            let last_row = (j + kl).min(n - 1);
+            let last_col = (j + ku).min(n - 1);
+            let p = (j..=last_row)
+                .max_by(|&a, &b| f.get(a, j).abs().total_cmp(&f.get(b, j).abs()))
+                .expect("the diagonal is in range");
+            if f.get(p, j) == 0.0 {
+                return Err(SparseError::ZeroPivot { index: j });
+            }
+            pivots.push(p);
+            if p != j {
+                for c in j..=last_col {
+                    let (a, b) = (f.offset(j, c), f.offset(p, c));
+                    f.data.swap(a, b);
+                }
+            }
+
+            let pivot = f.get(j, j);
+            for r in j + 1..=last_row {
+                let l = f.get(r, j) / pivot;
+                f.set(r, j, l);
+                if l != 0.0 {
+                    for c in j + 1..=last_col {
+                        let u = f.data[f.offset(j, c)];
+                        let k = f.offset(r, c);
+                        f.data[k] -= l * u;
+                    }
+                }
+            }
+        }
+
+        Ok(BandedLu { factors: f, pivots })
+    }
+
+    /// Return the rows `i` of column `j` that lie in the band.
+    fn band_rows(&self, j: usize) -> core::ops::Range<usize> {
+        j.saturating_sub(self.ku)..(j + self.kl + 1).min(self.n)
+    }
+}
+
+impl BandedLu {
+    /// Return the number of rows and columns
+    pub fn n(&self) -> usize {
+        self.factors.n
+    }
+
+    /// Solve `A x = b` with the factorization. Fails with [`SparseError::DimensionMismatch`]
+    /// if `b` has the wrong length. O(n (kl + ku)).
+    pub fn solve(&self, b: &[f64]) -> Result<Vec<f64>, SparseError> {
+        let f = &self.factors;
+        f.check_rhs(b)?;
+
+        // L y = P b, applying the interchanges as they happened.
+        let mut x = b.to_vec();
+        for (j, &p) in self.pivots.iter().enumerate() {
+            x.swap(j, p);
+            let xj = x[j];
+            let last = (j + f.kl + 1).min(f.n);
+            for (r, xr) in (j + 1..).zip(&mut x[j + 1..last]) {
+                *xr -= f.data[f.offset(r, j)] * xj;
+            }
+        }
+
+        // U x = y, where U has the diagonal and the superdiagonals of the factors.
+        for i in (0..f.n).rev() {
+            let last = (i + f.ku + 1).min(f.n);
+            let mut sum = x[i];
+            for (j, &xj) in (i + 1..).zip(&x[i + 1..last]) {
+                sum -= f.data[f.offset(i, j)] * xj;
+            }
+            x[i] = sum / f.data[f.offset(i, i)];
+        }
+        Ok(x)
+    }
+}
+
+#[test]
+fn test_banded_matrix() {
+    use crate::test_util::assert_close;
+
+    #[rustfmt::skip]
+    let dense = [
+        1.0, 2.0, 0.0, 0.0, 0.0,
+        3.0, 1.0, 1.0, 0.0, 0.0,
+        4.0, 2.0, 5.0, -1.0, 0.0,
+        0.0, 1.0, 1.0, 0.5, 2.0,
+        0.0, 0.0, 6.0, 1.0, 3.0,
+    ];
+    let a = CsrMatrix::from_dense(5, 5, &dense).unwrap();
+    assert_eq!(a.bandwidth(), (2, 1));
+    let banded = BandedMatrix::from_csr(&a).unwrap();
+    assert_eq!(banded.bandwidth(), (2, 1));
+    assert_eq!(banded.nnz(), 16);
+    assert_eq!(banded.get(2, 0), 4.0);
+    assert_eq!(banded.get(0, 4), 0.0);
+    assert_eq!(banded.to_csr().to_dense(), dense);
+
+    let x = [1.0, -2.0, 0.5, 3.0, -1.0];
+    let b = a.mul_vec(&x);
+    assert_eq!(banded.mul_vec(&x), b);
+
+    // The small diagonal forces row interchanges.
+    let lu = banded.lu().unwrap();
+    assert_close(&lu.solve(&b).unwrap(), &x, 1e-12);
+
+    let lower = banded.solve_lower_triangular(&b).unwrap();
+    let upper = banded.solve_upper_triangular(&b).unwrap();
+    assert_close(&lower, &a.solve_lower_triangular(&b).unwrap(), 1e-12);
+    assert_close(&upper, &a.solve_upper_triangular(&b).unwrap(), 1e-12);
+
+    let mut singular = BandedMatrix::new(3, 1, 1);
+    singular.set(0, 0, 1.0);
+    singular.set(1, 0, 1.0);
+    singular.set(2, 2, 1.0);
+    assert_eq!(
+        singular.lu().unwrap_err(),
+        SparseError::ZeroPivot { index: 1 }
+    );
+    assert_eq!(
+        lu.solve(&[1.0]),
+        Err(SparseError::DimensionMismatch {
+            expected: 5,
+            found: 1
+        })
+    );
+}
+
+#[test]
+#[should_panic(expected = "(0, 2) is outside the band")]
+fn test_banded_set_outside_band() {
+    BandedMatrix::new(3, 1, 1).set(0, 2, 1.0);
+}
+
+#[test]
+fn test_banded_matrix_one_sided_bands() {
+    use crate::test_util::assert_close;
+
+    // A lower band has no superdiagonals, so the corner slots of the first rows and the last
+    // columns are never read; the upper triangular solve only sees the diagonal.
+    let mut lower = BandedMatrix::new(4, 2, 0);
+    for i in 0..4_usize {
+        for j in i.saturating_sub(2)..=i {
+            lower.set(i, j, (1 + i + j) as f64);
+        }
+    }
+    assert_eq!(lower.nnz(), 9);
+    assert_eq!(lower.get(0, 1), 0.0);
+    assert_eq!(lower.get(3, 0), 0.0);
+    assert_eq!(lower.get(3, 1), 5.0);
+    let b = [1.0, -1.0, 2.0, 0.5];
+    let x = lower.solve_lower_triangular(&b).unwrap();
+    assert_close(&lower.mul_vec(&x), &b, 1e-12);
+    let d = lower.solve_upper_triangular(&b).unwrap();
+    assert_close(&d, &[1.0, -1.0 / 3.0, 2.0 / 5.0, 0.5 / 7.0], 1e-12);
+
+    // The transpose, an upper band, through CSR.
+    let upper = BandedMatrix::from_csr(&lower.to_csr().transpose()).unwrap();
+    assert_eq!(upper.bandwidth(), (0, 2));
+    assert_eq!(upper.get(1, 3), 5.0);
+    let x = upper.solve_upper_triangular(&b).unwrap();
+    assert_close(&upper.mul_vec(&x), &b, 1e-12);
+    assert_close(&upper.lu().unwrap().solve(&b).unwrap(), &x, 1e-12);
+}
+
+#[test]
+fn test_banded_lu_fills_the_upper_band() {
+    use crate::test_util::assert_close;
+
+    // Small diagonals make every step take its pivot from the last row of the band, so `U` gets
+    // the `kl` extra superdiagonals of the factor storage.
+    let n = 5;
+    let mut a = BandedMatrix::new(n, 2, 0);
+    for i in 0..n {
+        a.set(i, i, 0.1);
+        if i >= 2 {
+            a.set(i, i - 2, 1.0 + i as f64);
+        }
+        if i >= 1 {
+            a.set(i, i - 1, 0.5);
+        }
+    }
+    let lu = a.lu().unwrap();
+    assert_eq!(lu.pivots[..3], [2, 3, 4]);
+    assert_eq!(lu.factors.bandwidth(), (2, 2));
+    assert_ne!(lu.factors.get(0, 2), 0.0);
+
+    let b = [1.0, -2.0, 0.5, 3.0, -1.0];
+    let x = lu.solve(&b).unwrap();
+    assert_close(&a.mul_vec(&x), &b, 1e-12);
+
+    // A full band, with the zero diagonal swapped away.
+    let mut full = BandedMatrix::new(3, 2, 2);
+    for (i, j, v) in [
+        (0, 1, 1.0),
+        (0, 2, 2.0),
+        (1, 0, 3.0),
+        (2, 0, 1.0),
+        (2, 2, 4.0),
+    ] {
+        full.set(i, j, v);
+    }
+    assert_eq!(
+        full.solve_upper_triangular(&[1.0; 3]).unwrap_err(),
+        SparseError::ZeroPivot { index: 1 }
+    );
+    let b = [1.0, 2.0, 3.0];
+    let x = full.lu().unwrap().solve(&b).unwrap();
+    assert_close(&full.mul_vec(&x), &b, 1e-12);
+}
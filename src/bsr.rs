@@ -0,0 +1,270 @@
+use alloc::format;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::csr::CsrMatrix;
+use crate::error::SparseError;
+use crate::scalar::Scalar;
+
+/// A sparse matrix in block sparse row (BSR) form: a CSR matrix whose entries are dense `r` by
+/// `c` blocks.
+///
+/// Matrices from vector-valued PDEs or multi-DOF finite elements come in small dense blocks, one
+/// per pair of coupled nodes. Storing one column index per block instead of per entry shrinks
+/// the index arrays by a factor `r c`, and the product runs dense `r` by `c` kernels with no
+/// indirection inside a block. Block `k` holds its values row-major in
+/// `data[k * r * c..(k + 1) * r * c]`; zeros inside a stored block are stored too.
+#[derive(Clone, Debug)]
+pub struct BsrMatrix<T = f64> {
+    nrows: usize,
+    ncols: usize,
+    /// Rows per block
+    r: usize,
+    /// Columns per block
+    c: usize,
+    /// Start of each block row in `indices`, plus the number of blocks at the end
+    indptr: Vec<usize>,
+    /// Block column of each stored block
+    indices: Vec<usize>,
+    /// Values of the blocks, `r * c` per block
+    data: Vec<T>,
+}
+
+impl<T: Scalar> BsrMatrix<T> {
+    /// Split a CSR matrix into `r` by `c` blocks, storing every block that holds at least one
+    /// entry. The dimensions must be multiples of the block dimensions. O(nnz + nrows + ncols / c)
+    /// plus the size of the stored blocks.
+    pub fn from_csr(a: &CsrMatrix<T>, r: usize, c: usize) -> Result<Self, SparseError> {
+        if r == 0 || c == 0 || !a.nrows().is_multiple_of(r) || !a.ncols().is_multiple_of(c) {
+            return Err(SparseError::InvalidStructure(format!(
+                "a {}x{} matrix can't be split into {r}x{c} blocks",
+                a.nrows(),
+                a.ncols()
+            )));
+        }
+
+        let n_block_rows = a.nrows() / r;
+        let n_block_cols = a.ncols() / c;
+        let block_size = r * c;
+        let mut indptr = Vec::with_capacity(n_block_rows + 1);
+        let mut indices = Vec::new();
+        let mut data = Vec::new();
+        indptr.push(0);
+
+        // `slot[jb]` is the position of block column jb in the current block row, if stored.
+        let mut slot = vec![usize::MAX; n_block_cols];
+        for ib in 0..n_block_rows {
+            let start = indices.len();
+            let rows = ib * r..(ib + 1) * r;
+            for i in rows.clone() {
+                for &j in a.row(i).0 {
+                    let jb = j / c;
+                    if slot[jb] == usize::MAX {
+                        slot[jb] = 0;
+                        indices.push(jb);
+                    }
+                }
+            }
+            indices[start..].sort_unstable();
+            for (k, &jb) in indices[start..].iter().enumerate() {
+                slot[jb] = start + k;
+            }
+
+            data.resize(indices.len() * block_size, T::zero());
+            for i in rows {
+                let (cols, values) = a.row(i);
+                for (&j, &v) in cols.iter().zip(values) {
+                    let offset = slot[j / c] * block_size + (i % r) * c + j % c;
+                    data[offset] = v;
+                }
+            }
+
+            for &jb in &indices[start..] {
+                slot[jb] = usize::MAX;
+            }
+            indptr.push(indices.len());
+        }
+
+        Ok(Self {
+            nrows: a.nrows(),
+            ncols: a.ncols(),
+            r,
+            c,
+            indptr,
+            indices,
+            data,
+        })
+    }
+
+    /// Return the number of rows
+    pub fn nrows(&self) -> usize {
+        self.nrows
+    }
+
+    /// Return the number of columns
+    pub fn ncols(&self) -> usize {
+        self.ncols
+    }
+
+    /// Return `(nrows, ncols)`
+    pub fn shape(&self) -> (usize, usize) {
+        (self.nrows, self.ncols)
+    }
+
+    /// Return the block dimensions `(r, c)`
+    pub fn block_shape(&self) -> (usize, usize) {
+        (self.r, self.c)
+    }
+
+    /// Return the number of stored blocks
+    pub fn nblocks(&self) -> usize {
+        self.indices.len()
+    }
+
+    /// Return the number of stored values, `r * c` per block, zeros inside the blocks included
+    pub fn nnz(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Multiply the matrix by the dense vector `x`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `x.len() != self.ncols()`.
+    pub fn mul_vec(&self, x: &[T]) -> Vec<T> {
+        assert_eq!(x.len(), self.ncols, "x has the wrong length");
+
+        let (r, c) = (self.r, self.c);
+        let mut y = vec![T::zero(); self.nrows];
+        for (ib, y_block) in y.chunks_mut(r).enumerate() {
+            for k in self.indptr[ib]..self.indptr[ib + 1] {
+                let x_block = &x[self.indices[k] * c..][..c];
+                let block = &self.data[k * r * c..][..r * c];
+                for (yi, block_row) in y_block.iter_mut().zip(block.chunks(c)) {
+                    let mut sum = T::zero();
+                    for (&a, &xj) in block_row.iter().zip(x_block) {
+                        sum += a * xj;
+                    }
+                    *yi += sum;
+                }
+            }
+        }
+        y
+    }
+
+    /// Convert to CSR, leaving out the zeros stored inside the blocks. O(nnz + nrows).
+    pub fn to_csr(&self) -> CsrMatrix<T> {
+        let (r, c) = (self.r, self.c);
+        let mut indptr = Vec::with_capacity(self.nrows + 1);
+        let mut indices = Vec::new();
+        let mut data = Vec::new();
+        indptr.push(0);
+        for i in 0..self.nrows {
+            let ib = i / r;
+            for k in self.indptr[ib]..self.indptr[ib + 1] {
+                let block_row = &self.data[k * r * c + (i % r) * c..][..c];
+                for (jj, &v) in block_row.iter().enumerate() {
+                    if v != T::zero() {
+                        indices.push(self.indices[k] * c + jj);
+                        data.push(v);
+                    }
+                }
+            }
+            indptr.push(indices.len());
+        }
+
+        CsrMatrix::from_parts(self.nrows, self.ncols, indptr, indices, data)
+    }
+}
+
+#[test]
+fn test_bsr_matrix() {
+    use crate::test_util::{assert_close, Lcg};
+
+    #[rustfmt::skip]
+    let dense = [
+        1.0, 2.0, 0.0, 0.0, 0.0, 3.0,
+        0.0, 4.0, 0.0, 0.0, 0.0, 0.0,
+        0.0, 0.0, 0.0, 0.0, 5.0, 6.0,
+        0.0, 0.0, 0.0, 0.0, 7.0, 8.0,
+    ];
+    let a = CsrMatrix::from_dense(4, 6, &dense).unwrap();
+    let bsr = BsrMatrix::from_csr(&a, 2, 2).unwrap();
+    assert_eq!(bsr.block_shape(), (2, 2));
+    assert_eq!(bsr.nblocks(), 3);
+    assert_eq!(bsr.indptr, [0, 2, 3]);
+    assert_eq!(bsr.indices, [0, 2, 2]);
+    assert_eq!(
+        bsr.data,
+        [1.0, 2.0, 0.0, 4.0, 0.0, 3.0, 0.0, 0.0, 5.0, 6.0, 7.0, 8.0]
+    );
+    assert_eq!(bsr.to_csr().to_dense(), dense);
+
+    let x = [1.0, -1.0, 2.0, 0.5, 3.0, -2.0];
+    assert_eq!(bsr.mul_vec(&x), a.mul_vec(&x));
+
+    // Blocks of 3 x 2 on a random matrix.
+    let mut rng = Lcg::new(5);
+    let dense = rng.dense(12, 10, 0.2);
+    let a = CsrMatrix::from_dense(12, 10, &dense).unwrap();
+    let bsr = BsrMatrix::from_csr(&a, 3, 2).unwrap();
+    assert_eq!(bsr.to_csr().to_dense(), dense);
+    let x: Vec<f64> = (0..10).map(|_| rng.uniform()).collect();
+    assert_close(&bsr.mul_vec(&x), &a.mul_vec(&x), 1e-12);
+
+    assert!(matches!(
+        BsrMatrix::from_csr(&a, 5, 2),
+        Err(SparseError::InvalidStructure(_))
+    ));
+}
+
+#[test]
+fn test_bsr_matrix_partial_blocks() {
+    let split_error = |shape: &str, r: usize, c: usize| {
+        SparseError::InvalidStructure(format!(
+            "a {shape} matrix can't be split into {r}x{c} blocks"
+        ))
+    };
+
+    // A trailing block row or column that would be cut short is rejected, even when the rows
+    // and columns it would cover are empty.
+    for (nrows, ncols) in [(5, 6), (4, 7), (5, 7), (1, 6), (4, 2)] {
+        let mut dense = vec![0.0; nrows * ncols];
+        dense[0] = 1.0;
+        let a = CsrMatrix::from_dense(nrows, ncols, &dense).unwrap();
+        assert_eq!(
+            BsrMatrix::from_csr(&a, 2, 3).unwrap_err(),
+            split_error(&format!("{nrows}x{ncols}"), 2, 3)
+        );
+    }
+    let a = CsrMatrix::<f64>::new(4, 6);
+    for (r, c) in [(0, 3), (2, 0)] {
+        assert_eq!(
+            BsrMatrix::from_csr(&a, r, c).unwrap_err(),
+            split_error("4x6", r, c)
+        );
+    }
+
+    // The last block row and column, when they fit exactly: only the bottom-right block is
+    // stored, and the block rows before it are empty.
+    #[rustfmt::skip]
+    let dense = [
+        0.0, 0.0, 0.0, 0.0, 0.0, 0.0,
+        0.0, 0.0, 0.0, 0.0, 0.0, 0.0,
+        0.0, 0.0, 0.0, 0.0, 0.0, 1.0,
+        0.0, 0.0, 0.0, 2.0, 0.0, 3.0,
+    ];
+    let a = CsrMatrix::from_dense(4, 6, &dense).unwrap();
+    let bsr = BsrMatrix::from_csr(&a, 2, 3).unwrap();
+    assert_eq!(bsr.indptr, [0, 0, 1]);
+    assert_eq!(bsr.indices, [1]);
+    assert_eq!(bsr.data, [0.0, 0.0, 1.0, 2.0, 0.0, 3.0]);
+    assert_eq!(bsr.to_csr().to_dense(), dense);
+    let x = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+    assert_eq!(bsr.mul_vec(&x), [0.0, 0.0, 6.0, 26.0]);
+
+    // A single block as large as the matrix.
+    let whole = BsrMatrix::from_csr(&a, 4, 6).unwrap();
+    assert_eq!((whole.nblocks(), whole.nnz()), (1, 24));
+    assert_eq!(whole.mul_vec(&x), a.mul_vec(&x));
+}
@@ -0,0 +1,280 @@
+//! Matrices from the SuiteSparse Matrix Collection (<https://sparse.tamu.edu>), fetched by
+//! name or number and cached on disk, behind the `collection` feature.
+//!
+//! ```no_run
+//! use sparse_matrix::collection::Collection;
+//!
+//! let collection = Collection::new("target/suitesparse");
+//! let (a, info) = collection.fetch("HB/bcsstk01").unwrap();
+//! assert_eq!(a.shape(), (info.nrows, info.ncols));
+//! ```
+//!
+//! The index of the collection, `ssstats.csv`, and the Matrix Market archive of every matrix
+//! fetched are kept under the cache directory, in the same layout as on the server, and never
+//! downloaded again: delete `ssstats.csv` to pick up matrices added since. Only real and
+//! pattern matrices can be read, as the crate has no complex Matrix Market reader.
+
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+use flate2::read::GzDecoder;
+
+use crate::csr::CsrMatrix;
+use crate::error::SparseError;
+use crate::io::matrix_market;
+
+/// The address of the collection.
+pub const DEFAULT_URL: &str = "https://sparse.tamu.edu";
+
+/// The metadata of a matrix, from the index of the collection.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MatrixInfo {
+    /// The number of the matrix in the collection, from 1
+    pub id: usize,
+    /// The group, usually the source, such as `HB` for Harwell–Boeing
+    pub group: String,
+    /// The name of the matrix within its group, such as `bcsstk01`
+    pub name: String,
+    /// The number of rows
+    pub nrows: usize,
+    /// The number of columns
+    pub ncols: usize,
+    /// The number of entries stored in the file, before symmetric halves are mirrored
+    pub nnz: usize,
+    /// Whether the values are real, as opposed to complex
+    pub real: bool,
+    /// Whether every value is 0 or 1, as in a pattern
+    pub binary: bool,
+    /// Whether the matrix is symmetric positive definite
+    pub positive_definite: bool,
+    /// The fraction of the off-diagonal entries whose mirror entry is present, from 0 to 1
+    pub pattern_symmetry: f64,
+    /// The fraction of the off-diagonal entries whose mirror entry has the same value
+    pub numerical_symmetry: f64,
+    /// The problem the matrix comes from, such as `structural problem`
+    pub kind: String,
+}
+
+/// A local cache of the SuiteSparse Matrix Collection.
+#[derive(Clone, Debug)]
+pub struct Collection {
+    cache_dir: PathBuf,
+    base_url: String,
+}
+
+impl Collection {
+    /// Use `cache_dir` to keep the downloaded files, creating it when needed.
+    pub fn new(cache_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            cache_dir: cache_dir.into(),
+            base_url: DEFAULT_URL.to_string(),
+        }
+    }
+
+    /// Download from a mirror of the collection rather than [`DEFAULT_URL`].
+    pub fn with_base_url(mut self, url: impl Into<String>) -> Self {
+        self.base_url = url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Return the metadata of every matrix in the collection, downloading the index the first
+    /// time.
+    pub fn index(&self) -> Result<Vec<MatrixInfo>, SparseError> {
+        let path = self.cached("files/ssstats.csv", "ssstats.csv")?;
+        parse_index(BufReader::new(File::open(path)?))
+    }
+
+    /// Look a matrix up by `group/name`, by name alone, or by number.
+    ///
+    /// Fails with [`SparseError::NotFound`] when no matrix matches, or when a name alone
+    /// matches matrices in several groups.
+    pub fn find(&self, key: &str) -> Result<MatrixInfo, SparseError> {
+        let index = self.index()?;
+        let matches: Vec<&MatrixInfo> = match key.parse::<usize>() {
+            Ok(id) => index.iter().filter(|m| m.id == id).collect(),
+            Err(_) => match key.split_once('/') {
+                Some((group, name)) => index
+                    .iter()
+                    .filter(|m| m.group == group && m.name == name)
+                    .collect(),
+                None => index.iter().filter(|m| m.name == key).collect(),
+            },
+        };
+        match matches[..] {
+            [info] => Ok(info.clone()),
+            [] => Err(SparseError::NotFound(format!(
+                "no matrix {key} in the collection"
+            ))),
+            _ => Err(SparseError::NotFound(format!(
+                "{key} names matrices in several groups, qualify it as group/name"
+            ))),
+        }
+    }
+
+    /// Return the matrix `key`, looked up as by [`Collection::find`], with its metadata,
+    /// downloading it the first time. Symmetric matrices come back with both halves.
+    ///
+    /// Fails with [`SparseError::InvalidStructure`] for complex matrices.
+    pub fn fetch(&self, key: &str) -> Result<(CsrMatrix<f64>, MatrixInfo), SparseError> {
+        let info = self.find(key)?;
+        if !info.real {
+            return Err(SparseError::InvalidStructure(format!(
+                "{}/{} is complex",
+                info.group, info.name
+            )));
+        }
+
+        let archive = format!("MM/{}/{}.tar.gz", info.group, info.name);
+        let path = self.cached(&archive, &archive)?;
+        // The archive holds `name/name.mtx`, next to right-hand sides and other extras.
+        let wanted = Path::new(&info.name).join(format!("{}.mtx", info.name));
+        let mut tar = tar::Archive::new(GzDecoder::new(File::open(path)?));
+        for entry in tar.entries()? {
+            let entry = entry?;
+            if entry.path()? == wanted {
+                let a = matrix_market::read(BufReader::new(entry))?
+                    .into_coo()
+                    .to_csr();
+                return Ok((a, info));
+            }
+        }
+        Err(SparseError::NotFound(format!(
+            "{} in {archive}",
+            wanted.display()
+        )))
+    }
+
+    /// Return the path of the cached copy of `url_path`, stored as `file` under the cache
+    /// directory, downloading it first when missing. Downloads go to a `.part` file renamed
+    /// once complete, so an interrupted one is never mistaken for a cached file.
+    fn cached(&self, url_path: &str, file: &str) -> Result<PathBuf, SparseError> {
+        let path = self.cache_dir.join(file);
+        if path.exists() {
+            return Ok(path);
+        }
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let url = format!("{}/{url_path}", self.base_url);
+        let response = ureq::get(&url)
+            .call()
+            .map_err(|err| SparseError::Io(format!("{url}: {err}")))?;
+        let part = path.with_extension("part");
+        io::copy(
+            &mut response.into_body().into_reader(),
+            &mut File::create(&part)?,
+        )?;
+        fs::rename(&part, &path)?;
+        Ok(path)
+    }
+}
+
+/// Parse `ssstats.csv`: the number of matrices, the date of the index, then one line per
+/// matrix in order of number: `group,name,nrows,ncols,nnz,real,binary,nd,posdef,
+/// pattern_symmetry,numerical_symmetry,kind,...`.
+fn parse_index(reader: impl BufRead) -> Result<Vec<MatrixInfo>, SparseError> {
+    let mut index = Vec::new();
+    for (k, line) in reader.lines().enumerate().skip(2) {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let error = |reason: String| SparseError::Parse {
+            line: k + 1,
+            reason,
+        };
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        if fields.len() < 12 {
+            return Err(error(format!("expected 12 fields, found {}", fields.len())));
+        }
+        let number = |f: usize| {
+            fields[f]
+                .parse::<usize>()
+                .map_err(|_| error(format!("expected a number, found {}", fields[f])))
+        };
+        let fraction = |f: usize| {
+            fields[f]
+                .parse::<f64>()
+                .map_err(|_| error(format!("expected a fraction, found {}", fields[f])))
+        };
+        index.push(MatrixInfo {
+            id: index.len() + 1,
+            group: fields[0].to_string(),
+            name: fields[1].to_string(),
+            nrows: number(2)?,
+            ncols: number(3)?,
+            nnz: number(4)?,
+            real: number(5)? == 1,
+            binary: number(6)? == 1,
+            positive_definite: number(8)? == 1,
+            pattern_symmetry: fraction(9)?,
+            numerical_symmetry: fraction(10)?,
+            kind: fields[11].to_string(),
+        });
+    }
+    Ok(index)
+}
+
+#[test]
+fn test_collection_from_cache() {
+    use std::io::Write;
+
+    use flate2::write::GzEncoder;
+
+    // A cache filled beforehand, and a mirror nothing listens on: nothing is downloaded.
+    let dir = std::env::temp_dir().join(format!("sparse-matrix-collection-{}", std::process::id()));
+    fs::create_dir_all(dir.join("MM/Test")).unwrap();
+    fs::write(
+        dir.join("ssstats.csv"),
+        "3\n01-Jan-2024 00:00:00\n\
+         Test,tiny,2,2,2,1,0,1,1,1,1,test problem,3\n\
+         Test,cplx,2,2,2,0,0,1,0,1,0,test problem,3\n\
+         Other,tiny,1,1,1,1,1,1,1,1,1,test problem,1\n",
+    )
+    .unwrap();
+    let mtx = b"%%MatrixMarket matrix coordinate real symmetric\n2 2 2\n1 1 4.0\n2 1 -1.0\n";
+    let mut tar = tar::Builder::new(GzEncoder::new(
+        File::create(dir.join("MM/Test/tiny.tar.gz")).unwrap(),
+        flate2::Compression::default(),
+    ));
+    for (name, body) in [
+        ("tiny/tiny_b.mtx", &b"not read"[..]),
+        ("tiny/tiny.mtx", mtx),
+    ] {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(body.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        tar.append_data(&mut header, name, body).unwrap();
+    }
+    tar.into_inner().unwrap().finish().unwrap().flush().unwrap();
+
+    let collection = Collection::new(&dir).with_base_url("http://127.0.0.1:9/");
+    assert_eq!(collection.index().unwrap().len(), 3);
+    assert_eq!(collection.find("2").unwrap().name, "cplx");
+    assert_eq!(collection.find("Other/tiny").unwrap().id, 3);
+    assert!(matches!(
+        collection.find("tiny"),
+        Err(SparseError::NotFound(_))
+    ));
+    assert!(matches!(
+        collection.find("missing"),
+        Err(SparseError::NotFound(_))
+    ));
+
+    let (a, info) = collection.fetch("Test/tiny").unwrap();
+    assert_eq!(info.kind, "test problem");
+    assert!(info.positive_definite);
+    assert_eq!(a.to_dense(), [4.0, -1.0, -1.0, 0.0]);
+    assert!(matches!(
+        collection.fetch("cplx"),
+        Err(SparseError::InvalidStructure(_))
+    ));
+    // Not cached, and the mirror can't be reached.
+    assert!(matches!(
+        collection.fetch("Other/tiny"),
+        Err(SparseError::Io(_))
+    ));
+    fs::remove_dir_all(&dir).unwrap();
+}
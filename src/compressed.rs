@@ -0,0 +1,292 @@
+//! Kernels shared by the compressed row and compressed column formats. Both store a sequence of
+//! packed vectors (the rows of a CSR matrix, the columns of a CSC matrix), called the outer
+//! dimension here, each holding sorted indices into the inner dimension.
+
+use alloc::format;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::convert::Infallible;
+
+use crate::error::SparseError;
+use crate::index::IndexType;
+use crate::ops::merge::{self, UnionStep};
+use crate::scalar::Scalar;
+
+/// Check that the raw arrays of a compressed matrix with `n_outer` packed vectors of length
+/// `n_inner` are consistent. `outer` names the outer dimension ("row" or "column") in errors.
+pub(crate) fn validate_compressed<I: IndexType>(
+    n_outer: usize,
+    n_inner: usize,
+    indptr: &[I],
+    indices: &[I],
+    data_len: usize,
+    outer: &str,
+) -> Result<(), SparseError> {
+    if indptr.len() != n_outer + 1 {
+        return Err(SparseError::DimensionMismatch {
+            expected: n_outer + 1,
+            found: indptr.len(),
+        });
+    }
+    if indices.len() != data_len {
+        return Err(SparseError::DimensionMismatch {
+            expected: indices.len(),
+            found: data_len,
+        });
+    }
+    if indptr[0].index() != 0 || indptr[n_outer].index() != indices.len() {
+        return Err(SparseError::InvalidStructure(format!(
+            "indptr must run from 0 to {}, found {:?}..{:?}",
+            indices.len(),
+            indptr[0],
+            indptr[n_outer]
+        )));
+    }
+
+    for k in 0..n_outer {
+        if indptr[k] > indptr[k + 1] {
+            return Err(SparseError::InvalidStructure(format!(
+                "indptr decreases at {outer} {k}"
+            )));
+        }
+
+        let inner = &indices[indptr[k].index()..indptr[k + 1].index()];
+        if let Some(&i) = inner.iter().find(|&&i| i.index() >= n_inner) {
+            return Err(SparseError::IndexOutOfBounds {
+                index: i.index(),
+                len: n_inner,
+            });
+        }
+        if inner.windows(2).any(|w| w[0] >= w[1]) {
+            return Err(SparseError::InvalidStructure(format!(
+                "indices of {outer} {k} are not strictly increasing"
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// Swap the outer and inner dimensions of a compressed matrix whose inner dimension has length
+/// `n_inner`: the rows of a CSR matrix become the rows of a CSC matrix, and the other way round.
+///
+/// The entries are first counted per inner index to build the new pointer array, then placed
+/// by scanning the outer vectors in order, so the new indices come out sorted. O(nnz + n_inner).
+pub(crate) fn transpose_compressed<T: Scalar>(
+    n_inner: usize,
+    indptr: &[usize],
+    indices: &[usize],
+    data: &[T],
+) -> (Vec<usize>, Vec<usize>, Vec<T>) {
+    let mut t_indptr = vec![0; n_inner + 1];
+    for &i in indices {
+        t_indptr[i + 1] += 1;
+    }
+    for i in 0..n_inner {
+        t_indptr[i + 1] += t_indptr[i];
+    }
+
+    let mut next = t_indptr[..n_inner].to_vec();
+    let mut t_indices = vec![0; indices.len()];
+    let mut t_data = vec![T::zero(); data.len()];
+
+    for k in 0..indptr.len() - 1 {
+        for p in indptr[k]..indptr[k + 1] {
+            let i = indices[p];
+            let q = next[i];
+            t_indices[q] = k;
+            t_data[q] = data[p];
+            next[i] += 1;
+        }
+    }
+
+    (t_indptr, t_indices, t_data)
+}
+
+/// The `(indptr, indices, data)` arrays of a compressed matrix
+pub(crate) type CompressedParts<T> = (Vec<usize>, Vec<usize>, Vec<T>);
+
+/// Compress unordered `(outer, inner, value)` triplets into `n_outer` packed vectors of length
+/// `n_inner`, summing the values of duplicated positions.
+///
+/// Two stable counting sorts do the work: the triplets are bucketed by inner index first, and
+/// [`transpose_compressed`] then buckets those by outer index, which leaves the inner indices of
+/// every outer vector sorted with duplicates next to each other in insertion order. A last pass
+/// folds the duplicates. O(nnz + n_outer + n_inner).
+pub(crate) fn compress_triplets<T: Scalar>(
+    n_outer: usize,
+    n_inner: usize,
+    outer: &[usize],
+    inner: &[usize],
+    values: &[T],
+) -> (Vec<usize>, Vec<usize>, Vec<T>) {
+    let folded = compress_triplets_with(n_outer, n_inner, outer, inner, values, |_, _, acc, v| {
+        *acc += v;
+        Ok::<(), Infallible>(())
+    });
+    match folded {
+        Ok(parts) => parts,
+        Err(never) => match never {},
+    }
+}
+
+/// [`compress_triplets`] folding each duplicate `v` of position `(outer, inner)` into the value
+/// `acc` kept so far with `combine(outer, inner, acc, v)`, in insertion order, and stopping at
+/// the first error it returns.
+pub(crate) fn compress_triplets_with<T: Scalar, E>(
+    n_outer: usize,
+    n_inner: usize,
+    outer: &[usize],
+    inner: &[usize],
+    values: &[T],
+    mut combine: impl FnMut(usize, usize, &mut T, T) -> Result<(), E>,
+) -> Result<CompressedParts<T>, E> {
+    let mut by_inner_ptr = vec![0; n_inner + 1];
+    for &i in inner {
+        by_inner_ptr[i + 1] += 1;
+    }
+    for i in 0..n_inner {
+        by_inner_ptr[i + 1] += by_inner_ptr[i];
+    }
+
+    let mut next = by_inner_ptr[..n_inner].to_vec();
+    let mut by_inner_outer = vec![0; outer.len()];
+    let mut by_inner_values = vec![T::zero(); values.len()];
+    for ((&o, &i), &v) in outer.iter().zip(inner).zip(values) {
+        by_inner_outer[next[i]] = o;
+        by_inner_values[next[i]] = v;
+        next[i] += 1;
+    }
+
+    let (indptr, indices, data) =
+        transpose_compressed(n_outer, &by_inner_ptr, &by_inner_outer, &by_inner_values);
+
+    let mut summed_ptr = Vec::with_capacity(n_outer + 1);
+    let mut summed_indices: Vec<usize> = Vec::with_capacity(indices.len());
+    let mut summed_data: Vec<T> = Vec::with_capacity(data.len());
+    summed_ptr.push(0);
+    for k in 0..n_outer {
+        let start = summed_indices.len();
+        for p in indptr[k]..indptr[k + 1] {
+            if summed_indices.len() > start && summed_indices.last() == Some(&indices[p]) {
+                combine(k, indices[p], summed_data.last_mut().unwrap(), data[p])?;
+            } else {
+                summed_indices.push(indices[p]);
+                summed_data.push(data[p]);
+            }
+        }
+        summed_ptr.push(summed_indices.len());
+    }
+
+    Ok((summed_ptr, summed_indices, summed_data))
+}
+
+/// Return the outer positions whose packed vector is empty, in increasing order. O(n_outer).
+pub(crate) fn empty_outer(indptr: &[usize]) -> Vec<usize> {
+    (0..indptr.len() - 1)
+        .filter(|&k| indptr[k] == indptr[k + 1])
+        .collect()
+}
+
+/// Return the inner positions no packed vector holds, in increasing order. O(nnz + n_inner).
+pub(crate) fn empty_inner(n_inner: usize, indices: &[usize]) -> Vec<usize> {
+    let mut seen = vec![false; n_inner];
+    for &i in indices {
+        seen[i] = true;
+    }
+    (0..n_inner).filter(|&i| !seen[i]).collect()
+}
+
+/// Drop the entries whose value fails `keep`, compacting the arrays of a compressed matrix in
+/// place and releasing the storage that frees up. O(nnz + n_outer).
+pub(crate) fn retain_compressed<T: Scalar>(
+    indptr: &mut [usize],
+    indices: &mut Vec<usize>,
+    data: &mut Vec<T>,
+    keep: impl Fn(T) -> bool,
+) {
+    let mut kept = 0;
+    let mut start = indptr[0];
+    for k in 0..indptr.len() - 1 {
+        let end = indptr[k + 1];
+        for p in start..end {
+            if keep(data[p]) {
+                indices[kept] = indices[p];
+                data[kept] = data[p];
+                kept += 1;
+            }
+        }
+        start = end;
+        indptr[k + 1] = kept;
+    }
+
+    indices.truncate(kept);
+    data.truncate(kept);
+    indices.shrink_to_fit();
+    data.shrink_to_fit();
+}
+
+/// Walk the union of the patterns of two compressed matrices of the same dimensions, outer
+/// vector by outer vector, calling `f` with the two values at each position. A position stored
+/// in only one of them passes 0.0 for the other. Stop and return false as soon as `f` does.
+/// O(nnz of both + n_outer).
+pub(crate) fn all_union_compressed<T: Scalar>(
+    (a_indptr, a_indices, a_data): (&[usize], &[usize], &[T]),
+    (b_indptr, b_indices, b_data): (&[usize], &[usize], &[T]),
+    mut f: impl FnMut(T, T) -> bool,
+) -> bool {
+    (0..a_indptr.len() - 1).all(|k| {
+        let (a_start, b_start) = (a_indptr[k], b_indptr[k]);
+        let a_values = &a_data[a_start..a_indptr[k + 1]];
+        let b_values = &b_data[b_start..b_indptr[k + 1]];
+        merge::all_union(
+            &a_indices[a_start..a_indptr[k + 1]],
+            &b_indices[b_start..b_indptr[k + 1]],
+            |step| match step {
+                UnionStep::Left(run) => a_values[run].iter().all(|&x| f(x, T::zero())),
+                UnionStep::Right(run) => b_values[run].iter().all(|&y| f(T::zero(), y)),
+                UnionStep::Both(p, q) => f(a_values[p], b_values[q]),
+            },
+        )
+    })
+}
+
+/// Return the largest absolute sum of the values of an outer vector, 0.0 when there is none and
+/// NaN when any value is NaN. O(nnz + n_outer).
+pub(crate) fn max_outer_abs_sum(indptr: &[usize], data: &[f64]) -> f64 {
+    nan_max(
+        indptr
+            .windows(2)
+            .map(|w| data[w[0]..w[1]].iter().map(|v| v.abs()).sum()),
+    )
+}
+
+/// Return the largest absolute sum of the values sharing an inner index, 0.0 when there is none
+/// and NaN when any value is NaN. O(nnz + n_inner).
+pub(crate) fn max_inner_abs_sum(n_inner: usize, indices: &[usize], data: &[f64]) -> f64 {
+    let mut sums = vec![0.0; n_inner];
+    for (&i, v) in indices.iter().zip(data) {
+        sums[i] += v.abs();
+    }
+    nan_max(sums)
+}
+
+/// Return the square root of the sum of the squared values, the Frobenius norm.
+pub(crate) fn frobenius(data: &[f64]) -> f64 {
+    crate::dense::sqrt(data.iter().map(|v| v * v).sum())
+}
+
+/// The maximum of non-negative values, 0.0 for none, NaN-propagating like
+/// [`crate::vec::PackedVec::norm_inf`].
+fn nan_max(values: impl IntoIterator<Item = f64>) -> f64 {
+    values.into_iter().fold(
+        0.0,
+        |max: f64, v| {
+            if v.is_nan() || v > max {
+                v
+            } else {
+                max
+            }
+        },
+    )
+}
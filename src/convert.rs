@@ -0,0 +1,193 @@
+//! `From` and `TryFrom` conversions between the storage formats, so that a matrix can be
+//! assembled in one format and handed to code that computes with another:
+//!
+//! ```
+//! use sparse_matrix::csr::CsrMatrix;
+//! use sparse_matrix::dok::DokMatrix;
+//! use sparse_matrix::ell::EllMatrix;
+//! use sparse_matrix::lil::LilMatrix;
+//!
+//! let mut dok = DokMatrix::new(2, 2);
+//! dok.insert(0, 1, 3.0);
+//! let csr = CsrMatrix::from(&dok);
+//! let ell = EllMatrix::from(&LilMatrix::from(&csr));
+//! assert_eq!(ell.mul_vec(&[1.0, 2.0]), [6.0, 0.0]);
+//! ```
+//!
+//! Every conversion goes through CSR, directly or in two steps, and costs O(nnz + nrows +
+//! ncols) plus the size of the target where that is padded, except for these:
+//!
+//! - DOK to COO sorts the entries: O(nnz log nnz).
+//! - ELL and DIA store padding: O(nrows × width) and O(nrows × number of diagonals).
+//! - Conversions to DIA fail with [`SparseError::InvalidStructure`] when the diagonals would
+//!   hold more than twice as many values as the matrix has entries; call
+//!   [`DiaMatrix::from_csr`] to pick the limit.
+//! - There is no conversion to BSR, which needs a block size: call [`BsrMatrix::from_csr`].
+//!
+//! Conversions to formats that never store zeros (DOK, LIL) drop explicit zeros, and
+//! conversions from BSR and DIA drop the zeros stored inside the blocks or on the diagonals.
+
+use crate::bsr::BsrMatrix;
+use crate::coo::CooMatrix;
+use crate::csc::CscMatrix;
+use crate::csr::CsrMatrix;
+use crate::dia::DiaMatrix;
+use crate::dok::DokMatrix;
+use crate::ell::EllMatrix;
+use crate::error::SparseError;
+use crate::hyb::HybMatrix;
+use crate::lil::LilMatrix;
+use crate::scalar::Scalar;
+
+/// Implement `From<&$from<T>> for $to<T>` with the given method of the source.
+macro_rules! impl_from_method {
+    ($($from:ident => $to:ident: $method:ident;)*) => {
+        $(
+            impl<T: Scalar> From<&$from<T>> for $to<T> {
+                fn from(a: &$from<T>) -> Self {
+                    a.$method()
+                }
+            }
+        )*
+    };
+}
+
+impl_from_method! {
+    CooMatrix => CsrMatrix: to_csr;
+    CooMatrix => CscMatrix: to_csc;
+    CsrMatrix => CscMatrix: to_csc;
+    CscMatrix => CsrMatrix: to_csr;
+    DokMatrix => CooMatrix: to_coo;
+    DokMatrix => CsrMatrix: to_csr;
+    LilMatrix => CsrMatrix: to_csr;
+    DiaMatrix => CsrMatrix: to_csr;
+    EllMatrix => CsrMatrix: to_csr;
+    HybMatrix => CsrMatrix: to_csr;
+    BsrMatrix => CsrMatrix: to_csr;
+}
+
+impl<T: Scalar> From<&CsrMatrix<T>> for CooMatrix<T> {
+    fn from(a: &CsrMatrix<T>) -> Self {
+        let mut coo = CooMatrix::new(a.nrows(), a.ncols());
+        for i in 0..a.nrows() {
+            let (cols, values) = a.row(i);
+            for (&j, &v) in cols.iter().zip(values) {
+                coo.push(i, j, v);
+            }
+        }
+        coo
+    }
+}
+
+impl<T: Scalar> From<&CsrMatrix<T>> for DokMatrix<T> {
+    fn from(a: &CsrMatrix<T>) -> Self {
+        let mut dok = DokMatrix::new(a.nrows(), a.ncols());
+        for i in 0..a.nrows() {
+            let (cols, values) = a.row(i);
+            for (&j, &v) in cols.iter().zip(values) {
+                dok.insert(i, j, v);
+            }
+        }
+        dok
+    }
+}
+
+impl<T: Scalar> From<&CsrMatrix<T>> for LilMatrix<T> {
+    fn from(a: &CsrMatrix<T>) -> Self {
+        LilMatrix::from_csr(a)
+    }
+}
+
+impl<T: Scalar> From<&CsrMatrix<T>> for EllMatrix<T> {
+    fn from(a: &CsrMatrix<T>) -> Self {
+        EllMatrix::from_csr(a)
+    }
+}
+
+impl<T: Scalar> From<&CsrMatrix<T>> for HybMatrix<T> {
+    fn from(a: &CsrMatrix<T>) -> Self {
+        HybMatrix::from_csr(a)
+    }
+}
+
+impl<T: Scalar> TryFrom<&CsrMatrix<T>> for DiaMatrix<T> {
+    type Error = SparseError;
+
+    fn try_from(a: &CsrMatrix<T>) -> Result<Self, SparseError> {
+        // Diagonals of nrows values each, holding at most 2 nnz values in total.
+        let max_diagonals = (2 * a.nnz()).div_ceil(a.nrows().max(1)).max(1);
+        DiaMatrix::from_csr(a, max_diagonals)
+    }
+}
+
+/// Implement `From<&$from<T>> for $to<T>` (or `TryFrom` for the `try` list) by converting to
+/// CSR first.
+macro_rules! impl_via_csr {
+    ($($from:ident => $($to:ident),+;)*) => {
+        $($(
+            impl<T: Scalar> From<&$from<T>> for $to<T> {
+                fn from(a: &$from<T>) -> Self {
+                    $to::from(&CsrMatrix::from(a))
+                }
+            }
+        )+)*
+    };
+    (try $($from:ident),+) => {
+        $(
+            impl<T: Scalar> TryFrom<&$from<T>> for DiaMatrix<T> {
+                type Error = SparseError;
+
+                fn try_from(a: &$from<T>) -> Result<Self, SparseError> {
+                    DiaMatrix::try_from(&CsrMatrix::from(a))
+                }
+            }
+        )+
+    };
+}
+
+impl_via_csr! {
+    CooMatrix => DokMatrix, LilMatrix, EllMatrix;
+    CscMatrix => CooMatrix, DokMatrix, LilMatrix, EllMatrix;
+    DokMatrix => CscMatrix, LilMatrix, EllMatrix;
+    LilMatrix => CooMatrix, CscMatrix, DokMatrix, EllMatrix;
+    DiaMatrix => CooMatrix, CscMatrix, DokMatrix, LilMatrix, EllMatrix;
+    EllMatrix => CooMatrix, CscMatrix, DokMatrix, LilMatrix;
+    BsrMatrix => CooMatrix, CscMatrix, DokMatrix, LilMatrix, EllMatrix;
+}
+
+impl_via_csr!(try CooMatrix, CscMatrix, DokMatrix, LilMatrix, EllMatrix, BsrMatrix);
+
+#[test]
+fn test_conversions() {
+    #[rustfmt::skip]
+    let dense = [
+        2.0, -1.0, 0.0, 0.0,
+        -1.0, 2.0, -1.0, 0.0,
+        0.0, -1.0, 2.0, -1.0,
+        0.0, 0.0, -1.0, 2.0,
+    ];
+    let csr = CsrMatrix::from_dense(4, 4, &dense).unwrap();
+
+    let coo = CooMatrix::from(&csr);
+    assert_eq!(coo.nnz(), 10);
+    let csc = CscMatrix::from(&coo);
+    let dok = DokMatrix::from(&csc);
+    let lil = LilMatrix::from(&dok);
+    let ell = EllMatrix::from(&lil);
+    let dia = DiaMatrix::try_from(&ell).unwrap();
+    assert_eq!(dia.offsets(), [-1, 0, 1]);
+    let bsr = BsrMatrix::from_csr(&CsrMatrix::from(&dia), 2, 2).unwrap();
+    let back = CooMatrix::from(&bsr);
+    assert_eq!(CsrMatrix::from(&back).to_dense(), dense);
+    assert_eq!(CsrMatrix::from(&HybMatrix::from(&csr)).to_dense(), dense);
+
+    // Three diagonals of 10 values for 3 entries.
+    let mut sparse = DokMatrix::new(10, 10);
+    sparse.insert(9, 0, 1.0);
+    sparse.insert(0, 9, 1.0);
+    sparse.insert(5, 5, 1.0);
+    assert!(matches!(
+        DiaMatrix::try_from(&sparse),
+        Err(SparseError::InvalidStructure(_))
+    ));
+}
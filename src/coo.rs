@@ -0,0 +1,464 @@
+use alloc::format;
+use alloc::vec::Vec;
+
+use crate::compressed::{compress_triplets, compress_triplets_with};
+use crate::csc::CscMatrix;
+use crate::csr::CsrMatrix;
+use crate::display;
+use crate::error::SparseError;
+use crate::scalar::Scalar;
+
+/// How the values of a position given several times are combined when triplets are
+/// compressed, see [`CooMatrix::to_csr_with`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DuplicatePolicy {
+    /// Add the values up, as finite element assembly expects
+    #[default]
+    Sum,
+    /// Keep the value given first
+    First,
+    /// Keep the value given last, for data where a later write overrides an earlier one
+    Last,
+    /// Keep the largest value. A NaN given after a number is dropped, and one given first is
+    /// kept.
+    Max,
+    /// Keep the smallest value, with NaNs treated as by `Max`
+    Min,
+    /// Fail with [`SparseError::DuplicateEntry`], reporting the first position given twice, in
+    /// row-major order for CSR and column-major order for CSC, with its first two values
+    Error,
+}
+
+/// A sparse matrix in coordinate (COO) form: an unordered list of `(row, column, value)`
+/// triplets.
+///
+/// This is the natural format for assembling a matrix entry by entry. The same position may be
+/// pushed several times, and the values are summed when the matrix is compressed to CSR or CSC,
+/// which is what finite element assembly and the like expect.
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(
+        try_from = "RawCooMatrix<T>",
+        bound(deserialize = "T: Scalar + serde::Deserialize<'de>")
+    )
+)]
+pub struct CooMatrix<T = f64> {
+    nrows: usize,
+    ncols: usize,
+    rows: Vec<usize>,
+    cols: Vec<usize>,
+    values: Vec<T>,
+}
+
+/// The fields of a [`CooMatrix`] as deserialized, before they are checked.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct RawCooMatrix<T> {
+    nrows: usize,
+    ncols: usize,
+    rows: Vec<usize>,
+    cols: Vec<usize>,
+    values: Vec<T>,
+}
+
+#[cfg(feature = "serde")]
+impl<T: Scalar> TryFrom<RawCooMatrix<T>> for CooMatrix<T> {
+    type Error = SparseError;
+
+    fn try_from(raw: RawCooMatrix<T>) -> Result<Self, SparseError> {
+        Self::from_triplets(raw.nrows, raw.ncols, raw.rows, raw.cols, raw.values)
+    }
+}
+
+impl<T: Scalar> CooMatrix<T> {
+    /// Create a `nrows` by `ncols` matrix with no triplets.
+    pub fn new(nrows: usize, ncols: usize) -> Self {
+        Self {
+            nrows,
+            ncols,
+            rows: Vec::new(),
+            cols: Vec::new(),
+            values: Vec::new(),
+        }
+    }
+
+    /// Build a matrix from parallel arrays of row indices, column indices and values.
+    pub fn from_triplets(
+        nrows: usize,
+        ncols: usize,
+        rows: Vec<usize>,
+        cols: Vec<usize>,
+        values: Vec<T>,
+    ) -> Result<Self, SparseError> {
+        for len in [cols.len(), values.len()] {
+            if len != rows.len() {
+                return Err(SparseError::DimensionMismatch {
+                    expected: rows.len(),
+                    found: len,
+                });
+            }
+        }
+        if let Some(&i) = rows.iter().find(|&&i| i >= nrows) {
+            return Err(SparseError::IndexOutOfBounds {
+                index: i,
+                len: nrows,
+            });
+        }
+        if let Some(&j) = cols.iter().find(|&&j| j >= ncols) {
+            return Err(SparseError::IndexOutOfBounds {
+                index: j,
+                len: ncols,
+            });
+        }
+
+        Ok(Self {
+            nrows,
+            ncols,
+            rows,
+            cols,
+            values,
+        })
+    }
+
+    /// Append the triplet `(i, j, v)`. Pushing a position twice adds the values up on conversion.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` or `j` is out of bounds.
+    pub fn push(&mut self, i: usize, j: usize, v: T) {
+        assert!(
+            i < self.nrows,
+            "row {i} out of bounds for {} rows",
+            self.nrows
+        );
+        assert!(
+            j < self.ncols,
+            "column {j} out of bounds for {} columns",
+            self.ncols
+        );
+        self.rows.push(i);
+        self.cols.push(j);
+        self.values.push(v);
+    }
+
+    /// Return the number of rows
+    pub fn nrows(&self) -> usize {
+        self.nrows
+    }
+
+    /// Return the number of columns
+    pub fn ncols(&self) -> usize {
+        self.ncols
+    }
+
+    /// Return `(nrows, ncols)`
+    pub fn shape(&self) -> (usize, usize) {
+        (self.nrows, self.ncols)
+    }
+
+    /// Return the number of triplets, duplicates included
+    pub fn nnz(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Iterate over the triplets in the order they were pushed.
+    pub fn triplets(&self) -> impl Iterator<Item = (usize, usize, T)> + '_ {
+        self.rows
+            .iter()
+            .zip(&self.cols)
+            .zip(&self.values)
+            .map(|((&i, &j), &v)| (i, j, v))
+    }
+
+    /// Compress to CSR, summing duplicated positions. O(nnz + nrows + ncols).
+    pub fn to_csr(&self) -> CsrMatrix<T> {
+        let (indptr, indices, data) =
+            compress_triplets(self.nrows, self.ncols, &self.rows, &self.cols, &self.values);
+        CsrMatrix::from_parts(self.nrows, self.ncols, indptr, indices, data)
+    }
+
+    /// Compress to CSC, summing duplicated positions. O(nnz + nrows + ncols).
+    pub fn to_csc(&self) -> CscMatrix<T> {
+        let (indptr, indices, data) =
+            compress_triplets(self.ncols, self.nrows, &self.cols, &self.rows, &self.values);
+        CscMatrix::from_parts(self.nrows, self.ncols, indptr, indices, data)
+    }
+}
+
+impl<T: Scalar + PartialOrd> CooMatrix<T> {
+    /// Build a matrix from parallel arrays of row indices, column indices and values, as
+    /// [`from_triplets`](Self::from_triplets) does, with the duplicated positions resolved by
+    /// `policy` up front. [`DuplicatePolicy::Sum`] leaves the duplicates in, to be summed on
+    /// conversion.
+    pub fn from_triplets_with(
+        nrows: usize,
+        ncols: usize,
+        rows: Vec<usize>,
+        cols: Vec<usize>,
+        values: Vec<T>,
+        policy: DuplicatePolicy,
+    ) -> Result<Self, SparseError> {
+        Self::from_triplets(nrows, ncols, rows, cols, values)?.deduplicate(policy)
+    }
+
+    /// Resolve the duplicated positions by `policy`, leaving one triplet per position in
+    /// row-major order. [`DuplicatePolicy::Sum`] returns the matrix as it is, since the
+    /// conversions sum duplicates anyway.
+    pub fn deduplicate(self, policy: DuplicatePolicy) -> Result<Self, SparseError> {
+        match policy {
+            DuplicatePolicy::Sum => Ok(self),
+            _ => Ok(CooMatrix::from(&self.to_csr_with(policy)?)),
+        }
+    }
+
+    /// Compress to CSR, combining duplicated positions by `policy`. With
+    /// [`DuplicatePolicy::Sum`] this is [`to_csr`](Self::to_csr). O(nnz + nrows + ncols).
+    pub fn to_csr_with(&self, policy: DuplicatePolicy) -> Result<CsrMatrix<T>, SparseError> {
+        if policy == DuplicatePolicy::Sum {
+            return Ok(self.to_csr());
+        }
+        let (indptr, indices, data) = compress_triplets_with(
+            self.nrows,
+            self.ncols,
+            &self.rows,
+            &self.cols,
+            &self.values,
+            |i, j, acc, v| combine(policy, (i, j), acc, v),
+        )?;
+        Ok(CsrMatrix::from_parts(
+            self.nrows, self.ncols, indptr, indices, data,
+        ))
+    }
+
+    /// Compress to CSC, combining duplicated positions by `policy`. With
+    /// [`DuplicatePolicy::Sum`] this is [`to_csc`](Self::to_csc). O(nnz + nrows + ncols).
+    pub fn to_csc_with(&self, policy: DuplicatePolicy) -> Result<CscMatrix<T>, SparseError> {
+        if policy == DuplicatePolicy::Sum {
+            return Ok(self.to_csc());
+        }
+        let (indptr, indices, data) = compress_triplets_with(
+            self.ncols,
+            self.nrows,
+            &self.cols,
+            &self.rows,
+            &self.values,
+            |j, i, acc, v| combine(policy, (i, j), acc, v),
+        )?;
+        Ok(CscMatrix::from_parts(
+            self.nrows, self.ncols, indptr, indices, data,
+        ))
+    }
+}
+
+/// Fold the duplicate `v` of the position `(i, j)` into the value `acc` kept so far.
+fn combine<T: Scalar + PartialOrd>(
+    policy: DuplicatePolicy,
+    (i, j): (usize, usize),
+    acc: &mut T,
+    v: T,
+) -> Result<(), SparseError> {
+    match policy {
+        DuplicatePolicy::Sum => *acc += v,
+        DuplicatePolicy::First => {}
+        DuplicatePolicy::Last => *acc = v,
+        DuplicatePolicy::Max => {
+            if v > *acc {
+                *acc = v;
+            }
+        }
+        DuplicatePolicy::Min => {
+            if v < *acc {
+                *acc = v;
+            }
+        }
+        DuplicatePolicy::Error => {
+            return Err(SparseError::DuplicateEntry {
+                row: i,
+                col: j,
+                first: format!("{acc:?}"),
+                second: format!("{v:?}"),
+            })
+        }
+    }
+    Ok(())
+}
+
+/// Two matrices are equal when the CSR matrices they compress to are, so duplicated positions
+/// are compared by their sum and the order of the triplets doesn't matter.
+impl<T: Scalar> PartialEq for CooMatrix<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.to_csr() == other.to_csr()
+    }
+}
+
+/// Compared as the CSR matrices they compress to, like [`PartialEq`].
+#[cfg(feature = "approx")]
+impl approx::AbsDiffEq for CooMatrix<f64> {
+    type Epsilon = f64;
+
+    fn default_epsilon() -> f64 {
+        f64::default_epsilon()
+    }
+
+    fn abs_diff_eq(&self, other: &Self, epsilon: f64) -> bool {
+        self.to_csr().abs_diff_eq(&other.to_csr(), epsilon)
+    }
+}
+
+#[cfg(feature = "approx")]
+impl approx::RelativeEq for CooMatrix<f64> {
+    fn default_max_relative() -> f64 {
+        f64::default_max_relative()
+    }
+
+    fn relative_eq(&self, other: &Self, epsilon: f64, max_relative: f64) -> bool {
+        self.to_csr()
+            .relative_eq(&other.to_csr(), epsilon, max_relative)
+    }
+}
+
+#[cfg(feature = "approx")]
+impl approx::UlpsEq for CooMatrix<f64> {
+    fn default_max_ulps() -> u32 {
+        f64::default_max_ulps()
+    }
+
+    fn ulps_eq(&self, other: &Self, epsilon: f64, max_ulps: u32) -> bool {
+        self.to_csr().ulps_eq(&other.to_csr(), epsilon, max_ulps)
+    }
+}
+
+/// Lists the triplets in the order they were pushed, duplicates included, truncated for large
+/// matrices.
+impl<T: Scalar + core::fmt::Display> core::fmt::Display for CooMatrix<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "{}x{} COO matrix, {} triplets",
+            self.nrows,
+            self.ncols,
+            self.nnz()
+        )?;
+        display::fmt_triplets(f, self.nnz(), self.triplets())
+    }
+}
+
+#[test]
+fn test_coo_matrix() {
+    let mut coo = CooMatrix::new(3, 4);
+    coo.push(2, 1, 3.0);
+    coo.push(0, 3, 2.0);
+    coo.push(0, 0, 1.0);
+    coo.push(2, 1, 0.5);
+    coo.push(1, 2, 7.0);
+    coo.push(0, 3, -2.0);
+    assert_eq!(coo.nnz(), 6);
+    assert_eq!(coo.triplets().next(), Some((2, 1, 3.0)));
+
+    #[rustfmt::skip]
+    let dense = [
+        1.0, 0.0, 0.0, 0.0,
+        0.0, 0.0, 7.0, 0.0,
+        0.0, 3.5, 0.0, 0.0,
+    ];
+
+    let csr = coo.to_csr();
+    assert_eq!(csr.to_dense(), dense);
+    // Duplicates are summed into one entry, even when they cancel.
+    assert_eq!(csr.nnz(), 4);
+    assert_eq!(csr.indptr(), [0, 2, 3, 4]);
+    assert_eq!(csr.indices(), [0, 3, 2, 1]);
+
+    let csc = coo.to_csc();
+    assert_eq!(csc.to_dense(), dense);
+    assert_eq!(csc.indptr(), [0, 1, 2, 3, 4]);
+
+    let empty = CooMatrix::<f64>::new(2, 2).to_csr();
+    assert_eq!(empty.nnz(), 0);
+    assert_eq!(empty.indptr(), [0, 0, 0]);
+
+    let from_triplets =
+        CooMatrix::from_triplets(2, 2, vec![0, 1, 0], vec![1, 0, 1], vec![1, 2, 3]).unwrap();
+    assert_eq!(from_triplets.to_csr().to_dense(), [0, 4, 2, 0]);
+    assert_eq!(
+        CooMatrix::from_triplets(2, 2, vec![0, 2], vec![1, 0], vec![1, 2]).unwrap_err(),
+        SparseError::IndexOutOfBounds { index: 2, len: 2 }
+    );
+    assert_eq!(
+        CooMatrix::from_triplets(2, 2, vec![0, 1], vec![1], vec![1, 2]).unwrap_err(),
+        SparseError::DimensionMismatch {
+            expected: 2,
+            found: 1
+        }
+    );
+}
+
+#[test]
+fn test_coo_duplicate_policies() {
+    // (0, 1) given three times, (2, 0) twice, in interleaved order.
+    let rows = vec![0, 2, 1, 0, 2, 0];
+    let cols = vec![1, 0, 2, 1, 0, 1];
+    let values = vec![2.0, -1.0, 5.0, 7.0, 3.0, 4.0];
+    let coo = CooMatrix::from_triplets(3, 3, rows.clone(), cols.clone(), values.clone()).unwrap();
+    let policies = [
+        (DuplicatePolicy::Sum, 13.0, 2.0),
+        (DuplicatePolicy::First, 2.0, -1.0),
+        (DuplicatePolicy::Last, 4.0, 3.0),
+        (DuplicatePolicy::Max, 7.0, 3.0),
+        (DuplicatePolicy::Min, 2.0, -1.0),
+    ];
+    for (policy, a01, a20) in policies {
+        #[rustfmt::skip]
+        let expected = [
+            0.0, a01, 0.0,
+            0.0, 0.0, 5.0,
+            a20, 0.0, 0.0,
+        ];
+        let csr = CsrMatrix::from_coo_with(&coo, policy).unwrap();
+        assert_eq!(csr.to_dense(), expected, "{policy:?}");
+        assert_eq!(csr.nnz(), 3);
+        assert_eq!(coo.to_csc_with(policy).unwrap().to_dense(), expected);
+
+        let deduplicated =
+            CooMatrix::from_triplets_with(3, 3, rows.clone(), cols.clone(), values.clone(), policy)
+                .unwrap();
+        assert_eq!(deduplicated.to_csr().to_dense(), expected);
+        if policy != DuplicatePolicy::Sum {
+            assert_eq!(deduplicated.nnz(), 3);
+        }
+    }
+    assert_eq!(CsrMatrix::from_coo(&coo), coo.to_csr());
+
+    // The error names the first duplicated position in row-major order, with its first two
+    // values, here and for CSC in column-major order.
+    let expected = SparseError::DuplicateEntry {
+        row: 0,
+        col: 1,
+        first: "2.0".into(),
+        second: "7.0".into(),
+    };
+    assert_eq!(
+        CsrMatrix::from_coo_with(&coo, DuplicatePolicy::Error).unwrap_err(),
+        expected
+    );
+    assert_eq!(
+        expected.to_string(),
+        "duplicate entry at (0, 1): 2.0 and 7.0"
+    );
+    assert_eq!(
+        coo.to_csc_with(DuplicatePolicy::Error).unwrap_err(),
+        SparseError::DuplicateEntry {
+            row: 2,
+            col: 0,
+            first: "-1.0".into(),
+            second: "3.0".into(),
+        }
+    );
+    let distinct = CooMatrix::from_triplets(2, 2, vec![0, 1], vec![1, 0], vec![1, 2]).unwrap();
+    assert_eq!(
+        CsrMatrix::from_coo_with(&distinct, DuplicatePolicy::Error).unwrap(),
+        distinct.to_csr()
+    );
+}
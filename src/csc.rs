@@ -0,0 +1,997 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::compressed::{
+    all_union_compressed, empty_inner, empty_outer, frobenius, max_inner_abs_sum,
+    max_outer_abs_sum, retain_compressed, transpose_compressed, validate_compressed,
+};
+use crate::csr::CsrMatrix;
+use crate::display;
+use crate::error::SparseError;
+use crate::index::{convert_indices, IndexType};
+use crate::scalar::Scalar;
+use crate::vec::PackedVec;
+
+/// A sparse matrix in compressed sparse column (CSC) form.
+///
+/// This is the column-oriented twin of [`CsrMatrix`]: the row indices and values of column `j`
+/// are `indices[indptr[j]..indptr[j + 1]]` and `data[indptr[j]..indptr[j + 1]]`, with the row
+/// indices of each column sorted. Column-oriented algorithms (left-looking LU, column slicing)
+/// work on this form.
+///
+/// The indices are usize unless narrowed with [`CscMatrix::to_index_type`]; the [`index`]
+/// module lists what a matrix with other indices supports.
+///
+/// [`index`]: crate::index
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(
+        try_from = "RawCscMatrix<T, I>",
+        bound(deserialize = "T: Scalar + serde::Deserialize<'de>, \
+                             I: IndexType + serde::Deserialize<'de>")
+    )
+)]
+pub struct CscMatrix<T = f64, I = usize> {
+    nrows: usize,
+    ncols: usize,
+    /// Start of each column in `indices` and `data`, plus the total number of entries at the end
+    indptr: Vec<I>,
+    /// Row index of each stored entry
+    indices: Vec<I>,
+    /// Value of each stored entry
+    data: Vec<T>,
+}
+
+/// The fields of a [`CscMatrix`] as deserialized, before they are checked.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct RawCscMatrix<T, I> {
+    nrows: usize,
+    ncols: usize,
+    indptr: Vec<I>,
+    indices: Vec<I>,
+    data: Vec<T>,
+}
+
+#[cfg(feature = "serde")]
+impl<T: Scalar, I: IndexType> TryFrom<RawCscMatrix<T, I>> for CscMatrix<T, I> {
+    type Error = SparseError;
+
+    fn try_from(raw: RawCscMatrix<T, I>) -> Result<Self, SparseError> {
+        let (nrows, ncols) = (raw.nrows, raw.ncols);
+        validate_compressed(
+            ncols,
+            nrows,
+            &raw.indptr,
+            &raw.indices,
+            raw.data.len(),
+            "column",
+        )?;
+
+        Ok(Self {
+            nrows,
+            ncols,
+            indptr: raw.indptr,
+            indices: raw.indices,
+            data: raw.data,
+        })
+    }
+}
+
+impl<T: Scalar> CscMatrix<T> {
+    /// Create a `nrows` by `ncols` matrix with no stored entries.
+    pub fn new(nrows: usize, ncols: usize) -> Self {
+        Self {
+            nrows,
+            ncols,
+            indptr: vec![0; ncols + 1],
+            indices: Vec::new(),
+            data: Vec::new(),
+        }
+    }
+
+    /// Build a matrix from its raw CSC arrays, checking that they describe a valid matrix:
+    /// `indptr` has `ncols + 1` non-decreasing entries from 0 to `indices.len()`, `indices` and
+    /// `data` have the same length, and the row indices of each column are strictly increasing
+    /// and less than `nrows`.
+    pub fn try_from_csc_data(
+        nrows: usize,
+        ncols: usize,
+        indptr: Vec<usize>,
+        indices: Vec<usize>,
+        data: Vec<T>,
+    ) -> Result<Self, SparseError> {
+        validate_compressed(ncols, nrows, &indptr, &indices, data.len(), "column")?;
+
+        Ok(Self {
+            nrows,
+            ncols,
+            indptr,
+            indices,
+            data,
+        })
+    }
+
+    /// Gather a row-major dense `nrows` by `ncols` array into a CSC matrix.
+    pub fn from_dense(nrows: usize, ncols: usize, dense: &[T]) -> Result<Self, SparseError> {
+        if dense.len() != nrows * ncols {
+            return Err(SparseError::DimensionMismatch {
+                expected: nrows * ncols,
+                found: dense.len(),
+            });
+        }
+
+        let mut matrix = Self::new(nrows, ncols);
+        for j in 0..ncols {
+            for i in 0..nrows {
+                let v = dense[i * ncols + j];
+                if v != T::zero() {
+                    matrix.indices.push(i);
+                    matrix.data.push(v);
+                }
+            }
+            matrix.indptr[j + 1] = matrix.indices.len();
+        }
+
+        Ok(matrix)
+    }
+}
+
+/// The accessors, for any index type.
+impl<T: Scalar, I: IndexType> CscMatrix<T, I> {
+    /// Return the number of rows
+    pub fn nrows(&self) -> usize {
+        self.nrows
+    }
+
+    /// Return the number of columns
+    pub fn ncols(&self) -> usize {
+        self.ncols
+    }
+
+    /// Return `(nrows, ncols)`
+    pub fn shape(&self) -> (usize, usize) {
+        (self.nrows, self.ncols)
+    }
+
+    /// Return the number of stored entries
+    pub fn nnz(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Return the column pointer array
+    pub fn indptr(&self) -> &[I] {
+        &self.indptr
+    }
+
+    /// Return the row index of every stored entry, column after column
+    pub fn indices(&self) -> &[I] {
+        &self.indices
+    }
+
+    /// Return the value of every stored entry, column after column
+    pub fn data(&self) -> &[T] {
+        &self.data
+    }
+
+    /// Return the sorted row indices and the values of column `j`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `j >= self.ncols()`.
+    pub fn col(&self, j: usize) -> (&[I], &[T]) {
+        let range = self.indptr[j].index()..self.indptr[j + 1].index();
+        (&self.indices[range.clone()], &self.data[range])
+    }
+
+    /// Return the sorted row indices and the mutable values of column `j`, for updates that keep
+    /// the pattern.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `j >= self.ncols()`.
+    pub(crate) fn col_mut(&mut self, j: usize) -> (&[I], &mut [T]) {
+        let range = self.indptr[j].index()..self.indptr[j + 1].index();
+        (&self.indices[range.clone()], &mut self.data[range])
+    }
+
+    /// Convert the indices to another [`IndexType`], such as u32 to halve their storage.
+    /// Fails with [`SparseError::IndexOutOfBounds`] when a row index or the number of stored
+    /// entries doesn't fit in `J`. O(nnz + ncols).
+    pub fn to_index_type<J: IndexType>(&self) -> Result<CscMatrix<T, J>, SparseError> {
+        Ok(CscMatrix {
+            nrows: self.nrows,
+            ncols: self.ncols,
+            indptr: convert_indices(&self.indptr)?,
+            indices: convert_indices(&self.indices)?,
+            data: self.data.clone(),
+        })
+    }
+}
+
+impl<T: Scalar> CscMatrix<T> {
+    /// Return the entry at row `i` and column `j`, 0 when it is not stored.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` or `j` is out of bounds.
+    pub fn get(&self, i: usize, j: usize) -> T {
+        assert!(
+            i < self.nrows,
+            "row {i} out of bounds for {} rows",
+            self.nrows
+        );
+        let (rows, values) = self.col(j);
+        match rows.binary_search(&i) {
+            Ok(k) => values[k],
+            Err(_) => T::zero(),
+        }
+    }
+
+    /// Return the rows with no stored entries, in increasing order. O(nnz + nrows).
+    pub fn find_empty_rows(&self) -> Vec<usize> {
+        empty_inner(self.nrows, &self.indices)
+    }
+
+    /// Return the columns with no stored entries, in increasing order. O(ncols).
+    pub fn find_empty_cols(&self) -> Vec<usize> {
+        empty_outer(&self.indptr)
+    }
+
+    /// Borrow the matrix as a [`CscView`], which holds the column kernels. O(1).
+    pub fn view(&self) -> CscView<'_, T> {
+        CscView {
+            nrows: self.nrows,
+            ncols: self.ncols,
+            indptr: &self.indptr,
+            indices: &self.indices,
+            data: &self.data,
+        }
+    }
+
+    /// Solve `L x = b` by forward substitution, where `L` is the lower triangle of this square
+    /// matrix, diagonal included. See [`CscView::solve_lower_triangular`].
+    pub fn solve_lower_triangular(&self, b: &[T]) -> Result<Vec<T>, SparseError> {
+        self.view().solve_lower_triangular(b)
+    }
+
+    /// Solve `U x = b` by backward substitution, where `U` is the upper triangle of this square
+    /// matrix, diagonal included. See [`CscView::solve_upper_triangular`].
+    pub fn solve_upper_triangular(&self, b: &[T]) -> Result<Vec<T>, SparseError> {
+        self.view().solve_upper_triangular(b)
+    }
+
+    /// Solve `L x = b` for a sparse right-hand side, where `L` is the lower triangle of this
+    /// square matrix. See [`CscView::solve_lower_triangular_sparse`].
+    pub fn solve_lower_triangular_sparse(
+        &self,
+        b: &PackedVec<T>,
+    ) -> Result<PackedVec<T>, SparseError> {
+        self.view().solve_lower_triangular_sparse(b)
+    }
+
+    /// Solve `U x = b` for a sparse right-hand side, where `U` is the upper triangle of this
+    /// square matrix. See [`CscView::solve_lower_triangular_sparse`].
+    pub fn solve_upper_triangular_sparse(
+        &self,
+        b: &PackedVec<T>,
+    ) -> Result<PackedVec<T>, SparseError> {
+        self.view().solve_upper_triangular_sparse(b)
+    }
+
+    /// Return the transpose in CSC form, in O(nnz + nrows).
+    pub fn transpose(&self) -> CscMatrix<T> {
+        let (indptr, indices, data) =
+            transpose_compressed(self.nrows, &self.indptr, &self.indices, &self.data);
+        CscMatrix {
+            nrows: self.ncols,
+            ncols: self.nrows,
+            indptr,
+            indices,
+            data,
+        }
+    }
+
+    /// Return the conjugate transpose `Aᴴ`, which is the transpose for real types.
+    pub fn adjoint(&self) -> CscMatrix<T> {
+        let mut t = self.transpose();
+        for v in &mut t.data {
+            *v = v.conj();
+        }
+        t
+    }
+
+    /// Return true if the matrix equals its conjugate transpose (for real types: is symmetric),
+    /// comparing values exactly.
+    pub fn is_hermitian(&self) -> bool {
+        if self.nrows != self.ncols {
+            return false;
+        }
+
+        // The adjoint has sorted indices too, so equal matrices have identical arrays.
+        let h = self.adjoint();
+        h.indptr == self.indptr && h.indices == self.indices && h.data == self.data
+    }
+
+    /// Reinterpret the matrix as the CSR form of its transpose, without touching the arrays:
+    /// the columns of `A` are the rows of `Aᵀ`. O(1).
+    pub fn into_transpose_csr(self) -> CsrMatrix<T> {
+        CsrMatrix::from_parts(self.ncols, self.nrows, self.indptr, self.indices, self.data)
+    }
+
+    /// Build a matrix from arrays known to be valid.
+    pub(crate) fn from_parts(
+        nrows: usize,
+        ncols: usize,
+        indptr: Vec<usize>,
+        indices: Vec<usize>,
+        data: Vec<T>,
+    ) -> Self {
+        debug_assert!(
+            validate_compressed(ncols, nrows, &indptr, &indices, data.len(), "column").is_ok()
+        );
+        Self {
+            nrows,
+            ncols,
+            indptr,
+            indices,
+            data,
+        }
+    }
+
+    /// Take the matrix apart into `(nrows, ncols, indptr, indices, data)`.
+    #[cfg(feature = "sprs")]
+    pub(crate) fn into_parts(self) -> (usize, usize, Vec<usize>, Vec<usize>, Vec<T>) {
+        (self.nrows, self.ncols, self.indptr, self.indices, self.data)
+    }
+
+    /// Convert to compressed sparse row form, in O(nnz + nrows).
+    pub fn to_csr(&self) -> CsrMatrix<T> {
+        let (indptr, indices, data) =
+            transpose_compressed(self.nrows, &self.indptr, &self.indices, &self.data);
+        CsrMatrix::from_parts(self.nrows, self.ncols, indptr, indices, data)
+    }
+
+    /// Scatter the matrix into a row-major dense array of `nrows * ncols` entries.
+    pub fn to_dense(&self) -> Vec<T> {
+        let mut dense = vec![T::zero(); self.nrows * self.ncols];
+        for j in 0..self.ncols {
+            let (rows, values) = self.col(j);
+            for (&i, &v) in rows.iter().zip(values) {
+                dense[i * self.ncols + j] = v;
+            }
+        }
+        dense
+    }
+
+    /// Walk the union of both patterns with `f`, see [`all_union_compressed`]. False when the
+    /// shapes differ.
+    fn all_union(&self, other: &Self, f: impl FnMut(T, T) -> bool) -> bool {
+        self.shape() == other.shape()
+            && all_union_compressed(
+                (&self.indptr, &self.indices, &self.data),
+                (&other.indptr, &other.indices, &other.data),
+                f,
+            )
+    }
+}
+
+impl CscMatrix<f64> {
+    /// Drop the stored entries with `|v| <= tol`, such as values that cancelled during
+    /// arithmetic, and release their storage. `prune(0.0)` removes just the explicit zeros. NaN
+    /// entries are kept.
+    pub fn prune(&mut self, tol: f64) {
+        retain_compressed(&mut self.indptr, &mut self.indices, &mut self.data, |v| {
+            v.abs() > tol || v.is_nan()
+        });
+    }
+
+    /// Return the Frobenius norm, the square root of the sum of the squared entries. O(nnz).
+    pub fn norm_fro(&self) -> f64 {
+        frobenius(&self.data)
+    }
+
+    /// Return the 1-norm, the largest absolute column sum. NaN when an entry is NaN.
+    /// O(nnz + ncols).
+    pub fn norm_one(&self) -> f64 {
+        max_outer_abs_sum(&self.indptr, &self.data)
+    }
+
+    /// Return the infinity norm, the largest absolute row sum. NaN when an entry is NaN.
+    /// O(nnz + nrows).
+    pub fn norm_inf(&self) -> f64 {
+        max_inner_abs_sum(self.nrows, &self.indices, &self.data)
+    }
+
+    /// Add `alpha` times column `j` to the dense vector `r`, `r ← r + α A e_j`, the residual
+    /// update of a coordinate descent step. O(entries of the column).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `j >= self.ncols()` or `r.len() != self.nrows()`.
+    pub fn col_axpy_into(&self, j: usize, alpha: f64, r: &mut [f64]) {
+        self.view().col_axpy_into(j, alpha, r)
+    }
+
+    /// Return the dot product of column `j` with the dense vector `r`, `(A e_j)ᵀ r`, a
+    /// coordinate of the gradient `Aᵀ r`. O(entries of the column).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `j >= self.ncols()` or `r.len() != self.nrows()`.
+    pub fn col_dot_dense(&self, j: usize, r: &[f64]) -> f64 {
+        self.view().col_dot_dense(j, r)
+    }
+}
+
+/// A borrowed matrix in CSC form, from [`CscMatrix::view`] or, without converting anything, from
+/// [`CsrMatrix::as_csc_view_symmetric`]. It holds the column-oriented kernels, so that both
+/// sources share them.
+#[derive(Clone, Copy, Debug)]
+pub struct CscView<'a, T = f64> {
+    nrows: usize,
+    ncols: usize,
+    indptr: &'a [usize],
+    indices: &'a [usize],
+    data: &'a [T],
+}
+
+impl<'a, T: Scalar> CscView<'a, T> {
+    /// View arrays known to describe a valid `nrows` by `ncols` CSC matrix.
+    pub(crate) fn from_parts(
+        nrows: usize,
+        ncols: usize,
+        indptr: &'a [usize],
+        indices: &'a [usize],
+        data: &'a [T],
+    ) -> Self {
+        Self {
+            nrows,
+            ncols,
+            indptr,
+            indices,
+            data,
+        }
+    }
+
+    /// Return the number of rows
+    pub fn nrows(&self) -> usize {
+        self.nrows
+    }
+
+    /// Return the number of columns
+    pub fn ncols(&self) -> usize {
+        self.ncols
+    }
+
+    /// Return `(nrows, ncols)`
+    pub fn shape(&self) -> (usize, usize) {
+        (self.nrows, self.ncols)
+    }
+
+    /// Return the number of stored entries
+    pub fn nnz(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Return the sorted row indices and the values of column `j`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `j >= self.ncols()`.
+    pub fn col(&self, j: usize) -> (&'a [usize], &'a [T]) {
+        let range = self.indptr[j]..self.indptr[j + 1];
+        (&self.indices[range.clone()], &self.data[range])
+    }
+
+    /// Copy the viewed matrix into an owned [`CscMatrix`]. O(nnz + ncols).
+    pub fn to_csc(&self) -> CscMatrix<T> {
+        CscMatrix::from_parts(
+            self.nrows,
+            self.ncols,
+            self.indptr.to_vec(),
+            self.indices.to_vec(),
+            self.data.to_vec(),
+        )
+    }
+
+    /// Solve `L x = b` by forward substitution, where `L` is the lower triangle of this square
+    /// matrix, diagonal included. Entries above the diagonal are ignored. O(nnz + n).
+    ///
+    /// Fails with [`SparseError::EmptyColumn`] before any arithmetic when a column has no
+    /// stored entries, and with [`SparseError::ZeroPivot`] when a diagonal entry is zero or
+    /// not stored.
+    pub fn solve_lower_triangular(&self, b: &[T]) -> Result<Vec<T>, SparseError> {
+        self.check_triangular_operand(b.len())?;
+        let mut x = b.to_vec();
+        for j in 0..self.ncols {
+            self.eliminate_column(j, true, &mut x)?;
+        }
+        Ok(x)
+    }
+
+    /// Solve `U x = b` by backward substitution, where `U` is the upper triangle of this square
+    /// matrix, diagonal included. Entries below the diagonal are ignored. O(nnz + n).
+    ///
+    /// Fails as [`solve_lower_triangular`](Self::solve_lower_triangular) does.
+    pub fn solve_upper_triangular(&self, b: &[T]) -> Result<Vec<T>, SparseError> {
+        self.check_triangular_operand(b.len())?;
+        let mut x = b.to_vec();
+        for j in (0..self.ncols).rev() {
+            self.eliminate_column(j, false, &mut x)?;
+        }
+        Ok(x)
+    }
+
+    /// Solve `L x = b` for a sparse right-hand side, where `L` is the lower triangle of this
+    /// square matrix, with the algorithm of Gilbert and Peierls.
+    ///
+    /// `x[i]` can only be nonzero if `i` is reachable from a nonzero of `b` in the graph with an
+    /// edge `j -> i` for every entry `L[i][j]`. A depth-first search computes this reach in
+    /// topological order first, and the substitution then only visits the columns in it. Apart
+    /// from O(n) for the work arrays, the cost is proportional to the number of flops, which is
+    /// what the left-looking LU factorization needs for each of its columns.
+    pub fn solve_lower_triangular_sparse(
+        &self,
+        b: &PackedVec<T>,
+    ) -> Result<PackedVec<T>, SparseError> {
+        self.solve_triangular_sparse(b, true)
+    }
+
+    /// Solve `U x = b` for a sparse right-hand side, where `U` is the upper triangle of this
+    /// square matrix. See [`CscView::solve_lower_triangular_sparse`].
+    pub fn solve_upper_triangular_sparse(
+        &self,
+        b: &PackedVec<T>,
+    ) -> Result<PackedVec<T>, SparseError> {
+        self.solve_triangular_sparse(b, false)
+    }
+
+    fn solve_triangular_sparse(
+        &self,
+        b: &PackedVec<T>,
+        lower: bool,
+    ) -> Result<PackedVec<T>, SparseError> {
+        if b.full_len() != self.ncols {
+            return Err(SparseError::DimensionMismatch {
+                expected: self.ncols,
+                found: b.full_len(),
+            });
+        }
+        self.sparse_triangular_solver(lower)?.solve(b)
+    }
+
+    /// Check the matrix once for a run of sparse triangular solves with the lower (or upper)
+    /// triangle, and allocate the work arrays they share.
+    pub(crate) fn sparse_triangular_solver(
+        &self,
+        lower: bool,
+    ) -> Result<SparseTriangularSolver<'a, T>, SparseError> {
+        self.check_triangular_operand(self.ncols)?;
+        Ok(SparseTriangularSolver {
+            view: *self,
+            lower,
+            marked: vec![false; self.ncols],
+            x: vec![T::zero(); self.ncols],
+            stack: Vec::new(),
+            reach: Vec::new(),
+        })
+    }
+
+    /// Return the nodes reachable from `starts` in the graph of the lower (or upper) triangle,
+    /// in topological order: the reverse of the order in which the depth-first search finishes
+    /// them.
+    #[cfg(test)]
+    fn reach(&self, starts: impl Iterator<Item = usize>, lower: bool) -> Vec<usize> {
+        let mut marked = vec![false; self.ncols];
+        let mut reach = Vec::new();
+        self.reach_into(starts, lower, &mut marked, &mut Vec::new(), &mut reach);
+        reach
+    }
+
+    /// Push the nodes reachable from `starts` that aren't marked yet onto `reach`, in
+    /// topological order, and mark them. `stack` is scratch space.
+    fn reach_into(
+        &self,
+        starts: impl Iterator<Item = usize>,
+        lower: bool,
+        marked: &mut [bool],
+        // (node, position of the next entry of its column to look at)
+        stack: &mut Vec<(usize, usize)>,
+        reach: &mut Vec<usize>,
+    ) {
+        let first = reach.len();
+        for start in starts {
+            if marked[start] {
+                continue;
+            }
+            marked[start] = true;
+            stack.push((start, self.indptr[start]));
+
+            while let Some((j, next)) = stack.last_mut() {
+                let j = *j;
+                let child = (*next..self.indptr[j + 1]).find(|&k| {
+                    let i = self.indices[k];
+                    i != j && (i > j) == lower && !marked[i]
+                });
+                match child {
+                    Some(k) => {
+                        *next = k + 1;
+                        let i = self.indices[k];
+                        marked[i] = true;
+                        stack.push((i, self.indptr[i]));
+                    }
+                    None => {
+                        reach.push(j);
+                        stack.pop();
+                    }
+                }
+            }
+        }
+
+        reach[first..].reverse();
+    }
+
+    /// Divide `x[j]` by the diagonal entry of column `j`, then subtract the column below (for
+    /// `lower`, above otherwise) the diagonal, scaled by `x[j]`, from `x`.
+    fn eliminate_column(&self, j: usize, lower: bool, x: &mut [T]) -> Result<(), SparseError> {
+        let (rows, values) = self.col(j);
+        let diag = match rows.binary_search(&j) {
+            Ok(k) if values[k] != T::zero() => values[k],
+            _ => return Err(SparseError::ZeroPivot { index: j }),
+        };
+
+        x[j] = x[j] / diag;
+        let xj = x[j];
+        for (&i, &v) in rows.iter().zip(values) {
+            if i != j && (i > j) == lower {
+                x[i] -= v * xj;
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that the matrix is square, that the operand has a component per row, and that no
+    /// column is empty, which only takes a look at `indptr`.
+    fn check_triangular_operand(&self, len: usize) -> Result<(), SparseError> {
+        for found in [self.nrows, len] {
+            if found != self.ncols {
+                return Err(SparseError::DimensionMismatch {
+                    expected: self.ncols,
+                    found,
+                });
+            }
+        }
+        match (0..self.ncols).find(|&j| self.indptr[j] == self.indptr[j + 1]) {
+            Some(index) => Err(SparseError::EmptyColumn { index }),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Sparse triangular solves with one matrix and many right-hand sides, such as the columns of
+/// a sparse matrix.
+///
+/// The work arrays are kept from one solve to the next and cleared along the reach only, so that
+/// after the O(n) allocation every solve costs its flops, as a single
+/// [`CscView::solve_lower_triangular_sparse`] does apart from its own allocation.
+pub(crate) struct SparseTriangularSolver<'a, T> {
+    view: CscView<'a, T>,
+    lower: bool,
+    marked: Vec<bool>,
+    x: Vec<T>,
+    stack: Vec<(usize, usize)>,
+    reach: Vec<usize>,
+}
+
+impl<T: Scalar> SparseTriangularSolver<'_, T> {
+    /// Solve with the right-hand side `b`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `b.full_len()` isn't the order of the matrix.
+    pub(crate) fn solve(&mut self, b: &PackedVec<T>) -> Result<PackedVec<T>, SparseError> {
+        let n = self.view.ncols;
+        assert_eq!(b.full_len(), n, "b has the wrong length");
+
+        self.reach.clear();
+        self.view.reach_into(
+            b.iter().map(|(i, _)| i),
+            self.lower,
+            &mut self.marked,
+            &mut self.stack,
+            &mut self.reach,
+        );
+        for (i, v) in b.iter() {
+            self.x[i] = v;
+        }
+        let eliminated = self
+            .reach
+            .iter()
+            .try_for_each(|&j| self.view.eliminate_column(j, self.lower, &mut self.x));
+        let x = eliminated
+            .and_then(|()| PackedVec::from_pairs(n, self.reach.iter().map(|&j| (j, self.x[j]))));
+
+        for &j in &self.reach {
+            self.x[j] = T::zero();
+            self.marked[j] = false;
+        }
+        x
+    }
+}
+
+impl CscView<'_, f64> {
+    /// Add `alpha` times column `j` to the dense vector `r`, `r ← r + α A e_j`, the residual
+    /// update of a coordinate descent step. O(entries of the column).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `j >= self.ncols()` or `r.len() != self.nrows()`.
+    pub fn col_axpy_into(&self, j: usize, alpha: f64, r: &mut [f64]) {
+        assert_eq!(r.len(), self.nrows, "r has the wrong length");
+        let (rows, values) = self.col(j);
+        f64::scatter_add(alpha, values, rows, r);
+    }
+
+    /// Return the dot product of column `j` with the dense vector `r`, `(A e_j)ᵀ r`, a
+    /// coordinate of the gradient `Aᵀ r`. O(entries of the column).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `j >= self.ncols()` or `r.len() != self.nrows()`.
+    pub fn col_dot_dense(&self, j: usize, r: &[f64]) -> f64 {
+        assert_eq!(r.len(), self.nrows, "r has the wrong length");
+        let (rows, values) = self.col(j);
+        rows.iter().zip(values).map(|(&i, &v)| v * r[i]).sum()
+    }
+}
+
+/// Two matrices are equal when they have the same shape and the same entries, with structural
+/// zeros compared as 0.0: an explicitly stored zero matches a missing entry.
+impl<T: Scalar> PartialEq for CscMatrix<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.all_union(other, |a, b| a == b)
+    }
+}
+
+/// Compared over the union of both patterns like [`PartialEq`].
+#[cfg(feature = "approx")]
+impl approx::AbsDiffEq for CscMatrix<f64> {
+    type Epsilon = f64;
+
+    fn default_epsilon() -> f64 {
+        f64::default_epsilon()
+    }
+
+    fn abs_diff_eq(&self, other: &Self, epsilon: f64) -> bool {
+        self.all_union(other, |a, b| a.abs_diff_eq(&b, epsilon))
+    }
+}
+
+#[cfg(feature = "approx")]
+impl approx::RelativeEq for CscMatrix<f64> {
+    fn default_max_relative() -> f64 {
+        f64::default_max_relative()
+    }
+
+    fn relative_eq(&self, other: &Self, epsilon: f64, max_relative: f64) -> bool {
+        self.all_union(other, |a, b| a.relative_eq(&b, epsilon, max_relative))
+    }
+}
+
+#[cfg(feature = "approx")]
+impl approx::UlpsEq for CscMatrix<f64> {
+    fn default_max_ulps() -> u32 {
+        f64::default_max_ulps()
+    }
+
+    fn ulps_eq(&self, other: &Self, epsilon: f64, max_ulps: u32) -> bool {
+        self.all_union(other, |a, b| a.ulps_eq(&b, epsilon, max_ulps))
+    }
+}
+
+/// Drawn like [`CsrMatrix`]: a dense grid when small, otherwise the entries column by column.
+impl<T: Scalar + core::fmt::Display> core::fmt::Display for CscMatrix<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let entries = (0..self.ncols).flat_map(|j| {
+            (self.indptr[j]..self.indptr[j + 1]).map(move |p| (self.indices[p], j, self.data[p]))
+        });
+        display::fmt_matrix(f, "CSC", self.shape(), self.nnz(), entries)
+    }
+}
+
+#[test]
+fn test_csc_matrix() {
+    #[rustfmt::skip]
+    let dense = vec![
+        1.0, 0.0, 0.0, 2.0,
+        0.0, 0.0, 0.0, 5.0,
+        0.0, 3.0, 4.0, 0.0,
+    ];
+
+    let a = CscMatrix::from_dense(3, 4, &dense).unwrap();
+    assert_eq!(a.shape(), (3, 4));
+    assert_eq!(a.nnz(), 5);
+    assert_eq!(a.indptr(), [0, 1, 2, 3, 5]);
+    assert_eq!(a.indices(), [0, 2, 2, 0, 1]);
+    assert_eq!(a.data(), [1.0, 3.0, 4.0, 2.0, 5.0]);
+    assert_eq!(a.col(3), (&[0, 1][..], &[2.0, 5.0][..]));
+    assert_eq!(a.get(1, 3), 5.0);
+    assert_eq!(a.get(1, 2), 0.0);
+    assert_eq!(a.to_dense(), dense);
+
+    // Round trips through the other compressed form.
+    let csr = a.to_csr();
+    assert_eq!(csr.indptr(), [0, 2, 3, 5]);
+    assert_eq!(csr.indices(), [0, 3, 3, 1, 2]);
+    assert_eq!(csr.to_dense(), dense);
+    let back = csr.to_csc();
+    assert_eq!(back.indptr(), a.indptr());
+    assert_eq!(back.indices(), a.indices());
+    assert_eq!(back.data(), a.data());
+
+    let t = a.transpose();
+    assert_eq!(t.shape(), (4, 3));
+    assert_eq!(t.to_csr().to_dense(), a.to_csr().transpose().to_dense());
+    assert_eq!(t.get(3, 1), 5.0);
+
+    assert!(!a.is_hermitian());
+    let symmetric = CscMatrix::from_dense(2, 2, &[1.0, 2.0, 2.0, 5.0]).unwrap();
+    assert!(symmetric.is_hermitian());
+    assert_eq!(symmetric.adjoint().to_dense(), symmetric.to_dense());
+
+    let empty = CsrMatrix::<f64>::new(3, 2).to_csc();
+    assert_eq!(empty.shape(), (3, 2));
+    assert_eq!(empty.indptr(), [0, 0, 0]);
+
+    assert_eq!(
+        CscMatrix::try_from_csc_data(2, 2, vec![0, 1, 2], vec![0, 2], vec![1.0, 1.0]).unwrap_err(),
+        SparseError::IndexOutOfBounds { index: 2, len: 2 }
+    );
+    assert!(matches!(
+        CscMatrix::try_from_csc_data(2, 2, vec![0, 2, 2], vec![1, 0], vec![1.0, 1.0]),
+        Err(SparseError::InvalidStructure(_))
+    ));
+}
+
+#[test]
+fn test_csc_triangular_solves() {
+    use crate::test_util::{assert_close, Lcg};
+
+    let n = 40;
+    let mut rng = Lcg::new(11);
+    let mut dense = rng.dense(n, n, 0.1);
+    for i in 0..n {
+        dense[i * n + i] = 4.0 + rng.uniform();
+    }
+    let a = CsrMatrix::from_dense(n, n, &dense).unwrap();
+    let mut lower = dense.clone();
+    let mut upper = dense.clone();
+    for i in 0..n {
+        for j in 0..n {
+            if j > i {
+                lower[i * n + j] = 0.0;
+            } else if j < i {
+                upper[i * n + j] = 0.0;
+            }
+        }
+    }
+    let l = CsrMatrix::from_dense(n, n, &lower).unwrap();
+    let u = CsrMatrix::from_dense(n, n, &upper).unwrap();
+
+    let b: Vec<f64> = (0..n).map(|_| rng.uniform()).collect();
+    for x in [
+        a.solve_lower_triangular(&b).unwrap(),
+        a.to_csc().solve_lower_triangular(&b).unwrap(),
+    ] {
+        assert_close(&l.mul_vec(&x), &b, 1e-12);
+    }
+    for x in [
+        a.solve_upper_triangular(&b).unwrap(),
+        a.to_csc().solve_upper_triangular(&b).unwrap(),
+    ] {
+        assert_close(&u.mul_vec(&x), &b, 1e-12);
+    }
+
+    // A sparse right-hand side gives the same solution, with only the reach stored.
+    let mut sparse_b = vec![0.0; n];
+    sparse_b[n - 5] = 1.0;
+    sparse_b[n - 2] = -2.0;
+    let packed_b = PackedVec::gather(&sparse_b);
+    let csc = a.to_csc();
+    let x = csc.solve_lower_triangular_sparse(&packed_b).unwrap();
+    assert!(x.iter().all(|(i, _)| i >= n - 5));
+    assert_close(
+        &x.scatter(),
+        &csc.solve_lower_triangular(&sparse_b).unwrap(),
+        1e-12,
+    );
+    let x = csc.solve_upper_triangular_sparse(&packed_b).unwrap();
+    assert_close(
+        &x.scatter(),
+        &csc.solve_upper_triangular(&sparse_b).unwrap(),
+        1e-12,
+    );
+}
+
+#[test]
+fn test_csc_triangular_reach_order() {
+    // L = I plus the edges 0 -> 2 -> 3 and 1 -> 3, so x[3] needs both x[1] and x[2] first.
+    #[rustfmt::skip]
+    let l = CsrMatrix::from_dense(4, 4, &[
+        1.0, 0.0, 0.0, 0.0,
+        0.0, 1.0, 0.0, 0.0,
+        1.0, 0.0, 1.0, 0.0,
+        0.0, 1.0, 1.0, 1.0,
+    ])
+    .unwrap()
+    .to_csc();
+    let b = PackedVec::gather(&[1.0, 1.0, 0.0, 0.0]);
+    assert_eq!(l.view().reach([0, 1].into_iter(), true), [1, 0, 2, 3]);
+    let x = l.solve_lower_triangular_sparse(&b).unwrap();
+    assert_eq!(x.scatter(), [1.0, 1.0, -1.0, 0.0]);
+    // x[3] cancels to zero and is not stored.
+    assert_eq!(x.len(), 3);
+
+    // The entry above the diagonal keeps row and column 1 from being empty.
+    let singular = CsrMatrix::from_dense(2, 2, &[1.0, 1.0, 1.0, 0.0]).unwrap();
+    assert_eq!(
+        singular.solve_lower_triangular(&[1.0, 1.0]),
+        Err(SparseError::ZeroPivot { index: 1 })
+    );
+    assert_eq!(
+        singular
+            .to_csc()
+            .solve_lower_triangular_sparse(&PackedVec::gather(&[1.0, 0.0])),
+        Err(SparseError::ZeroPivot { index: 1 })
+    );
+}
+
+#[test]
+fn test_csc_empty_rows_and_cols() {
+    use crate::csr::CsrMatrix;
+    use crate::vec::PackedVec;
+
+    #[rustfmt::skip]
+    let a = CsrMatrix::from_dense(4, 5, &[
+        1.0, 0.0, 0.0, 2.0, 0.0,
+        0.0, 0.0, 0.0, 0.0, 0.0,
+        3.0, 0.0, 0.0, 0.0, 0.0,
+        0.0, 0.0, 0.0, 0.0, 0.0,
+    ])
+    .unwrap();
+    let csc = a.to_csc();
+    assert_eq!(a.find_empty_rows(), [1, 3]);
+    assert_eq!(a.find_empty_cols(), [1, 2, 4]);
+    assert_eq!(csc.find_empty_rows(), [1, 3]);
+    assert_eq!(csc.find_empty_cols(), [1, 2, 4]);
+    assert!(CsrMatrix::<f64>::identity(3).find_empty_rows().is_empty());
+    assert!(CscMatrix::<f64>::new(2, 0).find_empty_cols().is_empty());
+
+    // The triangular solves fail on the empty row or column before looking at the pivots.
+    let singular =
+        CsrMatrix::from_dense(3, 3, &[1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0]).unwrap();
+    assert_eq!(
+        singular.solve_lower_triangular(&[1.0, 1.0, 1.0]),
+        Err(SparseError::EmptyRow { index: 2 })
+    );
+    assert_eq!(
+        singular.solve_upper_triangular(&[1.0, 1.0, 1.0]),
+        Err(SparseError::EmptyRow { index: 2 })
+    );
+    let singular = singular.to_csc();
+    assert_eq!(
+        singular.solve_lower_triangular(&[1.0, 1.0, 1.0]),
+        Err(SparseError::EmptyColumn { index: 1 })
+    );
+    assert_eq!(
+        singular.solve_upper_triangular(&[1.0, 1.0, 1.0]),
+        Err(SparseError::EmptyColumn { index: 1 })
+    );
+    assert_eq!(
+        singular.solve_lower_triangular_sparse(&PackedVec::gather(&[1.0, 0.0, 0.0])),
+        Err(SparseError::EmptyColumn { index: 1 })
+    );
+}
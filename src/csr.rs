@@ -0,0 +1,2429 @@
+use alloc::format;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::compressed::{
+    all_union_compressed, empty_inner, empty_outer, frobenius, max_inner_abs_sum,
+    max_outer_abs_sum, retain_compressed, transpose_compressed, validate_compressed,
+    CompressedParts,
+};
+use crate::coo::{CooMatrix, DuplicatePolicy};
+use crate::csc::{CscMatrix, CscView};
+use crate::dense::{dot, norm2, sqrt};
+use crate::display;
+use crate::error::SparseError;
+use crate::index::{convert_indices, IndexType};
+use crate::iterative::SolverOptions;
+use crate::ops::merge::{self, UnionStep};
+use crate::scalar::Scalar;
+use crate::vec::PackedVec;
+
+/// The relative tolerance within which [`CsrMatrix::as_csc_view_symmetric`] takes mirrored
+/// entries as equal.
+pub const SYMMETRY_TOL: f64 = 1e-12;
+
+/// A sparse matrix in compressed sparse row (CSR) form.
+///
+/// The matrix is stored row by row, each row as a packed vector: the column indices and values
+/// of row `i` are `indices[indptr[i]..indptr[i + 1]]` and `data[indptr[i]..indptr[i + 1]]`. The
+/// column indices of a row are kept sorted, so two rows can be combined with a sorted merge as
+/// the packed vectors are.
+///
+/// The indices are usize unless narrowed with [`CsrMatrix::to_index_type`]; the [`index`]
+/// module lists what a matrix with other indices supports.
+///
+/// [`index`]: crate::index
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(
+        try_from = "RawCsrMatrix<T, I>",
+        bound(deserialize = "T: Scalar + serde::Deserialize<'de>, \
+                             I: IndexType + serde::Deserialize<'de>")
+    )
+)]
+pub struct CsrMatrix<T = f64, I = usize> {
+    nrows: usize,
+    ncols: usize,
+    /// Start of each row in `indices` and `data`, plus the total number of entries at the end
+    indptr: Vec<I>,
+    /// Column index of each stored entry
+    indices: Vec<I>,
+    /// Value of each stored entry
+    data: Vec<T>,
+}
+
+/// The fields of a [`CsrMatrix`] as deserialized, before they are checked.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct RawCsrMatrix<T, I> {
+    nrows: usize,
+    ncols: usize,
+    indptr: Vec<I>,
+    indices: Vec<I>,
+    data: Vec<T>,
+}
+
+#[cfg(feature = "serde")]
+impl<T: Scalar, I: IndexType> TryFrom<RawCsrMatrix<T, I>> for CsrMatrix<T, I> {
+    type Error = SparseError;
+
+    fn try_from(raw: RawCsrMatrix<T, I>) -> Result<Self, SparseError> {
+        let (nrows, ncols) = (raw.nrows, raw.ncols);
+        validate_compressed(
+            nrows,
+            ncols,
+            &raw.indptr,
+            &raw.indices,
+            raw.data.len(),
+            "row",
+        )?;
+
+        Ok(Self {
+            nrows,
+            ncols,
+            indptr: raw.indptr,
+            indices: raw.indices,
+            data: raw.data,
+        })
+    }
+}
+
+impl<T: Scalar> CsrMatrix<T> {
+    /// Create a `nrows` by `ncols` matrix with no stored entries.
+    pub fn new(nrows: usize, ncols: usize) -> Self {
+        Self {
+            nrows,
+            ncols,
+            indptr: vec![0; nrows + 1],
+            indices: Vec::new(),
+            data: Vec::new(),
+        }
+    }
+
+    /// Build a matrix from its raw CSR arrays, checking that they describe a valid matrix:
+    /// `indptr` has `nrows + 1` non-decreasing entries from 0 to `indices.len()`, `indices` and
+    /// `data` have the same length, and the column indices of each row are strictly increasing
+    /// and less than `ncols`.
+    pub fn try_from_csr_data(
+        nrows: usize,
+        ncols: usize,
+        indptr: Vec<usize>,
+        indices: Vec<usize>,
+        data: Vec<T>,
+    ) -> Result<Self, SparseError> {
+        validate_compressed(nrows, ncols, &indptr, &indices, data.len(), "row")?;
+
+        Ok(Self {
+            nrows,
+            ncols,
+            indptr,
+            indices,
+            data,
+        })
+    }
+
+    /// Gather a row-major dense `nrows` by `ncols` array into a CSR matrix.
+    pub fn from_dense(nrows: usize, ncols: usize, dense: &[T]) -> Result<Self, SparseError> {
+        if dense.len() != nrows * ncols {
+            return Err(SparseError::DimensionMismatch {
+                expected: nrows * ncols,
+                found: dense.len(),
+            });
+        }
+
+        let mut matrix = Self::new(nrows, ncols);
+        for (i, row) in dense.chunks(ncols.max(1)).take(nrows).enumerate() {
+            for (j, &v) in row.iter().enumerate() {
+                if v != T::zero() {
+                    matrix.indices.push(j);
+                    matrix.data.push(v);
+                }
+            }
+            matrix.indptr[i + 1] = matrix.indices.len();
+        }
+
+        Ok(matrix)
+    }
+
+    /// Return the `n` by `n` identity matrix.
+    pub fn identity(n: usize) -> Self {
+        Self::from_diagonal(&vec![T::one(); n])
+    }
+
+    /// Return the square matrix with `diag` on its diagonal. Zeros in `diag` are not stored.
+    pub fn from_diagonal(diag: &[T]) -> Self {
+        let n = diag.len();
+        let mut matrix = Self::new(n, n);
+        for (i, &v) in diag.iter().enumerate() {
+            if v != T::zero() {
+                matrix.indices.push(i);
+                matrix.data.push(v);
+            }
+            matrix.indptr[i + 1] = matrix.indices.len();
+        }
+        matrix
+    }
+
+    /// Return the `n` by `n` tridiagonal Toeplitz matrix with `a` below the diagonal, `b` on it
+    /// and `c` above it. A zero band is not stored.
+    pub fn tridiagonal(a: T, b: T, c: T, n: usize) -> Self {
+        let mut matrix = Self::new(n, n);
+        for i in 0..n {
+            let band = [(i.wrapping_sub(1), a), (i, b), (i + 1, c)];
+            for (j, v) in band {
+                if j < n && v != T::zero() {
+                    matrix.indices.push(j);
+                    matrix.data.push(v);
+                }
+            }
+            matrix.indptr[i + 1] = matrix.indices.len();
+        }
+        matrix
+    }
+}
+
+/// The accessors and products, for any index type.
+impl<T: Scalar, I: IndexType> CsrMatrix<T, I> {
+    /// Return the number of rows
+    pub fn nrows(&self) -> usize {
+        self.nrows
+    }
+
+    /// Return the number of columns
+    pub fn ncols(&self) -> usize {
+        self.ncols
+    }
+
+    /// Return `(nrows, ncols)`
+    pub fn shape(&self) -> (usize, usize) {
+        (self.nrows, self.ncols)
+    }
+
+    /// Return the number of stored entries
+    pub fn nnz(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Return the row pointer array
+    pub fn indptr(&self) -> &[I] {
+        &self.indptr
+    }
+
+    /// Return the column index of every stored entry, row after row
+    pub fn indices(&self) -> &[I] {
+        &self.indices
+    }
+
+    /// Return the value of every stored entry, row after row
+    pub fn data(&self) -> &[T] {
+        &self.data
+    }
+
+    /// Return the sorted column indices and the values of row `i`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i >= self.nrows()`.
+    pub fn row(&self, i: usize) -> (&[I], &[T]) {
+        let range = self.indptr[i].index()..self.indptr[i + 1].index();
+        (&self.indices[range.clone()], &self.data[range])
+    }
+
+    /// Multiply the matrix by the dense vector `x`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `x.len() != self.ncols()`.
+    pub fn mul_vec(&self, x: &[T]) -> Vec<T> {
+        let mut y = vec![T::zero(); self.nrows];
+        self.mul_vec_into(x, &mut y);
+        y
+    }
+
+    /// Multiply the matrix by the dense vector `x`, writing the product into `y` without
+    /// allocating. Each entry of `y` is the inner product of a packed row with `x`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `x.len() != self.ncols()` or `y.len() != self.nrows()`.
+    pub fn mul_vec_into(&self, x: &[T], y: &mut [T]) {
+        assert_eq!(x.len(), self.ncols, "x has the wrong length");
+        assert_eq!(y.len(), self.nrows, "y has the wrong length");
+        I::mul_vec_rows(&self.indptr, &self.indices, &self.data, x, y);
+    }
+
+    /// Multiply the transpose of the matrix by the dense vector `x`, `Aᵀ x`, without forming
+    /// `Aᵀ`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `x.len() != self.nrows()`.
+    pub fn mul_vec_transposed(&self, x: &[T]) -> Vec<T> {
+        let mut y = vec![T::zero(); self.ncols];
+        self.mul_vec_transposed_into(x, &mut y);
+        y
+    }
+
+    /// Multiply the transpose of the matrix by `x`, writing `Aᵀ x` into `y` without allocating.
+    /// Row `i` is scattered into `y` scaled by `x[i]`, so the rows are read in order as in
+    /// [`CsrMatrix::mul_vec_into`] but `y` is written at random. No conjugation happens for
+    /// complex types. O(nnz + ncols).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `x.len() != self.nrows()` or `y.len() != self.ncols()`.
+    pub fn mul_vec_transposed_into(&self, x: &[T], y: &mut [T]) {
+        assert_eq!(x.len(), self.nrows, "x has the wrong length");
+        assert_eq!(y.len(), self.ncols, "y has the wrong length");
+
+        y.fill(T::zero());
+        for (i, &xi) in x.iter().enumerate() {
+            let row = self.indptr[i].index()..self.indptr[i + 1].index();
+            I::scatter_add(xi, &self.data[row.clone()], &self.indices[row], y);
+        }
+    }
+
+    /// Convert the indices to another [`IndexType`], such as u32 to halve their storage.
+    /// Fails with [`SparseError::IndexOutOfBounds`] when a column index or the number of
+    /// stored entries doesn't fit in `J`. O(nnz + nrows).
+    pub fn to_index_type<J: IndexType>(&self) -> Result<CsrMatrix<T, J>, SparseError> {
+        Ok(CsrMatrix {
+            nrows: self.nrows,
+            ncols: self.ncols,
+            indptr: convert_indices(&self.indptr)?,
+            indices: convert_indices(&self.indices)?,
+            data: self.data.clone(),
+        })
+    }
+}
+
+impl<T: Scalar + PartialOrd> CsrMatrix<T> {
+    /// Compress a COO matrix, summing duplicated positions. Same as [`CooMatrix::to_csr`].
+    pub fn from_coo(coo: &CooMatrix<T>) -> Self {
+        coo.to_csr()
+    }
+
+    /// Compress a COO matrix, combining duplicated positions by `policy`, see
+    /// [`CooMatrix::to_csr_with`]. Fails only with [`DuplicatePolicy::Error`].
+    pub fn from_coo_with(coo: &CooMatrix<T>, policy: DuplicatePolicy) -> Result<Self, SparseError> {
+        coo.to_csr_with(policy)
+    }
+}
+
+impl<T: Scalar> CsrMatrix<T> {
+    /// Return a view of row `i`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i >= self.nrows()`.
+    pub fn row_view(&self, i: usize) -> CsrRow<'_, T> {
+        let (indices, data) = self.row(i);
+        CsrRow {
+            index: i,
+            ncols: self.ncols,
+            indices,
+            data,
+        }
+    }
+
+    /// Iterate over views of the rows, in order. The iterator runs backwards too.
+    pub fn row_iter(&self) -> impl DoubleEndedIterator<Item = CsrRow<'_, T>> + ExactSizeIterator {
+        (0..self.nrows).map(|i| self.row_view(i))
+    }
+
+    /// Return the entry at row `i` and column `j`, 0 when it is not stored.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` or `j` is out of bounds.
+    pub fn get(&self, i: usize, j: usize) -> T {
+        assert!(
+            j < self.ncols,
+            "column {j} out of bounds for {} columns",
+            self.ncols
+        );
+        let (cols, values) = self.row(i);
+        match cols.binary_search(&j) {
+            Ok(k) => values[k],
+            Err(_) => T::zero(),
+        }
+    }
+
+    /// Return the submatrix of the rows in `rows` and the columns in `cols`, renumbered from 0.
+    /// Each row's range of columns is found by binary search, so this is O(rows.len() × log
+    /// (row length) + nnz of the result).
+    ///
+    /// # Panics
+    ///
+    /// Panics if a range is decreasing or reaches past the end of the matrix.
+    pub fn slice(&self, rows: Range<usize>, cols: Range<usize>) -> CsrMatrix<T> {
+        assert!(
+            rows.start <= rows.end && rows.end <= self.nrows,
+            "row range {rows:?} out of bounds for {} rows",
+            self.nrows
+        );
+        assert!(
+            cols.start <= cols.end && cols.end <= self.ncols,
+            "column range {cols:?} out of bounds for {} columns",
+            self.ncols
+        );
+
+        let mut indptr = Vec::with_capacity(rows.len() + 1);
+        let mut indices = Vec::new();
+        let mut data = Vec::new();
+        indptr.push(0);
+        for i in rows.clone() {
+            let (row_cols, values) = self.row(i);
+            let start = row_cols.partition_point(|&j| j < cols.start);
+            let end = row_cols.partition_point(|&j| j < cols.end);
+            indices.extend(row_cols[start..end].iter().map(|&j| j - cols.start));
+            data.extend_from_slice(&values[start..end]);
+            indptr.push(indices.len());
+        }
+
+        CsrMatrix::from_parts(rows.len(), cols.len(), indptr, indices, data)
+    }
+
+    /// Copy the block of rows `rows` and columns `cols` into a row-major dense array of
+    /// `rows.len() * cols.len()` entries, for coupling a small dense block to the sparse
+    /// operator. O(rows.len() * (cols.len() + log(row length))).
+    ///
+    /// # Panics
+    ///
+    /// Panics when a range is decreasing or reaches past the matrix, as [`slice`](Self::slice)
+    /// does.
+    pub fn dense_block(&self, rows: Range<usize>, cols: Range<usize>) -> Vec<T> {
+        assert!(
+            rows.start <= rows.end && rows.end <= self.nrows,
+            "row range {rows:?} out of bounds for {} rows",
+            self.nrows
+        );
+        assert!(
+            cols.start <= cols.end && cols.end <= self.ncols,
+            "column range {cols:?} out of bounds for {} columns",
+            self.ncols
+        );
+
+        let width = cols.len();
+        let mut dense = vec![T::zero(); rows.len() * width];
+        for (block_row, i) in dense.chunks_exact_mut(width.max(1)).zip(rows) {
+            let (row_cols, values) = self.row(i);
+            let start = row_cols.partition_point(|&j| j < cols.start);
+            let end = row_cols.partition_point(|&j| j < cols.end);
+            for (&j, &v) in row_cols[start..end].iter().zip(&values[start..end]) {
+                block_row[j - cols.start] = v;
+            }
+        }
+        dense
+    }
+
+    /// Return the main diagonal as a packed vector of length `min(nrows, ncols)`, leaving out
+    /// the zeros. O(n log(row length)).
+    pub fn diagonal(&self) -> PackedVec<T> {
+        let n = self.nrows.min(self.ncols);
+        PackedVec::from_pairs(n, (0..n).map(|i| (i, self.get(i, i))))
+            .expect("diagonal indices are in bounds")
+    }
+
+    /// Return the sum of the main diagonal. O(n log(row length)).
+    pub fn trace(&self) -> T {
+        let mut sum = T::zero();
+        for i in 0..self.nrows.min(self.ncols) {
+            sum += self.get(i, i);
+        }
+        sum
+    }
+
+    /// Overwrite the main diagonal with `diag`, inserting the entries that aren't stored yet.
+    /// A zero overwrites a stored entry but isn't inserted. O(nnz + n).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `diag.len() != min(nrows, ncols)`.
+    pub fn set_diagonal(&mut self, diag: &[T]) {
+        assert_eq!(
+            diag.len(),
+            self.nrows.min(self.ncols),
+            "diag has the wrong length"
+        );
+        self.update_diagonal(|i, _| diag[i]);
+    }
+
+    /// Add `alpha` to every entry of the main diagonal, `A + αI`, inserting the entries that
+    /// aren't stored yet. Shifting a matrix this way regularizes it or moves its spectrum.
+    /// O(nnz + n).
+    pub fn add_to_diagonal(&mut self, alpha: T) {
+        if alpha != T::zero() {
+            self.update_diagonal(|_, v| v + alpha);
+        }
+    }
+
+    /// Multiply row `i` by `d[i]`, which is `D A` for the diagonal matrix `D` of `d`. O(nnz).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `d.len() != self.nrows()`.
+    pub fn scale_rows(&mut self, d: &[T]) {
+        assert_eq!(d.len(), self.nrows, "d has the wrong length");
+        for (i, &di) in d.iter().enumerate() {
+            for v in &mut self.data[self.indptr[i]..self.indptr[i + 1]] {
+                *v *= di;
+            }
+        }
+    }
+
+    /// Multiply column `j` by `d[j]`, which is `A D` for the diagonal matrix `D` of `d`. O(nnz).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `d.len() != self.ncols()`.
+    pub fn scale_cols(&mut self, d: &[T]) {
+        assert_eq!(d.len(), self.ncols, "d has the wrong length");
+        for (v, &j) in self.data.iter_mut().zip(&self.indices) {
+            *v *= d[j];
+        }
+    }
+
+    /// Scale both sides by the same diagonal, `D A D`, which keeps a symmetric matrix
+    /// symmetric. O(nnz).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the matrix isn't square or `d` has the wrong length.
+    pub fn scale_symmetric(&mut self, d: &[T]) {
+        assert_eq!(self.nrows, self.ncols, "the matrix isn't square");
+        self.scale_rows(d);
+        self.scale_cols(d);
+    }
+
+    /// Replace every diagonal entry `v` at `(i, i)` with `f(i, v)`, rebuilding the arrays to
+    /// insert the missing ones for which `f(i, 0)` is nonzero.
+    fn update_diagonal(&mut self, f: impl Fn(usize, T) -> T) {
+        let n = self.nrows.min(self.ncols);
+        let mut indptr = Vec::with_capacity(self.nrows + 1);
+        let mut indices = Vec::with_capacity(self.indices.len() + n);
+        let mut data = Vec::with_capacity(self.data.len() + n);
+        indptr.push(0);
+        for i in 0..self.nrows {
+            let (cols, values) = self.row(i);
+            let k = cols.partition_point(|&j| j < i);
+            indices.extend_from_slice(&cols[..k]);
+            data.extend_from_slice(&values[..k]);
+            let rest = if i >= n {
+                k
+            } else if cols.get(k) == Some(&i) {
+                indices.push(i);
+                data.push(f(i, values[k]));
+                k + 1
+            } else {
+                let v = f(i, T::zero());
+                if v != T::zero() {
+                    indices.push(i);
+                    data.push(v);
+                }
+                k
+            };
+            indices.extend_from_slice(&cols[rest..]);
+            data.extend_from_slice(&values[rest..]);
+            indptr.push(indices.len());
+        }
+
+        self.indptr = indptr;
+        self.indices = indices;
+        self.data = data;
+    }
+
+    /// Return the submatrix of the given rows and columns, in the given order: entry `(r, c)`
+    /// of the result is entry `(rows[r], cols[c])` of this matrix. Indices may repeat, which
+    /// copies the row or column. O(ncols + len(cols) + nnz of the selected rows + s log s) for
+    /// `s` the longest row of the result.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an index is out of bounds.
+    pub fn select(&self, rows: &[usize], cols: &[usize]) -> CsrMatrix<T> {
+        // The new positions of old column j are `targets[target_ptr[j]..target_ptr[j + 1]]`.
+        let mut target_ptr = vec![0; self.ncols + 1];
+        for &j in cols {
+            assert!(
+                j < self.ncols,
+                "column {j} out of bounds for {} columns",
+                self.ncols
+            );
+            target_ptr[j + 1] += 1;
+        }
+        for j in 0..self.ncols {
+            target_ptr[j + 1] += target_ptr[j];
+        }
+        let mut next = target_ptr.clone();
+        let mut targets = vec![0; cols.len()];
+        for (c, &j) in cols.iter().enumerate() {
+            targets[next[j]] = c;
+            next[j] += 1;
+        }
+
+        let mut indptr = Vec::with_capacity(rows.len() + 1);
+        let mut indices = Vec::new();
+        let mut data = Vec::new();
+        let mut row_entries: Vec<(usize, T)> = Vec::new();
+        indptr.push(0);
+        for &i in rows {
+            assert!(
+                i < self.nrows,
+                "row {i} out of bounds for {} rows",
+                self.nrows
+            );
+            let (row_cols, values) = self.row(i);
+            row_entries.clear();
+            for (&j, &v) in row_cols.iter().zip(values) {
+                for &c in &targets[target_ptr[j]..target_ptr[j + 1]] {
+                    row_entries.push((c, v));
+                }
+            }
+            row_entries.sort_unstable_by_key(|&(c, _)| c);
+            for &(c, v) in &row_entries {
+                indices.push(c);
+                data.push(v);
+            }
+            indptr.push(indices.len());
+        }
+
+        CsrMatrix::from_parts(rows.len(), cols.len(), indptr, indices, data)
+    }
+
+    /// Return the rows with no stored entries, in increasing order. O(nrows).
+    pub fn find_empty_rows(&self) -> Vec<usize> {
+        empty_outer(&self.indptr)
+    }
+
+    /// Return the columns with no stored entries, in increasing order. O(nnz + ncols).
+    pub fn find_empty_cols(&self) -> Vec<usize> {
+        empty_inner(self.ncols, &self.indices)
+    }
+
+    /// Solve `L x = b` by forward substitution, where `L` is the lower triangle of this square
+    /// matrix, diagonal included. Entries above the diagonal are ignored, so the lower triangle
+    /// of any matrix can be solved with in place. O(nnz + n).
+    ///
+    /// Fails with [`SparseError::EmptyRow`] before any arithmetic when a row has no stored
+    /// entries, and with [`SparseError::ZeroPivot`] when a diagonal entry is zero or not
+    /// stored.
+    pub fn solve_lower_triangular(&self, b: &[T]) -> Result<Vec<T>, SparseError> {
+        self.solve_triangular(b, true)
+    }
+
+    /// Solve `U x = b` by backward substitution, where `U` is the upper triangle of this square
+    /// matrix, diagonal included. Entries below the diagonal are ignored. O(nnz + n).
+    ///
+    /// Fails as [`solve_lower_triangular`](Self::solve_lower_triangular) does.
+    pub fn solve_upper_triangular(&self, b: &[T]) -> Result<Vec<T>, SparseError> {
+        self.solve_triangular(b, false)
+    }
+
+    /// Row-oriented substitution: `x[i]` is computed from the entries of row `i` that hold
+    /// already computed components, in increasing row order for `lower`, decreasing otherwise.
+    fn solve_triangular(&self, b: &[T], lower: bool) -> Result<Vec<T>, SparseError> {
+        let n = self.nrows;
+        for len in [self.ncols, b.len()] {
+            if len != n {
+                return Err(SparseError::DimensionMismatch {
+                    expected: n,
+                    found: len,
+                });
+            }
+        }
+        if let Some(&index) = self.find_empty_rows().first() {
+            return Err(SparseError::EmptyRow { index });
+        }
+
+        let mut x = b.to_vec();
+        for step in 0..n {
+            let i = if lower { step } else { n - 1 - step };
+            let mut sum = x[i];
+            let mut diag = T::zero();
+            for k in self.indptr[i]..self.indptr[i + 1] {
+                let j = self.indices[k];
+                if j == i {
+                    diag = self.data[k];
+                } else if (j < i) == lower {
+                    sum -= self.data[k] * x[j];
+                }
+            }
+            if diag == T::zero() {
+                return Err(SparseError::ZeroPivot { index: i });
+            }
+            x[i] = sum / diag;
+        }
+
+        Ok(x)
+    }
+
+    /// Multiply two sparse matrices, `self * rhs`, with Gustavson's row-by-row algorithm.
+    ///
+    /// Row `i` of the product is the sum of the rows `k` of `rhs` scaled by `self[i][k]`, built
+    /// in a full-length sparse accumulator. A symbolic pass first counts the entries of every
+    /// output row, so the output arrays are allocated once at their final size. Entries that
+    /// cancel numerically stay stored.
+    pub fn matmul(&self, rhs: &CsrMatrix<T>) -> Result<CsrMatrix<T>, SparseError> {
+        self.matmul_with(rhs, usize::MAX, |acc, a, b| Some(acc + a * b))
+    }
+
+    /// [`matmul`](Self::matmul) with the update `acc + a b` of an entry done by `mul_add`,
+    /// which returns `None` on overflow, and failing with [`SparseError::TooManyEntries`]
+    /// before the numeric pass when the product would store more than `max_nnz` entries.
+    fn matmul_with(
+        &self,
+        rhs: &CsrMatrix<T>,
+        max_nnz: usize,
+        mul_add: impl Fn(T, T, T) -> Option<T>,
+    ) -> Result<CsrMatrix<T>, SparseError> {
+        if self.ncols != rhs.nrows {
+            return Err(SparseError::DimensionMismatch {
+                expected: self.ncols,
+                found: rhs.nrows,
+            });
+        }
+
+        // #1: Symbolic pass. `mark[j] == i` flags column j as already counted in row i.
+        let mut mark = vec![usize::MAX; rhs.ncols];
+        let mut indptr = vec![0; self.nrows + 1];
+        for i in 0..self.nrows {
+            let mut row_nnz = 0;
+            for &k in &self.indices[self.indptr[i]..self.indptr[i + 1]] {
+                for &j in &rhs.indices[rhs.indptr[k]..rhs.indptr[k + 1]] {
+                    if mark[j] != i {
+                        mark[j] = i;
+                        row_nnz += 1;
+                    }
+                }
+            }
+            indptr[i + 1] = indptr[i] + row_nnz;
+        }
+        let nnz = indptr[self.nrows];
+        if nnz > max_nnz {
+            return Err(SparseError::TooManyEntries {
+                nnz,
+                limit: max_nnz,
+            });
+        }
+
+        // #2: Numeric pass, accumulating each row in `acc` and sorting its column indices.
+        let mut indices = Vec::with_capacity(nnz);
+        let mut data = Vec::with_capacity(nnz);
+        let mut acc = vec![T::zero(); rhs.ncols];
+        mark.fill(usize::MAX);
+        for i in 0..self.nrows {
+            let row_start = indices.len();
+            for ka in self.indptr[i]..self.indptr[i + 1] {
+                let k = self.indices[ka];
+                let a_ik = self.data[ka];
+                for kb in rhs.indptr[k]..rhs.indptr[k + 1] {
+                    let j = rhs.indices[kb];
+                    if mark[j] != i {
+                        mark[j] = i;
+                        acc[j] = T::zero();
+                        indices.push(j);
+                    }
+                    acc[j] = mul_add(acc[j], a_ik, rhs.data[kb])
+                        .ok_or(SparseError::Overflow { row: i, col: j })?;
+                }
+            }
+
+            indices[row_start..].sort_unstable();
+            data.extend(indices[row_start..].iter().map(|&j| acc[j]));
+        }
+
+        Ok(CsrMatrix {
+            nrows: self.nrows,
+            ncols: rhs.ncols,
+            indptr,
+            indices,
+            data,
+        })
+    }
+
+    /// Raise a square matrix to the power `k` by repeated squaring, with the products done by
+    /// [`matmul_with`](Self::matmul_with), in O(log k) products.
+    fn pow_with(
+        &self,
+        k: usize,
+        max_nnz: usize,
+        mul_add: impl Fn(T, T, T) -> Option<T> + Copy,
+    ) -> Result<CsrMatrix<T>, SparseError> {
+        if self.nrows != self.ncols {
+            return Err(SparseError::DimensionMismatch {
+                expected: self.nrows,
+                found: self.ncols,
+            });
+        }
+
+        let mut result = CsrMatrix::identity(self.nrows);
+        let mut square = self.clone();
+        let mut k = k;
+        while k > 0 {
+            if k & 1 == 1 {
+                result = result.matmul_with(&square, max_nnz, mul_add)?;
+            }
+            k >>= 1;
+            if k > 0 {
+                square = square.matmul_with(&square, max_nnz, mul_add)?;
+            }
+        }
+        Ok(result)
+    }
+
+    /// Return the transpose in CSR form, in O(nnz + ncols).
+    ///
+    /// The entries are counted per column to size the rows of the transpose, then placed in one
+    /// pass over the rows, so no dense storage or sorting is involved.
+    pub fn transpose(&self) -> CsrMatrix<T> {
+        let (indptr, indices, data) =
+            transpose_compressed(self.ncols, &self.indptr, &self.indices, &self.data);
+        CsrMatrix {
+            nrows: self.ncols,
+            ncols: self.nrows,
+            indptr,
+            indices,
+            data,
+        }
+    }
+
+    /// Return the conjugate transpose `Aᴴ`, which is the transpose for real types.
+    pub fn adjoint(&self) -> CsrMatrix<T> {
+        let mut t = self.transpose();
+        for v in &mut t.data {
+            *v = v.conj();
+        }
+        t
+    }
+
+    /// Return true if the matrix equals its conjugate transpose (for real types: is symmetric),
+    /// comparing values exactly.
+    pub fn is_hermitian(&self) -> bool {
+        if self.nrows != self.ncols {
+            return false;
+        }
+
+        // The adjoint has sorted indices too, so equal matrices have identical arrays.
+        let h = self.adjoint();
+        h.indptr == self.indptr && h.indices == self.indices && h.data == self.data
+    }
+
+    /// Return the symmetric part `(A + Aᵀ) / 2` of a square matrix.
+    ///
+    /// One pass merges every row of `A` with the same row of `Aᵀ`, which the counting transpose
+    /// lays out without sorting. The two halves of an off-diagonal pair are computed from the
+    /// same two values, so the result is symmetric exactly. The pattern is the union of those of
+    /// `A` and `Aᵀ`. O(nnz + n).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the matrix is not square.
+    pub fn symmetric_part(&self) -> CsrMatrix<T> {
+        self.merge_with_transpose(|a, t| a + t)
+    }
+
+    /// Return the antisymmetric part `(A − Aᵀ) / 2` of a square matrix, computed as
+    /// [`symmetric_part`](Self::symmetric_part) is. The result is antisymmetric exactly, and
+    /// adds up with the symmetric part to `A` up to rounding. It has the same pattern as the
+    /// symmetric part, so the diagonal and the symmetric pairs of `A` are stored as zeros.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the matrix is not square.
+    pub fn antisymmetric_part(&self) -> CsrMatrix<T> {
+        self.merge_with_transpose(|a, t| a - t)
+    }
+
+    /// Replace the matrix with its symmetric part in place, when the pattern is structurally
+    /// symmetric already so that no entry needs to be added. O(nnz + n).
+    ///
+    /// Fails with [`SparseError::DimensionMismatch`] when the matrix is not square, and with
+    /// [`SparseError::InvalidStructure`] naming a stored entry whose mirror is not stored,
+    /// leaving the matrix untouched.
+    pub fn symmetrize_in_place(&mut self) -> Result<(), SparseError> {
+        if self.nrows != self.ncols {
+            return Err(SparseError::DimensionMismatch {
+                expected: self.nrows,
+                found: self.ncols,
+            });
+        }
+
+        // With the same pattern, position p holds a_ij here and a_ji in the transpose.
+        let t = transpose_compressed(self.ncols, &self.indptr, &self.indices, &self.data);
+        if let Some((row, col)) = self.find_unmirrored(&t, |a, t| a.is_some() && t.is_some()) {
+            return Err(SparseError::InvalidStructure(format!(
+                "entry ({row}, {col}) is stored but ({col}, {row}) is not"
+            )));
+        }
+
+        let two = T::one() + T::one();
+        for (v, t) in self.data.iter_mut().zip(t.2) {
+            *v = (*v + t) / two;
+        }
+        Ok(())
+    }
+
+    /// Reinterpret the arrays as the CSC form of the transpose, which is the matrix itself when
+    /// it is symmetric, without checking anything. See
+    /// [`as_csc_view_symmetric`](CsrMatrix::as_csc_view_symmetric). O(1).
+    pub fn as_csc_view_symmetric_unchecked(&self) -> CscView<'_, T> {
+        CscView::from_parts(
+            self.ncols,
+            self.nrows,
+            &self.indptr,
+            &self.indices,
+            &self.data,
+        )
+    }
+
+    /// Return the first stored entry `(i, j)`, row by row, for which `mirrored(a_ij, a_ji)` is
+    /// false, where `t` is the transpose and `None` stands for an entry that is not stored.
+    fn find_unmirrored(
+        &self,
+        (t_indptr, t_indices, t_data): &CompressedParts<T>,
+        mirrored: impl Fn(Option<T>, Option<T>) -> bool,
+    ) -> Option<(usize, usize)> {
+        let mut unmirrored = None;
+        (0..self.nrows).all(|i| {
+            let (cols, values) = self.row(i);
+            let t_row = t_indptr[i]..t_indptr[i + 1];
+            let (t_cols, t_values) = (&t_indices[t_row.clone()], &t_data[t_row]);
+            merge::all_union(cols, t_cols, |step| {
+                unmirrored = match step {
+                    UnionStep::Left(run) => run
+                        .into_iter()
+                        .find(|&p| !mirrored(Some(values[p]), None))
+                        .map(|p| (i, cols[p])),
+                    UnionStep::Right(run) => run
+                        .into_iter()
+                        .find(|&q| !mirrored(None, Some(t_values[q])))
+                        .map(|q| (t_cols[q], i)),
+                    UnionStep::Both(p, q) => {
+                        (!mirrored(Some(values[p]), Some(t_values[q]))).then_some((i, cols[p]))
+                    }
+                };
+                unmirrored.is_none()
+            })
+        });
+        unmirrored
+    }
+
+    /// Return `op(A, Aᵀ) / 2` over the union of their patterns, where `op` is a sum or a
+    /// difference. A position stored in only one of them passes 0 for the other.
+    fn merge_with_transpose(&self, op: impl Fn(T, T) -> T) -> CsrMatrix<T> {
+        assert_eq!(self.nrows, self.ncols, "the matrix is not square");
+        let n = self.nrows;
+        let (t_indptr, t_indices, t_data) =
+            transpose_compressed(n, &self.indptr, &self.indices, &self.data);
+
+        let two = T::one() + T::one();
+        let mut indptr = Vec::with_capacity(n + 1);
+        let mut indices = Vec::with_capacity(self.nnz());
+        let mut data = Vec::with_capacity(self.nnz());
+        indptr.push(0);
+        for i in 0..n {
+            let (cols, values) = self.row(i);
+            let t_row = t_indptr[i]..t_indptr[i + 1];
+            let (t_cols, t_values) = (&t_indices[t_row.clone()], &t_data[t_row]);
+            merge::all_union(cols, t_cols, |step| {
+                match step {
+                    UnionStep::Left(run) => {
+                        indices.extend_from_slice(&cols[run.clone()]);
+                        data.extend(values[run].iter().map(|&a| op(a, T::zero()) / two));
+                    }
+                    UnionStep::Right(run) => {
+                        indices.extend_from_slice(&t_cols[run.clone()]);
+                        data.extend(t_values[run].iter().map(|&t| op(T::zero(), t) / two));
+                    }
+                    UnionStep::Both(p, q) => {
+                        indices.push(cols[p]);
+                        data.push(op(values[p], t_values[q]) / two);
+                    }
+                }
+                true
+            });
+            indptr.push(indices.len());
+        }
+        CsrMatrix::from_parts(n, n, indptr, indices, data)
+    }
+
+    /// Return the lower and upper bandwidth `(kl, ku)`: the largest distance below and above
+    /// the diagonal of a stored entry. A banded solver works on `n (kl + ku + 1)` values, so a
+    /// small result tells that [`crate::banded::BandedMatrix`] pays off. O(nrows).
+    pub fn bandwidth(&self) -> (usize, usize) {
+        let (mut lower, mut upper) = (0, 0);
+        for i in 0..self.nrows {
+            let (cols, _) = self.row(i);
+            if let (Some(&first), Some(&last)) = (cols.first(), cols.last()) {
+                lower = lower.max(i.saturating_sub(first));
+                upper = upper.max(last.saturating_sub(i));
+            }
+        }
+        (lower, upper)
+    }
+
+    /// Reinterpret the matrix as the CSC form of its transpose, without touching the arrays:
+    /// the rows of `A` are the columns of `Aᵀ`. O(1).
+    pub fn into_transpose_csc(self) -> CscMatrix<T> {
+        CscMatrix::from_parts(self.ncols, self.nrows, self.indptr, self.indices, self.data)
+    }
+
+    /// Build a matrix from arrays known to be valid.
+    pub(crate) fn from_parts(
+        nrows: usize,
+        ncols: usize,
+        indptr: Vec<usize>,
+        indices: Vec<usize>,
+        data: Vec<T>,
+    ) -> Self {
+        debug_assert!(
+            validate_compressed(nrows, ncols, &indptr, &indices, data.len(), "row").is_ok()
+        );
+        Self {
+            nrows,
+            ncols,
+            indptr,
+            indices,
+            data,
+        }
+    }
+
+    /// Take the matrix apart into `(nrows, ncols, indptr, indices, data)`.
+    #[cfg(feature = "sprs")]
+    pub(crate) fn into_parts(self) -> (usize, usize, Vec<usize>, Vec<usize>, Vec<T>) {
+        (self.nrows, self.ncols, self.indptr, self.indices, self.data)
+    }
+
+    /// Convert to compressed sparse column form.
+    ///
+    /// This costs O(nnz + ncols): the entries are counted per column, then placed in one pass
+    /// over the rows, which leaves the row indices of each column sorted.
+    pub fn to_csc(&self) -> CscMatrix<T> {
+        let (indptr, indices, data) =
+            transpose_compressed(self.ncols, &self.indptr, &self.indices, &self.data);
+        CscMatrix::from_parts(self.nrows, self.ncols, indptr, indices, data)
+    }
+
+    /// Scatter the matrix into a row-major dense array of `nrows * ncols` entries.
+    pub fn to_dense(&self) -> Vec<T> {
+        let mut dense = vec![T::zero(); self.nrows * self.ncols];
+        for i in 0..self.nrows {
+            let (cols, values) = self.row(i);
+            for (&j, &v) in cols.iter().zip(values) {
+                dense[i * self.ncols + j] = v;
+            }
+        }
+        dense
+    }
+
+    /// Walk the union of both patterns with `f`, see [`all_union_compressed`]. False when the
+    /// shapes differ.
+    fn all_union(&self, other: &Self, f: impl FnMut(T, T) -> bool) -> bool {
+        self.shape() == other.shape()
+            && all_union_compressed(
+                (&self.indptr, &self.indices, &self.data),
+                (&other.indptr, &other.indices, &other.data),
+                f,
+            )
+    }
+}
+
+/// Multiply the row-major dense `dshape.0 × dshape.1` matrix `d` by the sparse matrix `a`,
+/// without densifying `a`: row `i` of the product is the combination of the rows of `a`
+/// weighted by row `i` of `d`, accumulated as in [`CsrMatrix::matmul`]. The zeros of `d` are
+/// skipped, so the product has the pattern of the rows of `a` they weight. Fails when `d` does
+/// not hold `dshape.0 * dshape.1` entries or when `dshape.1` differs from the rows of `a`.
+pub fn dense_times_csr<T: Scalar>(
+    d: &[T],
+    dshape: (usize, usize),
+    a: &CsrMatrix<T>,
+) -> Result<CsrMatrix<T>, SparseError> {
+    let (m, k) = dshape;
+    if d.len() != m * k {
+        return Err(SparseError::DimensionMismatch {
+            expected: m * k,
+            found: d.len(),
+        });
+    }
+    if k != a.nrows {
+        return Err(SparseError::DimensionMismatch {
+            expected: k,
+            found: a.nrows,
+        });
+    }
+
+    // `mark[j] == i` flags column j as already present in row i.
+    let mut mark = vec![usize::MAX; a.ncols];
+    let mut acc = vec![T::zero(); a.ncols];
+    let mut indptr = Vec::with_capacity(m + 1);
+    let mut indices = Vec::new();
+    let mut data = Vec::new();
+    indptr.push(0);
+    for (i, d_row) in d.chunks_exact(k.max(1)).take(m).enumerate() {
+        let row_start = indices.len();
+        for (p, &d_ip) in d_row.iter().enumerate() {
+            if d_ip == T::zero() {
+                continue;
+            }
+            let (cols, values) = a.row(p);
+            for &j in cols {
+                if mark[j] != i {
+                    mark[j] = i;
+                    acc[j] = T::zero();
+                    indices.push(j);
+                }
+            }
+            T::scatter_add(d_ip, values, cols, &mut acc);
+        }
+
+        indices[row_start..].sort_unstable();
+        data.extend(indices[row_start..].iter().map(|&j| acc[j]));
+        indptr.push(indices.len());
+    }
+    // With no columns in `d`, every row of the product is empty.
+    indptr.resize(m + 1, indices.len());
+
+    Ok(CsrMatrix::from_parts(m, a.ncols, indptr, indices, data))
+}
+
+impl CsrMatrix<f64> {
+    /// Borrow a symmetric matrix as a [`CscView`] for the column kernels, without converting it:
+    /// column `j` of the view is row `j` of the matrix, which are the same when the matrix is
+    /// symmetric. O(nnz + n) for the check.
+    ///
+    /// Every pair of mirrored entries must agree within [`SYMMETRY_TOL`] relative to the larger
+    /// of the two, with an entry missing on one side comparing as 0. Fails with
+    /// [`SparseError::DimensionMismatch`] when the matrix is not square and with
+    /// [`SparseError::InvalidStructure`] naming an entry whose mirror differs.
+    /// [`as_csc_view_symmetric_unchecked`](CsrMatrix::as_csc_view_symmetric_unchecked) skips
+    /// the check.
+    pub fn as_csc_view_symmetric(&self) -> Result<CscView<'_, f64>, SparseError> {
+        if self.nrows != self.ncols {
+            return Err(SparseError::DimensionMismatch {
+                expected: self.nrows,
+                found: self.ncols,
+            });
+        }
+
+        if let Some((row, col)) = self.asymmetric_entry(SYMMETRY_TOL) {
+            return Err(SparseError::InvalidStructure(format!(
+                "entry ({row}, {col}) differs from entry ({col}, {row})"
+            )));
+        }
+        Ok(self.as_csc_view_symmetric_unchecked())
+    }
+
+    /// Return true if the matrix is square and every pair of mirrored entries agrees within
+    /// `tol` relative to the larger of the two, with an entry missing on one side comparing as
+    /// 0, so that `tol = 0` asks for exact symmetry. O(nnz + n).
+    pub fn is_symmetric(&self, tol: f64) -> bool {
+        self.nrows == self.ncols && self.asymmetric_entry(tol).is_none()
+    }
+
+    /// Return an entry of a square matrix whose mirror differs from it by more than `tol`
+    /// relative to the larger of the two.
+    fn asymmetric_entry(&self, tol: f64) -> Option<(usize, usize)> {
+        let t = transpose_compressed(self.ncols, &self.indptr, &self.indices, &self.data);
+        let close = |a: Option<f64>, t: Option<f64>| {
+            let (a, t) = (a.unwrap_or(0.0), t.unwrap_or(0.0));
+            (a - t).abs() <= tol * a.abs().max(t.abs())
+        };
+        self.find_unmirrored(&t, close)
+    }
+
+    /// Equilibrate a square matrix symmetrically, `A ← D A D` with `dᵢ = 1 / √|aᵢᵢ|`, so that
+    /// every nonzero diagonal entry becomes ±1, and return `d`. This is the Jacobi scaling,
+    /// which often improves the conditioning of a symmetric positive definite matrix before an
+    /// iterative solve. A zero diagonal entry leaves its row and column unscaled (`dᵢ = 1`).
+    ///
+    /// To solve `A x = b`, solve `(D A D) y = D b` and recover `x = D y`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the matrix isn't square.
+    pub fn equilibrate_symmetric(&mut self) -> Vec<f64> {
+        assert_eq!(self.nrows, self.ncols, "the matrix isn't square");
+        let d: Vec<f64> = (0..self.nrows)
+            .map(|i| match self.get(i, i).abs() {
+                0.0 => 1.0,
+                a => 1.0 / sqrt(a),
+            })
+            .collect();
+        self.scale_symmetric(&d);
+        d
+    }
+
+    /// Return the 5-point finite difference Laplacian on an `nx` by `ny` grid with Dirichlet
+    /// boundaries: 4 on the diagonal and -1 for each of the (up to) four grid neighbours, the
+    /// classic symmetric positive definite test problem. Grid point `(x, y)` is unknown
+    /// `y * nx + x`, so the bandwidth is `nx`.
+    pub fn poisson2d(nx: usize, ny: usize) -> Self {
+        let n = nx * ny;
+        let mut matrix = Self::new(n, n);
+        for y in 0..ny {
+            for x in 0..nx {
+                let k = y * nx + x;
+                let stencil = [
+                    (y > 0, k.wrapping_sub(nx), -1.0),
+                    (x > 0, k.wrapping_sub(1), -1.0),
+                    (true, k, 4.0),
+                    (x + 1 < nx, k + 1, -1.0),
+                    (y + 1 < ny, k + nx, -1.0),
+                ];
+                for (inside, j, v) in stencil {
+                    if inside {
+                        matrix.indices.push(j);
+                        matrix.data.push(v);
+                    }
+                }
+                matrix.indptr[k + 1] = matrix.indices.len();
+            }
+        }
+        matrix
+    }
+
+    /// Drop the stored entries with `|v| <= tol`, such as values that cancelled during
+    /// arithmetic, and release their storage. `prune(0.0)` removes just the explicit zeros. NaN
+    /// entries are kept.
+    pub fn prune(&mut self, tol: f64) {
+        retain_compressed(&mut self.indptr, &mut self.indices, &mut self.data, |v| {
+            v.abs() > tol || v.is_nan()
+        });
+    }
+
+    /// Return the Frobenius norm, the square root of the sum of the squared entries. O(nnz).
+    pub fn norm_fro(&self) -> f64 {
+        frobenius(&self.data)
+    }
+
+    /// Return the 1-norm, the largest absolute column sum. NaN when an entry is NaN.
+    /// O(nnz + ncols).
+    pub fn norm_one(&self) -> f64 {
+        max_inner_abs_sum(self.ncols, &self.indices, &self.data)
+    }
+
+    /// Return the infinity norm, the largest absolute row sum. NaN when an entry is NaN.
+    /// O(nnz + nrows).
+    pub fn norm_inf(&self) -> f64 {
+        max_outer_abs_sum(&self.indptr, &self.data)
+    }
+
+    /// Estimate the 2-norm, the largest singular value, by power iteration on `x ↦ Aᵀ (A x)`,
+    /// see [`norm2_est_with_vector`](Self::norm2_est_with_vector).
+    pub fn norm2_est(&self, opts: &SolverOptions) -> f64 {
+        self.norm2_est_with_vector(opts).0
+    }
+
+    /// Estimate the 2-norm, the largest singular value σ₁, and its right singular vector by
+    /// power iteration on `x ↦ Aᵀ (A x)`, a product with `A` and one with `Aᵀ` per iteration
+    /// and no SVD. Return σ₁ and the unit vector, of length `ncols`.
+    ///
+    /// The iteration starts from a fixed vector with no particular structure, so the estimate
+    /// is deterministic, and stops once `‖AᵀA x − θ x‖ ≤ tol · θ` for the Rayleigh quotient
+    /// `θ = ‖A x‖²`, or after `max_iter` iterations. The estimate never exceeds σ₁ and its
+    /// error shrinks by `(σ₂ / σ₁)²` per iteration. The estimate of a matrix with no entries
+    /// is zero.
+    pub fn norm2_est_with_vector(&self, opts: &SolverOptions) -> (f64, Vec<f64>) {
+        let n = self.ncols;
+        let mut x: Vec<f64> = (1..=n)
+            .map(|i| 0.5 + (i as f64 * 0.618_033_988_749_895) % 1.0)
+            .collect();
+        let x_norm = norm2(&x);
+        x.iter_mut().for_each(|xi| *xi /= x_norm);
+
+        let mut ax = vec![0.0; self.nrows];
+        let mut z = vec![0.0; n];
+        for _ in 0..opts.max_iter {
+            self.mul_vec_into(&x, &mut ax);
+            self.mul_vec_transposed_into(&ax, &mut z);
+            let theta = dot(&ax, &ax);
+            let residual: f64 = z
+                .iter()
+                .zip(&x)
+                .map(|(zi, xi)| (zi - theta * xi) * (zi - theta * xi))
+                .sum();
+            let z_norm = norm2(&z);
+            if z_norm == 0.0 || sqrt(residual) <= opts.tol * theta {
+                return (sqrt(theta), x);
+            }
+            for (xi, zi) in x.iter_mut().zip(&z) {
+                *xi = zi / z_norm;
+            }
+        }
+        self.mul_vec_into(&x, &mut ax);
+        (norm2(&ax), x)
+    }
+}
+
+impl CsrMatrix<u64> {
+    /// Multiply two integer matrices, `self * rhs`, as [`matmul`](Self::matmul) does but
+    /// failing with [`SparseError::Overflow`] at the first entry of the product that overflows
+    /// instead of wrapping or panicking.
+    pub fn checked_matmul(&self, rhs: &CsrMatrix<u64>) -> Result<CsrMatrix<u64>, SparseError> {
+        self.matmul_with(rhs, usize::MAX, checked_mul_add)
+    }
+
+    /// Raise a square integer matrix to the power `k` exactly, by repeated squaring. For the
+    /// 0/1 adjacency matrix of a graph, entry `(i, j)` of `Aᵏ` counts the walks of length `k`
+    /// from `i` to `j`. `A⁰` is the identity.
+    ///
+    /// The products are checked: an entry that overflows u64 fails with
+    /// [`SparseError::Overflow`], which reports it in the product that overflowed, either a
+    /// square `A^(2^m)` or a partial product of them. Fails with
+    /// [`SparseError::DimensionMismatch`] on a non-square matrix.
+    ///
+    /// Powers fill in quickly: `Aᵏ` has an entry wherever a walk of length `k` exists, so
+    /// the power of a connected graph's adjacency matrix becomes dense once `k` reaches its
+    /// diameter, `n²` entries however sparse `A` is. Use [`pow_capped`](Self::pow_capped) to
+    /// fail instead of exhausting memory.
+    pub fn pow(&self, k: usize) -> Result<CsrMatrix<u64>, SparseError> {
+        self.pow_capped(k, usize::MAX)
+    }
+
+    /// [`pow`](Self::pow), failing with [`SparseError::TooManyEntries`] before computing any
+    /// product that would store more than `max_nnz` entries.
+    pub fn pow_capped(&self, k: usize, max_nnz: usize) -> Result<CsrMatrix<u64>, SparseError> {
+        self.pow_with(k, max_nnz, checked_mul_add)
+    }
+
+    /// Raise a square integer matrix to the power `k` in floating point, for walk counts past
+    /// the range of u64. The counts are exact up to 2⁵³ and rounded beyond, and overflow to
+    /// infinity rather than fail. Fills in as [`pow`](Self::pow) does.
+    pub fn pow_f64(&self, k: usize) -> Result<CsrMatrix<f64>, SparseError> {
+        self.pow_f64_capped(k, usize::MAX)
+    }
+
+    /// [`pow_f64`](Self::pow_f64), failing with [`SparseError::TooManyEntries`] before
+    /// computing any product that would store more than `max_nnz` entries.
+    pub fn pow_f64_capped(&self, k: usize, max_nnz: usize) -> Result<CsrMatrix<f64>, SparseError> {
+        let a = CsrMatrix {
+            nrows: self.nrows,
+            ncols: self.ncols,
+            indptr: self.indptr.clone(),
+            indices: self.indices.clone(),
+            data: self.data.iter().map(|&v| v as f64).collect(),
+        };
+        a.pow_with(k, max_nnz, |acc, a, b| Some(acc + a * b))
+    }
+}
+
+/// `acc + a b` in u64, `None` on overflow.
+fn checked_mul_add(acc: u64, a: u64, b: u64) -> Option<u64> {
+    a.checked_mul(b)?.checked_add(acc)
+}
+
+/// A borrowed row read as a packed vector, the item of the parallel row iterators.
+pub type PackedVecRef<'a, T> = CsrRow<'a, T>;
+
+/// A borrowed row of a [`CsrMatrix`], from [`CsrMatrix::row_view`] or [`CsrMatrix::row_iter`]:
+/// the sorted column indices and the values of its stored entries.
+#[derive(Clone, Copy, Debug)]
+pub struct CsrRow<'a, T> {
+    index: usize,
+    ncols: usize,
+    indices: &'a [usize],
+    data: &'a [T],
+}
+
+impl<'a, T: Scalar> CsrRow<'a, T> {
+    /// Return the index of the row in its matrix
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Return the sorted column indices of the stored entries
+    pub fn indices(&self) -> &'a [usize] {
+        self.indices
+    }
+
+    /// Return the values of the stored entries
+    pub fn data(&self) -> &'a [T] {
+        self.data
+    }
+
+    /// Return the number of stored entries
+    pub fn nnz(&self) -> usize {
+        self.indices.len()
+    }
+
+    /// Return the number of columns of the matrix, the length of the full row
+    pub fn full_len(&self) -> usize {
+        self.ncols
+    }
+
+    /// Return the entry in column `j`, 0 when it is not stored. O(log nnz).
+    pub fn get(&self, j: usize) -> T {
+        match self.indices.binary_search(&j) {
+            Ok(k) => self.data[k],
+            Err(_) => T::zero(),
+        }
+    }
+
+    /// Iterate over the stored `(column, value)` pairs in column order.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = (usize, T)> + ExactSizeIterator + 'a {
+        self.indices.iter().copied().zip(self.data.iter().copied())
+    }
+
+    /// Return the inner product of the row with the dense vector `x`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `x.len()` differs from the number of columns of the matrix.
+    pub fn dot(&self, x: &[T]) -> T {
+        assert_eq!(x.len(), self.ncols, "x has the wrong length");
+        T::gather_dot(self.data, self.indices, x)
+    }
+
+    /// Copy the row into a packed vector of the matrix's column count.
+    pub fn to_packed_vec(&self) -> PackedVec<T> {
+        PackedVec::from_pairs(self.ncols, self.iter()).expect("column indices are in bounds")
+    }
+}
+
+/// Two matrices are equal when they have the same shape and the same entries, with structural
+/// zeros compared as 0.0: an explicitly stored zero matches a missing entry.
+impl<T: Scalar> PartialEq for CsrMatrix<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.all_union(other, |a, b| a == b)
+    }
+}
+
+/// Compared over the union of both patterns like [`PartialEq`].
+#[cfg(feature = "approx")]
+impl approx::AbsDiffEq for CsrMatrix<f64> {
+    type Epsilon = f64;
+
+    fn default_epsilon() -> f64 {
+        f64::default_epsilon()
+    }
+
+    fn abs_diff_eq(&self, other: &Self, epsilon: f64) -> bool {
+        self.all_union(other, |a, b| a.abs_diff_eq(&b, epsilon))
+    }
+}
+
+#[cfg(feature = "approx")]
+impl approx::RelativeEq for CsrMatrix<f64> {
+    fn default_max_relative() -> f64 {
+        f64::default_max_relative()
+    }
+
+    fn relative_eq(&self, other: &Self, epsilon: f64, max_relative: f64) -> bool {
+        self.all_union(other, |a, b| a.relative_eq(&b, epsilon, max_relative))
+    }
+}
+
+#[cfg(feature = "approx")]
+impl approx::UlpsEq for CsrMatrix<f64> {
+    fn default_max_ulps() -> u32 {
+        f64::default_max_ulps()
+    }
+
+    fn ulps_eq(&self, other: &Self, epsilon: f64, max_ulps: u32) -> bool {
+        self.all_union(other, |a, b| a.ulps_eq(&b, epsilon, max_ulps))
+    }
+}
+
+/// Small matrices are drawn as a dense grid with `.` for structural zeros, larger ones as a
+/// truncated list of `(row, column) value` lines. A precision, as in `{:.3}`, applies to the values.
+impl<T: Scalar + core::fmt::Display> core::fmt::Display for CsrMatrix<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let entries = (0..self.nrows).flat_map(|i| {
+            (self.indptr[i]..self.indptr[i + 1]).map(move |p| (i, self.indices[p], self.data[p]))
+        });
+        display::fmt_matrix(f, "CSR", self.shape(), self.nnz(), entries)
+    }
+}
+
+impl<T: Scalar> core::ops::Mul for &CsrMatrix<T> {
+    type Output = CsrMatrix<T>;
+
+    /// Sparse matrix product, see [`CsrMatrix::matmul`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the inner dimensions don't agree.
+    fn mul(self, rhs: Self) -> Self::Output {
+        self.matmul(rhs)
+            .expect("inner dimensions of the product must agree")
+    }
+}
+
+impl<T: Scalar> core::ops::Mul for CsrMatrix<T> {
+    type Output = CsrMatrix<T>;
+
+    /// Sparse matrix product, see [`CsrMatrix::matmul`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the inner dimensions don't agree.
+    fn mul(self, rhs: Self) -> Self::Output {
+        &self * &rhs
+    }
+}
+
+#[test]
+fn test_csr_matrix() {
+    #[rustfmt::skip]
+    let dense = vec![
+        1.0, 0.0, 0.0, 2.0,
+        0.0, 0.0, 0.0, 0.0,
+        0.0, 3.0, 4.0, 0.0,
+    ];
+
+    let a = CsrMatrix::from_dense(3, 4, &dense).unwrap();
+    assert_eq!(a.shape(), (3, 4));
+    assert_eq!(a.nnz(), 4);
+    assert_eq!(a.indptr(), [0, 2, 2, 4]);
+    assert_eq!(a.indices(), [0, 3, 1, 2]);
+    assert_eq!(a.data(), [1.0, 2.0, 3.0, 4.0]);
+    assert_eq!(a.row(1), (&[][..], &[][..]));
+    assert_eq!(a.row(2), (&[1, 2][..], &[3.0, 4.0][..]));
+    assert_eq!(a.get(0, 3), 2.0);
+    assert_eq!(a.get(1, 3), 0.0);
+    assert_eq!(a.to_dense(), dense);
+
+    let b =
+        CsrMatrix::try_from_csr_data(3, 4, vec![0, 2, 2, 4], vec![0, 3, 1, 2], vec![1, 2, 3, 4])
+            .unwrap();
+    assert_eq!(b.to_dense(), [1, 0, 0, 2, 0, 0, 0, 0, 0, 3, 4, 0]);
+
+    let empty = CsrMatrix::<f64>::new(2, 3);
+    assert_eq!(empty.nnz(), 0);
+    assert_eq!(empty.to_dense(), [0.0; 6]);
+
+    let invalid = |indptr: Vec<usize>, indices: Vec<usize>| {
+        let data = vec![1.0; indices.len()];
+        CsrMatrix::try_from_csr_data(2, 3, indptr, indices, data).unwrap_err()
+    };
+    assert_eq!(
+        invalid(vec![0, 1], vec![0]),
+        SparseError::DimensionMismatch {
+            expected: 3,
+            found: 2
+        }
+    );
+    assert!(matches!(
+        invalid(vec![0, 1, 3], vec![0, 1]),
+        SparseError::InvalidStructure(_)
+    ));
+    assert!(matches!(
+        invalid(vec![0, 2, 1], vec![0, 1]),
+        SparseError::InvalidStructure(_)
+    ));
+    assert!(matches!(
+        invalid(vec![0, 2, 2], vec![1, 1]),
+        SparseError::InvalidStructure(_)
+    ));
+    assert_eq!(
+        invalid(vec![0, 1, 2], vec![0, 3]),
+        SparseError::IndexOutOfBounds { index: 3, len: 3 }
+    );
+    assert_eq!(
+        CsrMatrix::from_dense(2, 2, &[1.0, 2.0, 3.0]).unwrap_err(),
+        SparseError::DimensionMismatch {
+            expected: 4,
+            found: 3
+        }
+    );
+}
+
+#[test]
+fn test_csr_mul_vec() {
+    use crate::test_util::{assert_close, dense_mul_vec, Lcg};
+
+    let mut rng = Lcg::new(7);
+    for (nrows, ncols, density) in [(1, 1, 1.0), (5, 8, 0.3), (40, 25, 0.1), (30, 30, 0.0)] {
+        let dense = rng.dense(nrows, ncols, density);
+        let a = CsrMatrix::from_dense(nrows, ncols, &dense).unwrap();
+        let x: Vec<f64> = (0..ncols).map(|_| rng.uniform()).collect();
+
+        let expected = dense_mul_vec(nrows, ncols, &dense, &x);
+        assert_close(&a.mul_vec(&x), &expected, 1e-12);
+
+        let mut y = vec![f64::NAN; nrows];
+        a.mul_vec_into(&x, &mut y);
+        assert_close(&y, &expected, 1e-12);
+    }
+
+    let a = CsrMatrix::from_dense(2, 3, &[1, 0, 2, 0, 3, 0]).unwrap();
+    assert_eq!(a.mul_vec(&[1, 2, 3]), [7, 6]);
+}
+
+#[test]
+fn test_csr_matmul() {
+    use crate::test_util::{assert_close, Lcg};
+
+    let dense_matmul = |m: usize, n: usize, p: usize, a: &[f64], b: &[f64]| {
+        let mut c = vec![0.0; m * p];
+        for i in 0..m {
+            for k in 0..n {
+                for j in 0..p {
+                    c[i * p + j] += a[i * n + k] * b[k * p + j];
+                }
+            }
+        }
+        c
+    };
+
+    let mut rng = Lcg::new(11);
+    for (m, n, p, density) in [
+        (1, 1, 1, 1.0),
+        (6, 4, 5, 0.4),
+        (30, 20, 25, 0.1),
+        (8, 8, 8, 0.0),
+    ] {
+        let a_dense = rng.dense(m, n, density);
+        let b_dense = rng.dense(n, p, density);
+        let a = CsrMatrix::from_dense(m, n, &a_dense).unwrap();
+        let b = CsrMatrix::from_dense(n, p, &b_dense).unwrap();
+
+        let c = &a * &b;
+        assert_eq!(c.shape(), (m, p));
+        assert_close(
+            &c.to_dense(),
+            &dense_matmul(m, n, p, &a_dense, &b_dense),
+            1e-12,
+        );
+        // The symbolic pass sized the arrays exactly, and every row is sorted.
+        assert_eq!(c.indices().len(), c.indptr()[m]);
+        assert!(CsrMatrix::try_from_csr_data(
+            m,
+            p,
+            c.indptr().to_vec(),
+            c.indices().to_vec(),
+            c.data().to_vec()
+        )
+        .is_ok());
+    }
+
+    // Cancellation keeps a structural entry.
+    let a = CsrMatrix::from_dense(1, 2, &[1, 1]).unwrap();
+    let b = CsrMatrix::from_dense(2, 1, &[1, -1]).unwrap();
+    let c = a.clone() * b;
+    assert_eq!(c.nnz(), 1);
+    assert_eq!(c.to_dense(), [0]);
+
+    assert_eq!(
+        a.matmul(&a).unwrap_err(),
+        SparseError::DimensionMismatch {
+            expected: 2,
+            found: 1
+        }
+    );
+}
+
+#[test]
+fn test_csr_pow() {
+    // The path graph 0 - 1 - 2 - 3 - 4: Aᵏ counts the walks of length k.
+    let n = 5;
+    let mut path = vec![0u64; n * n];
+    for i in 0..n - 1 {
+        path[i * n + i + 1] = 1;
+        path[(i + 1) * n + i] = 1;
+    }
+    let a = CsrMatrix::from_dense(n, n, &path).unwrap();
+    assert_eq!(a.pow(0).unwrap(), CsrMatrix::identity(n));
+    assert_eq!(a.pow(1).unwrap(), a);
+    // 1 → 2 in three steps: 1-0-1-2, 1-2-1-2 and 1-2-3-2.
+    #[rustfmt::skip]
+    assert_eq!(a.pow(3).unwrap().to_dense(), [
+        0, 2, 0, 1, 0,
+        2, 0, 3, 0, 1,
+        0, 3, 0, 3, 0,
+        1, 0, 3, 0, 2,
+        0, 1, 0, 2, 0,
+    ]);
+    #[rustfmt::skip]
+    assert_eq!(a.pow(4).unwrap().to_dense(), [
+        2, 0, 3, 0, 1,
+        0, 5, 0, 4, 0,
+        3, 0, 6, 0, 3,
+        0, 4, 0, 5, 0,
+        1, 0, 3, 0, 2,
+    ]);
+    assert_eq!(a.pow(4).unwrap(), (&a * &a) * (&a * &a));
+
+    // A small directed graph, 0 → 1 → 2 → 0 with the shortcut 0 → 2 and the sink 2 → 3.
+    let mut coo = crate::coo::CooMatrix::new(4, 4);
+    for (i, j) in [(0, 1), (1, 2), (2, 0), (0, 2), (2, 3)] {
+        coo.push(i, j, 1u64);
+    }
+    let d = coo.to_csr();
+    #[rustfmt::skip]
+    assert_eq!(d.pow(3).unwrap().to_dense(), [
+        1, 1, 1, 1,
+        0, 1, 1, 0,
+        1, 0, 1, 1,
+        0, 0, 0, 0,
+    ]);
+    let d5 = d.pow(5).unwrap();
+    #[rustfmt::skip]
+    assert_eq!(d5.to_dense(), [
+        2, 1, 2, 2,
+        1, 1, 1, 1,
+        1, 1, 2, 1,
+        0, 0, 0, 0,
+    ]);
+    assert_eq!(d5, d.checked_matmul(&d.pow(4).unwrap()).unwrap());
+    let d5_f64 = d.pow_f64(5).unwrap();
+    assert!(d5_f64
+        .to_dense()
+        .iter()
+        .zip(d5.to_dense())
+        .all(|(&x, y)| x == y as f64));
+
+    // The cap stops the fill: A² of a longer path already has 26 entries.
+    let long = CsrMatrix::<u64>::tridiagonal(1, 0, 1, 10);
+    assert_eq!(
+        long.pow_capped(4, 20).unwrap_err(),
+        SparseError::TooManyEntries { nnz: 26, limit: 20 }
+    );
+    assert_eq!(long.pow_capped(4, 40).unwrap(), long.pow(4).unwrap());
+    assert_eq!(
+        long.pow_f64_capped(4, 20).unwrap_err(),
+        SparseError::TooManyEntries { nnz: 26, limit: 20 }
+    );
+
+    assert_eq!(
+        CsrMatrix::<u64>::new(2, 3).pow(2).unwrap_err(),
+        SparseError::DimensionMismatch {
+            expected: 2,
+            found: 3
+        }
+    );
+}
+
+#[test]
+fn test_csr_pow_overflow() {
+    // Every entry of the k-th power of the all-ones 4 × 4 matrix is 4^(k − 1).
+    let ones = CsrMatrix::from_dense(4, 4, &[1u64; 16]).unwrap();
+    assert_eq!(ones.pow(32).unwrap().get(3, 1), 1 << 62);
+    assert_eq!(
+        ones.pow(40).unwrap_err(),
+        SparseError::Overflow { row: 0, col: 0 }
+    );
+    assert_eq!(
+        ones.pow(32)
+            .unwrap()
+            .checked_matmul(&ones.pow(2).unwrap())
+            .unwrap_err(),
+        SparseError::Overflow { row: 0, col: 0 }
+    );
+
+    // The floating-point powers don't overflow, and these are exact.
+    let p = ones.pow_f64(40).unwrap();
+    assert!(p.data().iter().all(|&v| v == 2f64.powi(78)));
+}
+
+#[test]
+fn test_csr_transpose() {
+    use crate::test_util::Lcg;
+
+    let mut rng = Lcg::new(3);
+    for (nrows, ncols) in [(1, 1), (4, 7), (9, 3), (0, 5)] {
+        let dense = rng.dense(nrows, ncols, 0.4);
+        let a = CsrMatrix::from_dense(nrows, ncols, &dense).unwrap();
+
+        let mut expected = vec![0.0; nrows * ncols];
+        for i in 0..nrows {
+            for j in 0..ncols {
+                expected[j * nrows + i] = dense[i * ncols + j];
+            }
+        }
+
+        let t = a.transpose();
+        assert_eq!(t.shape(), (ncols, nrows));
+        assert_eq!(t.to_dense(), expected);
+        assert_eq!(t.transpose().to_dense(), dense);
+
+        let view = a.clone().into_transpose_csc();
+        assert_eq!(view.shape(), (ncols, nrows));
+        assert_eq!(view.indptr(), a.indptr());
+        assert_eq!(view.to_dense(), expected);
+        assert_eq!(view.into_transpose_csr().to_dense(), dense);
+    }
+}
+
+#[test]
+fn test_csr_symmetric_parts() {
+    use crate::test_util::Lcg;
+
+    let mut rng = Lcg::new(67);
+    for n in [0, 1, 6, 30] {
+        let dense = rng.dense(n, n, 0.3);
+        let a = CsrMatrix::from_dense(n, n, &dense).unwrap();
+        let sym = a.symmetric_part();
+        let anti = a.antisymmetric_part();
+
+        // Exactly symmetric and antisymmetric, on the pattern of A + Aᵀ.
+        assert_eq!(sym.transpose(), sym);
+        assert_eq!(
+            anti.transpose().data(),
+            &anti.data().iter().map(|v| -v).collect::<Vec<_>>()
+        );
+        assert_eq!(sym.indptr(), anti.indptr());
+        assert_eq!(sym.indices(), anti.indices());
+        assert!(sym.is_hermitian());
+
+        let (s, t) = (sym.to_dense(), anti.to_dense());
+        for i in 0..n {
+            for j in 0..n {
+                let (a_ij, a_ji) = (dense[i * n + j], dense[j * n + i]);
+                assert_eq!(s[i * n + j], (a_ij + a_ji) / 2.0);
+                assert_eq!(t[i * n + j], (a_ij - a_ji) / 2.0);
+                assert!((s[i * n + j] + t[i * n + j] - a_ij).abs() <= f64::EPSILON * a_ij.abs());
+            }
+        }
+
+        // The values of A on the symmetric pattern of A + Aᵀ symmetrize in place.
+        let mut b = sym.clone();
+        for i in 0..n {
+            for k in b.indptr[i]..b.indptr[i + 1] {
+                b.data[k] = dense[i * n + b.indices[k]];
+            }
+        }
+        b.symmetrize_in_place().unwrap();
+        assert_eq!(b, sym);
+    }
+
+    // An entry without its mirror is named, and the matrix is left alone.
+    #[rustfmt::skip]
+    let mut a = CsrMatrix::from_dense(3, 3, &[
+        1.0, 2.0, 0.0,
+        2.0, 1.0, 0.0,
+        0.0, 5.0, 1.0,
+    ])
+    .unwrap();
+    let before = a.clone();
+    assert_eq!(
+        a.symmetrize_in_place(),
+        Err(SparseError::InvalidStructure(
+            "entry (2, 1) is stored but (1, 2) is not".into()
+        ))
+    );
+    assert_eq!(a, before);
+    assert!(matches!(
+        CsrMatrix::<f64>::new(2, 3).symmetrize_in_place(),
+        Err(SparseError::DimensionMismatch { .. })
+    ));
+}
+
+#[test]
+fn test_csr_as_csc_view_symmetric() {
+    let a = CsrMatrix::poisson2d(5, 4);
+    let csc = a.to_csc();
+    let view = a.as_csc_view_symmetric().unwrap();
+    assert_eq!(view.shape(), csc.shape());
+    assert_eq!(view.nnz(), csc.nnz());
+    for j in 0..a.ncols() {
+        assert_eq!(view.col(j), csc.col(j));
+    }
+    assert_eq!(view.to_csc(), csc);
+
+    // The column kernels give the same results through the view.
+    let r: Vec<f64> = (0..a.nrows()).map(|i| (i as f64 * 0.7).sin()).collect();
+    for j in [0, 7, 19] {
+        assert_eq!(view.col_dot_dense(j, &r), csc.col_dot_dense(j, &r));
+        let (mut x, mut y) = (r.clone(), r.clone());
+        view.col_axpy_into(j, -0.5, &mut x);
+        csc.col_axpy_into(j, -0.5, &mut y);
+        assert_eq!(x, y);
+    }
+    let b = PackedVec::from_pairs(a.nrows(), [(2, 1.0), (11, -2.0)]).unwrap();
+    assert_eq!(
+        view.solve_lower_triangular_sparse(&b).unwrap(),
+        csc.solve_lower_triangular_sparse(&b).unwrap()
+    );
+
+    // Rounding noise on one side of a pair passes, a real difference doesn't.
+    let mut noisy = a.clone();
+    noisy.data[1] *= 1.0 + 1e-14;
+    assert!(noisy.as_csc_view_symmetric().is_ok());
+    assert!(noisy.is_symmetric(SYMMETRY_TOL));
+    assert!(!noisy.is_symmetric(0.0));
+    let mut asymmetric = a.clone();
+    asymmetric.data[1] = 3.0;
+    assert_eq!(
+        asymmetric.as_csc_view_symmetric().unwrap_err(),
+        SparseError::InvalidStructure("entry (0, 1) differs from entry (1, 0)".into())
+    );
+    #[rustfmt::skip]
+    let lower = CsrMatrix::from_dense(2, 2, &[
+        1.0, 0.0,
+        2.0, 1.0,
+    ])
+    .unwrap();
+    assert_eq!(
+        lower.as_csc_view_symmetric().unwrap_err(),
+        SparseError::InvalidStructure("entry (1, 0) differs from entry (0, 1)".into())
+    );
+    assert!(!lower.is_symmetric(0.5));
+    assert!(lower.is_symmetric(1.0));
+    assert!(!CsrMatrix::<f64>::new(2, 3).is_symmetric(1.0));
+    assert!(matches!(
+        CsrMatrix::<f64>::new(2, 3).as_csc_view_symmetric(),
+        Err(SparseError::DimensionMismatch { .. })
+    ));
+
+    // Unchecked, the view is the transpose.
+    let unchecked = lower.as_csc_view_symmetric_unchecked();
+    assert_eq!(unchecked.to_csc(), lower.transpose().to_csc());
+}
+
+#[cfg(feature = "complex")]
+#[test]
+fn test_csr_complex() {
+    use num_complex::Complex;
+
+    let c = |re: f64, im: f64| Complex::new(re, im);
+    let zero = c(0.0, 0.0);
+    #[rustfmt::skip]
+    let dense = [
+        c(2.0, 0.0), c(1.0, -1.0), zero,
+        c(1.0, 1.0), c(3.0, 0.0), c(0.0, 2.0),
+        zero, c(0.0, -2.0), c(1.0, 0.0),
+    ];
+    let a = CsrMatrix::from_dense(3, 3, &dense).unwrap();
+    assert!(a.is_hermitian());
+    assert_eq!(a.adjoint().to_dense(), dense);
+    // Hermitian but not symmetric.
+    assert_ne!(a.transpose().to_dense(), dense);
+
+    let x = [c(1.0, 0.0), c(0.0, 1.0), c(1.0, 1.0)];
+    assert_eq!(a.mul_vec(&x), [c(3.0, 1.0), c(-1.0, 6.0), c(3.0, 1.0)]);
+
+    let b = CsrMatrix::from_dense(1, 2, &[c(0.0, 1.0), c(2.0, 0.0)]).unwrap();
+    assert!(!b.is_hermitian());
+    let bh = b.adjoint();
+    assert_eq!(bh.shape(), (2, 1));
+    assert_eq!(bh.to_dense(), [c(0.0, -1.0), c(2.0, 0.0)]);
+    assert_eq!((&b * &bh).to_dense(), [c(5.0, 0.0)]);
+    assert_eq!(b.to_csc().adjoint().to_csr().to_dense(), bh.to_dense());
+}
+
+#[test]
+fn test_csr_prune() {
+    #[rustfmt::skip]
+    let mut a = CsrMatrix::try_from_csr_data(
+        3, 3,
+        vec![0, 2, 3, 5],
+        vec![0, 2, 1, 0, 2],
+        vec![1.0, 0.0, -1e-14, 2.0, 3.0],
+    )
+    .unwrap();
+
+    let mut exact = a.clone();
+    exact.prune(0.0);
+    assert_eq!(exact.indptr(), [0, 1, 2, 4]);
+    assert_eq!(exact.nnz(), 4);
+
+    a.prune(1e-12);
+    assert_eq!(a.indptr(), [0, 1, 1, 3]);
+    assert_eq!(a.indices(), [0, 0, 2]);
+    assert_eq!(a.data(), [1.0, 2.0, 3.0]);
+    assert_eq!(a.to_dense(), [1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 2.0, 0.0, 3.0]);
+
+    let mut csc = CsrMatrix::from_dense(2, 2, &[1.0, 0.5, 0.0, 4.0])
+        .unwrap()
+        .to_csc();
+    csc.prune(0.5);
+    assert_eq!(csc.indptr(), [0, 1, 2]);
+    assert_eq!(csc.to_dense(), [1.0, 0.0, 0.0, 4.0]);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_csr_serde() {
+    use crate::coo::CooMatrix;
+
+    let a = CsrMatrix::from_dense(2, 3, &[1.0, 0.0, 2.0, 0.0, 3.0, 0.0]).unwrap();
+    let json = serde_json::to_string(&a).unwrap();
+    assert_eq!(
+        json,
+        r#"{"nrows":2,"ncols":3,"indptr":[0,2,3],"indices":[0,2,1],"data":[1.0,2.0,3.0]}"#
+    );
+    let back: CsrMatrix = serde_json::from_str(&json).unwrap();
+    assert_eq!(back.to_dense(), a.to_dense());
+
+    let csc: CscMatrix =
+        serde_json::from_str(&serde_json::to_string(&a.to_csc()).unwrap()).unwrap();
+    assert_eq!(csc.to_dense(), a.to_dense());
+
+    let coo = CooMatrix::from_triplets(2, 3, vec![0, 1], vec![2, 1], vec![2.0, 3.0]).unwrap();
+    let coo: CooMatrix = serde_json::from_str(&serde_json::to_string(&coo).unwrap()).unwrap();
+    assert_eq!(
+        coo.triplets().collect::<Vec<_>>(),
+        [(0, 2, 2.0), (1, 1, 3.0)]
+    );
+
+    // The structure is checked on the way in.
+    let unsorted =
+        r#"{"nrows":2,"ncols":3,"indptr":[0,2,3],"indices":[2,0,1],"data":[1.0,2.0,3.0]}"#;
+    let err = serde_json::from_str::<CsrMatrix>(unsorted).unwrap_err();
+    assert!(err.to_string().contains("not strictly increasing"), "{err}");
+    let out_of_bounds = r#"{"nrows":1,"ncols":1,"rows":[0],"cols":[1],"values":[1.0]}"#;
+    assert!(serde_json::from_str::<CooMatrix>(out_of_bounds).is_err());
+}
+
+#[test]
+fn test_csr_display() {
+    let a = CsrMatrix::from_dense(2, 3, &[1.0, 0.0, -2.5, 0.0, 10.0, 0.0]).unwrap();
+    assert_eq!(
+        a.to_string(),
+        "2x3 CSR matrix, 3 stored entries\n[   1    . -2.5]\n[   .   10    .]"
+    );
+    assert_eq!(
+        format!("{:.1}", a.to_csc()),
+        "2x3 CSC matrix, 3 stored entries\n[ 1.0    . -2.5]\n[   . 10.0    .]"
+    );
+
+    let n = 30;
+    let dense: Vec<f64> = (0..n * n)
+        .map(|k| if k % (n + 1) == 0 { 2.0 } else { 0.0 })
+        .collect();
+    let identity = CsrMatrix::from_dense(n, n, &dense).unwrap();
+    let text = identity.to_string();
+    assert!(text.starts_with("30x30 CSR matrix, 30 stored entries\n(0, 0) 2\n(1, 1) 2\n"));
+    assert!(text.ends_with("(19, 19) 2\n... 10 more"));
+
+    let mut coo = crate::coo::CooMatrix::new(2, 2);
+    coo.push(1, 0, 1.0);
+    coo.push(1, 0, 2.0);
+    assert_eq!(
+        coo.to_string(),
+        "2x2 COO matrix, 2 triplets\n(1, 0) 1\n(1, 0) 2"
+    );
+}
+
+#[test]
+fn test_csr_special_matrices() {
+    let eye = CsrMatrix::<i32>::identity(3);
+    assert_eq!(eye.to_dense(), [1, 0, 0, 0, 1, 0, 0, 0, 1]);
+
+    let d = CsrMatrix::from_diagonal(&[2.0, 0.0, -1.0]);
+    assert_eq!(d.nnz(), 2);
+    assert_eq!(d.mul_vec(&[1.0, 1.0, 1.0]), [2.0, 0.0, -1.0]);
+
+    #[rustfmt::skip]
+    assert_eq!(CsrMatrix::tridiagonal(-1.0, 2.0, -1.0, 4).to_dense(), [
+        2.0, -1.0, 0.0, 0.0,
+        -1.0, 2.0, -1.0, 0.0,
+        0.0, -1.0, 2.0, -1.0,
+        0.0, 0.0, -1.0, 2.0,
+    ]);
+    assert_eq!(CsrMatrix::tridiagonal(0.0, 1.0, 3.0, 3).nnz(), 5);
+    assert_eq!(CsrMatrix::tridiagonal(0.0, 1.0, 3.0, 3).bandwidth(), (0, 1));
+    assert_eq!(
+        CsrMatrix::<f64>::tridiagonal(1.0, 1.0, 1.0, 0).shape(),
+        (0, 0)
+    );
+
+    let p = CsrMatrix::poisson2d(3, 2);
+    #[rustfmt::skip]
+    assert_eq!(p.to_dense(), [
+        4.0, -1.0, 0.0, -1.0, 0.0, 0.0,
+        -1.0, 4.0, -1.0, 0.0, -1.0, 0.0,
+        0.0, -1.0, 4.0, 0.0, 0.0, -1.0,
+        -1.0, 0.0, 0.0, 4.0, -1.0, 0.0,
+        0.0, -1.0, 0.0, -1.0, 4.0, -1.0,
+        0.0, 0.0, -1.0, 0.0, -1.0, 4.0,
+    ]);
+    let p = CsrMatrix::poisson2d(20, 20);
+    assert!(p.is_hermitian());
+    assert_eq!(p.nnz(), 5 * 400 - 4 * 20);
+    assert_eq!(p.bandwidth(), (20, 20));
+    // The row sums vanish in the interior, where the stencil is complete.
+    assert_eq!(p.mul_vec(&vec![1.0; 400])[8 * 20 + 8], 0.0);
+}
+
+#[test]
+fn test_csr_slice_select() {
+    #[rustfmt::skip]
+    let a = CsrMatrix::from_dense(3, 4, &[
+        1.0, 0.0, 2.0, 0.0,
+        0.0, 3.0, 0.0, 4.0,
+        5.0, 6.0, 0.0, 7.0,
+    ]).unwrap();
+
+    #[rustfmt::skip]
+    assert_eq!(a.slice(1..3, 1..4).to_dense(), [
+        3.0, 0.0, 4.0,
+        6.0, 0.0, 7.0,
+    ]);
+    assert_eq!(a.slice(0..2, 2..2).shape(), (2, 0));
+    assert_eq!(a.slice(0..3, 0..4).to_dense(), a.to_dense());
+
+    // Reordered and repeated indices.
+    #[rustfmt::skip]
+    assert_eq!(a.select(&[2, 0, 2], &[3, 0, 0]).to_dense(), [
+        7.0, 5.0, 5.0,
+        0.0, 1.0, 1.0,
+        7.0, 5.0, 5.0,
+    ]);
+    assert_eq!(a.select(&[], &[1]).shape(), (0, 1));
+}
+
+#[test]
+#[should_panic(expected = "column range 2..5 out of bounds for 4 columns")]
+fn test_csr_slice_out_of_bounds() {
+    CsrMatrix::<f64>::new(3, 4).slice(0..1, 2..5);
+}
+
+#[test]
+fn test_csr_dense_block() {
+    use crate::test_util::Lcg;
+
+    let (m, n) = (12, 9);
+    let mut rng = Lcg::new(41);
+    let dense = rng.dense(m, n, 0.3);
+    let a = CsrMatrix::from_dense(m, n, &dense).unwrap();
+
+    let (rows, cols) = (3..10, 2..7);
+    let expected: Vec<f64> = rows
+        .clone()
+        .flat_map(|i| dense[i * n + cols.start..i * n + cols.end].to_vec())
+        .collect();
+    assert_eq!(a.dense_block(rows, cols), expected);
+    assert_eq!(a.dense_block(0..m, 0..n), dense);
+
+    // Empty blocks, and a block overlapping no stored entries.
+    assert!(a.dense_block(4..4, 0..n).is_empty());
+    assert!(a.dense_block(0..m, 5..5).is_empty());
+    #[rustfmt::skip]
+    let b = CsrMatrix::from_dense(3, 4, &[
+        1.0, 0.0, 0.0, 2.0,
+        0.0, 0.0, 0.0, 3.0,
+        4.0, 0.0, 0.0, 0.0,
+    ]).unwrap();
+    assert_eq!(b.dense_block(0..3, 1..3), vec![0.0; 6]);
+}
+
+#[test]
+#[should_panic(expected = "row range 2..4 out of bounds for 3 rows")]
+fn test_csr_dense_block_out_of_bounds() {
+    CsrMatrix::<f64>::new(3, 4).dense_block(2..4, 0..1);
+}
+
+#[test]
+fn test_dense_times_csr() {
+    use crate::test_util::{assert_close, Lcg};
+
+    let (m, k, n) = (4, 15, 11);
+    let mut rng = Lcg::new(43);
+    let mut d = rng.dense(m, k, 0.6);
+    // A zero row of `d` gives an empty row of the product.
+    d[k..2 * k].fill(0.0);
+    let a_dense = rng.dense(k, n, 0.2);
+    let a = CsrMatrix::from_dense(k, n, &a_dense).unwrap();
+
+    let product = dense_times_csr(&d, (m, k), &a).unwrap();
+    assert_eq!(product.shape(), (m, n));
+    let mut expected = vec![0.0; m * n];
+    for i in 0..m {
+        for p in 0..k {
+            for j in 0..n {
+                expected[i * n + j] += d[i * k + p] * a_dense[p * n + j];
+            }
+        }
+    }
+    assert_close(&product.to_dense(), &expected, 1e-12);
+    assert_eq!(product.row(1).0.len(), 0);
+    // It agrees with the sparse product of the densified block.
+    let d_sparse = CsrMatrix::from_dense(m, k, &d).unwrap();
+    assert_eq!(product, d_sparse.matmul(&a).unwrap());
+
+    // Empty shapes.
+    assert_eq!(dense_times_csr(&[], (0, k), &a).unwrap().shape(), (0, n));
+    let empty = dense_times_csr(&[], (3, 0), &CsrMatrix::<f64>::new(0, 5)).unwrap();
+    assert_eq!((empty.shape(), empty.nnz()), ((3, 5), 0));
+
+    assert_eq!(
+        dense_times_csr(&d[1..], (m, k), &a).unwrap_err(),
+        SparseError::DimensionMismatch {
+            expected: m * k,
+            found: m * k - 1
+        }
+    );
+    assert_eq!(
+        dense_times_csr(&d[..m * (k - 1)], (m, k - 1), &a).unwrap_err(),
+        SparseError::DimensionMismatch {
+            expected: k - 1,
+            found: k
+        }
+    );
+}
+
+#[test]
+fn test_csr_row_iter() {
+    #[rustfmt::skip]
+    let a = CsrMatrix::from_dense(3, 3, &[
+        1.0, 0.0, 2.0,
+        0.0, 0.0, 0.0,
+        3.0, 4.0, 0.0,
+    ]).unwrap();
+
+    let x = [1.0, 10.0, 100.0];
+    let y: Vec<f64> = a.row_iter().map(|row| row.dot(&x)).collect();
+    assert_eq!(y, a.mul_vec(&x));
+    assert_eq!(a.row_iter().len(), 3);
+
+    let last = a.row_iter().next_back().unwrap();
+    assert_eq!(last.index(), 2);
+    assert_eq!(last.indices(), [0, 1]);
+    assert_eq!(last.data(), [3.0, 4.0]);
+    assert_eq!(last.get(1), 4.0);
+    assert_eq!(last.get(2), 0.0);
+    assert_eq!(last.iter().collect::<Vec<_>>(), [(0, 3.0), (1, 4.0)]);
+
+    let empty = a.row_view(1);
+    assert_eq!(empty.nnz(), 0);
+    let packed = a.row_view(0).to_packed_vec();
+    assert_eq!(packed.full_len(), 3);
+    assert_eq!(packed.get(2), 2.0);
+}
+
+#[test]
+fn test_csr_diagonal() {
+    #[rustfmt::skip]
+    let mut a = CsrMatrix::from_dense(3, 4, &[
+        2.0, 1.0, 0.0, 0.0,
+        1.0, 0.0, 0.0, 5.0,
+        0.0, 0.0, 3.0, 0.0,
+    ]).unwrap();
+    let d = a.diagonal();
+    assert_eq!(d.full_len(), 3);
+    assert_eq!(d.iter().collect::<Vec<_>>(), [(0, 2.0), (2, 3.0)]);
+    assert_eq!(a.trace(), 5.0);
+
+    a.add_to_diagonal(1.0);
+    #[rustfmt::skip]
+    assert_eq!(a.to_dense(), [
+        3.0, 1.0, 0.0, 0.0,
+        1.0, 1.0, 0.0, 5.0,
+        0.0, 0.0, 4.0, 0.0,
+    ]);
+    assert_eq!(a.nnz(), 6);
+
+    a.set_diagonal(&[0.0, 7.0, 8.0]);
+    assert_eq!(a.get(0, 0), 0.0);
+    assert_eq!(a.trace(), 15.0);
+    assert_eq!(a.row(1), (&[0, 1, 3][..], &[1.0, 7.0, 5.0][..]));
+
+    // A tall matrix keeps the rows below the diagonal untouched.
+    let mut tall = CsrMatrix::from_dense(3, 2, &[0.0, 0.0, 0.0, 0.0, 9.0, 0.0]).unwrap();
+    tall.add_to_diagonal(-2.0);
+    assert_eq!(tall.to_dense(), [-2.0, 0.0, 0.0, -2.0, 9.0, 0.0]);
+}
+
+#[test]
+fn test_csr_scaling() {
+    use crate::test_util::assert_close;
+
+    let dense = [4.0, 2.0, 0.0, 2.0, 9.0, 3.0, 0.0, 3.0, 0.0];
+    let a = CsrMatrix::from_dense(3, 3, &dense).unwrap();
+
+    let mut rows = a.clone();
+    rows.scale_rows(&[1.0, 2.0, -1.0]);
+    assert_eq!(
+        rows.to_dense(),
+        [4.0, 2.0, 0.0, 4.0, 18.0, 6.0, 0.0, -3.0, 0.0]
+    );
+    let mut cols = a.clone();
+    cols.scale_cols(&[1.0, 2.0, -1.0]);
+    assert_eq!(
+        cols.to_dense(),
+        [4.0, 4.0, 0.0, 2.0, 18.0, -3.0, 0.0, 6.0, 0.0]
+    );
+
+    let mut scaled = a.clone();
+    let d = scaled.equilibrate_symmetric();
+    assert_eq!(d, [0.5, 1.0 / 3.0, 1.0]);
+    assert!(scaled.is_hermitian());
+    assert_eq!(scaled.get(0, 0), 1.0);
+    assert_close(
+        &[scaled.get(1, 1), scaled.get(0, 1)],
+        &[1.0, 1.0 / 3.0],
+        1e-15,
+    );
+
+    // (D A D) y = D b gives x = D y.
+    let x = [1.0, -1.0, 2.0];
+    let b = a.mul_vec(&x);
+    let db: Vec<f64> = b.iter().zip(&d).map(|(bi, di)| bi * di).collect();
+    let y: Vec<f64> = x.iter().zip(&d).map(|(xi, di)| xi / di).collect();
+    assert_close(&scaled.mul_vec(&y), &db, 1e-14);
+}
+
+#[test]
+fn test_csr_norms() {
+    let a = CsrMatrix::from_dense(2, 3, &[1.0, -2.0, 0.0, 0.0, 3.0, -4.0]).unwrap();
+    assert_eq!(a.norm_fro(), 30f64.sqrt());
+    assert_eq!(a.norm_one(), 5.0);
+    assert_eq!(a.norm_inf(), 7.0);
+
+    let c = a.to_csc();
+    assert_eq!(c.norm_fro(), a.norm_fro());
+    assert_eq!(c.norm_one(), 5.0);
+    assert_eq!(c.norm_inf(), 7.0);
+
+    assert_eq!(CsrMatrix::<f64>::new(2, 2).norm_inf(), 0.0);
+    let nan = CsrMatrix::from_dense(2, 1, &[f64::NAN, 5.0]).unwrap();
+    assert!(nan.norm_inf().is_nan());
+    assert!(nan.to_csc().norm_inf().is_nan());
+}
+
+#[test]
+fn test_csr_norm2_est() {
+    use crate::test_util::{assert_close, dense_norm2, Lcg};
+
+    let opts = SolverOptions::default();
+    let mut rng = Lcg::new(53);
+    for (m, n) in [(30, 20), (15, 40), (25, 25)] {
+        let dense = rng.dense(m, n, 0.3);
+        let a = CsrMatrix::from_dense(m, n, &dense).unwrap();
+        let expected = dense_norm2(m, n, &dense);
+        let (estimate, v) = a.norm2_est_with_vector(&opts);
+        assert!((estimate - expected).abs() <= 0.02 * expected);
+        assert!(estimate <= expected * (1.0 + 1e-12));
+        assert_eq!(estimate, a.norm2_est(&opts));
+        // v is a unit right singular vector: ‖A v‖ = σ₁.
+        assert!((norm2(&v) - 1.0).abs() < 1e-12);
+        assert!((norm2(&a.mul_vec(&v)) - estimate).abs() < 1e-9);
+    }
+
+    // Exact on a diagonal matrix, square or not.
+    let d = CsrMatrix::from_diagonal(&[3.0, -7.0, 2.0, 0.5]);
+    let (estimate, v) = d.norm2_est_with_vector(&opts);
+    assert_eq!(estimate, 7.0);
+    let v_abs: Vec<f64> = v.iter().map(|vi| vi.abs()).collect();
+    assert_close(&v_abs, &[0.0, 1.0, 0.0, 0.0], 1e-9);
+    assert_eq!(d.slice(0..2, 0..4).norm2_est(&opts), 7.0);
+
+    assert_eq!(CsrMatrix::<f64>::new(3, 4).norm2_est(&opts), 0.0);
+    assert_eq!(
+        CsrMatrix::<f64>::new(0, 0).norm2_est_with_vector(&opts),
+        (0.0, vec![])
+    );
+}
+
+#[test]
+fn test_csr_mul_vec_transposed() {
+    use crate::test_util::{assert_close, Lcg};
+
+    let mut rng = Lcg::new(9);
+    let a = CsrMatrix::from_dense(7, 5, &rng.dense(7, 5, 0.4)).unwrap();
+    let x: Vec<f64> = (0..7).map(|_| rng.uniform()).collect();
+    assert_close(&a.mul_vec_transposed(&x), &a.transpose().mul_vec(&x), 1e-14);
+
+    // y is overwritten, not accumulated into.
+    let mut y = vec![1.0; 5];
+    a.mul_vec_transposed_into(&x, &mut y);
+    assert_close(&y, &a.transpose().mul_vec(&x), 1e-14);
+}
+
+#[test]
+fn test_csr_eq() {
+    let a = CsrMatrix::from_dense(2, 3, &[1.0, 0.0, 2.0, 0.0, 3.0, 0.0]).unwrap();
+    // The same matrix with an explicitly stored zero at (0, 1).
+    let explicit = CsrMatrix::try_from_csr_data(
+        2,
+        3,
+        vec![0, 3, 4],
+        vec![0, 1, 2, 1],
+        vec![1.0, 0.0, 2.0, 3.0],
+    )
+    .unwrap();
+    assert_eq!(a, explicit);
+    assert_eq!(a.to_csc(), explicit.to_csc());
+    assert_ne!(
+        a,
+        CsrMatrix::from_dense(2, 3, &[1.0, 0.0, 2.0, 0.0, 3.0, 1.0]).unwrap()
+    );
+    assert_ne!(
+        a,
+        CsrMatrix::from_dense(2, 2, &[1.0, 0.0, 0.0, 3.0]).unwrap()
+    );
+    assert_ne!(CsrMatrix::<f64>::new(2, 3), CsrMatrix::new(3, 2));
+}
+
+#[cfg(feature = "approx")]
+#[test]
+fn test_csr_approx() {
+    use approx::{assert_relative_eq, assert_ulps_eq, relative_ne};
+
+    use crate::coo::CooMatrix;
+    use crate::sym::SymCsrMatrix;
+    use crate::test_util::Lcg;
+
+    // The two ways to associate a product round differently.
+    let mut rng = Lcg::new(11);
+    let mut random = || CsrMatrix::from_dense(8, 8, &rng.dense(8, 8, 0.4)).unwrap();
+    let (a, b, c) = (random(), random(), random());
+    let left = &(&a * &b) * &c;
+    let right = &a * &(&b * &c);
+    assert_relative_eq!(left, right, max_relative = 1e-12);
+    assert_relative_eq!(left.to_csc(), right.to_csc(), max_relative = 1e-12);
+    assert_ulps_eq!(left, right, max_ulps = 64);
+
+    // A tiny entry against a missing one, and matrices of different shapes.
+    let tiny = CsrMatrix::from_dense(1, 2, &[1.0, 1e-20]).unwrap();
+    let one = CsrMatrix::from_dense(1, 2, &[1.0, 0.0]).unwrap();
+    assert_relative_eq!(tiny, one, epsilon = 1e-15);
+    assert!(relative_ne!(tiny, one, epsilon = 1e-25, max_relative = 0.0));
+    assert!(relative_ne!(one, one.transpose()));
+
+    // COO compares the sums of duplicates, the symmetric format its upper triangle.
+    let mut split = CooMatrix::new(2, 2);
+    split.push(0, 1, 0.1);
+    split.push(0, 1, 0.2);
+    let mut whole = CooMatrix::new(2, 2);
+    whole.push(0, 1, 0.3);
+    assert_ne!(split, whole);
+    assert_relative_eq!(split, whole);
+    let upper = |v: f64| {
+        SymCsrMatrix::from_upper(&CsrMatrix::from_dense(2, 2, &[2.0, v, 0.0, 2.0]).unwrap())
+            .unwrap()
+    };
+    assert_relative_eq!(upper(0.1 + 0.2), upper(0.3));
+}
+
+#[test]
+fn test_csr_index_type() {
+    let a = CsrMatrix::poisson2d(5, 4);
+    let x: Vec<f64> = (0..20).map(|i| i as f64 - 7.5).collect();
+
+    let small: CsrMatrix<f64, u32> = a.to_index_type().unwrap();
+    assert_eq!(small.shape(), (20, 20));
+    assert_eq!(small.nnz(), a.nnz());
+    assert_eq!(small.indices()[..3], [0u32, 1, 5]);
+    assert_eq!(small.mul_vec(&x), a.mul_vec(&x));
+    assert_eq!(small.mul_vec_transposed(&x), a.mul_vec_transposed(&x));
+    assert_eq!(
+        crate::traits::SparseMatrix::mul_vec(&small, &x),
+        a.mul_vec(&x)
+    );
+    assert_eq!(small.to_index_type::<usize>().unwrap(), a);
+
+    let csc: CscMatrix<f64, u16> = a.to_csc().to_index_type().unwrap();
+    assert_eq!(
+        crate::traits::SparseMatrix::mul_vec(&csc, &x),
+        a.mul_vec(&x)
+    );
+    assert_eq!(csc.col(0).0, [0u16, 1, 5]);
+
+    // Column 70000 doesn't fit in u16.
+    let wide =
+        CsrMatrix::try_from_csr_data(1, 70001, vec![0, 2], vec![3, 70000], vec![1.0, 2.0]).unwrap();
+    assert_eq!(
+        wide.to_index_type::<u16>().unwrap_err(),
+        SparseError::IndexOutOfBounds {
+            index: 70000,
+            len: 65536
+        }
+    );
+}
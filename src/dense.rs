@@ -0,0 +1,112 @@
+//! Dense vector kernels for the solvers, and the dense eigensolver of the Lanczos method.
+
+use crate::simd;
+
+pub(crate) fn dot(x: &[f64], y: &[f64]) -> f64 {
+    simd::dot(x, y)
+}
+
+pub(crate) fn norm2(x: &[f64]) -> f64 {
+    sqrt(dot(x, x))
+}
+
+/// `x.sqrt()`, which core lacks: without `std` it comes from libm.
+pub(crate) fn sqrt(x: f64) -> f64 {
+    #[cfg(feature = "std")]
+    return x.sqrt();
+    #[cfg(not(feature = "std"))]
+    return libm::sqrt(x);
+}
+
+/// `x.hypot(y)`, from libm without `std` as for [`sqrt`].
+pub(crate) fn hypot(x: f64, y: f64) -> f64 {
+    #[cfg(feature = "std")]
+    return x.hypot(y);
+    #[cfg(not(feature = "std"))]
+    return libm::hypot(x, y);
+}
+
+/// `y += alpha * x`
+pub(crate) fn axpy(alpha: f64, x: &[f64], y: &mut [f64]) {
+    simd::axpy(alpha, x, y)
+}
+
+/// Compute the eigenvalues and eigenvectors of a symmetric tridiagonal matrix by the QL
+/// algorithm with implicit Wilkinson shifts, as in EISPACK's `tql2`.
+///
+/// On entry `d` holds the diagonal and `e[i]` the entry coupling `i` and `i + 1`, with `e` as
+/// long as `d` and its last entry ignored. On return `d` holds the eigenvalues, unsorted, and
+/// column `i` of the row-major `n` by `n` matrix `z` the unit eigenvector of `d[i]`; `e` is
+/// overwritten. Return false if an eigenvalue takes more than 30 iterations, which leaves the
+/// results unspecified. O(n³) with the eigenvectors.
+pub(crate) fn symmetric_tridiagonal_eigen(d: &mut [f64], e: &mut [f64], z: &mut [f64]) -> bool {
+    let n = d.len();
+    z.iter_mut().for_each(|zi| *zi = 0.0);
+    for i in 0..n {
+        z[i * n + i] = 1.0;
+    }
+    if n == 0 {
+        return true;
+    }
+    e[n - 1] = 0.0;
+
+    for l in 0..n {
+        let mut iterations = 0;
+        loop {
+            // Look for a negligible off-diagonal entry that splits the matrix at m.
+            let mut m = l;
+            while m + 1 < n {
+                let dd = d[m].abs() + d[m + 1].abs();
+                if e[m].abs() <= f64::EPSILON * dd {
+                    break;
+                }
+                m += 1;
+            }
+            if m == l {
+                break;
+            }
+            iterations += 1;
+            if iterations > 30 {
+                return false;
+            }
+
+            // The Wilkinson shift from the leading 2 by 2 block, then the chase from m up to l.
+            let mut g = (d[l + 1] - d[l]) / (2.0 * e[l]);
+            let r = hypot(g, 1.0);
+            g = d[m] - d[l] + e[l] / (g + if g >= 0.0 { r } else { -r });
+            let (mut s, mut c, mut p) = (1.0, 1.0, 0.0);
+            let mut underflow = false;
+            for i in (l..m).rev() {
+                let f = s * e[i];
+                let b = c * e[i];
+                let r = hypot(f, g);
+                e[i + 1] = r;
+                if r == 0.0 {
+                    d[i + 1] -= p;
+                    e[m] = 0.0;
+                    underflow = true;
+                    break;
+                }
+                s = f / r;
+                c = g / r;
+                g = d[i + 1] - p;
+                let r = (d[i] - g) * s + 2.0 * c * b;
+                p = s * r;
+                d[i + 1] = g + p;
+                g = c * r - b;
+                for k in 0..n {
+                    let f = z[k * n + i + 1];
+                    z[k * n + i + 1] = s * z[k * n + i] + c * f;
+                    z[k * n + i] = c * z[k * n + i] - s * f;
+                }
+            }
+            if underflow {
+                continue;
+            }
+            d[l] -= p;
+            e[l] = g;
+            e[m] = 0.0;
+        }
+    }
+    true
+}
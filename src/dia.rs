@@ -0,0 +1,254 @@
+use alloc::format;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::csr::CsrMatrix;
+use crate::error::SparseError;
+use crate::scalar::Scalar;
+
+/// A sparse matrix in diagonal (DIA) form: a few whole diagonals, each identified by its offset
+/// from the main diagonal.
+///
+/// Diagonal `k` holds the entries `(i, i + offsets[k])`, stored by row in
+/// `data[k * nrows..(k + 1) * nrows]`; positions that fall outside the matrix are padding. The
+/// matrix-vector product then needs no index arrays at all and streams through `x` and `y`
+/// contiguously, which makes DIA the fastest format for banded and stencil matrices. It only
+/// pays off when the entries sit on a small number of diagonals.
+#[derive(Clone, Debug)]
+pub struct DiaMatrix<T = f64> {
+    nrows: usize,
+    ncols: usize,
+    /// Offset of each stored diagonal, increasing: negative below the main diagonal, positive
+    /// above
+    offsets: Vec<isize>,
+    /// Values of the diagonals, `nrows` per diagonal
+    data: Vec<T>,
+}
+
+impl<T: Scalar> DiaMatrix<T> {
+    /// Collect the diagonals of a CSR matrix that hold at least one entry. Fails with
+    /// [`SparseError::InvalidStructure`] when there are more than `max_diagonals` of them, since
+    /// the padded storage would then outgrow the CSR form. O(nnz + nrows + ncols) plus the size
+    /// of the diagonals.
+    pub fn from_csr(a: &CsrMatrix<T>, max_diagonals: usize) -> Result<Self, SparseError> {
+        let (nrows, ncols) = a.shape();
+
+        // Diagonal with offset o is at `occupied[o + nrows]`.
+        let mut occupied = vec![false; nrows + ncols];
+        for i in 0..nrows {
+            for &j in a.row(i).0 {
+                occupied[j + nrows - i] = true;
+            }
+        }
+        let offsets: Vec<isize> = (0..nrows + ncols)
+            .filter(|&k| occupied[k])
+            .map(|k| k as isize - nrows as isize)
+            .collect();
+        if offsets.len() > max_diagonals {
+            return Err(SparseError::InvalidStructure(format!(
+                "the matrix has {} occupied diagonals, more than the limit of {max_diagonals}",
+                offsets.len()
+            )));
+        }
+
+        // `slot[o + nrows]` is the position of the diagonal with offset o in `offsets`.
+        let mut slot = vec![0; nrows + ncols];
+        for (k, &offset) in offsets.iter().enumerate() {
+            slot[(offset + nrows as isize) as usize] = k;
+        }
+        let mut data = vec![T::zero(); offsets.len() * nrows];
+        for i in 0..nrows {
+            let (cols, values) = a.row(i);
+            for (&j, &v) in cols.iter().zip(values) {
+                data[slot[j + nrows - i] * nrows + i] += v;
+            }
+        }
+
+        Ok(Self {
+            nrows,
+            ncols,
+            offsets,
+            data,
+        })
+    }
+
+    /// Return the number of rows
+    pub fn nrows(&self) -> usize {
+        self.nrows
+    }
+
+    /// Return the number of columns
+    pub fn ncols(&self) -> usize {
+        self.ncols
+    }
+
+    /// Return `(nrows, ncols)`
+    pub fn shape(&self) -> (usize, usize) {
+        (self.nrows, self.ncols)
+    }
+
+    /// Return the number of stored entries that lie inside the matrix, the padding of the
+    /// diagonals left out. O(number of diagonals).
+    pub fn nnz(&self) -> usize {
+        self.offsets
+            .iter()
+            .map(|&offset| self.rows_in_bounds(offset).len())
+            .sum()
+    }
+
+    /// Return the offsets of the stored diagonals, in increasing order
+    pub fn offsets(&self) -> &[isize] {
+        &self.offsets
+    }
+
+    /// Return diagonal `k` by row: element `i` is the entry `(i, i + offsets()[k])`, or padding
+    /// when that lies outside the matrix.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `k` is out of bounds.
+    pub fn diagonal(&self, k: usize) -> &[T] {
+        &self.data[k * self.nrows..][..self.nrows]
+    }
+
+    /// Multiply the matrix by the dense vector `x`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `x.len() != self.ncols()`.
+    pub fn mul_vec(&self, x: &[T]) -> Vec<T> {
+        assert_eq!(x.len(), self.ncols, "x has the wrong length");
+
+        let mut y = vec![T::zero(); self.nrows];
+        for (k, &offset) in self.offsets.iter().enumerate() {
+            let rows = self.rows_in_bounds(offset);
+            let cols =
+                (rows.start as isize + offset) as usize..(rows.end as isize + offset) as usize;
+            let diagonal = &self.diagonal(k)[rows.clone()];
+            for ((yi, &d), &xj) in y[rows].iter_mut().zip(diagonal).zip(&x[cols]) {
+                *yi += d * xj;
+            }
+        }
+        y
+    }
+
+    /// Convert to CSR, leaving out the padding and the zeros stored on the diagonals.
+    /// O(nrows × number of diagonals).
+    pub fn to_csr(&self) -> CsrMatrix<T> {
+        let ranges: Vec<Range<usize>> = self
+            .offsets
+            .iter()
+            .map(|&offset| self.rows_in_bounds(offset))
+            .collect();
+
+        let mut indptr = Vec::with_capacity(self.nrows + 1);
+        let mut indices = Vec::new();
+        let mut data = Vec::new();
+        indptr.push(0);
+        for i in 0..self.nrows {
+            // Increasing offsets give increasing columns within the row.
+            for (k, &offset) in self.offsets.iter().enumerate() {
+                let v = self.data[k * self.nrows + i];
+                if ranges[k].contains(&i) && v != T::zero() {
+                    indices.push((i as isize + offset) as usize);
+                    data.push(v);
+                }
+            }
+            indptr.push(indices.len());
+        }
+
+        CsrMatrix::from_parts(self.nrows, self.ncols, indptr, indices, data)
+    }
+
+    /// Rows `i` for which `(i, i + offset)` lies inside the matrix.
+    fn rows_in_bounds(&self, offset: isize) -> Range<usize> {
+        let start = (-offset).max(0) as usize;
+        let end = (self.ncols as isize - offset).clamp(0, self.nrows as isize) as usize;
+        start.min(end)..end
+    }
+}
+
+#[test]
+fn test_dia_matrix() {
+    #[rustfmt::skip]
+    let dense = [
+        4.0, -1.0, 0.0, 0.0, 0.0,
+        -1.0, 4.0, -1.0, 0.0, 0.0,
+        2.0, -1.0, 4.0, -1.0, 0.0,
+        0.0, 2.0, -1.0, 4.0, -1.0,
+    ];
+    let a = CsrMatrix::from_dense(4, 5, &dense).unwrap();
+    let dia = DiaMatrix::from_csr(&a, 4).unwrap();
+    assert_eq!(dia.offsets(), [-2, -1, 0, 1]);
+    assert_eq!(dia.diagonal(0)[2..], [2.0, 2.0]);
+    assert_eq!(dia.diagonal(3), [-1.0; 4]);
+    assert_eq!(dia.nnz(), 13);
+    assert_eq!(dia.to_csr().to_dense(), dense);
+
+    let x = [1.0, 2.0, -1.0, 0.5, 3.0];
+    assert_eq!(dia.mul_vec(&x), a.mul_vec(&x));
+
+    // Tall, with a diagonal that only touches the corner.
+    let mut dense = [0.0; 6 * 3];
+    dense[5 * 3] = 7.0;
+    dense[4] = 1.0;
+    let a = CsrMatrix::from_dense(6, 3, &dense).unwrap();
+    let dia = DiaMatrix::from_csr(&a, 2).unwrap();
+    assert_eq!(dia.offsets(), [-5, 0]);
+    assert_eq!(dia.nnz(), 4);
+    assert_eq!(dia.to_csr().to_dense(), dense);
+    assert_eq!(
+        dia.mul_vec(&[1.0, 2.0, 3.0]),
+        [0.0, 2.0, 0.0, 0.0, 0.0, 7.0]
+    );
+
+    assert!(matches!(
+        DiaMatrix::from_csr(&a, 1),
+        Err(SparseError::InvalidStructure(_))
+    ));
+}
+
+#[test]
+fn test_dia_matrix_offsets_outside_the_band() {
+    // The extreme diagonals of a wide matrix each touch one corner only: the rest of them is
+    // padding, which is NaN here to show that nothing reads it.
+    #[rustfmt::skip]
+    let dense = [
+        0.0, 0.0, 0.0, 0.0, 5.0,
+        0.0, 0.0, 0.0, 0.0, 0.0,
+        3.0, 0.0, 0.0, 0.0, 0.0,
+    ];
+    let a = CsrMatrix::from_dense(3, 5, &dense).unwrap();
+    let mut dia = DiaMatrix::from_csr(&a, 2).unwrap();
+    assert_eq!(dia.offsets(), [-2, 4]);
+    assert_eq!(dia.rows_in_bounds(-2), 2..3);
+    assert_eq!(dia.rows_in_bounds(4), 0..1);
+    for (k, i) in [(0, 0), (0, 1), (1, 1), (1, 2)] {
+        dia.data[k * 3 + i] = f64::NAN;
+    }
+    assert_eq!(dia.nnz(), 2);
+    assert_eq!(dia.to_csr().to_dense(), dense);
+    assert_eq!(dia.mul_vec(&[1.0, 2.0, 3.0, 4.0, 5.0]), [25.0, 0.0, 3.0]);
+
+    // Offsets past the last column or the last row select no row at all, and a diagonal
+    // stored there contributes nothing.
+    for offset in [5, 9, -3, -8] {
+        assert!(dia.rows_in_bounds(offset).is_empty(), "{offset}");
+    }
+    let outside = DiaMatrix {
+        nrows: 3,
+        ncols: 5,
+        offsets: vec![-3, 5],
+        data: vec![1.0; 6],
+    };
+    assert_eq!(outside.nnz(), 0);
+    assert_eq!(outside.to_csr().nnz(), 0);
+    assert_eq!(outside.mul_vec(&[1.0; 5]), [0.0; 3]);
+
+    // With no rows or no columns every offset is outside.
+    let empty = DiaMatrix::from_csr(&CsrMatrix::<f64>::new(0, 4), 0).unwrap();
+    assert!(empty.offsets().is_empty());
+    assert!(empty.rows_in_bounds(0).is_empty());
+    assert!(empty.mul_vec(&[1.0; 4]).is_empty());
+}
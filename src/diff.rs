@@ -0,0 +1,323 @@
+//! Entry-by-entry comparison of two CSR matrices, to track down where two assembly codes that
+//! should agree part ways.
+//!
+//! [`CsrMatrix::diff`] merges the two matrices row by row over the union of their patterns, so
+//! it costs O(nnz) and never allocates dense storage, and sorts the entries into those stored
+//! only in one matrix and those stored in both whose values differ by more than a tolerance.
+//! The [`MatrixDiff`] it returns keeps the counts and the first few entries of each kind, and
+//! prints as a short report that can go straight into a bug report.
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::csr::CsrMatrix;
+use crate::ops::merge::{all_union, UnionStep};
+
+/// An entry stored in one matrix only, as `(row, column, value)`
+pub type DiffEntry = (usize, usize, f64);
+
+/// The differences between two matrices `A` and `B`, from [`CsrMatrix::diff`].
+///
+/// An entry stored in only one of the matrices counts as only in that matrix even when its
+/// value is zero, as the patterns differ. The listed entries are the first ones in row-major
+/// order, at most [`MatrixDiff::LIST_CAP`] of each kind.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MatrixDiff {
+    /// Shape of `A`
+    pub shape_a: (usize, usize),
+    /// Shape of `B`
+    pub shape_b: (usize, usize),
+    /// Number of stored entries of `A`
+    pub nnz_a: usize,
+    /// Number of stored entries of `B`
+    pub nnz_b: usize,
+    /// The tolerance the values were compared with
+    pub tol: f64,
+    /// Number of entries stored in `A` but not in `B`
+    pub only_in_a: usize,
+    /// Number of entries stored in `B` but not in `A`
+    pub only_in_b: usize,
+    /// Number of entries stored in both whose values differ by more than `tol`
+    pub differing: usize,
+    /// The first entries only in `A`, as `(row, column, value)`
+    pub only_in_a_entries: Vec<DiffEntry>,
+    /// The first entries only in `B`, as `(row, column, value)`
+    pub only_in_b_entries: Vec<DiffEntry>,
+    /// The first differing entries, as `(row, column, value in A, value in B)`
+    pub differing_entries: Vec<(usize, usize, f64, f64)>,
+    /// The largest `|a − b|` over the union of the patterns, a missing entry counting as zero
+    pub max_abs: f64,
+    /// Where `max_abs` is reached, `None` when both matrices have no entries
+    pub max_abs_at: Option<(usize, usize)>,
+    /// The largest `|a − b| / max(|a|, |b|)` over the union of the patterns
+    pub max_rel: f64,
+    /// Where `max_rel` is reached, `None` when no entry differs
+    pub max_rel_at: Option<(usize, usize)>,
+}
+
+impl MatrixDiff {
+    /// The number of entries of each kind listed in the diff
+    pub const LIST_CAP: usize = 10;
+
+    /// Return true when the shapes agree and no entry is only in one matrix or differs by
+    /// more than the tolerance.
+    pub fn is_empty(&self) -> bool {
+        self.shape_a == self.shape_b
+            && self.only_in_a == 0
+            && self.only_in_b == 0
+            && self.differing == 0
+    }
+
+    /// Account for an entry stored in one matrix only.
+    fn push_only(count: &mut usize, entries: &mut Vec<DiffEntry>, entry: DiffEntry) {
+        *count += 1;
+        if entries.len() < Self::LIST_CAP {
+            entries.push(entry);
+        }
+    }
+
+    /// Account for the discrepancy `|a − b|` at `(i, j)` in the maxima.
+    fn record_discrepancy(&mut self, i: usize, j: usize, a: f64, b: f64) {
+        let abs = (a - b).abs();
+        if abs > self.max_abs || self.max_abs_at.is_none() {
+            self.max_abs = abs;
+            self.max_abs_at = Some((i, j));
+        }
+        if abs > 0.0 {
+            let rel = abs / a.abs().max(b.abs());
+            if rel > self.max_rel {
+                self.max_rel = rel;
+                self.max_rel_at = Some((i, j));
+            }
+        }
+    }
+}
+
+impl fmt::Display for MatrixDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (ma, na) = self.shape_a;
+        let (mb, nb) = self.shape_b;
+        writeln!(
+            f,
+            "matrix diff: A is {ma}x{na} with {} entries, B is {mb}x{nb} with {} entries, tol {:e}",
+            self.nnz_a, self.nnz_b, self.tol
+        )?;
+        if self.is_empty() {
+            return writeln!(f, "  no differences");
+        }
+        if self.shape_a != self.shape_b {
+            writeln!(f, "  shapes differ")?;
+        }
+
+        let lists: [(&str, usize, &[DiffEntry]); 2] = [
+            ("only in A", self.only_in_a, &self.only_in_a_entries),
+            ("only in B", self.only_in_b, &self.only_in_b_entries),
+        ];
+        for (label, count, entries) in lists {
+            if count == 0 {
+                continue;
+            }
+            writeln!(f, "  {count} entries {label}:")?;
+            for &(i, j, v) in entries {
+                writeln!(f, "    ({i}, {j}) = {v:e}")?;
+            }
+            if count > entries.len() {
+                writeln!(f, "    ... and {} more", count - entries.len())?;
+            }
+        }
+        if self.differing > 0 {
+            writeln!(f, "  {} entries differ by more than tol:", self.differing)?;
+            for &(i, j, a, b) in &self.differing_entries {
+                writeln!(
+                    f,
+                    "    ({i}, {j}): A = {a:e}, B = {b:e}, |A - B| = {:e}",
+                    (a - b).abs()
+                )?;
+            }
+            if self.differing > self.differing_entries.len() {
+                writeln!(
+                    f,
+                    "    ... and {} more",
+                    self.differing - self.differing_entries.len()
+                )?;
+            }
+        }
+
+        if let Some((i, j)) = self.max_abs_at {
+            writeln!(f, "  max abs discrepancy {:e} at ({i}, {j})", self.max_abs)?;
+        }
+        if let Some((i, j)) = self.max_rel_at {
+            writeln!(f, "  max rel discrepancy {:e} at ({i}, {j})", self.max_rel)?;
+        }
+        Ok(())
+    }
+}
+
+impl CsrMatrix<f64> {
+    /// Compare the matrix `A = self` with `other = B` entry by entry, see [`MatrixDiff`]. Two
+    /// entries stored in both differ when `|a − b| > tol`.
+    ///
+    /// The rows are merged pairwise over the union of their patterns, in O(nnz(A) + nnz(B))
+    /// and without dense storage. When the shapes differ the rows and columns past the end of
+    /// one matrix hold entries only in the other.
+    ///
+    /// ```
+    /// use sparse_matrix::prelude::*;
+    ///
+    /// let a = CsrMatrix::from_dense(2, 2, &[1.0, 2.0, 0.0, 3.0]).unwrap();
+    /// let b = CsrMatrix::from_dense(2, 2, &[1.0, 0.0, 4.0, 3.5]).unwrap();
+    /// let diff = a.diff(&b, 1e-12);
+    /// assert_eq!((diff.only_in_a, diff.only_in_b, diff.differing), (1, 1, 1));
+    /// assert_eq!(diff.differing_entries, [(1, 1, 3.0, 3.5)]);
+    /// ```
+    pub fn diff(&self, other: &CsrMatrix<f64>, tol: f64) -> MatrixDiff {
+        let mut diff = MatrixDiff {
+            shape_a: self.shape(),
+            shape_b: other.shape(),
+            nnz_a: self.nnz(),
+            nnz_b: other.nnz(),
+            tol,
+            only_in_a: 0,
+            only_in_b: 0,
+            differing: 0,
+            only_in_a_entries: Vec::new(),
+            only_in_b_entries: Vec::new(),
+            differing_entries: Vec::new(),
+            max_abs: 0.0,
+            max_abs_at: None,
+            max_rel: 0.0,
+            max_rel_at: None,
+        };
+
+        let empty: (&[usize], &[f64]) = (&[], &[]);
+        for i in 0..self.nrows().max(other.nrows()) {
+            let (a_cols, a_values) = if i < self.nrows() { self.row(i) } else { empty };
+            let (b_cols, b_values) = if i < other.nrows() {
+                other.row(i)
+            } else {
+                empty
+            };
+            all_union(a_cols, b_cols, |step| {
+                match step {
+                    UnionStep::Left(run) => {
+                        for k in run {
+                            let (j, v) = (a_cols[k], a_values[k]);
+                            MatrixDiff::push_only(
+                                &mut diff.only_in_a,
+                                &mut diff.only_in_a_entries,
+                                (i, j, v),
+                            );
+                            diff.record_discrepancy(i, j, v, 0.0);
+                        }
+                    }
+                    UnionStep::Right(run) => {
+                        for k in run {
+                            let (j, v) = (b_cols[k], b_values[k]);
+                            MatrixDiff::push_only(
+                                &mut diff.only_in_b,
+                                &mut diff.only_in_b_entries,
+                                (i, j, v),
+                            );
+                            diff.record_discrepancy(i, j, 0.0, v);
+                        }
+                    }
+                    UnionStep::Both(ka, kb) => {
+                        let (j, a, b) = (a_cols[ka], a_values[ka], b_values[kb]);
+                        // NaNs count as different, so that they show up.
+                        if (a - b).abs() > tol || (a - b).is_nan() {
+                            diff.differing += 1;
+                            if diff.differing_entries.len() < MatrixDiff::LIST_CAP {
+                                diff.differing_entries.push((i, j, a, b));
+                            }
+                        }
+                        diff.record_discrepancy(i, j, a, b);
+                    }
+                }
+                true
+            });
+        }
+        diff
+    }
+}
+
+#[test]
+fn test_matrix_diff() {
+    use crate::test_util::Lcg;
+
+    let (m, n) = (30, 25);
+    let mut rng = Lcg::new(47);
+    let mut dense = rng.dense(m, n, 0.2);
+    // Keep the planted positions out of the random pattern.
+    for j in 0..n {
+        dense[3 * n + j] = 0.0;
+    }
+    dense[3 * n + 4] = 1.5;
+    dense[3 * n + 9] = 2.5;
+    dense[3 * n + 12] = -1.0;
+    dense[3 * n + 20] = 4.0;
+    let a = CsrMatrix::from_dense(m, n, &dense).unwrap();
+
+    let mut planted = dense.clone();
+    planted[3 * n + 4] = 0.0; // only in A
+    planted[3 * n + 7] = -2.0; // only in B
+    planted[3 * n + 9] = 2.5 + 1e-3; // differs
+    planted[3 * n + 12] = -1.0 + 1e-15; // within tol
+    planted[3 * n + 20] = 3.0; // differs
+    let b = CsrMatrix::from_dense(m, n, &planted).unwrap();
+
+    let diff = a.diff(&b, 1e-12);
+    assert!(!diff.is_empty());
+    assert_eq!((diff.only_in_a, diff.only_in_b, diff.differing), (1, 1, 2));
+    assert_eq!(diff.only_in_a_entries, [(3, 4, 1.5)]);
+    assert_eq!(diff.only_in_b_entries, [(3, 7, -2.0)]);
+    assert_eq!(
+        diff.differing_entries,
+        [(3, 9, 2.5, 2.5 + 1e-3), (3, 20, 4.0, 3.0)]
+    );
+    assert_eq!((diff.max_abs, diff.max_abs_at), (2.0, Some((3, 7))));
+    assert_eq!((diff.max_rel, diff.max_rel_at), (1.0, Some((3, 4))));
+
+    // The reverse diff swaps the sides.
+    let reverse = b.diff(&a, 1e-12);
+    assert_eq!(reverse.only_in_a_entries, [(3, 7, -2.0)]);
+    assert_eq!(reverse.only_in_b_entries, [(3, 4, 1.5)]);
+
+    // Identical matrices, and a loose enough tolerance.
+    let same = a.diff(&a.clone(), 0.0);
+    assert!(same.is_empty());
+    assert_eq!(
+        (same.max_abs, same.max_rel, same.max_rel_at),
+        (0.0, 0.0, None)
+    );
+    assert!(same.only_in_a_entries.is_empty() && same.differing_entries.is_empty());
+    assert!(same.to_string().ends_with("  no differences\n"));
+    assert_eq!(a.diff(&b, 1.5).differing, 0);
+}
+
+#[test]
+fn test_matrix_diff_report() {
+    let a = CsrMatrix::from_dense(2, 3, &[1.0, 0.0, 2.0, 0.0, 3.0, 0.0]).unwrap();
+    let b = CsrMatrix::from_dense(3, 3, &[1.0, 0.5, 2.0, 0.0, 3.25, 0.0, 0.0, 0.0, 6.0]).unwrap();
+    let diff = a.diff(&b, 1e-9);
+    assert_eq!(
+        diff.to_string(),
+        "matrix diff: A is 2x3 with 3 entries, B is 3x3 with 5 entries, tol 1e-9\n  \
+         shapes differ\n  \
+         2 entries only in B:\n    \
+         (0, 1) = 5e-1\n    \
+         (2, 2) = 6e0\n  \
+         1 entries differ by more than tol:\n    \
+         (1, 1): A = 3e0, B = 3.25e0, |A - B| = 2.5e-1\n  \
+         max abs discrepancy 6e0 at (2, 2)\n  \
+         max rel discrepancy 1e0 at (0, 1)\n"
+    );
+
+    // The lists are capped, the counts aren't.
+    let n = 3 * MatrixDiff::LIST_CAP;
+    let diff = CsrMatrix::<f64>::identity(n).diff(&CsrMatrix::new(n, n), 0.0);
+    assert_eq!(diff.only_in_a, n);
+    assert_eq!(diff.only_in_a_entries.len(), MatrixDiff::LIST_CAP);
+    assert!(diff
+        .to_string()
+        .contains(&alloc::format!("... and {} more", n - MatrixDiff::LIST_CAP)));
+}
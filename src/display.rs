@@ -0,0 +1,80 @@
+//! `Display` helpers shared by the sparse containers. Small matrices are drawn as a dense grid,
+//! larger ones as a truncated list of triplets, so printing never floods the terminal.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use core::fmt;
+
+/// Matrices with at most this many rows and columns are drawn as a dense grid.
+pub(crate) const DENSE_VIEW_MAX_DIM: usize = 10;
+
+/// At most this many entries are listed before the output is cut short.
+pub(crate) const MAX_LISTED_ENTRIES: usize = 20;
+
+/// Format `v` with the precision given to the outer formatter, if any.
+pub(crate) fn value_string<T: fmt::Display>(v: T, f: &fmt::Formatter<'_>) -> String {
+    match f.precision() {
+        Some(p) => format!("{v:.p$}"),
+        None => format!("{v}"),
+    }
+}
+
+/// Draw a small matrix as rows of right-aligned values, with `.` marking structural zeros.
+/// `entries` yields the stored `(row, column, value)` triplets in any order.
+pub(crate) fn fmt_dense<T: fmt::Display>(
+    f: &mut fmt::Formatter<'_>,
+    nrows: usize,
+    ncols: usize,
+    entries: impl Iterator<Item = (usize, usize, T)>,
+) -> fmt::Result {
+    let mut grid = vec![String::from("."); nrows * ncols];
+    for (i, j, v) in entries {
+        grid[i * ncols + j] = value_string(v, f);
+    }
+
+    let width = grid.iter().map(|s| s.chars().count()).max().unwrap_or(1);
+    for row in grid.chunks(ncols.max(1)).take(nrows) {
+        write!(f, "\n[")?;
+        for (j, cell) in row.iter().enumerate() {
+            if j > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{cell:>width$}")?;
+        }
+        write!(f, "]")?;
+    }
+    Ok(())
+}
+
+/// List the first [`MAX_LISTED_ENTRIES`] of `nnz` triplets, one per line, and say how many
+/// were left out.
+pub(crate) fn fmt_triplets<T: fmt::Display>(
+    f: &mut fmt::Formatter<'_>,
+    nnz: usize,
+    entries: impl Iterator<Item = (usize, usize, T)>,
+) -> fmt::Result {
+    for (i, j, v) in entries.take(MAX_LISTED_ENTRIES) {
+        write!(f, "\n({i}, {j}) {}", value_string(v, f))?;
+    }
+    if nnz > MAX_LISTED_ENTRIES {
+        write!(f, "\n... {} more", nnz - MAX_LISTED_ENTRIES)?;
+    }
+    Ok(())
+}
+
+/// Write the header line and body of a compressed matrix. `format` names the storage format.
+pub(crate) fn fmt_matrix<T: fmt::Display>(
+    f: &mut fmt::Formatter<'_>,
+    format: &str,
+    (nrows, ncols): (usize, usize),
+    nnz: usize,
+    entries: impl Iterator<Item = (usize, usize, T)>,
+) -> fmt::Result {
+    write!(f, "{nrows}x{ncols} {format} matrix, {nnz} stored entries")?;
+    if nrows <= DENSE_VIEW_MAX_DIM && ncols <= DENSE_VIEW_MAX_DIM {
+        fmt_dense(f, nrows, ncols, entries)
+    } else {
+        fmt_triplets(f, nnz, entries)
+    }
+}
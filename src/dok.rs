@@ -0,0 +1,176 @@
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+
+#[cfg(not(feature = "std"))]
+use hashbrown::HashMap;
+
+use crate::coo::CooMatrix;
+use crate::csr::CsrMatrix;
+use crate::scalar::Scalar;
+
+/// A sparse matrix in dictionary of keys (DOK) form: a hash map from `(row, column)` to value.
+///
+/// Reading, inserting, updating and removing a single entry take expected O(1), in any order,
+/// which suits algorithms that revisit scattered entries many times before the matrix is
+/// finished. Convert to CSR for arithmetic. The map holds no explicit zeros: storing a zero
+/// removes the entry.
+#[derive(Clone, Debug)]
+pub struct DokMatrix<T = f64> {
+    nrows: usize,
+    ncols: usize,
+    entries: HashMap<(usize, usize), T>,
+}
+
+impl<T: Scalar> DokMatrix<T> {
+    /// Create a `nrows` by `ncols` matrix with no stored entries.
+    pub fn new(nrows: usize, ncols: usize) -> Self {
+        Self {
+            nrows,
+            ncols,
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Return the number of rows
+    pub fn nrows(&self) -> usize {
+        self.nrows
+    }
+
+    /// Return the number of columns
+    pub fn ncols(&self) -> usize {
+        self.ncols
+    }
+
+    /// Return `(nrows, ncols)`
+    pub fn shape(&self) -> (usize, usize) {
+        (self.nrows, self.ncols)
+    }
+
+    /// Return the number of stored entries
+    pub fn nnz(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Return the entry at `(i, j)`, zero when nothing is stored there.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` or `j` is out of bounds.
+    pub fn get(&self, i: usize, j: usize) -> T {
+        self.check_bounds(i, j);
+        self.entries.get(&(i, j)).copied().unwrap_or(T::zero())
+    }
+
+    /// Set the entry at `(i, j)` to `v`, removing it when `v` is zero, and return the value it
+    /// had before.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` or `j` is out of bounds.
+    pub fn insert(&mut self, i: usize, j: usize, v: T) -> T {
+        self.check_bounds(i, j);
+        let old = if v == T::zero() {
+            self.entries.remove(&(i, j))
+        } else {
+            self.entries.insert((i, j), v)
+        };
+        old.unwrap_or(T::zero())
+    }
+
+    /// Add `v` to the entry at `(i, j)`, removing it if the sum is zero.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` or `j` is out of bounds.
+    pub fn add(&mut self, i: usize, j: usize, v: T) {
+        let sum = self.get(i, j) + v;
+        self.insert(i, j, sum);
+    }
+
+    /// Remove the entry at `(i, j)` and return its value, zero when nothing was stored there.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` or `j` is out of bounds.
+    pub fn remove(&mut self, i: usize, j: usize) -> T {
+        self.insert(i, j, T::zero())
+    }
+
+    /// Iterate over the stored `(row, column, value)` triplets, in no particular order.
+    pub fn triplets(&self) -> impl Iterator<Item = (usize, usize, T)> + '_ {
+        self.entries.iter().map(|(&(i, j), &v)| (i, j, v))
+    }
+
+    /// Convert to COO, with the triplets sorted by row and then column. O(nnz log nnz).
+    pub fn to_coo(&self) -> CooMatrix<T> {
+        let mut triplets: Vec<(usize, usize, T)> = self.triplets().collect();
+        triplets.sort_unstable_by_key(|&(i, j, _)| (i, j));
+
+        let mut coo = CooMatrix::new(self.nrows, self.ncols);
+        for (i, j, v) in triplets {
+            coo.push(i, j, v);
+        }
+        coo
+    }
+
+    /// Compress to CSR. O(nnz + nrows + ncols).
+    pub fn to_csr(&self) -> CsrMatrix<T> {
+        let mut coo = CooMatrix::new(self.nrows, self.ncols);
+        for (i, j, v) in self.triplets() {
+            coo.push(i, j, v);
+        }
+        coo.to_csr()
+    }
+
+    fn check_bounds(&self, i: usize, j: usize) {
+        assert!(
+            i < self.nrows,
+            "row {i} out of bounds for {} rows",
+            self.nrows
+        );
+        assert!(
+            j < self.ncols,
+            "column {j} out of bounds for {} columns",
+            self.ncols
+        );
+    }
+}
+
+#[test]
+fn test_dok_matrix() {
+    let mut dok = DokMatrix::new(3, 4);
+    assert_eq!(dok.insert(2, 1, 3.0), 0.0);
+    assert_eq!(dok.insert(2, 1, 5.0), 3.0);
+    dok.insert(0, 3, 2.0);
+    dok.add(0, 0, 1.0);
+    dok.add(0, 0, 0.5);
+    dok.add(1, 2, 7.0);
+    assert_eq!(dok.nnz(), 4);
+    assert_eq!(dok.get(0, 0), 1.5);
+    assert_eq!(dok.get(1, 1), 0.0);
+
+    // Zeros are never stored.
+    dok.add(1, 2, -7.0);
+    assert_eq!(dok.remove(0, 3), 2.0);
+    dok.insert(2, 2, 0.0);
+    assert_eq!(dok.nnz(), 2);
+
+    let coo = dok.to_coo();
+    assert_eq!(
+        coo.triplets().collect::<Vec<_>>(),
+        [(0, 0, 1.5), (2, 1, 5.0)]
+    );
+    #[rustfmt::skip]
+    assert_eq!(dok.to_csr().to_dense(), [
+        1.5, 0.0, 0.0, 0.0,
+        0.0, 0.0, 0.0, 0.0,
+        0.0, 5.0, 0.0, 0.0,
+    ]);
+}
+
+#[test]
+#[should_panic(expected = "column 4 out of bounds for 4 columns")]
+fn test_dok_out_of_bounds() {
+    DokMatrix::new(3, 4).insert(0, 4, 1.0);
+}
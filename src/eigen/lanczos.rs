@@ -0,0 +1,303 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use super::{start_vector, Eigenpair};
+use crate::dense::{axpy, dot, norm2, sqrt, symmetric_tridiagonal_eigen};
+use crate::error::SparseError;
+use crate::iterative::SolverOptions;
+use crate::operator::LinearOperator;
+
+/// The end of the spectrum [`lanczos`] looks for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Extreme {
+    /// The algebraically largest eigenvalues, in decreasing order
+    Largest,
+    /// The algebraically smallest eigenvalues, in increasing order
+    Smallest,
+    /// The eigenvalues of largest magnitude, in decreasing magnitude
+    LargestMagnitude,
+}
+
+/// How [`lanczos`] keeps its basis orthogonal. Without reorthogonalization the Lanczos vectors
+/// lose their orthogonality as soon as a Ritz pair converges, and converged eigenvalues come
+/// back as spurious copies.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Reorthogonalization {
+    /// Orthogonalize every new vector against all the previous ones, twice: O(n j) extra work
+    /// at step `j`, and the most robust choice.
+    Full,
+    /// Orthogonalize only against the Ritz vectors that have converged to working accuracy,
+    /// the directions along which orthogonality is lost (Parlett and Scott). Cheaper than
+    /// [`Reorthogonalization::Full`] while few Ritz vectors have converged.
+    Selective,
+}
+
+/// Find `k` extreme eigenpairs of a symmetric `A` by the Lanczos method.
+///
+/// The Lanczos recurrence builds an orthonormal basis `V` of the Krylov subspace of `A` one
+/// product at a time, together with the tridiagonal `T = Vᵀ A V`. The eigenpairs `(θ, s)` of
+/// `T` give the Ritz pairs `(θ, V s)`, whose residual norm is `β |sₘ|` for the last recurrence
+/// coefficient `β` and last entry `sₘ` of `s`. The subspace grows until the `k` wanted Ritz
+/// pairs have a residual norm below `tol · ‖T‖`, which measures the error against the spectrum
+/// as a whole so that a zero eigenvalue, as of a graph Laplacian, converges too. The pairs are
+/// returned in the order of `extreme`, each with the number of steps taken as its iterations.
+///
+/// At most `opts.max_iter` steps are taken, and never more than the size of `A`, at which
+/// point the Ritz pairs are exact. The whole basis is kept, n values per step, to form the
+/// eigenvectors. A single Krylov subspace holds one copy of a repeated eigenvalue; a basis
+/// that becomes invariant is continued with a new vector orthogonal to it, which brings out the
+/// further copies.
+///
+/// Fails with [`SparseError::DimensionMismatch`] if `A` isn't square, and with
+/// [`SparseError::NanValue`] if a product with `A` yields a NaN or an infinity, `index` being
+/// the step.
+///
+/// # Panics
+///
+/// Panics if `k` exceeds the number of rows of `A`.
+pub fn lanczos(
+    a: &impl LinearOperator,
+    k: usize,
+    extreme: Extreme,
+    reorthogonalization: Reorthogonalization,
+    opts: &SolverOptions,
+) -> Result<Vec<Eigenpair>, SparseError> {
+    let mut v = start_vector(a, None)?;
+    let n = v.len();
+    assert!(k <= n, "k exceeds the size of the matrix");
+    if k == 0 {
+        return Ok(Vec::new());
+    }
+    let max_steps = n.min(opts.max_iter.max(k));
+    let norm = norm2(&v);
+    v.iter_mut().for_each(|vi| *vi /= norm);
+
+    let mut basis = vec![v];
+    let (mut alpha, mut beta): (Vec<f64>, Vec<f64>) = (Vec::new(), Vec::new());
+    let mut w = vec![0.0; n];
+    loop {
+        let j = basis.len() - 1;
+
+        // β w = A vⱼ − α vⱼ − β_prev vⱼ₋₁
+        a.apply(&basis[j], &mut w);
+        let alpha_j = dot(&basis[j], &w);
+        axpy(-alpha_j, &basis[j], &mut w);
+        if j > 0 {
+            axpy(-beta[j - 1], &basis[j - 1], &mut w);
+        }
+        alpha.push(alpha_j);
+        if reorthogonalization == Reorthogonalization::Full {
+            orthogonalize(&mut w, &basis);
+        }
+        let mut beta_j = norm2(&w);
+        if !(alpha_j.is_finite() && beta_j.is_finite()) {
+            return Err(SparseError::NanValue { index: j });
+        }
+
+        let ritz = Ritz::new(&alpha, &beta);
+        let t_norm = ritz.values.iter().fold(0.0, |max: f64, v| max.max(v.abs()));
+        if reorthogonalization == Reorthogonalization::Selective {
+            let threshold = sqrt(f64::EPSILON) * t_norm;
+            for i in 0..ritz.len() {
+                if beta_j * ritz.last(i).abs() <= threshold {
+                    let y = ritz.vector(&basis, i);
+                    axpy(-dot(&y, &w), &y, &mut w);
+                }
+            }
+            beta_j = norm2(&w);
+        }
+
+        let wanted = ritz.wanted(extreme, k);
+        let converged = |i: usize| beta_j * ritz.last(i).abs() <= opts.tol * t_norm;
+        let invariant = beta_j <= n as f64 * f64::EPSILON * t_norm;
+        let done = !invariant && wanted.len() == k && wanted.iter().all(|&i| converged(i));
+
+        // The next vector, from the recurrence or, when the subspace is invariant, a new start
+        let next = if invariant {
+            restart(&basis).map(|v| (v, 0.0))
+        } else {
+            w.iter_mut().for_each(|wi| *wi /= beta_j);
+            Some((w.clone(), beta_j))
+        };
+        match next {
+            Some((v, b)) if !done && basis.len() < max_steps => {
+                beta.push(b);
+                basis.push(v);
+            }
+            _ => {
+                let steps = basis.len();
+                return Ok(wanted
+                    .into_iter()
+                    .map(|i| {
+                        let vector = ritz.vector(&basis, i);
+                        Eigenpair::new(a, ritz.values[i], vector, steps, converged(i))
+                    })
+                    .collect());
+            }
+        }
+    }
+}
+
+/// The eigenpairs of the tridiagonal `T` of the Lanczos recurrence.
+struct Ritz {
+    values: Vec<f64>,
+    /// The eigenvectors of `T`, column `i` for `values[i]`, row-major
+    vectors: Vec<f64>,
+}
+
+impl Ritz {
+    /// Decompose the `T` with diagonal `alpha` and off-diagonal `beta`.
+    fn new(alpha: &[f64], beta: &[f64]) -> Self {
+        let m = alpha.len();
+        let mut values = alpha.to_vec();
+        let mut off = beta.to_vec();
+        off.push(0.0);
+        let mut vectors = vec![0.0; m * m];
+        assert!(
+            symmetric_tridiagonal_eigen(&mut values, &mut off, &mut vectors),
+            "the QL iteration converges on finite input"
+        );
+        Self { values, vectors }
+    }
+
+    fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Return the last entry of the eigenvector `i` of `T`.
+    fn last(&self, i: usize) -> f64 {
+        let m = self.len();
+        self.vectors[(m - 1) * m + i]
+    }
+
+    /// Return the indices of the `k` Ritz values at the `extreme` end, in its order, or of all
+    /// of them when there are fewer.
+    fn wanted(&self, extreme: Extreme, k: usize) -> Vec<usize> {
+        let values = &self.values;
+        let mut order: Vec<usize> = (0..self.len()).collect();
+        match extreme {
+            Extreme::Largest => order.sort_by(|&p, &q| values[q].total_cmp(&values[p])),
+            Extreme::Smallest => order.sort_by(|&p, &q| values[p].total_cmp(&values[q])),
+            Extreme::LargestMagnitude => {
+                order.sort_by(|&p, &q| values[q].abs().total_cmp(&values[p].abs()))
+            }
+        }
+        order.truncate(k);
+        order
+    }
+
+    /// Return the unit Ritz vector `V s` of `values[i]`.
+    fn vector(&self, basis: &[Vec<f64>], i: usize) -> Vec<f64> {
+        let m = self.len();
+        let mut y = vec![0.0; basis[0].len()];
+        for (l, q) in basis.iter().enumerate() {
+            axpy(self.vectors[l * m + i], q, &mut y);
+        }
+        let norm = norm2(&y);
+        y.iter_mut().for_each(|yi| *yi /= norm);
+        y
+    }
+}
+
+/// Remove from `w` its components along the orthonormal `basis`, twice so that the result is
+/// orthogonal to working accuracy.
+fn orthogonalize(w: &mut [f64], basis: &[Vec<f64>]) {
+    for _ in 0..2 {
+        for q in basis {
+            axpy(-dot(q, w), q, w);
+        }
+    }
+}
+
+/// Return a unit vector orthogonal to `basis`, to continue from once its span is invariant, or
+/// `None` if a few attempts leave nothing numerically.
+fn restart(basis: &[Vec<f64>]) -> Option<Vec<f64>> {
+    let n = basis[0].len();
+    for attempt in 1..=3 {
+        let seed = (basis.len() + attempt) as f64 * 0.414_213_562_373_095;
+        let mut v: Vec<f64> = (1..=n)
+            .map(|i| (i as f64 * 0.618_033_988_749_895 + seed) % 1.0 - 0.5)
+            .collect();
+        let norm = norm2(&v);
+        orthogonalize(&mut v, basis);
+        let rest = norm2(&v);
+        if rest > 1e-3 * norm {
+            v.iter_mut().for_each(|vi| *vi /= rest);
+            return Some(v);
+        }
+    }
+    None
+}
+
+#[test]
+fn test_lanczos() {
+    use crate::csr::CsrMatrix;
+    use core::f64::consts::PI;
+
+    // The eigenvalues of the 2D Laplacian on a 12 by 7 grid are
+    // 4 − 2 cos(iπ / 13) − 2 cos(jπ / 8), all distinct.
+    let a = CsrMatrix::poisson2d(12, 7);
+    let mut exact: Vec<f64> = (1..=12)
+        .flat_map(|i| (1..=7).map(move |j| (i, j)))
+        .map(|(i, j)| 4.0 - 2.0 * (i as f64 * PI / 13.0).cos() - 2.0 * (j as f64 * PI / 8.0).cos())
+        .collect();
+    exact.sort_by(f64::total_cmp);
+    let opts = SolverOptions::default();
+
+    for reorthogonalization in [Reorthogonalization::Full, Reorthogonalization::Selective] {
+        for (extreme, expected) in [
+            (Extreme::Smallest, exact[..4].to_vec()),
+            (
+                Extreme::Largest,
+                exact.iter().rev().take(4).copied().collect(),
+            ),
+        ] {
+            let pairs = lanczos(&a, 4, extreme, reorthogonalization, &opts).unwrap();
+            assert_eq!(pairs.len(), 4);
+            for (pair, value) in pairs.iter().zip(&expected) {
+                assert!(pair.converged, "{reorthogonalization:?} {pair:?}");
+                assert!((pair.value - value).abs() < 1e-8, "{pair:?} != {value}");
+                assert!(pair.residual_norm < 1e-6);
+                assert!(pair.iterations < a.nrows());
+            }
+            assert!(dot(&pairs[0].vector, &pairs[3].vector).abs() < 1e-6);
+        }
+    }
+
+    // The Laplacian of a path: the zero eigenvalue converges, relative to ‖A‖.
+    let n = 30;
+    let mut path = CsrMatrix::tridiagonal(-1.0, 2.0, -1.0, n);
+    let mut degrees = vec![2.0; n];
+    degrees[0] = 1.0;
+    degrees[n - 1] = 1.0;
+    path.set_diagonal(&degrees);
+    let pairs = lanczos(
+        &path,
+        2,
+        Extreme::Smallest,
+        Reorthogonalization::Full,
+        &opts,
+    )
+    .unwrap();
+    assert!(pairs[0].value.abs() < 1e-8 && pairs[0].converged);
+    assert!((pairs[1].value - (2.0 - 2.0 * (PI / n as f64).cos())).abs() < 1e-8);
+
+    // A diagonal matrix with a repeated eigenvalue: the basis runs out and is restarted.
+    let d = CsrMatrix::from_diagonal(&[1.0, -9.0, 3.0, 8.0, 3.0, 0.5]);
+    let pairs = lanczos(
+        &d,
+        4,
+        Extreme::LargestMagnitude,
+        Reorthogonalization::Full,
+        &opts,
+    )
+    .unwrap();
+    let values: Vec<f64> = pairs.iter().map(|p| p.value).collect();
+    crate::test_util::assert_close(&values, &[-9.0, 8.0, 3.0, 3.0], 1e-10);
+
+    assert!(
+        lanczos(&a, 0, Extreme::Largest, Reorthogonalization::Full, &opts)
+            .unwrap()
+            .is_empty()
+    );
+}
@@ -0,0 +1,450 @@
+//! Iterative eigensolvers for a few eigenpairs of a large sparse matrix.
+//!
+//! [`power_iteration`] finds the eigenvalue of largest magnitude through products with the
+//! matrix alone. [`inverse_iteration`] finds the eigenvalue closest to a shift `σ` by power
+//! iteration on `(A − σI)⁻¹`, which [`ShiftInvert`] applies through a factorization computed
+//! once. [`dominant_eigenpairs`] and [`eigenpairs_near`] find several eigenpairs of a symmetric
+//! matrix by deflation, keeping each iterate orthogonal to the eigenvectors already found.
+//! [`lanczos`] finds several extreme eigenpairs of a symmetric matrix at once from a single
+//! Krylov subspace, in far fewer products than deflated power iteration.
+//!
+//! The iterations share [`SolverOptions`] with the linear solvers. The power iterations stop
+//! once `‖M x − θ x‖ ≤ tol · |θ|` for the operator `M` being iterated and its Rayleigh quotient
+//! `θ`.
+
+pub mod lanczos;
+
+pub use lanczos::{lanczos, Extreme, Reorthogonalization};
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::csr::CsrMatrix;
+use crate::dense::{axpy, dot, norm2};
+use crate::error::SparseError;
+use crate::factor::{DirectOptions, DirectPath, DirectSolver};
+use crate::iterative::SolverOptions;
+use crate::operator::LinearOperator;
+
+/// An approximate eigenvalue and eigenvector, `A v ≈ λ v`, with how it was obtained.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Eigenpair {
+    /// The eigenvalue λ
+    pub value: f64,
+    /// The eigenvector `v`, of unit norm
+    pub vector: Vec<f64>,
+    /// Number of iterations performed
+    pub iterations: usize,
+    /// `‖A v − λ v‖`, recomputed from the returned pair
+    pub residual_norm: f64,
+    /// Whether the stopping criterion was met
+    pub converged: bool,
+}
+
+impl Eigenpair {
+    /// Collect the pair `(value, vector)` of `a`, computing its residual norm.
+    fn new(
+        a: &impl LinearOperator,
+        value: f64,
+        vector: Vec<f64>,
+        iterations: usize,
+        converged: bool,
+    ) -> Self {
+        let mut r = vec![0.0; a.nrows()];
+        a.apply(&vector, &mut r);
+        axpy(-value, &vector, &mut r);
+        Self {
+            value,
+            vector,
+            iterations,
+            residual_norm: norm2(&r),
+            converged,
+        }
+    }
+}
+
+/// The factorization of `A − σI` for a shift `σ`, applied as the operator `(A − σI)⁻¹`.
+///
+/// The eigenvalues of `(A − σI)⁻¹` are `1 / (λ − σ)` for the eigenvalues λ of `A`, with the same
+/// eigenvectors, so those of `A` closest to `σ` become the largest. `A − σI` is factored by a
+/// [`DirectSolver`], after a fill-reducing ordering: by Cholesky when it is symmetric positive
+/// definite, which a shift below the spectrum of a symmetric `A` gives, and by LU otherwise.
+#[derive(Clone, Debug)]
+pub struct ShiftInvert {
+    shift: f64,
+    solver: DirectSolver,
+}
+
+impl ShiftInvert {
+    /// Factor `A − σI` with the default [`DirectOptions`].
+    ///
+    /// Fails as [`ShiftInvert::with_options`] does.
+    pub fn new(a: &CsrMatrix<f64>, shift: f64) -> Result<Self, SparseError> {
+        Self::with_options(a, shift, DirectOptions::default())
+    }
+
+    /// Factor `A − σI` with a [`DirectSolver`] under `opts`.
+    ///
+    /// Fails with [`SparseError::DimensionMismatch`] if `a` isn't square, and with
+    /// [`SparseError::ZeroPivot`] if the factorization finds `A − σI` singular, that is when `σ`
+    /// is an eigenvalue; the other errors are those of [`DirectSolver::new`].
+    pub fn with_options(
+        a: &CsrMatrix<f64>,
+        shift: f64,
+        opts: DirectOptions,
+    ) -> Result<Self, SparseError> {
+        if a.nrows() != a.ncols() {
+            return Err(SparseError::DimensionMismatch {
+                expected: a.nrows(),
+                found: a.ncols(),
+            });
+        }
+        let mut shifted = a.clone();
+        shifted.add_to_diagonal(-shift);
+        Ok(Self {
+            shift,
+            solver: DirectSolver::new(&shifted, opts)?,
+        })
+    }
+
+    /// Return the shift σ
+    pub fn shift(&self) -> f64 {
+        self.shift
+    }
+
+    /// Return the number of rows and columns
+    pub fn n(&self) -> usize {
+        self.solver.n()
+    }
+
+    /// Return the factorization that was taken for `A − σI`
+    pub fn path(&self) -> DirectPath {
+        self.solver.path()
+    }
+}
+
+impl LinearOperator for ShiftInvert {
+    fn nrows(&self) -> usize {
+        self.n()
+    }
+
+    fn ncols(&self) -> usize {
+        self.n()
+    }
+
+    /// Solve `(A − σI) y = x`.
+    fn apply(&self, x: &[f64], y: &mut [f64]) {
+        assert_eq!(x.len(), self.n(), "x has the wrong length");
+        assert_eq!(y.len(), self.n(), "y has the wrong length");
+        self.solver.solve_into(x, y);
+    }
+}
+
+/// Find the eigenvalue of largest magnitude of a square `A` and its eigenvector by power
+/// iteration, `x ← A x / ‖A x‖`, starting from `x0` or from a fixed vector with no particular
+/// structure.
+///
+/// The error shrinks by `|λ₂ / λ₁|` per iteration for the two eigenvalues of largest magnitude,
+/// so the iteration is slow when they are close and doesn't converge when they have the same
+/// magnitude, as `λ` and `−λ` or a complex conjugate pair. Fails on a non-square `A` or a
+/// wrongly sized `x0`, and with [`SparseError::Breakdown`] when `x0` is zero or an iterate is
+/// mapped to zero, in the null space of `A`.
+pub fn power_iteration(
+    a: &impl LinearOperator,
+    x0: Option<&[f64]>,
+    opts: &SolverOptions,
+) -> Result<Eigenpair, SparseError> {
+    let x = start_vector(a, x0)?;
+    let (value, vector, iterations, converged) = iterate(a, x, &[], opts)?;
+    Ok(Eigenpair::new(a, value, vector, iterations, converged))
+}
+
+/// Find the eigenvalue of a square `A` closest to `shift` and its eigenvector by power iteration
+/// on `(A − σI)⁻¹`, factoring `A − σI` once with [`ShiftInvert`].
+///
+/// The error shrinks by `|λ₁ − σ| / |λ₂ − σ|` per iteration for the two eigenvalues closest to
+/// `σ`, so a good shift converges in a few iterations. Fails as [`ShiftInvert::new`] does, on
+/// a wrongly sized `x0`, and with [`SparseError::Breakdown`] when `x0` is zero.
+pub fn inverse_iteration(
+    a: &CsrMatrix<f64>,
+    shift: f64,
+    x0: Option<&[f64]>,
+    opts: &SolverOptions,
+) -> Result<Eigenpair, SparseError> {
+    let op = ShiftInvert::new(a, shift)?;
+    let x = start_vector(a, x0)?;
+    let (theta, vector, iterations, converged) = iterate(&op, x, &[], opts)?;
+    Ok(Eigenpair::new(
+        a,
+        shift + 1.0 / theta,
+        vector,
+        iterations,
+        converged,
+    ))
+}
+
+/// Find the `k` eigenvalues of largest magnitude of a symmetric `A` by power iteration with
+/// deflation, in decreasing magnitude. Each pair is iterated orthogonally to the eigenvectors
+/// found before it, which relies on the symmetry of `A`.
+///
+/// Fails with [`SparseError::Breakdown`] when `A` maps an iterate to zero, as it can once `k`
+/// exceeds the rank of `A`.
+///
+/// # Panics
+///
+/// Panics if `k` exceeds the number of rows of `A`.
+pub fn dominant_eigenpairs(
+    a: &impl LinearOperator,
+    k: usize,
+    opts: &SolverOptions,
+) -> Result<Vec<Eigenpair>, SparseError> {
+    let x0 = start_vector(a, None)?;
+    assert!(k <= x0.len(), "k exceeds the size of the matrix");
+    let mut pairs: Vec<Eigenpair> = Vec::with_capacity(k);
+    for _ in 0..k {
+        let (value, vector, iterations, converged) = iterate(a, x0.clone(), &pairs, opts)?;
+        pairs.push(Eigenpair::new(a, value, vector, iterations, converged));
+    }
+    Ok(pairs)
+}
+
+/// Find the `k` eigenvalues of a symmetric `A` closest to `shift` by inverse iteration with
+/// deflation, from the closest. `A − σI` is factored once for all of them.
+///
+/// # Panics
+///
+/// Panics if `k` exceeds the number of rows of `A`.
+pub fn eigenpairs_near(
+    a: &CsrMatrix<f64>,
+    shift: f64,
+    k: usize,
+    opts: &SolverOptions,
+) -> Result<Vec<Eigenpair>, SparseError> {
+    let op = ShiftInvert::new(a, shift)?;
+    let x0 = start_vector(a, None)?;
+    assert!(k <= x0.len(), "k exceeds the size of the matrix");
+    let mut pairs: Vec<Eigenpair> = Vec::with_capacity(k);
+    for _ in 0..k {
+        let (theta, vector, iterations, converged) = iterate(&op, x0.clone(), &pairs, opts)?;
+        pairs.push(Eigenpair::new(
+            a,
+            shift + 1.0 / theta,
+            vector,
+            iterations,
+            converged,
+        ));
+    }
+    Ok(pairs)
+}
+
+/// Check that `a` is square and that `x0`, if any, matches its size. Return `x0`, or a vector
+/// whose entries follow the fractional parts of multiples of the golden ratio, unlikely to be
+/// orthogonal to any eigenvector.
+pub(crate) fn start_vector(
+    a: &impl LinearOperator,
+    x0: Option<&[f64]>,
+) -> Result<Vec<f64>, SparseError> {
+    let n = a.nrows();
+    for len in [a.ncols(), x0.map_or(n, <[f64]>::len)] {
+        if len != n {
+            return Err(SparseError::DimensionMismatch {
+                expected: n,
+                found: len,
+            });
+        }
+    }
+    Ok(match x0 {
+        Some(x0) => x0.to_vec(),
+        None => (1..=n)
+            .map(|i| 0.5 + (i as f64 * 0.618_033_988_749_895) % 1.0)
+            .collect(),
+    })
+}
+
+/// Remove from `x` its components along the unit vectors of `locked`.
+pub(crate) fn deflate(x: &mut [f64], locked: &[Eigenpair]) {
+    for pair in locked {
+        axpy(-dot(&pair.vector, x), &pair.vector, x);
+    }
+}
+
+/// Run power iteration on `op` from `x`, orthogonally to `locked`. Return the last Rayleigh
+/// quotient `θ`, its unit vector, the iterations performed and whether the iteration converged.
+///
+/// Fails with [`SparseError::Breakdown`] when the iterate vanishes: at iteration 0 when `x` is
+/// zero or lies in the span of `locked`, and later when `op` maps it to zero, where it has no
+/// Rayleigh quotient to report.
+fn iterate(
+    op: &impl LinearOperator,
+    mut x: Vec<f64>,
+    locked: &[Eigenpair],
+    opts: &SolverOptions,
+) -> Result<(f64, Vec<f64>, usize, bool), SparseError> {
+    deflate(&mut x, locked);
+    let norm = norm2(&x);
+    if norm == 0.0 {
+        return Err(SparseError::Breakdown { iteration: 0 });
+    }
+    x.iter_mut().for_each(|xi| *xi /= norm);
+
+    let mut y = vec![0.0; x.len()];
+    let mut theta = 0.0;
+    let mut iterations = 0;
+    while iterations < opts.max_iter {
+        iterations += 1;
+        op.apply(&x, &mut y);
+        deflate(&mut y, locked);
+        let y_norm = norm2(&y);
+        if y_norm == 0.0 {
+            return Err(SparseError::Breakdown {
+                iteration: iterations,
+            });
+        }
+        theta = dot(&x, &y);
+
+        let mut r = y.clone();
+        axpy(-theta, &x, &mut r);
+        if norm2(&r) <= opts.tol * theta.abs() {
+            return Ok((theta, x, iterations, true));
+        }
+        for (xi, yi) in x.iter_mut().zip(&y) {
+            *xi = yi / y_norm;
+        }
+    }
+    Ok((theta, x, iterations, false))
+}
+
+#[test]
+fn test_power_iteration() {
+    // The eigenvalues of the 1D Laplacian are 2 − 2 cos(kπ / (n + 1)).
+    let n = 12;
+    let a = CsrMatrix::tridiagonal(-1.0, 2.0, -1.0, n);
+    let exact = |k: usize| 2.0 - 2.0 * (k as f64 * core::f64::consts::PI / (n + 1) as f64).cos();
+    let opts = SolverOptions::default();
+
+    let largest = power_iteration(&a, None, &opts).unwrap();
+    assert!(largest.converged);
+    assert!((largest.value - exact(n)).abs() < 1e-9);
+    assert!(largest.residual_norm < 1e-8);
+    assert!((norm2(&largest.vector) - 1.0).abs() < 1e-12);
+
+    let pairs = dominant_eigenpairs(&a, 3, &opts).unwrap();
+    for (pair, k) in pairs.iter().zip([n, n - 1, n - 2]) {
+        assert!(pair.converged, "{pair:?}");
+        assert!((pair.value - exact(k)).abs() < 1e-9);
+    }
+    assert!(dot(&pairs[0].vector, &pairs[2].vector).abs() < 1e-8);
+
+    // An eigenvector as the start converges at once.
+    let again = power_iteration(&a, Some(&largest.vector), &opts).unwrap();
+    assert!(again.iterations <= 2);
+    assert_eq!(
+        power_iteration(&a, Some(&[1.0]), &opts).unwrap_err(),
+        SparseError::DimensionMismatch {
+            expected: n,
+            found: 1
+        }
+    );
+}
+
+#[test]
+fn test_inverse_iteration() {
+    let n = 12;
+    let exact = |k: usize| 2.0 - 2.0 * (k as f64 * core::f64::consts::PI / (n + 1) as f64).cos();
+    let opts = SolverOptions::default();
+
+    // Symmetric: the smallest eigenvalue, then the three closest to 1.
+    let a = CsrMatrix::tridiagonal(-1.0, 2.0, -1.0, n);
+    let smallest = inverse_iteration(&a, 0.0, None, &opts).unwrap();
+    assert!(smallest.converged && smallest.iterations < 30);
+    assert!((smallest.value - exact(1)).abs() < 1e-10);
+    assert!(smallest.residual_norm < 1e-8);
+
+    let mut expected: Vec<f64> = (1..=n).map(exact).collect();
+    expected.sort_by(|p, q| (p - 1.0).abs().total_cmp(&(q - 1.0).abs()));
+    let near = eigenpairs_near(&a, 1.0, 3, &opts).unwrap();
+    for (pair, value) in near.iter().zip(&expected) {
+        assert!(pair.converged && pair.residual_norm < 1e-8, "{pair:?}");
+        assert!((pair.value - value).abs() < 1e-10);
+    }
+
+    // Nonsymmetric with the same spectrum.
+    let b = CsrMatrix::tridiagonal(-0.5, 2.0, -2.0, n);
+    let pair = inverse_iteration(&b, 1.0, None, &opts).unwrap();
+    assert!(pair.converged);
+    assert!((pair.value - expected[0]).abs() < 1e-10);
+
+    let singular = CsrMatrix::from_dense(2, 2, &[1.0, 2.0, 3.0, 6.0]).unwrap();
+    assert_eq!(
+        ShiftInvert::new(&singular, 0.0).unwrap_err(),
+        SparseError::ZeroPivot { index: 1 }
+    );
+}
+
+#[test]
+fn test_shift_invert_factorization() {
+    use crate::test_util::assert_close;
+
+    let opts = SolverOptions::default();
+
+    // Below the spectrum of a symmetric matrix the shifted matrix is definite, inside it not.
+    let a = CsrMatrix::poisson2d(8, 7);
+    let n = a.nrows();
+    assert_eq!(
+        ShiftInvert::new(&a, -0.5).unwrap().path(),
+        DirectPath::Cholesky
+    );
+    let inside = ShiftInvert::new(&a, 4.1).unwrap();
+    assert_eq!(inside.path(), DirectPath::Lu);
+    assert_eq!(inside.shift(), 4.1);
+
+    // The operator is the inverse of the shifted matrix.
+    let x: Vec<f64> = (0..n).map(|i| (i as f64).sin()).collect();
+    let mut y = vec![0.0; n];
+    inside.apply(&x, &mut y);
+    let mut back = a.mul_vec(&y);
+    axpy(-4.1, &y, &mut back);
+    assert_close(&back, &x, 1e-10);
+
+    // The ordering doesn't change the pair found.
+    let pair = inverse_iteration(&a, 4.1, None, &opts).unwrap();
+    assert!(pair.converged && pair.residual_norm < 1e-8, "{pair:?}");
+    let natural = DirectOptions {
+        ordering: crate::factor::OrderingMethod::Natural,
+        ..DirectOptions::default()
+    };
+    let op = ShiftInvert::with_options(&a, 4.1, natural).unwrap();
+    let (theta, _, _, converged) =
+        iterate(&op, start_vector(&a, None).unwrap(), &[], &opts).unwrap();
+    assert!(converged);
+    assert!((4.1 + 1.0 / theta - pair.value).abs() < 1e-10);
+}
+
+#[test]
+fn test_eigen_breakdown() {
+    let opts = SolverOptions::default();
+    let a = CsrMatrix::tridiagonal(-1.0, 2.0, -1.0, 5);
+
+    // A zero start has no direction to normalize.
+    let zero = vec![0.0; 5];
+    assert_eq!(
+        power_iteration(&a, Some(&zero), &opts).unwrap_err(),
+        SparseError::Breakdown { iteration: 0 }
+    );
+    assert_eq!(
+        inverse_iteration(&a, 0.5, Some(&zero), &opts).unwrap_err(),
+        SparseError::Breakdown { iteration: 0 }
+    );
+
+    // A start in the null space is mapped to zero, with no Rayleigh quotient.
+    let projector = CsrMatrix::from_dense(2, 2, &[1.0, 0.0, 0.0, 0.0]).unwrap();
+    assert_eq!(
+        power_iteration(&projector, Some(&[0.0, 1.0]), &opts).unwrap_err(),
+        SparseError::Breakdown { iteration: 1 }
+    );
+    let nilpotent = CsrMatrix::from_dense(2, 2, &[0.0, 1.0, 0.0, 0.0]).unwrap();
+    assert_eq!(
+        power_iteration(&nilpotent, Some(&[1.0, 1.0]), &opts).unwrap_err(),
+        SparseError::Breakdown { iteration: 2 }
+    );
+}
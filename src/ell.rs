@@ -0,0 +1,283 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::coo::CooMatrix;
+use crate::csr::CsrMatrix;
+use crate::scalar::Scalar;
+
+/// A sparse matrix in ELLPACK (ELL) form: every row padded to the same number of entries, stored
+/// column-major.
+///
+/// Slot `k` of row `i` is at position `k * nrows + i` of both `indices` and `data`, so the
+/// matrix-vector product walks slot by slot over all rows at once, with unit stride, no row
+/// pointers and no branch on the row length: the layout SIMD units and GPUs want. Padding slots
+/// hold a zero value and repeat the last column of their row (column 0 for an empty row), which
+/// also means an infinite or NaN component of `x` there turns into NaN in rows with padding.
+/// ELL suits matrices whose rows have similar lengths; see [`EllMatrix::padding_stats`].
+#[derive(Clone, Debug)]
+pub struct EllMatrix<T = f64> {
+    nrows: usize,
+    ncols: usize,
+    /// Slots per row
+    width: usize,
+    /// Number of entries that aren't padding
+    nnz: usize,
+    /// Number of entries in each row, to tell padding apart when converting back
+    row_lengths: Vec<usize>,
+    /// Column of each slot, column-major
+    indices: Vec<usize>,
+    /// Value of each slot, column-major
+    data: Vec<T>,
+}
+
+/// How much padding an [`EllMatrix`] carries.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PaddingStats {
+    /// Slots per row, the length of the longest row
+    pub width: usize,
+    /// Number of entries that aren't padding
+    pub nnz: usize,
+    /// Number of padding slots, `nrows * width - nnz`
+    pub padding: usize,
+    /// Stored slots divided by `nnz`, 1 when there is no padding (or no entry at all)
+    pub fill_ratio: f64,
+}
+
+impl<T: Scalar> EllMatrix<T> {
+    /// Pad the rows of a CSR matrix to the length of its longest row. O(nrows × width).
+    pub fn from_csr(a: &CsrMatrix<T>) -> Self {
+        let width = (0..a.nrows()).map(|i| a.row(i).0.len()).max().unwrap_or(0);
+        Self::from_csr_with_width(a, width).0
+    }
+
+    /// Pad or cut the rows of a CSR matrix to `width` slots, and return the entries beyond
+    /// `width` separately, in row order.
+    pub(crate) fn from_csr_with_width(a: &CsrMatrix<T>, width: usize) -> (Self, CooMatrix<T>) {
+        let nrows = a.nrows();
+        let row_lengths: Vec<usize> = (0..nrows).map(|i| a.row(i).0.len().min(width)).collect();
+        let mut overflow = CooMatrix::new(nrows, a.ncols());
+        let mut indices = vec![0; nrows * width];
+        let mut data = vec![T::zero(); nrows * width];
+        for i in 0..nrows {
+            let (cols, values) = a.row(i);
+            let pad_col = cols.last().copied().unwrap_or(0);
+            for (&j, &v) in cols.iter().zip(values).skip(width) {
+                overflow.push(i, j, v);
+            }
+            for k in 0..width {
+                let slot = k * nrows + i;
+                match (cols.get(k), values.get(k)) {
+                    (Some(&j), Some(&v)) => {
+                        indices[slot] = j;
+                        data[slot] = v;
+                    }
+                    _ => indices[slot] = pad_col,
+                }
+            }
+        }
+
+        let ell = Self {
+            nrows,
+            ncols: a.ncols(),
+            width,
+            nnz: row_lengths.iter().sum(),
+            row_lengths,
+            indices,
+            data,
+        };
+        (ell, overflow)
+    }
+
+    /// Return the number of rows
+    pub fn nrows(&self) -> usize {
+        self.nrows
+    }
+
+    /// Return the number of columns
+    pub fn ncols(&self) -> usize {
+        self.ncols
+    }
+
+    /// Return `(nrows, ncols)`
+    pub fn shape(&self) -> (usize, usize) {
+        (self.nrows, self.ncols)
+    }
+
+    /// Return the number of slots per row
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Return the number of entries that aren't padding
+    pub fn nnz(&self) -> usize {
+        self.nnz
+    }
+
+    /// Return the column of every slot, padding included, column-major: slot `k` of row `i` is
+    /// at `k * nrows + i`
+    pub fn indices(&self) -> &[usize] {
+        &self.indices
+    }
+
+    /// Return the value of every slot, zero for padding, in the order of
+    /// [`EllMatrix::indices`]
+    pub fn data(&self) -> &[T] {
+        &self.data
+    }
+
+    /// Return how much padding the matrix carries.
+    pub fn padding_stats(&self) -> PaddingStats {
+        let slots = self.nrows * self.width;
+        PaddingStats {
+            width: self.width,
+            nnz: self.nnz,
+            padding: slots - self.nnz,
+            fill_ratio: if self.nnz == 0 {
+                1.0
+            } else {
+                slots as f64 / self.nnz as f64
+            },
+        }
+    }
+
+    /// Multiply the matrix by the dense vector `x`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `x.len() != self.ncols()`.
+    pub fn mul_vec(&self, x: &[T]) -> Vec<T> {
+        assert_eq!(x.len(), self.ncols, "x has the wrong length");
+
+        let mut y = vec![T::zero(); self.nrows];
+        if self.nrows == 0 {
+            return y;
+        }
+        for (cols, values) in self
+            .indices
+            .chunks(self.nrows)
+            .zip(self.data.chunks(self.nrows))
+        {
+            for ((yi, &j), &v) in y.iter_mut().zip(cols).zip(values) {
+                *yi += v * x[j];
+            }
+        }
+        y
+    }
+
+    /// Convert to CSR, leaving out the padding. O(nnz + nrows).
+    pub fn to_csr(&self) -> CsrMatrix<T> {
+        let mut indptr = Vec::with_capacity(self.nrows + 1);
+        let mut indices = Vec::with_capacity(self.nnz);
+        let mut data = Vec::with_capacity(self.nnz);
+        indptr.push(0);
+        for (i, &len) in self.row_lengths.iter().enumerate() {
+            for k in 0..len {
+                let slot = k * self.nrows + i;
+                indices.push(self.indices[slot]);
+                data.push(self.data[slot]);
+            }
+            indptr.push(indices.len());
+        }
+
+        CsrMatrix::from_parts(self.nrows, self.ncols, indptr, indices, data)
+    }
+}
+
+#[test]
+fn test_ell_matrix() {
+    #[rustfmt::skip]
+    let dense = [
+        1.0, 0.0, 2.0, 0.0,
+        0.0, 0.0, 0.0, 0.0,
+        0.0, 3.0, 4.0, 5.0,
+        6.0, 0.0, 0.0, 0.0,
+    ];
+    let a = CsrMatrix::from_dense(4, 4, &dense).unwrap();
+    let ell = EllMatrix::from_csr(&a);
+    assert_eq!(ell.width(), 3);
+    assert_eq!(ell.indices, [0, 0, 1, 0, 2, 0, 2, 0, 2, 0, 3, 0]);
+    assert_eq!(
+        ell.data,
+        [1.0, 0.0, 3.0, 6.0, 2.0, 0.0, 4.0, 0.0, 0.0, 0.0, 5.0, 0.0]
+    );
+    assert_eq!(
+        ell.padding_stats(),
+        PaddingStats {
+            width: 3,
+            nnz: 6,
+            padding: 6,
+            fill_ratio: 2.0,
+        }
+    );
+    assert_eq!(ell.to_csr().to_dense(), dense);
+
+    let x = [1.0, -2.0, 0.5, 3.0];
+    assert_eq!(ell.mul_vec(&x), a.mul_vec(&x));
+}
+
+#[test]
+fn test_ell_matrix_width_zero() {
+    // Rows but no entry: no slot at all, and every row is empty in the product.
+    let ell = EllMatrix::from_csr(&CsrMatrix::<f64>::new(3, 2));
+    assert_eq!((ell.width(), ell.nnz()), (0, 0));
+    assert!(ell.indices().is_empty() && ell.data().is_empty());
+    assert_eq!(ell.padding_stats().padding, 0);
+    assert_eq!(ell.mul_vec(&[1.0, 2.0]), [0.0; 3]);
+    assert_eq!(ell.to_csr(), CsrMatrix::new(3, 2));
+
+    // Cutting a matrix with entries to width 0 leaves every entry to the overflow, in row order.
+    let a = CsrMatrix::from_dense(2, 3, &[0.0, 1.0, 2.0, 3.0, 0.0, 0.0]).unwrap();
+    let (ell, overflow) = EllMatrix::from_csr_with_width(&a, 0);
+    assert_eq!((ell.width(), ell.nnz()), (0, 0));
+    assert_eq!(ell.row_lengths, [0, 0]);
+    assert_eq!(ell.mul_vec(&[1.0; 3]), [0.0; 2]);
+    assert_eq!(ell.to_csr(), CsrMatrix::new(2, 3));
+    assert_eq!(
+        overflow.triplets().collect::<Vec<_>>(),
+        [(0, 1, 1.0), (0, 2, 2.0), (1, 0, 3.0)]
+    );
+}
+
+#[test]
+fn test_ell_matrix_ragged_rows() {
+    // Rows of 0, 4, 1 and 2 entries, one of them an explicitly stored zero.
+    let a = CsrMatrix::from_parts(
+        4,
+        5,
+        vec![0, 0, 4, 5, 7],
+        vec![0, 1, 3, 4, 2, 1, 4],
+        vec![1.0, 2.0, 3.0, 4.0, 0.0, 5.0, 6.0],
+    );
+    let ell = EllMatrix::from_csr(&a);
+    assert_eq!((ell.width(), ell.nnz()), (4, 7));
+    assert_eq!(ell.row_lengths, [0, 4, 1, 2]);
+    // Padding repeats the last column of its row, column 0 for the empty row.
+    #[rustfmt::skip]
+    assert_eq!(ell.indices, [
+        0, 0, 2, 1,
+        0, 1, 2, 4,
+        0, 3, 2, 4,
+        0, 4, 2, 4,
+    ]);
+    assert_eq!(ell.padding_stats().padding, 4 * 4 - 7);
+
+    // The stored zero is an entry, not padding, and survives the round trip.
+    let back = ell.to_csr();
+    assert_eq!(back, a);
+    assert_eq!(back.nnz(), 7);
+    let x = [1.0, -1.0, 2.0, 0.5, 3.0];
+    assert_eq!(ell.mul_vec(&x), a.mul_vec(&x));
+
+    // Cut to width 2, the long row overflows and the short ones are only padded.
+    let (cut, overflow) = EllMatrix::from_csr_with_width(&a, 2);
+    assert_eq!(cut.row_lengths, [0, 2, 1, 2]);
+    assert_eq!(
+        overflow.triplets().collect::<Vec<_>>(),
+        [(1, 3, 3.0), (1, 4, 4.0)]
+    );
+    let mut y = cut.mul_vec(&x);
+    for (i, j, v) in overflow.triplets() {
+        y[i] += v * x[j];
+    }
+    assert_eq!(y, a.mul_vec(&x));
+}
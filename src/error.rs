@@ -0,0 +1,98 @@
+use alloc::string::String;
+use core::fmt;
+
+/// The error type shared by the fallible operations of this crate.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SparseError {
+    /// An operand has a different length than the operation expects.
+    DimensionMismatch { expected: usize, found: usize },
+    /// An index (or the end of an index range) lies past the length of the operand.
+    IndexOutOfBounds { index: usize, len: usize },
+    /// A NaN was found where the policy in effect rejects it.
+    NanValue { index: usize },
+    /// The raw arrays handed to a matrix constructor don't describe a valid matrix.
+    InvalidStructure(String),
+    /// A factorization met a pivot that is zero, or not stored at all, in row `index`.
+    ZeroPivot { index: usize },
+    /// A file could not be read or written.
+    Io(String),
+    /// A file doesn't follow the format it is read as. `line` counts from 1.
+    Parse { line: usize, reason: String },
+    /// Packing would hold `nnz` of `len` components, taking at least as much storage as the
+    /// full-length array.
+    TooDense { nnz: usize, len: usize },
+    /// A lookup by name or number, such as in the matrix collection, found nothing.
+    NotFound(String),
+    /// An iterative method broke down after `iteration` steps: a quantity it divides by
+    /// vanished, typically because the matrix is singular on the space searched.
+    Breakdown { iteration: usize },
+    /// An integer entry at `(row, col)` of a result overflowed its type.
+    Overflow { row: usize, col: usize },
+    /// A result would store `nnz` entries, more than the `limit` the caller allows.
+    TooManyEntries { nnz: usize, limit: usize },
+    /// Row `index` of a matrix has no stored entries, which makes it singular.
+    EmptyRow { index: usize },
+    /// Column `index` of a matrix has no stored entries, which makes it singular.
+    EmptyColumn { index: usize },
+    /// The position `(row, col)` was given twice, with the values `first` and `second`, where
+    /// the policy in effect rejects duplicates.
+    DuplicateEntry {
+        row: usize,
+        col: usize,
+        first: String,
+        second: String,
+    },
+    /// A compute device could not be acquired, or failed to set up.
+    Device(String),
+}
+
+impl fmt::Display for SparseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DimensionMismatch { expected, found } => {
+                write!(f, "dimension mismatch: expected {expected}, found {found}")
+            }
+            Self::IndexOutOfBounds { index, len } => {
+                write!(f, "index {index} out of bounds for length {len}")
+            }
+            Self::NanValue { index } => write!(f, "NaN value at index {index}"),
+            Self::InvalidStructure(reason) => write!(f, "invalid matrix structure: {reason}"),
+            Self::ZeroPivot { index } => write!(f, "zero pivot in row {index}"),
+            Self::Io(reason) => write!(f, "I/O error: {reason}"),
+            Self::Parse { line, reason } => write!(f, "parse error on line {line}: {reason}"),
+            Self::TooDense { nnz, len } => write!(
+                f,
+                "{nnz} of {len} components are nonzero, too dense for a packed vector to pay off"
+            ),
+            Self::NotFound(what) => write!(f, "not found: {what}"),
+            Self::Breakdown { iteration } => {
+                write!(f, "iterative method broke down after {iteration} steps")
+            }
+            Self::Overflow { row, col } => write!(f, "integer overflow at entry ({row}, {col})"),
+            Self::TooManyEntries { nnz, limit } => {
+                write!(
+                    f,
+                    "the result would store {nnz} entries, over the limit of {limit}"
+                )
+            }
+            Self::EmptyRow { index } => write!(f, "row {index} is empty"),
+            Self::EmptyColumn { index } => write!(f, "column {index} is empty"),
+            Self::DuplicateEntry {
+                row,
+                col,
+                first,
+                second,
+            } => write!(f, "duplicate entry at ({row}, {col}): {first} and {second}"),
+            Self::Device(reason) => write!(f, "compute device error: {reason}"),
+        }
+    }
+}
+
+impl core::error::Error for SparseError {}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for SparseError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err.to_string())
+    }
+}
@@ -0,0 +1,525 @@
+//! Sparse Cholesky factorization, up-looking with the elimination tree.
+
+use alloc::format;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::iter;
+
+use super::collect_columns;
+use crate::csc::CscMatrix;
+use crate::dense::sqrt;
+use crate::error::SparseError;
+use crate::vec::PackedVec;
+
+/// Marks a root of the elimination tree, and a node no row subtree has visited yet.
+const NONE: usize = usize::MAX;
+
+/// The Cholesky factorization `A = L Lᵀ` of a sparse symmetric positive definite matrix, from
+/// [`CholeskyFactorization::new`].
+///
+/// `L` is lower triangular with a positive diagonal, stored in CSC form. The pattern of row `k`
+/// of `L` is the subtree of the elimination tree spanned by the pattern of column `k` of the
+/// upper triangle of `A`, where the parent of `j` is the first row below the diagonal in column
+/// `j` of `L`.
+#[derive(Clone, Debug)]
+pub struct CholeskyFactorization {
+    l: CscMatrix<f64>,
+    /// Parent of every column in the elimination tree, [`NONE`] for a root
+    parent: Vec<usize>,
+}
+
+impl CholeskyFactorization {
+    /// Factor the symmetric positive definite matrix `a`, reading only its upper triangle.
+    ///
+    /// The symbolic phase computes the elimination tree and, from the row subtrees, the number
+    /// of entries of every column of `L`, so that `L` is allocated once. The numeric phase is
+    /// up-looking: row `k` of `L` is the sparse triangular solve of the first `k` rows with
+    /// column `k` of `A`, on the pattern the row subtree gives. O(flops + nnz).
+    ///
+    /// Fails with [`SparseError::DimensionMismatch`] if `a` isn't square, with
+    /// [`SparseError::EmptyColumn`] or [`SparseError::EmptyRow`] before any arithmetic when `a`
+    /// has an empty column or row, and with [`SparseError::ZeroPivot`] at the first column whose
+    /// pivot isn't positive, which happens when `a` isn't positive definite.
+    pub fn new(a: &CscMatrix<f64>) -> Result<Self, SparseError> {
+        let n = a.nrows();
+        if a.ncols() != n {
+            return Err(SparseError::DimensionMismatch {
+                expected: n,
+                found: a.ncols(),
+            });
+        }
+        if let Some(&index) = a.find_empty_cols().first() {
+            return Err(SparseError::EmptyColumn { index });
+        }
+        if let Some(&index) = a.find_empty_rows().first() {
+            return Err(SparseError::EmptyRow { index });
+        }
+
+        let parent = elimination_tree(a);
+        let mut flag = vec![NONE; n];
+        let mut pattern = Vec::new();
+        let mut path = Vec::new();
+
+        // Column counts: the diagonal, plus one for every row subtree the column is in.
+        let mut l_ptr = vec![0; n + 1];
+        for k in 0..n {
+            row_subtree(a, k, &parent, &mut flag, &mut path, &mut pattern);
+            for &j in &pattern {
+                l_ptr[j + 1] += 1;
+            }
+            l_ptr[k + 1] += 1;
+        }
+        for k in 0..n {
+            l_ptr[k + 1] += l_ptr[k];
+        }
+
+        let mut l_rows = vec![0; l_ptr[n]];
+        let mut l_vals = vec![0.0; l_ptr[n]];
+        let mut next = l_ptr[..n].to_vec();
+        let mut x = vec![0.0; n];
+        flag.fill(NONE);
+        for k in 0..n {
+            row_subtree(a, k, &parent, &mut flag, &mut path, &mut pattern);
+            let (rows, values) = a.col(k);
+            for (&i, &v) in rows.iter().zip(values) {
+                if i <= k {
+                    x[i] = v;
+                }
+            }
+
+            let mut d = x[k];
+            x[k] = 0.0;
+            for &j in &pattern {
+                let lkj = x[j] / l_vals[l_ptr[j]];
+                x[j] = 0.0;
+                for p in l_ptr[j] + 1..next[j] {
+                    x[l_rows[p]] -= l_vals[p] * lkj;
+                }
+                d -= lkj * lkj;
+                l_rows[next[j]] = k;
+                l_vals[next[j]] = lkj;
+                next[j] += 1;
+            }
+            if d.is_nan() || d <= 0.0 {
+                return Err(SparseError::ZeroPivot { index: k });
+            }
+            l_rows[next[k]] = k;
+            l_vals[next[k]] = sqrt(d);
+            next[k] += 1;
+        }
+
+        Ok(Self {
+            l: CscMatrix::from_parts(n, n, l_ptr, l_rows, l_vals),
+            parent,
+        })
+    }
+
+    /// Return the number of rows and columns
+    pub fn n(&self) -> usize {
+        self.l.ncols()
+    }
+
+    /// Return the lower triangular factor `L`, its diagonal first in every column
+    pub fn l(&self) -> &CscMatrix<f64> {
+        &self.l
+    }
+
+    /// Return the number of entries stored in `L`, the measure of fill-in
+    pub fn nnz(&self) -> usize {
+        self.l.nnz()
+    }
+
+    /// Solve `A x = b` with the factorization.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `b.len() != self.n()`.
+    pub fn solve(&self, b: &[f64]) -> Vec<f64> {
+        let mut x = vec![0.0; b.len()];
+        self.solve_into(b, &mut x);
+        x
+    }
+
+    /// Solve `A x = b` with the factorization, writing `x` into `x`: forward substitution with
+    /// `L`, then backward substitution with `Lᵀ`, reading the columns of `L` as its rows.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `b.len()` or `x.len()` isn't `self.n()`.
+    pub fn solve_into(&self, b: &[f64], x: &mut [f64]) {
+        let n = self.n();
+        assert_eq!(b.len(), n, "b has the wrong length");
+        assert_eq!(x.len(), n, "x has the wrong length");
+
+        x.copy_from_slice(b);
+        for j in 0..n {
+            let (rows, values) = self.l.col(j);
+            x[j] /= values[0];
+            for (&i, &v) in rows.iter().zip(values).skip(1) {
+                x[i] -= v * x[j];
+            }
+        }
+        for j in (0..n).rev() {
+            let (rows, values) = self.l.col(j);
+            let below: f64 = (rows.iter().zip(values).skip(1))
+                .map(|(&i, &v)| v * x[i])
+                .sum();
+            x[j] = (x[j] - below) / values[0];
+        }
+    }
+
+    /// Solve `A X = B` for a sparse matrix `B` of right-hand sides, returning `X` as sparse.
+    ///
+    /// Every column goes through the sparse triangular solves with `L` and `Lᵀ`, whose work
+    /// arrays are allocated once and cleared along the reach only, as in
+    /// [`LuFactorization::solve_matrix`](super::LuFactorization::solve_matrix). `Lᵀ` is formed
+    /// once per call, in O(nnz(L)).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `b.nrows() != self.n()`.
+    pub fn solve_matrix(&self, b: &CscMatrix<f64>) -> CscMatrix<f64> {
+        let n = self.n();
+        assert_eq!(b.nrows(), n, "B must have a row per column of A");
+
+        let lt = self.l.transpose();
+        let mut lower =
+            (self.l.view().sparse_triangular_solver(true)).expect("the diagonal of L is positive");
+        let mut upper =
+            (lt.view().sparse_triangular_solver(false)).expect("the diagonal of L is positive");
+        let columns = (0..b.ncols())
+            .map(|j| {
+                let (rows, values) = b.col(j);
+                let bj = PackedVec::from_pairs(n, rows.iter().copied().zip(values.iter().copied()))
+                    .expect("the rows of B are in bounds");
+                let y = lower.solve(&bj).expect("the diagonal of L is positive");
+                let x = upper.solve(&y).expect("the diagonal of L is positive");
+                x.iter().unzip()
+            })
+            .collect();
+        collect_columns(n, columns)
+    }
+
+    /// Update the factorization of `A` to one of `A + σ w wᵀ` in place, a rank-one update for
+    /// `σ > 0` and a downdate for `σ < 0`, such as adding or removing a row `w` of a
+    /// least-squares problem from its normal matrix.
+    ///
+    /// This is the sparse update of Davis and Hager: if `f` is the first row of `w`, only the
+    /// columns on the path from `f` to the root of the elimination tree change, each in the
+    /// time of its entries, so the cost is that of the path rather than of a refactorization.
+    /// The pattern of `L` is kept, which requires the pattern of `w` to be contained in that of
+    /// column `f` of `L`; then `w wᵀ` creates no fill.
+    ///
+    /// Fails with [`SparseError::DimensionMismatch`] if `w` doesn't have a component per row,
+    /// with [`SparseError::InvalidStructure`] naming an entry of `w` outside the pattern of
+    /// column `f` of `L`, and with [`SparseError::ZeroPivot`] at the column where a downdate
+    /// leaves a pivot that isn't positive, because `A + σ w wᵀ` isn't positive definite. The
+    /// factorization is left as it was on failure.
+    pub fn rank1_update(&mut self, w: &PackedVec<f64>, sigma: f64) -> Result<(), SparseError> {
+        let n = self.n();
+        if w.full_len() != n {
+            return Err(SparseError::DimensionMismatch {
+                expected: n,
+                found: w.full_len(),
+            });
+        }
+        let Some(f) = w.iter().filter(|&(_, v)| v != 0.0).map(|(i, _)| i).min() else {
+            return Ok(());
+        };
+        let pattern = self.l.col(f).0;
+        if let Some((i, _)) =
+            (w.iter()).find(|&(i, v)| v != 0.0 && pattern.binary_search(&i).is_err())
+        {
+            return Err(SparseError::InvalidStructure(format!(
+                "w has an entry in row {i}, outside the pattern of column {f} of L"
+            )));
+        }
+        if sigma == 0.0 {
+            return Ok(());
+        }
+
+        let path: Vec<usize> = iter::successors(Some(f), |&j| match self.parent[j] {
+            NONE => None,
+            up => Some(up),
+        })
+        .collect();
+        let saved: Vec<Vec<f64>> = path.iter().map(|&j| self.l.col(j).1.to_vec()).collect();
+
+        // w scaled by √|σ|, updated along the path to the rows that are left to process
+        let scale = sqrt(sigma.abs());
+        let mut x = vec![0.0; n];
+        for (i, v) in w.iter() {
+            x[i] = scale * v;
+        }
+        let (update, sign) = if sigma > 0.0 {
+            (true, 1.0)
+        } else {
+            (false, -1.0)
+        };
+        let mut beta = 1.0;
+        for &j in &path {
+            let (rows, values) = self.l.col_mut(j);
+            let alpha = x[j] / values[0];
+            let beta2 = beta * beta + sign * alpha * alpha;
+            if beta2.is_nan() || beta2 <= 0.0 {
+                for (&j, saved) in path.iter().zip(&saved) {
+                    self.l.col_mut(j).1.copy_from_slice(saved);
+                }
+                return Err(SparseError::ZeroPivot { index: j });
+            }
+            let beta2 = sqrt(beta2);
+            let (delta, gamma) = if update {
+                (beta / beta2, alpha / (beta2 * beta))
+            } else {
+                (beta2 / beta, -alpha / (beta2 * beta))
+            };
+            values[0] = delta * values[0] + if update { gamma * x[j] } else { 0.0 };
+            beta = beta2;
+            for (&i, v) in rows.iter().zip(values.iter_mut()).skip(1) {
+                let before = x[i];
+                x[i] -= alpha * *v;
+                *v = delta * *v + gamma * if update { before } else { x[i] };
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Return the parent of every column in the elimination tree of the symmetric matrix whose upper
+/// triangle `a` holds, [`NONE`] for a root, with Liu's algorithm: path compression through the
+/// `ancestor` links makes it nearly O(nnz).
+fn elimination_tree(a: &CscMatrix<f64>) -> Vec<usize> {
+    let n = a.ncols();
+    let mut parent = vec![NONE; n];
+    let mut ancestor = vec![NONE; n];
+    for k in 0..n {
+        for &row in a.col(k).0 {
+            let mut i = row;
+            while i < k {
+                let up = ancestor[i];
+                ancestor[i] = k;
+                if up == NONE {
+                    parent[i] = k;
+                    break;
+                }
+                i = up;
+            }
+        }
+    }
+    parent
+}
+
+/// Set `pattern` to the columns of row `k` of `L` below the diagonal, which is the subtree of the
+/// elimination tree spanned by the rows above the diagonal in column `k` of `a`, in topological
+/// order: every node before its ancestors.
+///
+/// A node is visited when `flag` holds `k` for it, so that `flag` needs no clearing between rows.
+/// `path` is scratch space.
+fn row_subtree(
+    a: &CscMatrix<f64>,
+    k: usize,
+    parent: &[usize],
+    flag: &mut [usize],
+    path: &mut Vec<usize>,
+    pattern: &mut Vec<usize>,
+) {
+    pattern.clear();
+    flag[k] = k;
+    for &row in a.col(k).0 {
+        if row > k {
+            continue;
+        }
+        // Climb from the row to the first visited node, then keep the path so that the paths
+        // found last come first.
+        path.clear();
+        let mut i = row;
+        while flag[i] != k {
+            path.push(i);
+            flag[i] = k;
+            i = parent[i];
+        }
+        pattern.extend(path.iter().rev());
+    }
+    pattern.reverse();
+}
+
+#[test]
+fn test_cholesky_factorization() {
+    use crate::csr::CsrMatrix;
+    use crate::test_util::{assert_close, Lcg};
+
+    let a = CsrMatrix::poisson2d(6, 5);
+    let n = a.nrows();
+    let f = CholeskyFactorization::new(&a.to_csc()).unwrap();
+    assert_eq!(f.n(), n);
+
+    // A = L Lᵀ, with a positive diagonal first in every column.
+    let l = f.l().to_dense();
+    for j in 0..n {
+        let (rows, values) = f.l().col(j);
+        assert_eq!(rows[0], j);
+        assert!(values[0] > 0.0);
+    }
+    let llt: Vec<f64> = (0..n * n)
+        .map(|k| {
+            (0..n)
+                .map(|m| l[(k / n) * n + m] * l[(k % n) * n + m])
+                .sum()
+        })
+        .collect();
+    assert_close(&llt, &a.to_dense(), 1e-12);
+
+    // The parent of every column is its first row below the diagonal.
+    let parent = elimination_tree(&a.to_csc());
+    for (j, &p) in parent.iter().enumerate() {
+        assert_eq!(p, f.l().col(j).0.get(1).copied().unwrap_or(NONE));
+    }
+
+    let mut rng = Lcg::new(101);
+    let x_true: Vec<f64> = (0..n).map(|_| rng.uniform()).collect();
+    let b = a.mul_vec(&x_true);
+    assert_close(&f.solve(&b), &x_true, 1e-12);
+
+    // Only the upper triangle is read.
+    let mut dense = a.to_dense();
+    dense[n] = 5.0;
+    let upper = CscMatrix::from_dense(n, n, &dense).unwrap();
+    assert_eq!(CholeskyFactorization::new(&upper).unwrap().l(), f.l());
+}
+
+#[test]
+fn test_cholesky_errors() {
+    let chol = |rows: usize, cols: usize, dense: &[f64]| {
+        let a = CscMatrix::from_dense(rows, cols, dense).unwrap();
+        CholeskyFactorization::new(&a).map(|_| ())
+    };
+
+    assert_eq!(
+        chol(2, 2, &[1.0, 2.0, 2.0, 1.0]),
+        Err(SparseError::ZeroPivot { index: 1 })
+    );
+    assert_eq!(
+        chol(2, 2, &[-1.0, 0.0, 0.0, 1.0]),
+        Err(SparseError::ZeroPivot { index: 0 })
+    );
+    assert_eq!(
+        chol(2, 2, &[1.0, 0.0, 0.0, 0.0]),
+        Err(SparseError::EmptyColumn { index: 1 })
+    );
+    assert_eq!(
+        chol(2, 3, &[1.0; 6]),
+        Err(SparseError::DimensionMismatch {
+            expected: 2,
+            found: 3
+        })
+    );
+    assert_eq!(chol(0, 0, &[]), Ok(()));
+}
+
+#[test]
+fn test_cholesky_solve_matrix() {
+    use crate::csr::CsrMatrix;
+    use crate::test_util::{assert_close, Lcg};
+
+    let a = CsrMatrix::poisson2d(7, 6);
+    let n = a.nrows();
+    let f = CholeskyFactorization::new(&a.to_csc()).unwrap();
+    let m = 9;
+    let mut rng = Lcg::new(103);
+    let b = CscMatrix::from_dense(n, m, &rng.dense(n, m, 0.03)).unwrap();
+    let x = f.solve_matrix(&b);
+    assert_eq!(x.shape(), (n, m));
+
+    let lt = f.l().transpose();
+    for j in 0..m {
+        let (rows, values) = b.col(j);
+        let bj = PackedVec::from_pairs(n, rows.iter().copied().zip(values.iter().copied()));
+        let bj = bj.unwrap();
+
+        let xj: Vec<f64> = (0..n).map(|i| x.get(i, j)).collect();
+        assert_close(&xj, &f.solve(&bj.scatter()), 1e-12);
+
+        // The reused work arrays give the bits of independent solves.
+        let y = f.l().solve_lower_triangular_sparse(&bj).unwrap();
+        let z = lt.solve_upper_triangular_sparse(&y).unwrap();
+        let (x_rows, x_values) = x.col(j);
+        assert_eq!(z.iter().map(|(i, _)| i).collect::<Vec<_>>(), x_rows);
+        assert_eq!(z.iter().map(|(_, v)| v).collect::<Vec<_>>(), x_values);
+    }
+}
+
+#[test]
+fn test_cholesky_rank1_update() {
+    use crate::csr::CsrMatrix;
+    use crate::test_util::{assert_close, Lcg};
+
+    let a = CsrMatrix::poisson2d(6, 5);
+    let n = a.nrows();
+    let original = CholeskyFactorization::new(&a.to_csc()).unwrap();
+
+    // w on part of the pattern of column 3 of L, which spans several path columns.
+    let f = 3;
+    let mut rng = Lcg::new(127);
+    let rows = original.l().col(f).0.to_vec();
+    assert!(rows.len() > 3);
+    let w = PackedVec::from_pairs(n, rows.iter().step_by(2).map(|&i| (i, rng.uniform() + 2.0)))
+        .unwrap();
+    let wd = w.scatter();
+    let b: Vec<f64> = (0..n).map(|_| rng.uniform()).collect();
+
+    // The factor of A + s w wᵀ from scratch.
+    let scratch = |s: f64| {
+        let mut dense = a.to_dense();
+        for (k, v) in dense.iter_mut().enumerate() {
+            *v += s * wd[k / n] * wd[k % n];
+        }
+        CholeskyFactorization::new(&CscMatrix::from_dense(n, n, &dense).unwrap()).unwrap()
+    };
+
+    // An update, then a downdate of part of it.
+    let mut factor = original.clone();
+    for (sigma, total) in [(0.7, 0.7), (-0.3, 0.4)] {
+        factor.rank1_update(&w, sigma).unwrap();
+        let expected = scratch(total);
+        assert_close(&factor.solve(&b), &expected.solve(&b), 1e-12);
+        assert_close(&factor.l().to_dense(), &expected.l().to_dense(), 1e-12);
+        assert_eq!(factor.nnz(), original.nnz());
+    }
+
+    // Undoing both leaves the original factor.
+    factor.rank1_update(&w, -0.4).unwrap();
+    assert_close(&factor.l().to_dense(), &original.l().to_dense(), 1e-12);
+
+    // A downdate that leaves an indefinite matrix fails and changes nothing.
+    let e0 = PackedVec::from_pairs(n, [(0, 1.0)]).unwrap();
+    let mut downdated = original.clone();
+    assert_eq!(
+        downdated.rank1_update(&e0, -10.0),
+        Err(SparseError::ZeroPivot { index: 0 })
+    );
+    assert_eq!(downdated.l(), original.l());
+    let deep = PackedVec::from_pairs(n, rows.iter().map(|&i| (i, 1.0))).unwrap();
+    assert!(matches!(
+        downdated.rank1_update(&deep, -3.0),
+        Err(SparseError::ZeroPivot { index }) if index > f
+    ));
+    assert_eq!(downdated.l(), original.l());
+
+    // Fill outside the pattern of L and the wrong length.
+    let outside = PackedVec::from_pairs(n, [(0, 1.0), (n - 1, 1.0)]).unwrap();
+    assert_eq!(
+        downdated.rank1_update(&outside, 1.0),
+        Err(SparseError::InvalidStructure(format!(
+            "w has an entry in row {}, outside the pattern of column 0 of L",
+            n - 1
+        )))
+    );
+    assert!(matches!(
+        downdated.rank1_update(&PackedVec::from_pairs(n + 1, [(0, 1.0)]).unwrap(), 1.0),
+        Err(SparseError::DimensionMismatch { .. })
+    ));
+    assert_eq!(
+        downdated.rank1_update(&PackedVec::from_pairs(n, []).unwrap(), 1.0),
+        Ok(())
+    );
+}
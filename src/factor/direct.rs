@@ -0,0 +1,313 @@
+//! A sparse direct solver in one call, which orders, factors and permutes on its own.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use super::{CholeskyFactorization, LuFactorization, LuOptions};
+use crate::csr::{CsrMatrix, SYMMETRY_TOL};
+use crate::error::SparseError;
+use crate::ordering;
+use crate::permutation::Permutation;
+
+/// The fill-reducing ordering a [`DirectSolver`] applies before factoring.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OrderingMethod {
+    /// Factor the matrix as numbered
+    Natural,
+    /// Reverse Cuthill–McKee, [`ordering::rcm`], for matrices with a narrow band
+    Rcm,
+    /// Approximate minimum degree, [`ordering::amd`]
+    #[default]
+    Amd,
+}
+
+/// The factorization a [`DirectSolver`] took.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DirectPath {
+    /// `P A Pᵀ = L Lᵀ`, for a symmetric positive definite matrix
+    Cholesky,
+    /// `P A Pᵀ = Pᵣᵀ L U`, for any other
+    Lu,
+}
+
+/// Options of [`DirectSolver::new`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DirectOptions {
+    /// The ordering applied symmetrically before factoring
+    pub ordering: OrderingMethod,
+    /// The tolerance of [`CsrMatrix::is_symmetric`] under which the matrix is taken as
+    /// symmetric and Cholesky is tried
+    pub symmetry_tol: f64,
+    /// Options of the LU factorization, when it is taken
+    pub lu: LuOptions,
+}
+
+impl Default for DirectOptions {
+    fn default() -> Self {
+        Self {
+            ordering: OrderingMethod::default(),
+            symmetry_tol: SYMMETRY_TOL,
+            lu: LuOptions::default(),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Factor {
+    Cholesky(CholeskyFactorization),
+    Lu(LuFactorization),
+}
+
+/// A factored square matrix that solves `A x = b`, with the fill-reducing ordering applied to
+/// `b` and undone on `x` inside, so that the caller never handles a permutation.
+///
+/// ```
+/// use sparse_matrix::csr::CsrMatrix;
+/// use sparse_matrix::factor::{DirectOptions, DirectPath, DirectSolver};
+///
+/// let a = CsrMatrix::poisson2d(10, 10);
+/// let solver = DirectSolver::new(&a, DirectOptions::default()).unwrap();
+/// assert_eq!(solver.path(), DirectPath::Cholesky);
+///
+/// let x = solver.solve(&vec![1.0; 100]);
+/// let r: Vec<f64> = a.mul_vec(&x).iter().map(|v| v - 1.0).collect();
+/// assert!(r.iter().all(|v| v.abs() < 1e-12));
+/// ```
+#[derive(Clone, Debug)]
+pub struct DirectSolver {
+    /// The ordering `P`: the factored matrix is `P A Pᵀ`
+    ordering: Permutation,
+    factor: Factor,
+}
+
+impl DirectSolver {
+    /// Order and factor the square matrix `a`.
+    ///
+    /// `A` is reordered to `P A Pᵀ` with [`DirectOptions::ordering`]. If it is symmetric within
+    /// [`DirectOptions::symmetry_tol`], the reordered matrix is factored with
+    /// [`CholeskyFactorization`], and with [`LuFactorization`] when that finds a pivot that
+    /// isn't positive, since the matrix is then indefinite; any other matrix goes to LU
+    /// directly.
+    ///
+    /// Fails with [`SparseError::DimensionMismatch`] if `a` isn't square. Otherwise an error
+    /// is the one of the factorization that was run last, unchanged, which numbers rows and
+    /// columns as in `P A Pᵀ`: [`SparseError::EmptyRow`] or [`SparseError::EmptyColumn`] for an
+    /// empty row or column, and [`SparseError::ZeroPivot`] when `a` is singular.
+    pub fn new(a: &CsrMatrix<f64>, opts: DirectOptions) -> Result<DirectSolver, SparseError> {
+        if a.ncols() != a.nrows() {
+            return Err(SparseError::DimensionMismatch {
+                expected: a.nrows(),
+                found: a.ncols(),
+            });
+        }
+        let ordering = match opts.ordering {
+            OrderingMethod::Natural => Permutation::identity(a.nrows()),
+            OrderingMethod::Rcm => ordering::rcm(a)?,
+            OrderingMethod::Amd => ordering::amd(a)?,
+        };
+        let permuted = ordering.permute_symmetric(a).to_csc();
+
+        let factor = if a.is_symmetric(opts.symmetry_tol) {
+            match CholeskyFactorization::new(&permuted) {
+                Ok(cholesky) => Factor::Cholesky(cholesky),
+                Err(SparseError::ZeroPivot { .. }) => {
+                    Factor::Lu(LuFactorization::new(&permuted, &opts.lu)?)
+                }
+                Err(e) => return Err(e),
+            }
+        } else {
+            Factor::Lu(LuFactorization::new(&permuted, &opts.lu)?)
+        };
+        Ok(Self { ordering, factor })
+    }
+
+    /// Return the number of rows and columns
+    pub fn n(&self) -> usize {
+        self.ordering.len()
+    }
+
+    /// Return the factorization that was taken
+    pub fn path(&self) -> DirectPath {
+        match self.factor {
+            Factor::Cholesky(_) => DirectPath::Cholesky,
+            Factor::Lu(_) => DirectPath::Lu,
+        }
+    }
+
+    /// Return the number of entries stored in the factors: `L` for Cholesky, `L` and `U`
+    /// together for LU
+    pub fn factor_nnz(&self) -> usize {
+        match &self.factor {
+            Factor::Cholesky(cholesky) => cholesky.nnz(),
+            Factor::Lu(lu) => lu.nnz(),
+        }
+    }
+
+    /// Return the ordering `P`, the factored matrix being `P A Pᵀ`
+    pub fn ordering(&self) -> &Permutation {
+        &self.ordering
+    }
+
+    /// Solve `A x = b`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `b.len() != self.n()`.
+    pub fn solve(&self, b: &[f64]) -> Vec<f64> {
+        let mut x = vec![0.0; b.len()];
+        self.solve_into(b, &mut x);
+        x
+    }
+
+    /// Solve `A x = b`, writing `x` into `x`: solve `(P A Pᵀ) y = P b` with the factors, then
+    /// `x = Pᵀ y`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `b.len()` or `x.len()` isn't `self.n()`.
+    pub fn solve_into(&self, b: &[f64], x: &mut [f64]) {
+        assert_eq!(b.len(), self.n(), "b has the wrong length");
+        assert_eq!(x.len(), self.n(), "x has the wrong length");
+
+        let pb = self.ordering.apply_to_vec(b);
+        let y = match &self.factor {
+            Factor::Cholesky(cholesky) => cholesky.solve(&pb),
+            Factor::Lu(lu) => lu.solve(&pb),
+        };
+        for (&i, &yi) in self.ordering.forward().iter().zip(&y) {
+            x[i] = yi;
+        }
+    }
+}
+
+#[test]
+fn test_direct_solver_poisson() {
+    use crate::test_util::{assert_close, Lcg};
+
+    let a = CsrMatrix::poisson2d(12, 10);
+    let n = a.nrows();
+    let mut rng = Lcg::new(107);
+    let x_true: Vec<f64> = (0..n).map(|_| rng.uniform()).collect();
+    let b = a.mul_vec(&x_true);
+
+    let solver = DirectSolver::new(&a, DirectOptions::default()).unwrap();
+    assert_eq!(solver.path(), DirectPath::Cholesky);
+    let x = solver.solve(&b);
+    assert_close(&x, &x_true, 1e-12);
+
+    // The manual pipeline gives the same bits.
+    let p = ordering::amd(&a).unwrap();
+    let cholesky = CholeskyFactorization::new(&p.permute_symmetric(&a).to_csc()).unwrap();
+    let y = cholesky.solve(&p.apply_to_vec(&b));
+    assert_eq!(x, p.inverse().apply_to_vec(&y));
+    assert_eq!(solver.factor_nnz(), cholesky.nnz());
+    assert_eq!(solver.ordering(), &p);
+
+    // The minimum degree ordering fills in less than the natural one.
+    let natural = DirectOptions {
+        ordering: OrderingMethod::Natural,
+        ..DirectOptions::default()
+    };
+    let unordered = DirectSolver::new(&a, natural).unwrap();
+    assert!(solver.factor_nnz() < unordered.factor_nnz());
+    let mut x = vec![0.0; n];
+    unordered.solve_into(&b, &mut x);
+    assert_close(&x, &x_true, 1e-12);
+}
+
+#[test]
+fn test_direct_solver_nonsymmetric() {
+    use crate::test_util::{assert_close, Lcg};
+
+    // Convection-diffusion: the Laplacian plus an upwinded first derivative along x.
+    let (nx, ny) = (9, 8);
+    let mut a = CsrMatrix::poisson2d(nx, ny).to_dense();
+    let n = nx * ny;
+    for i in 0..n {
+        a[i * n + i] += 0.5;
+        if i % nx > 0 {
+            a[i * n + i - 1] -= 0.5;
+        }
+    }
+    let a = CsrMatrix::from_dense(n, n, &a).unwrap();
+    let mut rng = Lcg::new(109);
+    let x_true: Vec<f64> = (0..n).map(|_| rng.uniform()).collect();
+    let b = a.mul_vec(&x_true);
+
+    for method in [
+        OrderingMethod::Natural,
+        OrderingMethod::Rcm,
+        OrderingMethod::Amd,
+    ] {
+        let opts = DirectOptions {
+            ordering: method,
+            ..DirectOptions::default()
+        };
+        let solver = DirectSolver::new(&a, opts).unwrap();
+        assert_eq!(solver.path(), DirectPath::Lu);
+        let x = solver.solve(&b);
+        assert_close(&x, &x_true, 1e-12);
+
+        let p = solver.ordering();
+        let permuted = p.permute_symmetric(&a).to_csc();
+        let lu = LuFactorization::new(&permuted, &LuOptions::default()).unwrap();
+        let y = lu.solve(&p.apply_to_vec(&b));
+        assert_eq!(x, p.inverse().apply_to_vec(&y), "{method:?}");
+        assert_eq!(solver.factor_nnz(), lu.nnz());
+    }
+    let rcm = DirectSolver::new(
+        &a,
+        DirectOptions {
+            ordering: OrderingMethod::Rcm,
+            ..DirectOptions::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(rcm.ordering(), &ordering::rcm(&a).unwrap());
+
+    // Symmetric but indefinite: Cholesky gives way to LU.
+    let swap = CsrMatrix::from_dense(2, 2, &[0.0, 1.0, 1.0, 0.0]).unwrap();
+    let solver = DirectSolver::new(&swap, DirectOptions::default()).unwrap();
+    assert_eq!(solver.path(), DirectPath::Lu);
+    assert_eq!(solver.solve(&[2.0, 3.0]), [3.0, 2.0]);
+}
+
+#[test]
+fn test_direct_solver_errors() {
+    let natural = DirectOptions {
+        ordering: OrderingMethod::Natural,
+        ..DirectOptions::default()
+    };
+
+    // Singular and nonsymmetric: the third column is the sum of the first two.
+    #[rustfmt::skip]
+    let singular = CsrMatrix::from_dense(3, 3, &[
+        2.0, 1.0, 3.0,
+        0.0, 1.0, 1.0,
+        4.0, 2.0, 6.0,
+    ])
+    .unwrap();
+    let expected = LuFactorization::new(&singular.to_csc(), &LuOptions::default()).unwrap_err();
+    assert_eq!(expected, SparseError::ZeroPivot { index: 2 });
+    assert_eq!(DirectSolver::new(&singular, natural).unwrap_err(), expected);
+
+    // Singular and symmetric: LU reports it after Cholesky.
+    let rank_one = CsrMatrix::from_dense(2, 2, &[1.0, 1.0, 1.0, 1.0]).unwrap();
+    assert_eq!(
+        DirectSolver::new(&rank_one, natural).unwrap_err(),
+        SparseError::ZeroPivot { index: 1 }
+    );
+
+    let empty_row = CsrMatrix::from_dense(2, 2, &[1.0, 1.0, 0.0, 0.0]).unwrap();
+    assert_eq!(
+        DirectSolver::new(&empty_row, natural).unwrap_err(),
+        SparseError::EmptyRow { index: 1 }
+    );
+    assert_eq!(
+        DirectSolver::new(&CsrMatrix::new(2, 3), DirectOptions::default()).unwrap_err(),
+        SparseError::DimensionMismatch {
+            expected: 2,
+            found: 3
+        }
+    );
+}
@@ -0,0 +1,104 @@
+//! Sparse direct factorizations: [`LuFactorization`] for any square matrix and
+//! [`CholeskyFactorization`] for a symmetric positive definite one.
+//!
+//! Both keep their factors in [`CscMatrix`] form and solve with a dense right-hand side as well
+//! as with a sparse matrix of them, whose columns go through the sparse triangular solves of
+//! Gilbert and Peierls so that the cost follows the fill of the result rather than `n` per
+//! column. [`schur_complement`] builds on that to form `C A⁻¹ B` without a dense inverse.
+//!
+//! [`DirectSolver`] puts an ordering from [`crate::ordering`] in front of either and picks the
+//! factorization, for a solve in one call.
+
+pub mod cholesky;
+pub mod direct;
+pub mod lu;
+
+pub use cholesky::CholeskyFactorization;
+pub use direct::{DirectOptions, DirectPath, DirectSolver, OrderingMethod};
+pub use lu::{ColumnOrdering, LuFactorization, LuOptions};
+
+use alloc::vec::Vec;
+
+use crate::csc::CscMatrix;
+use crate::csr::CsrMatrix;
+use crate::error::SparseError;
+
+/// Return `C A⁻¹ B` for the factorization `a_factor` of `A`, the product subtracted from `D` in
+/// the Schur complement `S = D − C A⁻¹ B` of the block matrix `[A B; C D]`.
+///
+/// `A⁻¹ B` is solved column by column with [`LuFactorization::solve_matrix`], which keeps the
+/// sparsity of `B`, and then multiplied by `C` with a sparse matrix product, so no dense block is
+/// ever formed. Fails with [`SparseError::DimensionMismatch`] when `C` doesn't have a column per
+/// row of `A`.
+///
+/// # Panics
+///
+/// Panics if `B` doesn't have a row per column of `A`.
+pub fn schur_complement(
+    c: &CsrMatrix<f64>,
+    a_factor: &LuFactorization,
+    b: &CscMatrix<f64>,
+) -> Result<CsrMatrix<f64>, SparseError> {
+    c.matmul(&a_factor.solve_matrix(b).to_csr())
+}
+
+/// Gather the sparse columns `(rows, values)` of an `nrows` by `columns.len()` matrix, each with
+/// its rows sorted, into a [`CscMatrix`].
+fn collect_columns(nrows: usize, columns: Vec<(Vec<usize>, Vec<f64>)>) -> CscMatrix<f64> {
+    let mut indptr = Vec::with_capacity(columns.len() + 1);
+    let mut indices = Vec::new();
+    let mut data = Vec::new();
+    indptr.push(0);
+    let ncols = columns.len();
+    for (rows, values) in columns {
+        indices.extend(rows);
+        data.extend(values);
+        indptr.push(indices.len());
+    }
+    CscMatrix::from_parts(nrows, ncols, indptr, indices, data)
+}
+
+#[test]
+fn test_schur_complement() {
+    use crate::test_util::{assert_close, Lcg};
+
+    let (n, m) = (30, 7);
+    let mut rng = Lcg::new(83);
+    let mut dense_a = rng.dense(n, n, 0.1);
+    for i in 0..n {
+        dense_a[i * n + i] += 4.0;
+    }
+    let dense_b = rng.dense(n, m, 0.15);
+    let dense_c = rng.dense(m, n, 0.15);
+
+    let a = CscMatrix::from_dense(n, n, &dense_a).unwrap();
+    let b = CscMatrix::from_dense(n, m, &dense_b).unwrap();
+    let c = CsrMatrix::from_dense(m, n, &dense_c).unwrap();
+    let factor = LuFactorization::new(&a, &LuOptions::default()).unwrap();
+    let s = schur_complement(&c, &factor, &b).unwrap();
+    assert_eq!(s.shape(), (m, m));
+
+    // The dense reference: X = A⁻¹ B column by column, then C X.
+    let mut x = alloc::vec![0.0; n * m];
+    for j in 0..m {
+        let mut xj: Vec<f64> = (0..n).map(|i| dense_b[i * m + j]).collect();
+        crate::test_util::lu_solve(n, &mut dense_a.clone(), &mut xj).unwrap();
+        for i in 0..n {
+            x[i * m + j] = xj[i];
+        }
+    }
+    let expected: Vec<f64> = (0..m * m)
+        .map(|k| {
+            let (i, j) = (k / m, k % m);
+            (0..n).map(|l| dense_c[i * n + l] * x[l * m + j]).sum()
+        })
+        .collect();
+    assert_close(&s.to_dense(), &expected, 1e-10);
+
+    // C with the wrong number of columns.
+    let short = CsrMatrix::from_dense(1, n - 1, &alloc::vec![1.0; n - 1]).unwrap();
+    assert!(matches!(
+        schur_complement(&short, &factor, &b),
+        Err(SparseError::DimensionMismatch { .. })
+    ));
+}
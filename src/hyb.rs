@@ -0,0 +1,200 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::coo::CooMatrix;
+use crate::csr::CsrMatrix;
+use crate::ell::EllMatrix;
+use crate::scalar::Scalar;
+
+/// A sparse matrix in hybrid (HYB) form: the first entries of every row in an [`EllMatrix`], and
+/// the entries that don't fit its width in a [`CooMatrix`].
+///
+/// A pure ELL matrix pads every row to the longest one, so a handful of long rows can multiply
+/// its size. The hybrid form caps the ELL width near the typical row length and moves the
+/// overflow to COO, which stores exactly what it holds.
+#[derive(Clone, Debug)]
+pub struct HybMatrix<T = f64> {
+    ell: EllMatrix<T>,
+    coo: CooMatrix<T>,
+}
+
+impl<T: Scalar> HybMatrix<T> {
+    /// Split a CSR matrix at the width [`HybMatrix::split_width`] picks. O(nnz + nrows × width).
+    pub fn from_csr(a: &CsrMatrix<T>) -> Self {
+        let row_lengths: Vec<usize> = (0..a.nrows()).map(|i| a.row(i).0.len()).collect();
+        Self::from_csr_with_width(a, Self::split_width(&row_lengths))
+    }
+
+    /// Split a CSR matrix at a given ELL width: the first `width` entries of each row go to the
+    /// ELL part and the rest to the COO part. O(nnz + nrows × width).
+    pub fn from_csr_with_width(a: &CsrMatrix<T>, width: usize) -> Self {
+        let (ell, coo) = EllMatrix::from_csr_with_width(a, width);
+        Self { ell, coo }
+    }
+
+    /// Pick the ELL width for rows of the given lengths: the largest width that at least a third
+    /// of the rows fill completely. Every ELL slot then does useful work in at least a third of
+    /// the rows, while the long rows spill into COO. This is the rule of Bell and Garland's
+    /// HYB format.
+    pub fn split_width(row_lengths: &[usize]) -> usize {
+        let max = row_lengths.iter().copied().max().unwrap_or(0);
+        // `at_least[k]` is the number of rows of length at least k.
+        let mut at_least = vec![0; max + 2];
+        for &len in row_lengths {
+            at_least[len] += 1;
+        }
+        for k in (0..=max).rev() {
+            at_least[k] += at_least[k + 1];
+        }
+
+        let threshold = row_lengths.len().div_ceil(3).max(1);
+        (0..=max)
+            .rev()
+            .find(|&k| at_least[k] >= threshold)
+            .unwrap_or(0)
+    }
+
+    /// Return the number of rows
+    pub fn nrows(&self) -> usize {
+        self.ell.nrows()
+    }
+
+    /// Return the number of columns
+    pub fn ncols(&self) -> usize {
+        self.ell.ncols()
+    }
+
+    /// Return `(nrows, ncols)`
+    pub fn shape(&self) -> (usize, usize) {
+        self.ell.shape()
+    }
+
+    /// Return the number of entries in both parts, without the ELL padding
+    pub fn nnz(&self) -> usize {
+        self.ell.nnz() + self.coo.nnz()
+    }
+
+    /// Return the ELL part
+    pub fn ell(&self) -> &EllMatrix<T> {
+        &self.ell
+    }
+
+    /// Return the COO part, holding the entries past the ELL width in row order
+    pub fn coo(&self) -> &CooMatrix<T> {
+        &self.coo
+    }
+
+    /// Multiply the matrix by the dense vector `x`: the ELL kernel, then the overflow entries
+    /// added on top.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `x.len() != self.ncols()`.
+    pub fn mul_vec(&self, x: &[T]) -> Vec<T> {
+        let mut y = self.ell.mul_vec(x);
+        for (i, j, v) in self.coo.triplets() {
+            y[i] += v * x[j];
+        }
+        y
+    }
+
+    /// Convert to CSR. O(nnz + nrows + ncols).
+    pub fn to_csr(&self) -> CsrMatrix<T> {
+        let ell = self.ell.to_csr();
+        let mut coo = CooMatrix::new(self.nrows(), self.ncols());
+        for i in 0..ell.nrows() {
+            let (cols, values) = ell.row(i);
+            for (&j, &v) in cols.iter().zip(values) {
+                coo.push(i, j, v);
+            }
+        }
+        for (i, j, v) in self.coo.triplets() {
+            coo.push(i, j, v);
+        }
+        coo.to_csr()
+    }
+}
+
+#[test]
+fn test_hyb_matrix() {
+    // Row lengths 2, 1, 2, 6, 0, 2: a third of the rows fill width 2.
+    assert_eq!(HybMatrix::<f64>::split_width(&[2, 1, 2, 6, 0, 2]), 2);
+    assert_eq!(HybMatrix::<f64>::split_width(&[5, 5, 5]), 5);
+    assert_eq!(HybMatrix::<f64>::split_width(&[]), 0);
+
+    #[rustfmt::skip]
+    let dense = [
+        1.0, 0.0, 0.0, 2.0, 0.0, 0.0,
+        0.0, 3.0, 0.0, 0.0, 0.0, 0.0,
+        0.0, 0.0, 4.0, 5.0, 0.0, 0.0,
+        6.0, 7.0, 8.0, 9.0, 1.0, 2.0,
+        0.0, 0.0, 0.0, 0.0, 0.0, 0.0,
+        0.0, 3.0, 0.0, 0.0, 0.0, 4.0,
+    ];
+    let a = CsrMatrix::from_dense(6, 6, &dense).unwrap();
+    let hyb = HybMatrix::from_csr(&a);
+    assert_eq!(hyb.ell().width(), 2);
+    assert_eq!(hyb.ell().nnz(), 9);
+    assert_eq!(
+        hyb.coo().triplets().collect::<Vec<_>>(),
+        [(3, 2, 8.0), (3, 3, 9.0), (3, 4, 1.0), (3, 5, 2.0)]
+    );
+    assert_eq!(hyb.nnz(), a.nnz());
+    assert_eq!(hyb.to_csr().to_dense(), dense);
+
+    let x = [1.0, -1.0, 2.0, 0.5, -3.0, 4.0];
+    assert_eq!(hyb.mul_vec(&x), a.mul_vec(&x));
+
+    // Width 0 puts everything in COO.
+    let hyb = HybMatrix::from_csr_with_width(&a, 0);
+    assert_eq!(hyb.coo().nnz(), a.nnz());
+    assert_eq!(hyb.mul_vec(&x), a.mul_vec(&x));
+}
+
+#[test]
+fn test_hyb_matrix_split_threshold() {
+    let split = HybMatrix::<f64>::split_width;
+
+    // The width needs ⌈nrows / 3⌉ rows at least that long: one of 3, two of 4 to 6, three of 7.
+    assert_eq!(split(&[7, 0, 0]), 7);
+    assert_eq!(split(&[7, 0, 0, 0]), 0);
+    assert_eq!(split(&[7, 3, 0, 0]), 3);
+    assert_eq!(split(&[4, 4, 3, 3, 3, 0]), 4);
+    assert_eq!(split(&[4, 4, 3, 3, 3, 0, 0]), 3);
+    assert_eq!(split(&[4, 4, 4, 3, 3, 0, 0]), 4);
+    assert_eq!(split(&[1]), 1);
+    assert_eq!(split(&[0, 0, 0]), 0);
+
+    // Rows of 3, 3 and 4 entries among 6: width 3, so the rows exactly at the threshold stay
+    // whole in ELL and only the fourth entry of the long row spills.
+    #[rustfmt::skip]
+    let dense = [
+        1.0, 2.0, 3.0, 0.0, 0.0,
+        0.0, 4.0, 5.0, 6.0, 0.0,
+        7.0, 0.0, 8.0, 9.0, 1.0,
+        0.0, 0.0, 0.0, 0.0, 0.0,
+        0.0, 0.0, 0.0, 0.0, 0.0,
+        0.0, 0.0, 0.0, 0.0, 0.0,
+    ];
+    let a = CsrMatrix::from_dense(6, 5, &dense).unwrap();
+    let hyb = HybMatrix::from_csr(&a);
+    assert_eq!(hyb.ell().width(), 3);
+    assert_eq!(hyb.ell().nnz(), 9);
+    assert_eq!(hyb.coo().triplets().collect::<Vec<_>>(), [(2, 4, 1.0)]);
+    assert_eq!(hyb.to_csr(), a);
+
+    // One width less spills the third entry of every row and the fourth of the long one.
+    let narrower = HybMatrix::from_csr_with_width(&a, 2);
+    assert_eq!(
+        narrower.coo().triplets().collect::<Vec<_>>(),
+        [(0, 2, 3.0), (1, 3, 6.0), (2, 3, 9.0), (2, 4, 1.0)]
+    );
+    let x = [1.0, -1.0, 2.0, 0.5, 3.0];
+    assert_eq!(narrower.mul_vec(&x), a.mul_vec(&x));
+
+    // A width past the longest row spills nothing and only pads.
+    let wider = HybMatrix::from_csr_with_width(&a, 5);
+    assert_eq!(wider.coo().nnz(), 0);
+    assert_eq!(wider.ell().padding_stats().padding, 6 * 5 - 10);
+    assert_eq!(wider.mul_vec(&x), a.mul_vec(&x));
+}
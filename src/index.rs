@@ -0,0 +1,192 @@
+//! The integer types the compressed formats and packed vectors can store their indices in.
+//!
+//! [`CsrMatrix`](crate::csr::CsrMatrix), [`CscMatrix`](crate::csc::CscMatrix) and
+//! [`PackedVec`](crate::vec::PackedVec) take the index type as a second parameter, usize by
+//! default. With u32 indices a matrix of fewer than 2³² rows, columns and entries takes half
+//! the index storage, which is also half the index traffic of a matrix-vector product:
+//!
+//! ```
+//! use sparse_matrix::prelude::*;
+//!
+//! let a = CsrMatrix::<f64>::poisson2d(4, 4);
+//! let small: CsrMatrix<f64, u32> = a.to_index_type().unwrap();
+//! let x = vec![1.0; 16];
+//! assert_eq!(small.mul_vec(&x), a.mul_vec(&x));
+//! ```
+//!
+//! The constructors and most operations are written for usize indices; a matrix or vector is
+//! built there and converted with `to_index_type` once its structure is final. For any index
+//! type, the three types provide:
+//!
+//! - their shape, `nnz`, and the raw `indptr`, `indices` and `data` arrays;
+//! - `row` of a [`CsrMatrix`](crate::csr::CsrMatrix), `col` of a
+//!   [`CscMatrix`](crate::csc::CscMatrix), and `get`, `iter`, `scatter`, `dot_dense` and
+//!   `has_nan` of a [`PackedVec`](crate::vec::PackedVec);
+//! - the products of a `CsrMatrix` with a dense vector and its transpose, `mul_vec`,
+//!   `mul_vec_into`, `mul_vec_transposed` and `mul_vec_transposed_into`;
+//! - the [`SparseMatrix`](crate::traits::SparseMatrix) and
+//!   [`SparseVector`](crate::traits::SparseVector) impls, and serde under the `serde` feature;
+//! - `to_index_type` itself.
+//!
+//! Everything else needs usize indices. That includes construction, equality, slicing,
+//! transposition and conversion between CSR and CSC, sparse–sparse products such as `matmul`,
+//! the arithmetic of packed vectors, the solvers and preconditioners, and the I/O readers and
+//! writers. The other formats, COO, DOK, LIL, BSR, DIA, ELL, HYB, banded, skyline and
+//! symmetric CSR, always store usize indices.
+
+use alloc::vec::Vec;
+use core::fmt::Debug;
+use core::hash::Hash;
+
+use crate::error::SparseError;
+use crate::scalar::Scalar;
+
+/// An unsigned integer type that indices are stored in.
+pub trait IndexType: Copy + Ord + Hash + Debug + Default + Send + Sync + 'static {
+    /// The largest value of the type, as a usize (saturating where the type is wider)
+    const MAX: usize;
+
+    /// Convert from usize, or return `None` when `i` exceeds [`IndexType::MAX`].
+    fn try_from_usize(i: usize) -> Option<Self>;
+
+    /// Convert to usize
+    fn index(self) -> usize;
+
+    /// Return `Σ values[k] · x[indices[k]]`. usize goes through [`Scalar::gather_dot`] and its
+    /// vectorized f64 kernel; the narrower types use a plain loop.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an index is out of bounds for `x`.
+    fn gather_dot<T: Scalar>(values: &[T], indices: &[Self], x: &[T]) -> T {
+        let mut sum = T::zero();
+        for (&v, &j) in values.iter().zip(indices) {
+            sum += v * x[j.index()];
+        }
+        sum
+    }
+
+    /// Set `y[i]` to the [`gather_dot`](Self::gather_dot) of row `i` of the CSR arrays with
+    /// `x`, for every `i < y.len()`. usize goes through [`Scalar::mul_vec_rows`] as
+    /// [`gather_dot`](Self::gather_dot) does.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `indptr` has fewer than `y.len() + 1` offsets, or an offset or index is out
+    /// of bounds.
+    fn mul_vec_rows<T: Scalar>(
+        indptr: &[Self],
+        indices: &[Self],
+        data: &[T],
+        x: &[T],
+        y: &mut [T],
+    ) {
+        for (i, yi) in y.iter_mut().enumerate() {
+            let row = indptr[i].index()..indptr[i + 1].index();
+            *yi = Self::gather_dot(&data[row.clone()], &indices[row], x);
+        }
+    }
+
+    /// `y[indices[k]] += alpha · values[k]`. usize goes through [`Scalar::scatter_add`] as
+    /// [`gather_dot`](Self::gather_dot) does.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an index is out of bounds for `y`.
+    fn scatter_add<T: Scalar>(alpha: T, values: &[T], indices: &[Self], y: &mut [T]) {
+        for (&v, &j) in values.iter().zip(indices) {
+            y[j.index()] += alpha * v;
+        }
+    }
+}
+
+impl IndexType for usize {
+    const MAX: usize = usize::MAX;
+
+    fn try_from_usize(i: usize) -> Option<Self> {
+        Some(i)
+    }
+
+    fn index(self) -> usize {
+        self
+    }
+
+    fn gather_dot<T: Scalar>(values: &[T], indices: &[usize], x: &[T]) -> T {
+        T::gather_dot(values, indices, x)
+    }
+
+    fn mul_vec_rows<T: Scalar>(
+        indptr: &[usize],
+        indices: &[usize],
+        data: &[T],
+        x: &[T],
+        y: &mut [T],
+    ) {
+        T::mul_vec_rows(indptr, indices, data, x, y)
+    }
+
+    fn scatter_add<T: Scalar>(alpha: T, values: &[T], indices: &[usize], y: &mut [T]) {
+        T::scatter_add(alpha, values, indices, y)
+    }
+}
+
+macro_rules! impl_index_type {
+    ($($t:ty),*) => {$(
+        impl IndexType for $t {
+            const MAX: usize = if <$t>::MAX as u128 > usize::MAX as u128 {
+                usize::MAX
+            } else {
+                <$t>::MAX as usize
+            };
+
+            fn try_from_usize(i: usize) -> Option<Self> {
+                <$t>::try_from(i).ok()
+            }
+
+            fn index(self) -> usize {
+                self as usize
+            }
+        }
+    )*};
+}
+
+impl_index_type!(u64, u32, u16);
+
+/// Convert every index of `indices`, failing with [`SparseError::IndexOutOfBounds`] on the first
+/// that doesn't fit in `J`.
+pub(crate) fn convert_indices<I: IndexType, J: IndexType>(
+    indices: &[I],
+) -> Result<Vec<J>, SparseError> {
+    indices
+        .iter()
+        .map(|&i| {
+            J::try_from_usize(i.index()).ok_or(SparseError::IndexOutOfBounds {
+                index: i.index(),
+                len: J::MAX.saturating_add(1),
+            })
+        })
+        .collect()
+}
+
+#[test]
+fn test_index_type() {
+    assert_eq!(<u16 as IndexType>::MAX, 65535);
+    assert_eq!(u32::try_from_usize(7), Some(7));
+    assert_eq!(u16::try_from_usize(65536), None);
+    assert_eq!(42u16.index(), 42);
+    assert_eq!(
+        convert_indices::<usize, u16>(&[1, 70000]),
+        Err(SparseError::IndexOutOfBounds {
+            index: 70000,
+            len: 65536
+        })
+    );
+
+    let x = [1.0, 2.0, 3.0, 4.0];
+    let values = [0.5, -1.0, 2.0];
+    assert_eq!(<u32 as IndexType>::gather_dot(&values, &[3, 0, 2], &x), 7.0);
+    assert_eq!(
+        <usize as IndexType>::gather_dot(&values, &[3, 0, 2], &x),
+        7.0
+    );
+}
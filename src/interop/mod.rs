@@ -0,0 +1,15 @@
+//! Conversions to and from the types of other linear algebra crates, each behind a feature named
+//! after the crate. They are `From` and `TryFrom` impls, plus products with the other crate's
+//! dense vectors:
+//!
+//! - `nalgebra`: `DVector` and `DMatrix`, and the CSR and CSC matrices of `nalgebra-sparse`.
+//! - `ndarray`: `Array1`, `Array2` and their views.
+//! - `sprs`: `CsVec` and `CsMat`, moving the arrays rather than copying them where the layouts
+//!   agree.
+
+#[cfg(feature = "nalgebra")]
+mod nalgebra;
+#[cfg(feature = "ndarray")]
+mod ndarray;
+#[cfg(feature = "sprs")]
+mod sprs;
@@ -0,0 +1,148 @@
+use nalgebra::{DMatrix, DVector};
+
+use crate::csc::CscMatrix;
+use crate::csr::CsrMatrix;
+use crate::scalar::Scalar;
+use crate::vec::PackedVec;
+
+impl<T: Scalar + nalgebra::Scalar> From<&DVector<T>> for PackedVec<T> {
+    /// Gather the nonzero components, like [`PackedVec::gather`].
+    fn from(x: &DVector<T>) -> Self {
+        PackedVec::gather(x.as_slice())
+    }
+}
+
+impl<T: Scalar + nalgebra::Scalar> From<&PackedVec<T>> for DVector<T> {
+    /// Scatter into a dense vector, like [`PackedVec::scatter`].
+    fn from(x: &PackedVec<T>) -> Self {
+        DVector::from_vec(x.scatter())
+    }
+}
+
+impl<T: Scalar + nalgebra::Scalar> From<&DMatrix<T>> for CsrMatrix<T> {
+    /// Gather the nonzero entries. O(nrows × ncols).
+    fn from(m: &DMatrix<T>) -> Self {
+        // The column-major storage of `m` is the row-major storage of its transpose.
+        CscMatrix::from_dense(m.ncols(), m.nrows(), m.as_slice())
+            .expect("the storage of a DMatrix has the right length")
+            .into_transpose_csr()
+    }
+}
+
+impl<T: Scalar + nalgebra::Scalar> From<&DMatrix<T>> for CscMatrix<T> {
+    /// Gather the nonzero entries. O(nrows × ncols).
+    fn from(m: &DMatrix<T>) -> Self {
+        CsrMatrix::from_dense(m.ncols(), m.nrows(), m.as_slice())
+            .expect("the storage of a DMatrix has the right length")
+            .into_transpose_csc()
+    }
+}
+
+impl<T: Scalar + nalgebra::Scalar> From<&CsrMatrix<T>> for DMatrix<T> {
+    fn from(a: &CsrMatrix<T>) -> Self {
+        DMatrix::from_row_slice(a.nrows(), a.ncols(), &a.to_dense())
+    }
+}
+
+impl<T: Scalar + nalgebra::Scalar> From<&CscMatrix<T>> for DMatrix<T> {
+    fn from(a: &CscMatrix<T>) -> Self {
+        DMatrix::from_row_slice(a.nrows(), a.ncols(), &a.to_dense())
+    }
+}
+
+impl<T: Scalar + nalgebra::Scalar> From<&CsrMatrix<T>> for nalgebra_sparse::CsrMatrix<T> {
+    /// Copy the arrays, which satisfy the invariants of `nalgebra-sparse` as they are. O(nnz).
+    fn from(a: &CsrMatrix<T>) -> Self {
+        nalgebra_sparse::CsrMatrix::try_from_csr_data(
+            a.nrows(),
+            a.ncols(),
+            a.indptr().to_vec(),
+            a.indices().to_vec(),
+            a.data().to_vec(),
+        )
+        .expect("a valid CSR matrix is valid for nalgebra-sparse")
+    }
+}
+
+impl<T: Scalar + nalgebra::Scalar> From<&nalgebra_sparse::CsrMatrix<T>> for CsrMatrix<T> {
+    /// Copy the arrays: `nalgebra-sparse` keeps the column indices of a row sorted and unique
+    /// too. O(nnz).
+    fn from(a: &nalgebra_sparse::CsrMatrix<T>) -> Self {
+        let (indptr, indices, data) = a.csr_data();
+        CsrMatrix::from_parts(
+            a.nrows(),
+            a.ncols(),
+            indptr.to_vec(),
+            indices.to_vec(),
+            data.to_vec(),
+        )
+    }
+}
+
+impl<T: Scalar + nalgebra::Scalar> From<&CscMatrix<T>> for nalgebra_sparse::CscMatrix<T> {
+    /// Copy the arrays, which satisfy the invariants of `nalgebra-sparse` as they are. O(nnz).
+    fn from(a: &CscMatrix<T>) -> Self {
+        nalgebra_sparse::CscMatrix::try_from_csc_data(
+            a.nrows(),
+            a.ncols(),
+            a.indptr().to_vec(),
+            a.indices().to_vec(),
+            a.data().to_vec(),
+        )
+        .expect("a valid CSC matrix is valid for nalgebra-sparse")
+    }
+}
+
+impl<T: Scalar + nalgebra::Scalar> From<&nalgebra_sparse::CscMatrix<T>> for CscMatrix<T> {
+    /// Copy the arrays: `nalgebra-sparse` keeps the row indices of a column sorted and unique
+    /// too. O(nnz).
+    fn from(a: &nalgebra_sparse::CscMatrix<T>) -> Self {
+        let (indptr, indices, data) = a.csc_data();
+        CscMatrix::from_parts(
+            a.nrows(),
+            a.ncols(),
+            indptr.to_vec(),
+            indices.to_vec(),
+            data.to_vec(),
+        )
+    }
+}
+
+/// Multiply a sparse matrix by a dense nalgebra vector, returning a dense nalgebra vector.
+///
+/// # Panics
+///
+/// Panics if `x.len() != self.ncols()`.
+impl<T: Scalar + nalgebra::Scalar> std::ops::Mul<&DVector<T>> for &CsrMatrix<T> {
+    type Output = DVector<T>;
+
+    fn mul(self, x: &DVector<T>) -> DVector<T> {
+        DVector::from_vec(self.mul_vec(x.as_slice()))
+    }
+}
+
+#[test]
+fn test_nalgebra_interop() {
+    let m = DMatrix::from_row_slice(2, 3, &[1.0, 0.0, 2.0, 0.0, 3.0, 0.0]);
+    let csr = CsrMatrix::from(&m);
+    assert_eq!(csr.nnz(), 3);
+    assert_eq!(csr.to_dense(), [1.0, 0.0, 2.0, 0.0, 3.0, 0.0]);
+    let csc = CscMatrix::from(&m);
+    assert_eq!(csc.to_dense(), csr.to_dense());
+    assert_eq!(DMatrix::from(&csr), m);
+    assert_eq!(DMatrix::from(&csc), m);
+
+    let x = DVector::from_vec(vec![1.0, 2.0, 3.0]);
+    assert_eq!(&csr * &x, &m * &x);
+
+    let packed = PackedVec::from(&DVector::from_vec(vec![0.0, 4.0, 0.0]));
+    assert_eq!(packed.len(), 1);
+    assert_eq!(DVector::from(&packed).as_slice(), [0.0, 4.0, 0.0]);
+
+    let ns = nalgebra_sparse::CsrMatrix::from(&csr);
+    assert_eq!(ns.nnz(), 3);
+    assert_eq!(CsrMatrix::from(&ns).to_dense(), csr.to_dense());
+    let ns = nalgebra_sparse::CscMatrix::from(&csc);
+    assert_eq!(ns.nnz(), 3);
+    assert_eq!(CscMatrix::from(&ns).to_dense(), csc.to_dense());
+}
@@ -0,0 +1,123 @@
+use ndarray::{Array1, Array2, ArrayView1, ArrayView2};
+
+use crate::csc::CscMatrix;
+use crate::csr::CsrMatrix;
+use crate::scalar::Scalar;
+use crate::vec::PackedVec;
+
+impl<T: Scalar> From<ArrayView1<'_, T>> for PackedVec<T> {
+    /// Gather the nonzero components, like [`PackedVec::gather`]. The view may be strided.
+    fn from(x: ArrayView1<'_, T>) -> Self {
+        match x.as_slice() {
+            Some(slice) => PackedVec::gather(slice),
+            None => PackedVec::gather(&x.to_vec()),
+        }
+    }
+}
+
+impl<T: Scalar> From<&PackedVec<T>> for Array1<T> {
+    /// Scatter into a dense array, like [`PackedVec::scatter`].
+    fn from(x: &PackedVec<T>) -> Self {
+        Array1::from_vec(x.scatter())
+    }
+}
+
+impl<T: Scalar> From<ArrayView2<'_, T>> for CsrMatrix<T> {
+    /// Gather the nonzero entries, whatever the memory layout of the view.
+    /// O(nrows × ncols).
+    fn from(m: ArrayView2<'_, T>) -> Self {
+        let (nrows, ncols) = m.dim();
+        let dense: Vec<T> = m.iter().copied().collect();
+        CsrMatrix::from_dense(nrows, ncols, &dense).expect("the view has nrows × ncols elements")
+    }
+}
+
+impl<T: Scalar> From<ArrayView2<'_, T>> for CscMatrix<T> {
+    /// Gather the nonzero entries, whatever the memory layout of the view.
+    /// O(nrows × ncols).
+    fn from(m: ArrayView2<'_, T>) -> Self {
+        let (nrows, ncols) = m.dim();
+        let dense: Vec<T> = m.iter().copied().collect();
+        CscMatrix::from_dense(nrows, ncols, &dense).expect("the view has nrows × ncols elements")
+    }
+}
+
+impl<T: Scalar> From<&CsrMatrix<T>> for Array2<T> {
+    fn from(a: &CsrMatrix<T>) -> Self {
+        Array2::from_shape_vec(a.shape(), a.to_dense()).expect("to_dense is row-major")
+    }
+}
+
+impl<T: Scalar> From<&CscMatrix<T>> for Array2<T> {
+    fn from(a: &CscMatrix<T>) -> Self {
+        Array2::from_shape_vec(a.shape(), a.to_dense()).expect("to_dense is row-major")
+    }
+}
+
+/// Multiply a sparse matrix by a dense ndarray vector.
+///
+/// # Panics
+///
+/// Panics if `x.len() != self.ncols()`.
+impl<T: Scalar> std::ops::Mul<ArrayView1<'_, T>> for &CsrMatrix<T> {
+    type Output = Array1<T>;
+
+    fn mul(self, x: ArrayView1<'_, T>) -> Array1<T> {
+        assert_eq!(x.len(), self.ncols(), "x has the wrong length");
+        self.row_iter()
+            .map(|row| {
+                let mut sum = T::zero();
+                for (j, v) in row.iter() {
+                    sum += v * x[j];
+                }
+                sum
+            })
+            .collect()
+    }
+}
+
+/// Multiply a sparse matrix by a dense ndarray matrix: row `i` of the product is the sum of the
+/// rows `j` of `b` scaled by the entries `(i, j)`. O(nnz × b.ncols()).
+///
+/// # Panics
+///
+/// Panics if `b.nrows() != self.ncols()`.
+impl<T: Scalar> std::ops::Mul<ArrayView2<'_, T>> for &CsrMatrix<T> {
+    type Output = Array2<T>;
+
+    fn mul(self, b: ArrayView2<'_, T>) -> Array2<T> {
+        assert_eq!(b.nrows(), self.ncols(), "b has the wrong number of rows");
+        let mut c = Array2::from_elem((self.nrows(), b.ncols()), T::zero());
+        for (row, mut c_row) in self.row_iter().zip(c.rows_mut()) {
+            for (j, v) in row.iter() {
+                for (cij, &bjk) in c_row.iter_mut().zip(b.row(j)) {
+                    *cij += v * bjk;
+                }
+            }
+        }
+        c
+    }
+}
+
+#[test]
+fn test_ndarray_interop() {
+    use ndarray::{array, s};
+
+    let m = array![[1.0, 0.0, 2.0], [0.0, 3.0, 0.0]];
+    let csr = CsrMatrix::from(m.view());
+    assert_eq!(csr.nnz(), 3);
+    assert_eq!(Array2::from(&csr), m);
+    // A transposed view isn't in standard layout.
+    let csc = CscMatrix::from(m.t());
+    assert_eq!(Array2::from(&csc), m.t());
+
+    let x = array![1.0, 2.0, 3.0];
+    assert_eq!(&csr * x.view(), m.dot(&x));
+    let b = array![[1.0, 0.0], [2.0, 1.0], [0.0, -1.0]];
+    assert_eq!(&csr * b.view(), m.dot(&b));
+
+    let strided = array![0.0, 1.0, 5.0, 1.0, 0.0, 1.0];
+    let packed = PackedVec::from(strided.slice(s![..;2]));
+    assert_eq!(packed.iter().collect::<Vec<_>>(), [(1, 5.0)]);
+    assert_eq!(Array1::from(&packed), array![0.0, 5.0, 0.0]);
+}
@@ -0,0 +1,108 @@
+use sprs::{CsMat, CsVec};
+
+use crate::csc::CscMatrix;
+use crate::csr::CsrMatrix;
+use crate::scalar::Scalar;
+use crate::vec::PackedVec;
+
+impl<T: Scalar> From<PackedVec<T>> for CsVec<T> {
+    /// Move the arrays, sorting them by index first if they aren't. O(nnz) when sorted.
+    fn from(x: PackedVec<T>) -> Self {
+        let (len, index, data) = x.into_sorted_parts();
+        CsVec::new(len, index, data)
+    }
+}
+
+impl<T: Scalar> From<&PackedVec<T>> for CsVec<T> {
+    fn from(x: &PackedVec<T>) -> Self {
+        CsVec::from(x.clone())
+    }
+}
+
+impl<T: Scalar> From<CsVec<T>> for PackedVec<T> {
+    /// Move the arrays. O(1).
+    fn from(x: CsVec<T>) -> Self {
+        let len = x.dim();
+        let (index, data) = x.into_raw_storage();
+        PackedVec::from_parts(len, index, data)
+    }
+}
+
+impl<T: Scalar> From<CsrMatrix<T>> for CsMat<T> {
+    /// Move the arrays, which satisfy the invariants of `sprs` as they are. O(nrows) to check
+    /// them.
+    fn from(a: CsrMatrix<T>) -> Self {
+        let (nrows, ncols, indptr, indices, data) = a.into_parts();
+        CsMat::new((nrows, ncols), indptr, indices, data)
+    }
+}
+
+impl<T: Scalar> From<&CsrMatrix<T>> for CsMat<T> {
+    fn from(a: &CsrMatrix<T>) -> Self {
+        CsMat::from(a.clone())
+    }
+}
+
+impl<T: Scalar> From<CscMatrix<T>> for CsMat<T> {
+    /// Move the arrays into a CSC `CsMat`. O(ncols) to check them.
+    fn from(a: CscMatrix<T>) -> Self {
+        let (nrows, ncols, indptr, indices, data) = a.into_parts();
+        CsMat::new_csc((nrows, ncols), indptr, indices, data)
+    }
+}
+
+impl<T: Scalar> From<&CscMatrix<T>> for CsMat<T> {
+    fn from(a: &CscMatrix<T>) -> Self {
+        CsMat::from(a.clone())
+    }
+}
+
+impl<T: Scalar + Default> From<CsMat<T>> for CsrMatrix<T> {
+    /// Move the arrays of a CSR `CsMat`: `sprs` keeps the column indices of a row sorted and
+    /// unique too. A CSC `CsMat` is converted first, in O(nnz + nrows + ncols).
+    fn from(a: CsMat<T>) -> Self {
+        let (nrows, ncols) = a.shape();
+        let (indptr, indices, data) = a.into_csr().into_raw_storage();
+        CsrMatrix::from_parts(nrows, ncols, rebase(indptr), indices, data)
+    }
+}
+
+impl<T: Scalar + Default> From<CsMat<T>> for CscMatrix<T> {
+    /// Move the arrays of a CSC `CsMat`: `sprs` keeps the row indices of a column sorted and
+    /// unique too. A CSR `CsMat` is converted first, in O(nnz + nrows + ncols).
+    fn from(a: CsMat<T>) -> Self {
+        let (nrows, ncols) = a.shape();
+        let (indptr, indices, data) = a.into_csc().into_raw_storage();
+        CscMatrix::from_parts(nrows, ncols, rebase(indptr), indices, data)
+    }
+}
+
+/// Make an index pointer start at zero: `sprs` lets one start at an offset into the arrays.
+fn rebase(mut indptr: Vec<usize>) -> Vec<usize> {
+    let start = indptr[0];
+    if start != 0 {
+        indptr.iter_mut().for_each(|p| *p -= start);
+    }
+    indptr
+}
+
+#[test]
+fn test_sprs_interop() {
+    let csr = CsrMatrix::from_dense(2, 3, &[1.0, 0.0, 2.0, 0.0, 3.0, 0.0]).unwrap();
+    let m = CsMat::from(&csr);
+    assert!(m.is_csr());
+    assert_eq!(m.get(0, 2), Some(&2.0));
+    assert_eq!(CsrMatrix::from(m.clone()).to_dense(), csr.to_dense());
+    assert_eq!(CscMatrix::from(m).to_dense(), csr.to_dense());
+
+    let m = CsMat::from(csr.to_csc());
+    assert!(m.is_csc());
+    assert_eq!(m.get(1, 1), Some(&3.0));
+    assert_eq!(CsrMatrix::from(m).to_dense(), csr.to_dense());
+
+    let x = PackedVec::gather(&[0.0, 4.0, 0.0, 5.0]);
+    let v = CsVec::from(&x);
+    assert_eq!(v.dim(), 4);
+    assert_eq!(v.indices(), [1, 3]);
+    assert_eq!(PackedVec::from(v).scatter(), [0.0, 4.0, 0.0, 5.0]);
+}
@@ -0,0 +1,255 @@
+//! Delimited text listing one entry per line, as `row,col,value` triplets or as the
+//! `src dst [weight]` edge lists most graph datasets come in.
+//!
+//! Lines that are blank or start with `#` or `%` are comments. Each entry has two or three
+//! fields: an entry without a value, such as an unweighted edge, is read as 1.0. Duplicate
+//! entries add up when the matrix is converted, as with [`CooMatrix::push`], unless
+//! [`CsvOptions::duplicates`] says otherwise.
+
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use crate::coo::{CooMatrix, DuplicatePolicy};
+use crate::error::SparseError;
+
+/// How to read a delimited file.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CsvOptions {
+    /// The character between the fields. `None` splits on any run of whitespace or commas,
+    /// which covers CSV, TSV and space-separated files alike.
+    pub delimiter: Option<char>,
+    /// Whether the indices count from 1, as in MATLAB or Matrix Market, rather than from 0
+    pub one_based: bool,
+    /// Whether every off-diagonal entry `(i, j)` also stands for `(j, i)`, as in an undirected
+    /// graph listing each edge once. A file listing both directions must not set this, or the
+    /// edges count twice.
+    pub symmetric: bool,
+    /// Whether the first line that is not a comment is a header, such as `src,dst,weight`,
+    /// to skip
+    pub header: bool,
+    /// The shape of the matrix. `None` takes the smallest that holds every entry, square when
+    /// `symmetric` is set.
+    pub shape: Option<(usize, usize)>,
+    /// How an entry listed more than once is read. The default sums the values, when the
+    /// triplets are converted.
+    pub duplicates: DuplicatePolicy,
+}
+
+/// Read a delimited file from disk.
+pub fn read_path(
+    path: impl AsRef<Path>,
+    options: &CsvOptions,
+) -> Result<CooMatrix<f64>, SparseError> {
+    read(BufReader::new(File::open(path)?), options)
+}
+
+/// Read a delimited file as triplets.
+///
+/// ```
+/// use sparse_matrix::io::csv::{self, CsvOptions};
+///
+/// let edges = "# src dst\n1 2\n2 3\n";
+/// let options = CsvOptions { one_based: true, symmetric: true, ..Default::default() };
+/// let a = csv::read(edges.as_bytes(), &options).unwrap().to_csr();
+/// assert_eq!(a.to_dense(), [0.0, 1.0, 0.0, 1.0, 0.0, 1.0, 0.0, 1.0, 0.0]);
+/// ```
+pub fn read(reader: impl BufRead, options: &CsvOptions) -> Result<CooMatrix<f64>, SparseError> {
+    let parse_error = |line: usize, reason: String| SparseError::Parse { line, reason };
+    let (mut rows, mut cols, mut values) = (Vec::new(), Vec::new(), Vec::new());
+    let mut header = options.header;
+
+    for (k, line) in reader.lines().enumerate() {
+        let k = k + 1;
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with('%') {
+            continue;
+        }
+        if header {
+            header = false;
+            continue;
+        }
+
+        let fields: Vec<&str> = match options.delimiter {
+            Some(delimiter) => line.split(delimiter).map(str::trim).collect(),
+            None => line
+                .split(|c: char| c.is_whitespace() || c == ',')
+                .filter(|field| !field.is_empty())
+                .collect(),
+        };
+        if fields.len() != 2 && fields.len() != 3 {
+            return Err(parse_error(
+                k,
+                format!("expected 2 or 3 fields, found {}", fields.len()),
+            ));
+        }
+        let index = |s: &str| {
+            let i = s
+                .parse::<usize>()
+                .map_err(|_| parse_error(k, format!("expected an index, found {s}")))?;
+            match options.one_based {
+                true if i == 0 => Err(parse_error(k, "index 0 in a 1-based file".to_string())),
+                true => Ok(i - 1),
+                false => Ok(i),
+            }
+        };
+        let i = index(fields[0])?;
+        let j = index(fields[1])?;
+        let v = match fields.get(2) {
+            Some(s) => s
+                .parse::<f64>()
+                .map_err(|_| parse_error(k, format!("expected a value, found {s}")))?,
+            None => 1.0,
+        };
+        if let Some((nrows, ncols)) = options.shape {
+            let (i_max, j_max) = match options.symmetric {
+                true => (i.max(j), i.max(j)),
+                false => (i, j),
+            };
+            if i_max >= nrows || j_max >= ncols {
+                return Err(parse_error(
+                    k,
+                    format!("entry ({}, {}) out of bounds", fields[0], fields[1]),
+                ));
+            }
+        }
+
+        rows.push(i);
+        cols.push(j);
+        values.push(v);
+        if options.symmetric && i != j {
+            rows.push(j);
+            cols.push(i);
+            values.push(v);
+        }
+    }
+
+    let (nrows, ncols) = options.shape.unwrap_or_else(|| {
+        let nrows = rows.iter().max().map_or(0, |&i| i + 1);
+        let ncols = cols.iter().max().map_or(0, |&j| j + 1);
+        (nrows, ncols)
+    });
+    CooMatrix::from_triplets_with(nrows, ncols, rows, cols, values, options.duplicates)
+}
+
+#[test]
+fn test_csv_read() {
+    let read_dense = |text: &str, options: CsvOptions| {
+        let coo = read(text.as_bytes(), &options).unwrap();
+        (coo.shape(), coo.to_csr().to_dense())
+    };
+
+    // Triplets with a header, 0-based, summing the duplicate.
+    let triplets = "row,col,value\n0,1,2.5\n% a comment\n\n2, 0, -1\n0,1,0.5\n";
+    let options = CsvOptions {
+        header: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        read_dense(triplets, options),
+        ((3, 2), vec![0.0, 3.0, 0.0, 0.0, -1.0, 0.0])
+    );
+
+    // A weighted, 1-based, tab-separated edge list with a self-loop and a given shape.
+    let edges = "1\t3\t0.5\n2\t2\t4\n";
+    let options = CsvOptions {
+        delimiter: Some('\t'),
+        one_based: true,
+        symmetric: true,
+        shape: Some((4, 4)),
+        ..Default::default()
+    };
+    let (shape, dense) = read_dense(edges, options);
+    assert_eq!(shape, (4, 4));
+    assert_eq!(
+        &dense[..12],
+        [0.0, 0.0, 0.5, 0.0, 0.0, 4.0, 0.0, 0.0, 0.5, 0.0, 0.0, 0.0]
+    );
+
+    // A symmetric edge list is square even when its first column doesn't reach the last node.
+    assert_eq!(
+        read_dense(
+            "0 2\n",
+            CsvOptions {
+                symmetric: true,
+                ..Default::default()
+            }
+        )
+        .0,
+        (3, 3)
+    );
+    assert_eq!(read_dense("", CsvOptions::default()).0, (0, 0));
+
+    let errors = [
+        ("0 1 2 3\n", CsvOptions::default(), 1),
+        ("0 1\nx 1\n", CsvOptions::default(), 2),
+        ("0 1 one\n", CsvOptions::default(), 1),
+        (
+            "1 1\n0 1\n",
+            CsvOptions {
+                one_based: true,
+                ..Default::default()
+            },
+            2,
+        ),
+        (
+            "0 2\n",
+            CsvOptions {
+                shape: Some((3, 2)),
+                ..Default::default()
+            },
+            1,
+        ),
+        // An empty field in a file with an explicit delimiter.
+        (
+            "0,,1\n",
+            CsvOptions {
+                delimiter: Some(','),
+                ..Default::default()
+            },
+            1,
+        ),
+    ];
+    for (text, options, line) in errors {
+        match read(text.as_bytes(), &options) {
+            Err(SparseError::Parse { line: l, .. }) => assert_eq!(l, line, "{text}"),
+            other => panic!("expected a parse error, got {other:?}"),
+        }
+    }
+    assert!(matches!(
+        read_path("/nonexistent/edges.csv", &CsvOptions::default()),
+        Err(SparseError::Io(_))
+    ));
+}
+
+#[test]
+fn test_csv_duplicates() {
+    // A sensor log where the later reading of (0, 1) overrides the earlier one.
+    let log = "0 1 2.5
+1 0 1
+0 1 4
+";
+    let read_with = |duplicates| {
+        let options = CsvOptions {
+            duplicates,
+            ..Default::default()
+        };
+        read(log.as_bytes(), &options)
+    };
+    let sum = read_with(DuplicatePolicy::Sum).unwrap();
+    assert_eq!(sum.nnz(), 3);
+    assert_eq!(sum.to_csr().to_dense(), [0.0, 6.5, 1.0, 0.0]);
+    let last = read_with(DuplicatePolicy::Last).unwrap();
+    assert_eq!(last.nnz(), 2);
+    assert_eq!(last.to_csr().to_dense(), [0.0, 4.0, 1.0, 0.0]);
+    assert_eq!(
+        read_with(DuplicatePolicy::Error).unwrap_err(),
+        SparseError::DuplicateEntry {
+            row: 0,
+            col: 1,
+            first: "2.5".into(),
+            second: "4.0".into(),
+        }
+    );
+}
@@ -0,0 +1,555 @@
+//! The Harwell–Boeing and Rutherford–Boeing formats (`.hb`, `.rua`, `.rsa`, `.rb`, ...), in
+//! which many classic test collections were published.
+//!
+//! Both store a matrix by columns, as in [`CscMatrix`], on 80-column cards:
+//!
+//! 1. the title (72 characters) and a key (8 characters);
+//! 2. the number of cards of each section: in total, pointers, indices and values, plus the
+//!    right-hand side in Harwell–Boeing files;
+//! 3. the type, such as `RUA`, and `nrows ncols nnz`;
+//! 4. the Fortran formats of the pointers, indices and values, such as `(16I5)` or `(4E20.12)`;
+//! 5. in Harwell–Boeing files with right-hand sides, a line describing them;
+//!
+//! then the 1-based column pointers, the 1-based row indices and the values, in fixed-width
+//! fields that may run into each other. The reader accepts both formats, with real (`R`),
+//! integer (`I`) and pattern (`P`, `Q`) values, read as f64 with pattern entries becoming 1.0.
+//! Symmetric (`S`), Hermitian (`H`) and skew-symmetric (`Z`) files store the lower triangle,
+//! and the mirrored entries are added back. Complex and elemental (unassembled) files are
+//! rejected, and right-hand sides are skipped.
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+use crate::coo::CooMatrix;
+use crate::csc::CscMatrix;
+use crate::error::SparseError;
+
+/// A matrix read from a Harwell–Boeing or Rutherford–Boeing file.
+#[derive(Clone, Debug)]
+pub struct HarwellBoeing {
+    /// The title on the first line, without trailing blanks
+    pub title: String,
+    /// The key identifying the matrix in its collection, without trailing blanks
+    pub key: String,
+    /// The matrix, with duplicates summed and the mirrored half of a symmetric file added back
+    pub matrix: CscMatrix<f64>,
+}
+
+/// Which of the two formats to write. They differ in the second header line, which counts the
+/// cards of the right-hand side in Harwell–Boeing files only.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Variant {
+    HarwellBoeing,
+    RutherfordBoeing,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Symmetry {
+    General,
+    Symmetric,
+    SkewSymmetric,
+}
+
+/// A Fortran edit descriptor such as `(16I5)`: `per_line` fields of `width` characters.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct FixedFormat {
+    per_line: usize,
+    width: usize,
+}
+
+/// Read a Harwell–Boeing or Rutherford–Boeing file from disk.
+pub fn read_path(path: impl AsRef<Path>) -> Result<HarwellBoeing, SparseError> {
+    read(BufReader::new(File::open(path)?))
+}
+
+/// Read a Harwell–Boeing or Rutherford–Boeing file.
+pub fn read(reader: impl BufRead) -> Result<HarwellBoeing, SparseError> {
+    let parse_error = |line: usize, reason: String| SparseError::Parse { line, reason };
+    let mut cards = Cards {
+        lines: reader.lines(),
+        line: 0,
+    };
+
+    // #1: The header.
+    let first = cards.next("the title line")?;
+    let title = first.get(..72).unwrap_or(&first).trim_end().to_string();
+    let key = first.get(72..).unwrap_or("").trim().to_string();
+
+    let counts = cards.next("the card counts")?;
+    let counts = parse_integers(cards.line, &counts)?;
+    if !(4..=5).contains(&counts.len()) {
+        return Err(parse_error(
+            cards.line,
+            "expected 4 or 5 card counts".to_string(),
+        ));
+    }
+    let rhs_cards = counts.get(4).copied().unwrap_or(0);
+
+    let line = cards.next("the type line")?;
+    let k = cards.line;
+    // The type is three characters, which need not be one byte each in a corrupt file.
+    let split = line.char_indices().nth(3).map_or(line.len(), |(i, _)| i);
+    let mxtype: Vec<char> = line[..split]
+        .chars()
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+    let sizes = parse_integers(k, &line[split..])?;
+    if mxtype.len() != 3 || sizes.len() < 3 {
+        return Err(parse_error(
+            k,
+            "expected a type and nrows ncols nnz".to_string(),
+        ));
+    }
+    let (nrows, ncols, nnz) = (sizes[0], sizes[1], sizes[2]);
+    let (Some(npointers), Some(end)) = (ncols.checked_add(1), nnz.checked_add(1)) else {
+        return Err(parse_error(k, "too many columns or entries".to_string()));
+    };
+    let pattern = match mxtype[0] {
+        'R' | 'I' => false,
+        'P' | 'Q' => true,
+        other => return Err(parse_error(k, format!("unsupported value type {other}"))),
+    };
+    let symmetry = match mxtype[1] {
+        'U' | 'R' => Symmetry::General,
+        // Hermitian is the same as symmetric for real values.
+        'S' | 'H' => Symmetry::Symmetric,
+        'Z' => Symmetry::SkewSymmetric,
+        other => return Err(parse_error(k, format!("unsupported symmetry {other}"))),
+    };
+    if mxtype[2] != 'A' {
+        return Err(parse_error(
+            k,
+            "only assembled matrices are supported".to_string(),
+        ));
+    }
+    if symmetry != Symmetry::General && nrows != ncols {
+        return Err(parse_error(
+            k,
+            "a symmetric matrix must be square".to_string(),
+        ));
+    }
+
+    let line = cards.next("the format line")?;
+    let k = cards.line;
+    let formats: Vec<&str> = line.split_whitespace().collect();
+    let expected_formats = if pattern { 2 } else { 3 };
+    if formats.len() < expected_formats {
+        return Err(parse_error(
+            k,
+            format!("expected {expected_formats} formats"),
+        ));
+    }
+    let ptr_format = parse_format(k, formats[0])?;
+    let ind_format = parse_format(k, formats[1])?;
+    let val_format = if pattern {
+        None
+    } else {
+        Some(parse_format(k, formats[2])?)
+    };
+    if rhs_cards > 0 {
+        cards.next("the right-hand side line")?;
+    }
+
+    // #2: The columns.
+    let indptr = cards.fields(ptr_format, npointers, "a column pointer", parse_index)?;
+    let k = cards.line;
+    if indptr[0] != 1 || indptr[ncols] != end || !indptr.is_sorted() {
+        return Err(parse_error(
+            k,
+            format!("column pointers must increase from 1 to {end}"),
+        ));
+    }
+    let rows = cards.fields(ind_format, nnz, "a row index", parse_index)?;
+    let k = cards.line;
+    if let Some(&i) = rows.iter().find(|&&i| i == 0 || i > nrows) {
+        return Err(parse_error(k, format!("row {i} out of bounds")));
+    }
+    let values = match val_format {
+        Some(format) => cards.fields(format, nnz, "a value", parse_real)?,
+        None => vec![1.0; nnz],
+    };
+
+    let mut coo = CooMatrix::new(nrows, ncols);
+    for j in 0..ncols {
+        for k in indptr[j] - 1..indptr[j + 1] - 1 {
+            let (i, v) = (rows[k] - 1, values[k]);
+            coo.push(i, j, v);
+            if i != j {
+                match symmetry {
+                    Symmetry::General => {}
+                    Symmetry::Symmetric => coo.push(j, i, v),
+                    Symmetry::SkewSymmetric => coo.push(j, i, -v),
+                }
+            }
+        }
+    }
+
+    Ok(HarwellBoeing {
+        title,
+        key,
+        matrix: coo.to_csc(),
+    })
+}
+
+/// Write a CSC matrix as an assembled real unsymmetric (`RUA`, or `RRA` when not square) file.
+/// The title is cut to 72 characters and the key to 8.
+pub fn write_csc(
+    mut writer: impl Write,
+    a: &CscMatrix<f64>,
+    title: &str,
+    key: &str,
+    variant: Variant,
+) -> Result<(), SparseError> {
+    let index_width = (a.nnz().max(a.nrows()) + 1).to_string().len() + 1;
+    let index_format = FixedFormat {
+        per_line: 80 / index_width,
+        width: index_width,
+    };
+    // 17 significant digits, enough to read back every f64 exactly.
+    let value_format = FixedFormat {
+        per_line: 3,
+        width: 26,
+    };
+    let ptr_cards = (a.ncols() + 1).div_ceil(index_format.per_line);
+    let ind_cards = a.nnz().div_ceil(index_format.per_line);
+    let val_cards = a.nnz().div_ceil(value_format.per_line);
+    let total = ptr_cards + ind_cards + val_cards;
+    let mxtype = if a.nrows() == a.ncols() { "RUA" } else { "RRA" };
+    let index_fortran = format!("({}I{})", index_format.per_line, index_format.width);
+
+    writeln!(writer, "{title:<72.72}{key:<8.8}")?;
+    match variant {
+        Variant::HarwellBoeing => writeln!(
+            writer,
+            "{total:>14}{ptr_cards:>14}{ind_cards:>14}{val_cards:>14}{:>14}",
+            0
+        )?,
+        Variant::RutherfordBoeing => writeln!(
+            writer,
+            "{total:>14}{ptr_cards:>14}{ind_cards:>14}{val_cards:>14}"
+        )?,
+    }
+    writeln!(
+        writer,
+        "{mxtype}{:11}{:>14}{:>14}{:>14}{:>14}",
+        "",
+        a.nrows(),
+        a.ncols(),
+        a.nnz(),
+        0
+    )?;
+    writeln!(
+        writer,
+        "{index_fortran:<16}{index_fortran:<16}{:<20}",
+        "(3E26.16)"
+    )?;
+
+    let one_based = |p: &usize| format!("{:>width$}", p + 1, width = index_format.width);
+    write_fields(&mut writer, index_format, a.indptr().iter().map(one_based))?;
+    write_fields(&mut writer, index_format, a.indices().iter().map(one_based))?;
+    let value = |v: &f64| format!("{v:>26.16E}");
+    write_fields(&mut writer, value_format, a.data().iter().map(value))?;
+    Ok(())
+}
+
+/// Write a CSC matrix to a file on disk, see [`write_csc`].
+pub fn write_csc_path(
+    path: impl AsRef<Path>,
+    a: &CscMatrix<f64>,
+    title: &str,
+    key: &str,
+    variant: Variant,
+) -> Result<(), SparseError> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_csc(&mut writer, a, title, key, variant)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Write formatted fields, `format.per_line` to a line.
+fn write_fields(
+    writer: &mut impl Write,
+    format: FixedFormat,
+    fields: impl Iterator<Item = String>,
+) -> Result<(), SparseError> {
+    let mut on_line = 0;
+    for field in fields {
+        writer.write_all(field.as_bytes())?;
+        on_line += 1;
+        if on_line == format.per_line {
+            writeln!(writer)?;
+            on_line = 0;
+        }
+    }
+    if on_line > 0 {
+        writeln!(writer)?;
+    }
+    Ok(())
+}
+
+/// The lines of a file, numbered from 1 as they are read.
+struct Cards<L> {
+    lines: L,
+    line: usize,
+}
+
+impl<L: Iterator<Item = std::io::Result<String>>> Cards<L> {
+    fn next(&mut self, expected: &str) -> Result<String, SparseError> {
+        self.line += 1;
+        match self.lines.next() {
+            Some(line) => Ok(line?),
+            None => Err(SparseError::Parse {
+                line: self.line,
+                reason: format!("unexpected end of file, expected {expected}"),
+            }),
+        }
+    }
+
+    /// Read `count` fixed-width fields laid out by `format`, starting on a new line. `count`
+    /// comes from the header, so the fields are collected as they are read rather than
+    /// allocated up front: a corrupt count runs out of lines instead of memory.
+    fn fields<T>(
+        &mut self,
+        format: FixedFormat,
+        count: usize,
+        expected: &str,
+        parse: impl Fn(&str) -> Option<T>,
+    ) -> Result<Vec<T>, SparseError> {
+        let mut fields = Vec::new();
+        while fields.len() < count {
+            let line = self.next(expected)?;
+            let line = line.trim_end();
+            for k in 0..format.per_line {
+                if fields.len() == count || k * format.width >= line.len() {
+                    break;
+                }
+                let end = ((k + 1) * format.width).min(line.len());
+                let field = line.get(k * format.width..end).map(str::trim);
+                match field.and_then(&parse) {
+                    Some(value) => fields.push(value),
+                    None => {
+                        return Err(SparseError::Parse {
+                            line: self.line,
+                            reason: format!(
+                                "expected {expected}, found {}",
+                                field.unwrap_or("non-ASCII text")
+                            ),
+                        })
+                    }
+                }
+            }
+        }
+        Ok(fields)
+    }
+}
+
+fn parse_integers(line: usize, text: &str) -> Result<Vec<usize>, SparseError> {
+    text.split_whitespace()
+        .map(|s| {
+            s.parse().map_err(|_| SparseError::Parse {
+                line,
+                reason: format!("expected an integer, found {s}"),
+            })
+        })
+        .collect()
+}
+
+fn parse_index(s: &str) -> Option<usize> {
+    s.parse().ok()
+}
+
+/// Parse a Fortran real: the exponent letter may be `D` as well as `E`, or be left out before
+/// the exponent's sign, as in `1.5-3`.
+fn parse_real(s: &str) -> Option<f64> {
+    let s = s.replace(['D', 'd'], "E");
+    if let Ok(v) = s.parse() {
+        return Some(v);
+    }
+    let sign = s.get(1..)?.rfind(['+', '-'])? + 1;
+    if s[..sign].ends_with(['E', 'e']) {
+        return None;
+    }
+    format!("{}E{}", &s[..sign], &s[sign..]).parse().ok()
+}
+
+/// Parse a Fortran format such as `(16I5)`, `(5E16.8)` or `(1P,4D20.12)` into the number of
+/// fields on a line and their width.
+fn parse_format(line: usize, text: &str) -> Result<FixedFormat, SparseError> {
+    let error = || SparseError::Parse {
+        line,
+        reason: format!("unsupported Fortran format {text}"),
+    };
+    let upper = text.to_uppercase();
+    let mut spec = upper.trim_start_matches('(').trim_end_matches(')');
+    // A scale factor such as `1P` doesn't change the layout.
+    if let Some((_, rest)) = spec.split_once('P') {
+        spec = rest.trim_start_matches(',');
+    }
+    let letter = spec.find(['I', 'E', 'D', 'F', 'G']).ok_or_else(error)?;
+    let per_line = match &spec[..letter] {
+        "" => 1,
+        count => count.parse().map_err(|_| error())?,
+    };
+    let rest = &spec[letter + 1..];
+    let width_end = rest
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(rest.len());
+    let width = rest[..width_end].parse().map_err(|_| error())?;
+    if per_line == 0 || width == 0 {
+        return Err(error());
+    }
+    Ok(FixedFormat { per_line, width })
+}
+
+#[test]
+fn test_harwell_boeing_read() {
+    // A 4 by 4 matrix with values written the way old Fortran codes did: fields running into
+    // each other, a D exponent and one without an exponent letter.
+    #[rustfmt::skip]
+    let text = [
+        "Unsymmetric test matrix                                                 TEST0001",
+        "             4             1             1             2             0",
+        "RUA                        4             4             6             0",
+        "(5I3)           (6I3)           (4E12.4)            ",
+        "  1  3  4  6  7",
+        "  1  3  2  1  4  4",
+        "  1.0000E+00 -2.0000D+00  3.0000E00     4.5-1",
+        "  5.0000E+00  6.0000E+00",
+    ]
+    .join("\n");
+    let hb = read(text.as_bytes()).unwrap();
+    assert_eq!(hb.title, "Unsymmetric test matrix");
+    assert_eq!(hb.key, "TEST0001");
+    #[rustfmt::skip]
+    assert_eq!(hb.matrix.to_dense(), [
+        1.0, 0.0, 4.5e-1, 0.0,
+        0.0, 3.0, 0.0, 0.0,
+        -2.0, 0.0, 0.0, 0.0,
+        0.0, 0.0, 5.0, 6.0,
+    ]);
+
+    // A Rutherford–Boeing symmetric pattern, with four card counts and no value format.
+    #[rustfmt::skip]
+    let text = [
+        "Symmetric pattern                                                       PAT",
+        "             3             1             1             0",
+        "PSA                        3             3             4             0",
+        "(4I4)           (4I4)",
+        "   1   3   4   5",
+        "   1   3   2   3",
+    ]
+    .join("\n");
+    #[rustfmt::skip]
+    assert_eq!(read(text.as_bytes()).unwrap().matrix.to_dense(), [
+        1.0, 0.0, 1.0,
+        0.0, 1.0, 0.0,
+        1.0, 0.0, 1.0,
+    ]);
+
+    assert_eq!(parse_real("1.5-3"), Some(1.5e-3));
+    assert_eq!(parse_real("-2.5+02"), Some(-250.0));
+    assert_eq!(parse_real("1.0E-"), None);
+    assert_eq!(
+        parse_format(1, "(1P,4D20.12)").unwrap(),
+        FixedFormat {
+            per_line: 4,
+            width: 20
+        }
+    );
+
+    let header = "title\n 3 1 1 1 0\nRUA 2 2 2 0\n(3I3) (3I3) (3E10.2)\n";
+    let errors = [
+        ("", 1),
+        ("title\n 3 1 1 1 0\nCUA 2 2 2 0\n", 3),
+        ("title\n 3 1 1 1 0\nRUE 2 2 2 0\n", 3),
+        ("title\n 3 1 1 1 0\nRUA 2 2 2 0\n(3X3) (3I3) (3E10.2)\n", 4),
+        (&format!("{header}  1  2  2\n"), 5),
+        (&format!("{header}  1  2  3\n  1  3\n"), 6),
+        (&format!("{header}  1  2  3\n  1  2\n  1.0  x\n"), 7),
+        (&format!("{header}  1  2  3\n  1  2\n"), 7),
+    ];
+    for (text, line) in errors {
+        match read(text.as_bytes()) {
+            Err(SparseError::Parse { line: l, .. }) => assert_eq!(l, line, "{text}"),
+            other => panic!("expected a parse error, got {other:?}"),
+        }
+    }
+}
+
+#[test]
+fn test_harwell_boeing_corrupt_header() {
+    let parse_error = |line: usize, reason: &str| SparseError::Parse {
+        line,
+        reason: reason.to_string(),
+    };
+
+    // A multibyte type character is one character, not three bytes of the type.
+    for line in ["€ 1 1 1", "RU"] {
+        let text = format!("title\n 3 1 1 1 0\n{line}\n");
+        assert_eq!(
+            read(text.as_bytes()).unwrap_err(),
+            parse_error(3, "expected a type and nrows ncols nnz")
+        );
+    }
+    for (line, reason) in [
+        ("€€€ 1 1 1", "unsupported value type €"),
+        ("R€A 1 1 1", "unsupported symmetry €"),
+        ("RU€ 1 1 1", "only assembled matrices are supported"),
+    ] {
+        let text = format!("title\n 3 1 1 1 0\n{line}\n");
+        assert_eq!(read(text.as_bytes()).unwrap_err(), parse_error(3, reason));
+    }
+    // The type is read by characters, case-insensitively, before the sizes.
+    let text = "title\n 2 1 1 0\npsa 1 1 1\n(2I2) (1I2)\n 1 2\n 1\n";
+    assert_eq!(read(text.as_bytes()).unwrap().matrix.to_dense(), [1.0]);
+
+    // Counts past the address space are rejected, not wrapped.
+    let text = format!("title\n 3 1 1 1 0\nRUA 1 {} 0\n", usize::MAX);
+    assert_eq!(
+        read(text.as_bytes()).unwrap_err(),
+        parse_error(3, "too many columns or entries")
+    );
+    let text = format!("title\n 3 1 1 1 0\nRUA 1 1 {}\n", usize::MAX);
+    assert_eq!(
+        read(text.as_bytes()).unwrap_err(),
+        parse_error(3, "too many columns or entries")
+    );
+
+    // A huge count with few lines runs out of lines rather than allocating for the count.
+    let text = format!(
+        "title\n 3 1 1 1 0\nRUA 1 {} 1\n(3I3) (3I3) (3E10.2)\n  1  2\n",
+        usize::MAX - 1
+    );
+    assert_eq!(
+        read(text.as_bytes()).unwrap_err(),
+        parse_error(6, "unexpected end of file, expected a column pointer")
+    );
+}
+
+#[test]
+fn test_harwell_boeing_round_trip() {
+    use crate::test_util::Lcg;
+
+    let mut rng = Lcg::new(5);
+    let (nrows, ncols) = (30, 17);
+    let a = CscMatrix::from_dense(nrows, ncols, &rng.dense(nrows, ncols, 0.2)).unwrap();
+    for variant in [Variant::HarwellBoeing, Variant::RutherfordBoeing] {
+        let mut buf = Vec::new();
+        write_csc(&mut buf, &a, "A random matrix", "RAND", variant).unwrap();
+        assert!(String::from_utf8_lossy(&buf).lines().all(|l| l.len() <= 80));
+        let back = read(&buf[..]).unwrap();
+        assert_eq!(
+            (back.title.as_str(), back.key.as_str()),
+            ("A random matrix", "RAND")
+        );
+        assert_eq!(back.matrix.to_dense(), a.to_dense());
+    }
+
+    let path = std::env::temp_dir().join(format!("sparse-matrix-{}.rua", std::process::id()));
+    let third = CscMatrix::from_dense(1, 1, &[1.0 / 3.0]).unwrap();
+    write_csc_path(&path, &third, "", "", Variant::RutherfordBoeing).unwrap();
+    let from_disk = read_path(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(from_disk.matrix.to_dense(), [1.0 / 3.0]);
+}
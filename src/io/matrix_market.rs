@@ -0,0 +1,517 @@
+//! The Matrix Market exchange format (`.mtx`).
+//!
+//! A file starts with a banner `%%MatrixMarket matrix <format> <field> <symmetry>`, followed by
+//! `%` comment lines, a size line and the entries:
+//!
+//! * `coordinate` files list `row col [value]` triplets, 1-based, after a `nrows ncols nnz` line;
+//! * `array` files list every value in column-major order after a `nrows ncols` line.
+//!
+//! The `real`, `integer` and `pattern` fields are read as f64 (pattern entries become 1.0), with
+//! `general`, `symmetric`, `skew-symmetric` and `hermitian` symmetry. Only the lower triangle of
+//! a symmetric file is stored, and the mirrored entries are added back when reading.
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+use crate::coo::{CooMatrix, DuplicatePolicy};
+use crate::csr::CsrMatrix;
+use crate::error::SparseError;
+
+/// The matrix read from a Matrix Market file.
+#[derive(Clone, Debug)]
+pub enum MatrixMarket {
+    /// A sparse matrix from a `coordinate` file
+    Coordinate(CooMatrix<f64>),
+    /// A dense matrix from an `array` file, stored row-major
+    Array {
+        nrows: usize,
+        ncols: usize,
+        values: Vec<f64>,
+    },
+}
+
+impl MatrixMarket {
+    /// Return the matrix as triplets, dropping the zeros of a dense array.
+    pub fn into_coo(self) -> CooMatrix<f64> {
+        match self {
+            Self::Coordinate(coo) => coo,
+            Self::Array {
+                nrows,
+                ncols,
+                values,
+            } => {
+                let mut coo = CooMatrix::new(nrows, ncols);
+                for (k, &v) in values.iter().enumerate() {
+                    if v != 0.0 {
+                        coo.push(k / ncols, k % ncols, v);
+                    }
+                }
+                coo
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Real,
+    Integer,
+    Pattern,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Symmetry {
+    General,
+    Symmetric,
+    SkewSymmetric,
+}
+
+/// How to read a Matrix Market file.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MatrixMarketOptions {
+    /// How a `coordinate` file listing a position more than once is read. The default sums
+    /// the values, when the triplets are converted.
+    pub duplicates: DuplicatePolicy,
+}
+
+/// Read a Matrix Market file from disk.
+pub fn read_path(path: impl AsRef<Path>) -> Result<MatrixMarket, SparseError> {
+    read(BufReader::new(File::open(path)?))
+}
+
+/// Read a Matrix Market file from disk with `options`.
+pub fn read_path_with(
+    path: impl AsRef<Path>,
+    options: &MatrixMarketOptions,
+) -> Result<MatrixMarket, SparseError> {
+    read_with(BufReader::new(File::open(path)?), options)
+}
+
+/// Read a Matrix Market file.
+pub fn read(reader: impl BufRead) -> Result<MatrixMarket, SparseError> {
+    read_with(reader, &MatrixMarketOptions::default())
+}
+
+/// Read a Matrix Market file with `options`.
+pub fn read_with(
+    reader: impl BufRead,
+    options: &MatrixMarketOptions,
+) -> Result<MatrixMarket, SparseError> {
+    let mut lines = reader.lines().enumerate().map(|(k, line)| (k + 1, line));
+    let parse_error = |line: usize, reason: String| SparseError::Parse { line, reason };
+
+    // #1: The banner.
+    let (_, banner) = lines
+        .next()
+        .ok_or_else(|| parse_error(1, "empty file".to_string()))?;
+    let banner = banner?.to_lowercase();
+    let words: Vec<&str> = banner.split_whitespace().collect();
+    if words.len() != 5 || words[0] != "%%matrixmarket" || words[1] != "matrix" {
+        return Err(parse_error(
+            1,
+            format!("not a Matrix Market banner: {banner}"),
+        ));
+    }
+    let coordinate = match words[2] {
+        "coordinate" => true,
+        "array" => false,
+        other => return Err(parse_error(1, format!("unsupported format {other}"))),
+    };
+    let field = match words[3] {
+        "real" | "double" => Field::Real,
+        "integer" => Field::Integer,
+        "pattern" if coordinate => Field::Pattern,
+        other => return Err(parse_error(1, format!("unsupported field {other}"))),
+    };
+    let symmetry = match words[4] {
+        "general" => Symmetry::General,
+        // Hermitian is the same as symmetric for real values.
+        "symmetric" | "hermitian" => Symmetry::Symmetric,
+        "skew-symmetric" => Symmetry::SkewSymmetric,
+        other => return Err(parse_error(1, format!("unsupported symmetry {other}"))),
+    };
+
+    // #2: Skip the comments, and split every remaining line into numbers.
+    let mut data_lines = lines.filter_map(|(k, line)| match line {
+        Ok(line) if line.trim().is_empty() || line.trim_start().starts_with('%') => None,
+        Ok(line) => Some(Ok((k, line))),
+        Err(err) => Some(Err(SparseError::from(err))),
+    });
+    let mut last_line = 1;
+    let mut next_fields = |expected: &str| -> Result<(usize, Vec<String>), SparseError> {
+        match data_lines.next() {
+            Some(line) => {
+                let (k, line) = line?;
+                last_line = k;
+                Ok((k, line.split_whitespace().map(str::to_string).collect()))
+            }
+            None => Err(parse_error(
+                last_line + 1,
+                format!("unexpected end of file, expected {expected}"),
+            )),
+        }
+    };
+    let parse_usize = |k: usize, s: &str| {
+        s.parse::<usize>()
+            .map_err(|_| parse_error(k, format!("expected an index, found {s}")))
+    };
+    let parse_value = |k: usize, s: &str| {
+        match field {
+            Field::Integer => s.parse::<i64>().map(|v| v as f64).map_err(|_| ()),
+            _ => s.parse::<f64>().map_err(|_| ()),
+        }
+        .map_err(|_| parse_error(k, format!("expected a value, found {s}")))
+    };
+
+    // #3: The size line and the entries.
+    let (k, size) = next_fields("the size line")?;
+    let expected_sizes = if coordinate { 3 } else { 2 };
+    if size.len() != expected_sizes {
+        return Err(parse_error(k, format!("expected {expected_sizes} sizes")));
+    }
+    let nrows = parse_usize(k, &size[0])?;
+    let ncols = parse_usize(k, &size[1])?;
+    if symmetry != Symmetry::General && nrows != ncols {
+        return Err(parse_error(
+            k,
+            "a symmetric matrix must be square".to_string(),
+        ));
+    }
+
+    if coordinate {
+        let nnz = parse_usize(k, &size[2])?;
+        let mut coo = CooMatrix::new(nrows, ncols);
+        let fields_per_entry = if field == Field::Pattern { 2 } else { 3 };
+        for _ in 0..nnz {
+            let (k, entry) = next_fields("an entry")?;
+            if entry.len() != fields_per_entry {
+                return Err(parse_error(
+                    k,
+                    format!("expected {fields_per_entry} fields"),
+                ));
+            }
+            let i = parse_usize(k, &entry[0])?;
+            let j = parse_usize(k, &entry[1])?;
+            if i == 0 || i > nrows || j == 0 || j > ncols {
+                return Err(parse_error(k, format!("entry ({i}, {j}) out of bounds")));
+            }
+            let v = match field {
+                Field::Pattern => 1.0,
+                _ => parse_value(k, &entry[2])?,
+            };
+
+            coo.push(i - 1, j - 1, v);
+            if i != j {
+                match symmetry {
+                    Symmetry::General => {}
+                    Symmetry::Symmetric => coo.push(j - 1, i - 1, v),
+                    Symmetry::SkewSymmetric => coo.push(j - 1, i - 1, -v),
+                }
+            }
+        }
+        Ok(MatrixMarket::Coordinate(
+            coo.deduplicate(options.duplicates)?,
+        ))
+    } else {
+        let len = nrows
+            .checked_mul(ncols)
+            .ok_or_else(|| parse_error(k, format!("a {nrows} by {ncols} array is too large")))?;
+        // Symmetric arrays store the lower triangle, skew-symmetric ones without diagonal.
+        let first_row = |j: usize| match symmetry {
+            Symmetry::General => 0,
+            Symmetry::Symmetric => j,
+            Symmetry::SkewSymmetric => j + 1,
+        };
+
+        // Read the stored values before allocating the array, so that a size line the file
+        // doesn't live up to ends in a parse error rather than in a huge allocation.
+        let mut stored = Vec::new();
+        if len > 0 {
+            for j in 0..ncols {
+                for _ in first_row(j)..nrows {
+                    let (k, entry) = next_fields("a value")?;
+                    if entry.len() != 1 {
+                        return Err(parse_error(k, "expected one value".to_string()));
+                    }
+                    stored.push(parse_value(k, &entry[0])?);
+                }
+            }
+        }
+
+        let mut values = vec![0.0; len];
+        let mut stored = stored.into_iter();
+        for j in 0..ncols.min(len) {
+            for i in first_row(j)..nrows {
+                let v = stored.next().expect("one value was read per position");
+                values[i * ncols + j] = v;
+                match symmetry {
+                    Symmetry::General => {}
+                    Symmetry::Symmetric => values[j * ncols + i] = v,
+                    Symmetry::SkewSymmetric => values[j * ncols + i] = -v,
+                }
+            }
+        }
+        Ok(MatrixMarket::Array {
+            nrows,
+            ncols,
+            values,
+        })
+    }
+}
+
+/// Write triplets as a `coordinate real general` file. Duplicates are written as they are.
+pub fn write_coo(mut writer: impl Write, coo: &CooMatrix<f64>) -> Result<(), SparseError> {
+    writeln!(writer, "%%MatrixMarket matrix coordinate real general")?;
+    writeln!(writer, "{} {} {}", coo.nrows(), coo.ncols(), coo.nnz())?;
+    for (i, j, v) in coo.triplets() {
+        writeln!(writer, "{} {} {v:e}", i + 1, j + 1)?;
+    }
+    Ok(())
+}
+
+/// Write a CSR matrix as a `coordinate real general` file.
+pub fn write_csr(mut writer: impl Write, a: &CsrMatrix<f64>) -> Result<(), SparseError> {
+    writeln!(writer, "%%MatrixMarket matrix coordinate real general")?;
+    writeln!(writer, "{} {} {}", a.nrows(), a.ncols(), a.nnz())?;
+    for i in 0..a.nrows() {
+        let (cols, values) = a.row(i);
+        for (&j, &v) in cols.iter().zip(values) {
+            writeln!(writer, "{} {} {v:e}", i + 1, j + 1)?;
+        }
+    }
+    Ok(())
+}
+
+/// Write a row-major dense matrix as an `array real general` file.
+pub fn write_array(
+    mut writer: impl Write,
+    nrows: usize,
+    ncols: usize,
+    values: &[f64],
+) -> Result<(), SparseError> {
+    if nrows.checked_mul(ncols) != Some(values.len()) {
+        return Err(SparseError::DimensionMismatch {
+            expected: nrows.saturating_mul(ncols),
+            found: values.len(),
+        });
+    }
+
+    writeln!(writer, "%%MatrixMarket matrix array real general")?;
+    writeln!(writer, "{nrows} {ncols}")?;
+    for j in 0..ncols {
+        for i in 0..nrows {
+            writeln!(writer, "{:e}", values[i * ncols + j])?;
+        }
+    }
+    Ok(())
+}
+
+/// Write a CSR matrix to a file on disk, see [`write_csr`].
+pub fn write_csr_path(path: impl AsRef<Path>, a: &CsrMatrix<f64>) -> Result<(), SparseError> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_csr(&mut writer, a)?;
+    writer.flush()?;
+    Ok(())
+}
+
+#[test]
+fn test_matrix_market_read() {
+    let read_dense = |text: &str| {
+        let coo = read(text.as_bytes()).unwrap().into_coo();
+        coo.to_csr().to_dense()
+    };
+
+    let general = "%%MatrixMarket matrix coordinate real general
+% a comment
+3 3 4
+1 1 1.5
+3 2 -2e1
+2 3 4
+1 1 0.5
+";
+    assert_eq!(
+        read_dense(general),
+        [2.0, 0.0, 0.0, 0.0, 0.0, 4.0, 0.0, -20.0, 0.0]
+    );
+
+    let symmetric = "%%MatrixMarket matrix coordinate integer symmetric
+3 3 3
+1 1 2
+3 1 5
+3 2 7
+";
+    assert_eq!(
+        read_dense(symmetric),
+        [2.0, 0.0, 5.0, 0.0, 0.0, 7.0, 5.0, 7.0, 0.0]
+    );
+
+    let skew = "%%MatrixMarket matrix coordinate real skew-symmetric
+2 2 1
+2 1 3.0
+";
+    assert_eq!(read_dense(skew), [0.0, -3.0, 3.0, 0.0]);
+
+    let pattern = "%%MatrixMarket matrix coordinate pattern general
+2 3 2
+1 3
+2 1
+";
+    assert_eq!(read_dense(pattern), [0.0, 0.0, 1.0, 1.0, 0.0, 0.0]);
+
+    let array = "%%MatrixMarket matrix array real general
+2 3
+1
+4
+2
+5
+3
+6
+";
+    match read(array.as_bytes()).unwrap() {
+        MatrixMarket::Array {
+            nrows,
+            ncols,
+            values,
+        } => {
+            assert_eq!((nrows, ncols), (2, 3));
+            assert_eq!(values, [1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        }
+        MatrixMarket::Coordinate(_) => panic!("expected an array"),
+    }
+
+    let symmetric_array = "%%MatrixMarket matrix array real symmetric
+2 2
+1
+2
+3
+";
+    assert_eq!(read_dense(symmetric_array), [1.0, 2.0, 2.0, 3.0]);
+
+    let errors = [
+        ("", 1),
+        (
+            "%%MatrixMarket matrix coordinate complex general\n1 1 1\n1 1 1 0\n",
+            1,
+        ),
+        (
+            "%%MatrixMarket matrix coordinate real general\n2 2 1\n3 1 1.0\n",
+            3,
+        ),
+        (
+            "%%MatrixMarket matrix coordinate real general\n2 2 1\n1 1 x\n",
+            3,
+        ),
+        (
+            "%%MatrixMarket matrix coordinate real symmetric\n2 3 0\n",
+            2,
+        ),
+    ];
+    for (text, line) in errors {
+        match read(text.as_bytes()) {
+            Err(SparseError::Parse { line: l, .. }) => assert_eq!(l, line, "{text}"),
+            other => panic!("expected a parse error, got {other:?}"),
+        }
+    }
+    assert!(matches!(
+        read("%%MatrixMarket matrix coordinate real general\n2 2 2\n1 1 1.0\n".as_bytes()),
+        Err(SparseError::Parse { line: 4, .. })
+    ));
+}
+
+#[test]
+fn test_matrix_market_duplicates() {
+    let text = "%%MatrixMarket matrix coordinate real general
+2 2 3
+1 2 1.5
+2 1 3
+1 2 -0.5
+";
+    let read_dense = |duplicates| {
+        let options = MatrixMarketOptions { duplicates };
+        read_with(text.as_bytes(), &options).map(|m| m.into_coo().to_csr().to_dense())
+    };
+    assert_eq!(
+        read_dense(DuplicatePolicy::Sum).unwrap(),
+        [0.0, 1.0, 3.0, 0.0]
+    );
+    assert_eq!(
+        read_dense(DuplicatePolicy::Max).unwrap(),
+        [0.0, 1.5, 3.0, 0.0]
+    );
+    assert_eq!(
+        read_dense(DuplicatePolicy::Error).unwrap_err(),
+        SparseError::DuplicateEntry {
+            row: 0,
+            col: 1,
+            first: "1.5".into(),
+            second: "-0.5".into(),
+        }
+    );
+}
+
+#[test]
+fn test_matrix_market_oversized_array() {
+    // The size overflows usize.
+    let overflow = "%%MatrixMarket matrix array real general\n4294967296 4294967296\n1\n";
+    match read(overflow.as_bytes()) {
+        Err(SparseError::Parse { line: 2, reason }) => assert!(reason.contains("too large")),
+        other => panic!("expected a parse error, got {other:?}"),
+    }
+
+    // The size fits but the file holds only two of its 10¹⁰ values: nothing is allocated for
+    // them up front.
+    let short = "%%MatrixMarket matrix array real general\n100000 100000\n1\n2\n";
+    match read(short.as_bytes()) {
+        Err(SparseError::Parse { line: 5, reason }) => assert!(reason.contains("end of file")),
+        other => panic!("expected a parse error, got {other:?}"),
+    }
+
+    assert_eq!(
+        write_array(Vec::new(), usize::MAX, 2, &[1.0]).unwrap_err(),
+        SparseError::DimensionMismatch {
+            expected: usize::MAX,
+            found: 1
+        }
+    );
+}
+
+#[test]
+fn test_matrix_market_round_trip() {
+    let a = CsrMatrix::from_dense(3, 2, &[1.0, 0.0, 0.0, -2.5e-7, 1.0 / 3.0, 8.0]).unwrap();
+
+    let mut buf = Vec::new();
+    write_csr(&mut buf, &a).unwrap();
+    let back = read(&buf[..]).unwrap().into_coo().to_csr();
+    assert_eq!(back.to_dense(), a.to_dense());
+
+    let mut buf = Vec::new();
+    write_coo(
+        &mut buf,
+        &CooMatrix::from_triplets(2, 2, vec![0, 0], vec![1, 1], vec![1.0, 2.0]).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        read(&buf[..]).unwrap().into_coo().to_csr().to_dense(),
+        [0.0, 3.0, 0.0, 0.0]
+    );
+
+    let dense = a.to_dense();
+    let mut buf = Vec::new();
+    write_array(&mut buf, 3, 2, &dense).unwrap();
+    match read(&buf[..]).unwrap() {
+        MatrixMarket::Array { values, .. } => assert_eq!(values, dense),
+        MatrixMarket::Coordinate(_) => panic!("expected an array"),
+    }
+
+    let path = std::env::temp_dir().join(format!("sparse-matrix-{}.mtx", std::process::id()));
+    write_csr_path(&path, &a).unwrap();
+    let from_disk = read_path(&path).unwrap().into_coo().to_csr();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(from_disk.to_dense(), a.to_dense());
+
+    assert!(matches!(
+        read_path("/nonexistent/matrix.mtx"),
+        Err(SparseError::Io(_))
+    ));
+}
@@ -0,0 +1,7 @@
+//! Reading and writing matrices in the file formats used to exchange them.
+
+pub mod csv;
+pub mod harwell_boeing;
+pub mod matrix_market;
+#[cfg(feature = "npz")]
+pub mod npz;
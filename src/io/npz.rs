@@ -0,0 +1,572 @@
+//! SciPy's sparse `.npz` files, as written by `scipy.sparse.save_npz` and read by `load_npz`.
+//!
+//! Such a file is a zip archive of NumPy `.npy` arrays: `format` (`b"csr"` or `b"csc"`),
+//! `shape`, `indptr`, `indices` and `data`. Archives may be deflated or stored, the indices may
+//! be 32- or 64-bit integers and the values any NumPy real or integer type, read as f64. Files
+//! in SciPy's other sparse formats (`coo`, `dia`, `bsr`) are rejected.
+//!
+//! Writing produces deflated archives like `save_npz` does by default, with 32-bit indices
+//! when they fit and f64 values, so nothing is lost on the way to Python and back.
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Seek, Write};
+use std::path::Path;
+
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::coo::CooMatrix;
+use crate::csc::CscMatrix;
+use crate::csr::CsrMatrix;
+use crate::error::SparseError;
+
+/// The matrix read from a `.npz` file, in the format it was saved in.
+#[derive(Clone, Debug)]
+pub enum Npz {
+    Csr(CsrMatrix<f64>),
+    Csc(CscMatrix<f64>),
+}
+
+impl Npz {
+    /// Return the matrix in CSR form, converting a CSC one.
+    pub fn into_csr(self) -> CsrMatrix<f64> {
+        match self {
+            Self::Csr(a) => a,
+            Self::Csc(a) => a.to_csr(),
+        }
+    }
+
+    /// Return the matrix in CSC form, converting a CSR one.
+    pub fn into_csc(self) -> CscMatrix<f64> {
+        match self {
+            Self::Csr(a) => a.to_csc(),
+            Self::Csc(a) => a,
+        }
+    }
+}
+
+/// The element type of a `.npy` array, from its `descr` such as `<f8` or `|S3`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Dtype {
+    Float(usize),
+    Int(usize),
+    UInt(usize),
+    Bytes(usize),
+    Unicode(usize),
+}
+
+/// A decoded `.npy` array: its element type, its number of elements and its raw little-endian
+/// bytes.
+#[derive(Debug)]
+struct NpyArray {
+    dtype: Dtype,
+    len: usize,
+    bytes: Vec<u8>,
+}
+
+/// Read a SciPy `.npz` file from disk.
+pub fn load_path(path: impl AsRef<Path>) -> Result<Npz, SparseError> {
+    load(BufReader::new(File::open(path)?))
+}
+
+/// Read a SciPy `.npz` archive. Duplicated entries are summed and the indices of each row or
+/// column sorted, as SciPy does not require either.
+pub fn load(reader: impl Read + Seek) -> Result<Npz, SparseError> {
+    let mut archive = ZipArchive::new(reader).map_err(zip_error)?;
+
+    let format = read_npy(&mut archive, "format")?.text()?;
+    let shape = read_npy(&mut archive, "shape")?.integers()?;
+    let &[nrows, ncols] = &shape[..] else {
+        return Err(SparseError::InvalidStructure(format!(
+            "expected a shape of two sizes, found {shape:?}"
+        )));
+    };
+    let (outer, inner) = match format.as_str() {
+        "csr" => (nrows, ncols),
+        "csc" => (ncols, nrows),
+        other => {
+            return Err(SparseError::InvalidStructure(format!(
+                "unsupported sparse format {other}"
+            )))
+        }
+    };
+    let indptr = read_npy(&mut archive, "indptr")?.integers()?;
+    let indices = read_npy(&mut archive, "indices")?.integers()?;
+    let data = read_npy(&mut archive, "data")?.floats()?;
+
+    if indptr.len() != outer + 1
+        || indptr[0] != 0
+        || !indptr.is_sorted()
+        || indptr[outer] != indices.len()
+        || indices.len() != data.len()
+    {
+        return Err(SparseError::InvalidStructure(
+            "indptr, indices and data don't describe a compressed matrix".to_string(),
+        ));
+    }
+    if let Some(&i) = indices.iter().find(|&&i| i >= inner) {
+        return Err(SparseError::IndexOutOfBounds {
+            index: i,
+            len: inner,
+        });
+    }
+
+    let mut coo = CooMatrix::new(nrows, ncols);
+    for (p, bounds) in indptr.windows(2).enumerate() {
+        for k in bounds[0]..bounds[1] {
+            let (i, j) = if format == "csr" {
+                (p, indices[k])
+            } else {
+                (indices[k], p)
+            };
+            coo.push(i, j, data[k]);
+        }
+    }
+    Ok(match format.as_str() {
+        "csr" => Npz::Csr(coo.to_csr()),
+        _ => Npz::Csc(coo.to_csc()),
+    })
+}
+
+/// Write a CSR matrix as a SciPy `.npz` archive.
+pub fn save_csr(writer: impl Write + Seek, a: &CsrMatrix<f64>) -> Result<(), SparseError> {
+    save(writer, "csr", a.shape(), a.indptr(), a.indices(), a.data())
+}
+
+/// Write a CSC matrix as a SciPy `.npz` archive.
+pub fn save_csc(writer: impl Write + Seek, a: &CscMatrix<f64>) -> Result<(), SparseError> {
+    save(writer, "csc", a.shape(), a.indptr(), a.indices(), a.data())
+}
+
+/// Write a CSR matrix to a `.npz` file on disk, see [`save_csr`].
+pub fn save_csr_path(path: impl AsRef<Path>, a: &CsrMatrix<f64>) -> Result<(), SparseError> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    save_csr(&mut writer, a)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Write a CSC matrix to a `.npz` file on disk, see [`save_csc`].
+pub fn save_csc_path(path: impl AsRef<Path>, a: &CscMatrix<f64>) -> Result<(), SparseError> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    save_csc(&mut writer, a)?;
+    writer.flush()?;
+    Ok(())
+}
+
+fn save(
+    writer: impl Write + Seek,
+    format: &str,
+    (nrows, ncols): (usize, usize),
+    indptr: &[usize],
+    indices: &[usize],
+    data: &[f64],
+) -> Result<(), SparseError> {
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    let mut zip = ZipWriter::new(writer);
+    let mut entry = |name: &str, descr: &str, shape: &str, bytes: Vec<u8>| {
+        zip.start_file(format!("{name}.npy"), options)
+            .map_err(zip_error)?;
+        write_npy(&mut zip, descr, shape, &bytes)
+    };
+
+    // SciPy keeps 32-bit indices whenever the largest one fits.
+    let wide = indptr.last().copied().unwrap_or(0).max(nrows).max(ncols) > i32::MAX as usize;
+    let index_bytes = |values: &[usize]| -> Vec<u8> {
+        if wide {
+            values
+                .iter()
+                .flat_map(|&v| (v as i64).to_le_bytes())
+                .collect()
+        } else {
+            values
+                .iter()
+                .flat_map(|&v| (v as i32).to_le_bytes())
+                .collect()
+        }
+    };
+    let index_descr = if wide { "<i8" } else { "<i4" };
+    let vector = |len: usize| format!("({len},)");
+
+    entry(
+        "indices",
+        index_descr,
+        &vector(indices.len()),
+        index_bytes(indices),
+    )?;
+    entry(
+        "indptr",
+        index_descr,
+        &vector(indptr.len()),
+        index_bytes(indptr),
+    )?;
+    entry(
+        "format",
+        &format!("|S{}", format.len()),
+        "()",
+        format.into(),
+    )?;
+    let shape = [nrows as i64, ncols as i64];
+    entry(
+        "shape",
+        "<i8",
+        "(2,)",
+        shape.iter().flat_map(|v| v.to_le_bytes()).collect(),
+    )?;
+    entry(
+        "data",
+        "<f8",
+        &vector(data.len()),
+        data.iter().flat_map(|v| v.to_le_bytes()).collect(),
+    )?;
+    zip.finish().map_err(zip_error)?;
+    Ok(())
+}
+
+fn zip_error(err: zip::result::ZipError) -> SparseError {
+    SparseError::Io(err.to_string())
+}
+
+/// Write a version 1.0 `.npy` array: the magic string, the header length and a Python dict
+/// literal padded so that the data starts on a multiple of 64 bytes.
+fn write_npy(
+    writer: &mut impl Write,
+    descr: &str,
+    shape: &str,
+    bytes: &[u8],
+) -> Result<(), SparseError> {
+    let mut header = format!("{{'descr': '{descr}', 'fortran_order': False, 'shape': {shape}, }}");
+    let unpadded = 10 + header.len() + 1;
+    header.extend(std::iter::repeat_n(
+        ' ',
+        unpadded.next_multiple_of(64) - unpadded,
+    ));
+    header.push('\n');
+    writer.write_all(b"\x93NUMPY\x01\x00")?;
+    writer.write_all(&(header.len() as u16).to_le_bytes())?;
+    writer.write_all(header.as_bytes())?;
+    writer.write_all(bytes)?;
+    Ok(())
+}
+
+/// Read the array `name`.npy from the archive.
+fn read_npy<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    name: &str,
+) -> Result<NpyArray, SparseError> {
+    let mut file = archive.by_name(&format!("{name}.npy")).map_err(zip_error)?;
+    let mut raw = Vec::new();
+    file.read_to_end(&mut raw)?;
+    let error = |reason: &str| SparseError::Parse {
+        line: 1,
+        reason: format!("{name}.npy: {reason}"),
+    };
+
+    // #1: The magic string, the version and the header length.
+    if raw.len() < 10 || !raw.starts_with(b"\x93NUMPY") {
+        return Err(error("not a .npy array"));
+    }
+    let (header_start, header_len) = match raw[6] {
+        1 => (10, u16::from_le_bytes([raw[8], raw[9]]) as usize),
+        2 | 3 if raw.len() >= 12 => (
+            12,
+            u32::from_le_bytes([raw[8], raw[9], raw[10], raw[11]]) as usize,
+        ),
+        _ => return Err(error("unsupported .npy version")),
+    };
+    let header = raw
+        .get(header_start..header_start + header_len)
+        .and_then(|h| std::str::from_utf8(h).ok())
+        .ok_or_else(|| error("truncated header"))?;
+
+    // #2: The header, a Python dict literal such as
+    // {'descr': '<f8', 'fortran_order': False, 'shape': (3,), }
+    let value_of = |key: &str| {
+        let start = header.find(&format!("'{key}':"))? + key.len() + 3;
+        Some(header[start..].trim_start())
+    };
+    let descr = value_of("descr")
+        .and_then(|v| v.strip_prefix('\'')?.split('\'').next())
+        .ok_or_else(|| error("no descr"))?;
+    if value_of("fortran_order").is_some_and(|v| v.starts_with("True")) {
+        return Err(error("Fortran order is not supported"));
+    }
+    let shape = value_of("shape")
+        .and_then(|v| v.strip_prefix('(')?.split(')').next())
+        .ok_or_else(|| error("no shape"))?;
+    let mut len: usize = 1;
+    for size in shape.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        let size = size
+            .trim_end_matches('L')
+            .parse::<usize>()
+            .map_err(|_| error("bad shape"))?;
+        len = len
+            .checked_mul(size)
+            .ok_or_else(|| error("the shape overflows"))?;
+    }
+
+    let (order, kind) = descr.split_at(1.min(descr.len()));
+    let (kind, size) = kind.split_at(1.min(kind.len()));
+    let size: usize = size.parse().map_err(|_| error("bad descr"))?;
+    let dtype = match kind {
+        "f" => Dtype::Float(size),
+        "i" => Dtype::Int(size),
+        "u" => Dtype::UInt(size),
+        "S" => Dtype::Bytes(size),
+        "U" => Dtype::Unicode(size),
+        _ => return Err(error(&format!("unsupported dtype {descr}"))),
+    };
+    // Only the numeric widths the decoders handle; f16 and 128-bit types are not.
+    let item_size = match dtype {
+        Dtype::Float(4 | 8) | Dtype::Int(1 | 2 | 4 | 8) | Dtype::UInt(1 | 2 | 4 | 8) => size,
+        Dtype::Bytes(n) => n,
+        Dtype::Unicode(n) => n
+            .checked_mul(4)
+            .ok_or_else(|| error(&format!("unsupported dtype {descr}")))?,
+        _ => return Err(error(&format!("unsupported dtype {descr}"))),
+    };
+    if order == ">" && item_size > 1 && !matches!(dtype, Dtype::Bytes(_)) {
+        return Err(error("big-endian arrays are not supported"));
+    }
+    let bytes = raw.split_off(header_start + header_len);
+    if len.checked_mul(item_size) != Some(bytes.len()) {
+        return Err(error("the data doesn't match the shape"));
+    }
+    Ok(NpyArray { dtype, len, bytes })
+}
+
+impl NpyArray {
+    fn error(&self, expected: &str) -> SparseError {
+        SparseError::InvalidStructure(format!("expected {expected}, found {:?}", self.dtype))
+    }
+
+    fn text(&self) -> Result<String, SparseError> {
+        let text = match self.dtype {
+            Dtype::Bytes(_) => String::from_utf8_lossy(&self.bytes).into_owned(),
+            Dtype::Unicode(_) => self
+                .bytes
+                .chunks_exact(4)
+                .filter_map(|c| char::from_u32(u32::from_le_bytes([c[0], c[1], c[2], c[3]])))
+                .collect(),
+            _ => return Err(self.error("a string")),
+        };
+        Ok(text.trim_end_matches('\0').to_string())
+    }
+
+    /// Decode integers that must be nonnegative, as sizes and indices are.
+    fn integers(&self) -> Result<Vec<usize>, SparseError> {
+        let signed = match self.dtype {
+            Dtype::Int(_) => true,
+            Dtype::UInt(_) => false,
+            _ => return Err(self.error("integers")),
+        };
+        let size = self.bytes.len() / self.len.max(1);
+        self.bytes
+            .chunks_exact(size.max(1))
+            .map(|c| {
+                let mut word = [0; 8];
+                word[..c.len()].copy_from_slice(c);
+                // Sign-extend, so that negative values are caught below.
+                if signed && c.last().is_some_and(|&b| b & 0x80 != 0) {
+                    word[c.len()..].fill(0xff);
+                }
+                let v = i64::from_le_bytes(word);
+                usize::try_from(v).map_err(|_| {
+                    SparseError::InvalidStructure(format!("negative size or index {v}"))
+                })
+            })
+            .collect()
+    }
+
+    fn floats(&self) -> Result<Vec<f64>, SparseError> {
+        let size = self.bytes.len() / self.len.max(1);
+        let chunks = self.bytes.chunks_exact(size.max(1));
+        match self.dtype {
+            Dtype::Float(8) => Ok(chunks
+                .map(|c| f64::from_le_bytes(c.try_into().unwrap()))
+                .collect()),
+            Dtype::Float(4) => Ok(chunks
+                .map(|c| f32::from_le_bytes(c.try_into().unwrap()) as f64)
+                .collect()),
+            Dtype::Int(_) | Dtype::UInt(_) => {
+                let signed = matches!(self.dtype, Dtype::Int(_));
+                Ok(chunks
+                    .map(|c| {
+                        let mut word = [0; 8];
+                        word[..c.len()].copy_from_slice(c);
+                        if signed && c.last().is_some_and(|&b| b & 0x80 != 0) {
+                            word[c.len()..].fill(0xff);
+                            i64::from_le_bytes(word) as f64
+                        } else {
+                            u64::from_le_bytes(word) as f64
+                        }
+                    })
+                    .collect())
+            }
+            _ => Err(self.error("real or integer values")),
+        }
+    }
+}
+
+#[test]
+fn test_npz_round_trip() {
+    use std::io::Cursor;
+
+    use crate::test_util::Lcg;
+
+    let mut rng = Lcg::new(3);
+    let a = CsrMatrix::from_dense(7, 5, &rng.dense(7, 5, 0.4)).unwrap();
+
+    let mut buf = Cursor::new(Vec::new());
+    save_csr(&mut buf, &a).unwrap();
+    buf.set_position(0);
+    match load(&mut buf).unwrap() {
+        Npz::Csr(back) => {
+            assert_eq!(back.indptr(), a.indptr());
+            assert_eq!(back.indices(), a.indices());
+            assert_eq!(back.data(), a.data());
+        }
+        Npz::Csc(_) => panic!("expected a CSR matrix"),
+    }
+
+    let mut buf = Cursor::new(Vec::new());
+    save_csc(&mut buf, &a.to_csc()).unwrap();
+    buf.set_position(0);
+    let back = load(&mut buf).unwrap();
+    assert!(matches!(back, Npz::Csc(_)));
+    assert_eq!(back.into_csr().to_dense(), a.to_dense());
+
+    let path = std::env::temp_dir().join(format!("sparse-matrix-{}.npz", std::process::id()));
+    save_csr_path(&path, &a).unwrap();
+    let from_disk = load_path(&path).unwrap().into_csc();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(from_disk.to_dense(), a.to_dense());
+}
+
+#[test]
+fn test_npz_load_scipy_layout() {
+    use std::io::Cursor;
+
+    // An uncompressed archive, as `save_npz(f, a, compressed=False)` writes, with 64-bit
+    // indices, f32 values, the format as a NumPy unicode string, and unsorted and duplicated
+    // column indices in row 0.
+    let mut buf = Cursor::new(Vec::new());
+    let mut zip = ZipWriter::new(&mut buf);
+    let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+    let i64s = |v: &[i64]| -> Vec<u8> { v.iter().flat_map(|x| x.to_le_bytes()).collect() };
+    let arrays: [(&str, &str, &str, Vec<u8>); 5] = [
+        ("indices", "<i8", "(4,)", i64s(&[2, 0, 2, 1])),
+        ("indptr", "<i8", "(3,)", i64s(&[0, 3, 4])),
+        (
+            "format",
+            "<U3",
+            "()",
+            "csr"
+                .chars()
+                .flat_map(|c| (c as u32).to_le_bytes())
+                .collect(),
+        ),
+        ("shape", "<i8", "(2,)", i64s(&[2, 3])),
+        (
+            "data",
+            "<f4",
+            "(4,)",
+            [1.0f32, 2.0, 0.5, 4.0]
+                .iter()
+                .flat_map(|v| v.to_le_bytes())
+                .collect(),
+        ),
+    ];
+    for (name, descr, shape, bytes) in arrays {
+        zip.start_file(format!("{name}.npy"), stored).unwrap();
+        write_npy(&mut zip, descr, shape, &bytes).unwrap();
+    }
+    zip.finish().unwrap();
+
+    buf.set_position(0);
+    #[rustfmt::skip]
+    assert_eq!(load(&mut buf).unwrap().into_csr().to_dense(), [
+        2.0, 0.0, 1.5,
+        0.0, 4.0, 0.0,
+    ]);
+
+    assert!(matches!(
+        load(Cursor::new(b"not a zip archive".to_vec())),
+        Err(SparseError::Io(_))
+    ));
+}
+
+#[test]
+fn test_npz_unsupported_arrays() {
+    use std::io::Cursor;
+
+    // Read back a single array written with the given header.
+    let read = |descr: &str, shape: &str, bytes: &[u8]| {
+        let mut buf = Cursor::new(Vec::new());
+        let mut zip = ZipWriter::new(&mut buf);
+        zip.start_file("a.npy", SimpleFileOptions::default())
+            .unwrap();
+        write_npy(&mut zip, descr, shape, bytes).unwrap();
+        zip.finish().unwrap();
+        buf.set_position(0);
+        read_npy(&mut ZipArchive::new(buf).unwrap(), "a")
+    };
+    let unsupported = |descr: &str| SparseError::Parse {
+        line: 1,
+        reason: format!("a.npy: unsupported dtype {descr}"),
+    };
+
+    // Half floats and 128-bit integers are rejected while parsing the header, not decoded.
+    for (descr, size) in [
+        ("<f2", 2),
+        ("<f16", 16),
+        ("<i16", 16),
+        ("<u16", 16),
+        ("<f0", 0),
+    ] {
+        assert_eq!(
+            read(descr, "(2,)", &vec![0; 2 * size]).unwrap_err(),
+            unsupported(descr)
+        );
+    }
+    for (descr, size) in [
+        ("<f4", 4),
+        ("<f8", 8),
+        ("|i1", 1),
+        ("<i2", 2),
+        ("<u4", 4),
+        ("<u8", 8),
+    ] {
+        let array = read(descr, "(2,)", &vec![0; 2 * size]).unwrap();
+        assert_eq!(array.floats().unwrap(), [0.0; 2]);
+    }
+    assert_eq!(
+        read("|i1", "(2,)", &[0xff, 3])
+            .unwrap()
+            .integers()
+            .unwrap_err(),
+        SparseError::InvalidStructure("negative size or index -1".to_string())
+    );
+
+    // Sizes whose product overflows are rejected rather than wrapped.
+    let huge = format!("({}, 2)", usize::MAX / 2 + 1);
+    assert_eq!(
+        read("<f8", &huge, &[]).unwrap_err(),
+        SparseError::Parse {
+            line: 1,
+            reason: "a.npy: the shape overflows".to_string()
+        }
+    );
+    let huge = format!("({},)", usize::MAX / 4 + 1);
+    assert_eq!(
+        read("<f8", &huge, &[]).unwrap_err(),
+        SparseError::Parse {
+            line: 1,
+            reason: "a.npy: the data doesn't match the shape".to_string()
+        }
+    );
+    assert_eq!(
+        read(&format!("<U{}", usize::MAX / 2), "(1,)", &[]).unwrap_err(),
+        unsupported(&format!("<U{}", usize::MAX / 2))
+    );
+}
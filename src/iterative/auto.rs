@@ -0,0 +1,239 @@
+use alloc::vec::Vec;
+
+use super::{cg, gmres, initial_guess, SolverOptions};
+use crate::csr::CsrMatrix;
+use crate::dense::norm2;
+use crate::error::SparseError;
+use crate::factor::{DirectOptions, DirectPath, DirectSolver};
+use crate::preconditioner::{Ilu0, Preconditioner};
+
+/// Systems up to this size are solved by a sparse direct factorization. Beyond it the fill-in
+/// of the factors of a 2-D or 3-D problem grows faster than the work of a preconditioned
+/// Krylov method.
+pub const DIRECT_SOLVE_LIMIT: usize = 500;
+
+/// Restart length of GMRES when [`solve`] picks it.
+const GMRES_RESTART: usize = 30;
+
+/// The method [`solve`] used.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SolveMethod {
+    /// Sparse Cholesky factorization through [`DirectSolver`], for small symmetric positive
+    /// definite matrices
+    Cholesky,
+    /// Sparse LU factorization through [`DirectSolver`], for the other small matrices
+    Lu,
+    /// Conjugate gradients, for large matrices that look symmetric positive definite
+    Cg,
+    /// Restarted GMRES, for the other large matrices
+    Gmres,
+}
+
+/// What [`solve`] found out about the matrix and how the solution was obtained.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SolveReport {
+    /// The method that produced the solution
+    pub method: SolveMethod,
+    /// Whether the matrix is exactly symmetric
+    pub symmetric: bool,
+    /// Whether the matrix was taken for positive definite: symmetric with a positive diagonal,
+    /// which is necessary but not sufficient. A CG run that doesn't converge falls back to
+    /// GMRES.
+    pub assumed_positive_definite: bool,
+    /// Whether an ILU(0) preconditioner was used. It is skipped when the factorization fails.
+    pub preconditioned: bool,
+    /// Iterations of the iterative method, 0 for a direct solve
+    pub iterations: usize,
+    /// `‖b − A x‖` of the returned solution
+    pub residual_norm: f64,
+    /// Whether the solution meets the tolerance. Always set for a direct solve.
+    pub converged: bool,
+}
+
+/// Solve `A x = b`, choosing the method from the matrix.
+///
+/// Small systems (up to [`DIRECT_SOLVE_LIMIT`] unknowns) are solved by a [`DirectSolver`] with
+/// its default options, which orders `A` to reduce fill and factors it with Cholesky when it
+/// is symmetric positive definite and with pivoted LU otherwise. Larger systems are solved
+/// iteratively with an ILU(0) preconditioner when it exists: with CG when `A` is symmetric with
+/// a positive diagonal, falling back to GMRES if CG doesn't converge, and with GMRES otherwise.
+/// `opts` applies to the iterative methods.
+///
+/// Fails before any method runs with [`SparseError::DimensionMismatch`] when `A` isn't square
+/// or `b` has the wrong length, and with [`SparseError::EmptyRow`] when a row empty in `A` has
+/// a nonzero in `b`; CG and GMRES check the same and so never fail with it. A direct solve
+/// fails as [`DirectSolver::new`] does: with [`SparseError::ZeroPivot`] when `A` is singular,
+/// and with [`SparseError::EmptyRow`] or [`SparseError::EmptyColumn`] for an empty row or
+/// column even when `b` is zero there. A CG breakdown only falls back to GMRES, and GMRES stops
+/// early when `A M⁻¹` is singular on the Krylov space. An iterative method that runs out of
+/// iterations or stops short of the tolerance is reported with `converged` unset.
+pub fn solve(
+    a: &CsrMatrix<f64>,
+    b: &[f64],
+    opts: &SolverOptions,
+) -> Result<(Vec<f64>, SolveReport), SparseError> {
+    initial_guess(a, b, None)?;
+    let n = a.nrows();
+    let symmetric = a.is_hermitian();
+    let assumed_positive_definite = symmetric && (0..n).all(|i| a.get(i, i) > 0.0);
+
+    let mut report = SolveReport {
+        method: SolveMethod::Lu,
+        symmetric,
+        assumed_positive_definite,
+        preconditioned: false,
+        iterations: 0,
+        residual_norm: 0.0,
+        converged: true,
+    };
+
+    let x = if n <= DIRECT_SOLVE_LIMIT {
+        let solver = DirectSolver::new(a, DirectOptions::default())?;
+        if solver.path() == DirectPath::Cholesky {
+            report.method = SolveMethod::Cholesky;
+        }
+        solver.solve(b)
+    } else {
+        let ilu = Ilu0::new(a).ok();
+        let precond = ilu.as_ref().map(|p| p as &dyn Preconditioner);
+        report.preconditioned = precond.is_some();
+
+        let mut result = None;
+        if assumed_positive_definite {
+            let attempt = cg(a, b, None, precond, opts)?;
+            if attempt.converged {
+                report.method = SolveMethod::Cg;
+                result = Some(attempt);
+            }
+        }
+        let result = match result {
+            Some(result) => result,
+            None => {
+                report.method = SolveMethod::Gmres;
+                gmres(a, b, None, precond, GMRES_RESTART, opts)?
+            }
+        };
+        report.iterations = result.iterations;
+        report.converged = result.converged;
+        result.x
+    };
+
+    let mut r = a.mul_vec(&x);
+    for (ri, bi) in r.iter_mut().zip(b) {
+        *ri = bi - *ri;
+    }
+    report.residual_norm = norm2(&r);
+    Ok((x, report))
+}
+
+#[test]
+fn test_solve_dispatch() {
+    use crate::coo::CooMatrix;
+    use crate::test_util::assert_close;
+
+    // The 5-point stencil on an m x m grid; `skew` makes it nonsymmetric.
+    let grid = |m: usize, skew: f64| {
+        let n = m * m;
+        let mut coo = CooMatrix::new(n, n);
+        for i in 0..m {
+            for j in 0..m {
+                let k = i * m + j;
+                coo.push(k, k, 4.0);
+                if i > 0 {
+                    coo.push(k, k - m, -1.0 - skew);
+                }
+                if i + 1 < m {
+                    coo.push(k, k + m, -1.0 + skew);
+                }
+                if j > 0 {
+                    coo.push(k, k - 1, -1.0);
+                }
+                if j + 1 < m {
+                    coo.push(k, k + 1, -1.0);
+                }
+            }
+        }
+        coo.to_csr()
+    };
+    let opts = SolverOptions::default();
+
+    for (a, method) in [
+        (grid(10, 0.0), SolveMethod::Cholesky),
+        (grid(10, 0.3), SolveMethod::Lu),
+        (grid(30, 0.0), SolveMethod::Cg),
+        (grid(30, 0.3), SolveMethod::Gmres),
+    ] {
+        let n = a.nrows();
+        let expected: Vec<f64> = (0..n).map(|k| ((k * 7) % 11) as f64 - 5.0).collect();
+        let b = a.mul_vec(&expected);
+        let (x, report) = solve(&a, &b, &opts).unwrap();
+        assert_eq!(report.method, method);
+        assert!(report.converged);
+        assert_eq!(report.preconditioned, n > DIRECT_SOLVE_LIMIT);
+        assert!(report.residual_norm <= 1e-8 * norm2(&b), "{report:?}");
+        assert_close(&x, &expected, 1e-7);
+    }
+}
+
+#[test]
+fn test_solve_fallbacks() {
+    // Symmetric with a positive diagonal, but indefinite: Cholesky fails and LU takes over.
+    let a = CsrMatrix::from_dense(2, 2, &[1.0, 2.0, 2.0, 1.0]).unwrap();
+    let (x, report) = solve(&a, &[3.0, 3.0], &SolverOptions::default()).unwrap();
+    assert!(report.assumed_positive_definite);
+    assert_eq!(report.method, SolveMethod::Lu);
+    assert_eq!(x, [1.0, 1.0]);
+
+    let singular = CsrMatrix::from_dense(2, 2, &[1.0, 2.0, 2.0, 4.0]).unwrap();
+    assert_eq!(
+        solve(&singular, &[1.0, 1.0], &SolverOptions::default()),
+        Err(SparseError::ZeroPivot { index: 1 })
+    );
+    assert_eq!(
+        solve(&a, &[1.0], &SolverOptions::default()),
+        Err(SparseError::DimensionMismatch {
+            expected: 2,
+            found: 1
+        })
+    );
+}
+
+#[test]
+fn test_solve_direct_errors() {
+    use crate::coo::CooMatrix;
+
+    let opts = SolverOptions::default();
+
+    // A symmetric matrix with a zero on the diagonal is solved by the direct path too.
+    let saddle = CsrMatrix::from_dense(2, 2, &[0.0, 1.0, 1.0, 0.0]).unwrap();
+    let (x, report) = solve(&saddle, &[2.0, 3.0], &opts).unwrap();
+    assert!(report.symmetric && !report.assumed_positive_definite);
+    assert_eq!(report.method, SolveMethod::Lu);
+    assert_eq!(x, [3.0, 2.0]);
+
+    // An empty row with a zero in b reaches the factorization, which reports it.
+    let empty_row = CsrMatrix::from_dense(2, 2, &[1.0, 1.0, 0.0, 0.0]).unwrap();
+    assert!(matches!(
+        solve(&empty_row, &[1.0, 0.0], &opts),
+        Err(SparseError::EmptyRow { .. })
+    ));
+    assert_eq!(
+        solve(&empty_row, &[1.0, 1.0], &opts),
+        Err(SparseError::EmptyRow { index: 1 })
+    );
+
+    // Past the direct limit, GMRES stops on a singular matrix as soon as A b = 0, on the first
+    // step, and the unsolved system is reported as not converged.
+    let n = DIRECT_SOLVE_LIMIT + 1;
+    let mut coo = CooMatrix::new(n, n);
+    for i in 0..n {
+        coo.push(i, 0, 1.0);
+    }
+    let mut b = vec![0.0; n];
+    b[1] = 1.0;
+    let (x, report) = solve(&coo.to_csr(), &b, &opts).unwrap();
+    assert_eq!(report.method, SolveMethod::Gmres);
+    assert!(!report.converged);
+    assert_eq!((report.iterations, report.residual_norm), (0, 1.0));
+    assert!(x.iter().all(|&xi| xi == 0.0));
+}
@@ -0,0 +1,153 @@
+use alloc::vec;
+
+use super::{initial_guess, SolveResult, SolverOptions};
+use crate::dense::{axpy, dot, norm2};
+use crate::error::SparseError;
+use crate::operator::LinearOperator;
+use crate::preconditioner::Preconditioner;
+
+/// Solve `A x = b` for a symmetric positive definite `A` with the conjugate gradient method,
+/// starting from `x0` (zero when `None`).
+///
+/// Each iteration costs one product with `A`, one application of the preconditioner `M` if
+/// there is one, and a few dense vector updates. `M` must be symmetric positive definite as
+/// well. On a matrix that is not SPD the iteration may diverge or break down; it then stops
+/// without converging.
+pub fn cg(
+    a: &impl LinearOperator,
+    b: &[f64],
+    x0: Option<&[f64]>,
+    precond: Option<&dyn Preconditioner>,
+    opts: &SolverOptions,
+) -> Result<SolveResult, SparseError> {
+    let mut x = initial_guess(a, b, x0)?;
+    let n = x.len();
+    let threshold = opts.tol * norm2(b);
+
+    // r = b - A x, p = r
+    let mut r = vec![0.0; n];
+    a.apply(&x, &mut r);
+    for (ri, bi) in r.iter_mut().zip(b) {
+        *ri = bi - *ri;
+    }
+    // z = M⁻¹ r, p = z
+    let mut z = vec![0.0; n];
+    let precondition = |r: &[f64], z: &mut [f64]| match precond {
+        Some(m) => m.apply(r, z),
+        None => z.copy_from_slice(r),
+    };
+    precondition(&r, &mut z);
+    let mut p = z.clone();
+    let mut ap = vec![0.0; n];
+    let mut rz = dot(&r, &z);
+    let mut residual_norm = norm2(&r);
+    let mut residual_history = vec![residual_norm];
+
+    let mut iterations = 0;
+    while residual_norm > threshold && iterations < opts.max_iter {
+        a.apply(&p, &mut ap);
+        let pap = dot(&p, &ap);
+        if pap <= 0.0 || !pap.is_finite() {
+            // A is not positive definite along p: no further progress is possible.
+            break;
+        }
+
+        let alpha = rz / pap;
+        axpy(alpha, &p, &mut x);
+        axpy(-alpha, &ap, &mut r);
+        residual_norm = norm2(&r);
+
+        precondition(&r, &mut z);
+        let rz_next = dot(&r, &z);
+        let beta = rz_next / rz;
+        for (pi, zi) in p.iter_mut().zip(&z) {
+            *pi = zi + beta * *pi;
+        }
+        rz = rz_next;
+        iterations += 1;
+        residual_history.push(residual_norm);
+    }
+
+    Ok(SolveResult {
+        x,
+        iterations,
+        residual_norm,
+        converged: residual_norm <= threshold,
+        residual_history,
+    })
+}
+
+#[test]
+fn test_cg() {
+    use crate::coo::CooMatrix;
+    use crate::test_util::assert_close;
+
+    // The 1-D Laplacian tridiag(-1, 2, -1) is SPD.
+    let n = 50;
+    let mut coo = CooMatrix::new(n, n);
+    for i in 0..n {
+        coo.push(i, i, 2.0);
+        if i > 0 {
+            coo.push(i, i - 1, -1.0);
+            coo.push(i - 1, i, -1.0);
+        }
+    }
+    let a = coo.to_csr();
+
+    let expected: Vec<f64> = (0..n).map(|i| (i as f64 * 0.3).sin()).collect();
+    let b = a.mul_vec(&expected);
+
+    let result = cg(&a, &b, None, None, &SolverOptions::default()).unwrap();
+    assert!(result.converged);
+    // Exact arithmetic would converge in n steps.
+    assert!(result.iterations <= n);
+    assert!(result.residual_norm <= 1e-10 * norm2(&b));
+    assert_eq!(result.residual_history.len(), result.iterations + 1);
+    assert_eq!(result.residual_history[0], norm2(&b));
+    assert_eq!(result.residual_history.last(), Some(&result.residual_norm));
+    assert_close(&result.x, &expected, 1e-8);
+
+    // Starting from the solution needs no iteration.
+    let result = cg(&a, &b, Some(&expected), None, &SolverOptions::default()).unwrap();
+    assert_eq!(result.iterations, 0);
+    assert!(result.converged);
+
+    let limited = SolverOptions {
+        max_iter: 3,
+        ..SolverOptions::default()
+    };
+    let result = cg(&a, &b, None, None, &limited).unwrap();
+    assert_eq!(result.iterations, 3);
+    assert!(!result.converged);
+
+    assert_eq!(
+        cg(&a, &b[1..], None, None, &SolverOptions::default()).unwrap_err(),
+        SparseError::DimensionMismatch {
+            expected: n,
+            found: n - 1
+        }
+    );
+}
+
+#[test]
+fn test_cg_empty_row() {
+    use crate::csr::CsrMatrix;
+
+    // Row 1 is empty, so no x satisfies it unless b[1] is zero.
+    let a = CsrMatrix::from_dense(3, 3, &[2.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 2.0]).unwrap();
+    let opts = SolverOptions::default();
+    assert_eq!(
+        cg(&a, &[1.0, 1.0, 1.0], None, None, &opts).map(|r| r.x),
+        Err(SparseError::EmptyRow { index: 1 })
+    );
+    assert_eq!(
+        super::gmres(&a, &[1.0, 1.0, 1.0], None, None, 10, &opts).map(|r| r.x),
+        Err(SparseError::EmptyRow { index: 1 })
+    );
+    assert_eq!(
+        cg(&a.to_csc(), &[0.0, 1.0, 0.0], None, None, &opts).map(|r| r.x),
+        Err(SparseError::EmptyRow { index: 1 })
+    );
+    let result = cg(&a, &[1.0, 0.0, 1.0], None, None, &opts).unwrap();
+    assert_eq!(result.x, [0.5, 0.0, 0.5]);
+}
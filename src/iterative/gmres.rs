@@ -0,0 +1,268 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use super::{initial_guess, SolveResult, SolverOptions};
+use crate::dense::{axpy, dot, hypot, norm2};
+use crate::error::SparseError;
+use crate::operator::LinearOperator;
+use crate::preconditioner::Preconditioner;
+
+/// Solve `A x = b` for a general square `A` with restarted GMRES, starting from `x0` (zero when
+/// `None`).
+///
+/// Every cycle builds an orthonormal basis of the Krylov space of `A M⁻¹` with the Arnoldi
+/// process (modified Gram–Schmidt), and picks the iterate minimizing the residual over it.
+/// The small least-squares problem is kept triangular by Givens rotations, which also give the
+/// residual norm of every step for free. After `restart` steps the basis is dropped and a new
+/// cycle starts from the current iterate, bounding memory to `restart + 1` vectors.
+///
+/// The preconditioner is applied on the right, so the residual monitored is the true one.
+/// `opts.max_iter` counts Arnoldi steps over all cycles.
+///
+/// A pivot of the triangular least-squares problem vanishes when `A M⁻¹` is singular on the
+/// Krylov space. More steps can't lower the residual then, so GMRES stops with the least-squares
+/// iterate of the basis built so far: it has converged when `b` is in the range of `A`, and
+/// otherwise the residual tells how far it is. Fails with [`SparseError::Breakdown`] only when
+/// the pivot isn't finite, as with entries that aren't.
+pub fn gmres(
+    a: &impl LinearOperator,
+    b: &[f64],
+    x0: Option<&[f64]>,
+    precond: Option<&dyn Preconditioner>,
+    restart: usize,
+    opts: &SolverOptions,
+) -> Result<SolveResult, SparseError> {
+    let mut x = initial_guess(a, b, x0)?;
+    let n = x.len();
+    let m = restart.max(1);
+    let threshold = opts.tol * norm2(b);
+    let precondition = |v: &[f64], z: &mut [f64]| match precond {
+        Some(p) => p.apply(v, z),
+        None => z.copy_from_slice(v),
+    };
+
+    let mut r = vec![0.0; n];
+    let mut w = vec![0.0; n];
+    let mut z = vec![0.0; n];
+    let mut iterations = 0;
+    let mut residual_history = Vec::new();
+    let mut stalled = false;
+
+    loop {
+        // r = b - A x
+        a.apply(&x, &mut r);
+        for (ri, bi) in r.iter_mut().zip(b) {
+            *ri = bi - *ri;
+        }
+        let beta = norm2(&r);
+        if residual_history.is_empty() {
+            residual_history.push(beta);
+        }
+        if beta <= threshold || iterations >= opts.max_iter || stalled {
+            return Ok(SolveResult {
+                x,
+                iterations,
+                residual_norm: beta,
+                converged: beta <= threshold,
+                residual_history,
+            });
+        }
+
+        // Arnoldi basis, Hessenberg matrix stored by columns, rotations and rotated rhs.
+        let mut basis: Vec<Vec<f64>> = vec![r.iter().map(|ri| ri / beta).collect()];
+        let mut h: Vec<Vec<f64>> = Vec::with_capacity(m);
+        let mut cs: Vec<f64> = Vec::with_capacity(m);
+        let mut sn: Vec<f64> = Vec::with_capacity(m);
+        let mut g = vec![beta];
+
+        for j in 0..m {
+            precondition(&basis[j], &mut z);
+            a.apply(&z, &mut w);
+
+            let mut col = vec![0.0; j + 2];
+            for (i, v) in basis.iter().enumerate() {
+                col[i] = dot(&w, v);
+                axpy(-col[i], v, &mut w);
+            }
+            col[j + 1] = norm2(&w);
+            let h_next = col[j + 1];
+            let col_norm = norm2(&col);
+
+            // Apply the previous rotations to the new column, then zero its last entry.
+            for i in 0..j {
+                let (hi, hi1) = (col[i], col[i + 1]);
+                col[i] = cs[i] * hi + sn[i] * hi1;
+                col[i + 1] = -sn[i] * hi + cs[i] * hi1;
+            }
+            let rho = hypot(col[j], col[j + 1]);
+            if !rho.is_finite() {
+                return Err(SparseError::Breakdown {
+                    iteration: iterations,
+                });
+            }
+            // ρ is the pivot of the back substitution below. It vanishes, relative to the column
+            // it comes from, when A M⁻¹ is singular on the Krylov space: the new direction adds
+            // nothing, so solve over the previous ones and stop.
+            if rho <= f64::EPSILON * col_norm {
+                stalled = true;
+                break;
+            }
+            let (c, s) = (col[j] / rho, col[j + 1] / rho);
+            col[j] = rho;
+            col[j + 1] = 0.0;
+            cs.push(c);
+            sn.push(s);
+            g.push(-s * g[j]);
+            g[j] *= c;
+            h.push(col);
+            iterations += 1;
+            residual_history.push(g[j + 1].abs());
+
+            let done = g[j + 1].abs() <= threshold || iterations >= opts.max_iter;
+            // A zero h_next means the Krylov space is invariant: the solution is in it.
+            if done || h_next == 0.0 {
+                break;
+            }
+            basis.push(w.iter().map(|wi| wi / h_next).collect());
+        }
+
+        // Solve the triangular system H y = g and update x += M⁻¹ V y.
+        let k = h.len();
+        let mut y = vec![0.0; k];
+        for i in (0..k).rev() {
+            let mut sum = g[i];
+            for (l, yl) in y.iter().enumerate().skip(i + 1) {
+                sum -= h[l][i] * yl;
+            }
+            y[i] = sum / h[i][i];
+        }
+
+        let mut update = vec![0.0; n];
+        for (v, yi) in basis.iter().zip(&y) {
+            axpy(*yi, v, &mut update);
+        }
+        precondition(&update, &mut z);
+        axpy(1.0, &z, &mut x);
+    }
+}
+
+#[test]
+fn test_gmres() {
+    use crate::coo::CooMatrix;
+    use crate::preconditioner::Ilu0;
+    use crate::test_util::assert_close;
+
+    // 2-D convection-diffusion: a nonsymmetric 5-point stencil.
+    let m = 12;
+    let n = m * m;
+    let mut coo = CooMatrix::new(n, n);
+    for i in 0..m {
+        for j in 0..m {
+            let k = i * m + j;
+            coo.push(k, k, 4.0);
+            if i > 0 {
+                coo.push(k, k - m, -1.3);
+            }
+            if i + 1 < m {
+                coo.push(k, k + m, -0.7);
+            }
+            if j > 0 {
+                coo.push(k, k - 1, -1.5);
+            }
+            if j + 1 < m {
+                coo.push(k, k + 1, -0.5);
+            }
+        }
+    }
+    let a = coo.to_csr();
+    let expected: Vec<f64> = (0..n).map(|k| ((k * 7) % 11) as f64 - 5.0).collect();
+    let b = a.mul_vec(&expected);
+    let opts = SolverOptions::default();
+
+    // Without restarts GMRES terminates in at most n steps.
+    let full = gmres(&a, &b, None, None, n, &opts).unwrap();
+    assert!(full.converged);
+    assert!(full.iterations <= n);
+    assert_eq!(full.residual_history.len(), full.iterations + 1);
+    // GMRES minimizes the residual: it never grows within a cycle.
+    assert!(full
+        .residual_history
+        .windows(2)
+        .all(|w| w[1] <= w[0] * (1.0 + 1e-12)));
+    assert_close(&full.x, &expected, 1e-7);
+
+    let restarted = gmres(&a, &b, None, None, 10, &opts).unwrap();
+    assert!(restarted.converged);
+    assert!(restarted.iterations >= full.iterations);
+    assert_close(&restarted.x, &expected, 1e-7);
+
+    let ilu = Ilu0::new(&a).unwrap();
+    let preconditioned = gmres(&a, &b, None, Some(&ilu), 10, &opts).unwrap();
+    assert!(preconditioned.converged);
+    assert!(preconditioned.iterations < restarted.iterations);
+    assert_close(&preconditioned.x, &expected, 1e-7);
+
+    // The reported residual is the true one.
+    let mut ax = a.mul_vec(&preconditioned.x);
+    axpy(-1.0, &b, &mut ax);
+    assert!((norm2(&ax) - preconditioned.residual_norm).abs() < 1e-12 * norm2(&b));
+
+    let limited = SolverOptions {
+        max_iter: 5,
+        ..opts
+    };
+    let result = gmres(&a, &b, None, None, 3, &limited).unwrap();
+    assert_eq!(result.iterations, 5);
+    assert!(!result.converged);
+
+    // A diagonal system is solved in one step of a one-dimensional Krylov space per cycle.
+    let d = CooMatrix::from_triplets(3, 3, vec![0, 1, 2], vec![0, 1, 2], vec![2.0, 2.0, 2.0])
+        .unwrap()
+        .to_csr();
+    let result = gmres(&d, &[2.0, 4.0, 6.0], None, None, 5, &opts).unwrap();
+    assert_eq!(result.iterations, 1);
+    assert_close(&result.x, &[1.0, 2.0, 3.0], 1e-14);
+}
+
+#[test]
+fn test_gmres_breakdown() {
+    use crate::csr::CsrMatrix;
+    use crate::test_util::assert_close;
+
+    // A b = 0: the first pivot vanishes with nothing to solve over, and x0 is returned. Neither
+    // matrix has an empty row, which would fail before iterating.
+    let singular = CsrMatrix::from_dense(2, 2, &[1.0, 0.0, 1.0, 0.0]).unwrap();
+    let opts = SolverOptions::default();
+    let result = gmres(&singular, &[0.0, 1.0], None, None, 5, &opts).unwrap();
+    assert!(!result.converged);
+    assert_eq!((result.iterations, result.residual_norm), (0, 1.0));
+    assert_eq!(result.x, [0.0, 0.0]);
+
+    // A nilpotent matrix: A² b = 0 stops the second step, with the least-squares iterate of the
+    // first. The system has no solution, and that iterate is the closest GMRES gets.
+    let nilpotent = CsrMatrix::from_dense(2, 2, &[1.0, -1.0, 1.0, -1.0]).unwrap();
+    let result = gmres(&nilpotent, &[1.0, 0.0], None, None, 5, &opts).unwrap();
+    assert!(!result.converged);
+    assert_eq!(result.iterations, 1);
+    assert_close(&result.x, &[0.5, 0.0], 1e-15);
+    assert!((result.residual_norm - 0.5f64.sqrt()).abs() < 1e-15);
+
+    // With the empty row in the way, the cause is reported instead.
+    let singular = CsrMatrix::from_diagonal(&[1.0, 0.0]);
+    assert_eq!(
+        gmres(&singular, &[0.0, 1.0], None, None, 5, &opts).unwrap_err(),
+        SparseError::EmptyRow { index: 1 }
+    );
+
+    // A lucky breakdown, where the Krylov space is invariant, still solves the system.
+    let result = gmres(&singular, &[3.0, 0.0], None, None, 5, &opts).unwrap();
+    assert!(result.converged);
+    assert_eq!(result.x, [3.0, 0.0]);
+
+    // A pivot that isn't finite is still an error.
+    let infinite = CsrMatrix::from_diagonal(&[f64::INFINITY, 1.0]);
+    assert_eq!(
+        gmres(&infinite, &[1.0, 1.0], None, None, 5, &opts).unwrap_err(),
+        SparseError::Breakdown { iteration: 0 }
+    );
+}
@@ -0,0 +1,234 @@
+use alloc::vec;
+
+use super::{check_least_squares, LeastSquaresResult, SolverOptions};
+use crate::dense::{axpy, hypot, norm2, sqrt};
+use crate::error::SparseError;
+use crate::operator::TransposeOperator;
+
+/// Minimize `‖b − A x‖² + damp² ‖x‖²` over `x` for a rectangular `A` with LSMR (Fong and
+/// Saunders), starting from zero.
+///
+/// LSMR runs the same Golub–Kahan bidiagonalization as [`lsqr`](super::lsqr) but is equivalent
+/// to MINRES on the normal equations, so `‖Aᵀ r‖` decreases monotonically and the iteration can
+/// be stopped early more safely. It takes the same arguments and stopping rules as LSQR and
+/// costs a few more vector updates per iteration.
+pub fn lsmr(
+    a: &impl TransposeOperator,
+    b: &[f64],
+    damp: f64,
+    opts: &SolverOptions,
+) -> Result<LeastSquaresResult, SparseError> {
+    check_least_squares(a, b)?;
+    let (m, n) = (a.nrows(), a.ncols());
+    let mut x = vec![0.0; n];
+
+    // β u = b, α v = Aᵀ u
+    let mut u = b.to_vec();
+    let b_norm = norm2(b);
+    let mut beta = b_norm;
+    let mut v = vec![0.0; n];
+    let mut alpha = 0.0;
+    if beta > 0.0 {
+        u.iter_mut().for_each(|ui| *ui /= beta);
+        a.apply_transpose(&u, &mut v);
+        alpha = norm2(&v);
+    }
+    if alpha > 0.0 {
+        v.iter_mut().for_each(|vi| *vi /= alpha);
+    }
+    if alpha * beta == 0.0 {
+        // Aᵀ b = 0: x = 0 is the solution.
+        return Ok(LeastSquaresResult::new(a, b, damp, x, 0, true));
+    }
+
+    // The two rotations that make the bidiagonal upper and then lower
+    let mut zetabar = alpha * beta;
+    let mut alphabar = alpha;
+    let (mut rho, mut rhobar) = (1.0, 1.0);
+    let (mut cbar, mut sbar) = (1.0, 0.0);
+    let mut zeta = 0.0;
+    let mut h = v.clone();
+    let mut hbar = vec![0.0; n];
+
+    // The third rotation, which only serves to estimate ‖r‖
+    let mut betadd = beta;
+    let mut betad = 0.0;
+    let mut rhodold = 1.0;
+    let mut tautildeold = 0.0;
+    let mut thetatilde = 0.0;
+    let mut d = 0.0;
+    let mut a_norm_squared = alpha * alpha;
+
+    let mut au = vec![0.0; m];
+    let mut atv = vec![0.0; n];
+    let mut iterations = 0;
+    let mut converged = false;
+    while iterations < opts.max_iter {
+        iterations += 1;
+
+        // β u = A v − α u, α v = Aᵀ u − β v
+        a.apply(&v, &mut au);
+        for (ui, avi) in u.iter_mut().zip(&au) {
+            *ui = avi - alpha * *ui;
+        }
+        beta = norm2(&u);
+        if beta > 0.0 {
+            u.iter_mut().for_each(|ui| *ui /= beta);
+        }
+        a.apply_transpose(&u, &mut atv);
+        for (vi, atui) in v.iter_mut().zip(&atv) {
+            *vi = atui - beta * *vi;
+        }
+        alpha = norm2(&v);
+        if alpha > 0.0 {
+            v.iter_mut().for_each(|vi| *vi /= alpha);
+        }
+
+        let (chat, shat, alphahat) = sym_ortho(alphabar, damp);
+
+        let rhoold = rho;
+        let (c, s, r) = sym_ortho(alphahat, beta);
+        rho = r;
+        if rho == 0.0 {
+            // α̂ and β vanish together only once Aᵀ r = 0, so x is a solution.
+            converged = true;
+            break;
+        }
+        let thetanew = s * alpha;
+        alphabar = c * alpha;
+
+        let rhobarold = rhobar;
+        let zetaold = zeta;
+        let thetabar = sbar * rho;
+        let (cb, sb, rb) = sym_ortho(cbar * rho, thetanew);
+        (cbar, sbar, rhobar) = (cb, sb, rb);
+        zeta = cbar * zetabar;
+        zetabar *= -sbar;
+
+        // hbar = h − (θ̄ ρ / (ρ_old ρ̄_old)) hbar, x += (ζ / (ρ ρ̄)) hbar, h = v − (θ / ρ) h
+        let scale = thetabar * rho / (rhoold * rhobarold);
+        for (hbi, hi) in hbar.iter_mut().zip(&h) {
+            *hbi = hi - scale * *hbi;
+        }
+        axpy(zeta / (rho * rhobar), &hbar, &mut x);
+        for (hi, vi) in h.iter_mut().zip(&v) {
+            *hi = vi - thetanew / rho * *hi;
+        }
+
+        // Estimate ‖r‖.
+        let betaacute = chat * betadd;
+        let betacheck = -shat * betadd;
+        let betahat = c * betaacute;
+        betadd = -s * betaacute;
+
+        let thetatildeold = thetatilde;
+        let (ctildeold, stildeold, rhotildeold) = sym_ortho(rhodold, thetabar);
+        thetatilde = stildeold * rhobar;
+        rhodold = ctildeold * rhobar;
+        betad = -stildeold * betad + ctildeold * betahat;
+
+        tautildeold = (zetaold - thetatildeold * tautildeold) / rhotildeold;
+        let taud = (zeta - thetatilde * tautildeold) / rhodold;
+        d += betacheck * betacheck;
+        let r_norm = sqrt(d + (betad - taud) * (betad - taud) + betadd * betadd);
+
+        a_norm_squared += beta * beta;
+        let a_norm = sqrt(a_norm_squared);
+        a_norm_squared += alpha * alpha;
+
+        let normal_norm = zetabar.abs();
+        if r_norm <= opts.tol * (b_norm + a_norm * norm2(&x))
+            || normal_norm <= opts.tol * a_norm * r_norm
+        {
+            converged = true;
+            break;
+        }
+    }
+
+    Ok(LeastSquaresResult::new(
+        a, b, damp, x, iterations, converged,
+    ))
+}
+
+/// Return `(c, s, r)` with `r = √(a² + b²)` and `c = a / r`, `s = b / r`, the rotation that
+/// maps `(a, b)` to `(r, 0)`.
+fn sym_ortho(a: f64, b: f64) -> (f64, f64, f64) {
+    let r = hypot(a, b);
+    if r == 0.0 {
+        (1.0, 0.0, 0.0)
+    } else {
+        (a / r, b / r, r)
+    }
+}
+
+#[test]
+fn test_lsmr() {
+    use crate::csr::CsrMatrix;
+    use crate::test_util::{assert_close, normal_equations, Lcg};
+
+    let (m, n) = (60, 20);
+    let mut rng = Lcg::new(29);
+    let mut dense = rng.dense(m, n, 0.2);
+    for j in 0..n {
+        dense[j * n + j] += 2.0;
+    }
+    let a = CsrMatrix::from_dense(m, n, &dense).unwrap();
+    let b: Vec<f64> = (0..m).map(|_| rng.uniform()).collect();
+    let opts = SolverOptions::default();
+
+    for damp in [0.0, 0.7] {
+        let expected = normal_equations(&a, &b, damp);
+        let result = lsmr(&a, &b, damp, &opts).unwrap();
+        assert!(result.converged);
+        assert!(result.iterations <= 2 * n);
+        assert_close(&result.x, &expected, 1e-8);
+        assert!(result.normal_residual_norm < 1e-8);
+        // LSQR reaches the same solution.
+        assert_close(
+            &super::lsqr(&a, &b, damp, &opts).unwrap().x,
+            &result.x,
+            1e-8,
+        );
+    }
+
+    // A wide, underdetermined system: the minimum-norm solution lies in the row space of A.
+    let wide = a.transpose();
+    let c: Vec<f64> = (0..n).map(|_| rng.uniform()).collect();
+    let result = lsmr(&wide, &c, 0.0, &opts).unwrap();
+    assert!(result.converged && result.residual_norm < 1e-8);
+    let y = normal_equations(&a, &result.x, 0.0);
+    assert_close(&a.mul_vec(&y), &result.x, 1e-8);
+
+    let zero = lsmr(&a, &vec![0.0; m], 0.0, &opts).unwrap();
+    assert_eq!((zero.iterations, zero.x), (0, vec![0.0; n]));
+}
+
+#[test]
+fn test_lsmr_vanishing_rho() {
+    use crate::csr::CsrMatrix;
+    use crate::test_util::assert_close;
+
+    // The second column is zero, so the bidiagonalization runs out after one step.
+    let a = CsrMatrix::from_dense(3, 2, &[2.0, 0.0, 0.0, 0.0, 2.0, 0.0]).unwrap();
+    let b = [1.0, 1.0, 1.0];
+    let result = lsmr(&a, &b, 0.0, &SolverOptions::default()).unwrap();
+    assert!(result.converged);
+    assert_close(&result.x, &[0.5, 0.0], 1e-12);
+
+    // A negative tolerance disables the stopping tests, leaving the iteration to notice that ρ
+    // vanished once the bidiagonalization of this single column ran out, rather than divide by
+    // it.
+    let opts = SolverOptions {
+        tol: -1.0,
+        max_iter: 10,
+    };
+    let result = lsmr(
+        &CsrMatrix::from_dense(2, 1, &[1.0, 0.0]).unwrap(),
+        &[1.0, 4.0],
+        0.0,
+        &opts,
+    )
+    .unwrap();
+    assert!(result.converged && result.iterations < 10);
+    assert_eq!(result.x, vec![1.0]);
+}
@@ -0,0 +1,196 @@
+use alloc::vec;
+
+use super::{check_least_squares, LeastSquaresResult, SolverOptions};
+use crate::dense::{axpy, hypot, norm2, sqrt};
+use crate::error::SparseError;
+use crate::operator::TransposeOperator;
+
+/// Minimize `‖b − A x‖² + damp² ‖x‖²` over `x` for a rectangular `A` with LSQR (Paige and
+/// Saunders), starting from zero.
+///
+/// LSQR runs the Golub–Kahan bidiagonalization of `A`, one product with `A` and one with `Aᵀ`
+/// per iteration, and is equivalent in exact arithmetic to CG on the normal equations
+/// `(AᵀA + damp² I) x = Aᵀ b`, with better numerical behavior. A positive `damp` adds Tikhonov
+/// regularization, which keeps `x` small when `A` is ill-conditioned or rank-deficient; without
+/// damping and with `A` rank-deficient, LSQR converges to the minimum-norm solution.
+///
+/// It stops when `‖r‖ ≤ tol (‖b‖ + ‖A‖ ‖x‖)`, a compatible system solved, or when
+/// `‖Aᵀ r‖ ≤ tol ‖A‖ ‖r‖`, a least-squares solution found, with `r` the (damped) residual and
+/// `‖A‖` estimated from the bidiagonalization. Fails only when `b` doesn't have a component
+/// per row of `A`.
+pub fn lsqr(
+    a: &impl TransposeOperator,
+    b: &[f64],
+    damp: f64,
+    opts: &SolverOptions,
+) -> Result<LeastSquaresResult, SparseError> {
+    check_least_squares(a, b)?;
+    let (m, n) = (a.nrows(), a.ncols());
+    let mut x = vec![0.0; n];
+
+    // β u = b, α v = Aᵀ u
+    let mut u = b.to_vec();
+    let b_norm = norm2(b);
+    let mut beta = b_norm;
+    let mut v = vec![0.0; n];
+    let mut alpha = 0.0;
+    if beta > 0.0 {
+        u.iter_mut().for_each(|ui| *ui /= beta);
+        a.apply_transpose(&u, &mut v);
+        alpha = norm2(&v);
+    }
+    if alpha > 0.0 {
+        v.iter_mut().for_each(|vi| *vi /= alpha);
+    }
+    if alpha * beta == 0.0 {
+        // Aᵀ b = 0: x = 0 is the solution.
+        return Ok(LeastSquaresResult::new(a, b, damp, x, 0, true));
+    }
+
+    let mut w = v.clone();
+    let mut phibar = beta;
+    let mut rhobar = alpha;
+    let mut a_norm_squared = 0.0;
+    // The part of the damped residual norm eliminated by the damping rotations
+    let mut damped_squared = 0.0;
+    let mut au = vec![0.0; m];
+    let mut atv = vec![0.0; n];
+
+    let mut iterations = 0;
+    let mut converged = false;
+    while iterations < opts.max_iter {
+        iterations += 1;
+
+        // β u = A v − α u, α v = Aᵀ u − β v
+        a.apply(&v, &mut au);
+        for (ui, avi) in u.iter_mut().zip(&au) {
+            *ui = avi - alpha * *ui;
+        }
+        beta = norm2(&u);
+        if beta > 0.0 {
+            u.iter_mut().for_each(|ui| *ui /= beta);
+        }
+        a_norm_squared += alpha * alpha + beta * beta + damp * damp;
+        a.apply_transpose(&u, &mut atv);
+        for (vi, atui) in v.iter_mut().zip(&atv) {
+            *vi = atui - beta * *vi;
+        }
+        alpha = norm2(&v);
+        if alpha > 0.0 {
+            v.iter_mut().for_each(|vi| *vi /= alpha);
+        }
+
+        // Rotate the damping out of the lower bidiagonal, then reduce it to upper bidiagonal.
+        let rhobar1 = hypot(rhobar, damp);
+        if rhobar1 == 0.0 {
+            // ρ̄ and with it ρ vanish once α did without damping: Aᵀ r = 0, so x is a solution.
+            converged = true;
+            break;
+        }
+        let (c1, s1) = (rhobar / rhobar1, damp / rhobar1);
+        let psi = s1 * phibar;
+        phibar *= c1;
+
+        let rho = hypot(rhobar1, beta);
+        let (c, s) = (rhobar1 / rho, beta / rho);
+        let theta = s * alpha;
+        rhobar = -c * alpha;
+        let phi = c * phibar;
+        phibar *= s;
+
+        // x += (φ / ρ) w, w = v − (θ / ρ) w
+        axpy(phi / rho, &w, &mut x);
+        for (wi, vi) in w.iter_mut().zip(&v) {
+            *wi = vi - theta / rho * *wi;
+        }
+
+        damped_squared += psi * psi;
+        let r_norm = sqrt(phibar * phibar + damped_squared);
+        let normal_norm = alpha * (s * phi).abs();
+        let a_norm = sqrt(a_norm_squared);
+        if r_norm <= opts.tol * (b_norm + a_norm * norm2(&x))
+            || normal_norm <= opts.tol * a_norm * r_norm
+        {
+            converged = true;
+            break;
+        }
+    }
+
+    Ok(LeastSquaresResult::new(
+        a, b, damp, x, iterations, converged,
+    ))
+}
+
+#[test]
+fn test_lsqr() {
+    use crate::csr::CsrMatrix;
+    use crate::test_util::{assert_close, normal_equations, Lcg};
+
+    // An overdetermined system with no exact solution.
+    let (m, n) = (60, 20);
+    let mut rng = Lcg::new(17);
+    let mut dense = rng.dense(m, n, 0.2);
+    for j in 0..n {
+        dense[j * n + j] += 2.0;
+    }
+    let a = CsrMatrix::from_dense(m, n, &dense).unwrap();
+    let b: Vec<f64> = (0..m).map(|_| rng.uniform()).collect();
+    let opts = SolverOptions::default();
+
+    for damp in [0.0, 0.7] {
+        let expected = normal_equations(&a, &b, damp);
+        let result = lsqr(&a, &b, damp, &opts).unwrap();
+        assert!(result.converged);
+        assert!(result.iterations <= 2 * n);
+        assert_close(&result.x, &expected, 1e-8);
+        assert!(result.normal_residual_norm < 1e-8);
+        assert!(result.residual_norm > 0.1);
+    }
+
+    // A consistent system is solved exactly, through CSC as well.
+    let x: Vec<f64> = (0..n).map(|i| i as f64 * 0.25).collect();
+    let b = a.mul_vec(&x);
+    let result = lsqr(&a.to_csc(), &b, 0.0, &opts).unwrap();
+    assert!(result.converged && result.residual_norm < 1e-8);
+    assert_close(&result.x, &x, 1e-8);
+
+    let zero = lsqr(&a, &vec![0.0; m], 0.0, &opts).unwrap();
+    assert_eq!((zero.iterations, zero.x), (0, vec![0.0; n]));
+    assert_eq!(
+        lsqr(&a, &b[1..], 0.0, &opts).unwrap_err(),
+        SparseError::DimensionMismatch {
+            expected: m,
+            found: m - 1
+        }
+    );
+}
+
+#[test]
+fn test_lsqr_vanishing_rho() {
+    use crate::csr::CsrMatrix;
+    use crate::test_util::assert_close;
+
+    // The second column is zero, so the bidiagonalization runs out after one step.
+    let a = CsrMatrix::from_dense(3, 2, &[2.0, 0.0, 0.0, 0.0, 2.0, 0.0]).unwrap();
+    let b = [1.0, 1.0, 1.0];
+    let result = lsqr(&a, &b, 0.0, &SolverOptions::default()).unwrap();
+    assert!(result.converged);
+    assert_close(&result.x, &[0.5, 0.0], 1e-12);
+
+    // A negative tolerance disables the stopping tests, leaving the iteration to notice that ρ
+    // vanished once the bidiagonalization of this single column ran out, rather than divide by
+    // it.
+    let opts = SolverOptions {
+        tol: -1.0,
+        max_iter: 10,
+    };
+    let result = lsqr(
+        &CsrMatrix::from_dense(2, 1, &[1.0, 0.0]).unwrap(),
+        &[1.0, 4.0],
+        0.0,
+        &opts,
+    )
+    .unwrap();
+    assert!(result.converged && result.iterations < 10);
+    assert_eq!(result.x, vec![1.0]);
+}
@@ -0,0 +1,159 @@
+//! Iterative solvers for `A x = b`.
+//!
+//! The solvers reach the matrix through [`LinearOperator`] only, and share [`SolverOptions`] for
+//! the stopping criterion and [`SolveResult`] for reporting. [`solve`] picks a method, direct or
+//! iterative, for a given CSR matrix. The classical stationary iterations in [`stationary`]
+//! need the entries themselves and take a CSR matrix too. [`lsqr`] and [`lsmr`] solve
+//! rectangular least-squares problems through a [`TransposeOperator`] and report a
+//! [`LeastSquaresResult`].
+//!
+//! A square solver fails with [`SparseError::EmptyRow`] before iterating when a row that has no
+//! stored entries in `A` has a nonzero in `b`, since no `x` satisfies it.
+
+pub mod auto;
+pub mod bicgstab;
+pub mod cg;
+pub mod gmres;
+pub mod lsmr;
+pub mod lsqr;
+pub mod stationary;
+
+pub use auto::{solve, SolveMethod, SolveReport};
+pub use bicgstab::bicgstab;
+pub use cg::cg;
+pub use gmres::gmres;
+pub use lsmr::lsmr;
+pub use lsqr::lsqr;
+pub use stationary::{gauss_seidel, jacobi, sor, ssor, stationary, Smoother, Sweep};
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::dense::{axpy, norm2};
+use crate::error::SparseError;
+use crate::operator::{LinearOperator, TransposeOperator};
+
+/// Stopping criterion shared by the iterative solvers.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SolverOptions {
+    /// Stop once `‖b − A x‖ ≤ tol · ‖b‖`
+    pub tol: f64,
+    /// Give up after this many iterations
+    pub max_iter: usize,
+}
+
+impl Default for SolverOptions {
+    fn default() -> Self {
+        Self {
+            tol: 1e-10,
+            max_iter: 1000,
+        }
+    }
+}
+
+/// The outcome of an iterative solve. Running out of iterations is not an error: the last
+/// iterate is returned with `converged` unset, for the caller to judge.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SolveResult {
+    /// The last iterate
+    pub x: Vec<f64>,
+    /// Number of iterations performed
+    pub iterations: usize,
+    /// `‖b − A x‖` of the last iterate
+    pub residual_norm: f64,
+    /// The residual norm of the initial guess, then after every iteration. GMRES records the
+    /// estimate its least-squares problem provides rather than recomputing `‖b − A x‖`.
+    pub residual_history: Vec<f64>,
+    /// Whether the stopping criterion was met
+    pub converged: bool,
+}
+
+/// The outcome of a least-squares solve, `min ‖b − A x‖² + λ² ‖x‖²` for a damping λ. The norms
+/// are recomputed from the returned `x` rather than taken from the recurrences.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LeastSquaresResult {
+    /// The last iterate
+    pub x: Vec<f64>,
+    /// Number of iterations performed
+    pub iterations: usize,
+    /// `‖b − A x‖`, which needn't be small when the system has no exact solution
+    pub residual_norm: f64,
+    /// `‖Aᵀ (b − A x) − λ² x‖`, the gradient of the objective, zero at the minimizer
+    pub normal_residual_norm: f64,
+    /// Whether the stopping criterion was met
+    pub converged: bool,
+}
+
+impl LeastSquaresResult {
+    /// Collect the result for the iterate `x`, computing its residual norms.
+    fn new(
+        a: &impl TransposeOperator,
+        b: &[f64],
+        damp: f64,
+        x: Vec<f64>,
+        iterations: usize,
+        converged: bool,
+    ) -> Self {
+        let mut r = vec![0.0; a.nrows()];
+        a.apply(&x, &mut r);
+        for (ri, bi) in r.iter_mut().zip(b) {
+            *ri = bi - *ri;
+        }
+        let mut g = vec![0.0; a.ncols()];
+        a.apply_transpose(&r, &mut g);
+        axpy(-damp * damp, &x, &mut g);
+
+        Self {
+            x,
+            iterations,
+            residual_norm: norm2(&r),
+            normal_residual_norm: norm2(&g),
+            converged,
+        }
+    }
+}
+
+/// Check that `b` has a component per row of `a`.
+fn check_least_squares(a: &impl LinearOperator, b: &[f64]) -> Result<(), SparseError> {
+    if b.len() != a.nrows() {
+        return Err(SparseError::DimensionMismatch {
+            expected: a.nrows(),
+            found: b.len(),
+        });
+    }
+    Ok(())
+}
+
+/// Check that `a` is square, that `b` and the initial guess, if any, match its size, and that
+/// no row empty in `a` has a nonzero in `b`, which fails with [`SparseError::EmptyRow`]. Return
+/// the initial guess, zero when there is none.
+pub(crate) fn initial_guess(
+    a: &impl LinearOperator,
+    b: &[f64],
+    x0: Option<&[f64]>,
+) -> Result<Vec<f64>, SparseError> {
+    if a.nrows() != a.ncols() {
+        return Err(SparseError::DimensionMismatch {
+            expected: a.nrows(),
+            found: a.ncols(),
+        });
+    }
+    if b.len() != a.nrows() {
+        return Err(SparseError::DimensionMismatch {
+            expected: a.nrows(),
+            found: b.len(),
+        });
+    }
+    if let Some(index) = (a.find_empty_rows().into_iter()).find(|&i| b[i] != 0.0) {
+        return Err(SparseError::EmptyRow { index });
+    }
+
+    match x0 {
+        Some(x0) if x0.len() != a.ncols() => Err(SparseError::DimensionMismatch {
+            expected: a.ncols(),
+            found: x0.len(),
+        }),
+        Some(x0) => Ok(x0.to_vec()),
+        None => Ok(vec![0.0; a.ncols()]),
+    }
+}
@@ -0,0 +1,428 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use super::{initial_guess, SolveResult, SolverOptions};
+use crate::csr::CsrMatrix;
+use crate::dense::norm2;
+use crate::error::SparseError;
+use crate::scalar::Scalar;
+
+/// The order in which a stationary iteration updates the unknowns.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Sweep {
+    /// All unknowns at once from the previous iterate, `x += ω D⁻¹ (b − A x)`
+    Jacobi,
+    /// Gauss–Seidel by increasing row, each unknown using those already updated in the sweep
+    Forward,
+    /// Gauss–Seidel by decreasing row
+    Backward,
+    /// A forward Gauss–Seidel sweep then a backward one. For a symmetric `A` the sweep is then a
+    /// symmetric operator, as CG and multigrid want of a smoother.
+    Symmetric,
+}
+
+/// A stationary iteration on a CSR matrix, run for a fixed number of sweeps to damp the error
+/// of an approximate solution. Multigrid uses such sweeps to smooth the error before and after
+/// the coarse-grid correction.
+///
+/// Every update can be relaxed by a factor ω: each unknown moves ω times as far as the plain
+/// sweep would move it. Gauss–Seidel relaxed this way is SOR, and its symmetric sweep SSOR.
+#[derive(Clone, Debug)]
+pub struct Smoother<'a> {
+    a: &'a CsrMatrix<f64>,
+    /// `ω / a_ii` of every row
+    inv_diag: Vec<f64>,
+    sweep: Sweep,
+    omega: f64,
+}
+
+impl<'a> Smoother<'a> {
+    /// Prepare sweeps of kind `sweep` on the square matrix `a`. Fails with
+    /// [`SparseError::ZeroPivot`] when a diagonal entry is zero or not stored.
+    pub fn new(a: &'a CsrMatrix<f64>, sweep: Sweep) -> Result<Self, SparseError> {
+        let n = a.nrows();
+        if a.ncols() != n {
+            return Err(SparseError::DimensionMismatch {
+                expected: n,
+                found: a.ncols(),
+            });
+        }
+
+        let mut inv_diag = Vec::with_capacity(n);
+        for i in 0..n {
+            let d = a.get(i, i);
+            if d == 0.0 {
+                return Err(SparseError::ZeroPivot { index: i });
+            }
+            inv_diag.push(1.0 / d);
+        }
+
+        Ok(Self {
+            a,
+            inv_diag,
+            sweep,
+            omega: 1.0,
+        })
+    }
+
+    /// Relax every update by `omega`, 1 by default. Values above 1 over-relax, which speeds up
+    /// Gauss–Seidel on many problems; values below 1 under-relax, which makes Jacobi a good
+    /// smoother (2/3 is the classical choice for the Laplacian).
+    ///
+    /// # Panics
+    ///
+    /// Panics unless `0 < omega < 2`, outside of which SOR diverges even on an SPD matrix.
+    pub fn with_relaxation(mut self, omega: f64) -> Self {
+        assert!(
+            omega > 0.0 && omega < 2.0,
+            "the relaxation factor {omega} is not between 0 and 2"
+        );
+        for d in &mut self.inv_diag {
+            *d *= omega / self.omega;
+        }
+        self.omega = omega;
+        self
+    }
+
+    /// Return the kind of sweep performed
+    pub fn sweep(&self) -> Sweep {
+        self.sweep
+    }
+
+    /// Return the relaxation factor ω
+    pub fn relaxation(&self) -> f64 {
+        self.omega
+    }
+
+    /// Perform `sweeps` sweeps on `A x = b`, updating `x` in place. Each costs about one
+    /// product with `A`, twice that for [`Sweep::Symmetric`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `b` or `x` doesn't have the order of the matrix.
+    pub fn smooth(&self, b: &[f64], x: &mut [f64], sweeps: usize) {
+        let n = self.inv_diag.len();
+        assert_eq!(b.len(), n, "b has the wrong length");
+        assert_eq!(x.len(), n, "x has the wrong length");
+
+        let mut ax = match self.sweep {
+            Sweep::Jacobi => vec![0.0; n],
+            _ => Vec::new(),
+        };
+        for _ in 0..sweeps {
+            match self.sweep {
+                Sweep::Jacobi => {
+                    self.a.mul_vec_into(x, &mut ax);
+                    for i in 0..n {
+                        x[i] += (b[i] - ax[i]) * self.inv_diag[i];
+                    }
+                }
+                Sweep::Forward => (0..n).for_each(|i| self.relax(i, b, x)),
+                Sweep::Backward => (0..n).rev().for_each(|i| self.relax(i, b, x)),
+                Sweep::Symmetric => {
+                    (0..n).for_each(|i| self.relax(i, b, x));
+                    (0..n).rev().for_each(|i| self.relax(i, b, x));
+                }
+            }
+        }
+    }
+
+    /// Solve row `i` for `x[i]`, the other unknowns held at their current values.
+    fn relax(&self, i: usize, b: &[f64], x: &mut [f64]) {
+        let (cols, values) = self.a.row(i);
+        let ax = f64::gather_dot(values, cols, x);
+        x[i] += (b[i] - ax) * self.inv_diag[i];
+    }
+}
+
+/// Solve `A x = b` with the stationary iteration `sweep`, one sweep per iteration, starting from
+/// `x0` (zero when `None`).
+///
+/// Jacobi and Gauss–Seidel converge when `A` is strictly diagonally dominant, and Gauss–Seidel
+/// also when it is symmetric positive definite. Convergence is linear and slows down as the
+/// matrix grows, so these are mostly useful as smoothers or on small, well-conditioned systems.
+/// Fails with [`SparseError::ZeroPivot`] when a diagonal entry is zero or not stored. An
+/// iteration that diverges stops as soon as the residual stops being finite.
+pub fn stationary(
+    a: &CsrMatrix<f64>,
+    b: &[f64],
+    x0: Option<&[f64]>,
+    sweep: Sweep,
+    opts: &SolverOptions,
+) -> Result<SolveResult, SparseError> {
+    iterate(&Smoother::new(a, sweep)?, b, x0, opts)
+}
+
+/// Solve `A x = b` with the Jacobi iteration. See [`stationary`].
+pub fn jacobi(
+    a: &CsrMatrix<f64>,
+    b: &[f64],
+    x0: Option<&[f64]>,
+    opts: &SolverOptions,
+) -> Result<SolveResult, SparseError> {
+    stationary(a, b, x0, Sweep::Jacobi, opts)
+}
+
+/// Solve `A x = b` with forward Gauss–Seidel. See [`stationary`] for the other orders.
+pub fn gauss_seidel(
+    a: &CsrMatrix<f64>,
+    b: &[f64],
+    x0: Option<&[f64]>,
+    opts: &SolverOptions,
+) -> Result<SolveResult, SparseError> {
+    stationary(a, b, x0, Sweep::Forward, opts)
+}
+
+/// Solve `A x = b` with successive over-relaxation, forward Gauss–Seidel relaxed by `omega`.
+///
+/// SOR converges for a symmetric positive definite `A` whenever `0 < ω < 2`. On the model
+/// Poisson problems the best ω approaches 2 as the grid is refined, and then needs about the
+/// square root of the iterations of Gauss–Seidel. See [`stationary`] for the rest.
+///
+/// # Panics
+///
+/// Panics unless `0 < omega < 2`.
+pub fn sor(
+    a: &CsrMatrix<f64>,
+    b: &[f64],
+    x0: Option<&[f64]>,
+    omega: f64,
+    opts: &SolverOptions,
+) -> Result<SolveResult, SparseError> {
+    let smoother = Smoother::new(a, Sweep::Forward)?.with_relaxation(omega);
+    iterate(&smoother, b, x0, opts)
+}
+
+/// Solve `A x = b` with symmetric SOR: a forward then a backward sweep relaxed by `omega` per
+/// iteration. See [`sor`].
+///
+/// # Panics
+///
+/// Panics unless `0 < omega < 2`.
+pub fn ssor(
+    a: &CsrMatrix<f64>,
+    b: &[f64],
+    x0: Option<&[f64]>,
+    omega: f64,
+    opts: &SolverOptions,
+) -> Result<SolveResult, SparseError> {
+    let smoother = Smoother::new(a, Sweep::Symmetric)?.with_relaxation(omega);
+    iterate(&smoother, b, x0, opts)
+}
+
+/// Run single sweeps of `smoother` until the residual meets `opts`.
+fn iterate(
+    smoother: &Smoother,
+    b: &[f64],
+    x0: Option<&[f64]>,
+    opts: &SolverOptions,
+) -> Result<SolveResult, SparseError> {
+    let a = smoother.a;
+    let mut x = initial_guess(a, b, x0)?;
+    let threshold = opts.tol * norm2(b);
+
+    let mut r = vec![0.0; x.len()];
+    let residual = |x: &[f64], r: &mut [f64]| {
+        a.mul_vec_into(x, r);
+        for (ri, bi) in r.iter_mut().zip(b) {
+            *ri = bi - *ri;
+        }
+        norm2(r)
+    };
+    let mut residual_norm = residual(&x, &mut r);
+    let mut residual_history = vec![residual_norm];
+
+    let mut iterations = 0;
+    while residual_norm > threshold && iterations < opts.max_iter && residual_norm.is_finite() {
+        smoother.smooth(b, &mut x, 1);
+        residual_norm = residual(&x, &mut r);
+        iterations += 1;
+        residual_history.push(residual_norm);
+    }
+
+    Ok(SolveResult {
+        x,
+        iterations,
+        residual_norm,
+        converged: residual_norm <= threshold,
+        residual_history,
+    })
+}
+
+#[test]
+fn test_stationary() {
+    use crate::test_util::assert_close;
+
+    // Strictly diagonally dominant and nonsymmetric.
+    let n = 30;
+    let mut a = CsrMatrix::tridiagonal(-1.0, 4.0, -2.0, n);
+    let expected: Vec<f64> = (0..n).map(|i| (i as f64 * 0.4).cos()).collect();
+    let b = a.mul_vec(&expected);
+    let opts = SolverOptions::default();
+
+    let by_jacobi = jacobi(&a, &b, None, &opts).unwrap();
+    assert!(by_jacobi.converged);
+    assert_close(&by_jacobi.x, &expected, 1e-9);
+    assert_eq!(by_jacobi.residual_history.len(), by_jacobi.iterations + 1);
+
+    let mut iterations = Vec::new();
+    for sweep in [Sweep::Forward, Sweep::Backward, Sweep::Symmetric] {
+        let result = stationary(&a, &b, None, sweep, &opts).unwrap();
+        assert!(result.converged, "{sweep:?}");
+        assert_close(&result.x, &expected, 1e-9);
+        iterations.push(result.iterations);
+    }
+    // Gauss–Seidel uses the fresh values and needs fewer sweeps than Jacobi, and a symmetric
+    // sweep does the work of two.
+    assert!(iterations[0] < by_jacobi.iterations && iterations[1] < by_jacobi.iterations);
+    assert!(iterations[2] < iterations[0].min(iterations[1]));
+    assert_eq!(
+        gauss_seidel(&a, &b, None, &opts).unwrap().iterations,
+        iterations[0]
+    );
+
+    // As a smoother, a few sweeps damp the oscillating part of the error much more than the
+    // smooth part.
+    let poisson = CsrMatrix::poisson2d(16, 16);
+    let smoother = Smoother::new(&poisson, Sweep::Symmetric).unwrap();
+    let zero = vec![0.0; 256];
+    let wave = |k: f64| -> Vec<f64> { (0..256).map(|i| (k * (i % 16) as f64).sin()).collect() };
+    let mut rough = wave(3.0);
+    let mut smooth = wave(0.1);
+    let (rough_norm, smooth_norm) = (norm2(&rough), norm2(&smooth));
+    smoother.smooth(&zero, &mut rough, 3);
+    smoother.smooth(&zero, &mut smooth, 3);
+    assert!(norm2(&rough) / rough_norm < 0.1);
+    assert!(norm2(&smooth) / smooth_norm > 0.5);
+
+    a.set_diagonal(&[0.0; 30]);
+    assert_eq!(
+        jacobi(&a, &b, None, &opts).unwrap_err(),
+        SparseError::ZeroPivot { index: 0 }
+    );
+}
+
+#[test]
+fn test_sor() {
+    use crate::test_util::assert_close;
+
+    let a = CsrMatrix::poisson2d(12, 12);
+    let expected: Vec<f64> = (0..144).map(|i| (i as f64 * 0.1).sin()).collect();
+    let b = a.mul_vec(&expected);
+    let opts = SolverOptions {
+        tol: 1e-8,
+        max_iter: 2000,
+    };
+
+    let plain = gauss_seidel(&a, &b, None, &opts).unwrap();
+    // ω = 2 / (1 + sin(π h)) is optimal for this problem.
+    let omega = 2.0 / (1.0 + (core::f64::consts::PI / 13.0).sin());
+    let over = sor(&a, &b, None, omega, &opts).unwrap();
+    let symmetric = ssor(&a, &b, None, 1.5, &opts).unwrap();
+    for result in [&plain, &over, &symmetric] {
+        assert!(result.converged);
+        assert_close(&result.x, &expected, 1e-6);
+    }
+    assert!(3 * over.iterations < plain.iterations);
+    assert!(symmetric.iterations < plain.iterations);
+
+    // ω = 1 is Gauss–Seidel.
+    assert_eq!(sor(&a, &b, None, 1.0, &opts).unwrap(), plain);
+
+    // Under-relaxed Jacobi still converges, more slowly.
+    let smoother = Smoother::new(&a, Sweep::Jacobi)
+        .unwrap()
+        .with_relaxation(2.0 / 3.0);
+    assert_eq!(smoother.relaxation(), 2.0 / 3.0);
+    let few = SolverOptions {
+        tol: 1e-8,
+        max_iter: 50,
+    };
+    let damped = iterate(&smoother, &b, None, &few).unwrap();
+    let undamped = jacobi(&a, &b, None, &few).unwrap();
+    assert!(undamped.residual_norm < damped.residual_norm);
+    assert!(damped.residual_norm < damped.residual_history[0]);
+}
+
+#[test]
+#[should_panic(expected = "not between 0 and 2")]
+fn test_sor_bad_relaxation() {
+    let a = CsrMatrix::poisson2d(3, 3);
+    let _ = sor(&a, &[1.0; 9], None, 2.0, &SolverOptions::default());
+}
+
+#[test]
+fn test_stationary_bad_diagonals() {
+    let opts = SolverOptions::default();
+    let b = [1.0, 2.0, 3.0];
+
+    // A diagonal entry that isn't stored fails like a stored zero, in every sweep order.
+    let missing =
+        CsrMatrix::from_dense(3, 3, &[4.0, 0.0, 0.0, 0.0, 4.0, 1.0, 0.0, 1.0, 0.0]).unwrap();
+    assert_eq!(missing.get(2, 2), 0.0);
+    for sweep in [
+        Sweep::Jacobi,
+        Sweep::Forward,
+        Sweep::Backward,
+        Sweep::Symmetric,
+    ] {
+        assert_eq!(
+            stationary(&missing, &b, None, sweep, &opts).unwrap_err(),
+            SparseError::ZeroPivot { index: 2 }
+        );
+    }
+    let mut stored = CsrMatrix::identity(3);
+    stored.set_diagonal(&[1.0, -0.0, 1.0]);
+    assert_eq!(
+        Smoother::new(&stored, Sweep::Forward).unwrap_err(),
+        SparseError::ZeroPivot { index: 1 }
+    );
+
+    // A NaN diagonal isn't a zero pivot, but it poisons the residual, even of the zero guess
+    // since NaN times zero is NaN, and the iteration stops instead of running to `max_iter`.
+    let mut nan = CsrMatrix::identity(3);
+    nan.set_diagonal(&[1.0, f64::NAN, 1.0]);
+    for sweep in [Sweep::Jacobi, Sweep::Symmetric] {
+        let result = stationary(&nan, &b, None, sweep, &opts).unwrap();
+        assert!(
+            !result.converged && result.residual_norm.is_nan(),
+            "{sweep:?}"
+        );
+        assert_eq!(result.iterations, 0);
+    }
+
+    // Jacobi diverges on a matrix that isn't diagonally dominant, and stops once the residual
+    // overflows.
+    let bad = CsrMatrix::from_dense(2, 2, &[1.0, 3.0, 3.0, 1.0]).unwrap();
+    let result = jacobi(&bad, &[1.0, 0.0], None, &opts).unwrap();
+    assert!(!result.converged && !result.residual_norm.is_finite());
+    assert!(result.iterations < opts.max_iter);
+}
+
+#[test]
+fn test_smoother_relaxation_composes() {
+    // Changing ω rescales the stored `ω / a_ii`, so the last factor set is the one in effect.
+    let a = CsrMatrix::tridiagonal(-1.0, 3.0, -1.0, 6);
+    let b = [1.0; 6];
+    let direct = Smoother::new(&a, Sweep::Symmetric)
+        .unwrap()
+        .with_relaxation(0.7);
+    let chained = Smoother::new(&a, Sweep::Symmetric)
+        .unwrap()
+        .with_relaxation(1.9)
+        .with_relaxation(0.3)
+        .with_relaxation(0.7);
+    let (mut x, mut y) = (vec![0.0; 6], vec![0.0; 6]);
+    direct.smooth(&b, &mut x, 4);
+    chained.smooth(&b, &mut y, 4);
+    crate::test_util::assert_close(&x, &y, 1e-14);
+
+    // No sweeps leave the guess alone, and an exact guess takes no iterations.
+    let before = x.clone();
+    direct.smooth(&b, &mut x, 0);
+    assert_eq!(x, before);
+    let exact = a.mul_vec(&x);
+    let result = iterate(&direct, &exact, Some(&x), &SolverOptions::default()).unwrap();
+    assert!(result.converged);
+    assert_eq!(result.iterations, 0);
+}
@@ -1,2 +1,126 @@
-pub mod packed_vector;
+//! Sparse vectors and matrices, following *Direct Methods for Sparse Matrices*.
+//!
+//! The most used items are re-exported from [`prelude`]:
+//!
+//! ```
+//! use sparse_matrix::prelude::*;
+//!
+//! let x = PackedVec::gather(&[0.0, 1.0, 0.0, 2.0]);
+//! let y = PackedVec::gather(&[0.0, 3.0, 0.0, 0.0]);
+//! assert_eq!(x * y, 3.0);
+//! ```
+//!
+//! Everything else is in its module: the sparse vector in [`vec`], the storage formats in
+//! [`coo`], [`csr`], [`csc`] and their siblings, the operations combining matrices in [`ops`],
+//! the direct factorizations in [`factor`], the iterative solvers in [`iterative`] and the
+//! devices their products run on in [`backend`], the fill-reducing orderings in [`ordering`],
+//! the file formats in [`io`] and the errors in [`error`].
+//!
+//! Without the default `std` feature the crate is `no_std` and only needs `alloc`: the
+//! containers, their arithmetic and the solvers remain, but not the file formats in [`io`].
 
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "proptest")]
+pub mod arbitrary;
+pub mod argmax;
+pub mod backend;
+pub mod banded;
+pub mod bsr;
+#[cfg(feature = "collection")]
+pub mod collection;
+mod compressed;
+pub mod convert;
+pub mod coo;
+pub mod csc;
+pub mod csr;
+mod dense;
+pub mod dia;
+pub mod diff;
+mod display;
+pub mod dok;
+pub mod eigen;
+pub mod ell;
+pub mod error;
+pub mod factor;
+pub mod hyb;
+pub mod index;
+pub mod interop;
+#[cfg(feature = "std")]
+pub mod io;
+pub mod iterative;
+pub mod lil;
+#[cfg(feature = "mmap")]
+pub mod ooc;
+pub mod operator;
+pub mod ops;
+pub mod ordering;
+#[cfg(feature = "rayon")]
+pub mod parallel;
+pub mod permutation;
+pub mod preconditioner;
+pub mod prelude;
+pub mod qr;
+#[cfg(feature = "rand")]
+pub mod random;
+pub mod scalar;
+pub mod simd;
+pub mod skyline;
+pub mod spy;
+pub mod sym;
+#[cfg(feature = "telemetry")]
+pub mod telemetry;
+#[cfg(test)]
+mod test_util;
+pub mod traits;
+pub mod vec;
+pub mod view;
+
+/// The packed vector used to live here, before the crate was split into modules.
+#[deprecated(note = "use `sparse_matrix::vec::PackedVec` or the prelude instead")]
+pub mod packed_vector {
+    pub use crate::vec::PackedVec;
+}
+
+/// The iterative solvers used to live here, before they moved to [`iterative`].
+#[deprecated(note = "use `sparse_matrix::iterative` or the prelude instead")]
+pub mod solvers {
+    pub use crate::iterative::*;
+}
+
+/// The merge kernels used to live here, before they moved to [`ops::merge`].
+#[deprecated(note = "use `sparse_matrix::ops::merge` instead")]
+pub mod merge {
+    pub use crate::ops::merge::*;
+}
+
+/// The block assembly used to live here, before it moved to [`ops::stack`].
+#[deprecated(note = "use `sparse_matrix::ops::stack` instead")]
+pub mod stack {
+    pub use crate::ops::stack::*;
+}
+
+#[test]
+#[allow(deprecated)]
+fn test_deprecated_paths() {
+    let a = csr::CsrMatrix::<f64>::identity(2);
+    assert_eq!(
+        stack::vstack(&[&a, &a]).unwrap(),
+        ops::vstack(&[&a, &a]).unwrap()
+    );
+    assert_eq!(
+        merge::gallop(&[1, 3, 5], 0, 4),
+        ops::merge::gallop(&[1, 3, 5], 0, 4)
+    );
+    let opts = solvers::SolverOptions::default();
+    let x = solvers::cg::cg(&a, &[1.0, 2.0], None, None, &opts)
+        .unwrap()
+        .x;
+    assert_eq!(x, [1.0, 2.0]);
+    assert_eq!(
+        packed_vector::PackedVec::gather(&[0.0, 1.0]).scatter(),
+        [0.0, 1.0]
+    );
+}
@@ -0,0 +1,507 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::csr::CsrMatrix;
+use crate::error::SparseError;
+use crate::scalar::Scalar;
+#[cfg(feature = "telemetry")]
+use crate::telemetry::{FillStep, FillTrace};
+
+/// A sparse matrix in list of lists (LIL) form: every row is its own vector of
+/// `(column, value)` pairs, sorted by column.
+///
+/// Editing a row touches that row only, so entries can be set and removed, and whole rows
+/// inserted and deleted, without rebuilding the arrays a CSR matrix shares between its rows.
+/// Convert to CSR once the matrix is assembled. No explicit zeros are stored: setting an entry
+/// to zero removes it.
+#[derive(Clone, Debug)]
+pub struct LilMatrix<T = f64> {
+    ncols: usize,
+    rows: Vec<Vec<(usize, T)>>,
+}
+
+impl<T: Scalar> LilMatrix<T> {
+    /// Create a `nrows` by `ncols` matrix with no stored entries.
+    pub fn new(nrows: usize, ncols: usize) -> Self {
+        Self {
+            ncols,
+            rows: vec![Vec::new(); nrows],
+        }
+    }
+
+    /// Copy a CSR matrix into LIL form for editing, leaving out its explicit zeros.
+    pub fn from_csr(a: &CsrMatrix<T>) -> Self {
+        let rows = (0..a.nrows())
+            .map(|i| {
+                let (cols, values) = a.row(i);
+                cols.iter()
+                    .copied()
+                    .zip(values.iter().copied())
+                    .filter(|&(_, v)| v != T::zero())
+                    .collect()
+            })
+            .collect();
+        Self {
+            ncols: a.ncols(),
+            rows,
+        }
+    }
+
+    /// Return the number of rows
+    pub fn nrows(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Return the number of columns
+    pub fn ncols(&self) -> usize {
+        self.ncols
+    }
+
+    /// Return `(nrows, ncols)`
+    pub fn shape(&self) -> (usize, usize) {
+        (self.nrows(), self.ncols)
+    }
+
+    /// Return the number of stored entries. O(nrows).
+    pub fn nnz(&self) -> usize {
+        self.rows.iter().map(Vec::len).sum()
+    }
+
+    /// Return the `(column, value)` pairs of row `i`, sorted by column.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` is out of bounds.
+    pub fn row(&self, i: usize) -> &[(usize, T)] {
+        &self.rows[i]
+    }
+
+    /// Return the entry at `(i, j)`, zero when nothing is stored there. O(log(row length)).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` or `j` is out of bounds.
+    pub fn get(&self, i: usize, j: usize) -> T {
+        self.check_col(j);
+        let row = &self.rows[i];
+        match row.binary_search_by_key(&j, |&(col, _)| col) {
+            Ok(k) => row[k].1,
+            Err(_) => T::zero(),
+        }
+    }
+
+    /// Set the entry at `(i, j)` to `v`, removing it when `v` is zero. O(row length).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` or `j` is out of bounds.
+    pub fn set(&mut self, i: usize, j: usize, v: T) {
+        self.check_col(j);
+        let row = &mut self.rows[i];
+        match (
+            row.binary_search_by_key(&j, |&(col, _)| col),
+            v == T::zero(),
+        ) {
+            (Ok(k), false) => row[k].1 = v,
+            (Ok(k), true) => {
+                row.remove(k);
+            }
+            (Err(k), false) => row.insert(k, (j, v)),
+            (Err(_), true) => {}
+        }
+    }
+
+    /// Replace row `i` by the given `(column, value)` pairs, which may come in any order.
+    /// Values at the same column are summed and zeros are dropped.
+    pub fn set_row(
+        &mut self,
+        i: usize,
+        entries: impl IntoIterator<Item = (usize, T)>,
+    ) -> Result<(), SparseError> {
+        if i >= self.nrows() {
+            return Err(SparseError::IndexOutOfBounds {
+                index: i,
+                len: self.nrows(),
+            });
+        }
+
+        let mut row: Vec<(usize, T)> = entries.into_iter().collect();
+        if let Some(&(j, _)) = row.iter().find(|&&(j, _)| j >= self.ncols) {
+            return Err(SparseError::IndexOutOfBounds {
+                index: j,
+                len: self.ncols,
+            });
+        }
+        row.sort_by_key(|&(j, _)| j);
+        let mut merged: Vec<(usize, T)> = Vec::with_capacity(row.len());
+        for (j, v) in row {
+            match merged.last_mut() {
+                Some((last, sum)) if *last == j => *sum += v,
+                _ => merged.push((j, v)),
+            }
+        }
+        merged.retain(|&(_, v)| v != T::zero());
+
+        self.rows[i] = merged;
+        Ok(())
+    }
+
+    /// Insert an empty row before row `i`, which may be `nrows()` to append one. The rows from
+    /// `i` on move down by one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i > nrows()`.
+    pub fn insert_row(&mut self, i: usize) {
+        self.rows.insert(i, Vec::new());
+    }
+
+    /// Delete row `i` and return its `(column, value)` pairs. The rows after it move up by one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` is out of bounds.
+    pub fn remove_row(&mut self, i: usize) -> Vec<(usize, T)> {
+        self.rows.remove(i)
+    }
+
+    /// Compress to CSR. O(nnz + nrows).
+    pub fn to_csr(&self) -> CsrMatrix<T> {
+        let nnz = self.nnz();
+        let mut indptr = Vec::with_capacity(self.nrows() + 1);
+        let mut indices = Vec::with_capacity(nnz);
+        let mut data = Vec::with_capacity(nnz);
+        indptr.push(0);
+        for row in &self.rows {
+            for &(j, v) in row {
+                indices.push(j);
+                data.push(v);
+            }
+            indptr.push(indices.len());
+        }
+
+        CsrMatrix::from_parts(self.nrows(), self.ncols, indptr, indices, data)
+    }
+
+    /// Factor the square matrix in place as `A = L U` by Gaussian elimination without
+    /// pivoting, leaving the multipliers of the unit lower triangular `L` below the diagonal
+    /// and `U` on and above it.
+    ///
+    /// The rows are eliminated in order: every entry `(i, k)` left of the diagonal, including
+    /// the ones earlier updates of row `i` filled in, subtracts a multiple of the finished row
+    /// `k`. Entries that cancel to zero are removed, as everywhere in this format.
+    ///
+    /// Fails with [`SparseError::DimensionMismatch`] when the matrix is not square, with
+    /// [`SparseError::EmptyRow`] or [`SparseError::EmptyColumn`] before any arithmetic when a
+    /// row or column has no entries, and with [`SparseError::ZeroPivot`] when a diagonal
+    /// entry is zero when its row is used, leaving the matrix partly eliminated.
+    pub fn lu_in_place(&mut self) -> Result<(), SparseError> {
+        self.lu_in_place_with(|_, _, _, _| {})
+    }
+
+    /// Same as [`lu_in_place`](Self::lu_in_place), but also return the fill-in of every step.
+    /// Behind the `telemetry` feature.
+    #[cfg(feature = "telemetry")]
+    pub fn lu_in_place_traced(&mut self) -> Result<FillTrace, SparseError> {
+        let initial_nnz = self.nnz();
+        let positions = (self.nrows() * self.ncols).max(1) as f64;
+        let mut nnz = initial_nnz;
+        let mut steps = Vec::with_capacity(self.nrows());
+        self.lu_in_place_with(|step, updates, fill_in, cancelled| {
+            nnz = nnz + fill_in - cancelled;
+            steps.push(FillStep {
+                step,
+                updates,
+                fill_in,
+                cancelled,
+                nnz,
+                density: nnz as f64 / positions,
+            });
+        })?;
+        Ok(FillTrace {
+            shape: self.shape(),
+            initial_nnz,
+            steps,
+        })
+    }
+
+    /// The elimination of [`lu_in_place`](Self::lu_in_place), calling `record(i, updates,
+    /// fill_in, cancelled)` once row `i` is done.
+    fn lu_in_place_with(
+        &mut self,
+        mut record: impl FnMut(usize, usize, usize, usize),
+    ) -> Result<(), SparseError> {
+        let n = self.nrows();
+        if self.ncols != n {
+            return Err(SparseError::DimensionMismatch {
+                expected: n,
+                found: self.ncols,
+            });
+        }
+        if let Some(index) = self.rows.iter().position(Vec::is_empty) {
+            return Err(SparseError::EmptyRow { index });
+        }
+        let mut col_used = vec![false; n];
+        self.rows
+            .iter()
+            .flatten()
+            .for_each(|&(j, _)| col_used[j] = true);
+        if let Some(index) = col_used.iter().position(|&used| !used) {
+            return Err(SparseError::EmptyColumn { index });
+        }
+
+        for i in 0..n {
+            let (mut updates, mut fill_in, mut cancelled) = (0, 0, 0);
+            let (done, rest) = self.rows.split_at_mut(i);
+            let row = &mut rest[0];
+            let mut p = 0;
+            while let Some(&(k, a_ik)) = row.get(p).filter(|&&(k, _)| k < i) {
+                let pivot_row = &done[k];
+                let start = pivot_row.partition_point(|&(j, _)| j < k);
+                let pivot = match pivot_row.get(start) {
+                    Some(&(j, v)) if j == k && v != T::zero() => v,
+                    _ => return Err(SparseError::ZeroPivot { index: k }),
+                };
+                let l = a_ik / pivot;
+                row[p].1 = l;
+                let (filled, zeroed) = sub_scaled_tail(row, p + 1, l, &pivot_row[start + 1..]);
+                updates += 1;
+                fill_in += filled;
+                cancelled += zeroed;
+                p += 1;
+            }
+            match row.get(p) {
+                Some(&(j, v)) if j == i && v != T::zero() => {}
+                _ => return Err(SparseError::ZeroPivot { index: i }),
+            }
+            record(i, updates, fill_in, cancelled);
+        }
+        Ok(())
+    }
+
+    fn check_col(&self, j: usize) {
+        assert!(
+            j < self.ncols,
+            "column {j} out of bounds for {} columns",
+            self.ncols
+        );
+    }
+}
+
+/// `row[from..] -= l * pivot_tail`, merging the two sorted runs and dropping the entries that
+/// cancel to zero. Return the number of entries created and removed.
+fn sub_scaled_tail<T: Scalar>(
+    row: &mut Vec<(usize, T)>,
+    from: usize,
+    l: T,
+    pivot_tail: &[(usize, T)],
+) -> (usize, usize) {
+    let tail = row.split_off(from);
+    let (mut filled, mut cancelled) = (0, 0);
+    let (mut a, mut b) = (tail.into_iter().peekable(), pivot_tail.iter().peekable());
+    loop {
+        let entry = match (a.peek(), b.peek()) {
+            (Some(&(ja, _)), Some(&&(jb, _))) if ja == jb => {
+                let ((j, va), (_, vb)) = (a.next().unwrap(), *b.next().unwrap());
+                let v = va - l * vb;
+                if v == T::zero() {
+                    cancelled += 1;
+                    continue;
+                }
+                (j, v)
+            }
+            (Some(&(ja, _)), Some(&&(jb, _))) if ja < jb => a.next().unwrap(),
+            (Some(_), None) => a.next().unwrap(),
+            (_, Some(_)) => {
+                let (j, vb) = *b.next().unwrap();
+                let v = T::zero() - l * vb;
+                if v == T::zero() {
+                    continue;
+                }
+                filled += 1;
+                (j, v)
+            }
+            (None, None) => break,
+        };
+        row.push(entry);
+    }
+    (filled, cancelled)
+}
+
+#[test]
+fn test_lil_matrix() {
+    let mut lil = LilMatrix::new(2, 3);
+    lil.set(1, 2, 4.0);
+    lil.set(1, 0, 1.0);
+    lil.set(0, 1, 2.0);
+    lil.set(1, 2, 5.0);
+    assert_eq!(lil.row(1), [(0, 1.0), (2, 5.0)]);
+    assert_eq!(lil.get(1, 2), 5.0);
+    assert_eq!(lil.get(0, 0), 0.0);
+
+    lil.set(0, 1, 0.0);
+    assert_eq!(lil.nnz(), 2);
+
+    lil.insert_row(1);
+    lil.set_row(1, [(2, 1.0), (0, 3.0), (2, 1.0), (1, 0.0)])
+        .unwrap();
+    assert_eq!(lil.row(1), [(0, 3.0), (2, 2.0)]);
+    assert_eq!(lil.shape(), (3, 3));
+
+    #[rustfmt::skip]
+    assert_eq!(lil.to_csr().to_dense(), [
+        0.0, 0.0, 0.0,
+        3.0, 0.0, 2.0,
+        1.0, 0.0, 5.0,
+    ]);
+
+    assert_eq!(lil.remove_row(0), []);
+    let back = LilMatrix::from_csr(&lil.to_csr());
+    assert_eq!(back.shape(), (2, 3));
+    assert_eq!(back.row(1), [(0, 1.0), (2, 5.0)]);
+
+    assert_eq!(
+        lil.set_row(0, [(3, 1.0)]),
+        Err(SparseError::IndexOutOfBounds { index: 3, len: 3 })
+    );
+    assert_eq!(
+        lil.set_row(2, []),
+        Err(SparseError::IndexOutOfBounds { index: 2, len: 2 })
+    );
+}
+
+/// Multiply the unit lower and the upper triangle of an in-place LU factorization back
+/// together, densely.
+#[cfg(test)]
+fn lu_product(lu: &LilMatrix<f64>) -> Vec<f64> {
+    let n = lu.nrows();
+    let dense = lu.to_csr().to_dense();
+    let mut product = vec![0.0; n * n];
+    for i in 0..n {
+        for j in 0..n {
+            product[i * n + j] = (0..=i.min(j))
+                .map(|k| {
+                    let l = if k == i { 1.0 } else { dense[i * n + k] };
+                    l * dense[k * n + j]
+                })
+                .sum();
+        }
+    }
+    product
+}
+
+#[test]
+fn test_lil_lu_in_place() {
+    use crate::test_util::{assert_close, Lcg};
+
+    let mut rng = Lcg::new(71);
+    let n = 12;
+    let mut dense = rng.dense(n, n, 0.2);
+    for i in 0..n {
+        dense[i * n + i] += 4.0;
+    }
+    let mut lu = LilMatrix::from_csr(&CsrMatrix::from_dense(n, n, &dense).unwrap());
+    lu.lu_in_place().unwrap();
+    assert_close(&lu_product(&lu), &dense, 1e-12);
+
+    let mut empty_row = LilMatrix::from_csr(&CsrMatrix::from_diagonal(&[1.0, 0.0, 1.0]));
+    assert_eq!(
+        empty_row.lu_in_place(),
+        Err(SparseError::EmptyRow { index: 1 })
+    );
+    #[rustfmt::skip]
+    let mut empty_col = LilMatrix::from_csr(&CsrMatrix::from_dense(2, 2, &[
+        1.0, 0.0,
+        1.0, 0.0,
+    ]).unwrap());
+    assert_eq!(
+        empty_col.lu_in_place(),
+        Err(SparseError::EmptyColumn { index: 1 })
+    );
+    // Row 1 minus row 0 cancels the pivot of row 1.
+    #[rustfmt::skip]
+    let mut singular = LilMatrix::from_csr(&CsrMatrix::from_dense(2, 2, &[
+        1.0, 1.0,
+        1.0, 1.0,
+    ]).unwrap());
+    assert_eq!(
+        singular.lu_in_place(),
+        Err(SparseError::ZeroPivot { index: 1 })
+    );
+    assert!(matches!(
+        LilMatrix::<f64>::new(2, 3).lu_in_place(),
+        Err(SparseError::DimensionMismatch { .. })
+    ));
+}
+
+#[cfg(feature = "telemetry")]
+#[test]
+fn test_lil_fill_trace() {
+    use crate::test_util::assert_close;
+
+    // An arrow matrix: with the dense row and column first every row fills in completely,
+    // with them last nothing fills in.
+    let n = 6;
+    let arrow = |hub: usize| {
+        let mut lil = LilMatrix::new(n, n);
+        for i in 0..n {
+            lil.set(i, i, 2.0 * n as f64);
+            lil.set(hub, i, 1.0);
+            lil.set(i, hub, 1.0);
+        }
+        lil.set(hub, hub, 2.0 * n as f64);
+        lil
+    };
+
+    let mut first = arrow(0);
+    let dense = first.to_csr().to_dense();
+    let trace = first.lu_in_place_traced().unwrap();
+    assert_close(&lu_product(&first), &dense, 1e-12);
+    assert_eq!(trace.shape, (n, n));
+    assert_eq!(trace.initial_nnz, 3 * n - 2);
+    assert_eq!(trace.final_nnz(), n * n);
+    assert_eq!(trace.final_nnz(), first.nnz());
+    assert_eq!(trace.total_fill_in(), n * n - (3 * n - 2));
+    let mut nnz = trace.initial_nnz;
+    for (i, step) in trace.steps.iter().enumerate() {
+        assert_eq!(step.step, i);
+        assert_eq!(step.nnz, nnz + step.fill_in - step.cancelled);
+        assert_eq!(step.density, step.nnz as f64 / (n * n) as f64);
+        nnz = step.nnz;
+    }
+    // Subtracting the hub row fills in every other row at once, then row i is updated by all
+    // i rows before it.
+    assert_eq!(trace.steps[0].fill_in, 0);
+    assert!(trace.steps[1..].iter().all(|s| s.fill_in == n - 2));
+    assert_eq!(trace.steps[3].updates, 3);
+
+    let mut last = arrow(n - 1);
+    let trace = last.lu_in_place_traced().unwrap();
+    assert_eq!(trace.total_fill_in(), 0);
+    assert_eq!(trace.final_nnz(), trace.initial_nnz);
+
+    // Row 1 minus row 0 cancels entry (1, 2), which leaves the matrix.
+    #[rustfmt::skip]
+    let mut cancelling = LilMatrix::from_csr(&CsrMatrix::from_dense(3, 3, &[
+        1.0, 0.0, 1.0,
+        1.0, 2.0, 1.0,
+        0.0, 0.0, 1.0,
+    ]).unwrap());
+    let trace = cancelling.lu_in_place_traced().unwrap();
+    let step = trace.steps[1];
+    assert_eq!((step.updates, step.fill_in, step.cancelled), (1, 0, 1));
+    assert_eq!(trace.final_nnz(), 5);
+    assert_eq!(cancelling.nnz(), 5);
+
+    let report = trace.to_string();
+    assert!(report.starts_with("fill trace: 3x3, 6 entries before, 5 after, 0 filled in\n"));
+    assert_eq!(report.lines().count(), 2 + 3);
+    #[cfg(feature = "serde")]
+    {
+        let json = serde_json::to_string(&trace).unwrap();
+        assert_eq!(
+            serde_json::from_str::<crate::telemetry::FillTrace>(&json).unwrap(),
+            trace
+        );
+    }
+}
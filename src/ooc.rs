@@ -0,0 +1,434 @@
+//! Out-of-core CSR matrices, memory-mapped from a file, behind the `mmap` feature.
+//!
+//! A matrix too large for RAM, such as the adjacency matrix of a graph with billions of edges,
+//! is written row by row with an [`OocCsrWriter`] and mapped back as an [`OocCsrMatrix`]. Only
+//! the pages the kernel needs are read, and the matrix-vector product streams through the file
+//! in blocks of rows, releasing each block once it is done, so the resident memory stays
+//! bounded by the vectors and a block rather than by the matrix.
+//!
+//! The file is little-endian: a 32-byte header (the magic `SPMCSR\0\x01`, then `nrows`, `ncols`
+//! and `nnz` as u64), the entries row after row as `(column: u64, value: f64)` pairs, and last
+//! the `nrows + 1` row pointers as u64. The row pointers come at the end so that a writer can
+//! stream the entries without knowing their count in advance.
+//!
+//! The mapping assumes nobody else writes to the file while it is open.
+
+use std::fs::File;
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use memmap2::Mmap;
+
+use crate::csr::CsrMatrix;
+use crate::error::SparseError;
+
+const MAGIC: [u8; 8] = *b"SPMCSR\0\x01";
+const HEADER_LEN: usize = 32;
+/// Bytes per stored entry: a u64 column and an f64 value
+const ENTRY_LEN: usize = 16;
+
+/// The number of rows [`OocCsrMatrix::mul_vec_into`] handles per block.
+pub const DEFAULT_BLOCK_ROWS: usize = 1 << 16;
+
+/// Read the little-endian u64 at `offset`.
+fn read_u64(bytes: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap())
+}
+
+/// Decode packed `(column, value)` entries.
+fn entries(bytes: &[u8]) -> impl Iterator<Item = (usize, f64)> + '_ {
+    bytes.chunks_exact(ENTRY_LEN).map(|entry| {
+        let j = read_u64(entry, 0) as usize;
+        let v = f64::from_le_bytes(entry[8..].try_into().unwrap());
+        (j, v)
+    })
+}
+
+/// A read-only CSR matrix of f64 in a memory-mapped file.
+#[derive(Debug)]
+pub struct OocCsrMatrix {
+    map: Mmap,
+    nrows: usize,
+    ncols: usize,
+    nnz: usize,
+}
+
+impl OocCsrMatrix {
+    /// Map the matrix stored at `path`, checking that it is valid: the header and the file size
+    /// agree, the row pointers run from 0 to `nnz` without decreasing, and the columns of each
+    /// row are strictly increasing and less than `ncols`. The check streams once through the
+    /// whole file.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, SparseError> {
+        let matrix = Self::map(path.as_ref())?;
+
+        let mut start = matrix.indptr(0);
+        if start != 0 || matrix.indptr(matrix.nrows) != matrix.nnz {
+            return Err(SparseError::InvalidStructure(format!(
+                "indptr must run from 0 to {}, found {start}..{}",
+                matrix.nnz,
+                matrix.indptr(matrix.nrows)
+            )));
+        }
+        for i in 0..matrix.nrows {
+            let end = matrix.indptr(i + 1);
+            if end < start {
+                return Err(SparseError::InvalidStructure(format!(
+                    "indptr decreases at row {i}"
+                )));
+            }
+            let mut previous = None;
+            for (j, _) in entries(matrix.entry_bytes(start..end)) {
+                if j >= matrix.ncols {
+                    return Err(SparseError::IndexOutOfBounds {
+                        index: j,
+                        len: matrix.ncols,
+                    });
+                }
+                if previous.is_some_and(|p| p >= j) {
+                    return Err(SparseError::InvalidStructure(format!(
+                        "indices of row {i} are not strictly increasing"
+                    )));
+                }
+                previous = Some(j);
+            }
+            start = end;
+        }
+
+        Ok(matrix)
+    }
+
+    /// Write `a` to `path` and map it.
+    pub fn from_csr(path: impl AsRef<Path>, a: &CsrMatrix<f64>) -> Result<Self, SparseError> {
+        let mut writer = OocCsrWriter::create(path, a.ncols())?;
+        for i in 0..a.nrows() {
+            let (cols, values) = a.row(i);
+            writer.push_row(cols, values)?;
+        }
+        writer.finish()
+    }
+
+    /// Map the file and check its header and size, but not the entries.
+    fn map(path: &Path) -> Result<Self, SparseError> {
+        let file = File::open(path)?;
+        // SAFETY: the mapping is read-only, and the module docs require that the file isn't
+        // modified while it is mapped.
+        let map = unsafe { Mmap::map(&file)? };
+        #[cfg(unix)]
+        let _ = map.advise(memmap2::Advice::Sequential);
+
+        if map.len() < HEADER_LEN || map[..8] != MAGIC {
+            return Err(SparseError::InvalidStructure(
+                "not an out-of-core CSR file".into(),
+            ));
+        }
+        let nrows = read_u64(&map, 8) as usize;
+        let ncols = read_u64(&map, 16) as usize;
+        let nnz = read_u64(&map, 24) as usize;
+        let expected = nnz
+            .checked_mul(ENTRY_LEN)
+            .zip(nrows.checked_add(1).and_then(|n| n.checked_mul(8)))
+            .and_then(|(entries, indptr)| entries.checked_add(indptr))
+            .and_then(|body| body.checked_add(HEADER_LEN));
+        if expected != Some(map.len()) {
+            return Err(SparseError::InvalidStructure(format!(
+                "a {nrows} by {ncols} matrix with {nnz} entries doesn't fit a file of {} bytes",
+                map.len()
+            )));
+        }
+
+        Ok(Self {
+            map,
+            nrows,
+            ncols,
+            nnz,
+        })
+    }
+
+    /// Return the number of rows
+    pub fn nrows(&self) -> usize {
+        self.nrows
+    }
+
+    /// Return the number of columns
+    pub fn ncols(&self) -> usize {
+        self.ncols
+    }
+
+    /// Return `(nrows, ncols)`
+    pub fn shape(&self) -> (usize, usize) {
+        (self.nrows, self.ncols)
+    }
+
+    /// Return the number of stored entries
+    pub fn nnz(&self) -> usize {
+        self.nnz
+    }
+
+    /// Iterate over the `(column, value)` pairs of row `i`, in increasing column order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i >= self.nrows()`.
+    pub fn row(&self, i: usize) -> impl Iterator<Item = (usize, f64)> + '_ {
+        assert!(
+            i < self.nrows,
+            "row {i} out of bounds for {} rows",
+            self.nrows
+        );
+        entries(self.entry_bytes(self.indptr(i)..self.indptr(i + 1)))
+    }
+
+    /// Read the rows in `rows` into memory, as a matrix with `rows.len()` rows and all the
+    /// columns. Out-of-core algorithms work through the matrix one such block at a time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `rows` reaches past the last row.
+    pub fn load_rows(&self, rows: Range<usize>) -> CsrMatrix<f64> {
+        assert!(
+            rows.start <= rows.end && rows.end <= self.nrows,
+            "rows {rows:?} out of bounds for {} rows",
+            self.nrows
+        );
+        let first = self.indptr(rows.start);
+        let indptr = (rows.start..=rows.end)
+            .map(|i| self.indptr(i) - first)
+            .collect();
+        let (indices, data) = entries(self.entry_bytes(first..self.indptr(rows.end))).unzip();
+        CsrMatrix::from_parts(rows.len(), self.ncols, indptr, indices, data)
+    }
+
+    /// Multiply the matrix by the dense vector `x`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `x.len() != self.ncols()`.
+    pub fn mul_vec(&self, x: &[f64]) -> Vec<f64> {
+        let mut y = vec![0.0; self.nrows];
+        self.mul_vec_into(x, &mut y);
+        y
+    }
+
+    /// Multiply the matrix by `x`, writing the product into `y`, [`DEFAULT_BLOCK_ROWS`] rows at
+    /// a time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `x.len() != self.ncols()` or `y.len() != self.nrows()`.
+    pub fn mul_vec_into(&self, x: &[f64], y: &mut [f64]) {
+        self.mul_vec_blocked(x, y, DEFAULT_BLOCK_ROWS)
+    }
+
+    /// Multiply the matrix by `x`, writing the product into `y`, streaming through the file
+    /// `block_rows` rows at a time. On Unix the pages of each block are handed back to the
+    /// kernel once the block is done, so the mapped file never stays resident. O(nnz + nrows)
+    /// and one pass over the file.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `x.len() != self.ncols()`, `y.len() != self.nrows()` or `block_rows == 0`.
+    pub fn mul_vec_blocked(&self, x: &[f64], y: &mut [f64], block_rows: usize) {
+        assert_eq!(x.len(), self.ncols, "x has the wrong length");
+        assert_eq!(y.len(), self.nrows, "y has the wrong length");
+        assert!(block_rows > 0, "blocks must hold at least one row");
+
+        for (b, block) in y.chunks_mut(block_rows).enumerate() {
+            let first = b * block_rows;
+            let mut start = self.indptr(first);
+            let block_start = start;
+            for (i, yi) in block.iter_mut().enumerate() {
+                let end = self.indptr(first + i + 1);
+                *yi = entries(self.entry_bytes(start..end))
+                    .map(|(j, v)| v * x[j])
+                    .sum();
+                start = end;
+            }
+            self.release(block_start..start);
+        }
+    }
+
+    /// Tell the kernel that the pages holding entries `range` won't be needed again soon.
+    fn release(&self, range: Range<usize>) {
+        #[cfg(unix)]
+        if !range.is_empty() {
+            // SAFETY: the mapping is read-only and backed by the file, so dropped pages are
+            // read back from it on the next access rather than lost.
+            let _ = unsafe {
+                self.map.unchecked_advise_range(
+                    memmap2::UncheckedAdvice::DontNeed,
+                    HEADER_LEN + range.start * ENTRY_LEN,
+                    range.len() * ENTRY_LEN,
+                )
+            };
+        }
+        #[cfg(not(unix))]
+        let _ = range;
+    }
+
+    /// Row pointer `i`
+    fn indptr(&self, i: usize) -> usize {
+        read_u64(&self.map, HEADER_LEN + self.nnz * ENTRY_LEN + 8 * i) as usize
+    }
+
+    /// The bytes of entries `range`
+    fn entry_bytes(&self, range: Range<usize>) -> &[u8] {
+        &self.map[HEADER_LEN + range.start * ENTRY_LEN..HEADER_LEN + range.end * ENTRY_LEN]
+    }
+}
+
+/// Writes an [`OocCsrMatrix`] file one row at a time, holding only the row pointers in memory.
+///
+/// ```no_run
+/// use sparse_matrix::ooc::OocCsrWriter;
+///
+/// let mut writer = OocCsrWriter::create("graph.csr", 3)?;
+/// writer.push_row(&[1, 2], &[1.0, 1.0])?;
+/// writer.push_row(&[], &[])?;
+/// writer.push_row(&[0], &[1.0])?;
+/// let a = writer.finish()?;
+/// assert_eq!(a.mul_vec(&[1.0, 2.0, 3.0]), [5.0, 0.0, 1.0]);
+/// # Ok::<(), sparse_matrix::error::SparseError>(())
+/// ```
+#[derive(Debug)]
+pub struct OocCsrWriter {
+    path: PathBuf,
+    file: BufWriter<File>,
+    ncols: usize,
+    indptr: Vec<u64>,
+}
+
+impl OocCsrWriter {
+    /// Create the file at `path`, truncating it, for a matrix of `ncols` columns.
+    pub fn create(path: impl AsRef<Path>, ncols: usize) -> Result<Self, SparseError> {
+        let path = path.as_ref().to_path_buf();
+        let mut file = BufWriter::new(File::create(&path)?);
+        // The sizes are filled in by `finish`.
+        file.write_all(&MAGIC)?;
+        file.write_all(&[0; HEADER_LEN - 8])?;
+        Ok(Self {
+            path,
+            file,
+            ncols,
+            indptr: vec![0],
+        })
+    }
+
+    /// Append the next row, given by its strictly increasing columns and their values.
+    pub fn push_row(&mut self, cols: &[usize], values: &[f64]) -> Result<(), SparseError> {
+        if cols.len() != values.len() {
+            return Err(SparseError::DimensionMismatch {
+                expected: cols.len(),
+                found: values.len(),
+            });
+        }
+        if let Some(&j) = cols.iter().find(|&&j| j >= self.ncols) {
+            return Err(SparseError::IndexOutOfBounds {
+                index: j,
+                len: self.ncols,
+            });
+        }
+        if cols.windows(2).any(|w| w[0] >= w[1]) {
+            return Err(SparseError::InvalidStructure(format!(
+                "indices of row {} are not strictly increasing",
+                self.indptr.len() - 1
+            )));
+        }
+
+        for (&j, &v) in cols.iter().zip(values) {
+            self.file.write_all(&(j as u64).to_le_bytes())?;
+            self.file.write_all(&v.to_le_bytes())?;
+        }
+        let nnz = self.indptr.last().unwrap() + cols.len() as u64;
+        self.indptr.push(nnz);
+        Ok(())
+    }
+
+    /// Write the row pointers and the header, and map the finished matrix.
+    pub fn finish(mut self) -> Result<OocCsrMatrix, SparseError> {
+        for p in &self.indptr {
+            self.file.write_all(&p.to_le_bytes())?;
+        }
+        let nrows = self.indptr.len() as u64 - 1;
+        let nnz = *self.indptr.last().unwrap();
+        self.file.seek(SeekFrom::Start(8))?;
+        for size in [nrows, self.ncols as u64, nnz] {
+            self.file.write_all(&size.to_le_bytes())?;
+        }
+        self.file.flush()?;
+        drop(self.file);
+
+        OocCsrMatrix::map(&self.path)
+    }
+}
+
+#[test]
+fn test_ooc_csr_matrix() {
+    use crate::test_util::Lcg;
+
+    let path = std::env::temp_dir().join(format!("sparse-matrix-{}.ooc", std::process::id()));
+    let a = CsrMatrix::poisson2d(7, 5);
+    let ooc = OocCsrMatrix::from_csr(&path, &a).unwrap();
+    assert_eq!(ooc.shape(), (35, 35));
+    assert_eq!(ooc.nnz(), a.nnz());
+    assert_eq!(
+        ooc.row(8).collect::<Vec<_>>(),
+        a.row_view(8).iter().collect::<Vec<_>>()
+    );
+    assert_eq!(ooc.load_rows(10..17), a.slice(10..17, 0..35));
+    assert_eq!(ooc.load_rows(3..3).shape(), (0, 35));
+
+    let mut rng = Lcg::new(5);
+    let x: Vec<f64> = (0..35).map(|_| rng.uniform()).collect();
+    let expected = a.mul_vec(&x);
+    crate::test_util::assert_close(&ooc.mul_vec(&x), &expected, 1e-14);
+    for block_rows in [1, 4, 35, 100] {
+        let mut y = vec![0.0; 35];
+        ooc.mul_vec_blocked(&x, &mut y, block_rows);
+        crate::test_util::assert_close(&y, &expected, 1e-14);
+    }
+
+    // The solvers take it as an operator.
+    let b = vec![1.0; 35];
+    let opts = crate::iterative::SolverOptions::default();
+    let from_file = crate::iterative::cg(&ooc, &b, None, None, &opts).unwrap();
+    let in_memory = crate::iterative::cg(&a, &b, None, None, &opts).unwrap();
+    crate::test_util::assert_close(&from_file.x, &in_memory.x, 1e-10);
+
+    let reopened = OocCsrMatrix::open(&path).unwrap();
+    assert_eq!(reopened.load_rows(0..35), a);
+    // Nothing may stay mapped while the file is rewritten.
+    drop((ooc, reopened));
+
+    // Rows that aren't sorted or reach past the last column are refused.
+    let mut writer = OocCsrWriter::create(&path, 4).unwrap();
+    assert!(matches!(
+        writer.push_row(&[2, 1], &[1.0, 2.0]),
+        Err(SparseError::InvalidStructure(_))
+    ));
+    assert_eq!(
+        writer.push_row(&[4], &[1.0]),
+        Err(SparseError::IndexOutOfBounds { index: 4, len: 4 })
+    );
+    writer.push_row(&[0, 3], &[1.0, 2.0]).unwrap();
+    let small = writer.finish().unwrap();
+    assert_eq!(small.mul_vec(&[1.0; 4]), [3.0]);
+    drop(small);
+
+    // A truncated file is refused.
+    let bytes = std::fs::read(&path).unwrap();
+    std::fs::write(&path, &bytes[..bytes.len() - 1]).unwrap();
+    assert!(matches!(
+        OocCsrMatrix::open(&path),
+        Err(SparseError::InvalidStructure(_))
+    ));
+    // A column past the end is caught by the check on opening.
+    let mut bytes = bytes;
+    bytes[HEADER_LEN] = 9;
+    std::fs::write(&path, &bytes).unwrap();
+    assert_eq!(
+        OocCsrMatrix::open(&path).unwrap_err(),
+        SparseError::IndexOutOfBounds { index: 9, len: 4 }
+    );
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,180 @@
+use alloc::vec::Vec;
+
+use crate::banded::BandedMatrix;
+use crate::bsr::BsrMatrix;
+use crate::coo::CooMatrix;
+use crate::csc::CscMatrix;
+use crate::csr::CsrMatrix;
+use crate::dia::DiaMatrix;
+use crate::dok::DokMatrix;
+use crate::ell::EllMatrix;
+use crate::hyb::HybMatrix;
+use crate::lil::LilMatrix;
+use crate::scalar::Scalar;
+use crate::skyline::SkylineMatrix;
+use crate::sym::SymCsrMatrix;
+use crate::traits::SparseMatrix;
+use crate::view::{ScaledView, SubmatrixView, TransposedView};
+
+/// Anything that can be multiplied with a dense vector.
+///
+/// The iterative solvers only ever touch the matrix through this product, so they work as well
+/// on a sparse matrix as on an operator that is never stored at all. Every f64 matrix format
+/// implements it through [`SparseMatrix`].
+pub trait LinearOperator {
+    /// Return the number of rows, the length of the product
+    fn nrows(&self) -> usize;
+
+    /// Return the number of columns, the length of the operand
+    fn ncols(&self) -> usize;
+
+    /// Compute `y = A x`.
+    fn apply(&self, x: &[f64], y: &mut [f64]);
+
+    /// Return the rows known to have no stored entries, in increasing order.
+    ///
+    /// The solvers check these against the right-hand side before iterating, since no `x` can
+    /// satisfy a row that is empty in `A` but not in `b`. An operator that is not stored knows
+    /// of no empty row, which is the default.
+    fn find_empty_rows(&self) -> Vec<usize> {
+        Vec::new()
+    }
+}
+
+/// A [`LinearOperator`] that can also be multiplied by its transpose, as the least-squares
+/// solvers need.
+pub trait TransposeOperator: LinearOperator {
+    /// Compute `y = Aᵀ x`.
+    fn apply_transpose(&self, x: &[f64], y: &mut [f64]);
+}
+
+impl TransposeOperator for CsrMatrix<f64> {
+    fn apply_transpose(&self, x: &[f64], y: &mut [f64]) {
+        self.mul_vec_transposed_into(x, y)
+    }
+}
+
+impl TransposeOperator for CscMatrix<f64> {
+    /// Entry `j` of `Aᵀ x` is the inner product of column `j` with `x`.
+    fn apply_transpose(&self, x: &[f64], y: &mut [f64]) {
+        assert_eq!(x.len(), self.nrows(), "x has the wrong length");
+        assert_eq!(y.len(), self.ncols(), "y has the wrong length");
+        for (j, yj) in y.iter_mut().enumerate() {
+            let (rows, values) = self.col(j);
+            *yj = f64::gather_dot(values, rows, x);
+        }
+    }
+}
+
+impl TransposeOperator for TransposedView<'_, f64> {
+    fn apply_transpose(&self, x: &[f64], y: &mut [f64]) {
+        self.inner().mul_vec_into(x, y)
+    }
+}
+
+impl TransposeOperator for SubmatrixView<'_, f64> {
+    /// Row `i` of the block is scattered into `y` scaled by `x[i]`.
+    fn apply_transpose(&self, x: &[f64], y: &mut [f64]) {
+        assert_eq!(x.len(), self.nrows(), "x has the wrong length");
+        assert_eq!(y.len(), self.ncols(), "y has the wrong length");
+        let offset = self.col_offset();
+        y.fill(0.0);
+        for (i, &xi) in x.iter().enumerate() {
+            let (row_cols, values) = self.row_parts(i);
+            for (&j, &v) in row_cols.iter().zip(values) {
+                y[j - offset] += v * xi;
+            }
+        }
+    }
+}
+
+impl TransposeOperator for ScaledView<'_, f64> {
+    fn apply_transpose(&self, x: &[f64], y: &mut [f64]) {
+        self.inner().mul_vec_transposed_into(x, y);
+        y.iter_mut().for_each(|yi| *yi *= self.alpha());
+    }
+}
+
+macro_rules! impl_linear_operator {
+    ($($t:ty $({ $($extra:item)* })?),*) => {$(
+        impl LinearOperator for $t {
+            fn nrows(&self) -> usize {
+                SparseMatrix::nrows(self)
+            }
+
+            fn ncols(&self) -> usize {
+                SparseMatrix::ncols(self)
+            }
+
+            fn apply(&self, x: &[f64], y: &mut [f64]) {
+                SparseMatrix::mul_vec_into(self, x, y)
+            }
+
+            $($($extra)*)?
+        }
+    )*};
+}
+
+impl_linear_operator!(
+    BandedMatrix<f64>,
+    BsrMatrix<f64>,
+    CooMatrix<f64>,
+    CscMatrix<f64> {
+        fn find_empty_rows(&self) -> Vec<usize> {
+            CscMatrix::find_empty_rows(self)
+        }
+    },
+    CsrMatrix<f64> {
+        fn find_empty_rows(&self) -> Vec<usize> {
+            CsrMatrix::find_empty_rows(self)
+        }
+    },
+    DiaMatrix<f64>,
+    DokMatrix<f64>,
+    EllMatrix<f64>,
+    HybMatrix<f64>,
+    LilMatrix<f64>,
+    SkylineMatrix<f64>,
+    SymCsrMatrix<f64>,
+    TransposedView<'_, f64>,
+    SubmatrixView<'_, f64>,
+    ScaledView<'_, f64>
+);
+
+#[cfg(feature = "mmap")]
+impl_linear_operator!(crate::ooc::OocCsrMatrix);
+
+#[test]
+fn test_matrix_free_operator() {
+    use crate::iterative::{cg, SolverOptions};
+
+    /// The 1D Laplacian `tridiag(-1, 2, -1)`, never stored.
+    struct Laplacian(usize);
+
+    impl LinearOperator for Laplacian {
+        fn nrows(&self) -> usize {
+            self.0
+        }
+
+        fn ncols(&self) -> usize {
+            self.0
+        }
+
+        fn apply(&self, x: &[f64], y: &mut [f64]) {
+            for (i, yi) in y.iter_mut().enumerate() {
+                let left = if i > 0 { x[i - 1] } else { 0.0 };
+                let right = x.get(i + 1).copied().unwrap_or(0.0);
+                *yi = 2.0 * x[i] - left - right;
+            }
+        }
+    }
+
+    let n = 20;
+    let b = vec![1.0; n];
+    let opts = SolverOptions::default();
+    let free = cg(&Laplacian(n), &b, None, None, &opts).unwrap();
+    let a = CsrMatrix::tridiagonal(-1.0, 2.0, -1.0, n);
+    let stored = cg(&SymCsrMatrix::from_csr(&a).unwrap(), &b, None, None, &opts).unwrap();
+    assert!(free.converged && stored.converged);
+    crate::test_util::assert_close(&free.x, &stored.x, 1e-10);
+}
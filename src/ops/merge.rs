@@ -0,0 +1,345 @@
+//! Merge kernels over sorted index arrays, shared by the products and sums of
+//! [`PackedVec`](crate::vec::PackedVec) and by the row-by-row comparisons of the compressed
+//! formats. They gallop over the side that is behind, so merging a short array with a long one
+//! costs O(short · log(long)) instead of O(short + long). Sparse matrix products don't merge
+//! rows: [`CsrMatrix::matmul`](crate::csr::CsrMatrix::matmul) scatters them into a dense
+//! accumulator instead.
+
+use alloc::collections::BinaryHeap;
+use alloc::vec::Vec;
+use core::cmp::{Ordering, Reverse};
+use core::ops::Range;
+
+/// Merge several streams of `(index, value)` pairs, each sorted by index, into a single stream
+/// sorted by index. Entries sharing an index are all yielded, in the order of the streams they
+/// came from, so callers decide how to combine them.
+///
+/// A binary heap holds the current head index of every stream, which makes a merge of `k`
+/// streams with `n` entries in total cost O(n log k).
+pub fn kmerge<I, T>(streams: impl IntoIterator<Item = I>) -> KMerge<I::IntoIter, T>
+where
+    I: IntoIterator<Item = (usize, T)>,
+{
+    let mut streams: Vec<I::IntoIter> = streams.into_iter().map(|s| s.into_iter()).collect();
+    let mut heads = Vec::with_capacity(streams.len());
+    let mut heap = BinaryHeap::with_capacity(streams.len());
+
+    for (s, stream) in streams.iter_mut().enumerate() {
+        match stream.next() {
+            Some((i, v)) => {
+                heap.push(Reverse((i, s)));
+                heads.push(Some(v));
+            }
+            None => heads.push(None),
+        }
+    }
+
+    KMerge {
+        streams,
+        heads,
+        heap,
+    }
+}
+
+/// Iterator returned by [`kmerge`].
+pub struct KMerge<I, T> {
+    streams: Vec<I>,
+    /// The value belonging to the head index of each stream that is currently in the heap.
+    heads: Vec<Option<T>>,
+    /// Min-heap of (head index, stream number).
+    heap: BinaryHeap<Reverse<(usize, usize)>>,
+}
+
+impl<I, T> Iterator for KMerge<I, T>
+where
+    I: Iterator<Item = (usize, T)>,
+{
+    type Item = (usize, T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let Reverse((i, s)) = self.heap.pop()?;
+        let v = self.heads[s]
+            .take()
+            .expect("stream in heap without a head value");
+
+        if let Some((next_i, next_v)) = self.streams[s].next() {
+            self.heads[s] = Some(next_v);
+            self.heap.push(Reverse((next_i, s)));
+        }
+
+        Some((i, v))
+    }
+}
+
+/// Find the first position `k >= start` with `index[k] >= target` in a sorted index array, or
+/// `index.len()` when there is none.
+///
+/// This is the galloping (exponential) search used by timsort: probe `start + 1`, `start + 3`,
+/// `start + 7`, ... until overshooting `target`, then binary search the last gap. Skipping `d`
+/// entries costs O(log d) instead of the O(d) of stepping one entry at a time.
+pub fn gallop(index: &[usize], start: usize, target: usize) -> usize {
+    if start >= index.len() || index[start] >= target {
+        return start;
+    }
+
+    // Invariant: index[lo] < target.
+    let mut lo = start;
+    let mut step = 1;
+    let hi = loop {
+        let probe = lo + step;
+        if probe >= index.len() {
+            break index.len();
+        }
+        if index[probe] >= target {
+            break probe;
+        }
+        lo = probe;
+        step *= 2;
+    };
+
+    lo + 1 + index[lo + 1..hi].partition_point(|&i| i < target)
+}
+
+/// Call `f(kx, ky)` for every pair of positions with `x_index[kx] == y_index[ky]`, in increasing
+/// index order. Both index arrays must be sorted.
+///
+/// The two-pointer merge gallops over the side that is behind, so intersecting a short array
+/// with a long one costs O(short * log(long)) rather than O(short + long). The pairs visited,
+/// and their order, are exactly those of the plain linear merge.
+pub fn for_each_intersection(
+    x_index: &[usize],
+    y_index: &[usize],
+    mut f: impl FnMut(usize, usize),
+) {
+    let mut kx = 0;
+    let mut ky = 0;
+
+    while kx < x_index.len() && ky < y_index.len() {
+        let ix = x_index[kx];
+        let iy = y_index[ky];
+        match ix.cmp(&iy) {
+            Ordering::Equal => {
+                f(kx, ky);
+                kx += 1;
+                ky += 1;
+            }
+            Ordering::Greater => {
+                ky = gallop(y_index, ky, ix);
+            }
+            Ordering::Less => {
+                kx = gallop(x_index, kx, iy);
+            }
+        }
+    }
+}
+
+/// One step of a merge over the union of two sorted index arrays, see [`all_union`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum UnionStep {
+    /// Positions of a run of `x` whose indices aren't in `y`
+    Left(Range<usize>),
+    /// Positions of a run of `y` whose indices aren't in `x`
+    Right(Range<usize>),
+    /// Positions `(kx, ky)` of an index in both
+    Both(usize, usize),
+}
+
+/// Call `f` with the steps of a merge over the union of two sorted index arrays, in increasing
+/// index order, and return false as soon as `f` does, true otherwise.
+///
+/// A run of one side that has no counterpart on the other is found by galloping and handed to
+/// `f` whole, so the merge itself costs O(short * log(long)) when a short array meets a long one,
+/// and `f` can copy the run with a memcpy. Walking the runs entry by entry visits the indices of
+/// the plain linear merge in the same order, so results are bit-identical to it.
+pub fn all_union(
+    x_index: &[usize],
+    y_index: &[usize],
+    mut f: impl FnMut(UnionStep) -> bool,
+) -> bool {
+    let mut kx = 0;
+    let mut ky = 0;
+
+    while kx < x_index.len() && ky < y_index.len() {
+        let ix = x_index[kx];
+        let iy = y_index[ky];
+        let step = match ix.cmp(&iy) {
+            Ordering::Equal => {
+                kx += 1;
+                ky += 1;
+                UnionStep::Both(kx - 1, ky - 1)
+            }
+            Ordering::Greater => {
+                let end = gallop(y_index, ky, ix);
+                let run = ky..end;
+                ky = end;
+                UnionStep::Right(run)
+            }
+            Ordering::Less => {
+                let end = gallop(x_index, kx, iy);
+                let run = kx..end;
+                kx = end;
+                UnionStep::Left(run)
+            }
+        };
+        if !f(step) {
+            return false;
+        }
+    }
+
+    if kx < x_index.len() {
+        f(UnionStep::Left(kx..x_index.len()))
+    } else if ky < y_index.len() {
+        f(UnionStep::Right(ky..y_index.len()))
+    } else {
+        true
+    }
+}
+
+/// Index arrays that stress the galloping: skewed lengths, alternating entries, runs of
+/// matches between long gaps.
+#[cfg(test)]
+fn adversarial_cases() -> Vec<(Vec<usize>, Vec<usize>)> {
+    let long: Vec<usize> = (0..10_000).map(|i| 3 * i).collect();
+    vec![
+        (vec![], vec![]),
+        (vec![], long.clone()),
+        (vec![0], long.clone()),
+        (vec![29_997], long.clone()),
+        (vec![1, 2, 4, 5], long.clone()),
+        (vec![0, 3, 29_997, 30_000], long.clone()),
+        // Strictly alternating: the galloping never gets to skip anything.
+        (
+            (0..1000).map(|i| 2 * i).collect(),
+            (0..1000).map(|i| 2 * i + 1).collect(),
+        ),
+        // Runs of matches separated by long gaps on both sides.
+        (
+            (0..1000).filter(|i| i % 100 < 10).collect(),
+            (0..1000).filter(|i| i % 100 >= 5 && i % 100 < 15).collect(),
+        ),
+        (long.iter().step_by(97).copied().collect(), long.clone()),
+    ]
+}
+
+#[test]
+fn test_gallop() {
+    let index = [1, 3, 5, 7, 9, 11, 13, 15, 17];
+    for start in 0..=index.len() {
+        for target in 0..20 {
+            let linear = start + index[start..].iter().take_while(|&&i| i < target).count();
+            assert_eq!(gallop(&index, start, target), linear, "{start} {target}");
+        }
+    }
+    assert_eq!(gallop(&[], 0, 4), 0);
+}
+
+#[test]
+fn test_for_each_intersection() {
+    fn linear(x: &[usize], y: &[usize]) -> Vec<(usize, usize)> {
+        let mut pairs = Vec::new();
+        let (mut kx, mut ky) = (0, 0);
+        while kx < x.len() && ky < y.len() {
+            match x[kx].cmp(&y[ky]) {
+                Ordering::Equal => {
+                    pairs.push((kx, ky));
+                    kx += 1;
+                    ky += 1;
+                }
+                Ordering::Greater => ky += 1,
+                Ordering::Less => kx += 1,
+            }
+        }
+        pairs
+    }
+
+    for (x, y) in &adversarial_cases() {
+        for (a, b) in [(x, y), (y, x)] {
+            let mut pairs = Vec::new();
+            for_each_intersection(a, b, |ka, kb| pairs.push((ka, kb)));
+            assert_eq!(pairs, linear(a, b));
+        }
+    }
+}
+
+#[test]
+fn test_all_union() {
+    /// The union as `(index, position in x, position in y)`, by the linear merge.
+    fn linear(x: &[usize], y: &[usize]) -> Vec<(usize, Option<usize>, Option<usize>)> {
+        let mut steps = Vec::new();
+        let (mut kx, mut ky) = (0, 0);
+        while kx < x.len() || ky < y.len() {
+            let ix = x.get(kx).copied().unwrap_or(usize::MAX);
+            let iy = y.get(ky).copied().unwrap_or(usize::MAX);
+            match ix.cmp(&iy) {
+                Ordering::Equal => {
+                    steps.push((ix, Some(kx), Some(ky)));
+                    kx += 1;
+                    ky += 1;
+                }
+                Ordering::Greater => {
+                    steps.push((iy, None, Some(ky)));
+                    ky += 1;
+                }
+                Ordering::Less => {
+                    steps.push((ix, Some(kx), None));
+                    kx += 1;
+                }
+            }
+        }
+        steps
+    }
+
+    for (x, y) in &adversarial_cases() {
+        for (a, b) in [(x, y), (y, x)] {
+            let mut steps = Vec::new();
+            let mut calls = 0;
+            assert!(all_union(a, b, |step| {
+                calls += 1;
+                match step {
+                    UnionStep::Left(run) => steps.extend(run.map(|k| (a[k], Some(k), None))),
+                    UnionStep::Right(run) => steps.extend(run.map(|k| (b[k], None, Some(k)))),
+                    UnionStep::Both(ka, kb) => steps.push((a[ka], Some(ka), Some(kb))),
+                }
+                true
+            }));
+            assert_eq!(steps, linear(a, b));
+            // Runs are passed whole: never more calls than twice the shorter side, plus one.
+            assert!(calls <= 2 * a.len().min(b.len()) + 1);
+        }
+    }
+
+    // Stopping early.
+    let mut calls = 0;
+    assert!(!all_union(&[1, 2, 3], &[0, 2], |_| {
+        calls += 1;
+        calls < 2
+    }));
+    assert_eq!(calls, 2);
+}
+
+#[test]
+fn test_kmerge() {
+    let merged: Vec<_> = kmerge(vec![
+        vec![(0, 'a'), (4, 'b'), (9, 'c')],
+        vec![],
+        vec![(1, 'd'), (4, 'e')],
+        vec![(4, 'f'), (10, 'g')],
+    ])
+    .collect();
+
+    assert_eq!(
+        merged,
+        [
+            (0, 'a'),
+            (1, 'd'),
+            (4, 'b'),
+            (4, 'e'),
+            (4, 'f'),
+            (9, 'c'),
+            (10, 'g')
+        ]
+    );
+
+    let empty: Vec<Vec<(usize, f64)>> = Vec::new();
+    assert_eq!(kmerge(empty).count(), 0);
+}
@@ -0,0 +1,10 @@
+//! Operations that combine sparse matrices and vectors as a whole: the sorted-index merges
+//! behind their sums and products in [`merge`], and the assembly of a matrix from blocks in
+//! [`stack`].
+//!
+//! The arithmetic of a single format is in its own module, as methods of the type.
+
+pub mod merge;
+pub mod stack;
+
+pub use stack::{bmat, hstack, vstack};
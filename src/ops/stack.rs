@@ -0,0 +1,169 @@
+//! Assembling CSR matrices from blocks, in the manner of SciPy's `vstack`, `hstack` and `bmat`:
+//! saddle-point and KKT systems such as
+//!
+//! ```text
+//! [ H  Aᵀ ]
+//! [ A  0  ]
+//! ```
+//!
+//! are built from their blocks without going through triplets.
+
+use alloc::format;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::csr::CsrMatrix;
+use crate::error::SparseError;
+use crate::scalar::Scalar;
+
+/// Stack matrices with the same number of columns on top of each other. O(nnz + nrows).
+///
+/// Fails with [`SparseError::DimensionMismatch`] when the numbers of columns differ and with
+/// [`SparseError::InvalidStructure`] when `blocks` is empty.
+pub fn vstack<T: Scalar>(blocks: &[&CsrMatrix<T>]) -> Result<CsrMatrix<T>, SparseError> {
+    let grid: Vec<[Option<&CsrMatrix<T>>; 1]> = blocks.iter().map(|&b| [Some(b)]).collect();
+    let rows: Vec<&[Option<&CsrMatrix<T>>]> = grid.iter().map(|row| &row[..]).collect();
+    bmat(&rows)
+}
+
+/// Put matrices with the same number of rows side by side. O(nnz + nrows × blocks).
+///
+/// Fails with [`SparseError::DimensionMismatch`] when the numbers of rows differ and with
+/// [`SparseError::InvalidStructure`] when `blocks` is empty.
+pub fn hstack<T: Scalar>(blocks: &[&CsrMatrix<T>]) -> Result<CsrMatrix<T>, SparseError> {
+    let row: Vec<Option<&CsrMatrix<T>>> = blocks.iter().map(|&b| Some(b)).collect();
+    bmat(&[&row])
+}
+
+/// Build a matrix from a grid of blocks, `None` standing for a zero block.
+///
+/// All the blocks in a grid row must have the same number of rows, and all the blocks in a grid
+/// column the same number of columns, else [`SparseError::DimensionMismatch`]. Every grid row
+/// and column needs at least one block to fix its size, and the rows of the grid must have the
+/// same length, else [`SparseError::InvalidStructure`]. O(nnz + nrows × grid columns).
+pub fn bmat<T: Scalar>(grid: &[&[Option<&CsrMatrix<T>>]]) -> Result<CsrMatrix<T>, SparseError> {
+    let grid_cols = grid.first().map_or(0, |row| row.len());
+    if grid_cols == 0 {
+        return Err(SparseError::InvalidStructure(
+            "the block grid is empty".to_string(),
+        ));
+    }
+    if let Some(row) = grid.iter().find(|row| row.len() != grid_cols) {
+        return Err(SparseError::InvalidStructure(format!(
+            "block grid rows have {grid_cols} and {} blocks",
+            row.len()
+        )));
+    }
+
+    let mut heights = vec![None; grid.len()];
+    let mut widths = vec![None; grid_cols];
+    for (bi, row) in grid.iter().enumerate() {
+        for (bj, block) in row.iter().enumerate() {
+            let Some(block) = block else { continue };
+            for (size, found) in [
+                (&mut heights[bi], block.nrows()),
+                (&mut widths[bj], block.ncols()),
+            ] {
+                match *size {
+                    Some(expected) if expected != found => {
+                        return Err(SparseError::DimensionMismatch { expected, found });
+                    }
+                    _ => *size = Some(found),
+                }
+            }
+        }
+    }
+    let heights = sizes(heights, "row")?;
+    let widths = sizes(widths, "column")?;
+
+    let mut col_offsets = Vec::with_capacity(grid_cols);
+    let mut ncols = 0;
+    for &w in &widths {
+        col_offsets.push(ncols);
+        ncols += w;
+    }
+    let nrows = heights.iter().sum();
+    let nnz = grid
+        .iter()
+        .flat_map(|row| row.iter().flatten())
+        .map(|b| b.nnz())
+        .sum();
+
+    let mut indptr = Vec::with_capacity(nrows + 1);
+    let mut indices = Vec::with_capacity(nnz);
+    let mut data = Vec::with_capacity(nnz);
+    indptr.push(0);
+    for (row, &height) in grid.iter().zip(&heights) {
+        for i in 0..height {
+            for (block, &offset) in row.iter().zip(&col_offsets) {
+                if let Some(block) = block {
+                    let (cols, values) = block.row(i);
+                    indices.extend(cols.iter().map(|&j| j + offset));
+                    data.extend_from_slice(values);
+                }
+            }
+            indptr.push(indices.len());
+        }
+    }
+
+    Ok(CsrMatrix::from_parts(nrows, ncols, indptr, indices, data))
+}
+
+/// Unwrap the sizes of the grid rows or columns, failing on one without any block.
+fn sizes(sizes: Vec<Option<usize>>, what: &str) -> Result<Vec<usize>, SparseError> {
+    sizes
+        .into_iter()
+        .enumerate()
+        .map(|(k, size)| {
+            size.ok_or_else(|| {
+                SparseError::InvalidStructure(format!("block {what} {k} has no block to size it"))
+            })
+        })
+        .collect()
+}
+
+#[test]
+fn test_stack() {
+    let a = CsrMatrix::from_dense(2, 2, &[1.0, 2.0, 0.0, 3.0]).unwrap();
+    let b = CsrMatrix::from_dense(1, 2, &[4.0, 0.0]).unwrap();
+    let c = CsrMatrix::from_dense(2, 1, &[5.0, 6.0]).unwrap();
+
+    #[rustfmt::skip]
+    assert_eq!(vstack(&[&a, &b]).unwrap().to_dense(), [
+        1.0, 2.0,
+        0.0, 3.0,
+        4.0, 0.0,
+    ]);
+    #[rustfmt::skip]
+    assert_eq!(hstack(&[&a, &c]).unwrap().to_dense(), [
+        1.0, 2.0, 5.0,
+        0.0, 3.0, 6.0,
+    ]);
+
+    // A KKT matrix [H Bᵀ; B 0].
+    let bt = b.transpose();
+    let kkt = bmat(&[&[Some(&a), Some(&bt)], &[Some(&b), None]]).unwrap();
+    #[rustfmt::skip]
+    assert_eq!(kkt.to_dense(), [
+        1.0, 2.0, 4.0,
+        0.0, 3.0, 0.0,
+        4.0, 0.0, 0.0,
+    ]);
+
+    assert_eq!(
+        vstack(&[&a, &c]).unwrap_err(),
+        SparseError::DimensionMismatch {
+            expected: 2,
+            found: 1
+        }
+    );
+    assert!(matches!(
+        bmat(&[&[Some(&a), None], &[None, None]]),
+        Err(SparseError::InvalidStructure(_))
+    ));
+    assert!(matches!(
+        hstack::<f64>(&[]),
+        Err(SparseError::InvalidStructure(_))
+    ));
+}
@@ -0,0 +1,514 @@
+//! Orderings of the rows and columns of a sparse matrix, returned as a [`Permutation`] to apply
+//! symmetrically before a factorization or a run of SpMVs.
+
+use alloc::collections::{BinaryHeap, VecDeque};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Reverse;
+
+use crate::csc::CscMatrix;
+use crate::csr::CsrMatrix;
+use crate::dense::sqrt;
+use crate::error::SparseError;
+use crate::permutation::Permutation;
+use crate::scalar::Scalar;
+
+/// Compute the reverse Cuthill–McKee ordering of a square matrix, which gathers the entries
+/// close to the diagonal and so reduces the bandwidth and profile of `P A Pᵀ`.
+///
+/// The graph is the pattern of `A + Aᵀ`, so an unsymmetric pattern is symmetrized first. Every
+/// connected component is numbered by a breadth-first search from a pseudo-peripheral node,
+/// visiting the neighbours of each node by increasing degree, and the whole order is reversed
+/// at the end. O(nnz log(max degree) + n) apart from the search for the starting nodes.
+///
+/// Apply the result with [`Permutation::permute_symmetric`].
+pub fn rcm<T: Scalar>(a: &CsrMatrix<T>) -> Result<Permutation, SparseError> {
+    let n = a.nrows();
+    if a.ncols() != n {
+        return Err(SparseError::DimensionMismatch {
+            expected: n,
+            found: a.ncols(),
+        });
+    }
+
+    let adjacency = symmetric_adjacency(a);
+    let degree = |v: usize| adjacency[v].len();
+
+    let mut visited = vec![false; n];
+    let mut order = Vec::with_capacity(n);
+    let mut queue = VecDeque::new();
+    let mut neighbours = Vec::new();
+    while order.len() < n {
+        let seed = (0..n)
+            .filter(|&v| !visited[v])
+            .min_by_key(|&v| degree(v))
+            .expect("an unvisited node is left");
+        let start = pseudo_peripheral(&adjacency, seed);
+
+        visited[start] = true;
+        queue.push_back(start);
+        while let Some(v) = queue.pop_front() {
+            order.push(v);
+            neighbours.clear();
+            neighbours.extend(adjacency[v].iter().copied().filter(|&w| !visited[w]));
+            neighbours.sort_by_key(|&w| degree(w));
+            for &w in &neighbours {
+                visited[w] = true;
+                queue.push_back(w);
+            }
+        }
+    }
+
+    order.reverse();
+    Permutation::new(order)
+}
+
+/// Compute an approximate minimum degree ordering of a square matrix, which reduces the fill-in
+/// of a Cholesky or LU factorization of `P A Pᵀ` far more than a bandwidth-reducing ordering
+/// does on anything but a narrow band.
+///
+/// The graph is the pattern of `A + Aᵀ`. The elimination is simulated on the quotient graph:
+/// eliminating a node turns it and the elements around it into one new element, the clique of
+/// its remaining neighbours, instead of adding the clique's edges, so the graph never takes
+/// more storage than `A`. The next node is the one of smallest approximate degree, the upper
+/// bound on its degree that AMD uses: its direct neighbours plus the sizes of its elements,
+/// which can overlap. Supervariables and aggressive absorption are left out. Nodes with more
+/// than `max(16, 10 √n)` neighbours are dense and ordered last, as AMD does.
+///
+/// Apply the result with [`Permutation::permute_symmetric`].
+pub fn amd<T: Scalar>(a: &CsrMatrix<T>) -> Result<Permutation, SparseError> {
+    let n = a.nrows();
+    if a.ncols() != n {
+        return Err(SparseError::DimensionMismatch {
+            expected: n,
+            found: a.ncols(),
+        });
+    }
+
+    let adjacency = symmetric_adjacency(a);
+    let threshold = dense_threshold(n);
+    let deferred: Vec<bool> = adjacency.iter().map(|nb| nb.len() > threshold).collect();
+    Permutation::new(minimum_degree(adjacency, Vec::new(), &deferred))
+}
+
+/// Compute a column approximate minimum degree ordering of a matrix, which reduces the fill-in
+/// of the LU factorization of `A Q` whatever rows the partial pivoting picks later.
+///
+/// The fill of `L` and `U` is bounded by that of the Cholesky factor of `Qᵀ AᵀA Q`, so the
+/// columns are ordered by minimum degree on the pattern of `AᵀA`, without forming it: every row
+/// of `A` is taken as an element from the start, the clique of the columns it touches, in the
+/// bipartite row and column structure of `A`. Eliminating a column merges the rows around it
+/// into one new row and frees their storage, as in [`amd`], which shares this minimum degree.
+/// Rows with more than `max(16, 10 √n)` entries are left out, since one of them would merge
+/// all its columns at once while pivoting can usually avoid its fill, and columns with more
+/// than `max(16, 10 √m)` entries are ordered last.
+///
+/// Apply the result with [`LuOptions::ordering`](crate::factor::LuOptions::ordering), or as the
+/// column permutation of [`Permutation::permute_cols`].
+pub fn colamd<T: Scalar>(a: &CscMatrix<T>) -> Permutation {
+    let (m, n) = a.shape();
+    let mut rows = vec![Vec::new(); m];
+    for j in 0..n {
+        for &i in a.col(j).0 {
+            rows[i].push(j);
+        }
+    }
+    let dense_row = dense_threshold(n);
+    rows.retain(|columns| columns.len() <= dense_row);
+    let dense_col = dense_threshold(m);
+    let deferred: Vec<bool> = (0..n).map(|j| a.col(j).0.len() > dense_col).collect();
+
+    let order = minimum_degree(vec![Vec::new(); n], rows, &deferred);
+    Permutation::new(order).expect("every column is ordered once")
+}
+
+/// Return the neighbours of every node in the graph of `A + Aᵀ`, without self loops.
+fn symmetric_adjacency<T: Scalar>(a: &CsrMatrix<T>) -> Vec<Vec<usize>> {
+    let mut adjacency = vec![Vec::new(); a.nrows()];
+    for i in 0..a.nrows() {
+        for &j in a.row(i).0 {
+            if i != j {
+                adjacency[i].push(j);
+                adjacency[j].push(i);
+            }
+        }
+    }
+    for neighbours in &mut adjacency {
+        neighbours.sort_unstable();
+        neighbours.dedup();
+    }
+    adjacency
+}
+
+/// Find a node of (nearly) maximal eccentricity in the component of `start`, with the heuristic
+/// of George and Liu: jump to a node of smallest degree on the last level of the breadth-first
+/// level structure, as long as that makes the structure deeper.
+fn pseudo_peripheral(adjacency: &[Vec<usize>], start: usize) -> usize {
+    let mut node = start;
+    let (mut depth, mut last_level) = level_structure(adjacency, node);
+    loop {
+        let candidate = *last_level
+            .iter()
+            .min_by_key(|&&v| adjacency[v].len())
+            .expect("the last level is never empty");
+        let (candidate_depth, candidate_level) = level_structure(adjacency, candidate);
+        if candidate_depth <= depth {
+            return node;
+        }
+        node = candidate;
+        depth = candidate_depth;
+        last_level = candidate_level;
+    }
+}
+
+/// Run a breadth-first search from `root`, returning the number of levels and the nodes of the
+/// last one.
+fn level_structure(adjacency: &[Vec<usize>], root: usize) -> (usize, Vec<usize>) {
+    let mut level = vec![usize::MAX; adjacency.len()];
+    level[root] = 0;
+    let mut queue = VecDeque::from([root]);
+    let mut last = Vec::new();
+    let mut depth = 0;
+    while let Some(v) = queue.pop_front() {
+        if level[v] > depth || last.is_empty() {
+            depth = level[v];
+            last.clear();
+        }
+        last.push(v);
+        for &w in &adjacency[v] {
+            if level[w] == usize::MAX {
+                level[w] = level[v] + 1;
+                queue.push_back(w);
+            }
+        }
+    }
+    (depth + 1, last)
+}
+
+/// Marks a variable or an element no elimination has visited yet.
+const NOT_MARKED: usize = usize::MAX;
+
+/// Return the number of neighbours beyond which a node is dense and left out of a minimum degree
+/// ordering of `n` nodes.
+fn dense_threshold(n: usize) -> usize {
+    16.max((10.0 * sqrt(n as f64)) as usize)
+}
+
+/// Return the elimination order of a greedy minimum degree ordering on the quotient graph whose
+/// variables are `0..adjacency.len()`, with the edges `adjacency` between variables and the
+/// cliques `elements` to begin with. The `deferred` variables are left out of the graph and
+/// ordered last.
+///
+/// Eliminating the variable `p` forms a new element of the variables adjacent to `p` directly
+/// or through an element, absorbs the elements of `p` into it and frees their storage, and
+/// drops the edges the new element covers. The degree of every variable of the new element is
+/// then bounded from above as AMD does, by its direct neighbours, the new element, and the part
+/// of each of its other elements outside the new one, and the smallest bound is picked next
+/// from a heap whose stale entries are skipped when they surface.
+fn minimum_degree(
+    mut adjacency: Vec<Vec<usize>>,
+    mut elements: Vec<Vec<usize>>,
+    deferred: &[bool],
+) -> Vec<usize> {
+    let n = adjacency.len();
+    let mut var_elements = vec![Vec::new(); n];
+    for list in adjacency.iter_mut().chain(elements.iter_mut()) {
+        list.retain(|&i| !deferred[i]);
+    }
+    for (e, members) in elements.iter().enumerate() {
+        for &i in members {
+            var_elements[i].push(e);
+        }
+    }
+    let mut absorbed = vec![false; elements.len()];
+    // outside[e] == |Le \ Lp| when outside_mark[e] == p, during the elimination of p
+    let mut outside = vec![0; elements.len()];
+    let mut outside_mark = vec![NOT_MARKED; elements.len()];
+    let mut eliminated = deferred.to_vec();
+    let mut remaining = eliminated.iter().filter(|&&done| !done).count();
+
+    // The elements of a variable overlap at the start, where the sum of their sizes is all
+    // there is to go by.
+    let mut degree = vec![0; n];
+    let mut heap = BinaryHeap::new();
+    for i in (0..n).filter(|&i| !deferred[i]) {
+        let through_elements: usize = var_elements[i].iter().map(|&e| elements[e].len() - 1).sum();
+        degree[i] = (adjacency[i].len() + through_elements).min(remaining - 1);
+        heap.push(Reverse((degree[i], i)));
+    }
+
+    // mark[i] == p once i joined the element of p
+    let mut mark = vec![NOT_MARKED; n];
+    let mut order = Vec::with_capacity(n);
+    while let Some(Reverse((d, p))) = heap.pop() {
+        if eliminated[p] || d != degree[p] {
+            continue;
+        }
+        eliminated[p] = true;
+        remaining -= 1;
+        order.push(p);
+
+        let mut members = Vec::new();
+        mark[p] = p;
+        for i in core::mem::take(&mut adjacency[p]) {
+            if !eliminated[i] && mark[i] != p {
+                mark[i] = p;
+                members.push(i);
+            }
+        }
+        for e in core::mem::take(&mut var_elements[p]) {
+            if absorbed[e] {
+                continue;
+            }
+            absorbed[e] = true;
+            for i in core::mem::take(&mut elements[e]) {
+                if !eliminated[i] && mark[i] != p {
+                    mark[i] = p;
+                    members.push(i);
+                }
+            }
+        }
+
+        let id = elements.len();
+        for &i in &members {
+            adjacency[i].retain(|&j| !eliminated[j] && mark[j] != p);
+            var_elements[i].retain(|&e| !absorbed[e]);
+            var_elements[i].push(id);
+        }
+        elements.push(members);
+        absorbed.push(false);
+        outside.push(0);
+        outside_mark.push(NOT_MARKED);
+
+        // |Le \ Lp| for every other element e around the new one, from |Le| minus one for each
+        // of its variables met in Lp.
+        for &i in &elements[id] {
+            for &e in &var_elements[i] {
+                if outside_mark[e] != p {
+                    outside_mark[e] = p;
+                    outside[e] = elements[e].len();
+                }
+                outside[e] -= 1;
+            }
+        }
+        let size = elements[id].len();
+        for &i in &elements[id] {
+            let through_elements: usize = (var_elements[i].iter())
+                .filter(|&&e| e != id)
+                .map(|&e| outside[e])
+                .sum();
+            degree[i] = (adjacency[i].len() + size - 1 + through_elements).min(remaining - 1);
+            heap.push(Reverse((degree[i], i)));
+        }
+    }
+
+    order.extend((0..n).filter(|&i| deferred[i]));
+    order
+}
+
+#[cfg(test)]
+fn bandwidth<T: Scalar>(a: &CsrMatrix<T>) -> usize {
+    let (lower, upper) = a.bandwidth();
+    lower.max(upper)
+}
+
+/// Return the 5-point Laplacian on an `m` by `m` grid, with the grid points numbered at random.
+#[cfg(test)]
+fn shuffled_laplacian(m: usize, rng: &mut crate::test_util::Lcg) -> CsrMatrix<f64> {
+    use crate::coo::CooMatrix;
+
+    let n = m * m;
+    let mut label: Vec<usize> = (0..n).collect();
+    for k in (1..n).rev() {
+        label.swap(k, rng.below(k + 1));
+    }
+
+    let mut coo = CooMatrix::new(n, n);
+    for r in 0..m {
+        for c in 0..m {
+            let v = label[r * m + c];
+            coo.push(v, v, 4.0);
+            if r > 0 {
+                coo.push(v, label[(r - 1) * m + c], -1.0);
+            }
+            if r + 1 < m {
+                coo.push(v, label[(r + 1) * m + c], -1.0);
+            }
+            if c > 0 {
+                coo.push(v, label[r * m + c - 1], -1.0);
+            }
+            if c + 1 < m {
+                coo.push(v, label[r * m + c + 1], -1.0);
+            }
+        }
+    }
+    coo.to_csr()
+}
+
+#[test]
+fn test_rcm() {
+    use crate::test_util::Lcg;
+
+    let m = 12;
+    let n = m * m;
+    let mut rng = Lcg::new(7);
+    let a = shuffled_laplacian(m, &mut rng);
+
+    let p = rcm(&a).unwrap();
+    let b = p.permute_symmetric(&a);
+    let (before, after) = (bandwidth(&a), bandwidth(&b));
+    assert!(after < before, "RCM bandwidth: {before} -> {after}");
+    assert!(before > 100, "{before}");
+    assert!(after <= 2 * m, "{after}");
+    assert_eq!(b.nnz(), a.nnz());
+
+    // P A Pᵀ (P x) = P (A x)
+    let x: Vec<f64> = (0..n).map(|_| rng.uniform()).collect();
+    crate::test_util::assert_close(
+        &b.mul_vec(&p.apply_to_vec(&x)),
+        &p.apply_to_vec(&a.mul_vec(&x)),
+        1e-12,
+    );
+}
+
+#[test]
+fn test_rcm_components() {
+    // Two disconnected paths, 0 - 3 - 1 and 2 - 4, plus the isolated node 5. The pattern is
+    // given as one triangle only, so it has to be symmetrized.
+    #[rustfmt::skip]
+    let dense = [
+        1.0, 0.0, 0.0, 1.0, 0.0, 0.0,
+        0.0, 1.0, 0.0, 0.0, 0.0, 0.0,
+        0.0, 0.0, 1.0, 0.0, 1.0, 0.0,
+        0.0, 1.0, 0.0, 1.0, 0.0, 0.0,
+        0.0, 0.0, 0.0, 0.0, 1.0, 0.0,
+        0.0, 0.0, 0.0, 0.0, 0.0, 1.0,
+    ];
+    let a = CsrMatrix::from_dense(6, 6, &dense).unwrap();
+    let p = rcm(&a).unwrap();
+    assert_eq!(p.len(), 6);
+    assert_eq!(bandwidth(&p.permute_symmetric(&a)), 1);
+
+    let rectangular = CsrMatrix::<f64>::new(2, 3);
+    assert_eq!(
+        rcm(&rectangular),
+        Err(SparseError::DimensionMismatch {
+            expected: 2,
+            found: 3
+        })
+    );
+}
+
+#[test]
+fn test_amd() {
+    use crate::factor::CholeskyFactorization;
+    use crate::test_util::Lcg;
+
+    let fill = |a: &CsrMatrix<f64>, p: &Permutation| {
+        let b = p.permute_symmetric(a).to_csc();
+        CholeskyFactorization::new(&b).unwrap().nnz()
+    };
+
+    let m = 16;
+    let mut rng = Lcg::new(11);
+    let a = shuffled_laplacian(m, &mut rng);
+    let p = amd(&a).unwrap();
+    assert_eq!(p.len(), m * m);
+    let natural = fill(&a, &Permutation::identity(m * m));
+    let banded = fill(&a, &rcm(&a).unwrap());
+    let minimum_degree = fill(&a, &p);
+    assert!(
+        minimum_degree < banded && banded < natural,
+        "fill: natural {natural}, RCM {banded}, AMD {minimum_degree}"
+    );
+
+    // An arrow: the hub is dense, goes last, and nothing fills in.
+    let n = 300;
+    let mut coo = crate::coo::CooMatrix::new(n, n);
+    for i in 0..n {
+        coo.push(i, i, if i == 0 { n as f64 } else { 4.0 });
+        if i > 0 {
+            coo.push(0, i, 1.0);
+            coo.push(i, 0, 1.0);
+        }
+    }
+    let arrow = coo.to_csr();
+    let p = amd(&arrow).unwrap();
+    assert_eq!(p.forward()[n - 1], 0);
+    assert_eq!(fill(&arrow, &p), 2 * n - 1);
+
+    assert_eq!(
+        amd(&CsrMatrix::<f64>::new(2, 3)),
+        Err(SparseError::DimensionMismatch {
+            expected: 2,
+            found: 3
+        })
+    );
+    assert_eq!(amd(&CsrMatrix::<f64>::new(0, 0)).unwrap().len(), 0);
+}
+
+#[test]
+fn test_colamd() {
+    use crate::factor::{ColumnOrdering, LuFactorization, LuOptions};
+    use crate::test_util::{assert_close, Lcg};
+
+    // Convection-diffusion on a 20 x 20 grid: the 5-point Laplacian plus an upwinded
+    // convection along both axes, which makes the matrix unsymmetric.
+    let m = 20;
+    let n = m * m;
+    let mut dense = CsrMatrix::poisson2d(m, m).to_dense();
+    for i in 0..n {
+        dense[i * n + i] += 1.5;
+        if i % m > 0 {
+            dense[i * n + i - 1] -= 1.0;
+        }
+        if i >= m {
+            dense[i * n + i - m] -= 0.5;
+        }
+    }
+    let a = CscMatrix::from_dense(n, n, &dense).unwrap();
+
+    let q = colamd(&a);
+    let mut seen = q.forward().to_vec();
+    seen.sort_unstable();
+    assert!(seen.iter().copied().eq(0..n));
+
+    let natural = LuFactorization::new(&a, &LuOptions::default()).unwrap();
+    let opts = LuOptions {
+        ordering: ColumnOrdering::Colamd,
+        ..LuOptions::default()
+    };
+    let ordered = LuFactorization::new(&a, &opts).unwrap();
+    assert_eq!(ordered.col_permutation(), &q);
+    assert!(
+        4 * ordered.nnz() < 3 * natural.nnz(),
+        "nnz(L) + nnz(U): natural {}, COLAMD {}",
+        natural.nnz(),
+        ordered.nnz()
+    );
+
+    let mut rng = Lcg::new(113);
+    let x_true: Vec<f64> = (0..n).map(|_| rng.uniform()).collect();
+    let b = crate::test_util::dense_mul_vec(n, n, &dense, &x_true);
+    assert_close(&ordered.solve(&b), &x_true, 1e-10);
+
+    // A dense row is left out and a dense column goes last.
+    let mut coo = crate::coo::CooMatrix::new(n, n);
+    for i in 0..n {
+        coo.push(i, i, 4.0);
+        coo.push(0, i, 1.0);
+        if i > 0 {
+            coo.push(i, n - 1, 1.0);
+            coo.push(i, i - 1, -1.0);
+        }
+    }
+    let arrow = coo.to_csc();
+    let q = colamd(&arrow);
+    assert_eq!(q.forward()[n - 1], n - 1);
+    let f = LuFactorization::new(&arrow, &opts).unwrap();
+    assert_eq!(f.col_permutation(), &q);
+
+    // Rectangular matrices are ordered too.
+    let wide = CscMatrix::from_dense(2, 3, &[1.0, 0.0, 1.0, 0.0, 1.0, 1.0]).unwrap();
+    assert_eq!(colamd(&wide).len(), 3);
+}
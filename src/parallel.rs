@@ -0,0 +1,273 @@
+//! Multithreaded CSR kernels on the rayon thread pool, behind the `rayon` feature.
+//!
+//! The rows are cut into a few chunks per thread holding about the same amount of work, rather
+//! than the same number of rows, so that a handful of dense rows doesn't leave all but one
+//! thread idle. The work of a row is its number of entries plus one for SpMV, and the number
+//! of products it takes plus one for SpGEMM. The results are identical to the sequential
+//! kernels, summation order included.
+
+use std::ops::Range;
+
+use rayon::prelude::*;
+
+use crate::csr::{CsrMatrix, PackedVecRef};
+use crate::error::SparseError;
+use crate::scalar::Scalar;
+
+/// Chunks per thread, so that rayon can still even out chunks that turn out slower.
+const CHUNKS_PER_THREAD: usize = 4;
+
+impl<T: Scalar + Send + Sync> CsrMatrix<T> {
+    /// Iterate over views of the rows on the rayon thread pool, for the per-row analyses the
+    /// crate doesn't provide. The iterator knows its length and splits anywhere, so
+    /// `with_min_len` and `zip` work as on a range; unlike the kernels below it does not balance
+    /// the rows by their entries.
+    pub fn par_rows(&self) -> impl IndexedParallelIterator<Item = PackedVecRef<'_, T>> {
+        (0..self.nrows())
+            .into_par_iter()
+            .map(move |i| self.row_view(i))
+    }
+
+    /// Return `f(i, row i)` for every row, computed on the rayon thread pool and collected in
+    /// row order.
+    pub fn par_row_map<R: Send>(
+        &self,
+        f: impl Fn(usize, PackedVecRef<'_, T>) -> R + Sync,
+    ) -> Vec<R> {
+        let f = &f;
+        self.par_rows()
+            .map(move |row| f(row.index(), row))
+            .collect()
+    }
+
+    /// Multiply the matrix by the dense vector `x` on the rayon thread pool. Same result as
+    /// [`CsrMatrix::mul_vec`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `x.len() != self.ncols()`.
+    pub fn par_mul_vec(&self, x: &[T]) -> Vec<T> {
+        let mut y = vec![T::zero(); self.nrows()];
+        self.par_mul_vec_into(x, &mut y);
+        y
+    }
+
+    /// Multiply the matrix by the dense vector `x` on the rayon thread pool, writing the
+    /// product into `y`. Same result as [`CsrMatrix::mul_vec_into`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `x.len() != self.ncols()` or `y.len() != self.nrows()`.
+    pub fn par_mul_vec_into(&self, x: &[T], y: &mut [T]) {
+        assert_eq!(x.len(), self.ncols(), "x has the wrong length");
+        assert_eq!(y.len(), self.nrows(), "y has the wrong length");
+
+        let indptr = self.indptr();
+        let chunks = balanced_chunks(self.nrows(), |i| indptr[i] + i);
+        let mut rest = y;
+        let mut parts = Vec::with_capacity(chunks.len());
+        for rows in chunks {
+            let (part, tail) = rest.split_at_mut(rows.len());
+            parts.push((rows, part));
+            rest = tail;
+        }
+        parts.into_par_iter().for_each(|(rows, part)| {
+            for (i, yi) in rows.zip(part) {
+                *yi = self.row_view(i).dot(x);
+            }
+        });
+    }
+
+    /// Multiply two sparse matrices on the rayon thread pool, `self * rhs`. Same result as
+    /// [`CsrMatrix::matmul`].
+    ///
+    /// Every chunk of rows runs Gustavson's algorithm on its own, with a sparse accumulator of
+    /// `rhs.ncols()` entries per rayon job, and the chunks are concatenated in order.
+    pub fn par_matmul(&self, rhs: &CsrMatrix<T>) -> Result<CsrMatrix<T>, SparseError> {
+        if self.ncols() != rhs.nrows() {
+            return Err(SparseError::DimensionMismatch {
+                expected: self.ncols(),
+                found: rhs.nrows(),
+            });
+        }
+
+        // flops[i] counts the products of the rows before i, plus one per row.
+        let mut flops = Vec::with_capacity(self.nrows() + 1);
+        flops.push(0);
+        for (i, row) in self.row_iter().enumerate() {
+            let products: usize = row.indices().iter().map(|&k| rhs.row(k).0.len()).sum();
+            flops.push(flops[i] + products + 1);
+        }
+        let chunks = balanced_chunks(self.nrows(), |i| flops[i]);
+
+        let n = rhs.ncols();
+        let products: Vec<_> = chunks
+            .into_par_iter()
+            .map_init(
+                || (vec![usize::MAX; n], vec![T::zero(); n]),
+                |(mark, acc), rows| matmul_rows(self, rhs, rows, mark, acc),
+            )
+            .collect();
+
+        let nnz = products.iter().map(|(_, indices, _)| indices.len()).sum();
+        let mut indptr = Vec::with_capacity(self.nrows() + 1);
+        let mut indices = Vec::with_capacity(nnz);
+        let mut data = Vec::with_capacity(nnz);
+        indptr.push(0);
+        for (row_ends, chunk_indices, chunk_data) in products {
+            let offset = indices.len();
+            indptr.extend(row_ends.iter().map(|&end| offset + end));
+            indices.extend(chunk_indices);
+            data.extend(chunk_data);
+        }
+
+        Ok(CsrMatrix::from_parts(
+            self.nrows(),
+            n,
+            indptr,
+            indices,
+            data,
+        ))
+    }
+}
+
+/// Gustavson's algorithm for the rows `rows` of `a * b`, returning the end of every row within
+/// the chunk, the column indices and the values. `mark[j] == i` flags column j as present in
+/// row i; row numbers are unique across chunks, so `mark` never needs resetting.
+fn matmul_rows<T: Scalar>(
+    a: &CsrMatrix<T>,
+    b: &CsrMatrix<T>,
+    rows: Range<usize>,
+    mark: &mut [usize],
+    acc: &mut [T],
+) -> (Vec<usize>, Vec<usize>, Vec<T>) {
+    let mut row_ends = Vec::with_capacity(rows.len());
+    let mut indices = Vec::new();
+    let mut data = Vec::new();
+    for i in rows {
+        let row_start = indices.len();
+        for (k, a_ik) in a.row_view(i).iter() {
+            let (cols, values) = b.row(k);
+            for (&j, &b_kj) in cols.iter().zip(values) {
+                if mark[j] != i {
+                    mark[j] = i;
+                    acc[j] = T::zero();
+                    indices.push(j);
+                }
+                acc[j] += a_ik * b_kj;
+            }
+        }
+        indices[row_start..].sort_unstable();
+        data.extend(indices[row_start..].iter().map(|&j| acc[j]));
+        row_ends.push(indices.len());
+    }
+    (row_ends, indices, data)
+}
+
+/// Cut the rows `0..nrows` into consecutive ranges of about the same work, a few per thread.
+/// `work(i)` is the work of the rows before `i`: nondecreasing, with `work(0) == 0`.
+fn balanced_chunks(nrows: usize, work: impl Fn(usize) -> usize) -> Vec<Range<usize>> {
+    let parts = rayon::current_num_threads() * CHUNKS_PER_THREAD;
+    let total = work(nrows);
+    let mut chunks = Vec::with_capacity(parts);
+    let mut start = 0;
+    for p in 1..=parts {
+        let target = (total as u128 * p as u128 / parts as u128) as usize;
+        // The first row boundary holding at least `target` work.
+        let (mut lo, mut hi) = (start, nrows);
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if work(mid) < target {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+        if lo > start {
+            chunks.push(start..lo);
+            start = lo;
+        }
+    }
+    chunks
+}
+
+#[test]
+fn test_parallel_kernels() {
+    use crate::test_util::Lcg;
+
+    let mut rng = Lcg::new(7);
+    // A few dense rows among sparse ones, and an empty matrix.
+    let (nrows, ncols) = (300, 200);
+    let mut dense = rng.dense(nrows, ncols, 0.02);
+    for i in [3, 150, 151] {
+        for v in &mut dense[i * ncols..(i + 1) * ncols] {
+            *v = rng.uniform();
+        }
+    }
+    let a = CsrMatrix::from_dense(nrows, ncols, &dense).unwrap();
+    let b = CsrMatrix::from_dense(ncols, 50, &rng.dense(ncols, 50, 0.05)).unwrap();
+    let x: Vec<f64> = (0..ncols).map(|_| rng.uniform()).collect();
+
+    assert_eq!(a.par_mul_vec(&x), a.mul_vec(&x));
+    let c = a.par_matmul(&b).unwrap();
+    let expected = a.matmul(&b).unwrap();
+    assert_eq!(c.indptr(), expected.indptr());
+    assert_eq!(c.indices(), expected.indices());
+    assert_eq!(c.data(), expected.data());
+    assert!(matches!(
+        b.par_matmul(&b),
+        Err(SparseError::DimensionMismatch { .. })
+    ));
+
+    let empty = CsrMatrix::<f64>::new(0, 3);
+    assert!(empty.par_mul_vec(&[1.0, 2.0, 3.0]).is_empty());
+    assert_eq!(
+        empty.par_matmul(&a.slice(0..3, 0..4)).unwrap().shape(),
+        (0, 4)
+    );
+
+    // The chunks cover the rows in order, and the two rows holding half the work each are
+    // split apart.
+    let work = [0, 1, 2, 3, 503, 504, 505, 1006];
+    let chunks = balanced_chunks(7, |i| work[i]);
+    assert_eq!(chunks.first().unwrap().start, 0);
+    assert_eq!(chunks.last().unwrap().end, 7);
+    assert!(chunks.windows(2).all(|w| w[0].end == w[1].start));
+    assert!(!chunks.iter().any(|c| c.contains(&3) && c.contains(&6)));
+}
+
+#[test]
+fn test_par_rows() {
+    use crate::dense::sqrt;
+    use crate::test_util::Lcg;
+
+    let norm = |_: usize, row: PackedVecRef<'_, f64>| -> f64 {
+        sqrt(row.data().iter().map(|v| v * v).sum())
+    };
+
+    let mut rng = Lcg::new(13);
+    let (nrows, ncols) = (500, 80);
+    let a = CsrMatrix::from_dense(nrows, ncols, &rng.dense(nrows, ncols, 0.1)).unwrap();
+    let serial: Vec<f64> = a.row_iter().map(|row| norm(row.index(), row)).collect();
+    assert_eq!(a.par_row_map(norm), serial);
+    assert_eq!(a.par_rows().len(), nrows);
+    let chunked: Vec<f64> = (a.par_rows().with_min_len(64))
+        .map(|row| norm(row.index(), row))
+        .collect();
+    assert_eq!(chunked, serial);
+    // The rows arrive with their own index, whichever thread takes them.
+    assert!(a.par_rows().enumerate().all(|(i, row)| row.index() == i));
+
+    // Nothing to split, and a single row that can't be split.
+    let empty = CsrMatrix::<f64>::new(0, 4);
+    assert_eq!(empty.par_rows().len(), 0);
+    assert!(empty.par_row_map(norm).is_empty());
+    for single in [a.slice(7..8, 0..ncols), CsrMatrix::<f64>::new(1, 5)] {
+        let serial: Vec<f64> = single
+            .row_iter()
+            .map(|row| norm(row.index(), row))
+            .collect();
+        assert_eq!(single.par_rows().with_min_len(4).len(), 1);
+        assert_eq!(single.par_row_map(norm), serial);
+    }
+}
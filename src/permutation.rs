@@ -0,0 +1,248 @@
+//! Permutations of rows, columns and vector components, the building block of fill-reducing
+//! orderings and pivoted factorizations.
+
+use alloc::format;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::csr::CsrMatrix;
+use crate::error::SparseError;
+use crate::scalar::Scalar;
+
+/// A permutation of `0..n`, stored both ways round so that it can be applied and inverted in
+/// O(n).
+///
+/// The convention is the one of gathering: applying the permutation to `x` gives `y` with
+/// `y[i] = x[forward[i]]`, so `forward[i]` is the old position of the entry that ends up at `i`,
+/// and `inverse[j]` is the new position of the entry that was at `j`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Permutation {
+    forward: Vec<usize>,
+    inverse: Vec<usize>,
+}
+
+impl Permutation {
+    /// Build a permutation from its forward array, checking that every index in
+    /// `0..forward.len()` appears exactly once.
+    pub fn new(forward: Vec<usize>) -> Result<Self, SparseError> {
+        let n = forward.len();
+        let mut inverse = vec![usize::MAX; n];
+        for (i, &j) in forward.iter().enumerate() {
+            if j >= n {
+                return Err(SparseError::IndexOutOfBounds { index: j, len: n });
+            }
+            if inverse[j] != usize::MAX {
+                return Err(SparseError::InvalidStructure(format!(
+                    "{j} appears twice in the permutation"
+                )));
+            }
+            inverse[j] = i;
+        }
+
+        Ok(Self { forward, inverse })
+    }
+
+    /// Return the permutation of `0..n` that leaves everything in place.
+    pub fn identity(n: usize) -> Self {
+        Self {
+            forward: (0..n).collect(),
+            inverse: (0..n).collect(),
+        }
+    }
+
+    /// Return the number of permuted positions
+    pub fn len(&self) -> usize {
+        self.forward.len()
+    }
+
+    /// Return true if the permutation is of the empty set
+    pub fn is_empty(&self) -> bool {
+        self.forward.is_empty()
+    }
+
+    /// Return the forward array: `forward()[i]` is the old position of the entry moved to `i`.
+    pub fn forward(&self) -> &[usize] {
+        &self.forward
+    }
+
+    /// Return the inverse array: `inverse_indices()[j]` is the new position of the entry that
+    /// was at `j`.
+    pub fn inverse_indices(&self) -> &[usize] {
+        &self.inverse
+    }
+
+    /// Return the permutation that undoes this one.
+    pub fn inverse(&self) -> Permutation {
+        Self {
+            forward: self.inverse.clone(),
+            inverse: self.forward.clone(),
+        }
+    }
+
+    /// Return the permutation that applies `other` first and then `self`, so that
+    /// `p.compose(&q).apply_to_vec(x) == p.apply_to_vec(&q.apply_to_vec(x))`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the two permutations have different lengths.
+    pub fn compose(&self, other: &Permutation) -> Permutation {
+        assert_eq!(
+            self.len(),
+            other.len(),
+            "permutations have different lengths"
+        );
+        let forward: Vec<usize> = self.forward.iter().map(|&i| other.forward[i]).collect();
+        let mut inverse = vec![0; forward.len()];
+        for (i, &j) in forward.iter().enumerate() {
+            inverse[j] = i;
+        }
+
+        Self { forward, inverse }
+    }
+
+    /// Return `y` with `y[i] = x[forward[i]]`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `x` doesn't have the length of the permutation.
+    pub fn apply_to_vec<T: Copy>(&self, x: &[T]) -> Vec<T> {
+        assert_eq!(
+            x.len(),
+            self.len(),
+            "vector length must match the permutation"
+        );
+        self.forward.iter().map(|&j| x[j]).collect()
+    }
+
+    /// Return `P A`: row `i` of the result is row `forward[i]` of `a`. O(nnz + nrows).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `a` doesn't have as many rows as the permutation has positions.
+    pub fn permute_rows<T: Scalar>(&self, a: &CsrMatrix<T>) -> CsrMatrix<T> {
+        assert_eq!(
+            a.nrows(),
+            self.len(),
+            "row count must match the permutation"
+        );
+
+        let mut indptr = Vec::with_capacity(a.nrows() + 1);
+        let mut indices = Vec::with_capacity(a.nnz());
+        let mut data = Vec::with_capacity(a.nnz());
+        indptr.push(0);
+        for &old in &self.forward {
+            let (cols, values) = a.row(old);
+            indices.extend_from_slice(cols);
+            data.extend_from_slice(values);
+            indptr.push(indices.len());
+        }
+
+        CsrMatrix::from_parts(a.nrows(), a.ncols(), indptr, indices, data)
+    }
+
+    /// Return `A Pᵀ`: column `j` of the result is column `forward[j]` of `a`. Every row is
+    /// relabelled and sorted again. O(nnz log(row length) + nrows).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `a` doesn't have as many columns as the permutation has positions.
+    pub fn permute_cols<T: Scalar>(&self, a: &CsrMatrix<T>) -> CsrMatrix<T> {
+        assert_eq!(
+            a.ncols(),
+            self.len(),
+            "column count must match the permutation"
+        );
+
+        let mut indices = Vec::with_capacity(a.nnz());
+        let mut data = Vec::with_capacity(a.nnz());
+        let mut row: Vec<(usize, T)> = Vec::new();
+        for i in 0..a.nrows() {
+            let (cols, values) = a.row(i);
+            row.clear();
+            row.extend(
+                cols.iter()
+                    .map(|&j| self.inverse[j])
+                    .zip(values.iter().copied()),
+            );
+            row.sort_unstable_by_key(|&(j, _)| j);
+            for &(j, v) in &row {
+                indices.push(j);
+                data.push(v);
+            }
+        }
+
+        CsrMatrix::from_parts(a.nrows(), a.ncols(), a.indptr().to_vec(), indices, data)
+    }
+
+    /// Return `P A Pᵀ`, renumbering the rows and columns of a square matrix alike, as an
+    /// ordering such as [`crate::ordering::rcm`] is meant to be applied.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `a` isn't square with as many rows as the permutation has positions.
+    pub fn permute_symmetric<T: Scalar>(&self, a: &CsrMatrix<T>) -> CsrMatrix<T> {
+        self.permute_cols(&self.permute_rows(a))
+    }
+}
+
+#[test]
+fn test_permutation() {
+    let p = Permutation::new(vec![2, 0, 3, 1]).unwrap();
+    assert_eq!(p.inverse_indices(), [1, 3, 0, 2]);
+    assert_eq!(p.apply_to_vec(&['a', 'b', 'c', 'd']), ['c', 'a', 'd', 'b']);
+    assert_eq!(
+        p.inverse().apply_to_vec(&p.apply_to_vec(&[1, 2, 3, 4])),
+        [1, 2, 3, 4]
+    );
+    assert_eq!(p.compose(&p.inverse()), Permutation::identity(4));
+
+    let q = Permutation::new(vec![1, 2, 3, 0]).unwrap();
+    let x = [10, 20, 30, 40];
+    assert_eq!(
+        p.compose(&q).apply_to_vec(&x),
+        p.apply_to_vec(&q.apply_to_vec(&x))
+    );
+
+    assert_eq!(
+        Permutation::new(vec![0, 2, 0]),
+        Err(SparseError::InvalidStructure(
+            "0 appears twice in the permutation".to_string()
+        ))
+    );
+    assert_eq!(
+        Permutation::new(vec![0, 3, 1]),
+        Err(SparseError::IndexOutOfBounds { index: 3, len: 3 })
+    );
+}
+
+#[test]
+fn test_permutation_matrices() {
+    #[rustfmt::skip]
+    let dense = [
+        1.0, 0.0, 2.0,
+        0.0, 3.0, 0.0,
+        4.0, 5.0, 6.0,
+    ];
+    let a = CsrMatrix::from_dense(3, 3, &dense).unwrap();
+    let p = Permutation::new(vec![2, 0, 1]).unwrap();
+
+    #[rustfmt::skip]
+    assert_eq!(p.permute_rows(&a).to_dense(), [
+        4.0, 5.0, 6.0,
+        1.0, 0.0, 2.0,
+        0.0, 3.0, 0.0,
+    ]);
+
+    let permuted = p.permute_cols(&a);
+    #[rustfmt::skip]
+    assert_eq!(permuted.to_dense(), [
+        2.0, 1.0, 0.0,
+        0.0, 0.0, 3.0,
+        6.0, 4.0, 5.0,
+    ]);
+    assert_eq!(permuted.indices(), [0, 1, 2, 0, 1, 2]);
+
+    // (A Pᵀ)(P x) = A x
+    let x = [1.0, 2.0, 3.0];
+    assert_eq!(permuted.mul_vec(&p.apply_to_vec(&x)), a.mul_vec(&x));
+}
@@ -0,0 +1,109 @@
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use super::Preconditioner;
+
+/// A block-diagonal preconditioner built from one preconditioner per block of unknowns.
+///
+/// Block `k` covers the next `size` unknowns after the blocks before it, and its preconditioner
+/// sees only those: `M = diag(M₁, M₂, …)`. Coupled problems whose unknowns fall into fields
+/// (velocity and pressure, several species) are preconditioned this way, each field with what
+/// suits it, ILU on one and Jacobi on another for instance.
+///
+/// ```
+/// use sparse_matrix::preconditioner::{BlockDiagonal, Identity, Jacobi, Preconditioner};
+///
+/// let m = BlockDiagonal::new()
+///     .block(2, Jacobi::from_diagonal(&[2.0, 4.0])?)
+///     .block(1, Identity);
+/// let mut z = [0.0; 3];
+/// m.apply(&[1.0, 1.0, 1.0], &mut z);
+/// assert_eq!(z, [0.5, 0.25, 1.0]);
+/// # Ok::<(), sparse_matrix::error::SparseError>(())
+/// ```
+pub struct BlockDiagonal<'a> {
+    /// Start of each block, plus the total size at the end
+    offsets: Vec<usize>,
+    blocks: Vec<Box<dyn Preconditioner + 'a>>,
+}
+
+impl Default for BlockDiagonal<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> BlockDiagonal<'a> {
+    /// Create a preconditioner with no block, of size 0.
+    pub fn new() -> Self {
+        Self {
+            offsets: vec![0],
+            blocks: Vec::new(),
+        }
+    }
+
+    /// Append a block of `size` unknowns preconditioned by `precond`.
+    pub fn block(mut self, size: usize, precond: impl Preconditioner + 'a) -> Self {
+        self.offsets.push(self.len() + size);
+        self.blocks.push(Box::new(precond));
+        self
+    }
+
+    /// Return the number of unknowns, the sum of the block sizes
+    pub fn len(&self) -> usize {
+        self.offsets[self.blocks.len()]
+    }
+
+    /// Return true if no block has any unknown
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Preconditioner for BlockDiagonal<'_> {
+    fn apply(&self, r: &[f64], z: &mut [f64]) {
+        assert_eq!(r.len(), self.len(), "r has the wrong length");
+        assert_eq!(z.len(), self.len(), "z has the wrong length");
+        for (k, block) in self.blocks.iter().enumerate() {
+            let range = self.offsets[k]..self.offsets[k + 1];
+            block.apply(&r[range.clone()], &mut z[range]);
+        }
+    }
+}
+
+#[test]
+fn test_block_diagonal() {
+    use super::{Identity, Ilu0, Jacobi};
+    use crate::csr::CsrMatrix;
+    use crate::iterative::{gmres, SolverOptions};
+    use crate::ops::stack::bmat;
+    use crate::test_util::assert_close;
+
+    // Two uncoupled fields: a nonsymmetric convection-diffusion block and a badly scaled
+    // diagonal one.
+    let first = CsrMatrix::tridiagonal(-1.5, 2.0, -0.5, 40);
+    let diag: Vec<f64> = (0..10).map(|i| 10f64.powi(i - 5)).collect();
+    let second = CsrMatrix::from_diagonal(&diag);
+    let a = bmat(&[&[Some(&first), None], &[None, Some(&second)]]).unwrap();
+    let b = vec![1.0; 50];
+
+    let ilu = Ilu0::new(&first).unwrap();
+    let m = BlockDiagonal::new()
+        .block(40, &ilu)
+        .block(10, Jacobi::new(&second).unwrap());
+    assert_eq!(m.len(), 50);
+
+    // Both blocks are solved exactly, so one step suffices.
+    let opts = SolverOptions::default();
+    let result = gmres(&a, &b, None, Some(&m), 30, &opts).unwrap();
+    assert!(result.converged);
+    assert!(result.iterations <= 2);
+    assert_close(&a.mul_vec(&result.x), &b, 1e-8);
+
+    let identity = BlockDiagonal::new().block(2, Identity).block(0, Identity);
+    let mut z = [0.0; 2];
+    identity.apply(&[3.0, 4.0], &mut z);
+    assert_eq!(z, [3.0, 4.0]);
+    assert!(BlockDiagonal::new().is_empty());
+}
@@ -0,0 +1,247 @@
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+
+use super::Preconditioner;
+use crate::csr::CsrMatrix;
+use crate::dense::sqrt;
+use crate::error::SparseError;
+
+/// The first diagonal shift tried when the factorization breaks down, relative to the diagonal.
+const INITIAL_SHIFT: f64 = 1e-3;
+
+/// Give up after this many doublings of the shift.
+const MAX_SHIFTS: usize = 40;
+
+/// Incomplete Cholesky factorization with zero fill-in, IC(0).
+///
+/// The Cholesky factorization `A = L Lᵀ` is run on the pattern of the lower triangle of `A`
+/// only, dropping every update outside of it, so `L` takes no more storage than that triangle.
+/// `M = L Lᵀ` is symmetric positive definite, which makes IC(0) the usual preconditioner for
+/// CG, and for a matrix whose factorization creates no fill it is the exact Cholesky factor.
+///
+/// Dropping fill can make a pivot negative even though `A` is SPD. The factorization is then
+/// restarted on `A + α diag(A)`, with α doubling from 10⁻³ until every pivot is positive
+/// (Manteuffel's shifted incomplete Cholesky); [`Ic0::shift`] reports the α used.
+#[derive(Clone, Debug)]
+pub struct Ic0 {
+    /// The lower triangle `L`, diagonal last in every row
+    l: CsrMatrix<f64>,
+    shift: f64,
+}
+
+impl Ic0 {
+    /// Factor a symmetric positive definite matrix, reading only its lower triangle. Fails
+    /// with [`SparseError::ZeroPivot`] when a diagonal entry is not positive or is NaN, since
+    /// no shift can help then, or at the row where the last shift failed when none works, which
+    /// only entries that aren't finite cause.
+    pub fn new(a: &CsrMatrix<f64>) -> Result<Self, SparseError> {
+        let n = a.nrows();
+        if a.ncols() != n {
+            return Err(SparseError::DimensionMismatch {
+                expected: n,
+                found: a.ncols(),
+            });
+        }
+        let not_positive = |d: f64| d <= 0.0 || d.is_nan();
+        if let Some(i) = (0..n).find(|&i| not_positive(a.get(i, i))) {
+            return Err(SparseError::ZeroPivot { index: i });
+        }
+
+        let mut shift = 0.0;
+        let mut failed = 0;
+        for _ in 0..=MAX_SHIFTS {
+            match factor(a, shift) {
+                Ok(l) => return Ok(Self { l, shift }),
+                Err(i) => failed = i,
+            }
+            shift = if shift == 0.0 {
+                INITIAL_SHIFT
+            } else {
+                2.0 * shift
+            };
+        }
+        Err(SparseError::ZeroPivot { index: failed })
+    }
+
+    /// Return the α of the factored `A + α diag(A)`, 0 when `A` itself could be factored
+    pub fn shift(&self) -> f64 {
+        self.shift
+    }
+
+    /// Return the factor `L`
+    pub fn factor(&self) -> &CsrMatrix<f64> {
+        &self.l
+    }
+}
+
+/// Factor the lower triangle of `A + shift · diag(A)` on its own pattern, or return the row of
+/// the first pivot that isn't positive.
+fn factor(a: &CsrMatrix<f64>, shift: f64) -> Result<CsrMatrix<f64>, usize> {
+    let n = a.nrows();
+    let mut indptr = Vec::with_capacity(n + 1);
+    let mut indices = Vec::new();
+    let mut values: Vec<f64> = Vec::new();
+    indptr.push(0);
+
+    for i in 0..n {
+        let start = indices.len();
+        let (cols, data) = a.row(i);
+        for (&j, &v) in cols.iter().zip(data).take_while(|&(&j, _)| j < i) {
+            indices.push(j);
+            values.push(v);
+        }
+
+        // l_ik = (a_ik − Σ_{j<k} l_ij l_kj) / l_kk, the sum over the columns both rows store.
+        for p in start..indices.len() {
+            let k = indices[p];
+            let (k_start, k_diag) = (indptr[k], indptr[k + 1] - 1);
+            let (mut q, mut r) = (start, k_start);
+            let mut sum = values[p];
+            while q < p && r < k_diag {
+                match indices[q].cmp(&indices[r]) {
+                    Ordering::Less => q += 1,
+                    Ordering::Greater => r += 1,
+                    Ordering::Equal => {
+                        sum -= values[q] * values[r];
+                        q += 1;
+                        r += 1;
+                    }
+                }
+            }
+            values[p] = sum / values[k_diag];
+        }
+
+        let pivot =
+            a.get(i, i) * (1.0 + shift) - values[start..].iter().map(|l| l * l).sum::<f64>();
+        if !(pivot > 0.0 && pivot.is_finite()) {
+            return Err(i);
+        }
+        indices.push(i);
+        values.push(sqrt(pivot));
+        indptr.push(indices.len());
+    }
+
+    Ok(CsrMatrix::from_parts(n, n, indptr, indices, values))
+}
+
+impl Preconditioner for Ic0 {
+    /// Solve `L y = r` by forward substitution, then `Lᵀ z = y` by backward substitution
+    /// through the rows of `L`.
+    fn apply(&self, r: &[f64], z: &mut [f64]) {
+        let n = self.l.nrows();
+        assert_eq!(r.len(), n, "r has the wrong length");
+        assert_eq!(z.len(), n, "z has the wrong length");
+
+        for i in 0..n {
+            let (cols, values) = self.l.row(i);
+            let (&diag, below) = values.split_last().unwrap();
+            let mut sum = r[i];
+            for (&j, &l) in cols.iter().zip(below) {
+                sum -= l * z[j];
+            }
+            z[i] = sum / diag;
+        }
+
+        for i in (0..n).rev() {
+            let (cols, values) = self.l.row(i);
+            let (&diag, below) = values.split_last().unwrap();
+            z[i] /= diag;
+            let zi = z[i];
+            for (&j, &l) in cols.iter().zip(below) {
+                z[j] -= l * zi;
+            }
+        }
+    }
+}
+
+#[test]
+fn test_ic0() {
+    use crate::iterative::{cg, SolverOptions};
+    use crate::test_util::assert_close;
+
+    // On a tridiagonal matrix IC(0) is the exact Cholesky factorization.
+    let a = CsrMatrix::tridiagonal(-1.0, 3.0, -1.0, 20);
+    let ic = Ic0::new(&a).unwrap();
+    assert_eq!(ic.shift(), 0.0);
+    assert_eq!(ic.factor().nnz(), 39);
+    let x: Vec<f64> = (0..20).map(|i| i as f64 - 3.5).collect();
+    let mut z = vec![0.0; 20];
+    ic.apply(&a.mul_vec(&x), &mut z);
+    assert_close(&z, &x, 1e-12);
+
+    // On the 2-D Laplacian it drops fill, but still speeds CG up.
+    let a = CsrMatrix::poisson2d(32, 32);
+    let b = vec![1.0; 1024];
+    let ic = Ic0::new(&a).unwrap();
+    let opts = SolverOptions::default();
+    let plain = cg(&a, &b, None, None, &opts).unwrap();
+    let preconditioned = cg(&a, &b, None, Some(&ic), &opts).unwrap();
+    assert!(plain.converged && preconditioned.converged);
+    assert!(preconditioned.iterations < plain.iterations * 2 / 3);
+    assert_close(&preconditioned.x, &plain.x, 1e-8);
+
+    // Kershaw's matrix is SPD, but IC(0) meets a negative pivot on it without a shift.
+    #[rustfmt::skip]
+    let kershaw = CsrMatrix::from_dense(4, 4, &[
+        3.0, -2.0, 0.0, 2.0,
+        -2.0, 3.0, -2.0, 0.0,
+        0.0, -2.0, 3.0, -2.0,
+        2.0, 0.0, -2.0, 3.0,
+    ])
+    .unwrap();
+    let ic = Ic0::new(&kershaw).unwrap();
+    assert!(ic.shift() > 0.0);
+    let b = [1.0, 2.0, 3.0, 4.0];
+    let result = cg(&kershaw, &b, None, Some(&ic), &opts).unwrap();
+    assert!(result.converged);
+    assert_close(&kershaw.mul_vec(&result.x), &b, 1e-8);
+
+    let indefinite = CsrMatrix::from_diagonal(&[1.0, -1.0]);
+    assert_eq!(
+        Ic0::new(&indefinite).unwrap_err(),
+        SparseError::ZeroPivot { index: 1 }
+    );
+}
+
+#[test]
+fn test_ic0_zero_and_nan_diagonals() {
+    // Zero, negative zero and missing diagonal entries are all rejected before any shift.
+    let missing = CsrMatrix::from_dense(2, 2, &[1.0, 0.5, 0.5, 0.0]).unwrap();
+    assert_eq!(
+        Ic0::new(&missing).unwrap_err(),
+        SparseError::ZeroPivot { index: 1 }
+    );
+    let mut a = CsrMatrix::tridiagonal(-1.0, 3.0, -1.0, 4);
+    a.set_diagonal(&[3.0, 3.0, -0.0, 3.0]);
+    assert_eq!(
+        Ic0::new(&a).unwrap_err(),
+        SparseError::ZeroPivot { index: 2 }
+    );
+
+    // A NaN diagonal isn't positive either, and no shift could fix it.
+    a.set_diagonal(&[3.0, 3.0, 3.0, f64::NAN]);
+    assert_eq!(
+        Ic0::new(&a).unwrap_err(),
+        SparseError::ZeroPivot { index: 3 }
+    );
+
+    // An infinite diagonal passes that check, but every shift fails at its row.
+    a.set_diagonal(&[3.0, f64::INFINITY, 3.0, 3.0]);
+    assert_eq!(
+        Ic0::new(&a).unwrap_err(),
+        SparseError::ZeroPivot { index: 1 }
+    );
+
+    // A NaN below the diagonal poisons the pivot of its row.
+    #[rustfmt::skip]
+    let nan_below = CsrMatrix::from_dense(3, 3, &[
+        3.0, -1.0, 0.0,
+        -1.0, 3.0, -1.0,
+        0.0, f64::NAN, 3.0,
+    ])
+    .unwrap();
+    assert_eq!(
+        Ic0::new(&nan_below).unwrap_err(),
+        SparseError::ZeroPivot { index: 2 }
+    );
+}
@@ -0,0 +1,181 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use super::Preconditioner;
+use crate::csr::CsrMatrix;
+use crate::error::SparseError;
+
+/// Incomplete LU factorization with zero fill-in, ILU(0).
+///
+/// Gaussian elimination is run on the pattern of `A` only: every update that would create an
+/// entry outside of it is dropped. The factors `L` (unit lower triangular) and `U` (upper
+/// triangular) then fit in the arrays of `A` itself, and `M = L U` agrees with `A` on its
+/// pattern. For a matrix whose elimination creates no fill, such as a tridiagonal one, ILU(0) is
+/// the exact LU factorization.
+#[derive(Clone, Debug)]
+pub struct Ilu0 {
+    indptr: Vec<usize>,
+    indices: Vec<usize>,
+    /// `L` below the diagonal (without its unit diagonal) and `U` on and above it
+    values: Vec<f64>,
+    /// Position of the diagonal entry of each row in `indices`
+    diag: Vec<usize>,
+}
+
+impl Ilu0 {
+    /// Factor a square matrix. Fails with [`SparseError::ZeroPivot`] when a diagonal entry is
+    /// not stored or becomes zero during the elimination.
+    pub fn new(a: &CsrMatrix<f64>) -> Result<Self, SparseError> {
+        let n = a.nrows();
+        if a.ncols() != n {
+            return Err(SparseError::DimensionMismatch {
+                expected: n,
+                found: a.ncols(),
+            });
+        }
+
+        let indptr = a.indptr().to_vec();
+        let indices = a.indices().to_vec();
+        let mut values = a.data().to_vec();
+        let mut diag = Vec::with_capacity(n);
+        for i in 0..n {
+            match indices[indptr[i]..indptr[i + 1]].binary_search(&i) {
+                Ok(k) => diag.push(indptr[i] + k),
+                Err(_) => return Err(SparseError::ZeroPivot { index: i }),
+            }
+        }
+
+        // `pos[j]` is the position of column j in the current row i, if stored.
+        let mut pos = vec![usize::MAX; n];
+        for i in 0..n {
+            for p in indptr[i]..indptr[i + 1] {
+                pos[indices[p]] = p;
+            }
+
+            // For every k < i in row i, in increasing order: l_ik = a_ik / u_kk, then subtract
+            // l_ik times row k of U from the entries of row i that are in the pattern.
+            for p in indptr[i]..diag[i] {
+                let k = indices[p];
+                let pivot = values[diag[k]];
+                if pivot == 0.0 {
+                    return Err(SparseError::ZeroPivot { index: k });
+                }
+                let l_ik = values[p] / pivot;
+                values[p] = l_ik;
+
+                for q in diag[k] + 1..indptr[k + 1] {
+                    let target = pos[indices[q]];
+                    if target != usize::MAX {
+                        values[target] -= l_ik * values[q];
+                    }
+                }
+            }
+
+            for p in indptr[i]..indptr[i + 1] {
+                pos[indices[p]] = usize::MAX;
+            }
+        }
+
+        if let Some(i) = (0..n).find(|&i| values[diag[i]] == 0.0) {
+            return Err(SparseError::ZeroPivot { index: i });
+        }
+
+        Ok(Self {
+            indptr,
+            indices,
+            values,
+            diag,
+        })
+    }
+}
+
+impl Preconditioner for Ilu0 {
+    /// Solve `L U z = r` by forward then backward substitution.
+    fn apply(&self, r: &[f64], z: &mut [f64]) {
+        let n = self.diag.len();
+        assert_eq!(r.len(), n, "r has the wrong length");
+        assert_eq!(z.len(), n, "z has the wrong length");
+
+        for i in 0..n {
+            let mut sum = r[i];
+            for p in self.indptr[i]..self.diag[i] {
+                sum -= self.values[p] * z[self.indices[p]];
+            }
+            z[i] = sum;
+        }
+
+        for i in (0..n).rev() {
+            let mut sum = z[i];
+            for p in self.diag[i] + 1..self.indptr[i + 1] {
+                sum -= self.values[p] * z[self.indices[p]];
+            }
+            z[i] = sum / self.values[self.diag[i]];
+        }
+    }
+}
+
+#[test]
+fn test_ilu0() {
+    use crate::coo::CooMatrix;
+    use crate::iterative::{cg, SolverOptions};
+    use crate::test_util::assert_close;
+
+    // On a tridiagonal matrix ILU(0) is the exact LU factorization.
+    let n = 20;
+    let mut coo = CooMatrix::new(n, n);
+    for i in 0..n {
+        coo.push(i, i, 4.0);
+        if i > 0 {
+            coo.push(i, i - 1, -1.0);
+            coo.push(i - 1, i, -2.0);
+        }
+    }
+    let a = coo.to_csr();
+    let ilu = Ilu0::new(&a).unwrap();
+    let x: Vec<f64> = (0..n).map(|i| i as f64 - 3.5).collect();
+    let mut z = vec![0.0; n];
+    ilu.apply(&a.mul_vec(&x), &mut z);
+    assert_close(&z, &x, 1e-12);
+
+    // On the 2-D Laplacian it drops fill, but still speeds CG up.
+    let m = 32;
+    let mut coo = CooMatrix::new(m * m, m * m);
+    for i in 0..m {
+        for j in 0..m {
+            let k = i * m + j;
+            coo.push(k, k, 4.0);
+            if i > 0 {
+                coo.push(k, k - m, -1.0);
+            }
+            if i + 1 < m {
+                coo.push(k, k + m, -1.0);
+            }
+            if j > 0 {
+                coo.push(k, k - 1, -1.0);
+            }
+            if j + 1 < m {
+                coo.push(k, k + 1, -1.0);
+            }
+        }
+    }
+    let a = coo.to_csr();
+    let b = vec![1.0; m * m];
+    let ilu = Ilu0::new(&a).unwrap();
+    let opts = SolverOptions::default();
+    let plain = cg(&a, &b, None, None, &opts).unwrap();
+    let preconditioned = cg(&a, &b, None, Some(&ilu), &opts).unwrap();
+    assert!(plain.converged && preconditioned.converged);
+    assert!(preconditioned.iterations < plain.iterations * 2 / 3);
+    assert_close(&preconditioned.x, &plain.x, 1e-8);
+
+    let no_diagonal = CsrMatrix::from_dense(2, 2, &[1.0, 1.0, 1.0, 0.0]).unwrap();
+    assert_eq!(
+        Ilu0::new(&no_diagonal).unwrap_err(),
+        SparseError::ZeroPivot { index: 1 }
+    );
+    let singular = CsrMatrix::from_dense(2, 2, &[1.0, 1.0, 1.0, 1.0]).unwrap();
+    assert_eq!(
+        Ilu0::new(&singular).unwrap_err(),
+        SparseError::ZeroPivot { index: 1 }
+    );
+}
@@ -0,0 +1,110 @@
+use alloc::vec::Vec;
+
+use super::Preconditioner;
+use crate::csr::CsrMatrix;
+use crate::error::SparseError;
+
+/// The Jacobi (diagonal) preconditioner, `M = diag(A)`.
+///
+/// The cheapest preconditioner there is: one multiplication per unknown. It removes bad scaling
+/// between the rows, and for a matrix with a dominant diagonal that is much of what makes it
+/// hard. `M` is symmetric positive definite when the diagonal is positive, so it suits CG.
+#[derive(Clone, Debug)]
+pub struct Jacobi {
+    /// `1 / a_ii` of every row
+    inv_diag: Vec<f64>,
+}
+
+impl Jacobi {
+    /// Take the diagonal of a square matrix. Fails with [`SparseError::ZeroPivot`] when a
+    /// diagonal entry is zero or not stored.
+    pub fn new(a: &CsrMatrix<f64>) -> Result<Self, SparseError> {
+        let n = a.nrows();
+        if a.ncols() != n {
+            return Err(SparseError::DimensionMismatch {
+                expected: n,
+                found: a.ncols(),
+            });
+        }
+        Self::from_diagonal(&(0..n).map(|i| a.get(i, i)).collect::<Vec<_>>())
+    }
+
+    /// Use `diag` as the diagonal of `M`. Fails with [`SparseError::ZeroPivot`] on a zero.
+    pub fn from_diagonal(diag: &[f64]) -> Result<Self, SparseError> {
+        if let Some(index) = diag.iter().position(|&d| d == 0.0) {
+            return Err(SparseError::ZeroPivot { index });
+        }
+        Ok(Self {
+            inv_diag: diag.iter().map(|d| 1.0 / d).collect(),
+        })
+    }
+}
+
+impl Preconditioner for Jacobi {
+    fn apply(&self, r: &[f64], z: &mut [f64]) {
+        assert_eq!(r.len(), self.inv_diag.len(), "r has the wrong length");
+        assert_eq!(z.len(), self.inv_diag.len(), "z has the wrong length");
+        for ((zi, ri), d) in z.iter_mut().zip(r).zip(&self.inv_diag) {
+            *zi = ri * d;
+        }
+    }
+}
+
+#[test]
+fn test_jacobi() {
+    use crate::iterative::{cg, SolverOptions};
+    use crate::test_util::assert_close;
+
+    // A Laplacian scaled symmetrically by factors from 10⁻³ to 10³.
+    let n = 200;
+    let mut a = CsrMatrix::tridiagonal(-1.0, 2.5, -1.0, n);
+    let scale: Vec<f64> = (0..n).map(|i| 10f64.powi(i as i32 % 7 - 3)).collect();
+    a.scale_symmetric(&scale);
+    let b = vec![1.0; n];
+
+    let jacobi = Jacobi::new(&a).unwrap();
+    let mut z = vec![0.0; n];
+    jacobi.apply(&b, &mut z);
+    assert_eq!(z[3], 1.0 / 2.5);
+
+    let opts = SolverOptions::default();
+    let plain = cg(&a, &b, None, None, &opts).unwrap();
+    let preconditioned = cg(&a, &b, None, Some(&jacobi), &opts).unwrap();
+    assert!(plain.converged && preconditioned.converged);
+    assert!(preconditioned.iterations < plain.iterations * 2 / 3);
+    assert_close(&a.mul_vec(&preconditioned.x), &b, 1e-6);
+
+    assert_eq!(
+        Jacobi::from_diagonal(&[1.0, 0.0]).unwrap_err(),
+        SparseError::ZeroPivot { index: 1 }
+    );
+}
+
+#[test]
+fn test_jacobi_zero_and_nan_diagonals() {
+    // A negative zero is a zero, and a diagonal entry that isn't stored is one too.
+    assert_eq!(
+        Jacobi::from_diagonal(&[1.0, 2.0, -0.0]).unwrap_err(),
+        SparseError::ZeroPivot { index: 2 }
+    );
+    let missing = CsrMatrix::from_dense(2, 2, &[0.0, 1.0, 1.0, 3.0]).unwrap();
+    assert_eq!(
+        Jacobi::new(&missing).unwrap_err(),
+        SparseError::ZeroPivot { index: 0 }
+    );
+
+    // A NaN diagonal isn't caught, and only spoils its own entry of `z`.
+    let mut a = CsrMatrix::tridiagonal(-1.0, 2.0, -1.0, 3);
+    a.set_diagonal(&[4.0, f64::NAN, 0.5]);
+    let mut z = [0.0; 3];
+    Jacobi::new(&a).unwrap().apply(&[1.0; 3], &mut z);
+    assert_eq!((z[0], z[2]), (0.25, 2.0));
+    assert!(z[1].is_nan());
+}
+
+#[test]
+#[should_panic(expected = "r has the wrong length")]
+fn test_jacobi_wrong_length() {
+    let jacobi = Jacobi::from_diagonal(&[1.0, 2.0]).unwrap();
+    jacobi.apply(&[1.0], &mut [0.0; 2]);
+}
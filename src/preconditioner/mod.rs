@@ -0,0 +1,41 @@
+//! Preconditioners for the iterative solvers.
+//!
+//! A preconditioner `M` approximates `A` while being cheap to invert. The solvers call
+//! [`Preconditioner::apply`] to compute `z = M⁻¹ r`, which turns a hard system into one whose
+//! iteration converges much faster. Every Krylov solver takes an `Option<&dyn Preconditioner>`,
+//! so any of the ones here, or a [`BlockDiagonal`] combination of them, plugs into any solver.
+
+pub mod block;
+pub mod ic0;
+pub mod ilu0;
+pub mod jacobi;
+pub mod ssor;
+
+pub use block::BlockDiagonal;
+pub use ic0::Ic0;
+pub use ilu0::Ilu0;
+pub use jacobi::Jacobi;
+pub use ssor::Ssor;
+
+/// An approximation `M` of the system matrix that can be inverted cheaply.
+pub trait Preconditioner {
+    /// Compute `z = M⁻¹ r`.
+    fn apply(&self, r: &[f64], z: &mut [f64]);
+}
+
+impl<P: Preconditioner + ?Sized> Preconditioner for &P {
+    fn apply(&self, r: &[f64], z: &mut [f64]) {
+        P::apply(self, r, z)
+    }
+}
+
+/// The identity, `M = I`: no preconditioning. Stands in for a block that needs none in a
+/// [`BlockDiagonal`], or for comparisons against the unpreconditioned solver.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Identity;
+
+impl Preconditioner for Identity {
+    fn apply(&self, r: &[f64], z: &mut [f64]) {
+        z.copy_from_slice(r);
+    }
+}
@@ -0,0 +1,14 @@
+//! Re-exports of the items most workflows need, so that `use sparse_matrix::prelude::*;` is
+//! enough to assemble a matrix, multiply with it and solve a system with it.
+//!
+//! The prelude stays small on purpose, so that a glob import doesn't shadow names of the
+//! importing crate. Everything else is imported from its module.
+
+pub use crate::coo::CooMatrix;
+pub use crate::csc::CscMatrix;
+pub use crate::csr::CsrMatrix;
+pub use crate::error::SparseError;
+pub use crate::factor::{DirectOptions, DirectSolver};
+pub use crate::iterative::{cg, gmres, solve, SolverOptions};
+pub use crate::operator::LinearOperator;
+pub use crate::vec::PackedVec;
@@ -0,0 +1,430 @@
+//! Thin QR factorization of a tall sparse matrix by modified Gram–Schmidt, for least-squares
+//! problems of moderate size.
+//!
+//! [`mgs`] orthogonalizes the columns of `A` one after the other against the columns of `Q`
+//! found so far, `A = Q R` with `Q` of orthonormal columns and `R` upper triangular. The columns
+//! of a sparse `A` stay sparse for a while, so each column of `Q` is kept packed while it is
+//! sparse and switched to dense storage once it fills in past a threshold; `R` has one row and
+//! column per column of `A` and is kept dense. Gram–Schmidt loses orthogonality in proportion
+//! to the condition number of `A`, so for ill-conditioned problems prefer
+//! [`lsqr`](crate::iterative::lsqr) or [`lsmr`](crate::iterative::lsmr).
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::csc::CscMatrix;
+use crate::dense::{axpy, dot, norm2};
+use crate::error::SparseError;
+use crate::vec::PackedVec;
+
+/// What [`mgs`] does with a column that is (numerically) a combination of the previous ones.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RankDeficiency {
+    /// Fail with [`SparseError::ZeroPivot`] naming the column
+    Error,
+    /// Move the column to the end and go on with the next one, so that `A P = Q [R₁₁ R₁₂]`
+    /// with `R₁₁` square and nonsingular
+    Pivot,
+}
+
+/// How [`mgs_with`] factors a matrix.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct QrOptions {
+    /// A column whose norm drops to `tol` times its original norm or less once orthogonalized
+    /// counts as dependent on the previous ones
+    pub tol: f64,
+    /// What to do with a dependent column
+    pub rank_deficiency: RankDeficiency,
+    /// The fraction of nonzero components past which a column of `Q` is stored dense
+    pub dense_threshold: f64,
+}
+
+impl Default for QrOptions {
+    fn default() -> Self {
+        Self {
+            tol: 1e-10,
+            rank_deficiency: RankDeficiency::Error,
+            dense_threshold: 0.25,
+        }
+    }
+}
+
+/// A column of `Q`, or a column of `A` being orthogonalized, in whichever storage suits its
+/// density.
+#[derive(Clone, Debug)]
+enum Column {
+    Packed(PackedVec),
+    Dense(Vec<f64>),
+}
+
+impl Column {
+    /// Return the inner product of two columns.
+    fn dot(&self, other: &Column) -> f64 {
+        match (self, other) {
+            (Column::Packed(x), Column::Packed(y)) => x.dot(y),
+            (Column::Packed(x), Column::Dense(y)) | (Column::Dense(y), Column::Packed(x)) => {
+                x.dot_dense(y)
+            }
+            (Column::Dense(x), Column::Dense(y)) => dot(x, y),
+        }
+    }
+
+    /// `self ← self + alpha · q`, switching `self` to dense storage when `q` is dense.
+    fn mul_add(&mut self, q: &Column, alpha: f64) {
+        if let (Column::Packed(x), Column::Dense(_)) = (&*self, q) {
+            *self = Column::Dense(x.scatter());
+        }
+        match (self, q) {
+            (Column::Packed(x), Column::Packed(y)) => x.mul_add(y, alpha),
+            (Column::Dense(x), Column::Packed(y)) => {
+                for (i, yi) in y.iter() {
+                    x[i] += alpha * yi;
+                }
+            }
+            (Column::Dense(x), Column::Dense(y)) => axpy(alpha, y, x),
+            (Column::Packed(_), Column::Dense(_)) => unreachable!("scattered above"),
+        }
+    }
+
+    fn norm2(&self) -> f64 {
+        match self {
+            Column::Packed(x) => x.norm_l2(),
+            Column::Dense(x) => norm2(x),
+        }
+    }
+
+    /// Scale to unit length, given the length, and settle the storage by the number of nonzero
+    /// components.
+    fn into_unit(self, norm: f64, dense_threshold: f64) -> Column {
+        let mut full = match self {
+            Column::Packed(x) => x.scatter(),
+            Column::Dense(x) => x,
+        };
+        full.iter_mut().for_each(|v| *v /= norm);
+        let nnz = full.iter().filter(|&&v| v != 0.0).count();
+        if nnz as f64 > dense_threshold * full.len() as f64 {
+            Column::Dense(full)
+        } else {
+            Column::Packed(PackedVec::gather(&full))
+        }
+    }
+}
+
+/// A thin QR factorization `A P = Q R` of an `m × n` matrix, from [`mgs`].
+///
+/// `Q` is `m × rank` with orthonormal columns and `R` is `rank × n`, upper triangular in its
+/// first `rank` columns. `P` only moves dependent columns to the end, and is the identity when
+/// `A` has full column rank.
+#[derive(Clone, Debug)]
+pub struct ThinQr {
+    nrows: usize,
+    q: Vec<Column>,
+    /// Row-major `rank × n`, in the permuted column order
+    r: Vec<f64>,
+    perm: Vec<usize>,
+}
+
+impl ThinQr {
+    /// Return the shape `(m, n)` of the factored matrix
+    pub fn shape(&self) -> (usize, usize) {
+        (self.nrows, self.perm.len())
+    }
+
+    /// Return the numerical rank, the number of columns of `Q`
+    pub fn rank(&self) -> usize {
+        self.q.len()
+    }
+
+    /// Return `R` as a row-major `rank × n` array, its columns in the order of
+    /// [`perm`](Self::perm).
+    pub fn r(&self) -> &[f64] {
+        &self.r
+    }
+
+    /// Return the column order: column `k` of `A P` is column `perm()[k]` of `A`.
+    pub fn perm(&self) -> &[usize] {
+        &self.perm
+    }
+
+    /// Return the number of columns of `Q` kept in dense storage.
+    pub fn dense_columns(&self) -> usize {
+        self.q
+            .iter()
+            .filter(|q| matches!(q, Column::Dense(_)))
+            .count()
+    }
+
+    /// Return `Q` as a row-major `m × rank` array.
+    pub fn q_to_dense(&self) -> Vec<f64> {
+        let rank = self.rank();
+        let mut dense = vec![0.0; self.nrows * rank];
+        for (k, q) in self.q.iter().enumerate() {
+            match q {
+                Column::Packed(x) => x.iter().for_each(|(i, v)| dense[i * rank + k] = v),
+                Column::Dense(x) => x
+                    .iter()
+                    .enumerate()
+                    .for_each(|(i, &v)| dense[i * rank + k] = v),
+            }
+        }
+        dense
+    }
+
+    /// Solve `min ‖b − A x‖` as `R₁₁ y = Qᵀ b`, with `Qᵀ b` applied column by column as in
+    /// modified Gram–Schmidt. With a rank-deficient `A` the columns moved to the end get
+    /// `x = 0`, a basic solution rather than the minimum-norm one. Fails when `b` doesn't have
+    /// a component per row of `A`.
+    pub fn solve_least_squares(&self, b: &[f64]) -> Result<Vec<f64>, SparseError> {
+        if b.len() != self.nrows {
+            return Err(SparseError::DimensionMismatch {
+                expected: self.nrows,
+                found: b.len(),
+            });
+        }
+
+        let (rank, n) = (self.rank(), self.perm.len());
+        let mut residual = Column::Dense(b.to_vec());
+        let mut y = vec![0.0; rank];
+        for (yk, q) in y.iter_mut().zip(&self.q) {
+            *yk = q.dot(&residual);
+            residual.mul_add(q, -*yk);
+        }
+        for k in (0..rank).rev() {
+            let row = &self.r[k * n..(k + 1) * n];
+            let sum: f64 = (k + 1..rank).map(|l| row[l] * y[l]).sum();
+            y[k] = (y[k] - sum) / row[k];
+        }
+
+        let mut x = vec![0.0; n];
+        for (k, &yk) in y.iter().enumerate() {
+            x[self.perm[k]] = yk;
+        }
+        Ok(x)
+    }
+}
+
+/// Factor `A = Q R` by modified Gram–Schmidt with the default [`QrOptions`], failing with
+/// [`SparseError::ZeroPivot`] on a rank-deficient `A`.
+pub fn mgs(a: &CscMatrix) -> Result<ThinQr, SparseError> {
+    mgs_with(a, &QrOptions::default())
+}
+
+/// Factor `A P = Q R` by modified Gram–Schmidt.
+///
+/// Column `j` of `A` starts out packed and is orthogonalized against each column `q` of `Q` in
+/// turn, `v ← v − (qᵀ v) q`, with sparse inner products and updates while both are packed. A
+/// column whose norm drops to `tol` times its original norm is dependent on the previous ones:
+/// it fails with [`SparseError::ZeroPivot`] naming the column, or is moved to the end with
+/// [`RankDeficiency::Pivot`]. O(n² m) in the worst case, much less while the columns of `Q`
+/// stay sparse.
+pub fn mgs_with(a: &CscMatrix, opts: &QrOptions) -> Result<ThinQr, SparseError> {
+    let (m, n) = a.shape();
+    let mut q: Vec<Column> = Vec::new();
+    // The entries of R by column of A P, as they are found
+    let mut r_cols: Vec<Vec<f64>> = Vec::with_capacity(n);
+    let mut perm = Vec::with_capacity(n);
+    // The dependent columns: their index, their projections, and what is left of them
+    let mut deferred: Vec<(usize, Vec<f64>, Column)> = Vec::new();
+
+    for j in 0..n {
+        let (rows, values) = a.col(j);
+        let mut v = Column::Packed(PackedVec::from_parts(m, rows.to_vec(), values.to_vec()));
+        let original = v.norm2();
+        let mut r_col = Vec::with_capacity(q.len() + 1);
+        for qk in &q {
+            let r_kj = qk.dot(&v);
+            v.mul_add(qk, -r_kj);
+            r_col.push(r_kj);
+        }
+
+        let norm = v.norm2();
+        if norm <= opts.tol * original || norm == 0.0 {
+            match opts.rank_deficiency {
+                RankDeficiency::Error => return Err(SparseError::ZeroPivot { index: j }),
+                RankDeficiency::Pivot => {
+                    deferred.push((j, r_col, v));
+                    continue;
+                }
+            }
+        }
+        r_col.push(norm);
+        q.push(v.into_unit(norm, opts.dense_threshold));
+        r_cols.push(r_col);
+        perm.push(j);
+    }
+
+    // A dependent column is orthogonalized against the columns of Q found after it as well.
+    let rank = q.len();
+    for (j, mut r_col, mut v) in deferred {
+        for qk in &q[r_col.len()..] {
+            let r_kj = qk.dot(&v);
+            v.mul_add(qk, -r_kj);
+            r_col.push(r_kj);
+        }
+        r_cols.push(r_col);
+        perm.push(j);
+    }
+
+    let mut r = vec![0.0; rank * n];
+    for (col, r_col) in r_cols.iter().enumerate() {
+        for (k, &v) in r_col.iter().enumerate().take(rank) {
+            r[k * n + col] = v;
+        }
+    }
+    Ok(ThinQr {
+        nrows: m,
+        q,
+        r,
+        perm,
+    })
+}
+
+#[cfg(test)]
+fn assert_orthonormal(qr: &ThinQr, tol: f64) {
+    let (m, rank) = (qr.shape().0, qr.rank());
+    let q = qr.q_to_dense();
+    for k in 0..rank {
+        for l in 0..rank {
+            let qtq: f64 = (0..m).map(|i| q[i * rank + k] * q[i * rank + l]).sum();
+            let expected = if k == l { 1.0 } else { 0.0 };
+            assert!((qtq - expected).abs() < tol, "(QᵀQ)[{k}][{l}] = {qtq}");
+        }
+    }
+}
+
+#[cfg(test)]
+fn assert_reproduces(a: &[f64], n: usize, qr: &ThinQr, tol: f64) {
+    let (m, rank) = (qr.shape().0, qr.rank());
+    let q = qr.q_to_dense();
+    for i in 0..m {
+        for (col, &j) in qr.perm().iter().enumerate() {
+            let qr_ij: f64 = (0..rank)
+                .map(|k| q[i * rank + k] * qr.r()[k * n + col])
+                .sum();
+            assert!(
+                (qr_ij - a[i * n + j]).abs() < tol,
+                "(QR)[{i}][{j}] = {qr_ij}"
+            );
+        }
+    }
+}
+
+/// Factor a row-major dense `m × n` matrix of full column rank by classical Gram–Schmidt with
+/// reorthogonalization, the dense reference: return `Q` (row-major `m × n`) and `R` (row-major
+/// `n × n`).
+#[cfg(test)]
+fn dense_qr(m: usize, n: usize, a: &[f64]) -> (Vec<f64>, Vec<f64>) {
+    let mut q = vec![0.0; m * n];
+    let mut r = vec![0.0; n * n];
+    for j in 0..n {
+        let mut v: Vec<f64> = (0..m).map(|i| a[i * n + j]).collect();
+        for _pass in 0..2 {
+            let proj: Vec<f64> = (0..j)
+                .map(|k| (0..m).map(|i| q[i * n + k] * v[i]).sum())
+                .collect();
+            for (k, &p) in proj.iter().enumerate() {
+                r[k * n + j] += p;
+                for i in 0..m {
+                    v[i] -= p * q[i * n + k];
+                }
+            }
+        }
+        r[j * n + j] = norm2(&v);
+        for i in 0..m {
+            q[i * n + j] = v[i] / r[j * n + j];
+        }
+    }
+    (q, r)
+}
+
+#[test]
+fn test_mgs() {
+    use crate::csr::CsrMatrix;
+    use crate::test_util::{assert_close, normal_equations, Lcg};
+
+    let mut rng = Lcg::new(67);
+    for (m, n, density) in [(40, 12, 0.15), (30, 30, 0.5), (25, 5, 0.9)] {
+        let mut dense = rng.dense(m, n, density);
+        for j in 0..n {
+            dense[j * n + j] += 1.0;
+        }
+        let a = CscMatrix::from_dense(m, n, &dense).unwrap();
+        let qr = mgs(&a).unwrap();
+        assert_eq!((qr.shape(), qr.rank()), ((m, n), n));
+        assert_eq!(qr.perm(), (0..n).collect::<Vec<_>>());
+        assert_orthonormal(&qr, 1e-12);
+        assert_reproduces(&dense, n, &qr, 1e-12);
+        let (q_ref, r_ref) = dense_qr(m, n, &dense);
+        assert_close(&qr.q_to_dense(), &q_ref, 1e-10);
+        assert_close(qr.r(), &r_ref, 1e-10);
+        // R is upper triangular with a positive diagonal.
+        for k in 0..n {
+            assert!(qr.r()[k * n + k] > 0.0);
+            assert!(qr.r()[k * n..k * n + k].iter().all(|&v| v == 0.0));
+        }
+
+        // The least-squares solution matches the normal equations.
+        let b: Vec<f64> = (0..m).map(|_| rng.uniform()).collect();
+        let expected = normal_equations(&CsrMatrix::from_dense(m, n, &dense).unwrap(), &b, 0.0);
+        assert_close(&qr.solve_least_squares(&b).unwrap(), &expected, 1e-10);
+    }
+
+    // The columns of Q follow the density of the orthogonalized columns: an identity block
+    // stays packed, a full column of ones turns dense.
+    let m = 20;
+    let mut dense = vec![0.0; m * 4];
+    for j in 0..3 {
+        dense[j * 4 + j] = 2.0;
+    }
+    for i in 0..m {
+        dense[i * 4 + 3] = 1.0;
+    }
+    let qr = mgs(&CscMatrix::from_dense(m, 4, &dense).unwrap()).unwrap();
+    assert_eq!(qr.dense_columns(), 1);
+    assert_orthonormal(&qr, 1e-12);
+    assert_reproduces(&dense, 4, &qr, 1e-12);
+
+    assert_eq!(
+        qr.solve_least_squares(&[1.0; 3]).unwrap_err(),
+        SparseError::DimensionMismatch {
+            expected: m,
+            found: 3
+        }
+    );
+}
+
+#[test]
+fn test_mgs_rank_deficient() {
+    use crate::test_util::Lcg;
+
+    // Column 2 is the sum of columns 0 and 1, column 4 is zero.
+    let (m, n) = (15, 5);
+    let mut rng = Lcg::new(71);
+    let mut dense = rng.dense(m, n, 0.6);
+    for i in 0..m {
+        dense[i * n] += 1.0;
+        dense[i * n + 2] = dense[i * n] + dense[i * n + 1];
+        dense[i * n + 4] = 0.0;
+    }
+    let a = CscMatrix::from_dense(m, n, &dense).unwrap();
+    assert_eq!(mgs(&a).unwrap_err(), SparseError::ZeroPivot { index: 2 });
+
+    let opts = QrOptions {
+        rank_deficiency: RankDeficiency::Pivot,
+        ..QrOptions::default()
+    };
+    let qr = mgs_with(&a, &opts).unwrap();
+    assert_eq!(qr.rank(), 3);
+    assert_eq!(qr.perm(), [0, 1, 3, 2, 4]);
+    assert_orthonormal(&qr, 1e-12);
+    assert_reproduces(&dense, n, &qr, 1e-12);
+
+    // A consistent right-hand side is solved exactly by a basic solution.
+    let x: Vec<f64> = (0..n).map(|j| [1.0, -2.0, 0.0, 0.5, 0.0][j]).collect();
+    let b: Vec<f64> = (0..m)
+        .map(|i| (0..n).map(|j| dense[i * n + j] * x[j]).sum())
+        .collect();
+    let solution = qr.solve_least_squares(&b).unwrap();
+    assert_eq!((solution[2], solution[4]), (0.0, 0.0));
+    crate::test_util::assert_close(&solution, &x, 1e-12);
+}
@@ -0,0 +1,117 @@
+//! Random sparse vectors and matrices in the spirit of MATLAB's `sprand`, for testing and
+//! benchmarking. The generators take the random number generator as a parameter, so a seeded
+//! one gives reproducible inputs.
+
+use rand::seq::index::sample;
+use rand::{Rng, RngExt};
+
+use crate::coo::CooMatrix;
+use crate::csr::CsrMatrix;
+use crate::vec::PackedVec;
+
+/// Draw a value in `(0, 1]`, never zero so that every sampled position is stored.
+fn nonzero<R: Rng + ?Sized>(rng: &mut R) -> f64 {
+    1.0 - rng.random::<f64>()
+}
+
+/// Return a random packed vector of length `len` with `round(density * len)` nonzero
+/// components at distinct random positions, uniformly distributed in `(0, 1]`.
+pub fn sprand_vec<R: Rng + ?Sized>(len: usize, density: f64, rng: &mut R) -> PackedVec {
+    let nnz = (density.clamp(0.0, 1.0) * len as f64).round() as usize;
+    let indices = sample(rng, len, nnz).into_vec();
+    let pairs = indices.into_iter().map(|i| (i, nonzero(rng)));
+    PackedVec::from_pairs(len, pairs).expect("sampled indices are in bounds")
+}
+
+/// Return a random `nrows` by `ncols` matrix with `min(nnz_per_row, ncols)` entries in every row,
+/// at distinct random columns, uniformly distributed in `(0, 1]`.
+pub fn sprand_csr<R: Rng + ?Sized>(
+    nrows: usize,
+    ncols: usize,
+    nnz_per_row: usize,
+    rng: &mut R,
+) -> CsrMatrix {
+    let per_row = nnz_per_row.min(ncols);
+    let mut indptr = Vec::with_capacity(nrows + 1);
+    let mut indices = Vec::with_capacity(nrows * per_row);
+    let mut data = Vec::with_capacity(nrows * per_row);
+    indptr.push(0);
+    for _ in 0..nrows {
+        let mut cols = sample(rng, ncols, per_row).into_vec();
+        cols.sort_unstable();
+        for j in cols {
+            indices.push(j);
+            data.push(nonzero(rng));
+        }
+        indptr.push(indices.len());
+    }
+
+    CsrMatrix::from_parts(nrows, ncols, indptr, indices, data)
+}
+
+/// Return a random symmetric positive definite `n` by `n` matrix, for exercising CG and the
+/// other SPD solvers.
+///
+/// The off-diagonal part is `(B + Bᵀ) / 2` for a random `B` with about `nnz_per_row` entries per
+/// row, of either sign. Every diagonal entry is then set to one more than the sum of the
+/// absolute values in its row, which makes the matrix strictly diagonally dominant with a
+/// positive diagonal, hence positive definite.
+pub fn sprand_spd<R: Rng + ?Sized>(n: usize, nnz_per_row: usize, rng: &mut R) -> CsrMatrix {
+    let b = sprand_csr(n, n, nnz_per_row, rng);
+    let mut coo = CooMatrix::new(n, n);
+    let mut row_sums = vec![0.0; n];
+    for i in 0..n {
+        let (cols, values) = b.row(i);
+        for (&j, &v) in cols.iter().zip(values) {
+            if i == j {
+                continue;
+            }
+            let v = if rng.random_bool(0.5) { v } else { -v } / 2.0;
+            coo.push(i, j, v);
+            coo.push(j, i, v);
+            row_sums[i] += v.abs();
+            row_sums[j] += v.abs();
+        }
+    }
+    for (i, sum) in row_sums.into_iter().enumerate() {
+        coo.push(i, i, sum + 1.0);
+    }
+
+    coo.to_csr()
+}
+
+#[test]
+fn test_random() {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    let mut rng = StdRng::seed_from_u64(3);
+
+    let x = sprand_vec(1000, 0.05, &mut rng);
+    assert_eq!(x.len(), 50);
+    assert_eq!(x.full_len(), 1000);
+    assert!(x.iter().all(|(_, v)| v > 0.0 && v <= 1.0));
+
+    let a = sprand_csr(30, 20, 4, &mut rng);
+    assert_eq!(a.shape(), (30, 20));
+    assert_eq!(a.nnz(), 120);
+    assert!((0..30).all(|i| a.row(i).0.len() == 4));
+    assert_eq!(sprand_csr(2, 3, 10, &mut rng).nnz(), 6);
+
+    let spd = sprand_spd(200, 5, &mut rng);
+    assert!(spd.is_hermitian());
+    for i in 0..200 {
+        let (cols, values) = spd.row(i);
+        let off_diagonal: f64 = cols
+            .iter()
+            .zip(values)
+            .filter(|(&j, _)| j != i)
+            .map(|(_, v)| v.abs())
+            .sum();
+        assert!(spd.get(i, i) > off_diagonal);
+    }
+
+    let b = spd.mul_vec(&vec![1.0; 200]);
+    let result = crate::iterative::cg(&spd, &b, None, None, &Default::default()).unwrap();
+    assert!(result.converged);
+}
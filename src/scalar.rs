@@ -0,0 +1,157 @@
+use core::fmt::Debug;
+use core::ops::{Add, AddAssign, Div, Mul, MulAssign, Sub, SubAssign};
+
+/// The numeric types a sparse container can hold.
+///
+/// Only what the sparse kernels need is required: the field-like arithmetic operators and a
+/// zero to compare against, so that structural zeros can be told apart from stored entries.
+pub trait Scalar:
+    Copy
+    + PartialEq
+    + Debug
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + AddAssign
+    + SubAssign
+    + MulAssign
+{
+    /// The additive identity
+    fn zero() -> Self;
+
+    /// The multiplicative identity
+    fn one() -> Self;
+
+    /// Return true if this is a NaN. Types without a NaN never are.
+    fn is_nan(self) -> bool {
+        false
+    }
+
+    /// Return the complex conjugate, which is the value itself for real types.
+    fn conj(self) -> Self {
+        self
+    }
+
+    /// Return `Σ values[k] · x[indices[k]]`, the inner product of a packed row with a dense
+    /// vector. f64 overrides it with the kernel [`simd`](crate::simd) picks.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an index is out of bounds for `x`.
+    fn gather_dot(values: &[Self], indices: &[usize], x: &[Self]) -> Self {
+        let mut sum = Self::zero();
+        for (&v, &j) in values.iter().zip(indices) {
+            sum += v * x[j];
+        }
+        sum
+    }
+
+    /// Set `y[i]` to the [`gather_dot`](Self::gather_dot) of row `i` of the CSR arrays with
+    /// `x`, for every `i < y.len()`. f64 overrides it with the kernel [`simd`](crate::simd)
+    /// picks, which it only picks once for all the rows.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `indptr` has fewer than `y.len() + 1` offsets, or an offset or index is out
+    /// of bounds.
+    fn mul_vec_rows(
+        indptr: &[usize],
+        indices: &[usize],
+        data: &[Self],
+        x: &[Self],
+        y: &mut [Self],
+    ) {
+        for (i, yi) in y.iter_mut().enumerate() {
+            let row = indptr[i]..indptr[i + 1];
+            *yi = Self::gather_dot(&data[row.clone()], &indices[row], x);
+        }
+    }
+
+    /// `y[indices[k]] += alpha · values[k]`, adding a scaled packed row to a dense vector. f64
+    /// overrides it with the kernel [`simd`](crate::simd) picks.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an index is out of bounds for `y`.
+    fn scatter_add(alpha: Self, values: &[Self], indices: &[usize], y: &mut [Self]) {
+        for (&v, &j) in values.iter().zip(indices) {
+            y[j] += alpha * v;
+        }
+    }
+}
+
+macro_rules! impl_scalar_float {
+    ($($t:ty $({ $($extra:item)* })?),*) => {$(
+        impl Scalar for $t {
+            fn zero() -> Self {
+                0.0
+            }
+
+            fn one() -> Self {
+                1.0
+            }
+
+            fn is_nan(self) -> bool {
+                <$t>::is_nan(self)
+            }
+
+            $($($extra)*)?
+        }
+    )*};
+}
+
+macro_rules! impl_scalar_int {
+    ($($t:ty),*) => {$(
+        impl Scalar for $t {
+            fn zero() -> Self {
+                0
+            }
+
+            fn one() -> Self {
+                1
+            }
+        }
+    )*};
+}
+
+impl_scalar_float!(f32, f64 {
+    fn gather_dot(values: &[f64], indices: &[usize], x: &[f64]) -> f64 {
+        crate::simd::gather_dot(values, indices, x)
+    }
+
+    fn mul_vec_rows(indptr: &[usize], indices: &[usize], data: &[f64], x: &[f64], y: &mut [f64]) {
+        crate::simd::mul_vec_rows(indptr, indices, data, x, y)
+    }
+
+    fn scatter_add(alpha: f64, values: &[f64], indices: &[usize], y: &mut [f64]) {
+        crate::simd::scatter_add(alpha, values, indices, y)
+    }
+});
+impl_scalar_int!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+#[cfg(feature = "complex")]
+macro_rules! impl_scalar_complex {
+    ($($t:ty),*) => {$(
+        impl Scalar for num_complex::Complex<$t> {
+            fn zero() -> Self {
+                num_complex::Complex::new(0.0, 0.0)
+            }
+
+            fn one() -> Self {
+                num_complex::Complex::new(1.0, 0.0)
+            }
+
+            fn is_nan(self) -> bool {
+                self.re.is_nan() || self.im.is_nan()
+            }
+
+            fn conj(self) -> Self {
+                num_complex::Complex::conj(&self)
+            }
+        }
+    )*};
+}
+
+#[cfg(feature = "complex")]
+impl_scalar_complex!(f32, f64);
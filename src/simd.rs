@@ -0,0 +1,391 @@
+//! Vectorized f64 kernels for the hot loops, picked at run time: the dense dot product and axpy
+//! of the solvers, the gathered dot product `Σ values[k] · x[indices[k]]` at the heart of CSR
+//! SpMV and of [`PackedVec::dot_dense`](crate::vec::PackedVec::dot_dense), the loop over the
+//! rows of CSR SpMV that applies it, and the scattered
+//! update `y[indices[k]] += alpha · values[k]` of the transposed and CSC products and of
+//! [`dense_times_csr`](crate::csr::dense_times_csr).
+//!
+//! With the `simd` feature on x86-64, the CPU is asked once whether it has AVX2 and FMA, and the
+//! answer picks a table of function pointers that every later call goes through; without `std`
+//! the target features decide at compile time instead. Everywhere else the scalar kernels run.
+//! [`force_scalar`] or the `SPARSE_MATRIX_FORCE_SCALAR` environment variable, set to anything
+//! but `0` before the first call, selects the scalar kernels regardless, so that a binary built
+//! with the feature can be compared against the reference.
+//!
+//! axpy and the scattered update give the same bits on either path. The dot products keep four
+//! partial sums and fuse their multiplies and adds, so their rounding differs from the scalar
+//! loop, by no more than a few ulps of the sum of the absolute products.
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+/// The kernels a call goes through.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KernelSet {
+    /// The plain loops
+    Scalar,
+    /// Four lanes of f64 at a time with AVX2 and FMA
+    Avx2Fma,
+}
+
+/// The environment variable that selects the scalar kernels when set to anything but `0`.
+pub const FORCE_SCALAR_VAR: &str = "SPARSE_MATRIX_FORCE_SCALAR";
+
+static FORCE_SCALAR: AtomicBool = AtomicBool::new(false);
+
+/// Select the scalar kernels for the rest of the process, whatever the CPU supports. Meant for
+/// tests and benchmarks comparing the two paths; the calls already running keep their kernels.
+pub fn force_scalar() {
+    FORCE_SCALAR.store(true, Ordering::Relaxed);
+}
+
+/// Return the kernels the calls go through now.
+pub fn active() -> KernelSet {
+    kernels().set
+}
+
+/// The signature of [`mul_vec_rows`]
+type MulVecRows = fn(&[usize], &[usize], &[f64], &[f64], &mut [f64]);
+
+/// One implementation of every kernel.
+struct Kernels {
+    set: KernelSet,
+    dot: fn(&[f64], &[f64]) -> f64,
+    axpy: fn(f64, &[f64], &mut [f64]),
+    gather_dot: fn(&[f64], &[usize], &[f64]) -> f64,
+    mul_vec_rows: MulVecRows,
+    scatter_add: fn(f64, &[f64], &[usize], &mut [f64]),
+}
+
+static SCALAR: Kernels = Kernels {
+    set: KernelSet::Scalar,
+    dot: scalar::dot,
+    axpy: scalar::axpy,
+    gather_dot: scalar::gather_dot,
+    mul_vec_rows: scalar::mul_vec_rows,
+    scatter_add: scalar::scatter_add,
+};
+
+// Without std, only the target features can select it.
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+#[cfg_attr(not(feature = "std"), allow(dead_code))]
+static AVX2_FMA: Kernels = Kernels {
+    set: KernelSet::Avx2Fma,
+    // SAFETY: this table is only selected when the CPU supports AVX2 and FMA.
+    dot: |x, y| unsafe { avx2::dot(x, y) },
+    axpy: |alpha, x, y| unsafe { avx2::axpy(alpha, x, y) },
+    gather_dot: |values, indices, x| unsafe { avx2::gather_dot(values, indices, x) },
+    mul_vec_rows: |indptr, indices, data, x, y| unsafe {
+        avx2::mul_vec_rows(indptr, indices, data, x, y)
+    },
+    scatter_add: |alpha, values, indices, y| unsafe {
+        avx2::scatter_add(alpha, values, indices, y)
+    },
+};
+
+fn kernels() -> &'static Kernels {
+    if FORCE_SCALAR.load(Ordering::Relaxed) {
+        &SCALAR
+    } else {
+        detected()
+    }
+}
+
+/// Return the best kernels the CPU supports, asking it on the first call only.
+#[cfg(feature = "std")]
+fn detected() -> &'static Kernels {
+    static DETECTED: std::sync::OnceLock<&'static Kernels> = std::sync::OnceLock::new();
+    DETECTED.get_or_init(|| {
+        let forced = std::env::var_os(FORCE_SCALAR_VAR).is_some_and(|v| !v.is_empty() && v != "0");
+        if forced {
+            return &SCALAR;
+        }
+        #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+        if std::is_x86_feature_detected!("avx2") && std::is_x86_feature_detected!("fma") {
+            return &AVX2_FMA;
+        }
+        &SCALAR
+    })
+}
+
+/// Return the best kernels the target features promise, known at compile time.
+#[cfg(not(feature = "std"))]
+fn detected() -> &'static Kernels {
+    #[cfg(all(
+        feature = "simd",
+        target_arch = "x86_64",
+        target_feature = "avx2",
+        target_feature = "fma"
+    ))]
+    return &AVX2_FMA;
+    #[allow(unreachable_code)]
+    &SCALAR
+}
+
+/// Return `Σ x[i] · y[i]` over the common length.
+pub(crate) fn dot(x: &[f64], y: &[f64]) -> f64 {
+    (kernels().dot)(x, y)
+}
+
+/// `y += alpha * x` over the common length.
+pub(crate) fn axpy(alpha: f64, x: &[f64], y: &mut [f64]) {
+    (kernels().axpy)(alpha, x, y)
+}
+
+/// Return `Σ values[k] · x[indices[k]]` over the common length of `values` and `indices`.
+///
+/// # Panics
+///
+/// Panics if an index is out of bounds for `x`.
+pub(crate) fn gather_dot(values: &[f64], indices: &[usize], x: &[f64]) -> f64 {
+    (kernels().gather_dot)(values, indices, x)
+}
+
+/// `y[i] = Σ data[k] · x[indices[k]]` over the entries `indptr[i]..indptr[i + 1]` of each row
+/// `i < y.len()`, a CSR product that pays for the dispatch once rather than once per row.
+///
+/// # Panics
+///
+/// Panics if `indptr` has fewer than `y.len() + 1` offsets, or an offset or index is out of
+/// bounds.
+pub(crate) fn mul_vec_rows(
+    indptr: &[usize],
+    indices: &[usize],
+    data: &[f64],
+    x: &[f64],
+    y: &mut [f64],
+) {
+    (kernels().mul_vec_rows)(indptr, indices, data, x, y)
+}
+
+/// `y[indices[k]] += alpha * values[k]` over the common length of `values` and `indices`, in
+/// order, so that repeated indices accumulate.
+///
+/// # Panics
+///
+/// Panics if an index is out of bounds for `y`.
+pub(crate) fn scatter_add(alpha: f64, values: &[f64], indices: &[usize], y: &mut [f64]) {
+    (kernels().scatter_add)(alpha, values, indices, y)
+}
+
+/// The fallbacks, also the reference the vectorized kernels are tested against.
+mod scalar {
+    pub(super) fn dot(x: &[f64], y: &[f64]) -> f64 {
+        x.iter().zip(y).map(|(a, b)| a * b).sum()
+    }
+
+    pub(super) fn axpy(alpha: f64, x: &[f64], y: &mut [f64]) {
+        for (yi, xi) in y.iter_mut().zip(x) {
+            *yi += alpha * xi;
+        }
+    }
+
+    pub(super) fn gather_dot(values: &[f64], indices: &[usize], x: &[f64]) -> f64 {
+        let mut sum = 0.0;
+        for (&v, &j) in values.iter().zip(indices) {
+            sum += v * x[j];
+        }
+        sum
+    }
+
+    pub(super) fn mul_vec_rows(
+        indptr: &[usize],
+        indices: &[usize],
+        data: &[f64],
+        x: &[f64],
+        y: &mut [f64],
+    ) {
+        for (i, yi) in y.iter_mut().enumerate() {
+            let row = indptr[i]..indptr[i + 1];
+            *yi = gather_dot(&data[row.clone()], &indices[row], x);
+        }
+    }
+
+    pub(super) fn scatter_add(alpha: f64, values: &[f64], indices: &[usize], y: &mut [f64]) {
+        for (&v, &j) in values.iter().zip(indices) {
+            y[j] += alpha * v;
+        }
+    }
+}
+
+/// Four lanes of f64 at a time. The dot products fuse their multiplies and adds; axpy and the
+/// scattered update multiply and add separately so that they round like the scalar loops.
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+#[cfg_attr(not(feature = "std"), allow(dead_code))]
+mod avx2 {
+    use core::arch::x86_64::*;
+
+    #[target_feature(enable = "avx2,fma")]
+    pub(super) unsafe fn dot(x: &[f64], y: &[f64]) -> f64 {
+        let n = x.len().min(y.len());
+        let mut acc = _mm256_setzero_pd();
+        let mut k = 0;
+        while k + 4 <= n {
+            // SAFETY: k + 4 <= n, so both loads are in bounds.
+            let (a, b) = unsafe {
+                (
+                    _mm256_loadu_pd(x.as_ptr().add(k)),
+                    _mm256_loadu_pd(y.as_ptr().add(k)),
+                )
+            };
+            acc = _mm256_fmadd_pd(a, b, acc);
+            k += 4;
+        }
+        let mut sum = horizontal_sum(acc);
+        for (a, b) in x[k..n].iter().zip(&y[k..n]) {
+            sum += a * b;
+        }
+        sum
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn axpy(alpha: f64, x: &[f64], y: &mut [f64]) {
+        let n = x.len().min(y.len());
+        let alpha4 = _mm256_set1_pd(alpha);
+        let mut k = 0;
+        while k + 4 <= n {
+            // SAFETY: k + 4 <= n, so the loads and the store are in bounds.
+            unsafe {
+                let a = _mm256_loadu_pd(x.as_ptr().add(k));
+                let b = _mm256_loadu_pd(y.as_ptr().add(k));
+                _mm256_storeu_pd(
+                    y.as_mut_ptr().add(k),
+                    _mm256_add_pd(b, _mm256_mul_pd(alpha4, a)),
+                );
+            }
+            k += 4;
+        }
+        for (yi, xi) in y[k..n].iter_mut().zip(&x[k..n]) {
+            *yi += alpha * xi;
+        }
+    }
+
+    #[target_feature(enable = "avx2,fma")]
+    pub(super) unsafe fn gather_dot(values: &[f64], indices: &[usize], x: &[f64]) -> f64 {
+        let n = values.len().min(indices.len());
+        let mut acc = _mm256_setzero_pd();
+        let mut k = 0;
+        while k + 4 <= n {
+            let lanes = &indices[k..k + 4];
+            let max = lanes[0].max(lanes[1]).max(lanes[2]).max(lanes[3]);
+            assert!(max < x.len(), "index {max} out of bounds for {}", x.len());
+            // SAFETY: k + 4 <= n bounds the loads, and every gathered index was checked
+            // against x.len() just above. usize is 64 bits wide on x86-64.
+            let (v, g) = unsafe {
+                let idx = _mm256_loadu_si256(lanes.as_ptr().cast());
+                (
+                    _mm256_loadu_pd(values.as_ptr().add(k)),
+                    _mm256_i64gather_pd::<8>(x.as_ptr(), idx),
+                )
+            };
+            acc = _mm256_fmadd_pd(v, g, acc);
+            k += 4;
+        }
+        let mut sum = horizontal_sum(acc);
+        for (&v, &j) in values[k..n].iter().zip(&indices[k..n]) {
+            sum += v * x[j];
+        }
+        sum
+    }
+
+    #[target_feature(enable = "avx2,fma")]
+    pub(super) unsafe fn mul_vec_rows(
+        indptr: &[usize],
+        indices: &[usize],
+        data: &[f64],
+        x: &[f64],
+        y: &mut [f64],
+    ) {
+        for (i, yi) in y.iter_mut().enumerate() {
+            let row = indptr[i]..indptr[i + 1];
+            // SAFETY: this function has the features gather_dot needs.
+            *yi = unsafe { gather_dot(&data[row.clone()], &indices[row], x) };
+        }
+    }
+
+    /// AVX2 has no scatter, so only the products are vectorized and the four sums are stored
+    /// one lane at a time, in order, which keeps repeated indices right.
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn scatter_add(alpha: f64, values: &[f64], indices: &[usize], y: &mut [f64]) {
+        let n = values.len().min(indices.len());
+        let alpha4 = _mm256_set1_pd(alpha);
+        let mut products = [0.0; 4];
+        let mut k = 0;
+        while k + 4 <= n {
+            // SAFETY: k + 4 <= n, so the load is in bounds, and products holds four lanes.
+            unsafe {
+                let v = _mm256_loadu_pd(values.as_ptr().add(k));
+                _mm256_storeu_pd(products.as_mut_ptr(), _mm256_mul_pd(alpha4, v));
+            }
+            for (&j, p) in indices[k..k + 4].iter().zip(products) {
+                y[j] += p;
+            }
+            k += 4;
+        }
+        for (&v, &j) in values[k..n].iter().zip(&indices[k..n]) {
+            y[j] += alpha * v;
+        }
+    }
+
+    #[target_feature(enable = "avx2")]
+    fn horizontal_sum(v: __m256d) -> f64 {
+        let pair = _mm_add_pd(_mm256_castpd256_pd128(v), _mm256_extractf128_pd::<1>(v));
+        _mm_cvtsd_f64(_mm_add_sd(pair, _mm_unpackhi_pd(pair, pair)))
+    }
+}
+
+#[test]
+fn test_simd_kernels() {
+    use crate::test_util::Lcg;
+
+    // Both paths on the same inputs: the scalar table against the one the CPU supports, which
+    // is the scalar one again without the feature or the instructions.
+    let fast = detected();
+    let mut rng = Lcg::new(11);
+    // Lengths around the lane count exercise the tails.
+    for n in [0, 1, 3, 4, 5, 8, 13, 100] {
+        let x: Vec<f64> = (0..n).map(|_| rng.uniform()).collect();
+        let y: Vec<f64> = (0..n).map(|_| rng.uniform()).collect();
+        let indices: Vec<usize> = (0..n).map(|_| rng.below(n)).collect();
+        let bound: f64 = x.iter().zip(&y).map(|(a, b)| (a * b).abs()).sum();
+        let tol = 8.0 * f64::EPSILON * bound;
+
+        assert!(((fast.dot)(&x, &y) - scalar::dot(&x, &y)).abs() <= tol);
+        assert!((dot(&x, &y) - scalar::dot(&x, &y)).abs() <= tol);
+        let gathered = (fast.gather_dot)(&x, &indices, &y);
+        let expected = scalar::gather_dot(&x, &indices, &y);
+        assert!((gathered - expected).abs() <= 8.0 * f64::EPSILON * (n as f64));
+
+        let mut fast_y = y.clone();
+        let mut slow_y = y.clone();
+        (fast.axpy)(-0.3, &x, &mut fast_y);
+        scalar::axpy(-0.3, &x, &mut slow_y);
+        assert_eq!(fast_y, slow_y);
+
+        // Rows of a few entries each, cut from the same arrays.
+        let indptr: Vec<usize> = (0..=n / 3).map(|i| 3 * i).collect();
+        let mut fast_rows = vec![0.0; n / 3];
+        let mut slow_rows = vec![0.0; n / 3];
+        (fast.mul_vec_rows)(&indptr, &indices, &x, &y, &mut fast_rows);
+        scalar::mul_vec_rows(&indptr, &indices, &x, &y, &mut slow_rows);
+        for (f, s) in fast_rows.iter().zip(&slow_rows) {
+            assert!((f - s).abs() <= 8.0 * f64::EPSILON * 3.0);
+        }
+
+        // The indices repeat, which must accumulate.
+        (fast.scatter_add)(0.7, &x, &indices, &mut fast_y);
+        scalar::scatter_add(0.7, &x, &indices, &mut slow_y);
+        assert_eq!(fast_y, slow_y);
+    }
+}
+
+#[test]
+#[should_panic(expected = "out of bounds")]
+fn test_gather_dot_out_of_bounds() {
+    gather_dot(&[1.0; 8], &[0, 1, 2, 3, 4, 5, 6, 9], &[1.0; 8]);
+}
+
+#[test]
+#[should_panic(expected = "out of bounds")]
+fn test_scatter_add_out_of_bounds() {
+    scatter_add(1.0, &[1.0; 8], &[0, 1, 2, 3, 4, 5, 6, 9], &mut [0.0; 8]);
+}
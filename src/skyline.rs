@@ -0,0 +1,333 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::csr::CsrMatrix;
+use crate::error::SparseError;
+use crate::scalar::Scalar;
+
+/// A symmetric matrix in skyline (variable band, or envelope) form: every column of the upper
+/// triangle stored from its first nonzero row down to the diagonal, zeros in between included.
+///
+/// Column `j` holds rows `j + 1 - len..=j` in `data[colptr[j]..colptr[j + 1]]`, where `len` is
+/// the column's length, with the diagonal last. An `LDLᵀ` factorization fills in only inside
+/// this envelope, so it runs in place with no symbolic phase. The envelope, and with it the
+/// work, shrinks when the matrix is first reordered with [`crate::ordering::rcm`]; together that
+/// is the classic profile solver of structural engineering codes.
+#[derive(Clone, Debug)]
+pub struct SkylineMatrix<T = f64> {
+    /// Start of each column in `data`, plus the size of the envelope at the end
+    colptr: Vec<usize>,
+    data: Vec<T>,
+}
+
+/// The `LDLᵀ` factorization of a [`SkylineMatrix`], from [`SkylineMatrix::ldlt`].
+///
+/// `L` is unit lower triangular and `D` diagonal. `Lᵀ` is stored in the envelope of the factored
+/// matrix with `D` on its diagonal.
+#[derive(Clone, Debug)]
+pub struct SkylineLdlt<T = f64> {
+    factor: SkylineMatrix<T>,
+}
+
+impl<T: Scalar> SkylineMatrix<T> {
+    /// Store the upper triangle and diagonal of a square matrix, which is taken as symmetric:
+    /// the entries below the diagonal are ignored. Fails with
+    /// [`SparseError::DimensionMismatch`] if `a` isn't square. O(nnz + envelope size).
+    pub fn from_csr(a: &CsrMatrix<T>) -> Result<Self, SparseError> {
+        let n = a.nrows();
+        if a.ncols() != n {
+            return Err(SparseError::DimensionMismatch {
+                expected: n,
+                found: a.ncols(),
+            });
+        }
+
+        // The first row of column j is the smallest row with an entry at or above (j, j).
+        let mut first: Vec<usize> = (0..n).collect();
+        for i in 0..n {
+            for &j in a.row(i).0 {
+                if j >= i {
+                    first[j] = first[j].min(i);
+                }
+            }
+        }
+        let mut colptr = Vec::with_capacity(n + 1);
+        colptr.push(0);
+        for j in 0..n {
+            colptr.push(colptr[j] + j + 1 - first[j]);
+        }
+
+        let mut data = vec![T::zero(); colptr[n]];
+        for i in 0..n {
+            let (cols, values) = a.row(i);
+            for (&j, &v) in cols.iter().zip(values) {
+                if j >= i {
+                    data[colptr[j + 1] - 1 - (j - i)] = v;
+                }
+            }
+        }
+
+        Ok(Self { colptr, data })
+    }
+
+    /// Return the number of rows and columns
+    pub fn n(&self) -> usize {
+        self.colptr.len() - 1
+    }
+
+    /// Return the number of stored values, the size of the envelope including the diagonal
+    pub fn profile(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Return the first row stored in column `j`.
+    fn first_row(&self, j: usize) -> usize {
+        j + 1 - (self.colptr[j + 1] - self.colptr[j])
+    }
+
+    /// Return the stored part of column `j`, rows `first_row(j)..=j`.
+    fn col(&self, j: usize) -> &[T] {
+        &self.data[self.colptr[j]..self.colptr[j + 1]]
+    }
+
+    /// Return the entry at `(i, j)`, zero outside the envelope.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` or `j` is out of bounds.
+    pub fn get(&self, i: usize, j: usize) -> T {
+        let (i, j) = (i.min(j), i.max(j));
+        assert!(
+            j < self.n(),
+            "column {j} out of bounds for {} columns",
+            self.n()
+        );
+        if i < self.first_row(j) {
+            T::zero()
+        } else {
+            self.data[self.colptr[j + 1] - 1 - (j - i)]
+        }
+    }
+
+    /// Multiply the matrix by the dense vector `x`, mirroring the stored triangle.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `x.len() != self.n()`.
+    pub fn mul_vec(&self, x: &[T]) -> Vec<T> {
+        assert_eq!(x.len(), self.n(), "x has the wrong length");
+
+        let mut y = vec![T::zero(); self.n()];
+        for j in 0..self.n() {
+            let first = self.first_row(j);
+            let (diagonal, above) = self.col(j).split_last().expect("the diagonal is stored");
+            let mut sum = *diagonal * x[j];
+            for (k, &v) in above.iter().enumerate() {
+                sum += v * x[first + k];
+                y[first + k] += v * x[j];
+            }
+            y[j] += sum;
+        }
+        y
+    }
+
+    /// Factor the matrix as `L D Lᵀ` in place, column by column in the profile (Crout) order.
+    /// Fails with [`SparseError::ZeroPivot`] when a pivot of `D` is zero; no pivoting is done,
+    /// so the matrix should be positive definite or otherwise safe to factor in its order.
+    /// O(Σⱼ lenⱼ²) for column lengths `lenⱼ`.
+    pub fn ldlt(mut self) -> Result<SkylineLdlt<T>, SparseError> {
+        for j in 0..self.n() {
+            let first_j = self.first_row(j);
+            let start_j = self.colptr[j];
+
+            // g(i, j) = a(i, j) - Σₖ u(k, i) g(k, j), over the rows k above i in both columns.
+            for i in first_j + 1..j {
+                let first_i = self.first_row(i);
+                let lo = first_i.max(first_j);
+                let mut sum = T::zero();
+                for k in lo..i {
+                    let u_ki = self.data[self.colptr[i] + (k - first_i)];
+                    sum += u_ki * self.data[start_j + (k - first_j)];
+                }
+                self.data[start_j + (i - first_j)] -= sum;
+            }
+
+            // u(i, j) = g(i, j) / d(i), and d(j) = a(j, j) - Σᵢ u(i, j) g(i, j).
+            let mut d = self.data[self.colptr[j + 1] - 1];
+            for i in first_j..j {
+                let g = self.data[start_j + (i - first_j)];
+                let u = g / self.data[self.colptr[i + 1] - 1];
+                d -= u * g;
+                self.data[start_j + (i - first_j)] = u;
+            }
+            if d == T::zero() {
+                return Err(SparseError::ZeroPivot { index: j });
+            }
+            self.data[self.colptr[j + 1] - 1] = d;
+        }
+
+        Ok(SkylineLdlt { factor: self })
+    }
+}
+
+impl<T: Scalar> SkylineLdlt<T> {
+    /// Return the number of rows and columns
+    pub fn n(&self) -> usize {
+        self.factor.n()
+    }
+
+    /// Solve `A x = b` with the factorization.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `b.len() != self.n()`.
+    pub fn solve(&self, b: &[T]) -> Vec<T> {
+        let mut x = b.to_vec();
+        self.solve_in_place(&mut x);
+        x
+    }
+
+    /// Solve `A x = b` with the factorization, overwriting `b` with `x`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `b.len() != self.n()`.
+    pub fn solve_in_place(&self, b: &mut [T]) {
+        assert_eq!(b.len(), self.n(), "b has the wrong length");
+        let f = &self.factor;
+
+        // L y = b, reading row j of L from column j of Lᵀ.
+        for j in 0..f.n() {
+            let first = f.first_row(j);
+            let above = &f.col(j)[..j - first];
+            let mut sum = T::zero();
+            for (k, &u) in above.iter().enumerate() {
+                sum += u * b[first + k];
+            }
+            b[j] -= sum;
+        }
+
+        for (j, bj) in b.iter_mut().enumerate() {
+            *bj = *bj / f.data[f.colptr[j + 1] - 1];
+        }
+
+        // Lᵀ x = z, column by column from the last.
+        for j in (0..f.n()).rev() {
+            let first = f.first_row(j);
+            let above = &f.col(j)[..j - first];
+            let xj = b[j];
+            for (k, &u) in above.iter().enumerate() {
+                b[first + k] -= u * xj;
+            }
+        }
+    }
+}
+
+#[test]
+fn test_skyline_ldlt() {
+    use crate::ordering::rcm;
+    use crate::permutation::Permutation;
+    use crate::test_util::{assert_close, Lcg};
+
+    // The 2-D Poisson matrix with its grid points numbered at random.
+    let poisson = CsrMatrix::poisson2d(8, 8);
+    let n = poisson.nrows();
+    let mut forward: Vec<usize> = (0..n).collect();
+    let mut rng = Lcg::new(11);
+    for k in (1..n).rev() {
+        forward.swap(k, rng.below(k + 1));
+    }
+    let a = Permutation::new(forward)
+        .unwrap()
+        .permute_symmetric(&poisson);
+
+    let p = rcm(&a).unwrap();
+    let reordered = p.permute_symmetric(&a);
+    let sky = SkylineMatrix::from_csr(&reordered).unwrap();
+    assert!(sky.profile() < SkylineMatrix::from_csr(&a).unwrap().profile());
+    assert_eq!(sky.get(3, 3), 4.0);
+
+    let x: Vec<f64> = (0..n).map(|i| (i % 5) as f64 - 2.0).collect();
+    assert_close(&sky.mul_vec(&x), &reordered.mul_vec(&x), 1e-12);
+
+    let b = reordered.mul_vec(&x);
+    let ldlt = sky.ldlt().unwrap();
+    assert_close(&ldlt.solve(&b), &x, 1e-10);
+
+    // Solving the original system through the permutation.
+    let b = a.mul_vec(&x);
+    let y = ldlt.solve(&p.apply_to_vec(&b));
+    assert_close(&p.inverse().apply_to_vec(&y), &x, 1e-10);
+
+    #[rustfmt::skip]
+    let singular = CsrMatrix::from_dense(3, 3, &[
+        1.0, 1.0, 0.0,
+        1.0, 1.0, 2.0,
+        0.0, 2.0, 1.0,
+    ]).unwrap();
+    assert_eq!(
+        SkylineMatrix::from_csr(&singular)
+            .unwrap()
+            .ldlt()
+            .unwrap_err(),
+        SparseError::ZeroPivot { index: 1 }
+    );
+}
+
+#[test]
+fn test_skyline_empty_profiles() {
+    // A diagonal matrix: every column holds its diagonal alone, and LDLᵀ leaves it as D.
+    let diagonal = CsrMatrix::from_diagonal(&[2.0, -4.0, 0.5]);
+    let sky = SkylineMatrix::from_csr(&diagonal).unwrap();
+    assert_eq!(sky.colptr, [0, 1, 2, 3]);
+    assert_eq!(
+        (0..3).map(|j| sky.first_row(j)).collect::<Vec<_>>(),
+        [0, 1, 2]
+    );
+    assert_eq!(sky.mul_vec(&[1.0, 1.0, 1.0]), [2.0, -4.0, 0.5]);
+    let ldlt = sky.ldlt().unwrap();
+    assert_eq!(ldlt.factor.data, [2.0, -4.0, 0.5]);
+    assert_eq!(ldlt.solve(&[2.0, 4.0, 1.0]), [1.0, -1.0, 2.0]);
+
+    // Entries below the diagonal only are ignored, so they leave the profiles empty.
+    #[rustfmt::skip]
+    let lower = CsrMatrix::from_dense(3, 3, &[
+        1.0, 0.0, 0.0,
+        5.0, 1.0, 0.0,
+        6.0, 7.0, 1.0,
+    ]).unwrap();
+    assert_eq!(SkylineMatrix::from_csr(&lower).unwrap().profile(), 3);
+
+    // Empty profiles between full ones: columns 0 and 2 hold only their diagonal, so the
+    // update of column 3 skips row 2 and reaches back to row 1 alone.
+    #[rustfmt::skip]
+    let dense = [
+        4.0, 1.0, 0.0, 0.0,
+        1.0, 4.0, 0.0, 1.0,
+        0.0, 0.0, 4.0, 2.0,
+        0.0, 1.0, 2.0, 4.0,
+    ];
+    let a = CsrMatrix::from_dense(4, 4, &dense).unwrap();
+    let sky = SkylineMatrix::from_csr(&a).unwrap();
+    assert_eq!(sky.colptr, [0, 1, 3, 4, 7]);
+    assert_eq!(
+        (sky.get(0, 2), sky.get(0, 3), sky.get(2, 3)),
+        (0.0, 0.0, 2.0)
+    );
+    let x = [1.0, -1.0, 2.0, 0.5];
+    assert_eq!(sky.mul_vec(&x), a.mul_vec(&x));
+    let ldlt = sky.ldlt().unwrap();
+    assert_eq!(ldlt.factor.profile(), 7);
+    let solved = ldlt.solve(&a.mul_vec(&x));
+    crate::test_util::assert_close(&solved, &x, 1e-14);
+
+    // A zero diagonal in an empty profile is the pivot that fails, at its own column.
+    let zero_pivot = CsrMatrix::from_diagonal(&[1.0, 2.0, 0.0, 3.0]);
+    assert_eq!(
+        SkylineMatrix::from_csr(&zero_pivot)
+            .unwrap()
+            .ldlt()
+            .unwrap_err(),
+        SparseError::ZeroPivot { index: 2 }
+    );
+}
@@ -0,0 +1,153 @@
+//! Pictures of the nonzero pattern of a matrix, in the manner of MATLAB's `spy`, to see the
+//! structure of a matrix and what an ordering does to it at a glance.
+//!
+//! The matrix is cut into a grid of at most `width` by `height` cells, and each cell is shaded
+//! by the number of nonzeros it holds relative to the fullest cell. [`spy`] draws the grid as
+//! text, and `spy_png`, behind the `png` feature, as a grayscale image with a pixel per cell.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::csr::CsrMatrix;
+#[cfg(feature = "png")]
+use crate::error::SparseError;
+use crate::scalar::Scalar;
+
+/// The shades of a text cell, from the emptiest nonempty cell to the fullest.
+const SHADES: [char; 4] = ['.', ':', '*', '#'];
+
+/// Count the nonzeros in each cell of a grid of at most `height` by `width` cells, never more
+/// than the matrix has rows and columns. Return the grid's height and width and the counts,
+/// row-major. O(nnz + height × width).
+fn count_cells<T: Scalar>(
+    a: &CsrMatrix<T>,
+    width: usize,
+    height: usize,
+) -> (usize, usize, Vec<usize>) {
+    let (nrows, ncols) = a.shape();
+    let h = height.min(nrows);
+    let w = width.min(ncols);
+    let mut counts = vec![0; h * w];
+    for row in a.row_iter() {
+        let ci = row.index() * h / nrows;
+        for (j, v) in row.iter() {
+            if v != T::zero() {
+                counts[ci * w + j * w / ncols] += 1;
+            }
+        }
+    }
+    (h, w, counts)
+}
+
+/// Draw the nonzero pattern in a frame of at most `width` by `height` characters inside the
+/// border: a blank for an empty cell, then `.`, `:`, `*` and `#` as the cell fills up relative
+/// to the fullest one. Explicitly stored zeros count as empty.
+///
+/// ```
+/// use sparse_matrix::csr::CsrMatrix;
+/// use sparse_matrix::spy::spy;
+///
+/// let a = CsrMatrix::<f64>::identity(3);
+/// assert_eq!(spy(&a, 80, 40), "+---+\n|#  |\n| # |\n|  #|\n+---+\n");
+/// ```
+pub fn spy<T: Scalar>(a: &CsrMatrix<T>, width: usize, height: usize) -> String {
+    let (h, w, counts) = count_cells(a, width, height);
+    let max = counts.iter().copied().max().unwrap_or(0);
+    let border = format!("+{}+\n", "-".repeat(w));
+
+    let mut picture = border.clone();
+    for row in counts.chunks(w.max(1)).take(h) {
+        picture.push('|');
+        for &count in row {
+            picture.push(match count {
+                0 => ' ',
+                _ => SHADES[(count * SHADES.len()).div_ceil(max) - 1],
+            });
+        }
+        picture.push_str("|\n");
+    }
+    picture.push_str(&border);
+    picture
+}
+
+/// Write the nonzero pattern as an 8-bit grayscale PNG of at most `width` by `height` pixels,
+/// one per cell: white for an empty cell, darker as the cell fills up relative to the fullest
+/// one, black for the fullest. A matrix without rows or columns gives a single white pixel.
+#[cfg(feature = "png")]
+pub fn spy_png<T: Scalar>(
+    writer: impl std::io::Write,
+    a: &CsrMatrix<T>,
+    width: usize,
+    height: usize,
+) -> Result<(), SparseError> {
+    let (h, w, counts) = count_cells(a, width, height);
+    let max = counts.iter().copied().max().unwrap_or(0).max(1);
+    let (h, w, pixels) = if h == 0 || w == 0 {
+        (1, 1, vec![u8::MAX])
+    } else {
+        let shade = |count: usize| (255 - count * 255 / max) as u8;
+        (h, w, counts.into_iter().map(shade).collect())
+    };
+
+    let png_error = |err: png::EncodingError| SparseError::Io(err.to_string());
+    let mut encoder = png::Encoder::new(writer, w as u32, h as u32);
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(png_error)?;
+    writer.write_image_data(&pixels).map_err(png_error)?;
+    writer.finish().map_err(png_error)
+}
+
+#[cfg(test)]
+fn lower_triangle() -> CsrMatrix<f64> {
+    let mut coo = crate::coo::CooMatrix::new(4, 4);
+    for i in 0..4 {
+        for j in 0..=i {
+            coo.push(i, j, 1.0);
+        }
+    }
+    coo.to_csr()
+}
+
+#[test]
+fn test_spy() {
+    // Downsampled 6 by 6 to 3 by 3: the diagonal cells hold 4 of the tridiagonal's entries,
+    // their neighbours 1.
+    let a = CsrMatrix::tridiagonal(-1.0, 2.0, -1.0, 6);
+    assert_eq!(
+        spy(&a, 3, 3),
+        "+---+\n\
+         |#. |\n\
+         |.#.|\n\
+         | .#|\n\
+         +---+\n"
+    );
+    // The lower triangle of a 4 by 4 matrix: cells of 3 and 4 entries.
+    assert_eq!(spy(&lower_triangle(), 2, 2), "+--+\n|* |\n|#*|\n+--+\n");
+
+    // Explicit zeros are not drawn, and an empty matrix is just a frame.
+    let zeros = CsrMatrix::try_from_csr_data(1, 2, vec![0, 1], vec![1], vec![0.0]).unwrap();
+    assert_eq!(spy(&zeros, 10, 10), "+--+\n|  |\n+--+\n");
+    assert_eq!(spy(&CsrMatrix::<f64>::new(0, 0), 10, 10), "++\n++\n");
+}
+
+#[cfg(feature = "png")]
+#[test]
+fn test_spy_png() {
+    let mut buf = Vec::new();
+    spy_png(&mut buf, &lower_triangle(), 2, 2).unwrap();
+
+    let mut reader = png::Decoder::new(std::io::Cursor::new(buf))
+        .read_info()
+        .unwrap();
+    let mut pixels = vec![0; reader.output_buffer_size().unwrap()];
+    let info = reader.next_frame(&mut pixels).unwrap();
+    assert_eq!((info.width, info.height), (2, 2));
+    assert_eq!(&pixels[..info.buffer_size()], [64, 255, 0, 64]);
+
+    let mut buf = Vec::new();
+    spy_png(&mut buf, &CsrMatrix::<f64>::new(0, 5), 3, 3).unwrap();
+    assert!(buf.starts_with(b"\x89PNG"));
+}
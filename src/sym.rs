@@ -0,0 +1,282 @@
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::coo::CooMatrix;
+use crate::csr::CsrMatrix;
+use crate::error::SparseError;
+use crate::scalar::Scalar;
+
+/// A symmetric matrix stored by its upper triangle, diagonal included, in CSR form.
+///
+/// The strictly lower triangle mirrors the upper one and isn't stored, which halves the memory
+/// of a symmetric matrix. The matrix is symmetric, `Aᵀ = A`, also for complex types: no
+/// conjugation happens when an entry is mirrored.
+#[derive(Clone, Debug)]
+pub struct SymCsrMatrix<T = f64> {
+    upper: CsrMatrix<T>,
+}
+
+impl<T: Scalar> SymCsrMatrix<T> {
+    /// Store a square matrix that is exactly symmetric. Fails with
+    /// [`SparseError::DimensionMismatch`] if `a` isn't square and with
+    /// [`SparseError::InvalidStructure`] if it isn't symmetric. O(nnz + n).
+    pub fn from_csr(a: &CsrMatrix<T>) -> Result<Self, SparseError> {
+        check_square(a)?;
+        let t = a.transpose();
+        if t.indptr() != a.indptr() || t.indices() != a.indices() || t.data() != a.data() {
+            return Err(SparseError::InvalidStructure(
+                "the matrix isn't symmetric".to_string(),
+            ));
+        }
+        Self::from_upper(a)
+    }
+
+    /// Take the upper triangle and diagonal of a square matrix and mirror them, ignoring what
+    /// is below the diagonal. Fails with [`SparseError::DimensionMismatch`] if `a` isn't square.
+    pub fn from_upper(a: &CsrMatrix<T>) -> Result<Self, SparseError> {
+        check_square(a)?;
+        let n = a.nrows();
+        let mut indptr = Vec::with_capacity(n + 1);
+        let mut indices = Vec::new();
+        let mut data = Vec::new();
+        indptr.push(0);
+        for i in 0..n {
+            let (cols, values) = a.row(i);
+            let start = cols.partition_point(|&j| j < i);
+            indices.extend_from_slice(&cols[start..]);
+            data.extend_from_slice(&values[start..]);
+            indptr.push(indices.len());
+        }
+
+        Ok(Self {
+            upper: CsrMatrix::from_parts(n, n, indptr, indices, data),
+        })
+    }
+
+    /// Take the lower triangle and diagonal of a square matrix and mirror them, ignoring what
+    /// is above the diagonal. Fails with [`SparseError::DimensionMismatch`] if `a` isn't square.
+    pub fn from_lower(a: &CsrMatrix<T>) -> Result<Self, SparseError> {
+        check_square(a)?;
+        Self::from_upper(&a.transpose())
+    }
+
+    /// Return the number of rows and columns
+    pub fn n(&self) -> usize {
+        self.upper.nrows()
+    }
+
+    /// Return `(n, n)`
+    pub fn shape(&self) -> (usize, usize) {
+        self.upper.shape()
+    }
+
+    /// Return the number of stored entries, those of the upper triangle and the diagonal
+    pub fn nnz(&self) -> usize {
+        self.upper.nnz()
+    }
+
+    /// Return the stored upper triangle
+    pub fn upper(&self) -> &CsrMatrix<T> {
+        &self.upper
+    }
+
+    /// Return the entry at `(i, j)`, looked up in the upper triangle.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` or `j` is out of bounds.
+    pub fn get(&self, i: usize, j: usize) -> T {
+        self.upper.get(i.min(j), i.max(j))
+    }
+
+    /// Multiply the matrix by the dense vector `x` in one pass over the stored triangle: an
+    /// entry `a` at `(i, j)` above the diagonal adds `a x[j]` to `y[i]` and, for the mirrored
+    /// entry, `a x[i]` to `y[j]`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `x.len() != self.n()`.
+    pub fn mul_vec(&self, x: &[T]) -> Vec<T> {
+        assert_eq!(x.len(), self.n(), "x has the wrong length");
+
+        let mut y = vec![T::zero(); self.n()];
+        for i in 0..self.n() {
+            let (cols, values) = self.upper.row(i);
+            let xi = x[i];
+            let mut sum = T::zero();
+            for (&j, &v) in cols.iter().zip(values) {
+                sum += v * x[j];
+                if j != i {
+                    y[j] += v * xi;
+                }
+            }
+            y[i] += sum;
+        }
+        y
+    }
+
+    /// Expand to a CSR matrix holding both triangles. O(nnz + n).
+    pub fn to_csr(&self) -> CsrMatrix<T> {
+        let mut coo = CooMatrix::new(self.n(), self.n());
+        for i in 0..self.n() {
+            let (cols, values) = self.upper.row(i);
+            for (&j, &v) in cols.iter().zip(values) {
+                coo.push(i, j, v);
+                if j != i {
+                    coo.push(j, i, v);
+                }
+            }
+        }
+        coo.to_csr()
+    }
+}
+
+fn check_square<T: Scalar>(a: &CsrMatrix<T>) -> Result<(), SparseError> {
+    if a.nrows() != a.ncols() {
+        return Err(SparseError::DimensionMismatch {
+            expected: a.nrows(),
+            found: a.ncols(),
+        });
+    }
+    Ok(())
+}
+
+/// Two matrices are equal when their stored upper triangles are.
+impl<T: Scalar> PartialEq for SymCsrMatrix<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.upper == other.upper
+    }
+}
+
+/// Compared by the stored upper triangles, like [`PartialEq`].
+#[cfg(feature = "approx")]
+impl approx::AbsDiffEq for SymCsrMatrix<f64> {
+    type Epsilon = f64;
+
+    fn default_epsilon() -> f64 {
+        f64::default_epsilon()
+    }
+
+    fn abs_diff_eq(&self, other: &Self, epsilon: f64) -> bool {
+        self.upper.abs_diff_eq(&other.upper, epsilon)
+    }
+}
+
+#[cfg(feature = "approx")]
+impl approx::RelativeEq for SymCsrMatrix<f64> {
+    fn default_max_relative() -> f64 {
+        f64::default_max_relative()
+    }
+
+    fn relative_eq(&self, other: &Self, epsilon: f64, max_relative: f64) -> bool {
+        self.upper.relative_eq(&other.upper, epsilon, max_relative)
+    }
+}
+
+#[cfg(feature = "approx")]
+impl approx::UlpsEq for SymCsrMatrix<f64> {
+    fn default_max_ulps() -> u32 {
+        f64::default_max_ulps()
+    }
+
+    fn ulps_eq(&self, other: &Self, epsilon: f64, max_ulps: u32) -> bool {
+        self.upper.ulps_eq(&other.upper, epsilon, max_ulps)
+    }
+}
+
+#[test]
+fn test_sym_csr_matrix() {
+    #[rustfmt::skip]
+    let dense = [
+        4.0, 1.0, 0.0, 2.0,
+        1.0, 5.0, 3.0, 0.0,
+        0.0, 3.0, 6.0, 0.0,
+        2.0, 0.0, 0.0, 7.0,
+    ];
+    let a = CsrMatrix::from_dense(4, 4, &dense).unwrap();
+    let sym = SymCsrMatrix::from_csr(&a).unwrap();
+    assert_eq!(sym.nnz(), 7);
+    assert_eq!(sym.get(3, 0), 2.0);
+    assert_eq!(sym.get(2, 0), 0.0);
+    assert_eq!(sym.to_csr().to_dense(), dense);
+
+    let x = [1.0, -2.0, 0.5, 3.0];
+    assert_eq!(sym.mul_vec(&x), a.mul_vec(&x));
+
+    // Either triangle of a nonsymmetric matrix.
+    let mut skewed = dense;
+    skewed[4] = -1.0;
+    let b = CsrMatrix::from_dense(4, 4, &skewed).unwrap();
+    assert!(matches!(
+        SymCsrMatrix::from_csr(&b),
+        Err(SparseError::InvalidStructure(_))
+    ));
+    assert_eq!(
+        SymCsrMatrix::from_upper(&b).unwrap().to_csr().to_dense(),
+        dense
+    );
+    assert_eq!(SymCsrMatrix::from_lower(&b).unwrap().get(0, 1), -1.0);
+
+    let wide = CsrMatrix::<f64>::from_dense(1, 2, &[1.0, 0.0]).unwrap();
+    assert_eq!(
+        SymCsrMatrix::from_upper(&wide).unwrap_err(),
+        SparseError::DimensionMismatch {
+            expected: 1,
+            found: 2
+        }
+    );
+}
+
+#[test]
+fn test_sym_csr_matrix_lower_only() {
+    // The lower triangle of a symmetric matrix, as files and Cholesky codes often hold it.
+    #[rustfmt::skip]
+    let lower = [
+        4.0, 0.0, 0.0, 0.0,
+        1.0, 5.0, 0.0, 0.0,
+        0.0, 3.0, 0.0, 0.0,
+        2.0, 0.0, -1.0, 7.0,
+    ];
+    #[rustfmt::skip]
+    let full = [
+        4.0, 1.0, 0.0, 2.0,
+        1.0, 5.0, 3.0, 0.0,
+        0.0, 3.0, 0.0, -1.0,
+        2.0, 0.0, -1.0, 7.0,
+    ];
+    let a = CsrMatrix::from_dense(4, 4, &lower).unwrap();
+
+    // Mirrored into the stored upper triangle, which is its transpose, including the row whose
+    // diagonal is missing.
+    let sym = SymCsrMatrix::from_lower(&a).unwrap();
+    assert_eq!(sym.upper(), &a.transpose());
+    assert_eq!(sym.nnz(), a.nnz());
+    assert_eq!(
+        (sym.get(0, 3), sym.get(3, 0), sym.get(2, 2)),
+        (2.0, 2.0, 0.0)
+    );
+    assert_eq!(sym.to_csr().to_dense(), full);
+    let x = [1.0, -2.0, 0.5, 3.0];
+    let expected = CsrMatrix::from_dense(4, 4, &full).unwrap().mul_vec(&x);
+    assert_eq!(sym.mul_vec(&x), expected);
+    assert_eq!(
+        sym,
+        SymCsrMatrix::from_csr(&CsrMatrix::from_dense(4, 4, &full).unwrap()).unwrap()
+    );
+
+    // Read as the upper triangle only the diagonal is left, and as a whole it isn't symmetric.
+    let diagonal = SymCsrMatrix::from_upper(&a).unwrap();
+    assert_eq!(diagonal.nnz(), 3);
+    assert_eq!(diagonal.mul_vec(&x), [4.0, -10.0, 0.0, 21.0]);
+    assert_eq!(
+        SymCsrMatrix::from_csr(&a).unwrap_err(),
+        SparseError::InvalidStructure("the matrix isn't symmetric".to_string())
+    );
+
+    // A strictly lower matrix keeps no diagonal entry at all.
+    let strict = CsrMatrix::from_dense(2, 2, &[0.0, 0.0, 3.0, 0.0]).unwrap();
+    let sym = SymCsrMatrix::from_lower(&strict).unwrap();
+    assert_eq!(sym.upper().indices(), [1]);
+    assert_eq!(sym.mul_vec(&[1.0, 2.0]), [6.0, 3.0]);
+}
@@ -0,0 +1,84 @@
+//! Fill-in statistics of a sparse elimination, step by step, behind the `telemetry` feature.
+//!
+//! Repeated row updates densify a sparse factorization, and once the rows fill in the cost
+//! grows with the square of their length rather than their pattern.
+//! [`LilMatrix::lu_in_place_traced`] records how many entries every step of its elimination
+//! created and cancelled into a [`FillTrace`], which prints as a table and serializes with the
+//! `serde` feature, so that the step where the factors blow up can be found without a profiler.
+//!
+//! [`LilMatrix::lu_in_place_traced`]: crate::lil::LilMatrix::lu_in_place_traced
+
+use alloc::vec::Vec;
+use core::fmt;
+
+/// The fill-in of one step of an elimination: row `step` after every earlier row was
+/// subtracted from it.
+///
+/// The matrix holds `nnz = previous nnz + fill_in − cancelled` entries after the step.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FillStep {
+    /// The row eliminated
+    pub step: usize,
+    /// Number of earlier rows subtracted from it
+    pub updates: usize,
+    /// Number of entries the updates created where nothing was stored
+    pub fill_in: usize,
+    /// Number of entries the updates cancelled to zero and removed
+    pub cancelled: usize,
+    /// Number of stored entries of the matrix after the step
+    pub nnz: usize,
+    /// `nnz` divided by the number of positions of the matrix
+    pub density: f64,
+}
+
+/// The steps of an elimination with their fill-in, from
+/// [`LilMatrix::lu_in_place_traced`](crate::lil::LilMatrix::lu_in_place_traced).
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FillTrace {
+    /// Shape of the matrix
+    pub shape: (usize, usize),
+    /// Number of stored entries before the first step
+    pub initial_nnz: usize,
+    /// One entry per step, in order
+    pub steps: Vec<FillStep>,
+}
+
+impl FillTrace {
+    /// Return the number of entries all steps created
+    pub fn total_fill_in(&self) -> usize {
+        self.steps.iter().map(|s| s.fill_in).sum()
+    }
+
+    /// Return the number of stored entries after the last step
+    pub fn final_nnz(&self) -> usize {
+        self.steps.last().map_or(self.initial_nnz, |s| s.nnz)
+    }
+}
+
+impl fmt::Display for FillTrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (m, n) = self.shape;
+        writeln!(
+            f,
+            "fill trace: {m}x{n}, {} entries before, {} after, {} filled in",
+            self.initial_nnz,
+            self.final_nnz(),
+            self.total_fill_in()
+        )?;
+        writeln!(
+            f,
+            "  {:>8} {:>8} {:>8} {:>8} {:>10} {:>8}",
+            "step", "updates", "fill-in", "cancel", "nnz", "density"
+        )?;
+        for s in &self.steps {
+            writeln!(
+                f,
+                "  {:>8} {:>8} {:>8} {:>8} {:>10} {:>8.4}",
+                s.step, s.updates, s.fill_in, s.cancelled, s.nnz, s.density
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,182 @@
+//! Deterministic pseudo-random data for the unit tests.
+
+/// A linear congruential generator, enough to make test inputs without extra dependencies.
+pub(crate) struct Lcg(u64);
+
+impl Lcg {
+    pub(crate) fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    /// Return a value in `0..bound`.
+    pub(crate) fn below(&mut self, bound: usize) -> usize {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (self.0 >> 33) as usize % bound
+    }
+
+    /// Return a value in `[-1, 1)`.
+    pub(crate) fn uniform(&mut self) -> f64 {
+        self.below(1 << 20) as f64 / (1 << 19) as f64 - 1.0
+    }
+
+    /// Return a row-major `nrows` by `ncols` array where roughly `density` of the entries are
+    /// non-zero.
+    pub(crate) fn dense(&mut self, nrows: usize, ncols: usize, density: f64) -> Vec<f64> {
+        (0..nrows * ncols)
+            .map(|_| {
+                if (self.below(1000) as f64) < density * 1000.0 {
+                    self.uniform()
+                } else {
+                    0.0
+                }
+            })
+            .collect()
+    }
+}
+
+/// Multiply a row-major dense matrix by a dense vector.
+pub(crate) fn dense_mul_vec(nrows: usize, ncols: usize, a: &[f64], x: &[f64]) -> Vec<f64> {
+    (0..nrows)
+        .map(|i| (0..ncols).map(|j| a[i * ncols + j] * x[j]).sum())
+        .collect()
+}
+
+/// Assert that two slices agree entry by entry within `tol`.
+pub(crate) fn assert_close(a: &[f64], b: &[f64], tol: f64) {
+    assert_eq!(a.len(), b.len());
+    for (k, (x, y)) in a.iter().zip(b).enumerate() {
+        assert!((x - y).abs() <= tol, "entry {k}: {x} != {y}");
+    }
+}
+
+/// Solve the damped normal equations `(AᵀA + damp² I) x = Aᵀ b` densely, the reference for the
+/// least-squares solvers.
+pub(crate) fn normal_equations(a: &crate::csr::CsrMatrix<f64>, b: &[f64], damp: f64) -> Vec<f64> {
+    let n = a.ncols();
+    let at = a.transpose();
+    let mut normal = (&at * a).to_dense();
+    for i in 0..n {
+        normal[i * n + i] += damp * damp;
+    }
+    let mut rhs = at.mul_vec(b);
+    assert!(cholesky_solve(n, &mut normal, &mut rhs));
+    rhs
+}
+
+/// Return the 2-norm of a row-major dense matrix, the square root of the largest eigenvalue of
+/// `AᵀA` found by cyclic Jacobi rotations, the brute-force reference for the norm estimates.
+pub(crate) fn dense_norm2(nrows: usize, ncols: usize, a: &[f64]) -> f64 {
+    let n = ncols;
+    let mut g: Vec<f64> = (0..n * n)
+        .map(|k| {
+            (0..nrows)
+                .map(|i| a[i * n + k / n] * a[i * n + k % n])
+                .sum()
+        })
+        .collect();
+    for _sweep in 0..100 {
+        let off: f64 = (0..n * n)
+            .filter(|k| k / n != k % n)
+            .map(|k| g[k] * g[k])
+            .sum();
+        if off < 1e-30 {
+            break;
+        }
+        for p in 0..n {
+            for q in p + 1..n {
+                if g[p * n + q] == 0.0 {
+                    continue;
+                }
+                // Rotate the (p, q) entry of G to zero.
+                let theta = (g[q * n + q] - g[p * n + p]) / (2.0 * g[p * n + q]);
+                let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+                let t = if theta == 0.0 { 1.0 } else { t };
+                let (c, s) = (1.0 / (t * t + 1.0).sqrt(), t / (t * t + 1.0).sqrt());
+                for k in 0..n {
+                    let (gkp, gkq) = (g[k * n + p], g[k * n + q]);
+                    g[k * n + p] = c * gkp - s * gkq;
+                    g[k * n + q] = s * gkp + c * gkq;
+                }
+                for k in 0..n {
+                    let (gpk, gqk) = (g[p * n + k], g[q * n + k]);
+                    g[p * n + k] = c * gpk - s * gqk;
+                    g[q * n + k] = s * gpk + c * gqk;
+                }
+            }
+        }
+    }
+    (0..n).map(|i| g[i * n + i]).fold(0.0, f64::max).sqrt()
+}
+
+/// Solve `A x = b` in place for a dense row-major `n` by `n` matrix by Gaussian elimination with
+/// partial pivoting, leaving `x` in `b`: the reference for the sparse factorizations. Fails
+/// when a column has no nonzero pivot left.
+pub(crate) fn lu_solve(
+    n: usize,
+    a: &mut [f64],
+    b: &mut [f64],
+) -> Result<(), crate::error::SparseError> {
+    for k in 0..n {
+        let p = (k..n)
+            .max_by(|&i, &j| a[i * n + k].abs().total_cmp(&a[j * n + k].abs()))
+            .unwrap_or(k);
+        if a[p * n + k] == 0.0 {
+            return Err(crate::error::SparseError::ZeroPivot { index: k });
+        }
+        if p != k {
+            for j in 0..n {
+                a.swap(k * n + j, p * n + j);
+            }
+            b.swap(k, p);
+        }
+
+        let pivot = a[k * n + k];
+        for i in k + 1..n {
+            let l = a[i * n + k] / pivot;
+            if l != 0.0 {
+                for j in k + 1..n {
+                    a[i * n + j] -= l * a[k * n + j];
+                }
+                b[i] -= l * b[k];
+            }
+        }
+    }
+
+    for i in (0..n).rev() {
+        let sum: f64 = (i + 1..n).map(|j| a[i * n + j] * b[j]).sum();
+        b[i] = (b[i] - sum) / a[i * n + i];
+    }
+    Ok(())
+}
+
+/// Solve `A x = b` in place for a dense row-major symmetric positive definite matrix with the
+/// Cholesky factorization `A = L Lᵀ`, overwriting the lower triangle of `a` with `L` and `b`
+/// with `x`. Return false, with `a` and `b` in an unspecified state, when a pivot is not
+/// positive, that is when `A` turns out not to be positive definite.
+pub(crate) fn cholesky_solve(n: usize, a: &mut [f64], b: &mut [f64]) -> bool {
+    for j in 0..n {
+        let d = a[j * n + j] - (0..j).map(|k| a[j * n + k] * a[j * n + k]).sum::<f64>();
+        if d.is_nan() || d <= 0.0 {
+            return false;
+        }
+        let d = d.sqrt();
+        a[j * n + j] = d;
+        for i in j + 1..n {
+            let s: f64 = (0..j).map(|k| a[i * n + k] * a[j * n + k]).sum();
+            a[i * n + j] = (a[i * n + j] - s) / d;
+        }
+    }
+
+    for i in 0..n {
+        let s: f64 = (0..i).map(|k| a[i * n + k] * b[k]).sum();
+        b[i] = (b[i] - s) / a[i * n + i];
+    }
+    for i in (0..n).rev() {
+        let s: f64 = (i + 1..n).map(|k| a[k * n + i] * b[k]).sum();
+        b[i] = (b[i] - s) / a[i * n + i];
+    }
+    true
+}
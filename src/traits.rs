@@ -0,0 +1,516 @@
+//! Traits shared by the storage formats, for code written once over any of them: the shape,
+//! the stored entry count and the product with a dense vector of a [`SparseMatrix`], the rows
+//! of a [`RowAccess`] matrix, and the stored components of a [`SparseVector`].
+//!
+//! Every matrix format and every [view](crate::view) implements [`SparseMatrix`], and for f64
+//! also [`LinearOperator`](crate::operator::LinearOperator), the only thing the iterative
+//! solvers need. Formats whose own `mul_vec` allocates go through it for `mul_vec_into` too.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::banded::BandedMatrix;
+use crate::bsr::BsrMatrix;
+use crate::coo::CooMatrix;
+use crate::csc::CscMatrix;
+use crate::csr::{CsrMatrix, CsrRow};
+use crate::dia::DiaMatrix;
+use crate::dok::DokMatrix;
+use crate::ell::EllMatrix;
+use crate::hyb::HybMatrix;
+use crate::index::IndexType;
+use crate::lil::LilMatrix;
+#[cfg(feature = "mmap")]
+use crate::ooc::OocCsrMatrix;
+use crate::scalar::Scalar;
+use crate::skyline::SkylineMatrix;
+use crate::sym::SymCsrMatrix;
+use crate::vec::PackedVec;
+use crate::view::{ScaledView, SubmatrixView, TransposedView};
+
+/// A sparse matrix in any storage format.
+pub trait SparseMatrix<T: Scalar> {
+    /// Return the number of rows
+    fn nrows(&self) -> usize;
+
+    /// Return the number of columns
+    fn ncols(&self) -> usize;
+
+    /// Return `(nrows, ncols)`
+    fn shape(&self) -> (usize, usize) {
+        (self.nrows(), self.ncols())
+    }
+
+    /// Return the number of stored entries. Only one triangle counts for the formats that
+    /// store one, and the zeros inside BSR blocks count but not the padding of ELL and DIA.
+    fn nnz(&self) -> usize;
+
+    /// Compute `y = A x`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `x.len() != self.ncols()` or `y.len() != self.nrows()`.
+    fn mul_vec_into(&self, x: &[T], y: &mut [T]);
+
+    /// Return `A x`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `x.len() != self.ncols()`.
+    fn mul_vec(&self, x: &[T]) -> Vec<T> {
+        let mut y = vec![T::zero(); self.nrows()];
+        self.mul_vec_into(x, &mut y);
+        y
+    }
+}
+
+/// A sparse matrix whose rows can be read one at a time, without copying the matrix.
+pub trait RowAccess<T: Scalar>: SparseMatrix<T> {
+    /// Iterate over the stored entries of row `i` as `(column, value)` pairs, by increasing
+    /// column.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i >= self.nrows()`.
+    fn row_entries(&self, i: usize) -> impl Iterator<Item = (usize, T)> + '_;
+}
+
+/// A sparse vector, holding some of the components of a vector of length `full_len`.
+pub trait SparseVector<T: Scalar> {
+    /// Return the length of the full vector
+    fn full_len(&self) -> usize;
+
+    /// Return the number of stored components
+    fn nnz(&self) -> usize;
+
+    /// Return component `i`, zero when it is not stored.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i >= self.full_len()`.
+    fn get(&self, i: usize) -> T;
+
+    /// Iterate over the stored `(index, value)` pairs.
+    fn iter(&self) -> impl Iterator<Item = (usize, T)> + '_;
+
+    /// Return the inner product `Σ self[i] · x[i]` with a dense vector.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `x.len() != self.full_len()`.
+    fn dot_dense(&self, x: &[T]) -> T {
+        assert_eq!(x.len(), self.full_len(), "x has the wrong length");
+        let mut sum = T::zero();
+        for (i, v) in self.iter() {
+            sum += v * x[i];
+        }
+        sum
+    }
+
+    /// Return the full vector.
+    fn scatter(&self) -> Vec<T> {
+        let mut dense = vec![T::zero(); self.full_len()];
+        for (i, v) in self.iter() {
+            dense[i] = v;
+        }
+        dense
+    }
+}
+
+/// Check the lengths of the operands of `y = A x`.
+fn check_mul_vec<T: Scalar>(a: &impl SparseMatrix<T>, x: &[T], y: &[T]) {
+    assert_eq!(x.len(), a.ncols(), "x has the wrong length");
+    assert_eq!(y.len(), a.nrows(), "y has the wrong length");
+}
+
+/// Implement [`SparseMatrix`] for formats with `nrows`, `ncols`, `nnz` and an allocating
+/// `mul_vec` of their own.
+macro_rules! impl_sparse_matrix {
+    ($($t:ident),*) => {$(
+        impl<T: Scalar> SparseMatrix<T> for $t<T> {
+            fn nrows(&self) -> usize {
+                $t::nrows(self)
+            }
+
+            fn ncols(&self) -> usize {
+                $t::ncols(self)
+            }
+
+            fn nnz(&self) -> usize {
+                $t::nnz(self)
+            }
+
+            fn mul_vec_into(&self, x: &[T], y: &mut [T]) {
+                check_mul_vec(self, x, y);
+                y.copy_from_slice(&$t::mul_vec(self, x));
+            }
+
+            fn mul_vec(&self, x: &[T]) -> Vec<T> {
+                $t::mul_vec(self, x)
+            }
+        }
+    )*};
+}
+
+impl_sparse_matrix!(BsrMatrix, DiaMatrix, EllMatrix, HybMatrix);
+
+/// Implement [`SparseMatrix`] for the square formats, which only know their order `n`.
+macro_rules! impl_sparse_matrix_square {
+    ($($t:ident => $nnz:ident),*) => {$(
+        impl<T: Scalar> SparseMatrix<T> for $t<T> {
+            fn nrows(&self) -> usize {
+                self.n()
+            }
+
+            fn ncols(&self) -> usize {
+                self.n()
+            }
+
+            fn nnz(&self) -> usize {
+                self.$nnz()
+            }
+
+            fn mul_vec_into(&self, x: &[T], y: &mut [T]) {
+                check_mul_vec(self, x, y);
+                y.copy_from_slice(&$t::mul_vec(self, x));
+            }
+
+            fn mul_vec(&self, x: &[T]) -> Vec<T> {
+                $t::mul_vec(self, x)
+            }
+        }
+    )*};
+}
+
+impl_sparse_matrix_square!(BandedMatrix => nnz, SkylineMatrix => profile, SymCsrMatrix => nnz);
+
+impl<T: Scalar, I: IndexType> SparseMatrix<T> for CsrMatrix<T, I> {
+    fn nrows(&self) -> usize {
+        CsrMatrix::nrows(self)
+    }
+
+    fn ncols(&self) -> usize {
+        CsrMatrix::ncols(self)
+    }
+
+    fn nnz(&self) -> usize {
+        CsrMatrix::nnz(self)
+    }
+
+    fn mul_vec_into(&self, x: &[T], y: &mut [T]) {
+        CsrMatrix::mul_vec_into(self, x, y)
+    }
+}
+
+impl<T: Scalar, I: IndexType> SparseMatrix<T> for CscMatrix<T, I> {
+    fn nrows(&self) -> usize {
+        CscMatrix::nrows(self)
+    }
+
+    fn ncols(&self) -> usize {
+        CscMatrix::ncols(self)
+    }
+
+    fn nnz(&self) -> usize {
+        CscMatrix::nnz(self)
+    }
+
+    /// Scatter column `j` scaled by `x[j]` into `y`, column after column. O(nnz + nrows).
+    fn mul_vec_into(&self, x: &[T], y: &mut [T]) {
+        check_mul_vec(self, x, y);
+        y.fill(T::zero());
+        for (j, &xj) in x.iter().enumerate() {
+            let (rows, values) = self.col(j);
+            I::scatter_add(xj, values, rows, y);
+        }
+    }
+}
+
+impl<T: Scalar> SparseMatrix<T> for CooMatrix<T> {
+    fn nrows(&self) -> usize {
+        CooMatrix::nrows(self)
+    }
+
+    fn ncols(&self) -> usize {
+        CooMatrix::ncols(self)
+    }
+
+    /// Duplicated positions count once per triplet.
+    fn nnz(&self) -> usize {
+        CooMatrix::nnz(self)
+    }
+
+    /// Duplicated positions add up, as on conversion. O(nnz + nrows).
+    fn mul_vec_into(&self, x: &[T], y: &mut [T]) {
+        check_mul_vec(self, x, y);
+        y.fill(T::zero());
+        for (i, j, v) in self.triplets() {
+            y[i] += v * x[j];
+        }
+    }
+}
+
+impl<T: Scalar> SparseMatrix<T> for DokMatrix<T> {
+    fn nrows(&self) -> usize {
+        DokMatrix::nrows(self)
+    }
+
+    fn ncols(&self) -> usize {
+        DokMatrix::ncols(self)
+    }
+
+    fn nnz(&self) -> usize {
+        DokMatrix::nnz(self)
+    }
+
+    /// The entries are visited in no particular order, so the rounding of the sums may change
+    /// from one matrix to another holding the same entries. O(nnz + nrows).
+    fn mul_vec_into(&self, x: &[T], y: &mut [T]) {
+        check_mul_vec(self, x, y);
+        y.fill(T::zero());
+        for (i, j, v) in self.triplets() {
+            y[i] += v * x[j];
+        }
+    }
+}
+
+impl<T: Scalar> SparseMatrix<T> for LilMatrix<T> {
+    fn nrows(&self) -> usize {
+        LilMatrix::nrows(self)
+    }
+
+    fn ncols(&self) -> usize {
+        LilMatrix::ncols(self)
+    }
+
+    fn nnz(&self) -> usize {
+        LilMatrix::nnz(self)
+    }
+
+    fn mul_vec_into(&self, x: &[T], y: &mut [T]) {
+        check_mul_vec(self, x, y);
+        for (i, yi) in y.iter_mut().enumerate() {
+            let mut sum = T::zero();
+            for &(j, v) in self.row(i) {
+                sum += v * x[j];
+            }
+            *yi = sum;
+        }
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl SparseMatrix<f64> for OocCsrMatrix {
+    fn nrows(&self) -> usize {
+        OocCsrMatrix::nrows(self)
+    }
+
+    fn ncols(&self) -> usize {
+        OocCsrMatrix::ncols(self)
+    }
+
+    fn nnz(&self) -> usize {
+        OocCsrMatrix::nnz(self)
+    }
+
+    fn mul_vec_into(&self, x: &[f64], y: &mut [f64]) {
+        OocCsrMatrix::mul_vec_into(self, x, y)
+    }
+}
+
+impl<T: Scalar> SparseMatrix<T> for TransposedView<'_, T> {
+    fn nrows(&self) -> usize {
+        TransposedView::nrows(self)
+    }
+
+    fn ncols(&self) -> usize {
+        TransposedView::ncols(self)
+    }
+
+    fn nnz(&self) -> usize {
+        self.inner().nnz()
+    }
+
+    fn mul_vec_into(&self, x: &[T], y: &mut [T]) {
+        self.inner().mul_vec_transposed_into(x, y)
+    }
+}
+
+impl<T: Scalar> SparseMatrix<T> for SubmatrixView<'_, T> {
+    fn nrows(&self) -> usize {
+        SubmatrixView::nrows(self)
+    }
+
+    fn ncols(&self) -> usize {
+        SubmatrixView::ncols(self)
+    }
+
+    /// Counts the entries inside the block. O(nrows × log(row length)).
+    fn nnz(&self) -> usize {
+        (0..self.nrows()).map(|i| self.row_parts(i).0.len()).sum()
+    }
+
+    fn mul_vec_into(&self, x: &[T], y: &mut [T]) {
+        check_mul_vec(self, x, y);
+        let offset = self.col_offset();
+        for (i, yi) in y.iter_mut().enumerate() {
+            let (cols, values) = self.row_parts(i);
+            let mut sum = T::zero();
+            for (&j, &v) in cols.iter().zip(values) {
+                sum += v * x[j - offset];
+            }
+            *yi = sum;
+        }
+    }
+}
+
+impl<T: Scalar> SparseMatrix<T> for ScaledView<'_, T> {
+    fn nrows(&self) -> usize {
+        ScaledView::nrows(self)
+    }
+
+    fn ncols(&self) -> usize {
+        ScaledView::ncols(self)
+    }
+
+    /// Counts the entries the scaling leaves nonzero. O(nnz + nrows).
+    fn nnz(&self) -> usize {
+        (0..self.nrows()).map(|i| self.scaled_row(i).count()).sum()
+    }
+
+    fn mul_vec_into(&self, x: &[T], y: &mut [T]) {
+        self.inner().mul_vec_into(x, y);
+        let alpha = self.alpha();
+        y.iter_mut().for_each(|yi| *yi = alpha * *yi);
+    }
+}
+
+impl<T: Scalar> RowAccess<T> for CsrMatrix<T> {
+    fn row_entries(&self, i: usize) -> impl Iterator<Item = (usize, T)> + '_ {
+        let (cols, values) = self.row(i);
+        cols.iter().copied().zip(values.iter().copied())
+    }
+}
+
+impl<T: Scalar> RowAccess<T> for SubmatrixView<'_, T> {
+    fn row_entries(&self, i: usize) -> impl Iterator<Item = (usize, T)> + '_ {
+        let offset = self.col_offset();
+        let (cols, values) = self.row_parts(i);
+        cols.iter()
+            .map(move |&j| j - offset)
+            .zip(values.iter().copied())
+    }
+}
+
+impl<T: Scalar> RowAccess<T> for ScaledView<'_, T> {
+    fn row_entries(&self, i: usize) -> impl Iterator<Item = (usize, T)> + '_ {
+        self.scaled_row(i)
+    }
+}
+
+impl<T: Scalar, I: IndexType> SparseVector<T> for PackedVec<T, I> {
+    fn full_len(&self) -> usize {
+        PackedVec::full_len(self)
+    }
+
+    fn nnz(&self) -> usize {
+        self.len()
+    }
+
+    fn get(&self, i: usize) -> T {
+        PackedVec::get(self, i)
+    }
+
+    fn iter(&self) -> impl Iterator<Item = (usize, T)> + '_ {
+        PackedVec::iter(self)
+    }
+
+    fn dot_dense(&self, x: &[T]) -> T {
+        PackedVec::dot_dense(self, x)
+    }
+
+    fn scatter(&self) -> Vec<T> {
+        PackedVec::scatter(self)
+    }
+}
+
+impl<T: Scalar> SparseVector<T> for CsrRow<'_, T> {
+    fn full_len(&self) -> usize {
+        CsrRow::full_len(self)
+    }
+
+    fn nnz(&self) -> usize {
+        CsrRow::nnz(self)
+    }
+
+    fn get(&self, j: usize) -> T {
+        assert!(j < self.full_len(), "column {j} out of bounds");
+        CsrRow::get(self, j)
+    }
+
+    fn iter(&self) -> impl Iterator<Item = (usize, T)> + '_ {
+        CsrRow::iter(self)
+    }
+
+    fn dot_dense(&self, x: &[T]) -> T {
+        self.dot(x)
+    }
+}
+
+#[test]
+fn test_sparse_matrix_formats() {
+    use crate::test_util::Lcg;
+
+    // Symmetric, so that the formats storing one triangle hold the same matrix.
+    let a = CsrMatrix::tridiagonal(-1.0, 4.0, -1.0, 6);
+    let mut rng = Lcg::new(7);
+    let x: Vec<f64> = (0..6).map(|_| rng.uniform()).collect();
+    let expected = a.mul_vec(&x);
+
+    fn check(m: &impl SparseMatrix<f64>, x: &[f64], expected: &[f64], nnz: usize) {
+        assert_eq!(m.shape(), (6, 6));
+        assert_eq!(m.nnz(), nnz);
+        crate::test_util::assert_close(&m.mul_vec(x), expected, 1e-14);
+        let mut y = vec![f64::NAN; 6];
+        m.mul_vec_into(x, &mut y);
+        crate::test_util::assert_close(&y, expected, 1e-14);
+    }
+
+    let mut dok = DokMatrix::new(6, 6);
+    let mut coo = CooMatrix::new(6, 6);
+    for row in a.row_iter() {
+        for (j, v) in row.iter() {
+            dok.insert(row.index(), j, v);
+            coo.push(row.index(), j, v);
+        }
+    }
+
+    check(&a, &x, &expected, 16);
+    check(&a.to_csc(), &x, &expected, 16);
+    check(&coo, &x, &expected, 16);
+    check(&dok, &x, &expected, 16);
+    check(&LilMatrix::from_csr(&a), &x, &expected, 16);
+    check(&BsrMatrix::from_csr(&a, 2, 2).unwrap(), &x, &expected, 28);
+    check(&DiaMatrix::from_csr(&a, 3).unwrap(), &x, &expected, 16);
+    check(&EllMatrix::from_csr(&a), &x, &expected, 16);
+    check(&HybMatrix::from_csr(&a), &x, &expected, 16);
+    check(&BandedMatrix::from_csr(&a).unwrap(), &x, &expected, 16);
+    check(&SkylineMatrix::from_csr(&a).unwrap(), &x, &expected, 11);
+    check(&SymCsrMatrix::from_csr(&a).unwrap(), &x, &expected, 11);
+}
+
+#[test]
+fn test_sparse_vector() {
+    fn check(v: &impl SparseVector<f64>) {
+        assert_eq!((v.full_len(), v.nnz()), (4, 2));
+        assert_eq!(v.get(1), 2.0);
+        assert_eq!(v.iter().collect::<Vec<_>>(), [(1, 2.0), (3, -1.0)]);
+        assert_eq!(v.dot_dense(&[5.0, 1.0, 5.0, 3.0]), -1.0);
+        assert_eq!(v.scatter(), [0.0, 2.0, 0.0, -1.0]);
+    }
+
+    let dense = [0.0, 2.0, 0.0, -1.0];
+    check(&PackedVec::gather(&dense));
+    let a = CsrMatrix::from_dense(1, 4, &dense).unwrap();
+    check(&a.row_view(0));
+}
@@ -0,0 +1,2070 @@
+use alloc::borrow::Cow;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::ops::Range;
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+
+#[cfg(not(feature = "std"))]
+use hashbrown::HashMap;
+
+use crate::display;
+use crate::error::SparseError;
+use crate::index::{convert_indices, IndexType};
+use crate::ops::merge::{self, for_each_intersection, kmerge, UnionStep};
+use crate::scalar::Scalar;
+
+/// A sparse vector may be held in a full-length vector of storage.
+/// But to economize in storage, we may pack the vector by holding the entries as real, interger
+/// pairs. Here we implement this idea by using a `T` array to store the data, and a usize array to
+/// store the index. The value type defaults to f64, but any [`Scalar`] (f32, integers, ...) works,
+/// and the index type can be narrowed to any [`IndexType`] with [`PackedVec::to_index_type`];
+/// the [`index`](crate::index) module lists what a vector with other indices supports.
+///
+/// In general, it is easy to see that the packed form requires less storage when the
+/// vector is at least 50% sparse. When the numerical values in the vector are held
+/// in double-precision arrays, or if the values are single-precision complex numbers,
+/// then the break-even point will drop to 25%, and will drop even further in the
+/// extended-precision real or double-precision complex case. Thus, the packed form
+/// generally requires far less storage in practical computations, where the vectors,
+/// at least at the beginning of the computation, are far less dense than 25%.
+#[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(
+        try_from = "RawPackedVec<T, I>",
+        bound(deserialize = "T: Scalar + serde::Deserialize<'de>, \
+                             I: IndexType + serde::Deserialize<'de>")
+    )
+)]
+pub struct PackedVec<T = f64, I = usize> {
+    /// Store the index of the non-zero data
+    index: Vec<I>,
+    /// Store the non-zero data
+    data: Vec<T>,
+
+    /// Store the original vector length for easier scatter back
+    full_length: usize,
+}
+
+/// Fill-in statistics of a single [`PackedVec::mul_add_tracked`] update.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FillStats {
+    /// Number of components that were zero before the update and are stored after it
+    pub fill_in: usize,
+    /// Number of stored components after the update
+    pub nnz: usize,
+    /// `nnz` divided by the full length of the vector
+    pub density: f64,
+}
+
+/// How [`PackedVec::gather_with`] treats NaN components of the full-length array.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NanPolicy {
+    /// Store NaN like any other non-zero value
+    Keep,
+    /// Treat NaN as zero and leave it out of the packed vector
+    Drop,
+    /// Fail with [`SparseError::NanValue`] on the first NaN
+    Error,
+}
+
+/// The helper storage [`PackedVec::mul_add_with`] uses to look up the entries of Y by their
+/// full-length index.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MulAddWorkspace {
+    /// Pick one of the others from the full length and the support of Y
+    Auto,
+    /// An array of the full length
+    Full,
+    /// An array spanning from the first to the last stored index of Y
+    Span,
+    /// A hash map holding exactly the stored indices of Y
+    Hash,
+}
+
+/// Vectors up to this full length always use a full-length workspace, which is the fastest.
+const FULL_WORKSPACE_LIMIT: usize = 1 << 16;
+
+/// Past [`FULL_WORKSPACE_LIMIT`], a span workspace is used while it has at most this many slots
+/// per entry of Y, and a hash map otherwise.
+const SPAN_SLOTS_PER_ENTRY: usize = 16;
+
+impl MulAddWorkspace {
+    /// Replace `Auto` by the workspace to use for adding a multiple of `y_vec` to a vector of
+    /// length `full_length`.
+    fn resolve<T: Scalar>(self, full_length: usize, y_vec: &PackedVec<T>) -> Self {
+        if self != Self::Auto {
+            return self;
+        }
+        if full_length <= FULL_WORKSPACE_LIMIT {
+            return Self::Full;
+        }
+
+        match y_vec.index_span() {
+            Some((first, last)) if last - first < SPAN_SLOTS_PER_ENTRY * y_vec.len() => Self::Span,
+            Some(_) => Self::Hash,
+            None => Self::Span,
+        }
+    }
+}
+
+/// Map from the full-length index of an entry of Y to its packed index.
+trait IndexMarks {
+    fn mark(&mut self, i: usize, k: usize);
+    fn take(&mut self, i: usize) -> Option<usize>;
+}
+
+impl IndexMarks for Vec<Option<usize>> {
+    fn mark(&mut self, i: usize, k: usize) {
+        self[i] = Some(k);
+    }
+
+    fn take(&mut self, i: usize) -> Option<usize> {
+        self[i].take()
+    }
+}
+
+/// Marks for the indices `first..first + marks.len()` only.
+struct SpanMarks {
+    first: usize,
+    marks: Vec<Option<usize>>,
+}
+
+impl IndexMarks for SpanMarks {
+    fn mark(&mut self, i: usize, k: usize) {
+        self.marks[i - self.first] = Some(k);
+    }
+
+    fn take(&mut self, i: usize) -> Option<usize> {
+        let slot = self.marks.get_mut(i.checked_sub(self.first)?)?;
+        slot.take()
+    }
+}
+
+impl IndexMarks for HashMap<usize, usize> {
+    fn mark(&mut self, i: usize, k: usize) {
+        self.insert(i, k);
+    }
+
+    fn take(&mut self, i: usize) -> Option<usize> {
+        self.remove(&i)
+    }
+}
+
+/// Turn `-0.0` into `+0.0` and leave every other value, NaN included, untouched. Results are
+/// stored through this so that the sign of a zero never shows up in comparisons of stored
+/// entries.
+fn positive_zero<T: Scalar>(x: T) -> T {
+    x + T::zero()
+}
+
+/// The fields of a [`PackedVec`] as deserialized, before they are checked.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct RawPackedVec<T, I> {
+    index: Vec<I>,
+    data: Vec<T>,
+    full_length: usize,
+}
+
+#[cfg(feature = "serde")]
+impl<T: Scalar, I: IndexType> TryFrom<RawPackedVec<T, I>> for PackedVec<T, I> {
+    type Error = SparseError;
+
+    fn try_from(raw: RawPackedVec<T, I>) -> Result<Self, SparseError> {
+        if raw.index.len() != raw.data.len() {
+            return Err(SparseError::DimensionMismatch {
+                expected: raw.index.len(),
+                found: raw.data.len(),
+            });
+        }
+        if let Some(&index) = raw.index.iter().find(|&&i| i.index() >= raw.full_length) {
+            return Err(SparseError::IndexOutOfBounds {
+                index: index.index(),
+                len: raw.full_length,
+            });
+        }
+
+        let mut sorted = raw.index.clone();
+        sorted.sort_unstable();
+        if let Some(w) = sorted.windows(2).find(|w| w[0] == w[1]) {
+            return Err(SparseError::InvalidStructure(format!(
+                "index {:?} is stored twice",
+                w[0]
+            )));
+        }
+
+        Ok(PackedVec {
+            index: raw.index,
+            data: raw.data,
+            full_length: raw.full_length,
+        })
+    }
+}
+
+impl<T: Scalar> Default for PackedVec<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The accessors and the product with a dense vector, for any index type.
+impl<T: Scalar, I: IndexType> PackedVec<T, I> {
+    /// Return the length of the full-length vector this packed vector represents
+    pub fn full_len(&self) -> usize {
+        self.full_length
+    }
+
+    /// Return the amount of the non-zero component
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Return true if all component are zero
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Return component `i`, which is zero when nothing is stored at `i`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` is not below [`PackedVec::full_len`].
+    pub fn get(&self, i: usize) -> T {
+        self.check_index(i);
+        match self.index.iter().position(|&k| k.index() == i) {
+            Some(k) => self.data[k],
+            None => T::zero(),
+        }
+    }
+
+    /// Scatter is a special verb describing the transformation from packed vector to full-length
+    /// array.
+    pub fn scatter(&self) -> Vec<T> {
+        let mut full_len_v = vec![T::zero(); self.full_length];
+
+        for kx in 0..self.len() {
+            let ix = self.index[kx].index();
+            full_len_v[ix] = self.data[kx];
+        }
+
+        full_len_v
+    }
+
+    /// Return the stored indices, in storage order, as [`PackedVec::iter`] visits them
+    pub fn indices(&self) -> &[I] {
+        &self.index
+    }
+
+    /// Return the stored values, in the order of [`PackedVec::indices`]
+    pub fn data(&self) -> &[T] {
+        &self.data
+    }
+
+    /// Iterate over the stored `(index, value)` pairs, in storage order. That is increasing index
+    /// order, except after [`PackedVec::mul_add`] appended fill-in at the end.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, T)> + '_ {
+        self.index
+            .iter()
+            .map(|&i| i.index())
+            .zip(self.data.iter().copied())
+    }
+
+    /// Return the inner product `Σ self[i] · x[i]` with a dense vector, gathering `x` at the
+    /// stored indices. O(nnz).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `x.len() != self.full_len()`.
+    pub fn dot_dense(&self, x: &[T]) -> T {
+        assert_eq!(x.len(), self.full_length, "x has the wrong length");
+        I::gather_dot(&self.data, &self.index, x)
+    }
+
+    /// Return true if any stored component is NaN. A NaN poisons every inner product and norm it
+    /// takes part in, so check this first when the input can't be trusted.
+    pub fn has_nan(&self) -> bool {
+        self.data.iter().any(|x| x.is_nan())
+    }
+
+    fn check_index(&self, i: usize) {
+        assert!(
+            i < self.full_length,
+            "index {i} out of bounds for a packed vector of length {}",
+            self.full_length
+        );
+    }
+
+    /// Convert the indices to another [`IndexType`], such as u32 to halve their storage. Fails
+    /// with [`SparseError::IndexOutOfBounds`] when a stored index doesn't fit in `J`. O(nnz).
+    pub fn to_index_type<J: IndexType>(&self) -> Result<PackedVec<T, J>, SparseError> {
+        Ok(PackedVec {
+            index: convert_indices(&self.index)?,
+            data: self.data.clone(),
+            full_length: self.full_length,
+        })
+    }
+}
+
+impl<T: Scalar> PackedVec<T> {
+    /// Create a empty PackedSparseVec to represent a sparse vector.
+    pub fn new() -> Self {
+        Self {
+            index: Vec::new(),
+            data: Vec::new(),
+            full_length: 0,
+        }
+    }
+
+    /// Gather is a special verb describing a transformation from full-length array to a packed
+    /// sparse vector.
+    ///
+    /// NaN components are kept, since `NaN != 0.0`. Use [`PackedVec::gather_with`] to drop them or
+    /// reject the input instead. Both `0.0` and `-0.0` count as zero and are not stored.
+    pub fn gather(original: &[T]) -> Self {
+        Self::gather_with(original, NanPolicy::Keep).expect("NanPolicy::Keep never fails")
+    }
+
+    /// Gather a full-length array, but fail with [`SparseError::TooDense`] instead when the packed
+    /// form wouldn't be smaller: an index and a value per entry take at least as much storage as
+    /// the whole array once half of the components are nonzero. [`PackedVec::gather`] packs
+    /// regardless.
+    pub fn try_gather(original: &[T]) -> Result<Self, SparseError> {
+        let packed = Self::gather(original);
+        if 2 * packed.len() >= original.len() && !original.is_empty() {
+            return Err(SparseError::TooDense {
+                nnz: packed.len(),
+                len: original.len(),
+            });
+        }
+        Ok(packed)
+    }
+
+    /// Gather a full-length array, handling its NaN components according to `nan`.
+    pub fn gather_with(original: &[T], nan: NanPolicy) -> Result<Self, SparseError> {
+        if nan == NanPolicy::Error {
+            if let Some(index) = original.iter().position(|x| x.is_nan()) {
+                return Err(SparseError::NanValue { index });
+            }
+        }
+
+        let (index, data): (Vec<usize>, Vec<T>) = original
+            .iter()
+            .enumerate()
+            .filter(|(_, &x)| x != T::zero() && !(nan == NanPolicy::Drop && x.is_nan()))
+            .unzip();
+
+        Ok(Self {
+            index,
+            data,
+            full_length: original.len(),
+        })
+    }
+
+    /// Set component `i` to `value`. A nonzero value updates or inserts the entry, while zero
+    /// removes it, so no explicit zero is left behind. The stored entries end up sorted by index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` is not below [`PackedVec::full_len`].
+    pub fn set(&mut self, i: usize, value: T) {
+        self.check_index(i);
+        self.sort_by_index();
+
+        match (self.index.binary_search(&i), value == T::zero()) {
+            (Ok(k), false) => self.data[k] = value,
+            (Ok(k), true) => {
+                self.index.remove(k);
+                self.data.remove(k);
+            }
+            (Err(k), false) => {
+                self.index.insert(k, i);
+                self.data.insert(k, value);
+            }
+            (Err(_), true) => {}
+        }
+    }
+
+    /// Return a mutable reference to component `i`, first storing an explicit zero there if
+    /// nothing is stored yet. The stored entries end up sorted by index. This is what `v[i]`
+    /// resolves to in assignments such as `v[i] += 1.0`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` is not below [`PackedVec::full_len`].
+    pub fn entry(&mut self, i: usize) -> &mut T {
+        self.check_index(i);
+        self.sort_by_index();
+
+        let k = match self.index.binary_search(&i) {
+            Ok(k) => k,
+            Err(k) => {
+                self.index.insert(k, i);
+                self.data.insert(k, T::zero());
+                k
+            }
+        };
+        &mut self.data[k]
+    }
+
+    /// Put the entries back in index order after [`PackedVec::mul_add`] appended fill-in.
+    fn sort_by_index(&mut self) {
+        if !self.index.is_sorted() {
+            (self.index, self.data) = self.sorted_pairs().into_iter().unzip();
+        }
+    }
+
+    /// Build a packed vector from parts known to be valid: indices in bounds and unique.
+    pub(crate) fn from_parts(full_length: usize, index: Vec<usize>, data: Vec<T>) -> Self {
+        debug_assert_eq!(index.len(), data.len());
+        debug_assert!(index.iter().all(|&i| i < full_length));
+        Self {
+            index,
+            data,
+            full_length,
+        }
+    }
+
+    /// Take the vector apart into `(full length, indices, values)`, sorted by index.
+    #[cfg(feature = "sprs")]
+    pub(crate) fn into_sorted_parts(mut self) -> (usize, Vec<usize>, Vec<T>) {
+        self.sort_by_index();
+        (self.full_length, self.index, self.data)
+    }
+
+    /// Return the inner product `Σ self[i] · other[i]` without consuming either vector. This is
+    /// what `&x * &y` computes.
+    pub fn dot(&self, other: &Self) -> T {
+        let (x_index, x_data) = self.sorted_parts();
+        let (y_index, y_data) = other.sorted_parts();
+        let mut product = T::zero();
+        for_each_intersection(&x_index, &y_index, |kx, ky| {
+            product += x_data[kx] * y_data[ky];
+        });
+
+        product
+    }
+
+    /// Return the conjugating inner product `Σ conj(self[i]) · other[i]`, the inner product of a
+    /// complex vector space. For real types it is the same as [`PackedVec::dot`].
+    pub fn dotc(&self, other: &Self) -> T {
+        let (x_index, x_data) = self.sorted_parts();
+        let (y_index, y_data) = other.sorted_parts();
+        let mut product = T::zero();
+        for_each_intersection(&x_index, &y_index, |kx, ky| {
+            product += x_data[kx].conj() * y_data[ky];
+        });
+
+        product
+    }
+
+    /// Return the component-wise (Hadamard) product of two vectors of the same length. Only
+    /// indices stored in both vectors can be nonzero, so the result holds just the intersection
+    /// of the two supports, sorted by index.
+    pub fn hadamard(&self, other: &Self) -> PackedVec<T> {
+        assert_eq!(
+            self.full_length, other.full_length,
+            "packed vectors have different lengths"
+        );
+
+        let (x_index, x_data) = self.sorted_parts();
+        let (y_index, y_data) = other.sorted_parts();
+        let mut index = Vec::new();
+        let mut data = Vec::new();
+        for_each_intersection(&x_index, &y_index, |kx, ky| {
+            index.push(x_index[kx]);
+            data.push(x_data[kx] * y_data[ky]);
+        });
+
+        PackedVec {
+            index,
+            data,
+            full_length: self.full_length,
+        }
+    }
+
+    /// Adding a multiple of one vector to another. To distinguish the index of the packed vector
+    /// and the actual index of the full-length vector, I use `k` denote that it is the index of
+    /// packed vector and `i` to denote that it is the index of the actual vector.
+    ///
+    /// For example, `ix` is a index for the full-length vector X and `kx` is a index for the
+    /// packed vector.
+    pub fn mul_add(&mut self, y_vec: &Self, alpha: T) {
+        self.mul_add_with(y_vec, alpha, MulAddWorkspace::Auto)
+    }
+
+    /// [`PackedVec::mul_add`] with an explicit choice of the helper storage. The result is the
+    /// same for every workspace, only the memory used and the speed differ.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an index of either vector is not below the full length of `self`, whichever
+    /// the workspace: the full one would index past its end, and the others would store the
+    /// component out of bounds.
+    pub fn mul_add_with(&mut self, y_vec: &Self, alpha: T, workspace: MulAddWorkspace) {
+        if let Some(&i) = (self.index.iter())
+            .chain(&y_vec.index)
+            .find(|&&i| i >= self.full_length)
+        {
+            panic!(
+                "index {i} out of bounds for a packed vector of length {}",
+                self.full_length
+            );
+        }
+
+        // Use a helper storage for flagging non-zero entry in the Y vector.
+        //
+        // There are three approch.
+        //  * One is using the full-length vector for all time manipulation. This is simpler
+        //  implementation, and the fastest one as long as the vector is short.
+        //  * The second one is to use a vector only spanning from the first entry of Y to the
+        //  last entry of Y. An index of X outside of it can't match any entry of Y anyway, so in
+        //  some case this can save much space comparing to the full-length vector.
+        //  * The last one is a hash map holding only the entries of Y, for when even the span is
+        //  mostly empty.
+        match workspace.resolve(self.full_length, y_vec) {
+            MulAddWorkspace::Full => {
+                self.mul_add_marked(y_vec, alpha, &mut vec![None; self.full_length]);
+            }
+            MulAddWorkspace::Span => {
+                let (first, last) = y_vec.index_span().unwrap_or((0, 0));
+                let mut tmp = SpanMarks {
+                    first,
+                    marks: vec![None; last - first + 1],
+                };
+                self.mul_add_marked(y_vec, alpha, &mut tmp);
+            }
+            MulAddWorkspace::Hash => {
+                let mut tmp = HashMap::with_capacity(y_vec.len());
+                self.mul_add_marked(y_vec, alpha, &mut tmp);
+            }
+            MulAddWorkspace::Auto => unreachable!("resolve never returns Auto"),
+        }
+    }
+
+    fn mul_add_marked(&mut self, y_vec: &Self, alpha: T, tmp: &mut impl IndexMarks) {
+        // #1: Store the y_vec data index
+
+        // for each entry Y[k], place its position in the vector tmp[k].
+        for ky in 0..y_vec.len() {
+            let iy = y_vec.index[ky];
+            tmp.mark(iy, ky);
+        }
+
+        // #2: For existing entry in x_vec, multiply the y vector and add it into x.
+
+        // Scan the X vector, for each entry X[i], check tmp[i] to get the packed vector index Y_k.
+        // If the index is not None, use it to find the value of Y[i], and reset tmp[k] to None.
+        for kx in 0..self.len() {
+            let ix = self.index[kx];
+            if let Some(ky) = tmp.take(ix) {
+                self.data[kx] = positive_zero(self.data[kx] + alpha * y_vec.data[ky]);
+            }
+        }
+
+        // #3: For unexisting entry in x_vec, fill the empty place
+
+        // Scan the Y vector. For each entry Y[i] check tmp[i]. If it is not None, add a new
+        // component with value `Alpha * Y[i]` to the packed form of X. Reset tmp[i] to None.
+        for ky in 0..y_vec.len() {
+            let iy = y_vec.index[ky];
+            if tmp.take(iy).is_some() {
+                self.data.push(positive_zero(alpha * y_vec.data[ky]));
+                self.index.push(iy);
+            }
+        }
+    }
+
+    /// Return the smallest and the largest stored index, or None for an empty vector.
+    fn index_span(&self) -> Option<(usize, usize)> {
+        let first = *self.index.iter().min()?;
+        let last = *self.index.iter().max()?;
+        Some((first, last))
+    }
+
+    /// Same as [`PackedVec::mul_add`], but also report how much the update filled the vector in.
+    ///
+    /// Repeated updates (as in sparse Gaussian elimination) tend to densify the vectors, and the
+    /// returned statistics make that visible.
+    pub fn mul_add_tracked(&mut self, y_vec: &Self, alpha: T) -> FillStats {
+        let before = self.len();
+        self.mul_add(y_vec, alpha);
+
+        // `mul_add` never removes components: every new entry is a fill-in appended at the end.
+        let nnz = self.len();
+        FillStats {
+            fill_in: nnz - before,
+            nnz,
+            density: if self.full_length == 0 {
+                0.0
+            } else {
+                nnz as f64 / self.full_length as f64
+            },
+        }
+    }
+}
+
+/// Inputs to `bincount` with at least this many entries are accumulated by sorting instead of
+/// hashing: a hash map pays off while the stream is short, a single sort once it is long.
+const BINCOUNT_SORT_THRESHOLD: usize = 1 << 12;
+
+impl<T: Scalar> PackedVec<T> {
+    /// Accumulate the weight of each `(index, weight)` pair into a packed vector of length `len`.
+    ///
+    /// The result is canonical: sorted by index, with no stored zeros. Weights landing on the same
+    /// index are summed in stream order whichever internal strategy is used, so the result does
+    /// not depend on the size of the input.
+    pub fn bincount_weighted(
+        len: usize,
+        pairs: impl IntoIterator<Item = (usize, T)>,
+    ) -> Result<PackedVec<T>, SparseError> {
+        let mut pairs: Vec<(usize, T)> = pairs.into_iter().collect();
+        if let Some(&(index, _)) = pairs.iter().find(|&&(i, _)| i >= len) {
+            return Err(SparseError::IndexOutOfBounds { index, len });
+        }
+
+        let accumulated = if pairs.len() < BINCOUNT_SORT_THRESHOLD {
+            Self::accumulate_hashed(&pairs)
+        } else {
+            Self::accumulate_sorted(&mut pairs)
+        };
+
+        let (index, data) = accumulated
+            .into_iter()
+            .filter(|&(_, v)| v != T::zero())
+            .unzip();
+        Ok(PackedVec {
+            index,
+            data,
+            full_length: len,
+        })
+    }
+
+    /// Build a packed vector of full length `len` straight from `(index, value)` pairs, without
+    /// going through a dense array. The pairs may come in any order. Values sharing an index are
+    /// summed, and components that end up zero are not stored.
+    ///
+    /// This is [`PackedVec::bincount_weighted`] under the name a constructor is looked up by.
+    pub fn from_pairs(
+        len: usize,
+        pairs: impl IntoIterator<Item = (usize, T)>,
+    ) -> Result<PackedVec<T>, SparseError> {
+        Self::bincount_weighted(len, pairs)
+    }
+
+    fn accumulate_hashed(pairs: &[(usize, T)]) -> Vec<(usize, T)> {
+        let mut bins: HashMap<usize, T> = HashMap::new();
+        for &(i, w) in pairs {
+            *bins.entry(i).or_insert(T::zero()) += w;
+        }
+
+        let mut accumulated: Vec<(usize, T)> = bins.into_iter().collect();
+        accumulated.sort_unstable_by_key(|&(i, _)| i);
+        accumulated
+    }
+
+    fn accumulate_sorted(pairs: &mut [(usize, T)]) -> Vec<(usize, T)> {
+        // A stable sort keeps equal indices in stream order, so the run sums below add the
+        // weights in the same order as the hashed path.
+        pairs.sort_by_key(|&(i, _)| i);
+
+        let mut accumulated: Vec<(usize, T)> = Vec::new();
+        for &(i, w) in pairs.iter() {
+            match accumulated.last_mut() {
+                Some((last, sum)) if *last == i => *sum += w,
+                _ => accumulated.push((i, T::zero() + w)),
+            }
+        }
+        accumulated
+    }
+
+    /// Sum many packed vectors of full length `len` at once.
+    ///
+    /// Adding them pairwise rewrites the growing accumulator for every input. Instead, the sorted
+    /// index streams of all the vectors are merged with [`kmerge`], so each output index is
+    /// produced exactly once, with the sum of all the values stored at it. Components that cancel
+    /// to exactly zero are dropped.
+    pub fn sum_all(len: usize, vecs: &[&PackedVec<T>]) -> Result<PackedVec<T>, SparseError> {
+        if let Some(v) = vecs.iter().find(|v| v.full_length != len) {
+            return Err(SparseError::DimensionMismatch {
+                expected: len,
+                found: v.full_length,
+            });
+        }
+
+        let mut sum = PackedVec {
+            full_length: len,
+            ..PackedVec::new()
+        };
+
+        let mut merged = kmerge(vecs.iter().map(|v| v.sorted_pairs())).peekable();
+        while let Some((i, mut value)) = merged.next() {
+            while let Some((_, v)) = merged.next_if(|&(next_i, _)| next_i == i) {
+                value += v;
+            }
+
+            if value != T::zero() {
+                sum.index.push(i);
+                sum.data.push(value);
+            }
+        }
+
+        Ok(sum)
+    }
+}
+
+impl<T: Scalar> PackedVec<T> {
+    /// Extract the components in `range` as a packed vector of length `range.len()`, with the
+    /// indices shifted down by `range.start`.
+    pub fn slice(&self, range: Range<usize>) -> Result<PackedVec<T>, SparseError> {
+        self.check_range(range.start, range.len())?;
+
+        let (index, data) = self
+            .sorted_pairs()
+            .into_iter()
+            .filter(|(i, _)| range.contains(i))
+            .map(|(i, v)| (i - range.start, v))
+            .unzip();
+
+        Ok(PackedVec {
+            index,
+            data,
+            full_length: range.len(),
+        })
+    }
+
+    /// Overwrite the components `offset..offset + src.full_len()` with the components of `src`.
+    ///
+    /// Existing entries inside the range are removed and the entries of `src`, shifted by
+    /// `offset`, are spliced in their place. Entries outside the range are kept, and the result
+    /// is sorted by index.
+    pub fn assign_slice(&mut self, offset: usize, src: &PackedVec<T>) -> Result<(), SparseError> {
+        self.check_range(offset, src.full_length)?;
+        let end = offset + src.full_length;
+
+        let pairs = self.sorted_pairs();
+        let start_k = pairs.partition_point(|&(i, _)| i < offset);
+        let end_k = pairs.partition_point(|&(i, _)| i < end);
+
+        let before = pairs[..start_k].iter().copied();
+        let inside = src.sorted_pairs().into_iter().map(|(i, v)| (i + offset, v));
+        let after = pairs[end_k..].iter().copied();
+        (self.index, self.data) = before.chain(inside).chain(after).unzip();
+
+        Ok(())
+    }
+
+    /// Add `alpha` times `src`, shifted by `offset`, to the components
+    /// `offset..offset + src.full_len()`. This is [`PackedVec::mul_add`] restricted to a block of
+    /// the vector, and leaves the result sorted by index.
+    pub fn add_slice(
+        &mut self,
+        offset: usize,
+        src: &PackedVec<T>,
+        alpha: T,
+    ) -> Result<(), SparseError> {
+        self.check_range(offset, src.full_length)?;
+
+        let x = self.sorted_pairs();
+        let y = src.sorted_pairs();
+        let mut index = Vec::with_capacity(x.len() + y.len());
+        let mut data = Vec::with_capacity(x.len() + y.len());
+        let mut kx = 0;
+        let mut ky = 0;
+
+        while kx < x.len() || ky < y.len() {
+            let (ix, vx) = x.get(kx).copied().unwrap_or((usize::MAX, T::zero()));
+            let iy = y.get(ky).map_or(usize::MAX, |&(i, _)| i + offset);
+            match ix.cmp(&iy) {
+                Ordering::Equal => {
+                    index.push(ix);
+                    data.push(positive_zero(vx + alpha * y[ky].1));
+                    kx += 1;
+                    ky += 1;
+                }
+                Ordering::Less => {
+                    index.push(ix);
+                    data.push(vx);
+                    kx += 1;
+                }
+                Ordering::Greater => {
+                    index.push(iy);
+                    data.push(positive_zero(alpha * y[ky].1));
+                    ky += 1;
+                }
+            }
+        }
+
+        self.index = index;
+        self.data = data;
+        Ok(())
+    }
+
+    /// Check that the block `offset..offset + len` fits inside the vector.
+    fn check_range(&self, offset: usize, len: usize) -> Result<(), SparseError> {
+        match offset.checked_add(len) {
+            Some(end) if end <= self.full_length => Ok(()),
+            _ => Err(SparseError::IndexOutOfBounds {
+                index: offset.saturating_add(len),
+                len: self.full_length,
+            }),
+        }
+    }
+
+    /// Return the (index, value) pairs sorted by index. `mul_add` appends fill-in at the end of
+    /// the packed arrays, so the stored order can't be relied on when comparing two vectors.
+    fn sorted_pairs(&self) -> Vec<(usize, T)> {
+        let mut pairs: Vec<(usize, T)> = self
+            .index
+            .iter()
+            .copied()
+            .zip(self.data.iter().copied())
+            .collect();
+        pairs.sort_unstable_by_key(|&(i, _)| i);
+        pairs
+    }
+
+    /// Remove the stored entries equal to zero, keeping the order of the others. NaN is kept.
+    fn drop_zeros(&mut self) {
+        let mut kept = 0;
+        for k in 0..self.data.len() {
+            if self.data[k] != T::zero() {
+                self.index[kept] = self.index[k];
+                self.data[kept] = self.data[k];
+                kept += 1;
+            }
+        }
+        self.index.truncate(kept);
+        self.data.truncate(kept);
+    }
+
+    /// Return the stored indices and values in index order, as the merges over two supports
+    /// need. They are borrowed as they are unless `mul_add` left fill-in out of order.
+    fn sorted_parts(&self) -> (Cow<'_, [usize]>, Cow<'_, [T]>) {
+        if self.index.is_sorted() {
+            (Cow::Borrowed(&self.index), Cow::Borrowed(&self.data))
+        } else {
+            let (index, data): (Vec<usize>, Vec<T>) = self.sorted_pairs().into_iter().unzip();
+            (Cow::Owned(index), Cow::Owned(data))
+        }
+    }
+
+    /// Combine two vectors of the same length component by component into a new vector sorted by
+    /// index. `f` is called once for every index in the union of both supports, structural zeros
+    /// being passed as 0.0.
+    fn merge_with(&self, other: &Self, f: impl Fn(T, T) -> T) -> PackedVec<T> {
+        assert_eq!(
+            self.full_length, other.full_length,
+            "packed vectors have different lengths"
+        );
+
+        let (x_index, x_data) = self.sorted_parts();
+        let (y_index, y_data) = other.sorted_parts();
+        let mut index = Vec::with_capacity(x_index.len() + y_index.len());
+        let mut data = Vec::with_capacity(x_index.len() + y_index.len());
+        merge::all_union(&x_index, &y_index, |step| {
+            match step {
+                UnionStep::Left(run) => {
+                    index.extend_from_slice(&x_index[run.clone()]);
+                    data.extend(x_data[run].iter().map(|&v| positive_zero(f(v, T::zero()))));
+                }
+                UnionStep::Right(run) => {
+                    index.extend_from_slice(&y_index[run.clone()]);
+                    data.extend(y_data[run].iter().map(|&v| positive_zero(f(T::zero(), v))));
+                }
+                UnionStep::Both(kx, ky) => {
+                    index.push(x_index[kx]);
+                    data.push(positive_zero(f(x_data[kx], y_data[ky])));
+                }
+            }
+            true
+        });
+
+        PackedVec {
+            index,
+            data,
+            full_length: self.full_length,
+        }
+    }
+
+    /// Walk the union of both supports, calling `f` with the two values at each index. Structural
+    /// zeros are passed as 0.0. Stop and return false as soon as `f` does, or when the full
+    /// lengths differ.
+    fn all_union(&self, other: &Self, mut f: impl FnMut(T, T) -> bool) -> bool {
+        if self.full_length != other.full_length {
+            return false;
+        }
+
+        let (x_index, x_data) = self.sorted_parts();
+        let (y_index, y_data) = other.sorted_parts();
+        merge::all_union(&x_index, &y_index, |step| match step {
+            UnionStep::Left(run) => x_data[run].iter().all(|&v| f(v, T::zero())),
+            UnionStep::Right(run) => y_data[run].iter().all(|&v| f(T::zero(), v)),
+            UnionStep::Both(kx, ky) => f(x_data[kx], y_data[ky]),
+        })
+    }
+}
+
+impl PackedVec<f64> {
+    /// Gather a full-length array of measured data, treating the components with `|x| < tol` as
+    /// zero. Noise rarely cancels to exactly 0.0, so [`PackedVec::gather`] would store almost
+    /// everything. NaN components are kept, as they are by `gather`.
+    pub fn gather_with_tol(original: &[f64], tol: f64) -> PackedVec {
+        let (index, data) = original
+            .iter()
+            .enumerate()
+            .filter(|(_, &x)| x != 0.0 && (x.abs() >= tol || x.is_nan()))
+            .unzip();
+
+        PackedVec {
+            index,
+            data,
+            full_length: original.len(),
+        }
+    }
+
+    /// Count the occurrences of each index in `indices` into a packed vector of length `len`.
+    pub fn bincount(
+        len: usize,
+        indices: impl IntoIterator<Item = usize>,
+    ) -> Result<PackedVec, SparseError> {
+        Self::bincount_weighted(len, indices.into_iter().map(|i| (i, 1.0)))
+    }
+
+    /// Return the sum of the absolute values of the components.
+    pub fn norm_l1(&self) -> f64 {
+        self.data.iter().map(|v| v.abs()).sum()
+    }
+
+    /// Return the sum of the squared components, the square of [`PackedVec::norm_l2`].
+    pub fn norm_squared(&self) -> f64 {
+        self.data.iter().map(|v| v * v).sum()
+    }
+
+    /// Return the Euclidean length of the vector.
+    pub fn norm_l2(&self) -> f64 {
+        crate::dense::sqrt(self.norm_squared())
+    }
+
+    /// Return the largest absolute value of a component, 0.0 for the zero vector and NaN when any
+    /// component is NaN.
+    pub fn norm_inf(&self) -> f64 {
+        self.data.iter().fold(0.0, |max: f64, v| {
+            if v.is_nan() || v.abs() > max {
+                v.abs()
+            } else {
+                max
+            }
+        })
+    }
+
+    /// Drop the stored components with `|v| <= tol`, such as values that cancelled in
+    /// [`PackedVec::mul_add`], and release their storage. `prune(0.0)` removes just the explicit
+    /// zeros. NaN components are kept.
+    pub fn prune(&mut self, tol: f64) {
+        let mut kept = 0;
+        for k in 0..self.data.len() {
+            if self.data[k].abs() > tol || self.data[k].is_nan() {
+                self.index[kept] = self.index[k];
+                self.data[kept] = self.data[k];
+                kept += 1;
+            }
+        }
+
+        self.index.truncate(kept);
+        self.data.truncate(kept);
+        self.index.shrink_to_fit();
+        self.data.shrink_to_fit();
+    }
+
+    /// Return true if the two vectors have the same length and every pair of components differs
+    /// by at most `tol`, structural zeros counting as 0.0.
+    ///
+    /// A NaN component never compares equal, unless `nan_equal` is set, in which case a NaN
+    /// matches a NaN at the same index (and still nothing else). Equal infinities compare equal.
+    pub fn approx_eq(&self, other: &Self, tol: f64, nan_equal: bool) -> bool {
+        self.all_union(other, |a, b| {
+            if a.is_nan() || b.is_nan() {
+                nan_equal && a.is_nan() && b.is_nan()
+            } else {
+                a == b || (a - b).abs() <= tol
+            }
+        })
+    }
+}
+
+/// Two packed vectors are equal when they represent the same full-length vector: the lengths
+/// match and every component is equal, with structural zeros compared as 0.0.
+impl<T: Scalar> PartialEq for PackedVec<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.all_union(other, |a, b| a == b)
+    }
+}
+
+#[cfg(feature = "approx")]
+impl approx::AbsDiffEq for PackedVec<f64> {
+    type Epsilon = f64;
+
+    fn default_epsilon() -> f64 {
+        f64::default_epsilon()
+    }
+
+    fn abs_diff_eq(&self, other: &Self, epsilon: f64) -> bool {
+        self.all_union(other, |a, b| a.abs_diff_eq(&b, epsilon))
+    }
+}
+
+#[cfg(feature = "approx")]
+impl approx::RelativeEq for PackedVec<f64> {
+    fn default_max_relative() -> f64 {
+        f64::default_max_relative()
+    }
+
+    fn relative_eq(&self, other: &Self, epsilon: f64, max_relative: f64) -> bool {
+        self.all_union(other, |a, b| a.relative_eq(&b, epsilon, max_relative))
+    }
+}
+
+#[cfg(feature = "approx")]
+impl approx::UlpsEq for PackedVec<f64> {
+    fn default_max_ulps() -> u32 {
+        f64::default_max_ulps()
+    }
+
+    fn ulps_eq(&self, other: &Self, epsilon: f64, max_ulps: u32) -> bool {
+        self.all_union(other, |a, b| a.ulps_eq(&b, epsilon, max_ulps))
+    }
+}
+
+impl<T: Scalar> core::ops::Mul for PackedVec<T> {
+    type Output = T;
+
+    /// Inner product of two packed vectors
+    fn mul(self, rhs: Self) -> Self::Output {
+        self.dot(&rhs)
+    }
+}
+
+impl<T: Scalar> core::ops::Mul for &PackedVec<T> {
+    type Output = T;
+
+    /// Inner product of two packed vectors, leaving both in place
+    fn mul(self, rhs: Self) -> Self::Output {
+        self.dot(rhs)
+    }
+}
+
+/// Implement a component-wise binary operator and its assigning form for packed vectors, for
+/// every combination of owned and borrowed operands. Both operands must have the same length.
+macro_rules! impl_packed_vec_op {
+    ($op:ident, $method:ident, $op_assign:ident, $method_assign:ident, $f:expr) => {
+        impl<T: Scalar> core::ops::$op<&PackedVec<T>> for &PackedVec<T> {
+            type Output = PackedVec<T>;
+
+            fn $method(self, rhs: &PackedVec<T>) -> PackedVec<T> {
+                self.merge_with(rhs, $f)
+            }
+        }
+
+        impl<T: Scalar> core::ops::$op for PackedVec<T> {
+            type Output = PackedVec<T>;
+
+            fn $method(self, rhs: PackedVec<T>) -> PackedVec<T> {
+                self.merge_with(&rhs, $f)
+            }
+        }
+
+        impl<T: Scalar> core::ops::$op_assign<&PackedVec<T>> for PackedVec<T> {
+            fn $method_assign(&mut self, rhs: &PackedVec<T>) {
+                *self = self.merge_with(rhs, $f);
+            }
+        }
+
+        impl<T: Scalar> core::ops::$op_assign for PackedVec<T> {
+            fn $method_assign(&mut self, rhs: PackedVec<T>) {
+                *self = self.merge_with(&rhs, $f);
+            }
+        }
+    };
+}
+
+impl_packed_vec_op!(Add, add, AddAssign, add_assign, |a, b| a + b);
+impl_packed_vec_op!(Sub, sub, SubAssign, sub_assign, |a, b| a - b);
+
+/// Lists the stored components as `{index: value, ...}` sorted by index, followed by the full
+/// length. Long vectors are cut short after the first few entries. A precision, as in `{:.3}`,
+/// applies to the values.
+impl<T: Scalar + core::fmt::Display> core::fmt::Display for PackedVec<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{{")?;
+        for (k, (i, v)) in self
+            .sorted_pairs()
+            .into_iter()
+            .take(display::MAX_LISTED_ENTRIES)
+            .enumerate()
+        {
+            if k > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{i}: {}", display::value_string(v, f))?;
+        }
+        if self.len() > display::MAX_LISTED_ENTRIES {
+            write!(f, ", ... {} more", self.len() - display::MAX_LISTED_ENTRIES)?;
+        }
+        write!(f, "}} (length {})", self.full_length)
+    }
+}
+
+/// `v[i]` reads component `i` like [`PackedVec::get`], and `v[i] = x` writes it through
+/// [`PackedVec::entry`]. A structural zero is read as a reference to a constant zero, which is why
+/// these are implemented for the primitive types only.
+macro_rules! impl_packed_vec_index {
+    ($($t:ty),*) => {$(
+        impl core::ops::Index<usize> for PackedVec<$t> {
+            type Output = $t;
+
+            fn index(&self, i: usize) -> &$t {
+                const ZERO: $t = 0 as $t;
+                self.check_index(i);
+                match self.index.iter().position(|&k| k == i) {
+                    Some(k) => &self.data[k],
+                    None => &ZERO,
+                }
+            }
+        }
+
+        impl core::ops::IndexMut<usize> for PackedVec<$t> {
+            fn index_mut(&mut self, i: usize) -> &mut $t {
+                self.entry(i)
+            }
+        }
+    )*};
+}
+
+impl_packed_vec_index!(f32, f64, i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+impl<T: Scalar> core::ops::Mul<T> for PackedVec<T> {
+    type Output = PackedVec<T>;
+
+    /// Scale every component by `alpha`
+    fn mul(mut self, alpha: T) -> PackedVec<T> {
+        self *= alpha;
+        self
+    }
+}
+
+/// Scale every component by `alpha`, dropping the components that become zero: all of them
+/// when `alpha` is zero, and those that underflow otherwise.
+impl<T: Scalar> core::ops::MulAssign<T> for PackedVec<T> {
+    fn mul_assign(&mut self, alpha: T) {
+        for v in &mut self.data {
+            *v *= alpha;
+        }
+        self.drop_zeros();
+    }
+}
+
+impl<T: Scalar> core::ops::Div<T> for PackedVec<T> {
+    type Output = PackedVec<T>;
+
+    /// Divide every component by `alpha`, dropping the components that become zero, as an
+    /// integer quotient or an underflow can.
+    fn div(mut self, alpha: T) -> PackedVec<T> {
+        for v in &mut self.data {
+            *v = *v / alpha;
+        }
+        self.drop_zeros();
+        self
+    }
+}
+
+impl<T: Scalar + core::ops::Neg<Output = T>> core::ops::Neg for PackedVec<T> {
+    type Output = PackedVec<T>;
+
+    /// Negate every component. The stored entries stay as they are, an explicit zero remaining a
+    /// `+0.0` rather than turning into `-0.0`.
+    fn neg(mut self) -> PackedVec<T> {
+        for v in &mut self.data {
+            *v = positive_zero(-*v);
+        }
+        self
+    }
+}
+
+#[test]
+fn test_packed_vector() {
+    #[rustfmt::skip]
+    let x = vec![
+        0.0, 0.0, 0.0, 0.0, 0.0,
+        0.0, 1.0, 0.0, 0.0, 2.0,
+        0.0, 0.0, 3.0, 0.0, 0.0,
+        0.0, 0.0, 0.0, 1.0, 0.0,
+    ];
+
+    let mut packed_x = PackedVec::gather(&x);
+    assert_eq!(packed_x.full_length, x.len());
+    assert_eq!(packed_x.data, [1.0, 2.0, 3.0, 1.0]);
+    assert_eq!(packed_x.index, [6, 9, 12, 18]);
+    assert!(!packed_x.is_empty());
+    assert_eq!(packed_x.len(), 4);
+
+    #[rustfmt::skip]
+    let y = vec![
+        0.0, 1.0, 0.0, 0.0, 0.0,
+        0.0, 1.0, 0.0, 7.0, 2.0,
+        0.0, 0.0, 2.0, 0.0, 0.0,
+        0.0, 1.0, 0.0, 0.0, 0.0,
+    ];
+
+    let packed_y = PackedVec::gather(&y);
+
+    let inner_product = packed_x.clone() * packed_y.clone();
+    assert_eq!(inner_product, 11.0);
+
+    packed_x.mul_add(&packed_y, 32.0);
+    assert_eq!(
+        packed_x.data,
+        [
+            1.0 + 1.0 * 32.0,
+            2.0 + 2.0 * 32.0,
+            3.0 + 2.0 * 32.0,
+            1.0,
+            1.0 * 32.0,
+            7.0 * 32.0,
+            1.0 * 32.0
+        ]
+    );
+
+    let scatter_back = packed_y.scatter();
+    assert_eq!(y, scatter_back);
+}
+
+#[test]
+fn test_packed_vector_dot() {
+    let x = PackedVec::gather(&[0.0, 1.0, 0.0, 2.0, 0.0, 3.0]);
+    let y = PackedVec::gather(&[4.0, 1.0, 0.0, 7.0, 0.0, 2.0]);
+    assert_eq!(&x * &y, 21.0);
+    assert_eq!(y.dot(&x), 21.0);
+    assert_eq!(x.dotc(&y), 21.0);
+    assert_eq!(x.clone() * y.clone(), 21.0);
+
+    // Fill-in from mul_add is appended out of order, and the product still sees it.
+    let mut z = PackedVec::gather(&[0.0, 0.0, 0.0, 1.0]);
+    z.mul_add(&PackedVec::gather(&[1.0, 0.0, 0.0, 0.0]), 1.0);
+    assert_eq!(z.index, [3, 0]);
+    let w = PackedVec::gather(&[1.0, 0.0, 0.0, 1.0]);
+    assert_eq!(z.dot(&w), 2.0);
+    assert_eq!(w.dot(&z), 2.0);
+    assert_eq!(z.dotc(&w), 2.0);
+    assert_eq!(&z * &z, 2.0);
+}
+
+#[test]
+fn test_packed_vector_generic() {
+    let x: Vec<f32> = vec![0.0, 1.5, 0.0, 2.0, 0.0];
+    let y: Vec<f32> = vec![4.0, 2.0, 0.0, 0.0, 0.0];
+    let mut packed_x = PackedVec::gather(&x);
+    let packed_y = PackedVec::gather(&y);
+    assert_eq!(packed_x.clone() * packed_y.clone(), 3.0f32);
+    packed_x.mul_add(&packed_y, 0.5);
+    assert_eq!(packed_x.scatter(), [2.0f32, 2.5, 0.0, 2.0, 0.0]);
+
+    let a: Vec<i64> = vec![0, 3, 0, -2, 0, 0];
+    let b: Vec<i64> = vec![1, 0, 0, 5, 0, 7];
+    let mut packed_a = PackedVec::gather(&a);
+    let packed_b = PackedVec::gather(&b);
+    assert_eq!(packed_a.len(), 2);
+    assert_eq!(packed_a.clone() * packed_b.clone(), -10);
+    packed_a.mul_add(&packed_b, 2);
+    assert_eq!(packed_a.scatter(), [2, 3, 0, 8, 0, 14]);
+
+    let total = PackedVec::sum_all(6, &[&packed_a, &packed_b]).unwrap();
+    assert_eq!(total.scatter(), [3, 3, 0, 13, 0, 21]);
+
+    let counts = PackedVec::bincount_weighted(4, [(1, 2u32), (3, 1), (1, 5)]).unwrap();
+    assert_eq!(counts.scatter(), [0, 7, 0, 1]);
+}
+
+#[cfg(feature = "complex")]
+#[test]
+fn test_packed_vector_complex() {
+    use num_complex::Complex;
+
+    let c = |re: f64, im: f64| Complex::new(re, im);
+    let zero = c(0.0, 0.0);
+    let x = PackedVec::gather(&[c(1.0, 2.0), zero, c(0.0, -1.0), zero]);
+    let y = PackedVec::gather(&[c(3.0, 0.0), c(5.0, 5.0), c(2.0, 1.0), zero]);
+    assert_eq!(x.len(), 2);
+
+    // (1 - 2i) 3 + (i)(2 + i) = 3 - 6i + 2i - 1
+    assert_eq!(x.dotc(&y), c(2.0, -4.0));
+    // The conjugating product of a vector with itself is its squared norm, a real number.
+    assert_eq!(x.dotc(&x), c(6.0, 0.0));
+    assert_eq!(x.clone() * y.clone(), c(4.0, 4.0));
+
+    let mut z = x.clone();
+    z.mul_add(&y, c(0.0, 1.0));
+    assert_eq!(z.scatter(), [c(1.0, 5.0), c(-5.0, 5.0), c(-1.0, 1.0), zero]);
+}
+
+#[test]
+fn test_packed_vector_try_gather() {
+    let sparse = PackedVec::try_gather(&[0.0, 1.0, 0.0, 0.0, 2.0]).unwrap();
+    assert_eq!(sparse.index, [1, 4]);
+
+    assert_eq!(
+        PackedVec::try_gather(&[0.0, 1.0, 0.0, 2.0]),
+        Err(SparseError::TooDense { nnz: 2, len: 4 })
+    );
+    // Plain gather still packs a dense array.
+    assert_eq!(PackedVec::gather(&[1.0, 2.0, 0.0]).len(), 2);
+    assert!(PackedVec::<f64>::try_gather(&[]).unwrap().is_empty());
+}
+
+#[test]
+fn test_packed_vector_gather_with_tol() {
+    let noisy = [1e-9, 0.8, -3e-10, 0.0, -1.2, 1e-3, f64::NAN];
+    let x = PackedVec::gather_with_tol(&noisy, 1e-6);
+    assert_eq!(x.index, [1, 4, 5, 6]);
+    assert_eq!(x.data[..3], [0.8, -1.2, 1e-3]);
+    assert_eq!(x.full_len(), 7);
+
+    // A tolerance of zero only drops the exact zeros, like gather.
+    assert_eq!(PackedVec::gather_with_tol(&noisy, 0.0).len(), 6);
+}
+
+#[test]
+fn test_packed_vector_add_sub() {
+    let x = PackedVec::gather(&[0.0, 1.0, 0.0, 2.0, 0.0, 3.0]);
+    let y = PackedVec::gather(&[4.0, 0.0, 0.0, -2.0, 0.0, 1.0]);
+
+    let sum = &x + &y;
+    assert_eq!(sum.index, [0, 1, 3, 5]);
+    assert_eq!(sum.data, [4.0, 1.0, 0.0, 4.0]);
+    assert_eq!(sum.full_length, 6);
+
+    let diff = &x - &y;
+    assert_eq!(diff.index, [0, 1, 3, 5]);
+    assert_eq!(diff.data, [-4.0, 1.0, 4.0, 2.0]);
+    assert_eq!(x.clone() - y.clone(), diff);
+
+    // The result is sorted even when an operand carries unsorted fill-in from mul_add.
+    let mut z = PackedVec::gather(&[0.0, 0.0, 0.0, 0.0, 0.0, 1.0]);
+    z.mul_add(&x, 1.0);
+    assert_eq!(z.index, [5, 1, 3]);
+    let mut acc = y.clone();
+    acc += &z;
+    assert_eq!(acc.index, [0, 1, 3, 5]);
+    assert_eq!(acc.scatter(), [4.0, 1.0, 0.0, 0.0, 0.0, 5.0]);
+    acc -= z;
+    assert_eq!(acc, y);
+
+    let ints = PackedVec::gather(&[0u32, 3, 0, 1]) + PackedVec::gather(&[2u32, 0, 0, 1]);
+    assert_eq!(ints.scatter(), [2, 3, 0, 2]);
+}
+
+#[test]
+#[should_panic(expected = "packed vectors have different lengths")]
+fn test_packed_vector_add_length_mismatch() {
+    let _ = PackedVec::gather(&[1.0, 2.0]) + PackedVec::gather(&[1.0, 2.0, 3.0]);
+}
+
+#[test]
+fn test_packed_vector_norms() {
+    let x = PackedVec::gather(&[0.0, 3.0, 0.0, -4.0, 0.0]);
+    assert_eq!(x.norm_l1(), 7.0);
+    assert_eq!(x.norm_squared(), 25.0);
+    assert_eq!(x.norm_l2(), 5.0);
+    assert_eq!(x.norm_inf(), 4.0);
+
+    let zero = PackedVec::gather(&[0.0; 3]);
+    assert_eq!(zero.norm_l1(), 0.0);
+    assert_eq!(zero.norm_l2(), 0.0);
+    assert_eq!(zero.norm_inf(), 0.0);
+
+    let with_nan = PackedVec::gather(&[1.0, f64::NAN, -7.0]);
+    assert!(with_nan.norm_inf().is_nan());
+    assert!(with_nan.norm_l2().is_nan());
+}
+
+#[test]
+fn test_packed_vector_scaling() {
+    let x = PackedVec::gather(&[0.0, 1.0, 0.0, -2.0]);
+
+    let doubled = x.clone() * 2.0;
+    assert_eq!(doubled.index, x.index);
+    assert_eq!(doubled.data, [2.0, -4.0]);
+    assert_eq!((doubled / 4.0).scatter(), [0.0, 0.5, 0.0, -1.0]);
+    assert_eq!((-x.clone()).scatter(), [0.0, -1.0, 0.0, 2.0]);
+
+    let mut y = x.clone();
+    y *= 3.0;
+    assert_eq!(y.data, [3.0, -6.0]);
+    assert_eq!(y.full_len(), 4);
+
+    let ints = PackedVec::gather(&[0u8, 6, 9]) / 3;
+    assert_eq!(ints.scatter(), [0, 2, 3]);
+}
+
+#[test]
+fn test_packed_vector_scaling_by_zero() {
+    let x: PackedVec = PackedVec::gather(&[0.0, 1.0, 0.0, -2.0]);
+
+    let zero = x.clone() * 0.0;
+    assert!(zero.is_empty());
+    assert_eq!(zero.full_len(), 4);
+    let mut y = x.clone();
+    y *= 0.0;
+    assert_eq!(y, zero);
+
+    // Only the components that become zero go: an integer quotient, then an underflow.
+    let ints = PackedVec::gather(&[0u8, 1, 9]) / 3;
+    assert_eq!(ints.index, [2]);
+    assert_eq!(ints.data, [3]);
+    let tiny = PackedVec::gather(&[f64::MIN_POSITIVE, 1.0]) / 1e300;
+    assert_eq!(tiny.index, [1]);
+
+    // An explicit zero, as left by a cancelling sum, stays +0.0 under negation.
+    let cancelled = &x + &PackedVec::gather(&[0.0, -1.0, 0.0, 0.0]);
+    assert_eq!(cancelled.data, [0.0, -2.0]);
+    let negated = -cancelled;
+    assert_eq!(negated.data, [0.0, 2.0]);
+    assert!(negated.data[0].is_sign_positive());
+}
+
+#[test]
+fn test_packed_vector_hadamard() {
+    let x = PackedVec::gather(&[0.0, 2.0, 0.0, 3.0, 4.0, 0.0]);
+    let mask = PackedVec::gather(&[1.0, 0.0, 0.0, 1.0, 0.5, 1.0]);
+
+    let masked = x.hadamard(&mask);
+    assert_eq!(masked.index, [3, 4]);
+    assert_eq!(masked.data, [3.0, 2.0]);
+    assert_eq!(masked.full_len(), 6);
+    assert_eq!(mask.hadamard(&x), masked);
+
+    let empty = x.hadamard(&PackedVec::gather(&[0.0; 6]));
+    assert!(empty.is_empty());
+    assert_eq!(empty.full_len(), 6);
+}
+
+#[test]
+fn test_packed_vector_hadamard_after_mul_add() {
+    // mul_add appends the fill-in at index 0 after the entry at index 3.
+    let mut x = PackedVec::gather(&[0.0, 0.0, 0.0, 1.0]);
+    x.mul_add(&PackedVec::gather(&[1.0, 0.0, 0.0, 0.0]), 1.0);
+    let mask = PackedVec::gather(&[1.0, 0.0, 0.0, 1.0]);
+
+    let masked = x.hadamard(&mask);
+    assert_eq!(masked.index, [0, 3]);
+    assert_eq!(masked.scatter(), [1.0, 0.0, 0.0, 1.0]);
+    assert_eq!(mask.hadamard(&x), masked);
+}
+
+#[test]
+fn test_packed_vector_dot_dense() {
+    let x = PackedVec::gather(&[0.0, 2.0, 0.0, 3.0, 4.0, 0.0, 1.0, -1.0, 0.5]);
+    let dense: Vec<f64> = (0..9).map(|i| i as f64).collect();
+    assert_eq!(x.dot_dense(&dense), 2.0 + 9.0 + 16.0 + 6.0 - 7.0 + 4.0);
+    assert_eq!(PackedVec::<i32>::new().dot_dense(&[]), 0);
+}
+
+#[test]
+fn test_packed_vector_get_set() {
+    let mut x = PackedVec::gather(&[0.0, 1.0, 0.0, 2.0, 0.0]);
+    assert_eq!(x.get(0), 0.0);
+    assert_eq!(x.get(3), 2.0);
+
+    x.set(0, 5.0);
+    x.set(3, 7.0);
+    x.set(1, 0.0);
+    x.set(2, 0.0);
+    assert_eq!(x.index, [0, 3]);
+    assert_eq!(x.data, [5.0, 7.0]);
+    assert_eq!(x.scatter(), [5.0, 0.0, 0.0, 7.0, 0.0]);
+
+    // Fill-in from mul_add is put back in order before inserting.
+    let mut y = PackedVec::gather(&[0.0, 0.0, 0.0, 0.0, 1.0]);
+    y.mul_add(&x, 1.0);
+    assert_eq!(y.index, [4, 0, 3]);
+    assert_eq!(y.get(3), 7.0);
+    y.set(2, -1.0);
+    assert_eq!(y.index, [0, 2, 3, 4]);
+    assert_eq!(y.scatter(), [5.0, 0.0, -1.0, 7.0, 1.0]);
+}
+
+#[test]
+fn test_packed_vector_index() {
+    let mut x: PackedVec = PackedVec::gather(&[0.0, 1.0, 0.0, 2.0]);
+    assert_eq!(x[1], 1.0);
+    assert_eq!(x[2], 0.0);
+
+    x[3] *= 2.0;
+    x[0] += 0.5;
+    assert_eq!(x.index, [0, 1, 3]);
+    assert_eq!(x.data, [0.5, 1.0, 4.0]);
+
+    // Writing through an index that is never assigned leaves an explicit zero behind.
+    *x.entry(2) += 0.0;
+    assert_eq!(x.len(), 4);
+    x.prune(0.0);
+    assert_eq!(x.index, [0, 1, 3]);
+
+    let mut counts = PackedVec::<u32>::from_pairs(3, []).unwrap();
+    for i in [2, 0, 2] {
+        counts[i] += 1;
+    }
+    assert_eq!(counts.scatter(), [1, 0, 2]);
+}
+
+#[test]
+#[should_panic(expected = "index 4 out of bounds for a packed vector of length 4")]
+fn test_packed_vector_index_out_of_bounds() {
+    let x: PackedVec = PackedVec::gather(&[0.0, 1.0, 0.0, 2.0]);
+    let _ = x[4];
+}
+
+#[test]
+#[should_panic(expected = "index 5 out of bounds for a packed vector of length 5")]
+fn test_packed_vector_set_out_of_bounds() {
+    PackedVec::gather(&[0.0, 1.0, 0.0, 2.0, 0.0]).set(5, 1.0);
+}
+
+/// Add to a vector of length 4 one whose last index is 4, with `workspace`.
+#[cfg(test)]
+fn mul_add_past_the_end(workspace: MulAddWorkspace) {
+    let mut x: PackedVec = PackedVec::gather(&[0.0, 1.0, 0.0, 2.0]);
+    let y = PackedVec {
+        index: vec![1, 4],
+        data: vec![1.0, 1.0],
+        full_length: 5,
+    };
+    x.mul_add_with(&y, 1.0, workspace);
+}
+
+#[test]
+#[should_panic(expected = "index 4 out of bounds for a packed vector of length 4")]
+fn test_packed_vector_mul_add_out_of_bounds_full() {
+    mul_add_past_the_end(MulAddWorkspace::Full);
+}
+
+#[test]
+#[should_panic(expected = "index 4 out of bounds for a packed vector of length 4")]
+fn test_packed_vector_mul_add_out_of_bounds_span() {
+    mul_add_past_the_end(MulAddWorkspace::Span);
+}
+
+#[test]
+#[should_panic(expected = "index 4 out of bounds for a packed vector of length 4")]
+fn test_packed_vector_mul_add_out_of_bounds_hash() {
+    mul_add_past_the_end(MulAddWorkspace::Hash);
+}
+
+#[test]
+fn test_packed_vector_from_pairs() {
+    let x = PackedVec::from_pairs(6, [(4, 1.0), (1, 2.0), (4, 0.5), (3, 1.0), (3, -1.0)]).unwrap();
+    assert_eq!(x.index, [1, 4]);
+    assert_eq!(x.data, [2.0, 1.5]);
+    assert_eq!(x.full_len(), 6);
+
+    let empty = PackedVec::<f64>::from_pairs(1_000_000_000, []).unwrap();
+    assert!(empty.is_empty());
+    assert_eq!(empty.full_len(), 1_000_000_000);
+
+    assert_eq!(
+        PackedVec::from_pairs(3, [(0, 1.0), (3, 1.0)]),
+        Err(SparseError::IndexOutOfBounds { index: 3, len: 3 })
+    );
+}
+
+#[test]
+fn test_packed_vector_prune() {
+    let mut x = PackedVec::gather(&[0.0, 1.0, 0.0, 2.0, 1e-12]);
+    x.mul_add(&PackedVec::gather(&[0.0, 1.0, 0.0, 1.0, 0.0]), -1.0);
+    assert_eq!(x.data, [0.0, 1.0, 1e-12]);
+
+    let mut exact = x.clone();
+    exact.prune(0.0);
+    assert_eq!(exact.index, [3, 4]);
+    assert_eq!(exact.data, [1.0, 1e-12]);
+
+    x.prune(1e-9);
+    assert_eq!(x.index, [3]);
+    assert_eq!(x.data, [1.0]);
+    assert_eq!(x.full_len(), 5);
+
+    let mut with_nan = PackedVec::gather(&[f64::NAN, 1e-3]);
+    with_nan.prune(1.0);
+    assert_eq!(with_nan.index, [0]);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_packed_vector_serde() {
+    let x = PackedVec::gather(&[0.0, 1.5, 0.0, -2.0]);
+    let json = serde_json::to_string(&x).unwrap();
+    assert_eq!(json, r#"{"index":[1,3],"data":[1.5,-2.0],"full_length":4}"#);
+    let back: PackedVec = serde_json::from_str(&json).unwrap();
+    assert_eq!(back.index, x.index);
+    assert_eq!(back.data, x.data);
+    assert_eq!(back.full_length, 4);
+
+    for (bad, reason) in [
+        (
+            r#"{"index":[1,3],"data":[1.5],"full_length":4}"#,
+            "dimension mismatch",
+        ),
+        (
+            r#"{"index":[1,4],"data":[1.5,2.0],"full_length":4}"#,
+            "out of bounds",
+        ),
+        (
+            r#"{"index":[1,1],"data":[1.5,2.0],"full_length":4}"#,
+            "stored twice",
+        ),
+    ] {
+        let err = serde_json::from_str::<PackedVec>(bad).unwrap_err();
+        assert!(err.to_string().contains(reason), "{err}");
+    }
+}
+
+#[test]
+fn test_packed_vector_display() {
+    let mut x = PackedVec::gather(&[0.0, 1.0, 0.0, 2.5, 0.0]);
+    x.mul_add(&PackedVec::gather(&[0.5, 0.0, 0.0, 0.0, 0.0]), 1.0);
+    assert_eq!(x.to_string(), "{0: 0.5, 1: 1, 3: 2.5} (length 5)");
+    assert_eq!(format!("{x:.2}"), "{0: 0.50, 1: 1.00, 3: 2.50} (length 5)");
+    assert_eq!(PackedVec::<f64>::new().to_string(), "{} (length 0)");
+
+    let long = PackedVec::gather(&[1u8; 25]);
+    assert!(long
+        .to_string()
+        .ends_with("18: 1, 19: 1, ... 5 more} (length 25)"));
+}
+
+#[test]
+fn test_packed_vector_sum_all() {
+    // A small linear congruential generator keeps the test deterministic without extra deps.
+    let mut seed: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut next = move |bound: usize| {
+        seed = seed
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (seed >> 33) as usize % bound
+    };
+
+    let len = 64;
+    let check = |vecs: &[PackedVec]| {
+        let refs: Vec<&PackedVec> = vecs.iter().collect();
+        let sum = PackedVec::sum_all(len, &refs).unwrap();
+
+        let mut dense = vec![0.0; len];
+        for v in vecs {
+            for (d, x) in dense.iter_mut().zip(v.scatter()) {
+                *d += x;
+            }
+        }
+        assert_eq!(sum.scatter(), dense);
+        assert!(sum.index.windows(2).all(|w| w[0] < w[1]));
+        assert!(sum.data.iter().all(|&x| x != 0.0));
+    };
+
+    // Random supports, mostly overlapping.
+    let random: Vec<PackedVec> = (0..40)
+        .map(|_| {
+            let mut x = vec![0.0; len];
+            for _ in 0..next(12) {
+                x[next(len)] = next(9) as f64 - 4.0;
+            }
+            PackedVec::gather(&x)
+        })
+        .collect();
+    check(&random);
+
+    // Many vectors with an identical support.
+    let same: Vec<PackedVec> = (0..30)
+        .map(|k| {
+            let mut x = vec![0.0; len];
+            for i in (0..len).step_by(7) {
+                x[i] = k as f64 + 1.0;
+            }
+            PackedVec::gather(&x)
+        })
+        .collect();
+    check(&same);
+
+    // Disjoint supports.
+    let disjoint: Vec<PackedVec> = (0..len)
+        .map(|i| {
+            let mut x = vec![0.0; len];
+            x[i] = i as f64 + 0.5;
+            PackedVec::gather(&x)
+        })
+        .collect();
+    check(&disjoint);
+
+    // Exact cancellation drops the component, and unsorted input from mul_add is fine.
+    let a = PackedVec::gather(&[0.0, 1.0, 0.0, 2.0]);
+    let mut b = PackedVec::gather(&[0.0, -1.0, 0.0, 0.0]);
+    b.mul_add(&PackedVec::gather(&[3.0, 0.0, 0.0, 0.0]), 1.0);
+    let sum = PackedVec::sum_all(4, &[&a, &b]).unwrap();
+    assert_eq!(sum.index, [0, 3]);
+    assert_eq!(sum.data, [3.0, 2.0]);
+
+    assert!(PackedVec::<f64>::sum_all(4, &[]).unwrap().is_empty());
+    assert_eq!(
+        PackedVec::sum_all(5, &[&a]).unwrap_err(),
+        SparseError::DimensionMismatch {
+            expected: 5,
+            found: 4
+        }
+    );
+}
+
+#[test]
+fn test_packed_vector_mul_add_tracked() {
+    let mut x = PackedVec::gather(&[0.0, 1.0, 0.0, 2.0, 0.0, 0.0, 0.0, 0.0]);
+    let y = PackedVec::gather(&[1.0, 1.0, 0.0, 0.0, 3.0, 0.0, 0.0, 0.0]);
+    let z = PackedVec::gather(&[0.0, 0.0, 0.0, 1.0, 1.0, 0.0, 5.0, 0.0]);
+
+    let before = x.len();
+    let stats = x.mul_add_tracked(&y, 2.0);
+    assert_eq!(stats.fill_in, stats.nnz - before);
+    assert_eq!(
+        stats,
+        FillStats {
+            fill_in: 2,
+            nnz: 4,
+            density: 0.5,
+        }
+    );
+
+    // Entries 3 and 4 already exist, only 6 fills in.
+    let stats = x.mul_add_tracked(&z, -1.0);
+    assert_eq!(stats.fill_in, 1);
+    assert_eq!(stats.nnz, 5);
+    assert_eq!(x.scatter(), [2.0, 3.0, 0.0, 1.0, 5.0, 0.0, -5.0, 0.0]);
+
+    // No fill when y's support is already covered.
+    let stats = x.mul_add_tracked(&y, 1.0);
+    assert_eq!(stats.fill_in, 0);
+    assert_eq!(stats.nnz, 5);
+}
+
+#[test]
+fn test_packed_vector_slices() {
+    let x = vec![1.0, 0.0, 2.0, 0.0, 0.0, 3.0, 4.0, 0.0, 5.0, 0.0];
+    let mut packed = PackedVec::gather(&x);
+
+    // Extract a block, modify it, write it back.
+    let mut block = packed.slice(2..7).unwrap();
+    assert_eq!(block.full_len(), 5);
+    assert_eq!(block.scatter(), [2.0, 0.0, 0.0, 3.0, 4.0]);
+    block.mul_add(&PackedVec::gather(&[0.0, 1.0, 0.0, 0.0, 0.0]), 7.0);
+    packed.assign_slice(2, &block).unwrap();
+    assert_eq!(
+        packed.scatter(),
+        [1.0, 0.0, 2.0, 7.0, 0.0, 3.0, 4.0, 0.0, 5.0, 0.0]
+    );
+    assert!(packed.index.windows(2).all(|w| w[0] < w[1]));
+
+    // Round trip: assigning a slice of itself back is a no-op.
+    let same = packed.slice(2..7).unwrap();
+    let mut round_trip = packed.clone();
+    round_trip.assign_slice(2, &same).unwrap();
+    assert_eq!(round_trip.index, packed.index);
+    assert_eq!(round_trip.data, packed.data);
+
+    // An empty source clears a block that was densely populated.
+    let mut dense = PackedVec::gather(&[1.0; 8]);
+    dense
+        .assign_slice(3, &PackedVec::gather(&[0.0; 4]))
+        .unwrap();
+    assert_eq!(dense.scatter(), [1.0, 1.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0]);
+    assert_eq!(dense.len(), 4);
+
+    let mut sum = PackedVec::gather(&x);
+    sum.add_slice(4, &PackedVec::gather(&[1.0, 1.0, 0.0, 1.0]), 2.0)
+        .unwrap();
+    assert_eq!(
+        sum.scatter(),
+        [1.0, 0.0, 2.0, 0.0, 2.0, 5.0, 4.0, 2.0, 5.0, 0.0]
+    );
+    assert!(sum.index.windows(2).all(|w| w[0] < w[1]));
+
+    let before = sum.clone();
+    sum.add_slice(0, &PackedVec::gather(&[0.0; 10]), 3.0)
+        .unwrap();
+    assert_eq!(sum.scatter(), before.scatter());
+
+    let out_of_range = SparseError::IndexOutOfBounds { index: 11, len: 10 };
+    assert_eq!(
+        packed.assign_slice(8, &PackedVec::gather(&[1.0, 0.0, 1.0])),
+        Err(out_of_range.clone())
+    );
+    assert_eq!(
+        packed.add_slice(8, &PackedVec::gather(&[1.0, 0.0, 1.0]), 1.0),
+        Err(out_of_range.clone())
+    );
+    assert_eq!(packed.slice(9..11).unwrap_err(), out_of_range);
+}
+
+#[test]
+fn test_packed_vector_bincount() {
+    let len = 50;
+    let dense_bincount = |pairs: &[(usize, f64)]| {
+        let mut dense = vec![0.0; len];
+        for &(i, w) in pairs {
+            dense[i] += w;
+        }
+        dense
+    };
+
+    // Heavy duplication on a handful of bins, below and above the strategy threshold.
+    for n in [
+        10,
+        BINCOUNT_SORT_THRESHOLD - 1,
+        BINCOUNT_SORT_THRESHOLD,
+        3 * BINCOUNT_SORT_THRESHOLD,
+    ] {
+        let indices: Vec<usize> = (0..n).map(|k| (k * k + 3 * k) % 7 * 6).collect();
+        let counts = PackedVec::bincount(len, indices.iter().copied()).unwrap();
+        let pairs: Vec<(usize, f64)> = indices.iter().map(|&i| (i, 1.0)).collect();
+        assert_eq!(counts.scatter(), dense_bincount(&pairs));
+        assert!(counts.index.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    // Both strategies give bit-identical sums, including cancellations to zero.
+    let pairs: Vec<(usize, f64)> = (0..2 * BINCOUNT_SORT_THRESHOLD)
+        .map(|k| {
+            (
+                k % 13,
+                if k % 26 < 13 {
+                    0.1 * k as f64
+                } else {
+                    -0.1 * (k - 13) as f64
+                },
+            )
+        })
+        .collect();
+    let hashed = PackedVec::accumulate_hashed(&pairs);
+    let sorted = PackedVec::accumulate_sorted(&mut pairs.clone());
+    assert_eq!(hashed.len(), sorted.len());
+    for ((i, a), (j, b)) in hashed.iter().zip(&sorted) {
+        assert_eq!(i, j);
+        assert_eq!(a.to_bits(), b.to_bits());
+    }
+    let weighted = PackedVec::bincount_weighted(len, pairs.iter().copied()).unwrap();
+    assert!(weighted.data.iter().all(|&w| w != 0.0));
+
+    let cancelled = PackedVec::bincount_weighted(len, [(4, 1.5), (7, 2.0), (4, -1.5)]).unwrap();
+    assert_eq!(cancelled.index, [7]);
+    assert_eq!(cancelled.data, [2.0]);
+
+    let empty = PackedVec::bincount(len, []).unwrap();
+    assert!(empty.is_empty());
+    assert_eq!(empty.full_len(), len);
+
+    assert_eq!(
+        PackedVec::bincount(len, [1, 2, 50, 3]).unwrap_err(),
+        SparseError::IndexOutOfBounds { index: 50, len }
+    );
+}
+
+#[test]
+fn test_packed_vector_nan_policy() {
+    let nan = f64::NAN;
+    let inf = f64::INFINITY;
+
+    // (input, policy, expected stored indices, or the index of the rejected NaN)
+    #[rustfmt::skip]
+    let cases = [
+        (vec![0.0, -0.0, 1.0], NanPolicy::Keep, Ok(vec![2])),
+        (vec![0.0, -0.0, 1.0], NanPolicy::Drop, Ok(vec![2])),
+        (vec![0.0, -0.0, 1.0], NanPolicy::Error, Ok(vec![2])),
+        (vec![nan, 0.0, 2.0, nan], NanPolicy::Keep, Ok(vec![0, 2, 3])),
+        (vec![nan, 0.0, 2.0, nan], NanPolicy::Drop, Ok(vec![2])),
+        (vec![1.0, 0.0, 2.0, nan], NanPolicy::Error, Err(3)),
+        (vec![inf, -inf, -0.0, 0.0], NanPolicy::Keep, Ok(vec![0, 1])),
+        (vec![inf, -inf, -0.0, 0.0], NanPolicy::Drop, Ok(vec![0, 1])),
+        (vec![inf, -inf, -0.0, 0.0], NanPolicy::Error, Ok(vec![0, 1])),
+    ];
+
+    for (x, policy, expected) in cases {
+        let gathered = PackedVec::gather_with(&x, policy);
+        match expected {
+            Ok(index) => {
+                let v = gathered.unwrap();
+                assert_eq!(v.index, index, "{x:?} {policy:?}");
+                assert_eq!(
+                    v.has_nan(),
+                    x.iter().any(|x| x.is_nan()) && policy == NanPolicy::Keep
+                );
+            }
+            Err(index) => assert_eq!(gathered.unwrap_err(), SparseError::NanValue { index }),
+        }
+    }
+
+    // NaN poisons the inner product, and only compares equal when asked to.
+    let with_nan = PackedVec::gather(&[nan, 1.0, 0.0]);
+    let plain = PackedVec::gather(&[1.0, 1.0, 0.0]);
+    assert!((with_nan.clone() * plain.clone()).is_nan());
+    assert!(!with_nan.approx_eq(&with_nan, 1e-12, false));
+    assert!(with_nan.approx_eq(&with_nan, 1e-12, true));
+    assert!(!with_nan.approx_eq(&plain, 1e-12, true));
+    assert_ne!(with_nan, with_nan.clone());
+
+    let infinite = PackedVec::gather(&[inf, 0.0, -inf]);
+    assert!(infinite.approx_eq(&infinite, 0.0, false));
+    assert!(!infinite.approx_eq(&PackedVec::gather(&[inf, 0.0, inf]), 1e300, false));
+
+    // A result of -0.0 is stored as +0.0, and compares equal to a structural zero.
+    let mut x: PackedVec = PackedVec::gather(&[1.0, 0.0, 0.0]);
+    x.mul_add(&PackedVec::gather(&[0.0, 2.0, 0.0]), -0.0);
+    assert_eq!(x.data[1].to_bits(), 0.0f64.to_bits());
+    assert_eq!(x, PackedVec::gather(&[1.0, 0.0, 0.0]));
+    assert!(x.approx_eq(&PackedVec::gather(&[1.0, -0.0, 0.0]), 0.0, false));
+}
+
+#[test]
+fn test_packed_vector_mul_add_workspaces() {
+    let workspaces = [
+        MulAddWorkspace::Full,
+        MulAddWorkspace::Span,
+        MulAddWorkspace::Hash,
+        MulAddWorkspace::Auto,
+    ];
+    let check = |x: &PackedVec, y: &PackedVec, alpha: f64| {
+        let mut expected = x.clone();
+        expected.mul_add_with(y, alpha, MulAddWorkspace::Full);
+        for workspace in workspaces {
+            let mut got = x.clone();
+            got.mul_add_with(y, alpha, workspace);
+            assert_eq!(got.index, expected.index, "{workspace:?}");
+            assert_eq!(got.data, expected.data, "{workspace:?}");
+        }
+    };
+
+    // A handful of nonzeros clustered at the far end of a very long vector. Only the span and
+    // hash workspaces are run here: the full one would allocate 10M slots.
+    let n = 10_000_000;
+    let x = PackedVec {
+        index: vec![n - 40, n - 7, n - 3],
+        data: vec![1.0, 2.0, 3.0],
+        full_length: n,
+    };
+    let y = PackedVec {
+        index: vec![n - 7, n - 5, n - 1],
+        data: vec![10.0, 20.0, 30.0],
+        full_length: n,
+    };
+    assert_eq!(MulAddWorkspace::Auto.resolve(n, &y), MulAddWorkspace::Span);
+    let spread = PackedVec {
+        index: vec![5, n / 2, n - 1],
+        data: vec![1.0, 1.0, 1.0],
+        full_length: n,
+    };
+    assert_eq!(
+        MulAddWorkspace::Auto.resolve(n, &spread),
+        MulAddWorkspace::Hash
+    );
+    assert_eq!(
+        MulAddWorkspace::Auto.resolve(FULL_WORKSPACE_LIMIT, &spread),
+        MulAddWorkspace::Full
+    );
+
+    let mut results = Vec::new();
+    for workspace in [
+        MulAddWorkspace::Span,
+        MulAddWorkspace::Hash,
+        MulAddWorkspace::Auto,
+    ] {
+        let mut got = x.clone();
+        got.mul_add_with(&y, 2.0, workspace);
+        got.mul_add_with(&spread, -1.0, workspace);
+        results.push(got);
+    }
+    for got in &results {
+        assert_eq!(got.index, [n - 40, n - 7, n - 3, n - 5, n - 1, 5, n / 2]);
+        assert_eq!(got.data, [1.0, 22.0, 3.0, 40.0, 59.0, -1.0, -1.0]);
+    }
+
+    // Short vectors, including unsorted input from a previous update and an empty Y.
+    let mut x = PackedVec::gather(&[0.0, 1.0, 0.0, 2.0, 0.0, 0.0, 3.0, 0.0]);
+    x.mul_add(
+        &PackedVec::gather(&[5.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0]),
+        1.0,
+    );
+    let y = PackedVec::gather(&[0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 1.0, 1.0]);
+    check(&x, &y, 0.5);
+    check(&y, &x, -2.0);
+    check(&x, &PackedVec::gather(&[0.0; 8]), 3.0);
+}
+
+#[cfg(feature = "approx")]
+#[test]
+fn test_packed_vector_approx() {
+    use approx::{assert_relative_eq, assert_ulps_eq};
+
+    let x = vec![0.0, 0.1, 0.0, 0.0, 0.3, 0.0, 0.7, 0.0];
+    let y = vec![0.2, 0.0, 0.0, 0.0, 0.1, 0.0, 0.0, 0.0];
+    let alpha = 0.3;
+
+    // mul_add in packed form against a dense axpy gathered back afterwards.
+    let mut packed = PackedVec::gather(&x);
+    packed.mul_add(&PackedVec::gather(&y), alpha);
+    let dense: Vec<f64> = x.iter().zip(&y).map(|(a, b)| a + alpha * b).collect();
+    let regathered = PackedVec::gather(&dense);
+
+    assert_relative_eq!(packed, regathered);
+    assert_ulps_eq!(packed, regathered);
+
+    // An explicitly stored zero matches a structural zero.
+    let mut explicit = regathered.clone();
+    explicit.index.push(3);
+    explicit.data.push(0.0);
+    assert_relative_eq!(explicit, regathered);
+    assert_eq!(explicit, regathered);
+
+    let perturbed = PackedVec::gather(&[0.0, 0.1, 0.0, 0.0, 0.3, 0.0, 0.7, 1e-3]);
+    assert!(approx::relative_ne!(PackedVec::gather(&x), perturbed));
+}
+
+#[cfg(feature = "approx")]
+#[test]
+#[should_panic(expected = "assert_relative_eq!(short, long)")]
+fn test_packed_vector_approx_shape_mismatch() {
+    use approx::assert_relative_eq;
+
+    let short = PackedVec::gather(&[1.0, 0.0, 2.0]);
+    let long = PackedVec::gather(&[1.0, 0.0, 2.0, 0.0]);
+    assert_relative_eq!(short, long);
+}
+
+#[test]
+fn test_packed_vector_index_type() {
+    let x = PackedVec::gather(&[0.0, 1.5, 0.0, -2.0, 0.0, 4.0]);
+    let small: PackedVec<f64, u16> = x.to_index_type().unwrap();
+    assert_eq!((small.len(), small.full_len()), (3, 6));
+    assert_eq!(small.get(3), -2.0);
+    assert_eq!(
+        small.iter().collect::<Vec<_>>(),
+        x.iter().collect::<Vec<_>>()
+    );
+    assert_eq!(small.scatter(), x.scatter());
+    assert_eq!(small.dot_dense(&[1.0; 6]), 3.5);
+    assert_eq!(small.to_index_type::<usize>().unwrap(), x);
+}
+
+#[test]
+fn test_packed_vector_parts() {
+    let x = PackedVec::gather(&[0.0, 2.0, 0.0, 3.0]);
+    assert_eq!(x.indices(), [1, 3]);
+    assert_eq!(x.data(), [2.0, 3.0]);
+
+    let empty = PackedVec::<f64>::gather(&[0.0; 3]);
+    assert!(empty.indices().is_empty() && empty.data().is_empty());
+}
@@ -0,0 +1,352 @@
+//! Views that present a CSR matrix as its transpose, a block of it, or a multiple of it without
+//! copying anything: [`TransposedView`], [`SubmatrixView`] and [`ScaledView`].
+//!
+//! A view borrows the matrix and does the work of the transpose, the block or the scaling inside
+//! its products, so it can be handed to any solver that takes a
+//! [`LinearOperator`](crate::operator::LinearOperator):
+//!
+//! ```
+//! use sparse_matrix::csr::CsrMatrix;
+//! use sparse_matrix::operator::LinearOperator;
+//! use sparse_matrix::view::TransposedView;
+//!
+//! let a = CsrMatrix::from_dense(2, 3, &[1.0, 0.0, 2.0, 0.0, 3.0, 0.0])?;
+//! let at = TransposedView::new(&a);
+//! let mut y = [0.0; 3];
+//! at.apply(&[1.0, 2.0], &mut y);
+//! assert_eq!(y, [1.0, 6.0, 2.0]);
+//! assert_eq!(at.to_owned().to_dense(), a.transpose().to_dense());
+//! # Ok::<(), sparse_matrix::error::SparseError>(())
+//! ```
+//!
+//! Every view implements [`SparseMatrix`](crate::traits::SparseMatrix), and the two whose rows
+//! are rows of the viewed matrix also [`RowAccess`](crate::traits::RowAccess). `to_owned`
+//! copies a view into a CSR matrix of its own.
+
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::csr::CsrMatrix;
+use crate::scalar::Scalar;
+
+/// The transpose `Aᵀ` of a CSR matrix `A`, without forming it.
+///
+/// A product with the view scatters the rows of `A`, as
+/// [`CsrMatrix::mul_vec_transposed_into`] does. The rows of `Aᵀ` are the columns of `A`, which
+/// CSR can't hand out cheaply, so the view has no row access: [`TransposedView::to_owned`]
+/// forms the transpose when that is needed.
+#[derive(Clone, Copy, Debug)]
+pub struct TransposedView<'a, T = f64> {
+    a: &'a CsrMatrix<T>,
+}
+
+impl<'a, T: Scalar> TransposedView<'a, T> {
+    /// View the transpose of `a`.
+    pub fn new(a: &'a CsrMatrix<T>) -> Self {
+        Self { a }
+    }
+
+    /// Return the number of rows, the number of columns of the viewed matrix
+    pub fn nrows(&self) -> usize {
+        self.a.ncols()
+    }
+
+    /// Return the number of columns, the number of rows of the viewed matrix
+    pub fn ncols(&self) -> usize {
+        self.a.nrows()
+    }
+
+    /// Return `(nrows, ncols)`
+    pub fn shape(&self) -> (usize, usize) {
+        (self.nrows(), self.ncols())
+    }
+
+    /// Return the viewed matrix
+    pub fn inner(&self) -> &'a CsrMatrix<T> {
+        self.a
+    }
+
+    /// Form the transpose in CSR form. O(nnz + nrows + ncols).
+    pub fn to_owned(&self) -> CsrMatrix<T> {
+        self.a.transpose()
+    }
+}
+
+/// The block of a CSR matrix at a contiguous range of rows and a contiguous range of columns,
+/// renumbered from 0 as by [`CsrMatrix::slice`], without copying it.
+///
+/// Row `i` of the block is the part of row `rows.start + i` of the matrix that falls in the
+/// column range, found by binary search. A product with the view costs O(rows.len() × log(row
+/// length)) on top of the entries of the block.
+#[derive(Clone, Debug)]
+pub struct SubmatrixView<'a, T = f64> {
+    a: &'a CsrMatrix<T>,
+    rows: Range<usize>,
+    cols: Range<usize>,
+}
+
+impl<'a, T: Scalar> SubmatrixView<'a, T> {
+    /// View the rows in `rows` and the columns in `cols` of `a`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a range is decreasing or reaches past the end of the matrix.
+    pub fn new(a: &'a CsrMatrix<T>, rows: Range<usize>, cols: Range<usize>) -> Self {
+        assert!(
+            rows.start <= rows.end && rows.end <= a.nrows(),
+            "row range {rows:?} out of bounds for {} rows",
+            a.nrows()
+        );
+        assert!(
+            cols.start <= cols.end && cols.end <= a.ncols(),
+            "column range {cols:?} out of bounds for {} columns",
+            a.ncols()
+        );
+        Self { a, rows, cols }
+    }
+
+    /// Return the number of rows, the length of the row range
+    pub fn nrows(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Return the number of columns, the length of the column range
+    pub fn ncols(&self) -> usize {
+        self.cols.len()
+    }
+
+    /// Return `(nrows, ncols)`
+    pub fn shape(&self) -> (usize, usize) {
+        (self.nrows(), self.ncols())
+    }
+
+    /// Return the viewed matrix
+    pub fn inner(&self) -> &'a CsrMatrix<T> {
+        self.a
+    }
+
+    /// Return the ranges of rows and columns of the viewed matrix that the block covers
+    pub fn ranges(&self) -> (Range<usize>, Range<usize>) {
+        (self.rows.clone(), self.cols.clone())
+    }
+
+    /// Copy the block into a CSR matrix of its own, see [`CsrMatrix::slice`].
+    pub fn to_owned(&self) -> CsrMatrix<T> {
+        self.a.slice(self.rows.clone(), self.cols.clone())
+    }
+
+    /// Return the stored part of row `i` of the block, with the column indices of the viewed
+    /// matrix.
+    pub(crate) fn row_parts(&self, i: usize) -> (&'a [usize], &'a [T]) {
+        assert!(
+            i < self.nrows(),
+            "row {i} out of bounds for {} rows",
+            self.nrows()
+        );
+        let (cols, values) = self.a.row(self.rows.start + i);
+        let start = cols.partition_point(|&j| j < self.cols.start);
+        let end = cols.partition_point(|&j| j < self.cols.end);
+        (&cols[start..end], &values[start..end])
+    }
+
+    /// Return the first column of the block in the viewed matrix
+    pub(crate) fn col_offset(&self) -> usize {
+        self.cols.start
+    }
+}
+
+/// `α A` for a CSR matrix `A` and a scalar `α`, without scaling a copy.
+///
+/// A product with the view is the product with `A`, scaled afterwards. Rows are those of `A`
+/// with scaled values, leaving out the entries the scaling makes zero, as `to_owned` does.
+#[derive(Clone, Copy, Debug)]
+pub struct ScaledView<'a, T = f64> {
+    a: &'a CsrMatrix<T>,
+    alpha: T,
+}
+
+impl<'a, T: Scalar> ScaledView<'a, T> {
+    /// View `alpha` times `a`.
+    pub fn new(a: &'a CsrMatrix<T>, alpha: T) -> Self {
+        Self { a, alpha }
+    }
+
+    /// Return the number of rows
+    pub fn nrows(&self) -> usize {
+        self.a.nrows()
+    }
+
+    /// Return the number of columns
+    pub fn ncols(&self) -> usize {
+        self.a.ncols()
+    }
+
+    /// Return `(nrows, ncols)`
+    pub fn shape(&self) -> (usize, usize) {
+        self.a.shape()
+    }
+
+    /// Return the viewed matrix
+    pub fn inner(&self) -> &'a CsrMatrix<T> {
+        self.a
+    }
+
+    /// Return the scaling factor α
+    pub fn alpha(&self) -> T {
+        self.alpha
+    }
+
+    /// Copy the scaled matrix into a CSR matrix of its own, leaving out the entries that the
+    /// scaling makes zero. O(nnz + nrows).
+    pub fn to_owned(&self) -> CsrMatrix<T> {
+        let mut indptr = Vec::with_capacity(self.nrows() + 1);
+        let mut indices = Vec::with_capacity(self.a.nnz());
+        let mut data = Vec::with_capacity(self.a.nnz());
+        indptr.push(0);
+        for i in 0..self.nrows() {
+            for (j, v) in self.scaled_row(i) {
+                indices.push(j);
+                data.push(v);
+            }
+            indptr.push(indices.len());
+        }
+
+        CsrMatrix::from_parts(self.nrows(), self.ncols(), indptr, indices, data)
+    }
+
+    /// Iterate over the nonzero scaled entries of row `i`.
+    pub(crate) fn scaled_row(&self, i: usize) -> impl Iterator<Item = (usize, T)> + 'a {
+        let alpha = self.alpha;
+        let (cols, values) = self.a.row(i);
+        cols.iter()
+            .zip(values)
+            .map(move |(&j, &v)| (j, alpha * v))
+            .filter(|&(_, v)| v != T::zero())
+    }
+}
+
+#[test]
+fn test_transposed_view() {
+    use crate::iterative::{gmres, SolverOptions};
+    use crate::operator::{LinearOperator, TransposeOperator};
+    use crate::test_util::{assert_close, Lcg};
+    use crate::traits::SparseMatrix;
+
+    // A nonsymmetric convection-diffusion matrix with a random perturbation.
+    let n = 60;
+    let mut rng = Lcg::new(41);
+    let mut dense = rng.dense(n, n, 0.05);
+    for i in 0..n {
+        dense[i * n + i] += 4.0;
+        if i + 1 < n {
+            dense[i * n + i + 1] -= 1.5;
+            dense[(i + 1) * n + i] -= 0.5;
+        }
+    }
+    let a = CsrMatrix::from_dense(n, n, &dense).unwrap();
+    let view = TransposedView::new(&a);
+    let at = view.to_owned();
+    assert_eq!(view.shape(), (n, n));
+    assert_eq!(SparseMatrix::nnz(&view), a.nnz());
+
+    let x: Vec<f64> = (0..n).map(|_| rng.uniform()).collect();
+    let (mut y, mut z) = (vec![0.0; n], vec![0.0; n]);
+    view.apply(&x, &mut y);
+    assert_close(&y, &at.mul_vec(&x), 1e-13);
+    view.apply_transpose(&x, &mut z);
+    assert_close(&z, &a.mul_vec(&x), 1e-13);
+
+    // GMRES takes the same steps on the view as on the transpose.
+    let b: Vec<f64> = (0..n).map(|_| rng.uniform()).collect();
+    let opts = SolverOptions::default();
+    let on_view = gmres(&view, &b, None, None, 10, &opts).unwrap();
+    let on_copy = gmres(&at, &b, None, None, 10, &opts).unwrap();
+    assert!(on_view.converged && on_copy.converged);
+    assert_eq!(on_view.iterations, on_copy.iterations);
+    assert_close(&on_view.residual_history, &on_copy.residual_history, 1e-12);
+    assert_close(&on_view.x, &on_copy.x, 1e-12);
+
+    // A wide matrix turns tall.
+    let wide = CsrMatrix::from_dense(2, 3, &[1.0, 0.0, 2.0, 0.0, 3.0, 0.0]).unwrap();
+    let view = TransposedView::new(&wide);
+    assert_eq!((view.nrows(), view.ncols()), (3, 2));
+    assert_eq!(LinearOperator::nrows(&view), 3);
+}
+
+#[test]
+fn test_submatrix_view() {
+    use crate::operator::{LinearOperator, TransposeOperator};
+    use crate::test_util::{assert_close, Lcg};
+    use crate::traits::{RowAccess, SparseMatrix};
+
+    let mut rng = Lcg::new(43);
+    let dense = rng.dense(20, 30, 0.3);
+    let a = CsrMatrix::from_dense(20, 30, &dense).unwrap();
+
+    for (rows, cols) in [
+        (3..17, 5..12),
+        (0..20, 0..30),
+        (4..4, 2..9),
+        (19..20, 29..30),
+    ] {
+        let view = SubmatrixView::new(&a, rows.clone(), cols.clone());
+        let block = view.to_owned();
+        assert_eq!(view.shape(), (rows.len(), cols.len()));
+        assert_eq!(block.shape(), view.shape());
+        assert_eq!(SparseMatrix::nnz(&view), block.nnz());
+
+        let x: Vec<f64> = (0..cols.len()).map(|_| rng.uniform()).collect();
+        let mut y = vec![f64::NAN; rows.len()];
+        view.apply(&x, &mut y);
+        assert_close(&y, &block.mul_vec(&x), 1e-14);
+
+        let u: Vec<f64> = (0..rows.len()).map(|_| rng.uniform()).collect();
+        let mut v = vec![f64::NAN; cols.len()];
+        view.apply_transpose(&u, &mut v);
+        assert_close(&v, &block.mul_vec_transposed(&u), 1e-14);
+
+        for i in 0..rows.len() {
+            let (block_cols, block_values) = block.row(i);
+            let entries: Vec<(usize, f64)> = view.row_entries(i).collect();
+            let expected: Vec<(usize, f64)> = block_cols
+                .iter()
+                .copied()
+                .zip(block_values.iter().copied())
+                .collect();
+            assert_eq!(entries, expected);
+        }
+    }
+}
+
+#[test]
+#[should_panic(expected = "column range 2..31 out of bounds for 30 columns")]
+fn test_submatrix_view_out_of_bounds() {
+    let a = CsrMatrix::<f64>::new(20, 30);
+    SubmatrixView::new(&a, 0..20, 2..31);
+}
+
+#[test]
+fn test_scaled_view() {
+    use crate::operator::{LinearOperator, TransposeOperator};
+    use crate::traits::RowAccess;
+
+    let a = CsrMatrix::from_dense(2, 3, &[1.0, 0.0, 2.0, 0.0, -3.0, 0.0]).unwrap();
+    let view = ScaledView::new(&a, -2.0);
+    assert_eq!(view.to_owned().to_dense(), [-2.0, 0.0, -4.0, 0.0, 6.0, 0.0]);
+    assert_eq!(
+        view.row_entries(0).collect::<Vec<_>>(),
+        [(0, -2.0), (2, -4.0)]
+    );
+
+    let mut y = [0.0; 2];
+    view.apply(&[1.0, 1.0, 1.0], &mut y);
+    assert_eq!(y, [-6.0, 6.0]);
+    let mut z = [0.0; 3];
+    view.apply_transpose(&[1.0, 1.0], &mut z);
+    assert_eq!(z, [-2.0, 6.0, -4.0]);
+
+    // Scaling by zero leaves nothing stored.
+    let zero = ScaledView::new(&a, 0.0);
+    assert_eq!(zero.to_owned().nnz(), 0);
+    assert_eq!(zero.row_entries(1).count(), 0);
+}
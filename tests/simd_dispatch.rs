@@ -0,0 +1,63 @@
+//! The scalar and vectorized kernels on the same inputs, switching through the process-wide
+//! override, which is why this runs as its own test binary rather than beside the unit tests.
+
+use sparse_matrix::csr::{dense_times_csr, CsrMatrix};
+use sparse_matrix::simd::{self, KernelSet};
+use sparse_matrix::traits::SparseMatrix;
+use sparse_matrix::vec::PackedVec;
+
+/// A reproducible `n × n` matrix with about `per_row` entries per row, some of them sharing a
+/// column, and a dense vector.
+fn inputs(n: usize, per_row: usize) -> (CsrMatrix<f64>, Vec<f64>) {
+    let mut state = 0x2545_f491_4f6c_dd1d_u64;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+    let mut dense = vec![0.0; n * n];
+    for i in 0..n {
+        for _ in 0..per_row {
+            let j = next() as usize % n;
+            dense[i * n + j] += (next() % 1000) as f64 / 500.0 - 1.0;
+        }
+    }
+    let x = (0..n).map(|_| (next() % 1000) as f64 / 1000.0).collect();
+    (CsrMatrix::from_dense(n, n, &dense).unwrap(), x)
+}
+
+#[test]
+fn test_force_scalar() {
+    let (a, x) = inputs(200, 9);
+    let d: Vec<f64> = (0..20 * 200).map(|k| x[k % 200] - 0.5).collect();
+    let packed = PackedVec::gather(&x);
+
+    let first = simd::active();
+    let fast_mul = a.mul_vec(&x);
+    let fast_transposed = a.mul_vec_transposed(&x);
+    let fast_csc = a.to_csc().mul_vec(&x);
+    let fast_dot = packed.dot_dense(&x);
+    let fast_dense = dense_times_csr(&d, (20, 200), &a).unwrap();
+
+    simd::force_scalar();
+    assert_eq!(simd::active(), KernelSet::Scalar);
+    let slow_mul = a.mul_vec(&x);
+    let slow_dot = packed.dot_dense(&x);
+
+    // The dot products only differ by their summation order, a few ulps of the sum of the
+    // absolute products, which is at most 9 per row here.
+    let tol = 16.0 * f64::EPSILON * 9.0;
+    for (fast, slow) in fast_mul.iter().zip(&slow_mul) {
+        assert!((fast - slow).abs() <= tol, "{fast} != {slow}");
+    }
+    assert!((fast_dot - slow_dot).abs() <= 16.0 * f64::EPSILON * 200.0);
+    if first == KernelSet::Scalar {
+        assert_eq!(fast_mul, slow_mul);
+    }
+
+    // The scattered updates round the same on either path.
+    assert_eq!(fast_transposed, a.mul_vec_transposed(&x));
+    assert_eq!(fast_csc, a.to_csc().mul_vec(&x));
+    assert_eq!(fast_dense, dense_times_csr(&d, (20, 200), &a).unwrap());
+}